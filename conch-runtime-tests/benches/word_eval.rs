@@ -0,0 +1,259 @@
+//! Benchmarks for evaluating words through the AST implementations.
+//!
+//! All fixtures are generated in code, so no external files are needed.
+//! Run with `cargo bench --bench word_eval`, and see `benches/README.md`
+//! for how to compare results against a saved baseline.
+
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast;
+use conch_runtime::env::{DefaultEnvArc, SetArgumentsEnvironment, VariableEnvironment};
+use conch_runtime::eval::{Fields, WordEval};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+type Word = ast::AtomicTopLevelWord<Arc<String>>;
+type Param = ast::Parameter<Arc<String>>;
+type Subst = ast::ParameterSubstitution<
+    Param,
+    Word,
+    ast::AtomicTopLevelCommand<Arc<String>>,
+    ast::Arithmetic<Arc<String>>,
+>;
+type SimpleWord = ast::SimpleWord<Arc<String>, Param, Box<Subst>>;
+type Part = ast::Word<Arc<String>, SimpleWord>;
+
+const SIZES: &[usize] = &[100, 1_000, 10_000];
+
+fn runtime() -> Runtime {
+    Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .expect("failed to create runtime")
+}
+
+fn new_env(rt: &mut Runtime) -> DefaultEnvArc {
+    rt.block_on(async { DefaultEnvArc::new() })
+        .expect("failed to create env")
+}
+
+fn set_var(env: &mut DefaultEnvArc, name: &str, val: String) {
+    env.set_var(Arc::new(name.to_owned()), Arc::new(val));
+}
+
+fn var(name: &str) -> Param {
+    ast::Parameter::Var(Arc::new(name.to_owned()))
+}
+
+fn lit(s: &str) -> Part {
+    ast::Word::Simple(ast::SimpleWord::Literal(Arc::new(s.to_owned())))
+}
+
+fn param(p: Param) -> Part {
+    ast::Word::Simple(ast::SimpleWord::Param(p))
+}
+
+fn star() -> Part {
+    ast::Word::Simple(ast::SimpleWord::Star)
+}
+
+fn subst(s: Subst) -> Part {
+    ast::Word::Simple(ast::SimpleWord::Subst(Box::new(s)))
+}
+
+fn single(part: Part) -> Word {
+    ast::AtomicTopLevelWord(ast::ComplexWord::Single(part))
+}
+
+fn concat(parts: Vec<Part>) -> Word {
+    ast::AtomicTopLevelWord(ast::ComplexWord::Concat(parts))
+}
+
+/// Generates `n` words separated by a mix of (default) IFS whitespace.
+fn words(n: usize) -> String {
+    let separators = [" ", "\t", "  ", "\n"];
+    let mut ret = String::new();
+    for i in 0..n {
+        if i > 0 {
+            ret.push_str(separators[i % separators.len()]);
+        }
+        ret.push_str("word");
+        ret.push_str(&i.to_string());
+    }
+    ret
+}
+
+/// Generates `${UNSET:-x${UNSET:-x...}}` nested `depth` levels deep.
+fn nested_default(depth: usize) -> Word {
+    let mut word = single(lit("x"));
+    for _ in 0..depth {
+        let default = ast::ParameterSubstitution::Default(true, var("UNSET"), Some(word));
+        word = concat(vec![lit("x"), subst(default)]);
+    }
+    word
+}
+
+async fn eval(word: &Word, env: &mut DefaultEnvArc) -> Fields<Arc<String>> {
+    word.eval(env).await.expect("eval failed").await
+}
+
+fn param_expansion(c: &mut Criterion) {
+    let mut rt = runtime();
+    let mut env = new_env(&mut rt);
+
+    let values = [
+        ("short", "/usr/local/share/archive.tar.gz".to_owned()),
+        (
+            "long",
+            format!("{}archive.tar.gz", "segment/".repeat(1_000)),
+        ),
+    ];
+
+    let suffix = concat(vec![lit("."), star()]);
+    let prefix = concat(vec![star(), lit("/")]);
+    let cases = vec![
+        (
+            "remove_smallest_suffix",
+            single(subst(ast::ParameterSubstitution::RemoveSmallestSuffix(
+                var("VAR"),
+                Some(suffix.clone()),
+            ))),
+        ),
+        (
+            "remove_largest_suffix",
+            single(subst(ast::ParameterSubstitution::RemoveLargestSuffix(
+                var("VAR"),
+                Some(suffix),
+            ))),
+        ),
+        (
+            "remove_largest_prefix",
+            single(subst(ast::ParameterSubstitution::RemoveLargestPrefix(
+                var("VAR"),
+                Some(prefix),
+            ))),
+        ),
+        (
+            "default",
+            single(subst(ast::ParameterSubstitution::Default(
+                true,
+                var("UNSET"),
+                Some(single(param(var("VAR")))),
+            ))),
+        ),
+        (
+            "len",
+            single(subst(ast::ParameterSubstitution::Len(var("VAR")))),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("param_expansion");
+    for (value_name, value) in &values {
+        set_var(&mut env, "VAR", value.clone());
+
+        for (name, word) in &cases {
+            group.bench_with_input(BenchmarkId::new(*name, value_name), word, |b, word| {
+                b.iter(|| black_box(rt.block_on(eval(word, &mut env))))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn field_splitting(c: &mut Criterion) {
+    let mut rt = runtime();
+    let mut env = new_env(&mut rt);
+    let word = single(param(var("WORDS")));
+
+    let mut group = c.benchmark_group("field_splitting");
+    for &n in SIZES {
+        set_var(&mut env, "WORDS", words(n));
+
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &word, |b, word| {
+            b.iter(|| black_box(rt.block_on(eval(word, &mut env))))
+        });
+    }
+    group.finish();
+}
+
+fn positional_args(c: &mut Criterion) {
+    let mut rt = runtime();
+    let mut env = new_env(&mut rt);
+
+    let cases = vec![
+        (
+            "quoted_at",
+            single(ast::Word::DoubleQuoted(vec![ast::SimpleWord::Param(
+                ast::Parameter::At,
+            )])),
+        ),
+        (
+            "quoted_star",
+            single(ast::Word::DoubleQuoted(vec![ast::SimpleWord::Param(
+                ast::Parameter::Star,
+            )])),
+        ),
+        ("unquoted_star", single(param(ast::Parameter::Star))),
+    ];
+
+    let mut group = c.benchmark_group("positional_args");
+    for &n in SIZES {
+        let args = (0..n)
+            .map(|i| Arc::new(format!("arg {}", i)))
+            .collect::<VecDeque<_>>();
+        env.set_args(Arc::new(args));
+
+        group.throughput(Throughput::Elements(n as u64));
+        for (name, word) in &cases {
+            group.bench_with_input(BenchmarkId::new(*name, n), word, |b, word| {
+                b.iter(|| black_box(rt.block_on(eval(word, &mut env))))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn concat_nesting(c: &mut Criterion) {
+    let mut rt = runtime();
+    let mut env = new_env(&mut rt);
+    set_var(&mut env, "SHORT", "value".to_owned());
+
+    let mut group = c.benchmark_group("concat_nesting");
+    for &n in &[10, 100, 1_000] {
+        let parts = (0..n)
+            .map(|i| {
+                if i % 2 == 0 {
+                    lit("literal")
+                } else {
+                    param(var("SHORT"))
+                }
+            })
+            .collect();
+        let word = concat(parts);
+
+        group.bench_with_input(BenchmarkId::new("wide", n), &word, |b, word| {
+            b.iter(|| black_box(rt.block_on(eval(word, &mut env))))
+        });
+    }
+
+    for &depth in &[8, 32, 128] {
+        let word = nested_default(depth);
+        group.bench_with_input(BenchmarkId::new("deep", depth), &word, |b, word| {
+            b.iter(|| black_box(rt.block_on(eval(word, &mut env))))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    param_expansion,
+    field_splitting,
+    positional_args,
+    concat_nesting
+);
+criterion_main!(benches);
@@ -1,9 +1,11 @@
 #![deny(rust_2018_idioms)]
 
 use conch_parser::ast::Arithmetic;
-use conch_runtime::env::{VarEnv, VariableEnvironment};
+use conch_runtime::env::{
+    DefaultEnv, VarAttributes, VariableAttributesEnvironment, VariableEnvironment,
+};
 use conch_runtime::error::ExpansionError;
-use conch_runtime::eval::ArithEval;
+use conch_runtime::eval::{assignable_value, eval_arith_str, ArithEval, ArithStrError};
 
 #[tokio::test]
 async fn test_eval_arith() {
@@ -14,7 +16,7 @@ async fn test_eval_arith() {
         Box::new(Literal(i))
     }
 
-    let env = &mut VarEnv::new();
+    let env = &mut DefaultEnv::<String>::new().unwrap();
     let var = "var name".to_owned();
     let var_value = 10;
     let var_string = "var string".to_owned();
@@ -148,3 +150,81 @@ async fn test_eval_arith() {
     assert_eq!(env.var("x").map(|s| &**s), Some("6"));
     assert_eq!(env.var("y").map(|s| &**s), Some("9"));
 }
+
+#[tokio::test]
+async fn test_eval_arith_str() {
+    let env = &mut DefaultEnv::<String>::new().unwrap();
+    env.set_var("x".to_owned(), "5".to_owned());
+
+    assert_eq!(eval_arith_str("", env), Ok(0));
+    assert_eq!(eval_arith_str("x << 2 | 1", env), Ok(21));
+    assert_eq!(eval_arith_str("y = x++ * 2, y + 1", env), Ok(11));
+    assert_eq!(env.var("x").map(|s| &**s), Some("6"));
+    assert_eq!(env.var("y").map(|s| &**s), Some("10"));
+
+    assert_eq!(
+        eval_arith_str("1 / (x - 6)", env),
+        Err(ArithStrError::Expansion(ExpansionError::DivideByZero))
+    );
+
+    for src in &["1 +", "1 2", "(1", "1 )"] {
+        assert_eq!(
+            eval_arith_str(src, env),
+            Err(ArithStrError::Syntax((*src).to_owned()))
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_integer_vars_evaluate_assignments_as_arithmetic() {
+    let env = &mut DefaultEnv::<String>::new().unwrap();
+    let var = "var".to_owned();
+    env.set_var_attributes(
+        var.clone(),
+        VarAttributes {
+            integer: true,
+            ..VarAttributes::default()
+        },
+    );
+
+    let assign = |value: &str, env: &mut DefaultEnv<String>| {
+        assignable_value(&var, value.to_owned(), env).map(|value| env.set_var(var.clone(), value))
+    };
+
+    assert_eq!(assign("2 ** 3 > 7 ? 0x10 : 0", env), Ok(()));
+    assert_eq!(env.var(&var).map(|s| &**s), Some("16"));
+
+    // Side effects of the expression are applied to the environment
+    assert_eq!(assign("other = var / 2, var + other", env), Ok(()));
+    assert_eq!(env.var(&var).map(|s| &**s), Some("24"));
+    assert_eq!(env.var("other").map(|s| &**s), Some("8"));
+
+    // Invalid expressions are reported and leave the variable untouched
+    assert_eq!(
+        assign("var +", env),
+        Err(ExpansionError::ArithSyntax("var +".to_owned()))
+    );
+    assert_eq!(assign("var / 0", env), Err(ExpansionError::DivideByZero));
+    assert_eq!(env.var(&var).map(|s| &**s), Some("24"));
+}
+
+#[tokio::test]
+async fn test_arith_cmd() {
+    use conch_parser::ast::Arithmetic::*;
+    use conch_runtime::spawn::arith_cmd;
+    use conch_runtime::{EXIT_ERROR, EXIT_SUCCESS};
+
+    let env = &mut DefaultEnv::<String>::new().unwrap();
+    let var = "var".to_owned();
+
+    let expr = PostIncr(var.clone());
+    assert_eq!(arith_cmd(&expr, env).await.unwrap().await, EXIT_ERROR);
+    assert_eq!(arith_cmd(&expr, env).await.unwrap().await, EXIT_SUCCESS);
+    assert_eq!(env.var(&var).map(|s| &**s), Some("2"));
+
+    let expr = Div(Box::new(Var(var)), Box::new(Literal(0)));
+    assert_eq!(
+        arith_cmd(&expr, env).await.err(),
+        Some(ExpansionError::DivideByZero)
+    );
+}
@@ -161,6 +161,28 @@ async fn test_double_quoted_param_star_expands_but_joined_by_ifs() {
     assert_eval_equals_single(double_quoted.clone(), Some(""), "fooonetwothreebar").await;
 }
 
+#[tokio::test]
+async fn test_double_quoted_joined_by_ifs_with_str_var_names() {
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::All,
+        split_fields_further: true,
+    };
+
+    let mut env = VarEnv::<&'static str, String>::new();
+    env.set_var("IFS", "!".to_owned());
+
+    let double_quoted: Word = DoubleQuoted(vec![mock_word_fields(Fields::Star(vec![
+        "one".to_owned(),
+        "two".to_owned(),
+    ]))]);
+
+    let future = double_quoted
+        .eval_with_config(&mut env, cfg)
+        .await
+        .expect("eval failed");
+    assert_eq!(Fields::Single("one!two".to_owned()), future.await);
+}
+
 #[tokio::test]
 async fn test_double_quoted_param_at_zero_fields_if_no_args() {
     let double_quoted = DoubleQuoted(vec![mock_word_fields(Fields::At(vec![]))]);
@@ -175,3 +197,34 @@ async fn test_double_quoted_no_field_splitting() {
     })]);
     assert_eval_equals_fields(double_quoted, Fields::Zero).await;
 }
+
+#[tokio::test]
+async fn test_quoted_words_are_escaped_when_evaluated_as_patterns() {
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::None,
+        split_fields_further: false,
+    };
+
+    let mut env = VarEnv::<String, String>::new();
+
+    let unquoted: Word = Simple(mock_word_fields(Fields::Single("*?".to_owned())));
+    assert_eq!(
+        Ok("*?".to_owned()),
+        unquoted.eval_pattern(&mut env, cfg).await
+    );
+
+    let single_quoted: Word = SingleQuoted("*?".to_owned());
+    assert_eq!(
+        Ok("[*][?]".to_owned()),
+        single_quoted.eval_pattern(&mut env, cfg).await
+    );
+
+    let double_quoted: Word = DoubleQuoted(vec![
+        mock_word_fields(Fields::Single("[a]".to_owned())),
+        mock_word_fields(Fields::Split(vec!["*".to_owned(), "b".to_owned()])),
+    ]);
+    assert_eq!(
+        Ok("[[]a[]][*] b".to_owned()),
+        double_quoted.eval_pattern(&mut env, cfg).await
+    );
+}
@@ -53,6 +53,18 @@ async fn single_command_status_inversion_on_error() {
     assert_eq!(run(list).await, Err(MockErr::Fatal(true)));
 }
 
+#[tokio::test]
+async fn pipefail_option_is_respected() {
+    let exit = ExitStatus::Code(42);
+    let list = || ListableCommand::Pipe(false, vec![mock_status(exit), mock_status(EXIT_SUCCESS)]);
+
+    let mut env = new_env_with_no_fds();
+    assert_eq!(list().spawn(&mut env).await.unwrap().await, EXIT_SUCCESS);
+
+    env.set_option(ShellOption::PipeFail, true);
+    assert_eq!(list().spawn(&mut env).await.unwrap().await, exit);
+}
+
 #[tokio::test]
 async fn single_command_env_changes_remain() {
     const VAR: &str = "var";
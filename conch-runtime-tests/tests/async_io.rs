@@ -3,8 +3,10 @@
 use conch_runtime::env::{AsyncIoEnvironment, TokioAsyncIoEnv};
 use conch_runtime::io::{FileDesc, Pipe};
 use futures_util::future::try_join3;
+use futures_util::stream::StreamExt;
 use std::borrow::Cow;
 use std::fs::File;
+use std::io::Write;
 
 #[macro_use]
 pub mod support;
@@ -32,6 +34,26 @@ async fn pipe() {
     assert_eq!(read_msg_best_effort, msg.as_bytes());
 }
 
+#[tokio::test]
+async fn read_chunks_as_available() {
+    let Pipe { reader, mut writer } = Pipe::new().expect("failed to create pipe");
+    let mut env = TokioAsyncIoEnv::new();
+    let mut chunks = env.read_chunks(reader);
+
+    writer.write_all(b"hello").expect("write failed");
+    let first = chunks.next().await.expect("no chunk").expect("read failed");
+    assert_eq!(first, b"hello");
+
+    writer.write_all(b" world").expect("write failed");
+    drop(writer);
+
+    let mut rest = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        rest.extend(chunk.expect("read failed"));
+    }
+    assert_eq!(rest, b" world");
+}
+
 #[tokio::test]
 async fn file() {
     let tempdir = mktmp!();
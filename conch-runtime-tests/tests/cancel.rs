@@ -0,0 +1,146 @@
+#![deny(rust_2018_idioms)]
+
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+mod support;
+pub use self::support::*;
+
+fn cancel_after(token: &CancellationToken, duration: Duration) {
+    let token = token.clone();
+    tokio::spawn(async move {
+        tokio::time::delay_for(duration).await;
+        token.cancel();
+    });
+}
+
+#[tokio::test]
+async fn should_propagate_result_if_not_cancelled() {
+    let token = CancellationToken::new();
+    let exit = ExitStatus::Code(42);
+
+    assert_eq!(
+        with_cancellation(&token, mock_status(exit), &mut ()).await,
+        Ok(exit)
+    );
+    assert_eq!(
+        with_cancellation(&token, mock_error(false), &mut ()).await,
+        Err(MockErr::Fatal(false))
+    );
+}
+
+#[tokio::test]
+async fn should_not_spawn_anything_if_already_cancelled() {
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(token.is_cancelled());
+
+    assert_eq!(
+        with_cancellation(&token, mock_panic("should not spawn"), &mut ()).await,
+        Err(MockErr::from(RuntimeError::Cancelled))
+    );
+}
+
+#[tokio::test]
+async fn should_cancel_pending_command() {
+    struct MockNeverFinishes;
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> Spawn<E> for MockNeverFinishes {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            Ok(Box::pin(pending()))
+        }
+    }
+
+    let token = CancellationToken::new();
+    cancel_after(&token, Duration::from_millis(10));
+
+    assert_eq!(
+        with_cancellation(&token, MockNeverFinishes, &mut ()).await,
+        Err(MockErr::from(RuntimeError::Cancelled))
+    );
+}
+
+#[tokio::test]
+async fn should_wake_waiters_even_if_others_were_dropped() {
+    let token = CancellationToken::new();
+
+    for _ in 0..10 {
+        let mut dropped = Box::pin(token.cancelled());
+        assert_eq!(futures_util::poll!(dropped.as_mut()), Poll::Pending);
+    }
+
+    cancel_after(&token, Duration::from_millis(10));
+    token.cancelled().await;
+    assert!(token.is_cancelled());
+}
+
+#[tokio::test]
+async fn should_cancel_loops_which_are_always_ready() {
+    struct MockInfiniteLoop;
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for MockInfiniteLoop
+    where
+        E: ?Sized + Send + ControlFlowEnvironment + LastStatusEnvironment + ShellOptionsEnvironment,
+    {
+        type Error = MockErr;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let guard = mock_status(EXIT_SUCCESS);
+            let body = mock_status(EXIT_SUCCESS);
+            let status = loop_cmd(false, guard, body, env).await?;
+            Ok(Box::pin(async move { status }))
+        }
+    }
+
+    let token = CancellationToken::new();
+    cancel_after(&token, Duration::from_millis(10));
+
+    let mut env = new_env();
+    assert_eq!(
+        with_cancellation(&token, MockInfiniteLoop, &mut env).await,
+        Err(MockErr::from(RuntimeError::Cancelled))
+    );
+}
+
+#[tokio::test]
+async fn should_cancel_for_loops_which_are_always_ready() {
+    struct MockInfiniteForLoop;
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for MockInfiniteForLoop
+    where
+        E: ?Sized
+            + Send
+            + ControlFlowEnvironment
+            + LastStatusEnvironment
+            + ShellOptionsEnvironment
+            + VariableEnvironment<VarName = Arc<String>, Var = Arc<String>>,
+    {
+        type Error = MockErr;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let args = (0..).map(|i: usize| Arc::new(i.to_string()));
+            let body = mock_status(EXIT_SUCCESS);
+            for_with_args(Arc::new("var".to_owned()), args, body, env).await
+        }
+    }
+
+    let token = CancellationToken::new();
+    cancel_after(&token, Duration::from_millis(10));
+
+    let mut env = new_env();
+    assert_eq!(
+        with_cancellation(&token, MockInfiniteForLoop, &mut env).await,
+        Err(MockErr::from(RuntimeError::Cancelled))
+    );
+}
+
+#[tokio::test]
+async fn yield_now_resolves() {
+    yield_now().await;
+}
@@ -0,0 +1,71 @@
+#![deny(rust_2018_idioms)]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn mock_clock_only_moves_when_advanced() {
+    let env = MockClockEnv::new();
+    let start = env.now();
+    assert_eq!(env.now(), start);
+
+    env.advance(Duration::from_secs(5));
+    assert_eq!(env.now(), start + Duration::from_secs(5));
+
+    // Sub environments share the same clock
+    let sub = env.sub_env();
+    sub.advance(Duration::from_secs(1));
+    assert_eq!(env.now(), start + Duration::from_secs(6));
+}
+
+#[tokio::test]
+async fn mock_clock_wall_time_only_moves_when_advanced_or_set() {
+    let env = MockClockEnv::new();
+    let start = env.now();
+
+    env.set_system_time(UNIX_EPOCH + Duration::from_secs(100));
+    assert_eq!(env.system_time(), UNIX_EPOCH + Duration::from_secs(100));
+    assert_eq!(env.now(), start);
+
+    env.advance(Duration::from_secs(5));
+    assert_eq!(env.system_time(), UNIX_EPOCH + Duration::from_secs(105));
+    assert_eq!(env.sub_env().system_time(), env.system_time());
+}
+
+#[tokio::test]
+async fn seeded_random_is_reproducible_and_in_range() {
+    let mut first = RandomEnv::with_seed(42);
+    let mut second = RandomEnv::with_seed(42);
+
+    let first = (0..100).map(|_| first.next_random()).collect::<Vec<_>>();
+    let second = (0..100).map(|_| second.next_random()).collect::<Vec<_>>();
+
+    assert_eq!(first, second);
+    assert!(first.iter().all(|&n| n <= RANDOM_MAX));
+
+    for seed in [0, 0x9E37_79B9_7F4A_7C15] {
+        let mut env = RandomEnv::with_seed(seed);
+        assert!((0..10).any(|_| env.next_random() != 0), "seed: {:#x}", seed);
+    }
+}
+
+#[tokio::test]
+async fn random_sub_env_continues_from_parent_state() {
+    let mut env = RandomEnv::with_seed(7);
+    env.next_random();
+
+    let mut sub = env.sub_env();
+    assert_eq!(sub.next_random(), env.next_random());
+}
+
+#[tokio::test]
+async fn mock_random_replays_values() {
+    let mut env = MockRandomEnv::new(vec![1, 2, u16::max_value()]);
+
+    assert_eq!(env.next_random(), 1);
+    assert_eq!(env.next_random(), 2);
+    assert_eq!(env.next_random(), RANDOM_MAX);
+    assert_eq!(env.next_random(), 1);
+}
@@ -65,3 +65,48 @@ async fn change_cur_dir_should_accept_relative_paths() {
     expected.pop();
     assert_eq!(env.current_working_dir(), expected);
 }
+
+#[tokio::test]
+async fn canonical_cur_dir_should_resolve_symlinks() {
+    let tempdir = mktmp!();
+    let tempdir_path = tempdir
+        .path()
+        .canonicalize()
+        .expect("failed to canonicalize");
+
+    let path_real = tempdir_path.join("real");
+    let path_sym = tempdir_path.join("sym");
+    std::fs::create_dir(&path_real).expect("failed to create real");
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&path_real, &path_sym).expect("failed to create symlink");
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(&path_real, &path_sym).expect("failed to create symlink");
+
+    let env = VirtualWorkingDirEnv::new(&path_sym).unwrap();
+    assert_eq!(env.current_working_dir(), path_sym);
+    assert_eq!(env.canonical_working_dir().unwrap(), path_real);
+}
+
+#[tokio::test]
+async fn path_helpers_should_resolve_relative_to_cur_dir() {
+    let tempdir = mktmp!();
+    let tempdir_path = tempdir
+        .path()
+        .canonicalize()
+        .expect("failed to canonicalize");
+
+    let path_real = tempdir_path.join("real");
+    std::fs::create_dir(&path_real).expect("failed to create real");
+    std::fs::write(path_real.join("file"), "").expect("failed to create file");
+
+    let env = VirtualWorkingDirEnv::new(&tempdir_path).unwrap();
+    let relative = Path::new("real/../real/file");
+    assert_eq!(env.resolve(relative), path_real.join("file"));
+    assert_eq!(env.resolve(&path_real), path_real);
+    assert_eq!(env.canonicalize(relative).unwrap(), path_real.join("file"));
+
+    assert!(env.path_exists(relative));
+    assert!(env.path_metadata(Path::new("real")).unwrap().is_dir());
+    assert!(!env.path_exists(Path::new("missing")));
+}
@@ -1,6 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use std::borrow::Cow;
+use std::sync::Arc;
 
 #[macro_use]
 pub mod support;
@@ -45,3 +46,83 @@ async fn sets_pwd_and_oldpwd_env_vars() {
         Some(&*env.current_working_dir().to_string_lossy())
     );
 }
+
+#[tokio::test]
+async fn snapshot_should_capture_and_restore_state() {
+    let mut env = Env::with_config(
+        DefaultEnvConfig::<String>::new()
+            .expect("failed to create test env")
+            .change_var_env(VarEnv::new())
+            .change_fn_error::<MockErr>(),
+    );
+
+    let tempdir = mktmp!();
+    let first = tempdir.path().join("first");
+    let second = tempdir.path().join("second");
+    std::fs::create_dir(&first).unwrap();
+    std::fs::create_dir(&second).unwrap();
+
+    env.change_working_dir(Cow::Borrowed(&first))
+        .expect("failed to cd");
+    env.set_exported_var("exported".to_owned(), "foo".to_owned(), true);
+    env.set_var("local".to_owned(), "bar".to_owned());
+    env.set_function("func".to_owned(), Arc::new(mock_status(EXIT_SUCCESS)));
+    env.set_option(ShellOption::ErrExit, true);
+    env.set_last_status(ExitStatus::Code(42));
+
+    let snapshot = env.snapshot();
+    assert_eq!(snapshot.working_dir, first);
+    assert_eq!(snapshot.functions, vec!("func".to_owned()));
+    assert_eq!(snapshot.options, vec!(ShellOption::ErrExit));
+    assert_eq!(snapshot.last_status, ExitStatus::Code(42));
+    assert!(snapshot.vars.contains(&SnapshotVar {
+        name: "local".to_owned(),
+        value: "bar".to_owned(),
+        exported: false,
+    }));
+
+    env.change_working_dir(Cow::Borrowed(&second))
+        .expect("failed to cd");
+    env.set_var("exported".to_owned(), "changed".to_owned());
+    env.unset_var(&"local".to_owned());
+    env.set_var("new".to_owned(), "new".to_owned());
+    env.unset_function(&"func".to_owned());
+    env.set_function("new_func".to_owned(), Arc::new(mock_status(EXIT_SUCCESS)));
+    env.set_option(ShellOption::ErrExit, false);
+    env.set_option(ShellOption::NoGlob, true);
+    env.set_last_status(EXIT_SUCCESS);
+
+    let json = serde_json::to_string(&snapshot).expect("serialize failed");
+    let snapshot: EnvSnapshot = serde_json::from_str(&json).expect("deserialize failed");
+    env.restore_from(&snapshot).expect("restore failed");
+
+    assert_eq!(env.current_working_dir(), first);
+    assert_eq!(
+        env.exported_var(&"exported".to_owned()),
+        Some((&"foo".to_owned(), true))
+    );
+    assert_eq!(
+        env.exported_var(&"local".to_owned()),
+        Some((&"bar".to_owned(), false))
+    );
+    assert_eq!(env.var("new"), None);
+    assert!(!env.has_function(&"new_func".to_owned()));
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert!(!env.is_option_enabled(ShellOption::NoGlob));
+    assert_eq!(env.last_status(), ExitStatus::Code(42));
+    assert_eq!(env.snapshot().functions, Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn restoring_snapshot_should_fail_if_working_dir_is_missing() {
+    let mut env = DefaultEnv::<String>::new().unwrap();
+    let tempdir = mktmp!();
+
+    let mut snapshot = env.snapshot();
+    snapshot.working_dir = tempdir.path().join("missing");
+    snapshot.last_status = ExitStatus::Code(42);
+
+    env.restore_from(&snapshot)
+        .expect_err("restore should have failed");
+    assert_eq!(env.last_status(), EXIT_SUCCESS);
+}
@@ -2,6 +2,8 @@
 
 use conch_parser::ast;
 use conch_parser::ast::SimpleWord::*;
+use std::collections::VecDeque;
+use std::sync::Arc;
 
 mod support;
 pub use self::support::*;
@@ -19,7 +21,7 @@ async fn assert_eval_equals_fields(word: SimpleWord, fields: Fields<String>) {
         split_fields_further: true,
     };
 
-    let mut env = VarEnv::<String, String>::new();
+    let mut env = DefaultEnv::<String>::new().unwrap();
     let future = word
         .eval_with_config(&mut env, cfg)
         .await
@@ -58,7 +60,7 @@ async fn test_lone_tilde_expansion() {
     };
 
     let home_value = "foo bar".to_owned();
-    let mut env = VarEnv::new();
+    let mut env = DefaultEnv::<String>::new().unwrap();
     env.set_var("HOME".to_owned(), home_value.clone());
 
     let word: SimpleWord = Tilde;
@@ -84,7 +86,7 @@ async fn test_subst_error() {
         split_fields_further: false,
     };
 
-    let mut env = VarEnv::<String, String>::new();
+    let mut env = DefaultEnv::<String>::new().unwrap();
     let word: SimpleWord = Subst(mock_word_error(true));
 
     assert_eq!(
@@ -104,6 +106,62 @@ async fn test_param_unset() {
     assert_eval_equals_fields(Param(MockParam::Fields(None)), Fields::Zero).await;
 }
 
+#[tokio::test]
+async fn test_param_unset_with_nounset() {
+    let mut env = DefaultEnv::<String>::new().unwrap();
+    env.set_option(ShellOption::NoUnset, true);
+
+    let word: SimpleWord = Param(MockParam::Fields(Some(Fields::Zero)));
+    let future = word.eval(&mut env).await.expect("eval failed");
+    assert_eq!(Fields::Zero, future.await);
+
+    let word: SimpleWord = Param(MockParam::Fields(None));
+    let err = ExpansionError::UnsetParameter("MockParam".to_owned());
+    assert_eq!(
+        Some(MockErr::ExpansionError(err)),
+        word.eval(&mut env).await.err()
+    );
+}
+
+#[tokio::test]
+async fn test_nounset_excludes_at_and_star() {
+    type SimpleWord = ast::SimpleWord<String, ast::Parameter<String>, MockWord>;
+
+    let mut env = DefaultEnv::<String>::new().unwrap();
+    env.set_option(ShellOption::NoUnset, true);
+    env.set_args(Arc::new(vec!["foo".to_owned()].into()));
+
+    for param in vec![ast::Parameter::At, ast::Parameter::Star] {
+        let word: SimpleWord = Param(param);
+        assert!(word.eval(&mut env).await.is_ok());
+    }
+
+    let word: SimpleWord = Param(ast::Parameter::Positional(1));
+    let future = word.eval(&mut env).await.expect("eval failed");
+    assert_eq!(Fields::Single("foo".to_owned()), future.await);
+
+    let unset = vec![
+        ast::Parameter::Positional(2),
+        ast::Parameter::Var("unset_var".to_owned()),
+    ];
+
+    for param in unset {
+        let err = ExpansionError::UnsetParameter(param.to_string());
+        let word: SimpleWord = Param(param);
+        assert_eq!(
+            Some(MockErr::ExpansionError(err)),
+            word.eval(&mut env).await.err()
+        );
+    }
+
+    env.set_args(Arc::new(VecDeque::new()));
+    for param in vec![ast::Parameter::At, ast::Parameter::Star] {
+        let word: SimpleWord = Param(param);
+        let future = word.eval(&mut env).await.expect("eval failed");
+        assert_eq!(Fields::Zero, future.await);
+    }
+}
+
 #[tokio::test]
 async fn test_param_splitting() {
     for &split in &[true, false] {
@@ -115,7 +173,7 @@ async fn test_param_splitting() {
         // Specific fields here aren't too important
         let fields = Fields::Split(vec!["~".to_owned(), "foo".to_owned()]);
 
-        let mut env = VarEnv::<String, String>::new();
+        let mut env = DefaultEnv::<String>::new().unwrap();
         let word: SimpleWord = Param(MockParam::Split(split, fields.clone()));
         let future = word
             .eval_with_config(&mut env, cfg)
@@ -5,7 +5,10 @@ use futures_util::future::{join4, FutureExt};
 use std::borrow::Cow;
 use std::env::current_dir;
 use std::ffi::OsStr;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
+#[macro_use]
 mod support;
 pub use self::support::*;
 
@@ -30,6 +33,10 @@ async fn spawn_executable_with_io() {
         stdin: Some(pipe_in.reader.try_unwrap().expect("unwrap failed")),
         stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
         stderr: Some(pipe_err.writer.try_unwrap().expect("unwrap failed")),
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
     };
 
     let pipe_in_writer = pipe_in.writer;
@@ -79,6 +86,10 @@ async fn env_vars_set_from_data_without_inheriting_from_process() {
         stdin: None,
         stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
         stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
     };
 
     let child = env.spawn_executable(data).expect("spawn failed");
@@ -111,6 +122,10 @@ async fn remote_spawn_smoke() {
         stdin: None,
         stdout: None,
         stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
     };
 
     // Spawning when not running in a task is the same as spawning
@@ -136,6 +151,10 @@ async fn defines_empty_path_env_var_if_not_provided_by_caller() {
         stdin: None,
         stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
         stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
     };
 
     let child = env.spawn_executable(data).expect("child failed");
@@ -147,3 +166,405 @@ async fn defines_empty_path_env_var_if_not_provided_by_caller() {
     assert_eq!(b"PATH=\n", &*stdout.await.expect("read failed"));
     assert!(child.await.success());
 }
+
+#[tokio::test]
+async fn child_killed_once_deadline_elapses() {
+    let env = TokioExecEnv::new();
+    let mut io_env = TokioFileDescManagerEnv::new();
+
+    // Holding on to the writer ensures the child blocks on reading stdin forever
+    let pipe_in = io_env.open_pipe().unwrap();
+
+    let bin_path = bin_path("cat-dup");
+    let data = ExecutableData {
+        name: OsStr::new(&bin_path),
+        args: &[],
+        env_vars: &[],
+        current_dir: &current_dir().expect("failed to get current_dir"),
+        stdin: Some(pipe_in.reader.try_unwrap().expect("unwrap failed")),
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: Some(Instant::now() + Duration::from_millis(100)),
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let status = child.await;
+    drop(pipe_in.writer);
+
+    assert!(!status.success());
+    #[cfg(unix)]
+    assert_eq!(status, ExitStatus::Signal(9)); // SIGKILL
+}
+
+#[cfg(unix)]
+fn create_script_without_shebang(dir: &std::path::Path) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.join("script");
+    std::fs::write(&script, "echo \"$@\"\n").expect("failed to write script");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("failed to set permissions");
+    script
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn enoexec_is_not_executable_without_fallback_interpreter() {
+    let tempdir = mktmp!();
+    let script = create_script_without_shebang(tempdir.path());
+
+    let env = TokioExecEnv::new();
+    let data = ExecutableData {
+        name: OsStr::new(&script),
+        args: &[],
+        env_vars: &[],
+        current_dir: tempdir.path(),
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    match env.spawn_executable(data) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(
+            e,
+            CommandError::NotExecutable(script.to_string_lossy().into_owned())
+        ),
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn enoexec_falls_back_to_running_interpreter_with_original_args() {
+    let tempdir = mktmp!();
+    let script = create_script_without_shebang(tempdir.path());
+
+    let env = TokioExecEnv::with_enoexec_interpreter("/bin/sh");
+    let mut io_env = TokioFileDescManagerEnv::new();
+
+    let pipe_out = io_env.open_pipe().unwrap();
+
+    let data = ExecutableData {
+        name: OsStr::new(&script),
+        args: &[OsStr::new("foo"), OsStr::new("bar")],
+        env_vars: &[],
+        current_dir: tempdir.path(),
+        stdin: None,
+        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let stdout = io_env.read_all(pipe_out.reader);
+
+    drop(env);
+    drop(io_env);
+
+    assert_eq!(b"foo bar\n", &*stdout.await.expect("read failed"));
+    assert!(child.await.success());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn resource_limits_applied_to_child() {
+    let mut env = TokioExecEnv::new();
+    env.set_resource_limits(vec![ResourceLimit::new(Resource::CoreFileSize, 0)]);
+
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe_out = io_env.open_pipe().unwrap();
+
+    let data = ExecutableData {
+        name: OsStr::new("/bin/sh"),
+        args: &[OsStr::new("-c"), OsStr::new("ulimit -c; ulimit -n")],
+        env_vars: &[],
+        current_dir: &current_dir().expect("failed to get current_dir"),
+        stdin: None,
+        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[ResourceLimit {
+            resource: Resource::OpenFiles,
+            soft: Some(42),
+            hard: None,
+        }],
+        process_group: None,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let stdout = io_env.read_all(pipe_out.reader);
+
+    drop(env);
+    drop(io_env);
+
+    assert_eq!(b"0\n42\n", &*stdout.await.expect("read failed"));
+    assert!(child.await.success());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn invalid_resource_limits_fail_spawn() {
+    let env = TokioExecEnv::new();
+
+    let data = ExecutableData {
+        name: OsStr::new("/bin/sh"),
+        args: &[],
+        env_vars: &[],
+        current_dir: &current_dir().expect("failed to get current_dir"),
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[ResourceLimit {
+            resource: Resource::OpenFiles,
+            soft: Some(100),
+            hard: Some(10),
+        }],
+        process_group: None,
+    };
+
+    match env.spawn_executable(data) {
+        Ok(_) => panic!("unexpected success"),
+        Err(CommandError::Io(_, Some(name))) => assert_eq!(name, "/bin/sh"),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn spawn_and_get_pid_and_pgid(env: &TokioExecEnv, group: Option<ProcessGroup>) -> (u32, u32) {
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe_out = io_env.open_pipe().unwrap();
+
+    let data = ExecutableData {
+        name: OsStr::new("/bin/sh"),
+        args: &[
+            OsStr::new("-c"),
+            OsStr::new("read -r pid comm state ppid pgrp rest < /proc/$$/stat; echo $pid $pgrp"),
+        ],
+        env_vars: &[],
+        current_dir: &current_dir().expect("failed to get current_dir"),
+        stdin: None,
+        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: group,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let stdout = io_env.read_all(pipe_out.reader);
+    drop(io_env);
+
+    let out = String::from_utf8(stdout.await.expect("read failed")).unwrap();
+    assert!(child.await.success());
+
+    let mut ids = out.split_whitespace().map(|id| id.parse().unwrap());
+    (ids.next().unwrap(), ids.next().unwrap())
+}
+
+#[cfg(target_os = "linux")]
+fn current_pgid() -> u32 {
+    let stat = std::fs::read_to_string("/proc/self/stat").unwrap();
+    // The command name may contain spaces, so skip past its closing paren
+    let rest = &stat[stat.rfind(')').unwrap() + 2..];
+    rest.split_whitespace().nth(2).unwrap().parse().unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn process_groups() {
+    let mut env = TokioExecEnv::new();
+
+    let (_, pgid) = spawn_and_get_pid_and_pgid(&env, None).await;
+    assert_eq!(pgid, current_pgid());
+
+    let (pid, pgid) = spawn_and_get_pid_and_pgid(&env, Some(ProcessGroup::New)).await;
+    assert_eq!(pid, pgid);
+
+    let join = Some(ProcessGroup::Join(current_pgid()));
+    let (_, pgid) = spawn_and_get_pid_and_pgid(&env, join).await;
+    assert_eq!(pgid, current_pgid());
+
+    env.set_job_control(true);
+    let (pid, pgid) = spawn_and_get_pid_and_pgid(&env, None).await;
+    assert_eq!(pid, pgid);
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn executables_of_a_job_share_a_process_group() {
+    let mut env = TokioExecEnv::new();
+    env.set_job_control(true);
+
+    assert!(env.begin_job());
+    assert!(!env.begin_job());
+
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe_in = io_env.open_pipe().unwrap();
+
+    // Both children stay alive until their stdin is closed
+    let mut spawn = |env: &TokioExecEnv| {
+        let pipe_out = io_env.open_pipe().unwrap();
+        let data = ExecutableData {
+            name: OsStr::new("/bin/sh"),
+            args: &[
+                OsStr::new("-c"),
+                OsStr::new(
+                    "read -r pid comm state ppid pgrp rest < /proc/$$/stat; echo $pid $pgrp; read -r x || true",
+                ),
+            ],
+            env_vars: &[],
+            current_dir: &current_dir().expect("failed to get current_dir"),
+            stdin: Some(pipe_in.reader.duplicate().unwrap()),
+            stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+            stderr: None,
+            inherited_fds: Vec::new(),
+            deadline: None,
+            resource_limits: &[],
+            process_group: None,
+        };
+
+        let child = env.spawn_executable(data).expect("spawn failed");
+        (child, io_env.read_all(pipe_out.reader))
+    };
+
+    let (first, first_out) = spawn(&env);
+    let (second, second_out) = spawn(&env.sub_env());
+    drop(pipe_in);
+    drop(io_env);
+
+    let ids = |out: Vec<u8>| {
+        let out = String::from_utf8(out).unwrap();
+        let mut ids = out.split_whitespace().map(|id| id.parse::<u32>().unwrap());
+        (ids.next().unwrap(), ids.next().unwrap())
+    };
+
+    let (first_pid, first_pgid) = ids(first_out.await.expect("read failed"));
+    let (_, second_pgid) = ids(second_out.await.expect("read failed"));
+    assert!(first.await.success());
+    assert!(second.await.success());
+
+    assert_eq!(first_pid, first_pgid);
+    assert_eq!(first_pgid, second_pgid);
+
+    env.end_job();
+    assert!(env.begin_job());
+}
+
+#[tokio::test]
+async fn arg_list_too_long_fails_before_spawning() {
+    let ptr_size = std::mem::size_of::<*const u8>();
+    let args = [OsStr::new("bar")];
+    let env_vars = [(OsStr::new("a"), OsStr::new("b"))];
+    let data = || ExecutableData {
+        name: OsStr::new("foo"),
+        args: &args,
+        env_vars: &env_vars,
+        current_dir: Path::new("."),
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    // "foo\0" + "bar\0" + "a=b\0" and a pointer to each
+    let size = 12 + 3 * ptr_size;
+
+    let mut env = TokioExecEnv::new();
+    if cfg!(unix) {
+        assert!(env.max_args_size().is_some());
+    }
+
+    env.set_max_args_size(Some(size - 1));
+    match env.spawn_executable(data()) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(
+            e,
+            CommandError::ArgListTooLong("foo".to_owned(), size, size - 1)
+        ),
+    }
+
+    // Within the limit, we fail to find the command instead
+    env.set_max_args_size(Some(size));
+    match env.spawn_executable(data()) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(e, CommandError::NotFound("foo".to_owned())),
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn inherited_fds_are_available_to_executable() {
+    let env = TokioExecEnv::new();
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe = io_env.open_pipe().unwrap();
+
+    let data = ExecutableData {
+        name: OsStr::new("sh"),
+        args: &[OsStr::new("-c"), OsStr::new("echo inherited >&3")],
+        env_vars: &[(OsStr::new("PATH"), OsStr::new("/bin:/usr/bin"))],
+        current_dir: &current_dir().expect("failed to get current_dir"),
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: vec![(3, pipe.writer.try_unwrap().expect("unwrap failed"))],
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let out = io_env.read_all(pipe.reader);
+
+    let (status, out) = futures_util::future::join(child, out).await;
+    assert!(status.success());
+    assert_eq!(out.expect("read failed"), b"inherited\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn relative_executable_paths_are_resolved_against_current_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tempdir = mktmp!();
+    let script = tempdir.path().join("script");
+    std::fs::write(&script, "#!/bin/sh\nexit 42\n").expect("failed to write script");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("failed to set permissions");
+
+    assert_ne!(current_dir().unwrap(), tempdir.path());
+
+    let env = TokioExecEnv::new();
+    let data = ExecutableData {
+        name: OsStr::new("./script"),
+        args: &[],
+        env_vars: &[],
+        current_dir: tempdir.path(),
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let status = env.spawn_executable(data).expect("spawn failed").await;
+    assert_eq!(status, ExitStatus::Code(42));
+}
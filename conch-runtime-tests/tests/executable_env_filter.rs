@@ -0,0 +1,72 @@
+#![deny(rust_2018_idioms)]
+
+use std::env::current_dir;
+use std::ffi::{OsStr, OsString};
+
+mod support;
+pub use self::support::*;
+
+fn spawn_with_vars<E: ExecutableEnvironment>(env: &E, name: &str) {
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData {
+        name: OsStr::new(name),
+        args: &[],
+        env_vars: &[
+            (OsStr::new("PATH"), OsStr::new("/bin")),
+            (OsStr::new("SECRET"), OsStr::new("hunter2")),
+        ],
+        current_dir: &cur_dir,
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    env.spawn_executable(data).expect("spawn failed");
+}
+
+fn os_pair(k: &str, v: &str) -> (OsString, OsString) {
+    (k.into(), v.into())
+}
+
+#[tokio::test]
+async fn filter_can_modify_vars_passed_to_executable() {
+    let mut mock = MockExecEnv::new();
+    mock.register("foo", MockExecOutput::default());
+    mock.register("bar", MockExecOutput::default());
+
+    let env = EnvVarFilterExecEnv::new(mock.clone(), |name, vars| {
+        vars.retain(|(k, _)| k != "SECRET");
+        vars.push(("CMD".into(), name.to_owned()));
+    });
+
+    spawn_with_vars(&env, "foo");
+    spawn_with_vars(&env.sub_env(), "bar");
+
+    let invocations = mock.invocations();
+    assert_eq!(invocations.len(), 2);
+    assert_eq!(
+        invocations[0].env_vars,
+        vec!(os_pair("PATH", "/bin"), os_pair("CMD", "foo"))
+    );
+    assert_eq!(
+        invocations[1].env_vars,
+        vec!(os_pair("PATH", "/bin"), os_pair("CMD", "bar"))
+    );
+}
+
+#[tokio::test]
+async fn allowlist_only_passes_listed_vars() {
+    let mut mock = MockExecEnv::new();
+    mock.register("foo", MockExecOutput::default());
+
+    let env = EnvVarFilterExecEnv::with_allowlist(mock.clone(), vec!["PATH", "HOME"]);
+    spawn_with_vars(&env, "foo");
+
+    let invocations = mock.invocations();
+    assert_eq!(invocations.len(), 1);
+    assert_eq!(invocations[0].env_vars, vec!(os_pair("PATH", "/bin")));
+}
@@ -0,0 +1,159 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::FileDescWrapper;
+use futures_util::future::join3;
+use std::env::current_dir;
+use std::ffi::{OsStr, OsString};
+use std::sync::Arc;
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn canned_output_is_written_and_status_returned() {
+    let mut env = MockExecEnv::new();
+    let mut io_env = TokioFileDescManagerEnv::new();
+
+    env.register(
+        "foo",
+        MockExecOutput::status(ExitStatus::Code(42))
+            .with_stdout("out\n")
+            .with_stderr("err\n"),
+    );
+
+    let pipe_out = io_env.open_pipe().unwrap();
+    let pipe_err = io_env.open_pipe().unwrap();
+    let cur_dir = current_dir().expect("failed to get current_dir");
+
+    let data = ExecutableData {
+        name: OsStr::new("foo"),
+        args: &[OsStr::new("bar")],
+        env_vars: &[(OsStr::new("key"), OsStr::new("val"))],
+        current_dir: &cur_dir,
+        stdin: None,
+        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+        stderr: Some(pipe_err.writer.try_unwrap().expect("unwrap failed")),
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let stdout = io_env.read_all(pipe_out.reader);
+    let stderr = io_env.read_all(pipe_err.reader);
+    drop(io_env);
+
+    let (status, out, err) = join3(child, stdout, stderr).await;
+
+    assert_eq!(status, ExitStatus::Code(42));
+    assert_eq!(out.unwrap(), b"out\n");
+    assert_eq!(err.unwrap(), b"err\n");
+
+    assert_eq!(
+        env.invocations(),
+        vec![MockExecInvocation {
+            name: OsString::from("foo"),
+            args: vec![OsString::from("bar")],
+            env_vars: vec![(OsString::from("key"), OsString::from("val"))],
+            current_dir: cur_dir,
+            inherited_fds: vec![],
+        }]
+    );
+}
+
+#[tokio::test]
+async fn closures_can_script_outcomes_and_paths_match_file_name() {
+    let mut env = MockExecEnv::new();
+    env.register_fn("foo", |invocation| {
+        if invocation.args.is_empty() {
+            Err(CommandError::NotExecutable("foo".to_owned()))
+        } else {
+            Ok(MockExecOutput::status(ExitStatus::Code(
+                invocation.args.len() as i32,
+            )))
+        }
+    });
+
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = |args| ExecutableData {
+        name: OsStr::new("/usr/bin/foo"),
+        args,
+        env_vars: &[],
+        current_dir: &cur_dir,
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    let args = [OsStr::new("a"), OsStr::new("b")];
+    let child = env.spawn_executable(data(&args)).expect("spawn failed");
+    assert_eq!(child.await, ExitStatus::Code(2));
+
+    match env.spawn_executable(data(&[])) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(e, CommandError::NotExecutable("foo".to_owned())),
+    }
+
+    assert_eq!(env.invocations().len(), 2);
+}
+
+#[tokio::test]
+async fn unregistered_commands_are_not_found() {
+    let mut env = MockExecEnv::new();
+    env.register("foo", MockExecOutput::default());
+    env.unregister("foo");
+
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData {
+        name: OsStr::new("foo"),
+        args: &[],
+        env_vars: &[],
+        current_dir: &cur_dir,
+        stdin: None,
+        stdout: None,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    };
+
+    match env.spawn_executable(data) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(e, CommandError::NotFound("foo".to_owned())),
+    }
+}
+
+#[tokio::test]
+async fn simple_command_uses_mock_registry() {
+    let mut exec_env = MockExecEnv::new();
+    exec_env.register("foo", MockExecOutput::status(ExitStatus::Code(5)));
+
+    let cfg = DefaultEnvConfigArc::new()
+        .expect("failed to create env cfg")
+        .change_file_desc_manager_env(TokioFileDescManagerEnv::new())
+        .change_exec_env(exec_env.clone())
+        .change_fn_error::<MockErr>();
+    let mut env = Env::with_config(cfg);
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("foo".to_owned()))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("bar".to_owned()))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(ExitStatus::Code(5), future.await.unwrap().await);
+
+    let invocations = exec_env.invocations();
+    assert_eq!(invocations.len(), 1);
+    assert_eq!(invocations[0].args, vec![OsString::from("bar")]);
+}
@@ -0,0 +1,84 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::{FileDesc, Pipe};
+use std::env::current_dir;
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+
+mod support;
+pub use self::support::*;
+
+fn spawn<E: ExecutableEnvironment>(
+    env: &E,
+    name: &str,
+    stderr: Option<FileDesc>,
+) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    env.spawn_executable(ExecutableData {
+        name: OsStr::new(name),
+        args: &[OsStr::new("arg")],
+        env_vars: &[],
+        current_dir: &cur_dir,
+        stdin: None,
+        stdout: None,
+        stderr,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    })
+}
+
+#[tokio::test]
+async fn hook_is_only_invoked_for_missing_commands() {
+    let mut mock = MockExecEnv::new();
+    mock.register("foo", MockExecOutput::status(ExitStatus::Code(5)));
+
+    let env = CommandNotFoundExecEnv::new(mock.clone(), |data| {
+        assert_eq!(data.name, "bar");
+        assert_eq!(data.args, [OsStr::new("arg")]);
+        Ok(Box::pin(async { ExitStatus::Code(42) }) as BoxFuture<'static, _>)
+    });
+
+    let foo = spawn(&env, "foo", None).expect("spawn failed");
+    assert_eq!(foo.await, ExitStatus::Code(5));
+
+    let bar = spawn(&env.sub_env(), "bar", None).expect("spawn failed");
+    assert_eq!(bar.await, ExitStatus::Code(42));
+
+    // Missing commands are detected without attempting to spawn them
+    assert_eq!(mock.invocations().len(), 1);
+}
+
+#[tokio::test]
+async fn hook_is_invoked_for_commands_missing_from_path() {
+    let env = CommandNotFoundExecEnv::new(TokioExecEnv::new(), |data| {
+        assert!(data.stderr.is_some());
+        Ok(Box::pin(async { ExitStatus::Code(42) }) as BoxFuture<'static, _>)
+    });
+
+    let Pipe { writer, .. } = Pipe::new().expect("failed to create pipe");
+    let missing = spawn(&env, "conch_runtime_missing_command", Some(writer));
+    assert_eq!(missing.expect("spawn failed").await, ExitStatus::Code(42));
+}
+
+#[tokio::test]
+async fn hook_can_write_suggestions_and_preserve_not_found() {
+    let env = CommandNotFoundExecEnv::new(MockExecEnv::new(), |data| {
+        let name = data.name.to_string_lossy().into_owned();
+        let mut stderr = data.stderr.expect("missing stderr");
+        writeln!(stderr, "try installing {}", name).expect("write failed");
+        Err(CommandError::NotFound(name))
+    });
+
+    let Pipe { mut reader, writer } = Pipe::new().expect("failed to create pipe");
+    match spawn(&env, "foo", Some(writer)) {
+        Err(CommandError::NotFound(name)) => assert_eq!(name, "foo"),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("unexpected success"),
+    }
+
+    let mut msg = String::new();
+    reader.read_to_string(&mut msg).expect("read failed");
+    assert_eq!(msg, "try installing foo\n");
+}
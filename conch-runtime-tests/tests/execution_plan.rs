@@ -0,0 +1,100 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::{FileDesc, Permissions};
+use conch_runtime::{Fd, STDOUT_FILENO};
+use std::ffi::OsStr;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn spawn<E: ExecutableEnvironment>(
+    env: &E,
+    name: &str,
+    cur_dir: &Path,
+    stdout: Option<FileDesc>,
+) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+    env.spawn_executable(ExecutableData {
+        name: OsStr::new(name),
+        args: &[OsStr::new("arg")],
+        env_vars: &[(OsStr::new("KEY"), OsStr::new("value"))],
+        current_dir: cur_dir,
+        stdin: None,
+        stdout,
+        stderr: None,
+        inherited_fds: Vec::new(),
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    })
+}
+
+#[tokio::test]
+async fn plan_should_record_opened_paths_and_spawned_executables() {
+    let tempdir = mktmp!();
+    let out = tempdir.path().join("out");
+
+    let mut mock = MockExecEnv::new();
+    mock.register("foo", MockExecOutput::status(ExitStatus::Code(5)));
+
+    let plan = ExecutionPlan::new();
+    let exec_env = ExecutionPlanEnv::new(mock.clone(), plan.clone());
+    let mut opener = ExecutionPlanEnv::new(FileDescOpenerEnv::new(), plan.clone());
+
+    let opts: OpenOptions = Permissions::Write.into();
+    let stdout = opener
+        .open_path_with_permissions(&out, Permissions::Write, &opts)
+        .expect("open failed");
+
+    let foo = spawn(&exec_env.sub_env(), "foo", tempdir.path(), Some(stdout));
+    assert_eq!(foo.expect("spawn failed").await, ExitStatus::Code(5));
+    assert_eq!(mock.invocations().len(), 1);
+
+    let cur_dir = tempdir.path().to_string_lossy().into_owned();
+    let command = PlannedCommand {
+        name: "foo".to_owned(),
+        args: vec!["arg".to_owned()],
+        env_vars: vec![("KEY".to_owned(), "value".to_owned())],
+        current_dir: cur_dir,
+        stdio: vec![STDOUT_FILENO],
+        inherited_fds: vec![],
+    };
+
+    assert_eq!(plan.commands(), vec!(command.clone()));
+    assert_eq!(
+        plan.steps(),
+        vec!(
+            PlannedStep::Open(PlannedOpen {
+                path: out.to_string_lossy().into_owned(),
+                permissions: Some(Permissions::Write),
+            }),
+            PlannedStep::Spawn(command),
+        )
+    );
+
+    let json = serde_json::to_string(&plan.steps()).expect("serialize failed");
+    let steps: Vec<PlannedStep> = serde_json::from_str(&json).expect("deserialize failed");
+    assert_eq!(steps, plan.take_steps());
+    assert_eq!(plan.steps(), vec!());
+}
+
+#[tokio::test]
+async fn dry_run_should_record_but_not_spawn_executables() {
+    let tempdir = mktmp!();
+    let mock = MockExecEnv::new();
+
+    let plan = ExecutionPlan::new();
+    let env = ExecutionPlanEnv::dry_run(mock.clone(), plan.clone());
+    assert!(env.is_dry_run());
+
+    let missing = spawn(&env, "missing", tempdir.path(), None).expect("spawn failed");
+    assert_eq!(missing.await, EXIT_SUCCESS);
+    assert_eq!(mock.invocations(), vec!());
+
+    let commands = plan.commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].name, "missing");
+    assert_eq!(commands[0].stdio, Vec::<Fd>::new());
+}
@@ -0,0 +1,62 @@
+#![deny(rust_2018_idioms)]
+
+use std::process;
+
+mod support;
+pub use self::support::*;
+
+#[test]
+fn code_and_signal_accessors() {
+    assert_eq!(ExitStatus::Code(3).code(), Some(3));
+    assert_eq!(ExitStatus::Code(3).signal(), None);
+    assert!(!ExitStatus::Code(3).core_dumped());
+
+    assert_eq!(ExitStatus::Signal(9).code(), None);
+    assert_eq!(ExitStatus::Signal(9).signal(), Some(9));
+    assert!(!ExitStatus::Signal(9).core_dumped());
+
+    assert_eq!(ExitStatus::CoreDumped(11).signal(), Some(11));
+    assert!(ExitStatus::CoreDumped(11).core_dumped());
+    assert!(!ExitStatus::CoreDumped(11).success());
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_names() {
+    assert_eq!(ExitStatus::Code(2).signal_name(), None);
+    assert_eq!(
+        ExitStatus::Signal(2).signal_name(),
+        Some("SIGINT".to_owned())
+    );
+    assert_eq!(
+        ExitStatus::CoreDumped(11).signal_name(),
+        Some("SIGSEGV".to_owned())
+    );
+    assert_eq!(ExitStatus::Signal(1000).signal_name(), None);
+
+    assert_eq!(ExitStatus::Code(2).to_string(), "exit code: 2");
+    assert_eq!(ExitStatus::Signal(2).to_string(), "signal: SIGINT");
+    assert_eq!(
+        ExitStatus::CoreDumped(11).to_string(),
+        "signal: SIGSEGV (core dumped)"
+    );
+    assert_eq!(ExitStatus::Signal(1000).to_string(), "signal: 1000");
+}
+
+#[cfg(unix)]
+#[test]
+fn std_conversions_round_trip() {
+    let statuses = [
+        EXIT_SUCCESS,
+        ExitStatus::Code(42),
+        ExitStatus::Signal(9),
+        ExitStatus::CoreDumped(6),
+    ];
+
+    for &status in &statuses {
+        let std_status = process::ExitStatus::from(status);
+        assert_eq!(std_status.success(), status.success());
+        assert_eq!(std_status.code(), status.code());
+        assert_eq!(ExitStatus::from(std_status), status);
+    }
+}
@@ -1,6 +1,11 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{AsyncIoEnvironment, FileDescOpener, TokioFileDescManagerEnv};
+use conch_runtime::env::{
+    AsyncIoEnvironment, FileDescEnvironment, FileDescManagerEnvironment, FileDescOpener,
+    TokioFileDescManagerEnv,
+};
+use conch_runtime::io::Permissions;
+use conch_runtime::Fd;
 use futures_util::future::try_join3;
 use std::borrow::Cow;
 
@@ -27,3 +32,38 @@ async fn fd_manager() {
     assert_eq!(read_msg, msg.as_bytes());
     assert_eq!(read_msg_best_effort, msg.as_bytes());
 }
+
+#[test]
+fn alloc_fd_should_use_lowest_unused_fd_above_9() {
+    let mut env = TokioFileDescManagerEnv::new();
+    let fdes = env.open_pipe().expect("failed to create pipe").reader;
+
+    env.set_file_desc(3, fdes.clone(), Permissions::Read);
+    env.set_file_desc(11, fdes.clone(), Permissions::Read);
+    assert_eq!(env.unused_fd().unwrap(), 10);
+
+    assert_eq!(env.alloc_fd(fdes.clone(), Permissions::Write).unwrap(), 10);
+    assert_eq!(env.file_desc(10), Some((&fdes, Permissions::Write)));
+
+    assert_eq!(env.alloc_fd(fdes.clone(), Permissions::Read).unwrap(), 12);
+    assert_eq!(env.unused_fd().unwrap(), 13);
+
+    env.close_file_desc(10);
+    assert_eq!(env.unused_fd().unwrap(), 10);
+}
+
+#[test]
+fn alloc_fd_should_error_if_all_fds_above_9_are_in_use() {
+    let mut env = TokioFileDescManagerEnv::new();
+    let fdes = env.open_pipe().expect("failed to create pipe").reader;
+
+    for fd in 10..=Fd::MAX {
+        env.set_file_desc(fd, fdes.clone(), Permissions::Read);
+    }
+
+    assert!(env.unused_fd().is_err());
+    assert!(env.alloc_fd(fdes.clone(), Permissions::Write).is_err());
+
+    env.close_file_desc(Fd::MAX);
+    assert_eq!(env.alloc_fd(fdes, Permissions::Write).unwrap(), Fd::MAX);
+}
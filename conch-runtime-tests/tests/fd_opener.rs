@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms)]
 use conch_runtime;
 
-use conch_runtime::env::{FileDescOpener, Pipe};
+use conch_runtime::env::{FileDescOpener, Pipe, TempFileEnvironment};
 use std::fs::OpenOptions;
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -59,3 +59,36 @@ async fn smoke_pipe() {
     guard.join().unwrap();
     assert_eq!(msg, read);
 }
+
+#[tokio::test]
+async fn smoke_temp_file() {
+    let msg = "temp file contents";
+    let mut opener = FileDescOpenerEnv::new();
+
+    let mut file = opener
+        .open_temp_file(msg.as_bytes())
+        .expect("failed to open temp file");
+
+    let mut read = String::new();
+    file.read_to_string(&mut read).unwrap();
+    assert_eq!(msg, read);
+}
+
+#[tokio::test]
+async fn named_temp_file_is_removed_on_drop() {
+    let msg = "named temp file contents";
+    let mut opener = FileDescOpenerEnv::new();
+
+    let mut file = opener
+        .open_named_temp_file(msg.as_bytes())
+        .expect("failed to open temp file");
+
+    let path = file.path.to_path_buf();
+    let mut read = String::new();
+    file.handle.read_to_string(&mut read).unwrap();
+    assert_eq!(msg, read);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), msg);
+
+    drop(file);
+    assert!(!path.exists());
+}
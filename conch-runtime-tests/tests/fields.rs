@@ -1,7 +1,10 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{UnsetVariableEnvironment, VarEnv, VariableEnvironment};
+use conch_runtime::env::{StrKey, UnsetVariableEnvironment, VarEnv, VariableEnvironment};
+use conch_runtime::eval::ifs_join_separator;
 use conch_runtime::eval::Fields::*;
+use std::borrow::Borrow;
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_fields_is_null() {
@@ -63,7 +66,30 @@ async fn test_fields_join_with_ifs() {
     assert_eq!(Single("foo".to_owned()).join_with_ifs(&env), "foo");
     assert_eq!(At(strs.clone()).join_with_ifs(&env), "foo  bar");
     assert_eq!(Star(strs.clone()).join_with_ifs(&env), "foo  bar");
-    assert_eq!(Split(strs).join_with_ifs(&env), "foo  bar");
+    assert_eq!(Split(strs.clone()).join_with_ifs(&env), "foo  bar");
+
+    // Multibyte IFS
+    env.set_var(ifs, "\u{2603}!".to_owned());
+    assert_eq!(Star(strs).join_with_ifs(&env), "foo\u{2603}\u{2603}bar");
+}
+
+#[tokio::test]
+async fn test_fields_join_with() {
+    let strs = vec!["foo".to_owned(), "".to_owned(), "bar".to_owned()];
+
+    assert_eq!(Zero::<String>.join_with(", "), "");
+    assert_eq!(Single("foo".to_owned()).join_with(", "), "foo");
+    assert_eq!(At(strs.clone()).join_with(", "), "foo, , bar");
+    assert_eq!(Star(strs.clone()).join_with(""), "foobar");
+    assert_eq!(Split(strs).join_with("!"), "foo!!bar");
+}
+
+#[test]
+fn test_ifs_join_separator() {
+    assert_eq!(ifs_join_separator(None), " ");
+    assert_eq!(ifs_join_separator(Some("")), "");
+    assert_eq!(ifs_join_separator(Some(" \t\n")), " ");
+    assert_eq!(ifs_join_separator(Some("\u{e9}x")), "\u{e9}");
 }
 
 #[tokio::test]
@@ -157,3 +183,93 @@ async fn test_no_splitting_if_ifs_blank() {
     let fields = Single(" \t\nfoo \t\nbar \t\n".to_owned());
     assert_eq!(fields.clone().split(&env), fields);
 }
+
+#[tokio::test]
+async fn test_splitting_and_joining_with_str_var_names() {
+    let mut env = VarEnv::<&'static str, String>::new();
+    env.set_var("IFS", "0".to_owned());
+
+    assert_eq!(
+        Single("foo0bar".to_owned()).split(&env),
+        Split(vec!("foo".to_owned(), "bar".to_owned()))
+    );
+    assert_eq!(
+        Star(vec!("foo".to_owned(), "bar".to_owned())).join_with_ifs(&env),
+        "foo0bar"
+    );
+
+    let mut env = VarEnv::<Arc<str>, String>::new();
+    env.set_var("IFS".into(), "0".to_owned());
+
+    assert_eq!(
+        Single("foo0bar".to_owned()).split(&env),
+        Split(vec!("foo".to_owned(), "bar".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn test_splitting_and_joining_with_custom_var_names() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Name(String);
+
+    impl Borrow<String> for Name {
+        fn borrow(&self) -> &String {
+            &self.0
+        }
+    }
+
+    impl StrKey for Name {
+        fn as_key_str(&self) -> &str {
+            &self.0
+        }
+
+        fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+        where
+            E: ?Sized + VariableEnvironment<VarName = Self>,
+        {
+            env.var(&name.to_owned())
+        }
+    }
+
+    let mut env = VarEnv::<Name, String>::new();
+    env.set_var(Name("IFS".to_owned()), "0".to_owned());
+
+    assert_eq!(
+        Single("foo0bar".to_owned()).split(&env),
+        Split(vec!("foo".to_owned(), "bar".to_owned()))
+    );
+    assert_eq!(
+        Star(vec!("foo".to_owned(), "bar".to_owned())).join_with_ifs(&env),
+        "foo0bar"
+    );
+}
+
+#[tokio::test]
+async fn test_splitting_reuses_unsplit_fields() {
+    let mut env = VarEnv::<String, String>::new();
+    env.set_var("IFS".to_owned(), " ".to_owned());
+
+    let foo = Arc::new("foo".to_owned());
+    let bar_baz = Arc::new("bar baz".to_owned());
+
+    match Split(vec![foo.clone(), bar_baz.clone()]).split(&env) {
+        Split(fields) => {
+            assert_eq!(fields.len(), 3);
+            assert!(Arc::ptr_eq(&fields[0], &foo));
+            assert_eq!(*fields[1], "bar");
+            assert_eq!(*fields[2], "baz");
+        }
+        fields => panic!("unexpected fields: {:#?}", fields),
+    }
+}
+
+#[tokio::test]
+async fn test_splitting_multibyte_chars() {
+    let mut env = VarEnv::new();
+    env.set_var("IFS".to_owned(), "é ".to_owned());
+
+    assert_eq!(
+        Single(" ünïcödé wörds ".to_owned()).split(&env),
+        Split(vec!("ünïcöd".to_owned(), "wörds".to_owned()))
+    );
+}
@@ -71,3 +71,28 @@ async fn test_file_desc_seeking() {
 
     assert_eq!(read, "***???!!!");
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_file_desc_send_and_recv_over_unix_socket() {
+    use conch_runtime::io::{recv_fd, send_fd};
+    use std::os::unix::net::UnixStream;
+
+    let msg = "sent over a socket";
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    let Pipe { mut reader, writer } = Pipe::new().unwrap();
+
+    send_fd(&sender, &writer).unwrap();
+    drop(writer);
+
+    let mut received = recv_fd(&receiver).unwrap();
+    received.write_all(msg.as_bytes()).unwrap();
+    drop(received);
+
+    let mut read = String::new();
+    reader.read_to_string(&mut read).unwrap();
+    assert_eq!(msg, read);
+
+    drop(sender);
+    assert!(recv_fd(&receiver).is_err());
+}
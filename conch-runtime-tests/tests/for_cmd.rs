@@ -1,5 +1,6 @@
 #![deny(rust_2018_idioms)]
 
+use conch_runtime::script::run_script;
 use std::collections::VecDeque;
 use std::sync::Arc;
 
@@ -172,3 +173,153 @@ async fn should_propagate_fatal_errors_in_body() {
     let for_cmd = for_with_args(name, vars_raw, &fatal, env);
     assert_eq!(Some(MockErr::Fatal(true)), for_cmd.await.err());
 }
+
+#[tokio::test]
+async fn should_handle_break_and_continue_requests() {
+    let env = &mut new_env();
+
+    let name = Arc::new("name".to_owned());
+    let args = || vec![Arc::new("foo".to_owned()), Arc::new("bar".to_owned())];
+    let should_not_run = mock_panic("must not run");
+    let flow = |flow| MockCmd::Error(MockErr::ControlFlow(flow));
+
+    let cmds = [flow(ControlFlow::Break(1)), should_not_run.clone()];
+    let for_cmd = for_with_args(name.clone(), args(), sequence_slice(&cmds), env);
+    assert_eq!(EXIT_SUCCESS, for_cmd.await.unwrap().await);
+    assert_eq!(Some(&Arc::new("foo".to_owned())), env.var(&name));
+
+    let cmds = [flow(ControlFlow::Continue(1)), should_not_run.clone()];
+    let for_cmd = for_with_args(name.clone(), args(), sequence_slice(&cmds), env);
+    assert_eq!(EXIT_SUCCESS, for_cmd.await.unwrap().await);
+    assert_eq!(Some(&Arc::new("bar".to_owned())), env.var(&name));
+
+    let cmds = [flow(ControlFlow::Continue(2)), should_not_run.clone()];
+    let for_cmd = for_with_args(name.clone(), args(), sequence_slice(&cmds), env);
+    assert_eq!(
+        Some(MockErr::ControlFlow(ControlFlow::Continue(1))),
+        for_cmd.await.err()
+    );
+    assert_eq!(Some(&Arc::new("foo".to_owned())), env.var(&name));
+
+    assert_eq!(0, env.loop_depth());
+}
+
+#[derive(Clone)]
+struct MockConcurrentCmd {
+    barrier: Arc<tokio::sync::Barrier>,
+    seen: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl Spawn<DefaultEnvArc> for MockConcurrentCmd {
+    type Error = MockErr;
+
+    async fn spawn(
+        &self,
+        env: &mut DefaultEnvArc,
+    ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        let val = (**env.var(&VAR.to_owned()).unwrap()).clone();
+        self.seen.lock().unwrap().push(val.clone());
+
+        // Only completes if all iterations are running at the same time
+        self.barrier.wait().await;
+
+        let status = if val == "ok" {
+            EXIT_SUCCESS
+        } else {
+            ExitStatus::Code(val.len() as i32)
+        };
+        Ok(Box::pin(async move { status }))
+    }
+}
+
+#[tokio::test]
+async fn should_run_concurrent_iterations_in_sub_envs() {
+    let env = &mut new_env();
+
+    let name = Arc::new(VAR.to_owned());
+    let vars_raw = vec!["ok".to_owned(), "xx".to_owned(), "yyy".to_owned()];
+    let vars = &[mock_word_fields(Fields::Split(vars_raw.clone()))];
+    let cmd = MockConcurrentCmd {
+        barrier: Arc::new(tokio::sync::Barrier::new(vars_raw.len())),
+        seen: Default::default(),
+    };
+
+    let for_cmd = for_each_concurrent(name.clone(), vars, &cmd, vars_raw.len(), env);
+    assert_eq!(ExitStatus::Code(2), for_cmd.await.unwrap().await);
+    assert_eq!(vars_raw, *cmd.seen.lock().unwrap());
+    assert_eq!(None, env.var(&name));
+}
+
+#[tokio::test]
+async fn should_report_body_errors_and_propagate_word_errors_of_concurrent_loop() {
+    let env = &mut new_env();
+
+    let name = Arc::new("name".to_owned());
+    let vars = &[mock_word_fields(Fields::Split(vec![
+        "foo".to_owned(),
+        "bar".to_owned(),
+    ]))];
+
+    let fatal = mock_error(true);
+    let for_cmd = for_each_concurrent(name.clone(), vars, &fatal, 0, env);
+    assert_eq!(EXIT_ERROR, for_cmd.await.unwrap().await);
+
+    let status = mock_status(MOCK_EXIT);
+    let for_cmd = for_each_concurrent(name.clone(), vars, &status, 1, env);
+    assert_eq!(MOCK_EXIT, for_cmd.await.unwrap().await);
+
+    let should_not_run = mock_panic("must not run");
+    let for_cmd = for_each_concurrent(name.clone(), vars.iter().take(0), &should_not_run, 4, env);
+    assert_eq!(EXIT_SUCCESS, for_cmd.await.unwrap().await);
+
+    let for_cmd = for_each_concurrent(
+        name,
+        std::iter::once(mock_word_error(true)),
+        &should_not_run,
+        4,
+        env,
+    );
+    assert_eq!(Some(MockErr::Fatal(true)), for_cmd.await.err());
+}
+
+/// Runs `src` with `a b c` as the positional parameters, returning the
+/// resulting value of `$seen`.
+async fn run_and_get_seen(src: &str) -> Option<String> {
+    let name = |name: &str| Arc::new(name.to_owned());
+
+    let mut env = new_env();
+    env.set_args(Arc::new(VecDeque::from(vec![
+        name("a"),
+        name("b"),
+        name("c"),
+    ])));
+
+    let status = run_script(src, &mut env).await.unwrap();
+    assert_eq!(EXIT_SUCCESS, status);
+    env.var(&name("seen")).map(|val| (**val).clone())
+}
+
+#[tokio::test]
+async fn should_snapshot_args_when_words_are_expanded() {
+    // Shifting does not affect the fields the loop iterates over, only
+    // later expansions of the positional parameters
+    let seen = run_and_get_seen("for x in \"$@\"; do shift; seen=\"$seen[$x:$#:$*]\"; done").await;
+    assert_eq!(seen.as_deref(), Some("[a:2:b c][b:1:c][c:0:]"));
+
+    let seen = run_and_get_seen("for x; do shift; seen=\"$seen[$x:$#:$*]\"; done").await;
+    assert_eq!(seen.as_deref(), Some("[a:2:b c][b:1:c][c:0:]"));
+
+    let seen =
+        run_and_get_seen("for x in \"$@\" \"$1\"; do set -- z; seen=\"$seen$x$1\"; done").await;
+    assert_eq!(seen.as_deref(), Some("azbzczaz"));
+
+    // Nested loops snapshot the arguments whenever they start running
+    let seen = run_and_get_seen("for x; do for y; do seen=\"$seen$x$y \"; done; shift; done").await;
+    assert_eq!(seen.as_deref(), Some("aa ab ac bb bc cc "));
+
+    // All words are expanded before the body is first run
+    let seen =
+        run_and_get_seen("i=0; for x in \"$@\" $((i += 1)); do seen=\"$seen$x$i\"; done").await;
+    assert_eq!(seen.as_deref(), Some("a1b1c111"));
+}
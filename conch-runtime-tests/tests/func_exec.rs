@@ -1,6 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use conch_runtime::spawn::function;
+use futures_util::future::{pending, FutureExt};
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -107,6 +108,66 @@ async fn should_propagate_errors_and_restore_args() {
     assert_eq!(env.is_fn_running(), false);
 }
 
+#[tokio::test]
+async fn should_restore_args_if_cancelled() {
+    struct MockNeverFinishes;
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> Spawn<E> for MockNeverFinishes {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            pending().await
+        }
+    }
+
+    let mut env = new_test_env();
+
+    let fn_name = "fn_name".to_owned();
+    env.set_function(fn_name.clone(), Arc::new(MockNeverFinishes));
+
+    let args = VecDeque::from(vec!["foo".to_owned(), "bar".to_owned()]);
+    env.set_args(Arc::new(args.clone()));
+
+    let future = function(&fn_name, VecDeque::from(vec!["qux".to_owned()]), &mut env);
+    assert!(future.now_or_never().is_none());
+
+    assert_eq!(env.args(), Vec::from(args));
+}
+
+#[tokio::test]
+async fn should_resolve_return_requests_and_propagate_exit_requests() {
+    let mut env = new_test_env();
+
+    let exit = ExitStatus::Code(42);
+    let fn_name = "fn_name".to_owned();
+    let cmd = MockCmd::Error(MockErr::ControlFlow(ControlFlow::Return(exit)));
+    env.set_function(fn_name.clone(), mock_wrapper(cmd));
+
+    let result = function(&fn_name, VecDeque::new(), &mut env)
+        .await
+        .expect("failed to find function")
+        .expect("function failed")
+        .await;
+    assert_eq!(exit, result);
+    assert_eq!(env.is_fn_running(), false);
+
+    let flow = ControlFlow::Exit(exit);
+    env.set_function(
+        fn_name.clone(),
+        mock_wrapper(MockCmd::Error(MockErr::ControlFlow(flow))),
+    );
+
+    match function(&fn_name, VecDeque::new(), &mut env)
+        .await
+        .expect("failed to find function")
+    {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(e, MockErr::ControlFlow(flow)),
+    }
+    assert_eq!(env.is_fn_running(), false);
+}
+
 struct MockFnRecursive<F> {
     callback: F,
 }
@@ -194,3 +255,74 @@ async fn test_env_run_function_nested_calls_do_not_destroy_upper_args() {
     assert_eq!(depth.load(Ordering::SeqCst), 0);
     assert_eq!(env.is_fn_running(), false);
 }
+
+#[tokio::test]
+async fn should_abort_unbounded_recursion_once_max_depth_exceeded() {
+    let max_depth = 10;
+    let fn_name = "fn name".to_owned();
+    let mut env = new_test_env();
+    env.set_max_fn_frame_depth(Some(max_depth));
+
+    {
+        let fn_name = fn_name.clone();
+        env.set_function(
+            fn_name.clone(),
+            MockFnRecursive::new(move |env| {
+                let fn_name = fn_name.clone();
+                Box::pin(async move {
+                    function(&fn_name, VecDeque::new(), env)
+                        .await
+                        .expect("failed to get function")
+                })
+            }),
+        );
+    }
+
+    match function(&fn_name, VecDeque::new(), &mut env)
+        .await
+        .expect("failed to find function")
+    {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert_eq!(e, MockErr::StackOverflow(StackOverflowError { max_depth })),
+    }
+
+    assert_eq!(env.fn_frame_depth(), 0);
+    assert_eq!(env.is_fn_running(), false);
+}
+
+#[tokio::test]
+async fn should_restore_local_options_once_function_returns() {
+    let fn_local = "fn_local".to_owned();
+    let fn_global = "fn_global".to_owned();
+    let mut env = new_test_env();
+    env.set_option(ShellOption::ErrExit, true);
+
+    env.set_function(
+        fn_local.clone(),
+        MockFnRecursive::new(|env| {
+            assert!(env.make_options_local());
+            env.set_option(ShellOption::ErrExit, false);
+            Box::pin(async { Ok(Box::pin(async { EXIT_SUCCESS }) as BoxFuture<'static, _>) })
+        }),
+    );
+
+    env.set_function(
+        fn_global.clone(),
+        MockFnRecursive::new(|env| {
+            env.set_option(ShellOption::NoGlob, true);
+            Box::pin(async { Ok(Box::pin(async { EXIT_SUCCESS }) as BoxFuture<'static, _>) })
+        }),
+    );
+
+    for name in &[fn_local, fn_global] {
+        function(name, VecDeque::new(), &mut env)
+            .await
+            .expect("failed to find function")
+            .expect("function failed")
+            .await;
+    }
+
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert!(env.is_option_enabled(ShellOption::NoGlob));
+    assert!(!env.make_options_local());
+}
@@ -0,0 +1,89 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast::builder::AtomicDefaultBuilder;
+use conch_parser::ast::{self, AtomicTopLevelCommand, PipeableCommand};
+use conch_parser::lexer::Lexer;
+use conch_parser::parse::Parser;
+
+mod support;
+pub use self::support::*;
+
+type Body = ast::ShellCompoundCommand<
+    String,
+    ast::AtomicTopLevelWord<String>,
+    AtomicTopLevelCommand<String>,
+>;
+
+fn parse_body(src: &str) -> Body {
+    let mut parser = Parser::with_builder(Lexer::new(src.chars()), AtomicDefaultBuilder::new());
+    let body = parser
+        .compound_command()
+        .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", src, e));
+    assert_eq!(parser.complete_command().unwrap(), None, "{}", src);
+    body
+}
+
+fn parse_fn_def(src: &str) -> Body {
+    let mut parser = Parser::with_builder(Lexer::new(src.chars()), AtomicDefaultBuilder::new());
+    let cmd: AtomicTopLevelCommand<String> = parser.complete_command().unwrap().unwrap();
+    match cmd.0 {
+        ast::Command::List(ast::AndOrList {
+            first: ast::ListableCommand::Single(PipeableCommand::FunctionDef(_, body)),
+            ..
+        }) => (*body).clone(),
+        cmd => panic!("not a function definition: {:?}", cmd),
+    }
+}
+
+fn assert_round_trip(src: &str) {
+    let body = parse_fn_def(src);
+    let source = body.function_source();
+    assert_eq!(parse_body(&source), body, "{}\n=>\n{}", src, source);
+}
+
+#[test]
+fn should_round_trip_commands() {
+    assert_round_trip("f() { echo foo bar; }");
+    assert_round_trip("f() ( cd /; pwd )");
+    assert_round_trip("f() { a && b || ! c | d; sleep 5 & wait; }");
+    assert_round_trip("f() { FOO=bar BAZ= cmd arg >out 2>>log <in 3<>rw 4>|clob <&0 >&2; }");
+    assert_round_trip("f() { nested() { echo inner; }; nested; } >/dev/null");
+    assert_round_trip(
+        "f() { cat <<EOF; cat <<-'QUOTED' | tr a b\nhello $1 \\$x\nEOF\n\tliteral $x\n\tQUOTED\n}",
+    );
+    assert_round_trip("f() { cat <<EOF\nEOF1\nEOF\n}");
+}
+
+#[test]
+fn should_round_trip_compound_commands() {
+    assert_round_trip("f() { if a; b; then c; elif d; then e; else g; fi; }");
+    assert_round_trip("f() { if a; then b; fi >out; }");
+    assert_round_trip("f() { while a; do b; done; until c; do d; done; }");
+    assert_round_trip("f() { for x; do echo $x; done; for y in a \"$@\" c; do :; done; }");
+    assert_round_trip("f() { for x in; do echo; done; }");
+    assert_round_trip("f() { case $1 in a|b*) echo ab;; [c]) ;; *) echo other; esac; }");
+}
+
+#[test]
+fn should_round_trip_words() {
+    assert_round_trip("f() { echo 'single quoted' \"double $x ${y} \\$ \\\" \\\\ \\` `cmd`\"; }");
+    assert_round_trip("f() { echo a\\ b \\# \\; \\& \\| \\> \\< \\( \\) \\{ \\} \\' \\\"; }");
+    assert_round_trip("f() { echo $0 $1 ${10} $@ $* $# $? $- $$ $! ${x}y $1a; }");
+    assert_round_trip("f() { echo ~ ~/foo a:b *.rs fo? [ab] x=y; }");
+    assert_round_trip(
+        "f() { echo ${#x} ${x:-a} ${x-} ${x:=$y} ${x=} ${x:?err} ${x?} ${x:+alt} ${x+}; }",
+    );
+    assert_round_trip("f() { echo ${x%.rs} ${x%%*} ${x#a} ${x##*/} \"${x:-a}b}\" ${x:-\\}}; }");
+    assert_round_trip("f() { echo $(echo sub; cat <<EOF\nbody\nEOF\n) \"$(echo quoted)\" $(); }");
+}
+
+#[test]
+fn should_round_trip_arithmetic() {
+    assert_round_trip("f() { echo $(( )) $((1 + 2 * 3)) $(((1 + 2) * 3)) $((2 ** 3 ** 2)); }");
+    assert_round_trip(
+        "f() { echo $((x++ + ++y - z-- - --w)) $((-x + +y)) $((- -x)) $((!x + ~y)); }",
+    );
+    assert_round_trip("f() { echo $((a / b % c << 1 >> 2)) $((a < b <= c > d >= e == f != g)); }");
+    assert_round_trip("f() { echo $((a & b ^ c | d && e || f)) $((a ? b : c ? d : e)); }");
+    assert_round_trip("f() { echo $((x = 5)) $((x += y * 2)) $((a = 1, b = 2)); }");
+}
@@ -0,0 +1,246 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::error::ExpansionError;
+use conch_runtime::eval::{expand_glob, glob_field, glob_word, GlobConfig, NoMatchBehavior};
+use std::fs;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn should_expand_matching_paths_in_sorted_order() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+    let env = VirtualWorkingDirEnv::new(path).unwrap();
+
+    fs::create_dir(path.join("sub2")).unwrap();
+    fs::create_dir(path.join("sub")).unwrap();
+    fs::create_dir(path.join("empty")).unwrap();
+    for file in &[
+        "b.rs",
+        "a.rs",
+        ".hidden.rs",
+        "sub/c.rs",
+        "sub2/d.rs",
+        "sub2/e.txt",
+    ] {
+        fs::write(path.join(file), "").unwrap();
+    }
+
+    for &max_concurrent_reads in &[0, 1, 8] {
+        let cfg = GlobConfig {
+            max_concurrent_reads,
+            ..GlobConfig::default()
+        };
+
+        assert_eq!(expand_glob("*.rs", &env, cfg).await, vec!("a.rs", "b.rs"));
+        assert_eq!(expand_glob(".*.rs", &env, cfg).await, vec!(".hidden.rs"));
+        assert_eq!(
+            expand_glob("*/*.rs", &env, cfg).await,
+            vec!("sub/c.rs", "sub2/d.rs")
+        );
+        assert_eq!(
+            expand_glob("sub*/?.*", &env, cfg).await,
+            vec!("sub/c.rs", "sub2/d.rs", "sub2/e.txt")
+        );
+        assert_eq!(
+            expand_glob("*/", &env, cfg).await,
+            vec!("empty/", "sub/", "sub2/")
+        );
+        assert_eq!(expand_glob("sub/c.rs", &env, cfg).await, vec!("sub/c.rs"));
+        assert_eq!(
+            expand_glob("missing/*", &env, cfg).await,
+            Vec::<String>::new()
+        );
+        assert_eq!(expand_glob("*.md", &env, cfg).await, Vec::<String>::new());
+    }
+}
+
+#[tokio::test]
+async fn should_expand_absolute_patterns_as_absolute_paths() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+    let env = VirtualWorkingDirEnv::new(path).unwrap();
+    fs::write(path.join("foo"), "").unwrap();
+
+    let dir = path.to_str().expect("non-utf8 tempdir");
+    let expected = path.join("foo").to_str().unwrap().to_owned();
+    assert_eq!(
+        expand_glob(&format!("{}/f*", dir), &env, GlobConfig::default()).await,
+        vec!(expected)
+    );
+}
+
+#[tokio::test]
+async fn glob_field_should_handle_patterns_which_match_nothing() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+    let env = VirtualWorkingDirEnv::new(path).unwrap();
+    fs::write(path.join("a*b"), "").unwrap();
+    fs::write(path.join("foo"), "").unwrap();
+
+    let glob = |field, pattern, no_match| {
+        let cfg = GlobConfig {
+            no_match,
+            ..GlobConfig::default()
+        };
+        glob_field(field, pattern, &env, cfg)
+    };
+
+    for &no_match in &[
+        NoMatchBehavior::Literal,
+        NoMatchBehavior::Null,
+        NoMatchBehavior::Fail,
+    ] {
+        // Matches are unaffected by the behavior
+        assert_eq!(glob("f*", "f*", no_match).await, Ok(vec!("foo".to_owned())));
+        assert_eq!(
+            glob("a*b*", "a[*]b*", no_match).await,
+            Ok(vec!("a*b".to_owned()))
+        );
+
+        // Fields without any unescaped special characters are never expanded
+        assert_eq!(
+            glob("bar", "bar", no_match).await,
+            Ok(vec!("bar".to_owned()))
+        );
+        assert_eq!(glob("*", "[*]", no_match).await, Ok(vec!("*".to_owned())));
+    }
+
+    assert_eq!(
+        glob("*.md", "*.md", NoMatchBehavior::Literal).await,
+        Ok(vec!("*.md".to_owned()))
+    );
+    assert_eq!(
+        glob("*.md", "*.md", NoMatchBehavior::Null).await,
+        Ok(vec!())
+    );
+    assert_eq!(
+        glob("*.md", "*.md", NoMatchBehavior::Fail).await,
+        Err(ExpansionError::NoGlobMatch("*.md".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn glob_field_should_not_expand_anything_with_noglob() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+    let env = VirtualWorkingDirEnv::new(path).unwrap();
+    fs::write(path.join("foo"), "").unwrap();
+
+    let mut opts = ShellOptionsEnv::new();
+    opts.set_option(ShellOption::NoGlob, true);
+    opts.set_option(ShellOption::FailGlob, true);
+
+    let cfg = GlobConfig::from_options(&opts);
+    assert!(cfg.noglob);
+    assert_eq!(
+        glob_field("f*", "f*", &env, cfg).await,
+        Ok(vec!("f*".to_owned()))
+    );
+    assert_eq!(
+        glob_field("*.md", "*.md", &env, cfg).await,
+        Ok(vec!("*.md".to_owned()))
+    );
+}
+
+#[test]
+fn no_match_behavior_should_respect_shell_options() {
+    let mut opts = ShellOptionsEnv::new();
+    assert_eq!(
+        NoMatchBehavior::from_options(&opts),
+        NoMatchBehavior::Literal
+    );
+
+    opts.set_option(ShellOption::NullGlob, true);
+    assert_eq!(NoMatchBehavior::from_options(&opts), NoMatchBehavior::Null);
+
+    opts.set_option(ShellOption::FailGlob, true);
+    assert_eq!(NoMatchBehavior::from_options(&opts), NoMatchBehavior::Fail);
+}
+
+#[tokio::test]
+async fn should_expand_extended_and_recursive_patterns_if_configured() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+    let env = VirtualWorkingDirEnv::new(path).unwrap();
+
+    fs::create_dir_all(path.join("a/b/c")).unwrap();
+    fs::create_dir_all(path.join(".hidden")).unwrap();
+    for file in &["x.rs", "x.md", "a/y.rs", "a/b/c/z.rs", ".hidden/w.rs"] {
+        fs::write(path.join(file), "").unwrap();
+    }
+
+    let default = GlobConfig::default();
+    assert_eq!(expand_glob("**/*.rs", &env, default).await, vec!("a/y.rs"));
+    assert_eq!(
+        expand_glob("!(*.md)", &env, default).await,
+        Vec::<String>::new()
+    );
+
+    let mut opts = ShellOptionsEnv::new();
+    opts.set_option(ShellOption::ExtGlob, true);
+    opts.set_option(ShellOption::GlobStar, true);
+    let cfg = GlobConfig::from_options(&opts);
+    assert!(cfg.extglob && cfg.globstar);
+
+    assert_eq!(
+        expand_glob("**/*.rs", &env, cfg).await,
+        vec!("x.rs", "a/y.rs", "a/b/c/z.rs")
+    );
+    assert_eq!(
+        expand_glob("**", &env, cfg).await,
+        vec!("a", "a/b", "a/b/c", "a/b/c/z.rs", "a/y.rs", "x.md", "x.rs")
+    );
+    assert_eq!(
+        expand_glob("a/**/", &env, cfg).await,
+        vec!("a/b/", "a/b/c/")
+    );
+    assert_eq!(expand_glob("!(*.md)", &env, cfg).await, vec!("a", "x.rs"));
+    assert_eq!(expand_glob("*.@(md|txt)", &env, cfg).await, vec!("x.md"));
+    assert_eq!(
+        glob_field("@(x).rs", "@(x).rs", &env, cfg).await,
+        Ok(vec!("x.rs".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn quoted_extended_groups_should_only_match_literally() {
+    let tempdir = mktmp!();
+    let path = tempdir.path();
+
+    fs::create_dir(path.join("@(a|b)")).unwrap();
+    for file in &["a", "b", "@(a|b)/c", "!(x)"] {
+        fs::write(path.join(file), "").unwrap();
+    }
+
+    let mut cfg = DefaultEnvConfig::<String>::new().expect("failed to create env cfg");
+    cfg.working_dir_env = VirtualWorkingDirEnv::new(path).unwrap();
+    let mut env = DefaultEnv::with_config(cfg);
+    env.set_option(ShellOption::ExtGlob, true);
+
+    let quoted = |word: &str| -> conch_parser::ast::Word<String, MockWord> {
+        conch_parser::ast::Word::SingleQuoted(word.to_owned())
+    };
+
+    assert_eq!(
+        glob_word(&quoted("@(a|b)"), &mut env).await,
+        Ok(vec!("@(a|b)".to_owned()))
+    );
+    assert_eq!(
+        glob_word(&quoted("!(x)"), &mut env).await,
+        Ok(vec!("!(x)".to_owned()))
+    );
+
+    // Quoted groups in a pattern which is otherwise expanded
+    let pat = GlobConfig::from_options(&env);
+    assert_eq!(
+        glob_field("@(a|b)/*", "@[(]a[|]b[)]/*", &env, pat).await,
+        Ok(vec!("@(a|b)/c".to_owned()))
+    );
+    assert_eq!(
+        glob_field("@(a|b)*", "@[(]a[|]b[)]*", &env, pat).await,
+        Ok(vec!("@(a|b)".to_owned()))
+    );
+}
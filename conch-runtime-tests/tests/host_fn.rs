@@ -0,0 +1,61 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::script::RcFile;
+use conch_runtime::shell::Shell;
+use conch_runtime::spawn::{function, host_fn};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+mod support;
+pub use self::support::*;
+
+fn rc(s: &str) -> Arc<String> {
+    Arc::new(s.to_owned())
+}
+
+#[tokio::test]
+async fn should_invoke_host_fn_with_args_and_env() {
+    let mut env = new_env();
+    let fn_name = rc("host");
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let calls_clone = calls.clone();
+    let func = host_fn(move |env: &mut DefaultEnvArc, args: Vec<String>| {
+        env.set_var(rc("host_var"), rc(&args.join(",")));
+
+        let status = ExitStatus::Code(args.len() as i32);
+        calls_clone.lock().unwrap().push(args);
+        Box::pin(async move { status }) as BoxFuture<'static, ExitStatus>
+    });
+    env.set_function(fn_name.clone(), Arc::new(func));
+
+    let args = vec![rc("foo"), rc("bar")]
+        .into_iter()
+        .collect::<VecDeque<_>>();
+    let future = function(&fn_name, args, &mut env)
+        .await
+        .expect("function not defined")
+        .expect("spawn failed");
+
+    assert_eq!(future.await, ExitStatus::Code(2));
+    assert_eq!(*calls.lock().unwrap(), vec![vec!["foo", "bar"]]);
+    assert_eq!(**env.var(&rc("host_var")).unwrap(), "foo,bar");
+
+    // Arguments are restored after the function completes
+    assert_eq!(env.args_len(), 0);
+}
+
+#[test]
+fn should_invoke_host_fn_from_shell() {
+    let mut shell = Shell::with_rc_file(&RcFile::Disabled).unwrap();
+    shell.define_host_fn("count_args", |_, args| {
+        let status = ExitStatus::Code(args.len() as i32);
+        Box::pin(async move { status })
+    });
+
+    assert_eq!(
+        shell.run_str("count_args a b c").unwrap(),
+        ExitStatus::Code(3)
+    );
+    assert_eq!(shell.run_str("count_args").unwrap(), EXIT_SUCCESS);
+}
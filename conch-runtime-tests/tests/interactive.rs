@@ -0,0 +1,321 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::HistoryEnvironment;
+use conch_runtime::interactive::{LineIter, LineSource, Repl, ReplStep};
+use conch_runtime::script::RcFile;
+use conch_runtime::EXIT_CMD_NOT_FOUND;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+/// Records the prompts displayed for each line read, and any history entries.
+#[derive(Debug)]
+struct RecordingSource {
+    lines: VecDeque<&'static str>,
+    prompts: Vec<String>,
+    history: Vec<String>,
+}
+
+impl RecordingSource {
+    fn new(lines: Vec<&'static str>) -> Self {
+        Self {
+            lines: lines.into(),
+            prompts: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LineSource for RecordingSource {
+    async fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        self.prompts.push(prompt.to_owned());
+        Ok(self.lines.pop_front().map(String::from))
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        self.history.push(entry.to_owned());
+    }
+}
+
+fn repl<I>(lines: I) -> Repl<LineIter<I::IntoIter>, DefaultEnvArc>
+where
+    I: IntoIterator<Item = &'static str>,
+{
+    Repl::new(LineIter::new(lines.into_iter()), new_env())
+}
+
+#[tokio::test]
+async fn should_run_commands_until_eof() {
+    let mut repl = repl(vec!["true", "", "false"]);
+
+    assert_eq!(ReplStep::Ran(EXIT_SUCCESS), repl.step().await.unwrap());
+    assert_eq!(ReplStep::Ran(EXIT_SUCCESS), repl.step().await.unwrap());
+    assert_eq!(ReplStep::Ran(EXIT_ERROR), repl.step().await.unwrap());
+    assert_eq!(ReplStep::Eof, repl.step().await.unwrap());
+
+    assert_eq!(EXIT_ERROR, repl.run().await.unwrap());
+}
+
+#[tokio::test]
+async fn should_stop_when_exit_requested() {
+    let mut repl = repl(vec!["true; exit 42; true", "true"]);
+    assert_eq!(ExitStatus::Code(42), repl.run().await.unwrap());
+    assert_eq!(ExitStatus::Code(42), repl.env().last_status());
+
+    let (mut lines, _) = repl.into_inner();
+    assert_eq!(Some("true".to_owned()), lines.read_line("").await.unwrap());
+}
+
+#[tokio::test]
+async fn should_run_rc_file_before_reading_input() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("rc");
+    fs::write(&path, "greet() { exit 4; }\n").unwrap();
+
+    let mut env = new_env();
+    env.set_var(
+        Arc::new("CONCHRC".to_owned()),
+        Arc::new(path.to_string_lossy().into_owned()),
+    );
+
+    let mut repl = Repl::new(LineIter::new(vec!["greet"].into_iter()), env.clone());
+    assert_eq!(ExitStatus::Code(4), repl.run().await.unwrap());
+
+    let mut repl = Repl::new(LineIter::new(vec!["greet"].into_iter()), env);
+    repl.set_rc_file(RcFile::Disabled);
+    assert_eq!(EXIT_CMD_NOT_FOUND, repl.run().await.unwrap());
+}
+
+#[tokio::test]
+async fn should_prompt_for_continuation_lines() {
+    let mut source = RecordingSource::new(vec!["true 'foo", "bar'", "exit 5"]);
+
+    let mut env = new_env();
+    env.set_var(Arc::new("PS1".to_owned()), Arc::new("ps1 ".to_owned()));
+    env.set_var(Arc::new("PS2".to_owned()), Arc::new("ps2 ".to_owned()));
+
+    let mut repl = Repl::new(&mut source, env);
+    assert_eq!(ExitStatus::Code(5), repl.run().await.unwrap());
+    drop(repl);
+
+    assert_eq!(source.prompts, vec!["ps1 ", "ps2 ", "ps1 "]);
+}
+
+#[tokio::test]
+async fn should_report_parse_errors_and_continue() {
+    let mut repl = repl(vec!["true )", "true"]);
+    assert_eq!(ReplStep::Ran(EXIT_ERROR), repl.step().await.unwrap());
+    assert_eq!(EXIT_ERROR, repl.env().last_status());
+    assert_eq!(ReplStep::Ran(EXIT_SUCCESS), repl.step().await.unwrap());
+}
+
+#[tokio::test]
+async fn should_fail_if_input_ends_with_incomplete_command() {
+    let mut repl = repl(vec!["true 'foo"]);
+    assert_eq!(ReplStep::Eof, repl.step().await.unwrap());
+    assert_eq!(EXIT_ERROR, repl.env().last_status());
+}
+
+#[tokio::test]
+async fn should_record_history() {
+    let mut repl = repl(vec!["true", "", "true 'foo", "bar'", "true !"]);
+    repl.run().await.unwrap();
+    assert_eq!(repl.env().history(), ["true", "true 'foo\nbar'", "true !"]);
+}
+
+#[tokio::test]
+async fn should_add_history_to_line_source() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("history");
+    fs::write(&path, "foo\n").unwrap();
+
+    let mut source = RecordingSource::new(vec!["true", "", "true 'foo", "bar'"]);
+    let mut repl = Repl::new(&mut source, new_env());
+    repl.env_mut().set_var(
+        Arc::new("HISTFILE".to_owned()),
+        Arc::new(path.to_string_lossy().into_owned()),
+    );
+
+    repl.load_history().await.unwrap();
+    repl.run().await.unwrap();
+    drop(repl);
+
+    assert_eq!(source.history, ["foo", "true", "true 'foo\nbar'"]);
+}
+
+#[tokio::test]
+async fn should_limit_history_to_histsize() {
+    let histsize = Arc::new("HISTSIZE".to_owned());
+
+    let mut limited = repl(vec!["true", "false", "true"]);
+    limited
+        .env_mut()
+        .set_var(histsize.clone(), Arc::new("2".to_owned()));
+    limited.run().await.unwrap();
+    assert_eq!(limited.env().history(), ["false", "true"]);
+    assert_eq!(limited.env().history_base(), 2);
+
+    let mut unlimited = repl(vec!["true", "false"]);
+    unlimited.env_mut().set_history_limit(Some(1));
+    unlimited
+        .env_mut()
+        .set_var(histsize, Arc::new("-1".to_owned()));
+    unlimited.run().await.unwrap();
+    assert_eq!(unlimited.env().history(), ["true", "false"]);
+    assert_eq!(unlimited.env().history_limit(), None);
+}
+
+#[tokio::test]
+async fn should_load_and_save_history_file() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("history");
+    let histfile = Arc::new(path.to_string_lossy().into_owned());
+    fs::write(&path, "foo\nbar\n").unwrap();
+
+    let mut persisted = repl(vec!["true"]);
+    persisted
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    persisted.load_history().await.unwrap();
+    assert_eq!(persisted.env().history(), ["foo", "bar"]);
+
+    persisted.run().await.unwrap();
+    persisted.save_history().await.unwrap();
+
+    // Only new entries are appended, each preceded by a timestamp
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines = contents.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4, "{:?}", contents);
+    assert_eq!(lines[..2], ["foo", "bar"]);
+    assert!(lines[2].starts_with('#'), "{:?}", contents);
+    assert_eq!(lines[3], "true");
+
+    // Saving again without any new entries leaves the file untouched
+    persisted.save_history().await.unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+    // Entries spanning multiple lines are restored as is
+    let mut multiline = repl(vec!["true 'foo", "", "bar'"]);
+    multiline
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    multiline.run().await.unwrap();
+    multiline.save_history().await.unwrap();
+
+    let mut restored = repl(vec![]);
+    restored
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    restored.load_history().await.unwrap();
+    assert_eq!(
+        restored.env().history(),
+        ["foo", "bar", "true", "true 'foo\n\nbar'"]
+    );
+
+    fs::remove_file(&path).unwrap();
+    let mut empty = repl(vec![]);
+    empty
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile);
+    empty.load_history().await.unwrap();
+    assert!(empty.env().history().is_empty());
+}
+
+#[tokio::test]
+async fn should_truncate_history_file_to_histfilesize() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("history");
+    let histfile = Arc::new(path.to_string_lossy().into_owned());
+    fs::write(&path, "foo\n#1\nbar\nbaz\n").unwrap();
+
+    let mut limited = repl(vec!["true", "false"]);
+    limited
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    limited.env_mut().set_var(
+        Arc::new("HISTFILESIZE".to_owned()),
+        Arc::new("2".to_owned()),
+    );
+    limited.run().await.unwrap();
+    limited.save_history().await.unwrap();
+
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines = contents.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4, "{:?}", contents);
+    assert!(lines[0].starts_with('#'), "{:?}", contents);
+    assert_eq!(lines[1], "true");
+    assert!(lines[2].starts_with('#'), "{:?}", contents);
+    assert_eq!(lines[3], "false");
+
+    // Falls back to $HISTSIZE, keeping multi-line entries whole
+    fs::write(&path, "foo\n#1\nbar\nbaz\n").unwrap();
+    let mut fallback = repl(vec!["true"]);
+    fallback
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    fallback
+        .env_mut()
+        .set_var(Arc::new("HISTSIZE".to_owned()), Arc::new("2".to_owned()));
+    fallback.run().await.unwrap();
+    fallback.save_history().await.unwrap();
+
+    let mut restored = repl(vec![]);
+    restored
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    restored.load_history().await.unwrap();
+    assert_eq!(restored.env().history(), ["bar\nbaz", "true"]);
+
+    // Negative values impose no limit
+    let mut unlimited = repl(vec!["true"]);
+    unlimited
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile.clone());
+    unlimited.env_mut().set_var(
+        Arc::new("HISTFILESIZE".to_owned()),
+        Arc::new("-1".to_owned()),
+    );
+    unlimited
+        .env_mut()
+        .set_var(Arc::new("HISTSIZE".to_owned()), Arc::new("1".to_owned()));
+    unlimited.run().await.unwrap();
+    unlimited.save_history().await.unwrap();
+
+    let mut restored = repl(vec![]);
+    restored
+        .env_mut()
+        .set_var(Arc::new("HISTFILE".to_owned()), histfile);
+    restored.load_history().await.unwrap();
+    assert_eq!(restored.env().history(), ["bar\nbaz", "true", "true"]);
+}
+
+#[tokio::test]
+async fn should_timestamp_history_with_env_clock() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("history");
+
+    let clock = MockClockEnv::new();
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(1234));
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.dynamic_var_env = DynamicVarEnv::with_sources(clock, RandomEnv::new());
+
+    let mut repl = Repl::new(
+        LineIter::new(vec!["true"].into_iter()),
+        DefaultEnvArc::with_config(cfg),
+    );
+    repl.env_mut().set_var(
+        Arc::new("HISTFILE".to_owned()),
+        Arc::new(path.to_string_lossy().into_owned()),
+    );
+    repl.run().await.unwrap();
+    repl.save_history().await.unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "#1234\ntrue\n");
+}
@@ -0,0 +1,99 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::introspect::{IntrospectEnvironment, PathScanner};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+#[macro_use]
+pub mod support;
+use crate::support::*;
+
+macro_rules! new_test_env {
+    () => {
+        Env::with_config(
+            DefaultEnvConfig::<String>::new()
+                .expect("failed to create test env")
+                .change_var_env(VarEnv::new())
+                .change_fn_error::<MockErr>(),
+        )
+    };
+}
+
+#[tokio::test]
+async fn should_list_defined_names() {
+    let mut env = new_test_env!();
+
+    env.set_function("zfunc".to_owned(), Arc::new(mock_status(EXIT_SUCCESS)));
+    env.set_function("afunc".to_owned(), Arc::new(mock_status(EXIT_SUCCESS)));
+    env.set_alias("ll".to_owned(), "ls -l".to_owned());
+    env.set_alias("la".to_owned(), "ls -a".to_owned());
+    env.set_exported_var("EXPORTED".to_owned(), "foo".to_owned(), true);
+    env.set_exported_var("ALSO_EXPORTED".to_owned(), "bar".to_owned(), true);
+    env.set_var("local".to_owned(), "baz".to_owned());
+
+    assert_eq!(env.function_names(), vec!("afunc", "zfunc"));
+    assert_eq!(env.alias_names(), vec!("la", "ll"));
+    assert_eq!(
+        env.exported_var_names(),
+        vec!("ALSO_EXPORTED", "EXPORTED", "OLDPWD", "PWD", "SHLVL")
+    );
+
+    let builtins = env.builtin_names();
+    for name in &["cd", "declare", "echo", "typeset", "unset"] {
+        assert!(builtins.contains(&(*name).to_owned()), "missing {}", name);
+    }
+    for name in &builtins {
+        assert!(env.builtin(name).is_some(), "unknown builtin {}", name);
+    }
+
+    let mut sorted = builtins.clone();
+    sorted.sort();
+    assert_eq!(builtins, sorted);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn path_scanner_should_list_and_cache_executables() {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    fn create_file(path: &std::path::Path, mode: u32) {
+        fs::write(path, "#!/bin/sh\n").expect("failed to write file");
+        fs::set_permissions(path, Permissions::from_mode(mode)).expect("failed to set permissions");
+    }
+
+    let tempdir = mktmp!();
+    let first = tempdir.path().join("first");
+    let second = tempdir.path().join("second");
+    fs::create_dir(&first).unwrap();
+    fs::create_dir(&second).unwrap();
+    fs::create_dir(first.join("dir")).unwrap();
+
+    create_file(&first.join("zexe"), 0o755);
+    create_file(&first.join("not_exe"), 0o644);
+    create_file(&second.join("aexe"), 0o700);
+    create_file(&second.join("zexe"), 0o755);
+
+    let mut env = new_test_env!();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .expect("failed to cd");
+
+    let mut scanner = PathScanner::new();
+    assert_eq!(scanner.executables(&env), Vec::<String>::new());
+
+    // Relative directories are resolved against the working directory
+    env.set_var("PATH".to_owned(), "first:second:missing".to_owned());
+    assert_eq!(scanner.executables(&env), vec!("aexe", "zexe"));
+
+    // Stale listings are rescanned once their directory is modified (after
+    // waiting long enough for the modification time to actually change)
+    tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+    fs::remove_file(second.join("aexe")).unwrap();
+    create_file(&second.join("bexe"), 0o755);
+    assert_eq!(scanner.executables(&env), vec!("bexe", "zexe"));
+
+    // Permission changes do not modify the directory, so need a manual rescan
+    fs::set_permissions(first.join("not_exe"), Permissions::from_mode(0o755)).unwrap();
+    scanner.clear();
+    assert_eq!(scanner.executables(&env), vec!("bexe", "not_exe", "zexe"));
+}
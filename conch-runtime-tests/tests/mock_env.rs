@@ -14,6 +14,7 @@ use std::sync::Arc;
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct MockFileAndVarEnv {
     file_desc_env: FileDescEnv<Arc<FileDesc>>,
+    nesting_env: NestingEnv,
     var_env: VarEnv<&'static str, &'static str>,
 }
 
@@ -21,6 +22,7 @@ impl MockFileAndVarEnv {
     pub fn new() -> Self {
         Self {
             file_desc_env: FileDescEnv::new(),
+            nesting_env: NestingEnv::new(),
             var_env: VarEnv::new(),
         }
     }
@@ -42,6 +44,25 @@ impl FileDescOpener for MockFileAndVarEnv {
     }
 }
 
+impl TempFileEnvironment for MockFileAndVarEnv {
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        FileDescOpenerEnv::new()
+            .open_temp_file(contents)
+            .map(Arc::new)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        let file = FileDescOpenerEnv::new().open_named_temp_file(contents)?;
+        Ok(TempFile {
+            handle: Arc::new(file.handle),
+            path: file.path,
+        })
+    }
+}
+
 impl AsyncIoEnvironment for MockFileAndVarEnv {
     type IoHandle = Arc<FileDesc>;
 
@@ -68,6 +89,10 @@ impl FileDescEnvironment for MockFileAndVarEnv {
         self.file_desc_env.file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        self.file_desc_env.file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, fdes: Self::FileHandle, perms: Permissions) {
         self.file_desc_env.set_file_desc(fd, fdes, perms)
     }
@@ -77,14 +102,36 @@ impl FileDescEnvironment for MockFileAndVarEnv {
     }
 }
 
+impl NestingEnvironment for MockFileAndVarEnv {
+    fn push_nesting_level(&mut self) {
+        self.nesting_env.push_nesting_level();
+    }
+
+    fn pop_nesting_level(&mut self) {
+        self.nesting_env.pop_nesting_level();
+    }
+
+    fn nesting_depth(&self) -> usize {
+        self.nesting_env.nesting_depth()
+    }
+
+    fn max_nesting_depth(&self) -> Option<usize> {
+        self.nesting_env.max_nesting_depth()
+    }
+
+    fn set_max_nesting_depth(&mut self, max_depth: Option<usize>) {
+        self.nesting_env.set_max_nesting_depth(max_depth);
+    }
+}
+
 impl VariableEnvironment for MockFileAndVarEnv {
     type VarName = &'static str;
     type Var = &'static str;
 
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.var_env.var(name)
     }
@@ -1,6 +1,6 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{VarEnv, VariableEnvironment};
+use conch_runtime::env::{UnsetVariableEnvironment, VarEnv, VariableEnvironment};
 use conch_runtime::eval::{assign, Fields, ParamEval, TildeExpansion, WordEvalConfig};
 
 mod support;
@@ -224,3 +224,25 @@ async fn eval_configs() {
     eval(false, &param, None).await.unwrap();
     eval(true, &param, None).await.unwrap();
 }
+
+#[tokio::test]
+async fn star_fields_should_be_joined_with_ifs_when_assigned() {
+    let name = "var".to_owned();
+    let param = MockParam::FieldsWithName(None, name.clone());
+    let strs = vec!["foo".to_owned(), "".to_owned(), "bar".to_owned()];
+
+    let mut env = VarEnv::<String, String>::new();
+    env.set_var("IFS".to_owned(), "-_".to_owned());
+
+    let word = mock_word_fields(Fields::Star(strs.clone()));
+    let ret = assign(false, &param, Some(word), &mut env, CFG).await;
+    assert_eq!(ret, Ok(Fields::Star(strs.clone())));
+    assert_eq!(env.var(&name), Some(&"foo--bar".to_owned()));
+
+    let word = mock_word_fields(Fields::At(strs));
+    env.unset_var(&name);
+    assign(false, &param, Some(word), &mut env, CFG)
+        .await
+        .unwrap();
+    assert_eq!(env.var(&name), Some(&"foo bar".to_owned()));
+}
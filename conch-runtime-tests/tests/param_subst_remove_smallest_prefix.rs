@@ -1,6 +1,8 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::eval::{remove_smallest_prefix, Fields};
+use conch_runtime::eval::{
+    remove_smallest_prefix, remove_smallest_prefix_with_config, Fields, PatternMatchConfig,
+};
 
 mod support;
 pub use self::support::*;
@@ -85,3 +87,40 @@ async fn should_propagate_errors_from_word_if_applicable() {
     );
     eval(&param, None).await.unwrap();
 }
+
+#[tokio::test]
+async fn should_match_case_insensitively_if_configured() {
+    let s = "abc \u{1F4A9} d abced".to_owned();
+    let param = MockParam::Fields(Some(Fields::Single(s.clone())));
+    let mock_word = mock_word_fields(Fields::Single("A*".to_owned()));
+
+    assert_eq!(
+        eval(&param, mock_word.clone()).await,
+        Ok(Fields::Single(s.clone()))
+    );
+
+    let cfg = PatternMatchConfig {
+        case_sensitive: false,
+        ..PatternMatchConfig::default()
+    };
+    let result = remove_smallest_prefix_with_config(&param, Some(mock_word), &mut (), cfg).await;
+    assert_eq!(
+        result,
+        Ok(Fields::Single("bc \u{1F4A9} d abced".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn should_handle_large_multibyte_values() {
+    let s = format!("{}tar", "\u{e9}.".repeat(10_000));
+    let param = MockParam::Fields(Some(Fields::Single(s.clone())));
+
+    let mock_word = mock_word_fields(Fields::Single("*.\u{e9}".to_owned()));
+    assert_eq!(
+        eval(&param, mock_word).await,
+        Ok(Fields::Single(format!(".{}tar", "\u{e9}.".repeat(9_998))))
+    );
+
+    let mock_word = mock_word_fields(Fields::Single("x*".to_owned()));
+    assert_eq!(eval(&param, mock_word).await, Ok(Fields::Single(s)));
+}
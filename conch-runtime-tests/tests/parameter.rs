@@ -2,10 +2,14 @@
 
 use conch_parser::ast::Parameter::*;
 use conch_runtime::env::{
-    ArgsEnv, ArgumentsEnvironment, Env, EnvConfig, LastStatusEnvironment, VariableEnvironment,
+    ArgsEnv, ArgumentsEnvironment, DynamicVarEnv, DynamicVariableEnvironment, Env, EnvConfig,
+    LastStatusEnv, LastStatusEnvironment, MockClockEnv, MockRandomEnv, ProcessIdEnv,
+    ProcessIdEnvironment, ShellOption, ShellOptionsEnvironment, SubEnvironment,
+    VariableEnvironment,
 };
-use conch_runtime::eval::{Fields, ParamEval};
+use conch_runtime::eval::{assignable_value, Fields, ParamEval};
 use conch_runtime::ExitStatus;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_eval_parameter_with_set_vars() {
@@ -38,10 +42,18 @@ async fn test_eval_parameter_with_set_vars() {
         Some(Fields::Single(getpid().to_string()))
     );
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
+    // FIXME: test this
     //assert_eq!(Bang.eval(false, &env), ...);
 
+    assert_eq!(Dash.eval(false, &env), Some(Fields::Single(String::new())));
+    env.set_option(ShellOption::ErrExit, true);
+    env.set_option(ShellOption::NoUnset, true);
+    env.set_option(ShellOption::PipeFail, true);
+    assert_eq!(
+        Dash.eval(false, &env),
+        Some(Fields::Single("eu".to_owned()))
+    );
+
     // Before anything is run it should be considered a success
     assert_eq!(
         Question.eval(false, &env),
@@ -93,8 +105,7 @@ async fn test_eval_parameter_with_unset_vars() {
     assert_eq!(At.eval(false, &env), Some(Fields::Zero));
     assert_eq!(Star.eval(false, &env), Some(Fields::Zero));
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
+    // FIXME: test this
     //assert_eq!(Bang.eval(false, &env), ...);
 
     assert_eq!(
@@ -113,6 +124,90 @@ async fn test_eval_parameter_with_unset_vars() {
     assert_eq!(Var("var2".to_owned()).eval(false, &env), None);
 }
 
+#[tokio::test]
+async fn test_eval_shell_and_subshell_pids() {
+    let env = Env::new().expect("failed to create env");
+    let sub_env = env.sub_env();
+
+    let bashpid = Var("BASHPID".to_owned());
+    let pid = Fields::Single(env.shell_pid().to_string());
+
+    assert_eq!(Dollar.eval(false, &env), Some(pid.clone()));
+    assert_eq!(bashpid.eval(false, &env), Some(pid.clone()));
+
+    assert_eq!(Dollar.eval(false, &sub_env), Some(pid.clone()));
+    assert_eq!(
+        bashpid.eval(false, &sub_env),
+        Some(Fields::Single(sub_env.subshell_pid().to_string()))
+    );
+    assert_ne!(bashpid.eval(false, &sub_env), Some(pid));
+}
+
+#[tokio::test]
+async fn test_eval_injected_pids_and_status() {
+    let env = Env::with_config(EnvConfig {
+        last_status_env: LastStatusEnv::with_status(ExitStatus::Code(3)),
+        pid_env: ProcessIdEnv::with_pids(1234, 1),
+        ..EnvConfig::new().expect("failed to create env")
+    });
+
+    let single = |s: &str| Some(Fields::Single(s.to_owned()));
+
+    assert_eq!(Dollar.eval(false, &env), single("1234"));
+    assert_eq!(Var("PPID".to_owned()).eval(false, &env), single("1"));
+    assert_eq!(Var("BASHPID".to_owned()).eval(false, &env), single("1234"));
+    assert_eq!(Question.eval(false, &env), single("3"));
+
+    let sub_env = env.sub_env();
+    assert_eq!(Dollar.eval(false, &sub_env), single("1234"));
+    assert_eq!(Var("PPID".to_owned()).eval(false, &sub_env), single("1"));
+    assert_eq!(
+        Var("BASHPID".to_owned()).eval(false, &sub_env),
+        single("1235")
+    );
+}
+
+#[tokio::test]
+async fn test_eval_dynamic_vars() {
+    let clock = MockClockEnv::new();
+    let mut env = Env::with_config(EnvConfig {
+        dynamic_var_env: DynamicVarEnv::with_sources(clock.clone(), MockRandomEnv::new(vec![4, 2])),
+        ..EnvConfig::new().expect("failed to create env")
+    });
+
+    let single = |s: &str| Some(Fields::Single(s.to_owned()));
+    let seconds = Var("SECONDS".to_owned());
+    let random = Var("RANDOM".to_owned());
+    let lineno = Var("LINENO".to_owned());
+
+    assert_eq!(seconds.eval(false, &env), single("0"));
+    clock.advance(Duration::from_millis(2500));
+    assert_eq!(seconds.eval(false, &env), single("2"));
+    assert_eq!(seconds.eval(false, &env.sub_env()), single("2"));
+
+    assert_eq!(random.eval(false, &env), single("4"));
+    assert_eq!(random.eval(false, &env), single("2"));
+    assert_eq!(random.eval(false, &env), single("4"));
+
+    assert_eq!(lineno.eval(false, &env), single("1"));
+    env.set_current_line(7);
+    assert_eq!(lineno.eval(false, &env), single("7"));
+
+    // Assignments restart the count of seconds and reseed the generator
+    let assign = |env: &mut Env<_, _, _, _, _, _, _, _, _>, name: &str, value: &str| {
+        let value = assignable_value(&name.to_owned(), value.to_owned(), env).unwrap();
+        env.set_var(name.to_owned(), value);
+    };
+
+    assign(&mut env, "SECONDS", "10");
+    assert_eq!(seconds.eval(false, &env), single("10"));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(seconds.eval(false, &env), single("11"));
+
+    assign(&mut env, "RANDOM", "5");
+    assert_eq!(random.eval(false, &env), single("4"));
+}
+
 #[tokio::test]
 async fn test_eval_parameter_splitting_with_default_ifs() {
     let val1 = " \t\nfoo\n\n\nbar \t\n".to_owned();
@@ -243,8 +338,7 @@ async fn test_eval_parameter_splitting_with_custom_ifs() {
     );
     assert_eq!(Var("var3".to_owned()).eval(true, &env), Some(Fields::Zero));
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
+    // FIXME: test this
     //assert_eq!(Bang.eval(false, &env), ...);
 
     assert_eq!(
@@ -282,8 +376,7 @@ async fn test_eval_parameter_splitting_with_custom_ifs() {
         Some(Fields::Single(val3))
     );
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
+    // FIXME: test this
     //assert_eq!(Bang.eval(false, &env), ...);
 
     assert_eq!(
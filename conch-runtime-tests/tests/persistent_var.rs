@@ -0,0 +1,165 @@
+#![deny(rust_2018_idioms)]
+
+use std::fs;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[test]
+fn designated_vars_should_survive_across_instances() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("vars");
+    let persisted = vec!["counter", "multiline", "unset_later"];
+
+    {
+        let store = FileVarStore::new(&path);
+        let mut env =
+            PersistentVarEnv::new(VarEnv::<String, String>::new(), store, persisted.clone())
+                .expect("failed to load vars");
+        assert!(env.is_persisted("counter"));
+        assert!(!env.is_persisted("other"));
+
+        env.set_var("counter".to_owned(), "1".to_owned());
+        env.set_var("multiline".to_owned(), "a\\b\nc".to_owned());
+        env.set_var("unset_later".to_owned(), "foo".to_owned());
+        env.set_var("other".to_owned(), "ignored".to_owned());
+        env.save().expect("failed to save vars");
+
+        env.unset_var(&"unset_later".to_owned());
+        env.save().expect("failed to save vars");
+    }
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "counter=1\nmultiline=a\\\\b\\nc\n"
+    );
+
+    let env = PersistentVarEnv::new(
+        VarEnv::<String, String>::new(),
+        FileVarStore::new(&path),
+        persisted,
+    )
+    .expect("failed to load vars");
+    assert_eq!(env.var("counter"), Some(&"1".to_owned()));
+    assert_eq!(env.var("multiline"), Some(&"a\\b\nc".to_owned()));
+    assert_eq!(env.var("unset_later"), None);
+    assert_eq!(env.var("other"), None);
+    assert_eq!(
+        env.exported_var(&"counter".to_owned()),
+        Some((&"1".to_owned(), false))
+    );
+}
+
+#[test]
+fn saving_should_preserve_vars_which_are_not_designated() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("vars");
+    fs::write(&path, "foreign=keep\nshared=old\n").unwrap();
+
+    let mut env = PersistentVarEnv::new(
+        VarEnv::<String, String>::new(),
+        FileVarStore::new(&path),
+        vec!["shared", "counter"],
+    )
+    .expect("failed to load vars");
+    assert_eq!(env.var("foreign"), None);
+
+    env.set_var("counter".to_owned(), "1".to_owned());
+    env.unset_var(&"shared".to_owned());
+    env.save().expect("failed to save vars");
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "counter=1\nforeign=keep\n"
+    );
+}
+
+#[test]
+fn missing_store_should_load_no_vars() {
+    let tempdir = mktmp!();
+    let store = FileVarStore::new(tempdir.path().join("missing"));
+
+    let env = PersistentVarEnv::new(VarEnv::<String, String>::new(), store, vec!["foo"])
+        .expect("failed to load vars");
+    assert_eq!(env.var("foo"), None);
+}
+
+#[test]
+fn malformed_store_should_fail_to_load() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("vars");
+    fs::write(&path, "no_separator\n").unwrap();
+
+    let result = PersistentVarEnv::new(
+        VarEnv::<String, String>::new(),
+        FileVarStore::new(&path),
+        vec!["foo"],
+    );
+    match result {
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidData),
+        Ok(_) => panic!("unexpected success"),
+    }
+}
+
+#[test]
+fn carriage_returns_should_survive_across_instances() {
+    let tempdir = mktmp!();
+    let store = FileVarStore::new(tempdir.path().join("vars"));
+
+    let vars = vec![
+        ("trailing".to_owned(), "foo\r".to_owned()),
+        ("crlf".to_owned(), "a\r\nb\\r".to_owned()),
+    ];
+    store.store(&vars).expect("failed to save vars");
+    assert_eq!(store.load().expect("failed to load vars"), vars);
+}
+
+#[test]
+fn invalid_names_should_fail_to_store() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("vars");
+    let store = FileVarStore::new(&path);
+    store
+        .store(&[("foo".to_owned(), "bar".to_owned())])
+        .expect("failed to save vars");
+
+    for name in &["", "a=b", "a\nb", "a\rb"] {
+        match store.store(&[((*name).to_owned(), "val".to_owned())]) {
+            Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+            Ok(()) => panic!("unexpected success for {:?}", name),
+        }
+    }
+
+    // Previously stored variables remain untouched
+    assert_eq!(fs::read_to_string(&path).unwrap(), "foo=bar\n");
+    assert_eq!(fs::read_dir(tempdir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn concurrent_stores_should_not_clobber_each_other() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("vars");
+
+    let threads = (0..8)
+        .map(|i| {
+            let store = FileVarStore::new(&path);
+            std::thread::spawn(move || {
+                for _ in 0..32 {
+                    let vars = [("var".to_owned(), i.to_string())];
+                    store.store(&vars).expect("failed to save vars");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let vars = FileVarStore::new(&path)
+        .load()
+        .expect("failed to load vars");
+    assert_eq!(vars.len(), 1);
+    assert_eq!(fs::read_dir(tempdir.path()).unwrap().count(), 1);
+}
@@ -0,0 +1,230 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[derive(Debug)]
+struct AllowListPolicy {
+    executables: Vec<&'static str>,
+    writable_dir: PathBuf,
+}
+
+fn denied() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "denied by policy")
+}
+
+impl SecurityPolicy for AllowListPolicy {
+    fn check_open_path(&self, path: &Path, perms: Permissions) -> io::Result<()> {
+        if perms.writable() && !path.starts_with(&self.writable_dir) {
+            Err(denied())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_spawn_executable(&self, _program: &Path, data: &ExecutableData<'_>) -> io::Result<()> {
+        if self.executables.iter().any(|&exe| data.name == exe) {
+            Ok(())
+        } else {
+            Err(denied())
+        }
+    }
+
+    fn check_change_working_dir(&self, path: &Path) -> io::Result<()> {
+        if path.starts_with(&self.writable_dir) {
+            Ok(())
+        } else {
+            Err(denied())
+        }
+    }
+}
+
+fn policy(writable_dir: &Path) -> Arc<AllowListPolicy> {
+    Arc::new(AllowListPolicy {
+        executables: vec!["git", "ls"],
+        writable_dir: writable_dir.canonicalize().unwrap(),
+    })
+}
+
+#[tokio::test]
+async fn policy_should_restrict_spawned_executables() {
+    let tempdir = mktmp!();
+
+    let mut mock = MockExecEnv::new();
+    mock.register("ls", MockExecOutput::status(ExitStatus::Code(5)));
+    mock.register("rm", MockExecOutput::status(ExitStatus::Code(6)));
+
+    let env = PolicyEnv::new(mock.clone(), policy(tempdir.path()));
+    let spawn = |name: &str| {
+        env.spawn_executable(ExecutableData {
+            name: OsStr::new(name),
+            args: &[],
+            env_vars: &[],
+            current_dir: tempdir.path(),
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            inherited_fds: Vec::new(),
+            deadline: None,
+            resource_limits: &[],
+            process_group: None,
+        })
+    };
+
+    let ls = spawn("ls").expect("spawn failed");
+    assert_eq!(ls.await, ExitStatus::Code(5));
+
+    match spawn("rm") {
+        Err(CommandError::Io(e, Some(name))) => {
+            assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+            assert_eq!(name, "rm");
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("unexpected success"),
+    }
+
+    assert_eq!(mock.invocations().len(), 1);
+}
+
+#[tokio::test]
+async fn policy_should_check_executables_found_via_path() {
+    #[derive(Debug)]
+    struct TrustedDirPolicy(PathBuf);
+
+    impl SecurityPolicy for TrustedDirPolicy {
+        fn check_spawn_executable(&self, program: &Path, _: &ExecutableData<'_>) -> io::Result<()> {
+            if program.starts_with(&self.0) {
+                Ok(())
+            } else {
+                Err(denied())
+            }
+        }
+    }
+
+    let tempdir = mktmp!();
+    let trusted = tempdir.path().join("trusted");
+    let untrusted = tempdir.path().join("untrusted");
+    fs::create_dir(&trusted).unwrap();
+    fs::create_dir(&untrusted).unwrap();
+    fs::write(trusted.join("git"), "").unwrap();
+    fs::write(untrusted.join("git"), "").unwrap();
+
+    let mut mock = MockExecEnv::new();
+    mock.register("git", MockExecOutput::status(ExitStatus::Code(5)));
+
+    let policy = Arc::new(TrustedDirPolicy(trusted.canonicalize().unwrap()));
+    let env = PolicyEnv::new(mock.clone(), policy);
+    let spawn = |path: &Path| {
+        env.spawn_executable(ExecutableData {
+            name: OsStr::new("git"),
+            args: &[],
+            env_vars: &[(OsStr::new("PATH"), path.as_os_str())],
+            current_dir: tempdir.path(),
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            inherited_fds: Vec::new(),
+            deadline: None,
+            resource_limits: &[],
+            process_group: None,
+        })
+    };
+
+    let git = spawn(&trusted).expect("spawn failed");
+    assert_eq!(git.await, ExitStatus::Code(5));
+
+    for path in &[untrusted.clone(), trusted.join("../untrusted")] {
+        match spawn(path) {
+            Err(CommandError::Io(e, _)) => assert_eq!(e.kind(), io::ErrorKind::PermissionDenied),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("unexpected success with PATH={}", path.display()),
+        }
+    }
+
+    assert_eq!(mock.invocations().len(), 1);
+}
+
+#[tokio::test]
+async fn policy_should_restrict_opened_paths() {
+    let tempdir = mktmp!();
+    let writable = tempdir.path().join("writable");
+    let readonly = tempdir.path().join("readonly");
+    fs::create_dir(&writable).unwrap();
+    fs::create_dir(&readonly).unwrap();
+    fs::write(readonly.join("file"), "hello").unwrap();
+
+    let mut env = PolicyEnv::new(FileDescOpenerEnv::new(), policy(&writable));
+
+    let write_opts: OpenOptions = Permissions::Write.into();
+    let read_opts: OpenOptions = Permissions::Read.into();
+
+    env.open_path_with_permissions(&writable.join("file"), Permissions::Write, &write_opts)
+        .expect("write under allowed dir failed");
+    env.open_path_with_permissions(&readonly.join("file"), Permissions::Read, &read_opts)
+        .expect("read outside allowed dir failed");
+
+    let err = env
+        .open_path_with_permissions(&readonly.join("new"), Permissions::Write, &write_opts)
+        .expect_err("write outside allowed dir succeeded");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert!(!readonly.join("new").exists());
+
+    // Opening without known permissions is treated as a write
+    let err = env
+        .open_path(&readonly.join("file"), &read_opts)
+        .expect_err("open without permissions succeeded");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+    // Paths cannot escape the allowed dir through `..` components
+    for name in &["file", "new"] {
+        let path = writable.join("..").join("readonly").join(name);
+        let err = env
+            .open_path_with_permissions(&path, Permissions::Write, &write_opts)
+            .expect_err("write outside allowed dir via `..` succeeded");
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+    assert_eq!(fs::read_to_string(readonly.join("file")).unwrap(), "hello");
+    assert!(!readonly.join("new").exists());
+
+    // Nor through symbolic links
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&readonly, writable.join("link")).unwrap();
+        let err = env
+            .open_path_with_permissions(&writable.join("link/new"), Permissions::Write, &write_opts)
+            .expect_err("write outside allowed dir via symlink succeeded");
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!readonly.join("new").exists());
+    }
+}
+
+#[tokio::test]
+async fn policy_should_restrict_working_dir_changes() {
+    let tempdir = mktmp!();
+    let allowed = tempdir.path().join("allowed");
+    fs::create_dir_all(allowed.join("sub")).unwrap();
+
+    let cur_dir = VirtualWorkingDirEnv::new(&allowed).unwrap();
+    let mut env = PolicyEnv::new(cur_dir, policy(&allowed));
+
+    env.change_working_dir(Cow::Borrowed(Path::new("sub")))
+        .expect("change_working_dir failed");
+    assert_eq!(env.current_working_dir(), allowed.join("sub"));
+
+    for path in &["../..", "../../allowed/.."] {
+        let err = env
+            .change_working_dir(Cow::Borrowed(Path::new(path)))
+            .expect_err("change_working_dir succeeded");
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+    assert_eq!(env.current_working_dir(), allowed.join("sub"));
+}
@@ -0,0 +1,105 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::prompt::{expand_prompt, expand_ps1, expand_ps2, expand_ps4};
+use std::borrow::Cow;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn set_var(env: &mut DefaultEnvArc, name: &str, val: &str) {
+    env.set_var(Arc::new(name.to_owned()), Arc::new(val.to_owned()));
+}
+
+#[tokio::test]
+async fn should_expand_user_and_host_from_env() {
+    let mut env = new_env();
+    set_var(&mut env, "USER", "me");
+    set_var(&mut env, "HOSTNAME", "box.example.com");
+
+    assert_eq!(expand_prompt("\\u@\\h:\\H", &env), "me@box:box.example.com");
+}
+
+#[tokio::test]
+async fn should_abbreviate_home_in_working_dir() {
+    let tempdir = mktmp!();
+    let home = tempdir.path().join("home");
+    let sub = home.join("sub");
+    fs::create_dir_all(&sub).unwrap();
+
+    let mut env = new_env();
+    set_var(&mut env, "HOME", &home.to_string_lossy());
+
+    env.change_working_dir(Cow::Borrowed(&home)).unwrap();
+    assert_eq!(expand_prompt("\\w \\W", &env), "~ ~");
+
+    env.change_working_dir(Cow::Borrowed(&sub)).unwrap();
+    assert_eq!(expand_prompt("\\w \\W", &env), "~/sub sub");
+
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .unwrap();
+    let name = tempdir.path().file_name().unwrap().to_string_lossy();
+    assert_eq!(
+        expand_prompt("\\w \\W", &env),
+        format!("{} {}", tempdir.path().display(), name)
+    );
+}
+
+#[tokio::test]
+async fn should_expand_literal_escapes() {
+    let env = new_env();
+
+    assert_eq!(
+        expand_prompt("\\[\\e[1m\\]a\\\\b\\n\\101\\0601\\q\\", &env),
+        "\x1b[1ma\\b\nA01\\q\\"
+    );
+}
+
+#[tokio::test]
+async fn should_expand_times_with_fixed_widths() {
+    let env = new_env();
+    let time = expand_prompt("\\t|\\T|\\A|\\@", &env);
+    let parts = time.split('|').map(str::len).collect::<Vec<_>>();
+    assert_eq!(parts, vec![8, 8, 5, 8]);
+}
+
+#[tokio::test]
+async fn should_expand_times_from_env_clock() {
+    let clock = MockClockEnv::new();
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.dynamic_var_env = DynamicVarEnv::with_sources(clock.clone(), RandomEnv::new());
+    let env = DefaultEnvArc::with_config(cfg);
+
+    // 2020-01-15T12:00:00Z, NB: time zones are offset by whole minutes
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(1_579_089_600));
+    let first = expand_prompt("\\d \\t", &env);
+    assert!(first.ends_with(":00"), "{}", first);
+    assert_eq!(expand_prompt("\\d \\t", &env), first);
+
+    clock.advance(Duration::from_secs(7));
+    let second = expand_prompt("\\d \\t", &env);
+    assert_eq!(second, format!("{}07", &first[..first.len() - 2]));
+}
+
+#[tokio::test]
+async fn should_expand_prompt_vars_or_defaults() {
+    let mut env = new_env();
+    let dollar = expand_prompt("\\$", &env);
+    assert!(dollar == "$" || dollar == "#");
+
+    assert_eq!(expand_ps1(&env), format!("{} ", dollar));
+    assert_eq!(expand_ps2(&env), "> ");
+    assert_eq!(expand_ps4(&env), "+ ");
+
+    set_var(&mut env, "USER", "me");
+    set_var(&mut env, "PS1", "\\u\\$ ");
+    set_var(&mut env, "PS2", "...");
+    set_var(&mut env, "PS4", "+\\u ");
+
+    assert_eq!(expand_ps1(&env), format!("me{} ", dollar));
+    assert_eq!(expand_ps2(&env), "...");
+    assert_eq!(expand_ps4(&env), "+me ");
+}
@@ -144,7 +144,6 @@ async fn eval_write_and_clobber() {
     let cases = vec![
         (STDOUT_FILENO, Write(None, path.clone())),
         (42, Write(Some(42), path.clone())),
-        // FIXME: split out clobber tests and check clobber semantics
         (STDOUT_FILENO, Clobber(None, path.clone())),
         (42, Clobber(Some(42), path.clone())),
     ];
@@ -171,6 +170,42 @@ async fn eval_write_and_clobber() {
     .await;
 }
 
+#[tokio::test]
+async fn eval_write_with_noclobber() {
+    use conch_runtime::env::{ShellOption, ShellOptionsEnvironment};
+
+    let tempdir = mktmp!();
+    let file_path = tempdir.path().join("out");
+    let path = mock_word_fields(Fields::Single(file_path.display().to_string()));
+    let dev_null = mock_word_fields(Fields::Single(DEV_NULL.to_owned()));
+
+    let mut env = new_env_with_no_fds();
+    env.set_option(ShellOption::NoClobber, true);
+
+    match Write(None, path.clone()).eval(&mut env).await {
+        Ok(RedirectAction::Open(STDOUT_FILENO, _, Permissions::Write)) => {}
+        result => panic!("unexpected result: {:#?}", result),
+    }
+    assert!(file_path.exists());
+
+    let err = Err(MockErr::RedirectionError(Arc::new(
+        RedirectionError::NoClobber(file_path.display().to_string()),
+    )));
+    assert_eq!(Write(None, path.clone()).eval(&mut env).await, err);
+
+    // Clobbering should still be allowed
+    match Clobber(None, path.clone()).eval(&mut env).await {
+        Ok(RedirectAction::Open(STDOUT_FILENO, _, Permissions::Write)) => {}
+        result => panic!("unexpected result: {:#?}", result),
+    }
+
+    // And so should writing to files which aren't regular files
+    match Write(None, dev_null).eval(&mut env).await {
+        Ok(RedirectAction::Open(STDOUT_FILENO, _, Permissions::Write)) => {}
+        result => panic!("unexpected result: {:#?}", result),
+    }
+}
+
 #[tokio::test]
 async fn eval_read_write() {
     let original = "original message";
@@ -285,6 +320,217 @@ async fn eval_heredoc() {
     }
 }
 
+#[tokio::test]
+async fn eval_herestring() {
+    use conch_runtime::eval::redirect_herestring;
+
+    let fields = vec!["first".to_owned(), "second".to_owned()];
+    let joined = b"first second\n".to_vec();
+
+    let cases = vec![
+        (mock_word_fields(Fields::Zero), b"\n".to_vec()),
+        (
+            mock_word_fields(Fields::Single("single".to_owned())),
+            b"single\n".to_vec(),
+        ),
+        (mock_word_fields(Fields::At(fields.clone())), joined.clone()),
+        (
+            mock_word_fields(Fields::Star(fields.clone())),
+            joined.clone(),
+        ),
+        (
+            mock_word_fields(Fields::Split(fields.clone())),
+            joined.clone(),
+        ),
+    ];
+
+    let mut env = new_env();
+    for (word, expected) in cases {
+        let action = RedirectAction::HereDoc(STDIN_FILENO, expected.clone());
+        assert_eq!(redirect_herestring(None, &word, &mut env).await, Ok(action));
+
+        let action = RedirectAction::HereDoc(42, expected.clone());
+        assert_eq!(
+            redirect_herestring(Some(42), &word, &mut env).await,
+            Ok(action)
+        );
+    }
+}
+
+#[tokio::test]
+async fn eval_write_and_append_to_stdout_and_stderr() {
+    use conch_runtime::eval::{redirect_append_all, redirect_write_all};
+
+    let tempdir = mktmp!();
+    let file_path = tempdir.path().join("out");
+    let path = mock_word_fields(Fields::Single(file_path.display().to_string()));
+
+    let mut env = new_env_with_no_fds();
+    File::create(&file_path)
+        .and_then(|mut file| file.write_all(b"old"))
+        .unwrap();
+
+    for &append in &[false, true] {
+        let action = if append {
+            redirect_append_all(&path, &mut env).await
+        } else {
+            redirect_write_all(&path, &mut env).await
+        };
+        action.expect("eval failed").apply(&mut env).unwrap();
+
+        let (stdout, perms) = env.file_desc(STDOUT_FILENO).expect("stdout not opened");
+        assert_eq!(perms, Permissions::Write);
+        assert_eq!(
+            env.file_desc(conch_runtime::STDERR_FILENO),
+            Some((stdout, perms))
+        );
+
+        let msg = if append { "appended" } else { "new" };
+        (&**stdout).write_all(msg.as_bytes()).unwrap();
+        env.close_file_desc(STDOUT_FILENO);
+        env.close_file_desc(conch_runtime::STDERR_FILENO);
+    }
+
+    let mut read = String::new();
+    File::open(&file_path)
+        .and_then(|mut file| file.read_to_string(&mut read))
+        .unwrap();
+    assert_eq!(read, "newappended");
+}
+
+#[tokio::test]
+async fn eval_dup_write_or_all() {
+    use crate::RedirectionError::BadFdSrc;
+    use conch_runtime::eval::redirect_dup_write_or_all;
+
+    let tempdir = mktmp!();
+    let file_path = tempdir.path().join("out");
+    let path = mock_word_fields(Fields::Single(file_path.display().to_string()));
+
+    let mut env = new_env_with_no_fds();
+    let fdes = dev_null(&mut env);
+    env.set_file_desc(5, fdes.clone(), Permissions::Write);
+
+    let word = mock_word_fields(Fields::Single("5".to_owned()));
+    let action = RedirectAction::Open(STDOUT_FILENO, fdes.clone(), Permissions::Write);
+    assert_eq!(
+        redirect_dup_write_or_all(None, &word, &mut env).await,
+        Ok(action)
+    );
+
+    let action = RedirectAction::Open(42, fdes.clone(), Permissions::Write);
+    assert_eq!(
+        redirect_dup_write_or_all(Some(42), &word, &mut env).await,
+        Ok(action)
+    );
+
+    let word = mock_word_fields(Fields::Single("-".to_owned()));
+    let action = RedirectAction::Close(42);
+    assert_eq!(
+        redirect_dup_write_or_all(Some(42), &word, &mut env).await,
+        Ok(action)
+    );
+
+    let err = Err(MockErr::RedirectionError(Arc::new(BadFdSrc(
+        file_path.display().to_string(),
+    ))));
+    assert_eq!(
+        redirect_dup_write_or_all(Some(2), &path, &mut env).await,
+        err
+    );
+    assert!(!file_path.exists());
+
+    match redirect_dup_write_or_all(None, &path, &mut env).await {
+        Ok(RedirectAction::Multiple(actions)) => match actions.as_slice() {
+            [RedirectAction::Open(STDOUT_FILENO, out, Permissions::Write), RedirectAction::Open(2, err, Permissions::Write)] =>
+            {
+                assert_eq!(out, err)
+            }
+            actions => panic!("unexpected actions: {:#?}", actions),
+        },
+        result => panic!("unexpected result: {:#?}", result),
+    }
+    assert!(file_path.exists());
+}
+
+#[tokio::test]
+async fn eval_fd_var() {
+    use crate::RedirectionError::BadFdVar;
+    use conch_runtime::env::{EnvRestorer, RedirectEnvRestorer, VariableEnvironment};
+    use conch_runtime::eval::redirect_fd_var;
+
+    let mut env = new_env_with_no_fds();
+    let fdes = dev_null(&mut env);
+    env.set_file_desc(10, fdes.clone(), Permissions::Read);
+
+    let var = Arc::new("fd".to_owned());
+    let redirect = mock_redirect(RedirectAction::Open(1, fdes.clone(), Permissions::Write));
+    let action = redirect_fd_var(&var, redirect, &mut env).await.unwrap();
+    assert_eq!(
+        action,
+        RedirectAction::Persistent(Box::new(RedirectAction::Open(
+            11,
+            fdes.clone(),
+            Permissions::Write
+        )))
+    );
+    assert_eq!(env.var(&var), Some(&Arc::new("11".to_owned())));
+
+    {
+        let mut restorer = EnvRestorer::new(&mut env);
+        RedirectAction::<Arc<_>>::Close(10)
+            .apply_with_restorer(&mut restorer)
+            .unwrap();
+        action.apply_with_restorer(&mut restorer).unwrap();
+        assert_eq!(restorer.get().file_desc(10), None);
+        restorer.restore_redirects();
+    }
+
+    // Only the non-persistent redirect should have been restored
+    assert_eq!(env.file_desc(10), Some((&fdes, Permissions::Read)));
+    assert_eq!(env.file_desc(11), Some((&fdes, Permissions::Write)));
+
+    let redirect = mock_redirect(RedirectAction::<Arc<_>>::Close(1));
+    let action = redirect_fd_var(&var, redirect, &mut env).await.unwrap();
+    assert_eq!(
+        action,
+        RedirectAction::Persistent(Box::new(RedirectAction::Close(11)))
+    );
+
+    {
+        let mut restorer = EnvRestorer::new(&mut env);
+        action.apply_with_restorer(&mut restorer).unwrap();
+        restorer.restore_redirects();
+    }
+    assert_eq!(env.file_desc(11), None);
+
+    env.set_var(var.clone(), Arc::new("foo".to_owned()));
+    let redirect = mock_redirect(RedirectAction::<Arc<FileDesc>>::Close(1));
+    let err = Err(MockErr::RedirectionError(Arc::new(BadFdVar(
+        "fd".to_owned(),
+    ))));
+    assert_eq!(redirect_fd_var(&var, redirect, &mut env).await, err);
+}
+
+#[tokio::test]
+async fn eval_fd_var_should_error_if_no_fds_are_unused() {
+    use conch_runtime::eval::redirect_fd_var;
+
+    let mut env = new_env_with_no_fds();
+    let fdes = dev_null(&mut env);
+    for fd in 10..=Fd::MAX {
+        env.set_file_desc(fd, fdes.clone(), Permissions::Read);
+    }
+
+    let var = Arc::new("fd".to_owned());
+    let redirect = mock_redirect(RedirectAction::Open(1, fdes.clone(), Permissions::Write));
+    let err = Err(MockErr::RedirectionError(Arc::new(RedirectionError::Io(
+        std::io::Error::new(std::io::ErrorKind::Other, "too many open file descriptors"),
+        Some("fd".to_owned()),
+    ))));
+    assert_eq!(redirect_fd_var(&var, redirect, &mut env).await, err);
+}
+
 #[tokio::test]
 async fn apply_redirect_action() {
     let mut env = new_env_with_no_fds();
@@ -364,6 +610,17 @@ async fn should_split_word_fields_if_interactive_and_expand_first_tilde() {
                 .await
                 .expect("did not get successful response");
         }
+
+        let cfg_herestring = WordEvalConfig {
+            tilde_expansion: TildeExpansion::First,
+            split_fields_further: false,
+        };
+
+        let word =
+            mock_word_assert_cfg_with_fields(Fields::Single("foo".to_owned()), cfg_herestring);
+        conch_runtime::eval::redirect_herestring(None, word, &mut env)
+            .await
+            .expect("did not get successful response");
     }
 }
 
@@ -418,6 +675,36 @@ async fn should_eval_dup_raises_appropriate_perms_or_bad_src_errors() {
     assert_eq!(DupRead(Some(fd), path.clone()).eval(&mut env).await, err);
 }
 
+#[tokio::test]
+async fn should_resolve_special_paths_to_shell_descriptors() {
+    use crate::RedirectionError::BadFdSrc;
+
+    let mut env = new_env_with_no_fds();
+    let stdout = dev_null(&mut env);
+    let fd3 = dev_null(&mut env);
+    env.set_file_desc(STDOUT_FILENO, stdout.clone(), Permissions::Write);
+    env.set_file_desc(3, fd3.clone(), Permissions::Write);
+    env.set_option(ShellOption::NoClobber, true);
+
+    let path = |p: &str| mock_word_fields(Fields::Single(p.to_owned()));
+
+    assert_eq!(
+        Write(Some(5), path("/dev/stdout")).eval(&mut env).await,
+        Ok(RedirectAction::Open(5, stdout, Permissions::Write))
+    );
+    assert_eq!(
+        Append(None, path("/dev/fd/3")).eval(&mut env).await,
+        Ok(RedirectAction::Open(STDOUT_FILENO, fd3, Permissions::Write))
+    );
+
+    for special in &["/dev/stdin", "/dev/stderr", "/dev/fd/4"] {
+        let err = Err(MockErr::RedirectionError(Arc::new(BadFdSrc(
+            special.to_string(),
+        ))));
+        assert_eq!(Read(None, path(special)).eval(&mut env).await, err);
+    }
+}
+
 #[tokio::test]
 async fn eval_ambiguous_path() {
     use crate::RedirectionError::Ambiguous;
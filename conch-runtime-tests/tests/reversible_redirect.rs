@@ -1,6 +1,8 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{EnvRestorer, FileDescEnvironment, RedirectEnvRestorer};
+use conch_runtime::env::{
+    EnvRestorer, FileDescEnvironment, RedirectEnvRestorer, RedirectRestorer, Restorer,
+};
 use conch_runtime::eval::RedirectAction;
 use conch_runtime::io::{FileDesc, Permissions};
 use std::sync::Arc;
@@ -124,3 +126,36 @@ fn restore_on_drop() {
     drop(restorer);
     assert_eq!(env_original, env);
 }
+
+#[test]
+fn redirect_restorer_restores_on_drop_or_explicitly() {
+    type RA = RedirectAction<Arc<FileDesc>>;
+
+    let mut env = MockFileAndVarEnv::new();
+
+    let a = dev_null(&mut env);
+    let x = dev_null(&mut env);
+    let y = dev_null(&mut env);
+
+    env.set_file_desc(1, a, Permissions::Read);
+    let env_original = env.clone();
+
+    {
+        let mut restorer = RedirectRestorer::new(&mut env);
+        RA::Open(1, x, Permissions::Write)
+            .apply(&mut restorer)
+            .unwrap();
+        RA::HereDoc(2, vec![]).apply(&mut restorer).unwrap();
+        assert_ne!(env_original, *restorer.get());
+    }
+    assert_eq!(env_original, env);
+
+    let mut restorer = RedirectRestorer::new(&mut env);
+    RA::Close(1).apply(&mut restorer).unwrap();
+    RA::Open(3, y, Permissions::Read)
+        .apply(&mut restorer)
+        .unwrap();
+    assert_ne!(env_original, *restorer.get());
+    restorer.restore();
+    assert_eq!(env_original, env);
+}
@@ -2,7 +2,7 @@
 
 use conch_runtime::env::{
     EnvRestorer, ExportedVariableEnvironment, Restorer, UnsetVariableEnvironment, VarEnvRestorer,
-    VariableEnvironment,
+    VarRestorer, VariableEnvironment,
 };
 
 mod mock_env;
@@ -148,3 +148,30 @@ fn clear_persists_changes() {
 
     assert_eq!(env, current);
 }
+
+#[test]
+fn var_restorer_restores_on_drop_or_explicitly() {
+    let key_existing = "key_existing";
+    let val_existing = "val_existing";
+    let key_originally_unset = "key_originally_unset";
+
+    let mut env = MockFileAndVarEnv::new();
+    env.set_var(key_existing, val_existing);
+
+    let env_original = env.clone();
+
+    {
+        let mut restorer = VarRestorer::new(&mut env);
+        restorer.set_var(key_existing, "some other value");
+        restorer.set_exported_var(key_originally_unset, "some new value", true);
+        assert_ne!(env_original, *restorer.get());
+    }
+    assert_eq!(env_original, env);
+
+    let mut restorer = VarRestorer::new(&mut env);
+    restorer.unset_var(&key_existing);
+    restorer.set_var(key_originally_unset, "some new value");
+    assert_ne!(env_original, *restorer.get());
+    restorer.restore();
+    assert_eq!(env_original, env);
+}
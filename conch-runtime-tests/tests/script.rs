@@ -0,0 +1,380 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::{ShellOption, ShellOptionsEnvironment};
+use conch_runtime::script::{
+    export_function, import_functions, run_rc_file, run_script, source_script, RcFile, ScriptError,
+    ScriptSource,
+};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn should_run_all_commands_and_return_last_status() {
+    let mut env = new_env();
+    let status = run_script("false; true\nfalse", &mut env).await.unwrap();
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.last_status(), EXIT_ERROR);
+
+    let status = run_script("false\ntrue", &mut env).await.unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn should_stop_on_failure_if_errexit_enabled() {
+    let mut env = new_env();
+    env.set_option(ShellOption::ErrExit, true);
+
+    let status = run_script("true; false; exit 5", &mut env).await.unwrap();
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.last_status(), EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn errexit_should_apply_to_commands_nested_in_compound_commands() {
+    for &script in &[
+        "{ false; exit 5; }; exit 6",
+        "(false; exit 5); exit 6",
+        "f() { false; exit 5; }; f; exit 6",
+        "if true; then false; exit 5; fi; exit 6",
+        "while true; do false; exit 5; done; exit 6",
+        "for x in a; do false; exit 5; done; exit 6",
+        "true && false; exit 6",
+    ] {
+        let mut env = new_env();
+        env.set_option(ShellOption::ErrExit, true);
+
+        let status = run_script(script, &mut env).await.unwrap();
+        assert_eq!(status, EXIT_ERROR, "{}", script);
+    }
+}
+
+#[tokio::test]
+async fn errexit_should_ignore_tested_commands() {
+    for &script in &[
+        "if false; then exit 5; fi; exit 6",
+        "if false; then exit 5; elif false; then exit 5; fi; exit 6",
+        "while false; do exit 5; done; exit 6",
+        "until true; do exit 5; done; exit 6",
+        "false && exit 5; exit 6",
+        "false || false && exit 5; exit 6",
+        "! true; exit 6",
+        "{ false && true; }; exit 6",
+        "if { false; true; }; then exit 6; fi",
+    ] {
+        let mut env = new_env();
+        env.set_option(ShellOption::ErrExit, true);
+
+        let status = run_script(script, &mut env).await.unwrap();
+        assert_eq!(status, ExitStatus::Code(6), "{}", script);
+    }
+}
+
+#[tokio::test]
+async fn should_stop_when_exit_requested() {
+    let mut env = new_env();
+    let status = run_script("true; exit 42; true", &mut env).await.unwrap();
+    assert_eq!(status, ExitStatus::Code(42));
+    assert_eq!(env.last_status(), ExitStatus::Code(42));
+}
+
+#[tokio::test]
+async fn should_only_expand_aliases_written_out_literally() {
+    let mut env = new_env();
+    env.set_expand_aliases(true);
+
+    let status = run_script("alias nope='true '\nnope nope", &mut env).await;
+    assert_eq!(status.unwrap(), EXIT_SUCCESS);
+
+    for &script in &["\\nope", "'nope'", "cmd=nope; $cmd", "\"nope\" nope"] {
+        let status = run_script(script, &mut env).await.unwrap();
+        assert_eq!(status, conch_runtime::EXIT_CMD_NOT_FOUND, "{}", script);
+    }
+}
+
+#[tokio::test]
+async fn should_not_run_anything_if_parsing_fails() {
+    let mut env = new_env();
+    match run_script("exit 42\ntrue )", &mut env).await {
+        Err(ScriptError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert_eq!(env.last_status(), EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn should_run_script_files_relative_to_working_dir() {
+    let tempdir = mktmp!();
+    fs::write(tempdir.path().join("script.sh"), "true\nexit 3\n").unwrap();
+
+    let mut env = new_env();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    let script = ScriptSource::Path(Path::new("script.sh"));
+    let status = run_script(script, &mut env).await.unwrap();
+    assert_eq!(status, ExitStatus::Code(3));
+
+    match run_script(Path::new("missing.sh"), &mut env).await {
+        Err(ScriptError::Io(_, path)) => assert_eq!(path, tempdir.path().join("missing.sh")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn should_track_line_of_each_command() {
+    let mut env = new_env();
+    let status = run_script("true\n\n# a comment\n  true; true # trailing\n", &mut env)
+        .await
+        .unwrap();
+
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.dynamic_var("LINENO"), Some("4".to_owned()));
+
+    run_script("\n\nfalse", &mut env).await.unwrap();
+    assert_eq!(env.dynamic_var("LINENO"), Some("3".to_owned()));
+}
+
+#[tokio::test]
+async fn should_source_scripts_with_their_own_name_and_args() {
+    let name = |name: &str| Arc::new(name.to_owned());
+
+    let tempdir = mktmp!();
+    let script = "inner=\"$0:$#:$*\"; shift; set_later=$1\n";
+    fs::write(tempdir.path().join("lib.sh"), script).unwrap();
+
+    let mut env = new_env();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .unwrap();
+    env.set_name(name("outer"));
+    env.set_args(Arc::new(VecDeque::from(vec![name("a"), name("b")])));
+
+    let args = VecDeque::from(vec![name("x"), name("y"), name("z")]);
+    let status = source_script(Path::new("lib.sh"), Some(args), &mut env)
+        .await
+        .unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let var = |env: &DefaultEnvArc, var: &str| env.var(&name(var)).map(|val| (**val).clone());
+    assert_eq!(var(&env, "inner"), Some("lib.sh:3:x y z".to_owned()));
+    assert_eq!(var(&env, "set_later"), Some("y".to_owned()));
+    assert_eq!(**env.name(), "outer");
+    assert_eq!(env.args(), vec!(name("a"), name("b")));
+
+    // Without any args the caller's positional parameters are shared
+    let status = source_script("inner=\"$0:$*\"; shift", None::<VecDeque<_>>, &mut env)
+        .await
+        .unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(var(&env, "inner"), Some("outer:a b".to_owned()));
+    assert_eq!(**env.name(), "outer");
+    assert_eq!(env.args(), vec!(name("b")));
+}
+
+#[tokio::test]
+async fn should_run_rc_files() {
+    let tempdir = mktmp!();
+    let home = tempdir.path().join("home");
+    fs::create_dir(&home).unwrap();
+    fs::write(tempdir.path().join("custom"), "var=custom\nexit 4\n").unwrap();
+
+    let mut env = new_env();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .unwrap();
+    env.unset_var(&Arc::new("CONCHRC".to_owned()));
+    let var = Arc::new("var".to_owned());
+    let set_var = |env: &mut DefaultEnvArc, name: &str, val: &str| {
+        env.set_var(Arc::new(name.to_owned()), Arc::new(val.to_owned()))
+    };
+
+    // A missing default rc file is skipped
+    set_var(&mut env, "HOME", &home.to_string_lossy());
+    assert_eq!(run_rc_file(&RcFile::Default, &mut env).await.unwrap(), None);
+
+    fs::write(home.join(".conchrc"), "var=default").unwrap();
+    let status = run_rc_file(&RcFile::Default, &mut env).await.unwrap();
+    assert_eq!(status, Some(EXIT_SUCCESS));
+    assert_eq!(env.var(&var).map(|val| &***val), Some("default"));
+
+    set_var(&mut env, "CONCHRC", "custom");
+    let status = run_rc_file(&RcFile::Default, &mut env).await.unwrap();
+    assert_eq!(status, Some(ExitStatus::Code(4)));
+    assert_eq!(env.var(&var).map(|val| &***val), Some("custom"));
+
+    set_var(&mut env, "var", "unchanged");
+    assert_eq!(
+        run_rc_file(&RcFile::Disabled, &mut env).await.unwrap(),
+        None
+    );
+    assert_eq!(env.var(&var).map(|val| &***val), Some("unchanged"));
+
+    // Unlike the default rc file, explicitly requested files must exist
+    match run_rc_file(&RcFile::Path("missing".into()), &mut env).await {
+        Err(ScriptError::Io(_, path)) => assert_eq!(path, tempdir.path().join("missing")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn should_export_and_import_functions() {
+    let name = |name: &str| Arc::new(name.to_owned());
+
+    let mut parent = new_env();
+    run_script("greet() { greeting=\"hello $1\"; }", &mut parent)
+        .await
+        .unwrap();
+    assert!(export_function(&name("greet"), &mut parent));
+    assert!(!export_function(&name("missing"), &mut parent));
+
+    let (def, exported) = parent
+        .exported_var(&name("CONCH_FUNC_greet"))
+        .expect("function not exported");
+    assert!(exported);
+
+    let mut child = new_env();
+    child.set_exported_var(name("CONCH_FUNC_greet"), def.clone(), true);
+    child.set_exported_var(name("CONCH_FUNC_evil"), name("{ :; }; pwned=yes"), true);
+    child.set_exported_var(name("CONCH_FUNC_"), name("{ :; }"), true);
+    child.set_var(name("CONCH_FUNC_local"), name("{ :; }"));
+
+    assert_eq!(import_functions(&mut child).await, vec!("greet"));
+    assert!(!child.has_function(&name("evil")));
+    assert!(!child.has_function(&name("local")));
+    assert_eq!(child.var(&name("pwned")), None);
+
+    // Imported functions can be re-exported to grandchildren
+    child.unset_var(&name("CONCH_FUNC_greet"));
+    assert!(export_function(&name("greet"), &mut child));
+    let (redef, _) = child.exported_var(&name("CONCH_FUNC_greet")).unwrap();
+    assert_eq!(redef, def);
+
+    run_script("greet world", &mut child).await.unwrap();
+    let greeting = child.var(&name("greeting")).map(|val| &***val);
+    assert_eq!(greeting, Some("hello world"));
+}
+
+#[tokio::test]
+async fn should_fail_invalid_assignments_to_readonly_and_integer_vars() {
+    // NB: Suppress error dumping to console
+    for &(script, var, value) in &[
+        ("declare -r x=1; x=2; exit $?", "x", "1"),
+        ("declare -r x=1; x=2 true; exit $?", "x", "1"),
+        ("declare -i y=1; y='1 / 0'; exit $?", "y", "1"),
+        ("declare -i y=1; y='1 +'; exit $?", "y", "1"),
+    ] {
+        let mut env = new_env_with_no_fds();
+        let status = run_script(script, &mut env).await.unwrap();
+        assert_eq!(status, EXIT_ERROR, "{}", script);
+
+        let val = env.var(&Arc::new(var.to_owned())).map(|v| (**v).clone());
+        assert_eq!(val.as_deref(), Some(value), "{}", script);
+    }
+}
+
+#[tokio::test]
+async fn assignments_to_dynamic_vars_should_reset_them() {
+    let mut env = new_env();
+    let script = "SECONDS=100; s=$SECONDS; RANDOM=42; a=$RANDOM; b=$(( RANDOM ))";
+    let status = run_script(script, &mut env).await.unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let var = |name: &str| env.var(&Arc::new(name.to_owned())).map(|v| (**v).clone());
+    let mut rng = RandomEnv::with_seed(42);
+    assert!(matches!(var("s").as_deref(), Some("100") | Some("101")));
+    assert_eq!(var("a"), Some(rng.next_random().to_string()));
+    assert_eq!(var("b"), Some(rng.next_random().to_string()));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn executables_should_inherit_open_file_descriptors() {
+    let tempdir = mktmp!();
+    let dir = tempdir.path().to_str().expect("non-utf8 tempdir");
+
+    let script = format!(
+        "/bin/sh -c 'echo redirect >&3' 3>{dir}/redirect; \
+         exec 4>{dir}/exec; /bin/sh -c 'echo exec >&4'",
+        dir = dir
+    );
+
+    let mut env = new_env_with_no_fds();
+    let status = run_script(script.as_str(), &mut env).await.unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+    drop(env);
+
+    let read = |name: &str| fs::read_to_string(tempdir.path().join(name)).unwrap();
+    assert_eq!(read("redirect"), "redirect\n");
+    assert_eq!(read("exec"), "exec\n");
+}
+
+#[tokio::test]
+async fn should_expand_pathname_patterns_in_words() {
+    let name = |name: &str| Arc::new(name.to_owned());
+
+    let tempdir = mktmp!();
+    fs::create_dir_all(tempdir.path().join("sub/deep")).unwrap();
+    for file in &["b.rs", "a.rs", "sub/deep/c.rs"] {
+        fs::write(tempdir.path().join(file), "").unwrap();
+    }
+
+    let mut env = new_env();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    let script = "set -- *.rs; all=\"$*\"\n\
+                  pat='*.rs'; set -- $pat; unquoted=\"$*\"\n\
+                  set -- \"*\".rs '*'.rs \\*.rs; quoted=\"$*\"\n\
+                  for f in *.rs; do looped=\"$looped$f\"; done\n\
+                  set -- /nonexistent*zzz; literal=\"$*\"\n\
+                  set -o nullglob; set -- /nonexistent*zzz; null=\"$#\"\n\
+                  set -- **/*/*.rs; star=\"$*\"\n\
+                  set -o globstar; set -- **/*.rs; globstar=\"$*\"\n\
+                  set -o failglob; set -- /nonexistent*zzz; failed=\"$?:$#\"\n";
+    let status = run_script(script, &mut env).await.unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let var = |var: &str| env.var(&name(var)).map(|val| (**val).clone());
+    assert_eq!(var("all").as_deref(), Some("a.rs b.rs"));
+    assert_eq!(var("unquoted").as_deref(), Some("a.rs b.rs"));
+    assert_eq!(var("quoted").as_deref(), Some("*.rs *.rs *.rs"));
+    assert_eq!(var("looped").as_deref(), Some("a.rsb.rs"));
+    assert_eq!(var("literal").as_deref(), Some("/nonexistent*zzz"));
+    assert_eq!(var("null").as_deref(), Some("0"));
+    assert_eq!(var("star").as_deref(), Some("sub/deep/c.rs"));
+    assert_eq!(var("globstar").as_deref(), Some("a.rs b.rs sub/deep/c.rs"));
+    assert_eq!(var("failed").as_deref(), Some("1:3"));
+}
+
+#[tokio::test]
+async fn function_bodies_should_not_count_as_nested_commands() {
+    let name = |name: &str| Arc::new(name.to_owned());
+
+    let mut env = new_env_with_no_fds();
+    env.set_max_nesting_depth(Some(2));
+
+    // Each call only runs an and-or list within the body, thus functions
+    // may recurse deeper than the maximum nesting depth
+    let args = (0..50).map(|i| i.to_string()).collect::<Vec<_>>();
+    let script = format!("f() {{ shift && f \"$@\"; }}; f {}", args.join(" "));
+    let status = run_script(script.as_str(), &mut env).await.unwrap();
+    assert_eq!(status, EXIT_ERROR);
+
+    // Although any commands nested within the body still count
+    let script = "g() { { { :; }; }; }; g; ok=$?\n\
+                  h() { { { { :; }; }; }; }; h; failed=$?";
+    let status = run_script(script, &mut env).await.unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let var = |var: &str| env.var(&name(var)).map(|val| (**val).clone());
+    assert_eq!(var("ok").as_deref(), Some("0"));
+    assert_eq!(var("failed").as_deref(), Some("1"));
+    assert_eq!(env.nesting_depth(), 0);
+    assert_eq!(env.max_nesting_depth(), Some(2));
+}
@@ -0,0 +1,135 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::script::{RcFile, ScriptError};
+use conch_runtime::shell::Shell;
+use std::fs;
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+/// Creates a shell which ignores any `$CONCHRC` or `~/.conchrc` on the host.
+fn new_shell() -> Shell {
+    Shell::with_rc_file(&RcFile::Disabled).unwrap()
+}
+
+#[test]
+fn should_run_source_and_track_last_status() {
+    let mut shell = new_shell();
+    assert_eq!(shell.last_status(), EXIT_SUCCESS);
+
+    assert_eq!(shell.run_str("true; false").unwrap(), EXIT_ERROR);
+    assert_eq!(shell.last_status(), EXIT_ERROR);
+
+    assert_eq!(shell.run_str("exit 7").unwrap(), ExitStatus::Code(7));
+    assert_eq!(shell.last_status(), ExitStatus::Code(7));
+
+    match shell.run_str("true )") {
+        Err(ScriptError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn should_run_files() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("script.sh");
+    fs::write(&path, "true\nexit 3\n").unwrap();
+
+    let mut shell = new_shell();
+    assert_eq!(shell.run_file(&path).unwrap(), ExitStatus::Code(3));
+}
+
+#[test]
+fn should_run_rc_file_defining_defaults() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("rc");
+    fs::write(
+        &path,
+        "alias greet='echo_nine'\necho_nine() { exit 9; }\nset -o errexit\n",
+    )
+    .unwrap();
+
+    let mut shell = new_shell();
+    let status = shell.run_rc_file(&RcFile::Path(path)).unwrap();
+    assert_eq!(status, Some(EXIT_SUCCESS));
+
+    assert_eq!(shell.env().alias("greet"), Some("echo_nine"));
+    assert!(shell.env().is_option_enabled(ShellOption::ErrExit));
+    assert_eq!(shell.run_str("echo_nine").unwrap(), ExitStatus::Code(9));
+
+    assert_eq!(shell.run_rc_file(&RcFile::Disabled).unwrap(), None);
+}
+
+#[test]
+fn should_run_rc_file_at_startup() {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("rc");
+    fs::write(
+        &path,
+        "greet() { exit 4; }
+",
+    )
+    .unwrap();
+
+    let mut shell = Shell::with_rc_file(&RcFile::Path(path)).unwrap();
+    assert_eq!(shell.last_status(), EXIT_SUCCESS);
+    assert_eq!(shell.run_str("greet").unwrap(), ExitStatus::Code(4));
+
+    let missing = tempdir.path().join("missing");
+    let shell = Shell::with_rc_file(&RcFile::Path(missing)).unwrap();
+    assert_eq!(shell.last_status(), EXIT_ERROR);
+}
+
+#[test]
+fn should_get_and_set_vars() {
+    let mut shell = new_shell();
+    assert_eq!(shell.get_var("conch_shell_test_var"), None);
+
+    shell.set_var("conch_shell_test_var", "foo");
+    assert_eq!(shell.get_var("conch_shell_test_var"), Some("foo"));
+
+    shell.run_str("unset conch_shell_test_var").unwrap();
+    assert_eq!(shell.get_var("conch_shell_test_var"), None);
+}
+
+#[test]
+fn should_define_functions() {
+    let mut shell = new_shell();
+    shell.define_fn("exit_nine", "true; exit 9").unwrap();
+
+    assert_eq!(
+        shell.run_str("exit_nine; true").unwrap(),
+        ExitStatus::Code(9)
+    );
+
+    match shell.define_fn("broken", "true )") {
+        Err(ScriptError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn should_export_functions_to_child_shells() {
+    let mut parent = new_shell();
+    parent.define_fn("exit_seven", "exit 7").unwrap();
+    parent.run_str("exit_eight() { exit 8; }").unwrap();
+    assert!(parent.export_fn("exit_seven"));
+    assert!(parent.export_fn("exit_eight"));
+    assert!(!parent.export_fn("missing"));
+
+    let mut child = new_shell();
+    for name in &["CONCH_FUNC_exit_seven", "CONCH_FUNC_exit_eight"] {
+        let def = parent.get_var(name).expect("function not exported");
+        child.env_mut().set_exported_var(
+            Arc::new((*name).to_owned()),
+            Arc::new(def.to_owned()),
+            true,
+        );
+    }
+
+    assert_eq!(child.import_fns(), vec!("exit_eight", "exit_seven"));
+    assert_eq!(child.run_str("exit_seven").unwrap(), ExitStatus::Code(7));
+    assert_eq!(child.run_str("exit_eight").unwrap(), ExitStatus::Code(8));
+}
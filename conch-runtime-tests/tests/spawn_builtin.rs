@@ -62,6 +62,33 @@ where
     }
 }
 
+#[tokio::test]
+async fn builtin_smoke_alias() {
+    let output = run_builtin("alias", &["ll=ls -l"]).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.alias("ll"), Some("ls -l"));
+}
+
+#[tokio::test]
+async fn builtin_smoke_bg() {
+    let output = run_builtin_with_prep("bg", &[], |env| {
+        let id = env.add_job("sleep 10".to_owned(), None, Box::pin(pending()));
+        env.set_job_stopped(id, true);
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "[1]+ sleep 10 &\n");
+    assert_eq!(output.env.job(1).unwrap().state(), JobState::Running);
+}
+
+#[tokio::test]
+async fn builtin_smoke_break() {
+    let output = run_builtin_with_prep("break", &[], |env| env.push_loop_frame()).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+}
+
 #[tokio::test]
 async fn builtin_smoke_cd() {
     let temp = mktmp!();
@@ -80,6 +107,31 @@ async fn builtin_smoke_colon() {
     assert_eq!(output.out, "");
 }
 
+#[tokio::test]
+async fn builtin_smoke_continue() {
+    let output = run_builtin_with_prep("continue", &[], |env| env.push_loop_frame()).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+}
+
+#[tokio::test]
+async fn builtin_smoke_declare() {
+    let output = run_builtin("typeset", &["-i", "var=1 + 2"]).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.var(&rc("var")), Some(&rc("3")));
+}
+
+#[tokio::test]
+async fn builtin_smoke_dirs() {
+    let output = run_builtin("dirs", &["-l"]).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(
+        output.out,
+        format!("{}\n", output.env.current_working_dir().display())
+    );
+}
+
 #[tokio::test]
 async fn builtin_smoke_echo() {
     let output = run_builtin("echo", &["foo", "bar"]).await;
@@ -87,6 +139,13 @@ async fn builtin_smoke_echo() {
     assert_eq!(output.out, "foo bar\n");
 }
 
+#[tokio::test]
+async fn builtin_smoke_exit() {
+    let output = run_builtin("exit", &["5"]).await;
+    assert_eq!(output.exit, ExitStatus::Code(5));
+    assert_eq!(output.out, "");
+}
+
 #[tokio::test]
 async fn builtin_smoke_false() {
     let output = run_builtin("false", &[]).await;
@@ -94,6 +153,63 @@ async fn builtin_smoke_false() {
     assert_eq!(output.out, "");
 }
 
+#[tokio::test]
+async fn builtin_smoke_fg() {
+    let output = run_builtin_with_prep("fg", &[], |env| {
+        env.add_job("foo".to_owned(), None, Box::pin(ready(ExitStatus::Code(3))));
+    })
+    .await;
+    assert_eq!(output.exit, ExitStatus::Code(3));
+    assert_eq!(output.out, "foo\n");
+    assert!(output.env.jobs().is_empty());
+}
+
+#[tokio::test]
+async fn builtin_smoke_jobs() {
+    let output = run_builtin_with_prep("jobs", &[], |env| {
+        env.add_job("sleep 10".to_owned(), None, Box::pin(pending()));
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "[1]+  Running                 sleep 10\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn builtin_smoke_kill() {
+    let output = run_builtin("kill", &["-l", "9"]).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "KILL\n");
+}
+
+#[tokio::test]
+async fn builtin_smoke_let() {
+    let output = run_builtin("let", &["var = 2 * 3", "var - 6"]).await;
+    assert_eq!(output.exit, EXIT_ERROR);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.var(&rc("var")), Some(&rc("6")));
+}
+
+#[tokio::test]
+async fn builtin_smoke_popd() {
+    let output = run_builtin_with_prep("popd", &[], |env| {
+        env.push_dir(env.current_working_dir().to_path_buf())
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert!(output.env.dir_stack().is_empty());
+}
+
+#[tokio::test]
+async fn builtin_smoke_pushd() {
+    let output = run_builtin_with_prep("pushd", &[], |env| {
+        env.push_dir(env.current_working_dir().to_path_buf())
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.env.dir_stack().len(), 1);
+}
+
 #[tokio::test]
 async fn builtin_smoke_pwd() {
     let output = run_builtin("pwd", &[]).await;
@@ -104,6 +220,22 @@ async fn builtin_smoke_pwd() {
     );
 }
 
+#[tokio::test]
+async fn builtin_smoke_return() {
+    let output = run_builtin_with_prep("return", &["5"], |env| env.push_fn_frame()).await;
+    assert_eq!(output.exit, ExitStatus::Code(5));
+    assert_eq!(output.out, "");
+}
+
+#[tokio::test]
+async fn builtin_smoke_set() {
+    let output = run_builtin("set", &["-e", "--", "foo"]).await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert!(output.env.is_option_enabled(ShellOption::ErrExit));
+    assert_eq!(output.env.args(), vec![rc("foo")]);
+}
+
 #[tokio::test]
 async fn builtin_smoke_shift() {
     let mut args = vec![
@@ -131,3 +263,47 @@ async fn builtin_smoke_true() {
     assert_eq!(output.exit, EXIT_SUCCESS);
     assert_eq!(output.out, "");
 }
+
+#[tokio::test]
+async fn builtin_smoke_unalias() {
+    let output = run_builtin_with_prep("unalias", &["ll"], |env| {
+        env.set_alias("ll".to_owned(), "ls -l".to_owned())
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.alias("ll"), None);
+}
+
+#[tokio::test]
+async fn builtin_smoke_unset() {
+    let output = run_builtin_with_prep("unset", &["foo"], |env| {
+        env.set_var(rc("foo"), rc("bar"));
+    })
+    .await;
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.var(&rc("foo")), None);
+}
+
+#[test]
+fn only_trivial_builtins_report_a_trivial_status() {
+    type Restorer<'a> = EnvRestorer<'a, DefaultEnvArc>;
+
+    let env = new_env_with_no_fds();
+    let trivial_status = |name: &str| {
+        let builtin = env
+            .builtin(&rc(name))
+            .unwrap_or_else(|| panic!("did not find builtin for `{}`", name));
+
+        <Builtin as BuiltinUtility<'_, Vec<Arc<String>>, Restorer<'_>, DefaultEnvArc>>::trivial_status(
+            &builtin,
+        )
+    };
+
+    assert_eq!(trivial_status(":"), Some(EXIT_SUCCESS));
+    assert_eq!(trivial_status("true"), Some(EXIT_SUCCESS));
+    assert_eq!(trivial_status("false"), Some(EXIT_ERROR));
+    assert_eq!(trivial_status("echo"), None);
+    assert_eq!(trivial_status("exit"), None);
+}
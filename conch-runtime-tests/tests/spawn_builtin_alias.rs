@@ -0,0 +1,106 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::STDOUT_FILENO;
+use futures_util::future::join;
+
+mod support;
+pub use self::support::spawn::builtin::{alias, unalias};
+pub use self::support::*;
+
+fn owned(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&s| s.to_owned()).collect()
+}
+
+async fn run_alias(env: &mut DefaultEnvArc, args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDOUT_FILENO, pipe.writer, Permissions::Write);
+
+    let read_to_end = env.read_all(pipe.reader);
+
+    let future = alias(owned(args), env).await;
+    env.close_file_desc(STDOUT_FILENO);
+
+    let (status, out) = join(future, read_to_end).await;
+    let out = String::from_utf8(out.unwrap()).expect("out invalid utf8");
+
+    (status, out)
+}
+
+#[tokio::test]
+async fn alias_defines_and_prints_aliases() {
+    let mut env = new_env_with_no_fds();
+
+    let (status, out) = run_alias(&mut env, &["ll=ls -l", "quote=echo 'hi'"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+    assert_eq!(env.alias("ll"), Some("ls -l"));
+
+    let (status, out) = run_alias(&mut env, &["ll"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "alias ll='ls -l'\n");
+
+    let expected = "alias ll='ls -l'\nalias quote='echo '\\''hi'\\'''\n";
+
+    let (status, out) = run_alias(&mut env, &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, expected);
+
+    let (status, out) = run_alias(&mut env, &["-p"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, expected);
+}
+
+#[tokio::test]
+async fn alias_reports_undefined_aliases() {
+    // NB: Suppress error dumping to console
+    let mut env = new_env_with_no_fds();
+    env.set_alias("ll".to_owned(), "ls -l".to_owned());
+
+    let (status, out) = run_alias(&mut env, &["missing", "ll"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "alias ll='ls -l'\n");
+}
+
+#[tokio::test]
+async fn unalias_removes_aliases() {
+    let mut env = new_env_with_no_fds();
+    env.set_alias("a".to_owned(), "1".to_owned());
+    env.set_alias("b".to_owned(), "2".to_owned());
+    env.set_alias("c".to_owned(), "3".to_owned());
+
+    let status = unalias(owned(&["a", "b"]), &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.aliases(), vec![("c", "3")]);
+
+    let status = unalias(owned(&["-a"]), &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.aliases(), vec![]);
+}
+
+#[tokio::test]
+async fn unalias_reports_undefined_aliases() {
+    // NB: Suppress error dumping to console
+    let mut env = new_env_with_no_fds();
+    env.set_alias("a".to_owned(), "1".to_owned());
+
+    let status = unalias(owned(&["missing", "a"]), &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.aliases(), vec![]);
+
+    let status = unalias(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn aliases_are_inherited_by_sub_envs() {
+    let mut env = new_env_with_no_fds();
+    env.set_alias("a".to_owned(), "1".to_owned());
+
+    let mut sub = env.sub_env();
+    assert_eq!(sub.alias("a"), Some("1"));
+
+    sub.unalias_all();
+    assert_eq!(sub.alias("a"), None);
+    assert_eq!(env.alias("a"), Some("1"));
+}
@@ -19,6 +19,7 @@ pub use self::support::*;
 struct CdResult {
     initial_cwd: PathBuf,
     final_cwd: PathBuf,
+    final_oldpwd: Option<String>,
     out: String,
     err: String,
     status: ExitStatus,
@@ -69,9 +70,14 @@ where
     let pwd = env.var(&String::from("PWD")).expect("unset PWD");
     assert_eq!(final_cwd.to_string_lossy(), &***pwd);
 
+    let final_oldpwd = env
+        .var(&String::from("OLDPWD"))
+        .map(|oldpwd| oldpwd.to_string());
+
     CdResult {
         initial_cwd,
         final_cwd,
+        final_oldpwd,
         out: String::from_utf8(out).expect("out invalid utf8"),
         err: String::from_utf8(err).expect("err invalid utf8"),
         status: exit,
@@ -299,6 +305,52 @@ async fn nulls_in_cdargs_treated_as_current_directory() {
     assert_eq!(result.err, "");
 }
 
+#[tokio::test]
+async fn dash_arg_swaps_pwd_and_oldpwd() {
+    let tempdir = mktmp!();
+    let old_dir = tempdir.path().to_string_lossy().into_owned();
+
+    let mut initial_pwd = None;
+    let result = run_cd(&["-"], |env| {
+        env.set_var("OLDPWD".to_owned().into(), old_dir.clone().into());
+        let pwd = env.current_working_dir().to_string_lossy().into_owned();
+        env.set_var("PWD".to_owned().into(), pwd.clone().into());
+        initial_pwd = Some(pwd);
+    })
+    .await;
+
+    assert_eq!(result.status, EXIT_SUCCESS);
+    assert_eq!(result.final_cwd, tempdir.path());
+    assert_eq!(result.out, format!("{}\n", old_dir));
+    assert_eq!(result.err, "");
+    assert_eq!(
+        Some(result.initial_cwd.to_string_lossy().into_owned()),
+        initial_pwd
+    );
+    assert_eq!(result.final_oldpwd, initial_pwd);
+}
+
+#[tokio::test]
+async fn physical_with_cdpath_resolves_symlinks() {
+    let tempdir = mktmp!();
+    let sym_foo = make_symlink_and_get_sym_input(&tempdir);
+    let expected = sym_foo.canonicalize().expect("canonicalize failed");
+
+    let sym = sym_foo.parent().expect("no parent").to_path_buf();
+    let result = run_cd(&["-P", "foo"], |env| {
+        env.set_var(
+            "CDPATH".to_owned().into(),
+            sym.to_string_lossy().into_owned().into(),
+        );
+    })
+    .await;
+
+    assert_eq!(result.status, EXIT_SUCCESS);
+    assert_eq!(result.final_cwd, expected);
+    assert_eq!(result.out, format!("{}\n", expected.to_string_lossy()));
+    assert_eq!(result.err, "");
+}
+
 #[tokio::test]
 async fn dash_unset_old_pwd_is_error() {
     let result = run_cd(&["-"], |env| {
@@ -0,0 +1,158 @@
+#![deny(rust_2018_idioms)]
+
+mod support;
+pub use self::support::spawn::builtin::{break_cmd, continue_cmd, exit, return_cmd};
+pub use self::support::*;
+
+#[tokio::test]
+async fn exit_requests_control_flow_with_status() {
+    let mut env = new_env_with_no_fds();
+
+    let status = exit(vec!["5".to_owned()], &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(5));
+    assert_eq!(
+        env.take_control_flow(),
+        Some(ControlFlow::Exit(ExitStatus::Code(5)))
+    );
+}
+
+#[tokio::test]
+async fn exit_status_is_truncated_to_eight_bits() {
+    let mut env = new_env_with_no_fds();
+
+    let status = exit(vec!["258".to_owned()], &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(2));
+
+    let status = exit(vec!["-1".to_owned()], &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(255));
+}
+
+#[tokio::test]
+async fn exit_without_args_uses_last_status() {
+    let mut env = new_env_with_no_fds();
+    env.set_last_status(ExitStatus::Code(42));
+
+    let status = exit(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(42));
+    assert_eq!(
+        env.take_control_flow(),
+        Some(ControlFlow::Exit(ExitStatus::Code(42)))
+    );
+}
+
+#[tokio::test]
+async fn exit_with_non_numeric_arg_is_an_error() {
+    // NB: Suppress usage dumping errors to console
+    let mut env = new_env_with_no_fds();
+
+    let status = exit(vec!["foo".to_owned()], &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.take_control_flow(), None);
+}
+
+#[tokio::test]
+async fn return_requests_control_flow_inside_function() {
+    let mut env = new_env_with_no_fds();
+    env.push_fn_frame();
+
+    let status = return_cmd(vec!["3".to_owned()], &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(3));
+    assert_eq!(
+        env.take_control_flow(),
+        Some(ControlFlow::Return(ExitStatus::Code(3)))
+    );
+}
+
+#[tokio::test]
+async fn return_outside_function_is_an_error() {
+    // NB: Suppress usage dumping errors to console
+    let mut env = new_env_with_no_fds();
+
+    let status = return_cmd(vec!["3".to_owned()], &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.take_control_flow(), None);
+}
+
+#[tokio::test]
+async fn control_flow_requests_are_not_inherited_by_sub_envs() {
+    let mut env = new_env_with_no_fds();
+    env.request_control_flow(ControlFlow::Exit(EXIT_SUCCESS));
+
+    assert_eq!(env.sub_env().take_control_flow(), None);
+    assert_eq!(
+        env.take_control_flow(),
+        Some(ControlFlow::Exit(EXIT_SUCCESS))
+    );
+}
+
+#[tokio::test]
+async fn break_and_continue_request_control_flow_inside_loops() {
+    let mut env = new_env_with_no_fds();
+    env.push_loop_frame();
+    env.push_loop_frame();
+
+    let status = break_cmd(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.take_control_flow(), Some(ControlFlow::Break(1)));
+
+    let status = continue_cmd(vec!["2".to_owned()], &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.take_control_flow(), Some(ControlFlow::Continue(2)));
+}
+
+#[tokio::test]
+async fn break_and_continue_clamp_loop_count_to_enclosing_loops() {
+    let mut env = new_env_with_no_fds();
+    env.push_loop_frame();
+    env.push_loop_frame();
+
+    let status = break_cmd(vec!["5".to_owned()], &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.take_control_flow(), Some(ControlFlow::Break(2)));
+
+    let status = continue_cmd(vec!["5".to_owned()], &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.take_control_flow(), Some(ControlFlow::Continue(2)));
+}
+
+#[tokio::test]
+async fn break_and_continue_with_invalid_loop_count_are_errors() {
+    // NB: Suppress usage dumping errors to console
+    let mut env = new_env_with_no_fds();
+    env.push_loop_frame();
+
+    for &arg in &["0", "-1", "foo"] {
+        let status = break_cmd(vec![arg.to_owned()], &mut env).await.await;
+        assert_eq!(status, EXIT_ERROR);
+
+        let status = continue_cmd(vec![arg.to_owned()], &mut env).await.await;
+        assert_eq!(status, EXIT_ERROR);
+    }
+
+    assert_eq!(env.take_control_flow(), None);
+}
+
+#[tokio::test]
+async fn break_and_continue_outside_loops_are_errors() {
+    // NB: Suppress usage dumping errors to console
+    let mut env = new_env_with_no_fds();
+
+    let status = break_cmd(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+
+    let status = continue_cmd(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+
+    assert_eq!(env.take_control_flow(), None);
+}
+
+#[tokio::test]
+async fn loop_depth_is_inherited_by_sub_envs() {
+    let mut env = new_env_with_no_fds();
+    env.push_loop_frame();
+
+    assert_eq!(env.sub_env().loop_depth(), 1);
+
+    env.pop_loop_frame();
+    assert_eq!(env.loop_depth(), 0);
+}
@@ -0,0 +1,154 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::STDOUT_FILENO;
+use futures_util::future::join;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::spawn::builtin::declare;
+pub use self::support::*;
+
+fn owned(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&s| s.to_owned()).collect()
+}
+
+fn var(env: &DefaultEnvArc, name: &str) -> Option<String> {
+    env.var(&name.to_owned()).map(|val| (**val).clone())
+}
+
+async fn run_declare(env: &mut DefaultEnvArc, args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDOUT_FILENO, pipe.writer, Permissions::Write);
+
+    let read_to_end = env.read_all(pipe.reader);
+
+    let future = declare(owned(args), env).await;
+    env.close_file_desc(STDOUT_FILENO);
+
+    let (status, out) = join(future, read_to_end).await;
+    let out = String::from_utf8(out.unwrap()).expect("out invalid utf8");
+
+    (status, out)
+}
+
+#[tokio::test]
+async fn declare_assigns_and_prints_variables() {
+    let mut env = new_env_with_no_fds();
+
+    let (status, out) = run_declare(&mut env, &["-x", "foo=a \"quoted\" $value", "bar"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+    assert_eq!(var(&env, "foo").as_deref(), Some("a \"quoted\" $value"));
+    assert_eq!(var(&env, "bar"), None);
+
+    let (status, out) = run_declare(&mut env, &["-p", "foo"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "declare -x foo=\"a \\\"quoted\\\" \\$value\"\n");
+
+    let (status, out) = run_declare(&mut env, &["+x", "foo"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+
+    let (status, out) = run_declare(&mut env, &["-p", "foo", "missing"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "declare -- foo=\"a \\\"quoted\\\" \\$value\"\n");
+}
+
+#[tokio::test]
+async fn declare_integer_variables_evaluate_assignments() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(Arc::new("y".to_owned()), Arc::new("4".to_owned()));
+
+    let (status, _) = run_declare(&mut env, &["-i", "x=y * (2 + 1) - 5"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(var(&env, "x").as_deref(), Some("7"));
+
+    let (status, _) = run_declare(&mut env, &["x=x + 1"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(var(&env, "x").as_deref(), Some("8"));
+
+    // Invalid expressions are reported and leave the variable untouched
+    let (status, _) = run_declare(&mut env, &["x=1 +"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(var(&env, "x").as_deref(), Some("8"));
+
+    let (status, _) = run_declare(&mut env, &["+i", "x=1 + 1"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(var(&env, "x").as_deref(), Some("1 + 1"));
+}
+
+#[tokio::test]
+async fn declare_readonly_variables_cannot_be_changed() {
+    // NB: Suppress error dumping to console
+    let mut env = new_env_with_no_fds();
+    let name = Arc::new("ro".to_owned());
+
+    let (status, _) = run_declare(&mut env, &["-r", "ro=value"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    env.set_var(name.clone(), Arc::new("changed".to_owned()));
+    env.unset_var(&name);
+    assert_eq!(var(&env, "ro").as_deref(), Some("value"));
+
+    let (status, _) = run_declare(&mut env, &["ro=changed"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(var(&env, "ro").as_deref(), Some("value"));
+
+    let (status, out) = run_declare(&mut env, &["-x", "ro"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+
+    let (status, out) = run_declare(&mut env, &["-r"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "declare -rx ro=\"value\"\n");
+}
+
+#[tokio::test]
+async fn declare_reports_invalid_options_and_names() {
+    // NB: Suppress error dumping to console
+    let mut env = new_env_with_no_fds();
+
+    for args in &[&["-z", "x"][..], &["1x=2"]] {
+        let (status, out) = run_declare(&mut env, args).await;
+        assert_eq!(status, EXIT_ERROR);
+        assert_eq!(out, "");
+    }
+}
+
+#[tokio::test]
+async fn declare_array_attributes_cannot_be_converted_or_removed() {
+    // NB: Suppress error dumping to console
+    let mut env = new_env_with_no_fds();
+
+    let (status, out) = run_declare(&mut env, &["-aA", "arr=value"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "");
+    assert_eq!(var(&env, "arr"), None);
+
+    let (status, _) = run_declare(&mut env, &["-a", "arr=value"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(var(&env, "arr").as_deref(), Some("value"));
+
+    let (status, out) = run_declare(&mut env, &["-A", "map=value"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+
+    let (status, out) = run_declare(&mut env, &["-p", "arr", "map"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(
+        out,
+        "declare -a arr=([0]=\"value\")\ndeclare -A map=([0]=\"value\")\n"
+    );
+
+    for args in &[
+        &["-A", "arr"][..],
+        &["-a", "map"],
+        &["+a", "arr"],
+        &["+A", "map"],
+    ] {
+        let (status, out) = run_declare(&mut env, args).await;
+        assert_eq!(status, EXIT_ERROR);
+        assert_eq!(out, "");
+    }
+}
@@ -0,0 +1,211 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::env::builtin::*;
+pub use self::support::*;
+
+fn rc(s: &str) -> Arc<String> {
+    Arc::new(s.to_owned())
+}
+
+async fn run(env: &mut DefaultEnvArc, name: &str, args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let args = args.iter().map(|&arg| rc(arg)).collect::<Vec<_>>();
+    let builtin = env.builtin(&rc(name)).expect("missing builtin");
+    let status = builtin
+        .spawn_builtin(args, &mut EnvRestorer::new(&mut *env))
+        .await
+        .await;
+    env.close_file_desc(conch_runtime::STDOUT_FILENO);
+
+    let out = env.read_all(pipe.reader).await.expect("failed to read");
+    (status, String::from_utf8(out).expect("out invalid utf8"))
+}
+
+/// Creates an environment whose working directory is `root`, which contains
+/// the directories `a`, `b`, and `c`.
+fn new_env_in(root: &Path) -> DefaultEnvArc {
+    for dir in &["a", "b", "c"] {
+        fs::create_dir(root.join(dir)).expect("failed to create dir");
+    }
+
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(Cow::Borrowed(root))
+        .expect("failed to change dir");
+    env.set_var(rc("HOME"), rc(&root.to_string_lossy()));
+    env
+}
+
+fn cwd(env: &DefaultEnvArc) -> PathBuf {
+    env.current_working_dir().to_path_buf()
+}
+
+#[tokio::test]
+async fn pushd_and_popd_change_directories() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let mut env = new_env_in(root);
+
+    let (status, out) = run(&mut env, "pushd", &["a"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~/a ~\n");
+    assert_eq!(cwd(&env), root.join("a"));
+    assert_eq!(***env.var(&rc("OLDPWD")).unwrap(), *root.to_string_lossy());
+
+    let (status, out) = run(&mut env, "pushd", &["../b"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~/b ~/a ~\n");
+    assert_eq!(cwd(&env), root.join("b"));
+
+    let (status, out) = run(&mut env, "popd", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~/a ~\n");
+    assert_eq!(cwd(&env), root.join("a"));
+
+    let (status, out) = run(&mut env, "popd", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~\n");
+    assert_eq!(cwd(&env), root);
+
+    let (status, out) = run(&mut env, "popd", &[]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "");
+}
+
+#[tokio::test]
+async fn pushd_without_arguments_swaps_top_directories() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let mut env = new_env_in(root);
+
+    let (status, _) = run(&mut env, "pushd", &[]).await;
+    assert_eq!(status, EXIT_ERROR);
+
+    run(&mut env, "pushd", &["a"]).await;
+    let (status, out) = run(&mut env, "pushd", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a\n");
+    assert_eq!(cwd(&env), root);
+}
+
+#[tokio::test]
+async fn pushd_rotates_stack() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let mut env = new_env_in(root);
+
+    run(&mut env, "pushd", &["c"]).await;
+    run(&mut env, "pushd", &["../b"]).await;
+    run(&mut env, "pushd", &["../a"]).await;
+
+    let (status, out) = run(&mut env, "pushd", &["+2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~/c ~ ~/a ~/b\n");
+    assert_eq!(cwd(&env), root.join("c"));
+
+    let (status, out) = run(&mut env, "pushd", &["-0"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~/b ~/c ~ ~/a\n");
+    assert_eq!(cwd(&env), root.join("b"));
+
+    let (status, out) = run(&mut env, "pushd", &["+4"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "");
+    assert_eq!(cwd(&env), root.join("b"));
+}
+
+#[tokio::test]
+async fn pushd_and_popd_without_changing_directories() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let mut env = new_env_in(root);
+
+    let (status, out) = run(&mut env, "pushd", &["-n", "a"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a\n");
+
+    run(&mut env, "pushd", &["-n", "b"]).await;
+    let (status, out) = run(&mut env, "popd", &["-n"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a\n");
+    assert_eq!(cwd(&env), root);
+}
+
+#[tokio::test]
+async fn popd_removes_specific_entries() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let mut env = new_env_in(root);
+
+    run(&mut env, "pushd", &["-n", "c"]).await;
+    run(&mut env, "pushd", &["-n", "b"]).await;
+    run(&mut env, "pushd", &["-n", "a"]).await;
+
+    let (status, out) = run(&mut env, "popd", &["+2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a ~/c\n");
+
+    let (status, out) = run(&mut env, "popd", &["-0"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a\n");
+    assert_eq!(cwd(&env), root);
+
+    for args in &[&["+5"][..], &["foo"], &["-q"], &["+1", "+1"]] {
+        let (status, out) = run(&mut env, "popd", args).await;
+        assert_eq!(status, EXIT_ERROR, "args: {:?}", args);
+        assert_eq!(out, "");
+    }
+}
+
+#[tokio::test]
+async fn dirs_prints_stack_in_different_styles() {
+    let tempdir = mktmp!();
+    let root = tempdir.path();
+    let root_str = root.to_string_lossy();
+    let mut env = new_env_in(root);
+
+    run(&mut env, "pushd", &["-n", "b"]).await;
+    run(&mut env, "pushd", &["-n", "a"]).await;
+
+    let (status, out) = run(&mut env, "dirs", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "~ ~/a ~/b\n");
+
+    let (_, out) = run(&mut env, "dirs", &["-l"]).await;
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_eq!(out, format!("{0} {0}{1}a {0}{1}b\n", root_str, sep));
+
+    let (_, out) = run(&mut env, "dirs", &["-p"]).await;
+    assert_eq!(out, "~\n~/a\n~/b\n");
+
+    let (_, out) = run(&mut env, "dirs", &["-v"]).await;
+    assert_eq!(out, " 0  ~\n 1  ~/a\n 2  ~/b\n");
+
+    let (_, out) = run(&mut env, "dirs", &["-v", "-0"]).await;
+    assert_eq!(out, " 2  ~/b\n");
+
+    let (_, out) = run(&mut env, "dirs", &["+1"]).await;
+    assert_eq!(out, "~/a\n");
+
+    let (status, out) = run(&mut env, "dirs", &["-c"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+    assert!(env.dir_stack().is_empty());
+
+    let (status, out) = run(&mut env, "dirs", &["+1"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "");
+}
@@ -6,7 +6,12 @@ pub use self::support::spawn::builtin::echo;
 pub use self::support::*;
 
 async fn run_echo(args: &[&str]) -> String {
+    run_echo_with_xpg_echo(args, false).await
+}
+
+async fn run_echo_with_xpg_echo(args: &[&str], xpg_echo: bool) -> String {
     let mut env = new_env_with_no_fds();
+    env.set_option(ShellOption::XpgEcho, xpg_echo);
 
     let pipe = env.open_pipe().expect("pipe failed");
     env.set_file_desc(
@@ -100,3 +105,16 @@ async fn no_escape_flag_turns_off_escape_interpretation() {
     assert_eq!(run_echo(&["-E", "-EE", msg]).await, format!("{}\n", msg));
     assert_eq!(run_echo(&["-e", "-eE", msg]).await, format!("{}\n", msg));
 }
+
+#[tokio::test]
+async fn xpg_echo_always_interprets_escapes_and_recognizes_no_flags() {
+    assert_eq!(
+        run_echo_with_xpg_echo(&["-n", r"foo\tbar"], true).await,
+        "-n foo\tbar\n"
+    );
+    assert_eq!(
+        run_echo_with_xpg_echo(&["-E", r"foo\c"], true).await,
+        "-E foo"
+    );
+    assert_eq!(run_echo_with_xpg_echo(&[r"\0101\x42"], true).await, "AB\n");
+}
@@ -0,0 +1,135 @@
+#![deny(rust_2018_idioms)]
+use conch_runtime::io::Permissions;
+use conch_runtime::{EXIT_CMD_NOT_FOUND, STDERR_FILENO, STDOUT_FILENO};
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::env_cmd;
+pub use self::support::*;
+
+async fn run_env(env: DefaultEnvArc, args: &[&str]) -> (ExitStatus, String) {
+    run_env_with_fd(env, STDOUT_FILENO, args).await
+}
+
+async fn run_env_with_fd(
+    mut env: DefaultEnvArc,
+    fd: conch_runtime::Fd,
+    args: &[&str],
+) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(fd, pipe.writer, Permissions::Write);
+
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+
+    let read_to_end = tokio::spawn(env.read_all(pipe.reader));
+    let exit = tokio::spawn(async move {
+        let future = env_cmd(args, &mut env).await;
+        drop(env);
+        future.await
+    });
+
+    let (output, exit) = join(read_to_end, exit).await;
+    let output = String::from_utf8(output.unwrap().unwrap()).expect("invalid utf8");
+    (exit.unwrap(), output)
+}
+
+fn var(name: &str) -> Arc<String> {
+    Arc::new(name.to_owned())
+}
+
+#[tokio::test]
+async fn prints_only_exported_vars_sorted_by_name() {
+    let mut env = new_env_with_no_fds();
+    env.set_exported_var(var("ZZZ_ENV_TEST"), var("last"), true);
+    env.set_exported_var(var("AAA_ENV_TEST"), var("first"), true);
+    env.set_exported_var(var("LOCAL_ENV_TEST"), var("local"), false);
+
+    let (status, output) = run_env(env, &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut sorted = lines.clone();
+    sorted.sort();
+    assert_eq!(lines, sorted);
+
+    assert!(lines.contains(&"AAA_ENV_TEST=first"));
+    assert!(lines.contains(&"ZZZ_ENV_TEST=last"));
+    assert!(!output.contains("LOCAL_ENV_TEST"));
+}
+
+#[tokio::test]
+async fn ignores_environment_and_applies_assignments() {
+    let mut env = new_env_with_no_fds();
+    env.set_exported_var(var("FOO"), var("foo"), true);
+
+    let (status, output) = run_env(env.sub_env(), &["-i", "B=2", "A=1", "B=3"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(output, "A=1\nB=3\n");
+
+    let (status, output) = run_env(env.sub_env(), &["FOO=bar", "EMPTY="]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert!(output.lines().any(|l| l == "FOO=bar"));
+    assert!(output.lines().any(|l| l == "EMPTY="));
+    assert!(!output.lines().any(|l| l == "FOO=foo"));
+}
+
+#[tokio::test]
+async fn removes_unset_vars() {
+    let mut env = new_env_with_no_fds();
+    env.set_exported_var(var("FOO"), var("foo"), true);
+    env.set_exported_var(var("BAR"), var("bar"), true);
+    env.set_exported_var(var("BAZ"), var("baz"), true);
+
+    let args = ["-u", "FOO", "--unset=BAR", "-uBAZ", "BAZ=1"];
+    let (status, output) = run_env(env.sub_env(), &args).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert!(!output.lines().any(|l| l.starts_with("FOO=")));
+    assert!(!output.lines().any(|l| l.starts_with("BAR=")));
+    assert!(output.lines().any(|l| l == "BAZ=1"));
+
+    let (status, output) = run_env_with_fd(env, STDERR_FILENO, &["-u"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(output, "env: -u: option requires an argument\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn passes_other_options_to_the_external_utility() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tempdir = mktmp!();
+    let external = tempdir.path().join("env");
+    std::fs::write(&external, "#!/bin/sh\necho \"external $*\"\n").expect("write failed");
+    std::fs::set_permissions(&external, std::fs::Permissions::from_mode(0o755))
+        .expect("failed to set permissions");
+
+    let mut env = new_env_with_no_fds();
+    let path = tempdir.path().to_str().expect("non-utf8 tempdir");
+    env.set_exported_var(var("PATH"), var(path), true);
+
+    let (status, output) = run_env(env, &["-i", "-0", "FOO=bar"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(output, "external -i -0 FOO=bar\n");
+}
+
+#[tokio::test]
+async fn reports_missing_utilities() {
+    let env = new_env_with_no_fds();
+    let args = ["-i", "some_utility_which_does_not_exist"];
+    let (status, output) = run_env_with_fd(env, STDERR_FILENO, &args).await;
+    assert_eq!(status, EXIT_CMD_NOT_FOUND);
+    assert!(output.starts_with("env: "));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn runs_utility_with_modified_environment() {
+    let mut env = new_env_with_no_fds();
+    env.set_exported_var(var("FOO"), var("foo"), true);
+
+    let args = ["-i", "FOO=bar", "/bin/sh", "-c", "echo \"$FOO\""];
+    let (status, output) = run_env(env, &args).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(output, "bar\n");
+}
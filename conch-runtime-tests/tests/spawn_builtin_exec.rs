@@ -0,0 +1,37 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+
+mod support;
+pub use self::support::spawn::builtin::exec;
+pub use self::support::*;
+
+#[tokio::test]
+async fn exec_without_utility_succeeds() {
+    let mut env = new_env_with_no_fds();
+    let exit = exec(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(exit, EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn exec_with_utility_is_unsupported() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+    let read_to_end = env.read_all(pipe.reader);
+
+    let exit = exec(vec!["true".to_owned()], &mut env).await;
+    drop(env);
+
+    let (exit, output) = futures_util::future::join(exit, read_to_end).await;
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(
+        String::from_utf8(output.unwrap()).unwrap(),
+        "exec: true: replacing the shell is not supported\n"
+    );
+}
@@ -0,0 +1,162 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::HistoryEnvironment;
+use conch_runtime::io::Permissions;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::env::builtin::*;
+pub use self::support::*;
+
+fn rc(s: &str) -> Arc<String> {
+    Arc::new(s.to_owned())
+}
+
+async fn run(env: &mut DefaultEnvArc, name: &str, args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let args = args.iter().map(|&arg| rc(arg)).collect::<Vec<_>>();
+    let builtin = env.builtin(&rc(name)).expect("missing builtin");
+    let status = builtin
+        .spawn_builtin(args, &mut EnvRestorer::new(&mut *env))
+        .await
+        .await;
+    env.close_file_desc(conch_runtime::STDOUT_FILENO);
+
+    let out = env.read_all(pipe.reader).await.expect("failed to read");
+    (status, String::from_utf8(out).expect("out invalid utf8"))
+}
+
+fn new_env_with_history(entries: &[&str]) -> DefaultEnvArc {
+    let mut env = new_env_with_no_fds();
+    for entry in entries {
+        env.add_history((*entry).to_owned());
+    }
+    env
+}
+
+#[tokio::test]
+async fn history_lists_entries() {
+    let mut env = new_env_with_history(&["foo", "bar", "baz"]);
+
+    let (status, out) = run(&mut env, "history", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "    1  foo\n    2  bar\n    3  baz\n");
+
+    let (status, out) = run(&mut env, "history", &["2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "    2  bar\n    3  baz\n");
+
+    let (status, out) = run(&mut env, "history", &["10"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "    1  foo\n    2  bar\n    3  baz\n");
+}
+
+#[tokio::test]
+async fn history_numbers_persist_when_oldest_entries_discarded() {
+    let mut env = new_env_with_history(&["foo", "bar", "baz"]);
+    env.set_history_limit(Some(2));
+
+    let (status, out) = run(&mut env, "history", &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "    2  bar\n    3  baz\n");
+}
+
+#[tokio::test]
+async fn history_clear() {
+    let mut env = new_env_with_history(&["foo", "bar"]);
+
+    let (status, out) = run(&mut env, "history", &["-c"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+    assert!(env.history().is_empty());
+
+    env.add_history("baz".to_owned());
+    let (_, out) = run(&mut env, "history", &[]).await;
+    assert_eq!(out, "    1  baz\n");
+}
+
+#[tokio::test]
+async fn history_invalid_arguments() {
+    let mut env = new_env_with_history(&["foo"]);
+
+    for args in &[&["-x"][..], &["foo"], &["1", "2"]] {
+        let (status, out) = run(&mut env, "history", args).await;
+        assert_eq!(status, EXIT_ERROR, "{:?}", args);
+        assert_eq!(out, "");
+    }
+}
+
+#[tokio::test]
+async fn fc_lists_recent_entries() {
+    let entries = (1..=20).map(|i| format!("cmd{}", i)).collect::<Vec<_>>();
+    let mut env = new_env_with_no_fds();
+    for entry in &entries {
+        env.add_history(entry.clone());
+    }
+
+    let expected = (5..=20)
+        .map(|i| format!("{}\tcmd{}\n", i, i))
+        .collect::<String>();
+
+    let (status, out) = run(&mut env, "fc", &["-l"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, expected);
+}
+
+#[tokio::test]
+async fn fc_lists_ranges() {
+    let mut env = new_env_with_history(&["echo foo", "true", "echo bar", "false"]);
+
+    let (status, out) = run(&mut env, "fc", &["-l", "2", "3"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "2\ttrue\n3\techo bar\n");
+
+    let (status, out) = run(&mut env, "fc", &["-l", "3", "2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "3\techo bar\n2\ttrue\n");
+
+    let (status, out) = run(&mut env, "fc", &["-l", "-2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "3\techo bar\n4\tfalse\n");
+
+    let (status, out) = run(&mut env, "fc", &["-l", "echo", "-1"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "3\techo bar\n4\tfalse\n");
+
+    let (status, out) = run(&mut env, "fc", &["-l", "0", "100"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "1\techo foo\n2\ttrue\n3\techo bar\n4\tfalse\n");
+
+    let (status, out) = run(&mut env, "fc", &["-lnr", "1", "2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "\ttrue\n\techo foo\n");
+
+    let (status, out) = run(&mut env, "fc", &["-l", "missing"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "");
+}
+
+#[tokio::test]
+async fn fc_with_empty_history() {
+    let mut env = new_env_with_no_fds();
+    let (status, out) = run(&mut env, "fc", &["-l"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+}
+
+#[tokio::test]
+async fn fc_only_supports_listing() {
+    let mut env = new_env_with_history(&["foo"]);
+
+    for args in &[&[][..], &["-s"], &["-e", "vi"], &["-lx"]] {
+        let (status, out) = run(&mut env, "fc", args).await;
+        assert_eq!(status, EXIT_ERROR, "{:?}", args);
+        assert_eq!(out, "");
+    }
+}
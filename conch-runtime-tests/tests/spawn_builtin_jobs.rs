@@ -0,0 +1,201 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+
+mod support;
+pub use self::support::spawn::builtin::{bg, fg, jobs};
+pub use self::support::*;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&arg| arg.to_owned()).collect()
+}
+
+fn add_running_job(env: &mut DefaultEnvArc, command: &str) -> usize {
+    env.add_job(command.to_owned(), None, Box::pin(pending()))
+}
+
+async fn run_jobs(env: &mut DefaultEnvArc, jobs_args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let status = jobs(args(jobs_args), env).await.await;
+    env.close_file_desc(conch_runtime::STDOUT_FILENO);
+
+    let out = env.read_all(pipe.reader).await.expect("failed to read");
+    (status, String::from_utf8(out).expect("out invalid utf8"))
+}
+
+#[tokio::test]
+async fn jobs_marks_current_and_previous_jobs() {
+    let mut env = new_env_with_no_fds();
+    add_running_job(&mut env, "first");
+    add_running_job(&mut env, "second");
+    add_running_job(&mut env, "third");
+
+    let (status, out) = run_jobs(&mut env, &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(
+        out,
+        "[1]   Running                 first\n\
+         [2]-  Running                 second\n\
+         [3]+  Running                 third\n"
+    );
+}
+
+#[tokio::test]
+async fn jobs_resolves_job_specs() {
+    let mut env = new_env_with_no_fds();
+    add_running_job(&mut env, "sleep 10");
+    add_running_job(&mut env, "cat foo");
+    add_running_job(&mut env, "sleep 20");
+
+    let (status, out) = run_jobs(&mut env, &["-p", "%1", "%%", "%-", "%cat", "%?20"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "");
+
+    let (status, out) = run_jobs(&mut env, &["%cat", "%?20", "%+"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(
+        out,
+        "[2]-  Running                 cat foo\n\
+         [3]+  Running                 sleep 20\n\
+         [3]+  Running                 sleep 20\n"
+    );
+}
+
+#[tokio::test]
+async fn jobs_with_invalid_or_ambiguous_specs_are_errors() {
+    let mut env = new_env_with_no_fds();
+    add_running_job(&mut env, "sleep 10");
+    add_running_job(&mut env, "sleep 20");
+
+    for &spec in &["1", "%3", "%sleep", "%?0", "%-1", "%cat"] {
+        let (status, out) = run_jobs(&mut env, &[spec]).await;
+        assert_eq!(status, EXIT_ERROR, "spec: {}", spec);
+        assert_eq!(out, "");
+    }
+}
+
+#[tokio::test]
+async fn jobs_prints_process_groups() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("foo".to_owned(), Some(42), Box::pin(pending()));
+    add_running_job(&mut env, "bar");
+
+    let (status, out) = run_jobs(&mut env, &["-p"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "42\n");
+
+    let (status, out) = run_jobs(&mut env, &["-l", "%1"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "[1]- 42  Running                 foo\n");
+}
+
+#[tokio::test]
+async fn jobs_forgets_done_jobs_after_reporting_them() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("true".to_owned(), None, Box::pin(ready(EXIT_SUCCESS)));
+    env.add_job(
+        "false".to_owned(),
+        None,
+        Box::pin(ready(ExitStatus::Code(1))),
+    );
+    add_running_job(&mut env, "sleep 10");
+
+    env.job(1).unwrap().wait().await;
+    env.job(2).unwrap().wait().await;
+
+    let (status, out) = run_jobs(&mut env, &[]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(
+        out,
+        "[1]   Done                    true\n\
+         [2]-  Exit 1                  false\n\
+         [3]+  Running                 sleep 10\n"
+    );
+
+    let ids = env.jobs().iter().map(|job| job.id()).collect::<Vec<_>>();
+    assert_eq!(ids, vec![3]);
+}
+
+#[tokio::test]
+async fn fg_waits_for_specified_job_and_stops_tracking_it() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("foo".to_owned(), None, Box::pin(ready(ExitStatus::Code(5))));
+    let id = add_running_job(&mut env, "bar");
+    env.set_job_stopped(id, true);
+
+    let status = fg(args(&["%foo"]), &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(5));
+    assert!(env.job(1).is_none());
+    assert_eq!(env.job(id).unwrap().state(), JobState::Stopped);
+}
+
+#[tokio::test]
+async fn fg_and_bg_without_jobs_are_errors() {
+    let mut env = new_env_with_no_fds();
+
+    assert_eq!(fg(args(&[]), &mut env).await.await, EXIT_ERROR);
+    assert_eq!(bg(args(&[]), &mut env).await.await, EXIT_ERROR);
+    assert_eq!(fg(args(&["%1"]), &mut env).await.await, EXIT_ERROR);
+    assert_eq!(bg(args(&["%1"]), &mut env).await.await, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn fg_reports_status_of_done_jobs() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("foo".to_owned(), None, Box::pin(ready(ExitStatus::Code(5))));
+    env.job(1).unwrap().wait().await;
+
+    let status = fg(args(&[]), &mut env).await.await;
+    assert_eq!(status, ExitStatus::Code(5));
+    assert!(env.jobs().is_empty());
+}
+
+#[tokio::test]
+async fn bg_resumes_stopped_jobs_and_makes_them_current() {
+    let mut env = new_env_with_no_fds();
+    let first = add_running_job(&mut env, "foo");
+    let second = add_running_job(&mut env, "bar");
+    env.set_job_stopped(first, true);
+
+    let status = bg(args(&["%1"]), &mut env).await.await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.job(first).unwrap().state(), JobState::Running);
+    assert_eq!(env.current_job(), Some(first));
+    assert_eq!(env.previous_job(), Some(second));
+}
+
+#[tokio::test]
+async fn bg_on_done_job_is_an_error() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("true".to_owned(), None, Box::pin(ready(EXIT_SUCCESS)));
+
+    env.job(1).unwrap().wait().await;
+
+    assert_eq!(bg(args(&[]), &mut env).await.await, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn job_ids_are_reused_once_higher_jobs_are_removed() {
+    let mut env = new_env_with_no_fds();
+    add_running_job(&mut env, "foo");
+    let second = add_running_job(&mut env, "bar");
+
+    env.remove_job(second);
+    assert_eq!(add_running_job(&mut env, "baz"), second);
+}
+
+#[tokio::test]
+async fn jobs_are_not_inherited_by_sub_envs() {
+    let mut env = new_env_with_no_fds();
+    add_running_job(&mut env, "foo");
+
+    let sub = env.sub_env();
+    assert!(sub.jobs().is_empty());
+    assert_eq!(sub.current_job(), None);
+}
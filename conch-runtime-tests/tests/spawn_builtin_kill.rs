@@ -0,0 +1,135 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+
+mod support;
+pub use self::support::spawn::builtin::kill;
+pub use self::support::*;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&arg| arg.to_owned()).collect()
+}
+
+async fn run_kill(env: &mut DefaultEnvArc, kill_args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let status = kill(args(kill_args), env).await.await;
+    env.close_file_desc(conch_runtime::STDOUT_FILENO);
+
+    let out = env.read_all(pipe.reader).await.expect("failed to read");
+    (status, String::from_utf8(out).expect("out invalid utf8"))
+}
+
+#[cfg(unix)]
+fn spawn_sleep(new_process_group: bool) -> std::process::Child {
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = std::process::Command::new("sleep");
+    cmd.arg("10");
+    if new_process_group {
+        cmd.process_group(0);
+    }
+
+    cmd.spawn().expect("failed to spawn sleep")
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn list_prints_all_signal_names() {
+    let mut env = new_env_with_no_fds();
+
+    let (status, out) = run_kill(&mut env, &["-l"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let names = out.lines().collect::<Vec<_>>();
+    assert_eq!(names[..2], ["HUP", "INT"]);
+    assert!(names.contains(&"KILL"));
+    assert!(names.contains(&"TERM"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn list_converts_between_signal_names_and_numbers() {
+    let mut env = new_env_with_no_fds();
+
+    let (status, out) = run_kill(&mut env, &["-l", "9", "137", "term", "SIGINT"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(out, "KILL\nKILL\n15\n2\n");
+
+    let (status, out) = run_kill(&mut env, &["-l", "1", "bogus"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(out, "HUP\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn sends_signals_to_processes() {
+    let mut env = new_env_with_no_fds();
+
+    for &signal in &["-9", "-KILL", "-sigkill"] {
+        let mut child = spawn_sleep(false);
+        let pid = child.id().to_string();
+
+        let (status, _) = run_kill(&mut env, &[signal, &pid]).await;
+        assert_eq!(status, EXIT_SUCCESS);
+
+        let child_status = child.wait().expect("failed to wait");
+        assert_eq!(ExitStatus::from(child_status), ExitStatus::Signal(9));
+    }
+
+    let mut child = spawn_sleep(false);
+    let pid = child.id().to_string();
+
+    let (status, _) = run_kill(&mut env, &["-s", "0", &pid]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let (status, _) = run_kill(&mut env, &["--", &pid]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let child_status = child.wait().expect("failed to wait");
+    assert_eq!(ExitStatus::from(child_status), ExitStatus::Signal(15));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn sends_signals_to_job_process_groups() {
+    let mut env = new_env_with_no_fds();
+
+    let mut child = spawn_sleep(true);
+    env.add_job("sleep 10".to_owned(), Some(child.id()), Box::pin(pending()));
+
+    let (status, _) = run_kill(&mut env, &["-s", "KILL", "%sleep"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    let child_status = child.wait().expect("failed to wait");
+    assert_eq!(ExitStatus::from(child_status), ExitStatus::Signal(9));
+}
+
+#[tokio::test]
+async fn invalid_arguments_are_errors() {
+    let mut env = new_env_with_no_fds();
+    env.add_job("foo".to_owned(), None, Box::pin(pending()));
+
+    let cases: &[&[&str]] = &[
+        &[],
+        &["-s"],
+        &["-s", "BOGUS", "1"],
+        &["-BOGUS", "1"],
+        &["-9"],
+        &["foo"],
+        &["%2"],
+        // Job has no process group
+        &["%1"],
+    ];
+
+    for case in cases {
+        let (status, out) = run_kill(&mut env, case).await;
+        assert_eq!(status, EXIT_ERROR, "args: {:?}", case);
+        assert_eq!(out, "");
+    }
+}
@@ -0,0 +1,64 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::STDERR_FILENO;
+use futures_util::future::join;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::spawn::builtin::let_cmd;
+pub use self::support::*;
+
+async fn run_let(env: &mut DefaultEnvArc, args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+
+    let read_to_end = env.read_all(pipe.reader);
+
+    let args = args.iter().map(|&s| s.to_owned());
+    let future = let_cmd(args, env).await;
+    env.close_file_desc(STDERR_FILENO);
+
+    let (status, err) = join(future, read_to_end).await;
+    let err = String::from_utf8(err.unwrap()).expect("err invalid utf8");
+
+    (status, err)
+}
+
+fn var(env: &DefaultEnvArc, name: &str) -> Option<String> {
+    env.var(&name.to_owned()).map(|val| (**val).clone())
+}
+
+#[tokio::test]
+async fn let_status_depends_on_last_expression() {
+    let mut env = new_env_with_no_fds();
+
+    let (status, err) = run_let(&mut env, &["x = 5", "y = x++ * 2"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(err, "");
+    assert_eq!(var(&env, "x").as_deref(), Some("6"));
+    assert_eq!(var(&env, "y").as_deref(), Some("10"));
+
+    let (status, err) = run_let(&mut env, &["x", "y - 10"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(err, "");
+}
+
+#[tokio::test]
+async fn let_reports_errors_and_stops_evaluating() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(Arc::new("x".to_owned()), Arc::new("1".to_owned()));
+
+    let (status, err) = run_let(&mut env, &["x / 0", "x = 2"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(err, "let: attempted to divide by zero\n");
+    assert_eq!(var(&env, "x").as_deref(), Some("1"));
+
+    let (status, err) = run_let(&mut env, &["x +"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(err, "let: x +: syntax error in expression\n");
+
+    let (status, err) = run_let(&mut env, &[]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(err, "let: expression expected\n");
+}
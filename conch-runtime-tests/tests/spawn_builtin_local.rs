@@ -0,0 +1,46 @@
+#![deny(rust_2018_idioms)]
+
+mod support;
+pub use self::support::spawn::builtin::local;
+pub use self::support::*;
+
+#[tokio::test]
+async fn local_dash_should_restore_options_once_function_frame_pops() {
+    let mut env = new_env_with_no_fds();
+    env.set_option(ShellOption::ErrExit, true);
+
+    env.push_fn_frame();
+    let exit = local(vec!["-".to_owned()], &mut env).await.await;
+    assert_eq!(exit, EXIT_SUCCESS);
+
+    env.set_option(ShellOption::ErrExit, false);
+    env.set_option(ShellOption::XTrace, true);
+    env.pop_fn_frame();
+
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert!(!env.is_option_enabled(ShellOption::XTrace));
+}
+
+#[tokio::test]
+async fn local_should_only_be_used_in_functions() {
+    let mut env = new_env_with_no_fds();
+
+    let exit = local(vec!["-".to_owned()], &mut env).await.await;
+    assert_eq!(exit, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn local_variables_are_unsupported() {
+    let mut env = new_env_with_no_fds();
+    env.push_fn_frame();
+    env.set_option(ShellOption::ErrExit, true);
+
+    let args = vec!["-".to_owned(), "var=value".to_owned()];
+    let exit = local(args, &mut env).await.await;
+    assert_eq!(exit, EXIT_ERROR);
+
+    // The options should not have been made local either
+    env.set_option(ShellOption::ErrExit, false);
+    env.pop_fn_frame();
+    assert!(!env.is_option_enabled(ShellOption::ErrExit));
+}
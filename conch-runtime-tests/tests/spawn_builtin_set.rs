@@ -0,0 +1,166 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use futures_util::future::join;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::spawn::builtin::set;
+pub use self::support::*;
+
+async fn run_set(env: &mut DefaultEnvArc, set_args: &[&str]) -> (ExitStatus, String) {
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let read_to_end = env.read_all(pipe.reader);
+    let args = set_args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+
+    let (exit, out) = join(
+        async {
+            let future = set(args, env).await;
+            env.close_file_desc(conch_runtime::STDOUT_FILENO);
+            future.await
+        },
+        read_to_end,
+    )
+    .await;
+
+    (exit, String::from_utf8(out.unwrap()).expect("invalid utf8"))
+}
+
+fn args_of(env: &DefaultEnvArc) -> Vec<String> {
+    env.args().iter().map(|s| (**s).clone()).collect()
+}
+
+#[tokio::test]
+async fn short_flags_toggle_options() {
+    let mut env = new_env_with_no_fds();
+
+    let (exit, out) = run_set(&mut env, &["-eu", "-x"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(out, "");
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert!(env.is_option_enabled(ShellOption::NoUnset));
+    assert!(env.is_option_enabled(ShellOption::XTrace));
+
+    let (exit, _) = run_set(&mut env, &["+ex"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(!env.is_option_enabled(ShellOption::ErrExit));
+    assert!(env.is_option_enabled(ShellOption::NoUnset));
+    assert!(!env.is_option_enabled(ShellOption::XTrace));
+}
+
+#[tokio::test]
+async fn long_names_toggle_options() {
+    let mut env = new_env_with_no_fds();
+
+    let (exit, _) = run_set(&mut env, &["-o", "errexit", "-o", "noclobber"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert!(env.is_option_enabled(ShellOption::NoClobber));
+
+    let (exit, _) = run_set(&mut env, &["+o", "errexit"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(!env.is_option_enabled(ShellOption::ErrExit));
+    assert!(env.is_option_enabled(ShellOption::NoClobber));
+}
+
+#[tokio::test]
+async fn invalid_options_are_errors() {
+    let mut env = new_env_with_no_fds();
+
+    let (exit, _) = run_set(&mut env, &["-eq"]).await;
+    assert_eq!(exit, EXIT_ERROR);
+    assert!(!env.is_option_enabled(ShellOption::ErrExit));
+
+    let (exit, _) = run_set(&mut env, &["-o", "not_an_option"]).await;
+    assert_eq!(exit, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn enabling_verbose_is_unsupported() {
+    let mut env = new_env_with_no_fds();
+
+    for args in &[&["-ev"][..], &["-o", "verbose"]] {
+        let (exit, _) = run_set(&mut env, args).await;
+        assert_eq!(exit, EXIT_ERROR);
+        assert!(!env.is_option_enabled(ShellOption::ErrExit));
+        assert!(!env.is_option_enabled(ShellOption::Verbose));
+    }
+
+    let (exit, _) = run_set(&mut env, &["+v"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn double_dash_replaces_positional_parameters() {
+    let mut env = new_env_with_no_fds();
+    env.set_args(Arc::new(vec![Arc::new("foo".to_owned())].into()));
+
+    let (exit, _) = run_set(&mut env, &["-e", "--", "a", "-b", "c"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(env.is_option_enabled(ShellOption::ErrExit));
+    assert_eq!(args_of(&env), vec!["a", "-b", "c"]);
+
+    let (exit, _) = run_set(&mut env, &["--"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(args_of(&env).is_empty());
+}
+
+#[tokio::test]
+async fn first_non_option_starts_positional_parameters() {
+    let mut env = new_env_with_no_fds();
+
+    let (exit, _) = run_set(&mut env, &["-u", "a", "-e"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(env.is_option_enabled(ShellOption::NoUnset));
+    assert!(!env.is_option_enabled(ShellOption::ErrExit));
+    assert_eq!(args_of(&env), vec!["a", "-e"]);
+}
+
+#[tokio::test]
+async fn no_args_keeps_positional_parameters() {
+    let mut env = new_env_with_no_fds();
+    env.set_args(Arc::new(vec![Arc::new("foo".to_owned())].into()));
+
+    let (exit, _) = run_set(&mut env, &[]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(args_of(&env), vec!["foo"]);
+
+    let (exit, _) = run_set(&mut env, &["-e"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(args_of(&env), vec!["foo"]);
+}
+
+#[tokio::test]
+async fn single_dash_disables_tracing() {
+    let mut env = new_env_with_no_fds();
+    env.set_option(ShellOption::XTrace, true);
+    env.set_option(ShellOption::Verbose, true);
+
+    let (exit, _) = run_set(&mut env, &["-", "a"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(!env.is_option_enabled(ShellOption::XTrace));
+    assert!(!env.is_option_enabled(ShellOption::Verbose));
+    assert_eq!(args_of(&env), vec!["a"]);
+}
+
+#[tokio::test]
+async fn print_options() {
+    let mut env = new_env_with_no_fds();
+    env.set_option(ShellOption::ErrExit, true);
+
+    let (exit, out) = run_set(&mut env, &["-o"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(out.contains("errexit        \ton\n"));
+    assert!(out.contains("xtrace         \toff\n"));
+
+    let (exit, out) = run_set(&mut env, &["+o"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert!(out.contains("set -o errexit\n"));
+    assert!(out.contains("set +o xtrace\n"));
+}
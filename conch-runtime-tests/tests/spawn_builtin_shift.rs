@@ -69,3 +69,25 @@ async fn shift_multiple_arg_does_nothing_and_exit_with_error() {
     let args = &["a", "b"];
     run_shift(args, &["1", "2"], args, EXIT_ERROR).await;
 }
+
+#[tokio::test]
+async fn shift_large_arg_reports_out_of_range() {
+    let mut env = new_env_with_no_fds();
+    env.set_args(Arc::new(vec![Arc::new("a".to_owned())].into()));
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        conch_runtime::io::Permissions::Write,
+    );
+    let read_to_end = env.read_all(pipe.reader);
+
+    let exit = shift(vec!["2".to_owned()], &mut env).await;
+    env.close_file_desc(conch_runtime::STDERR_FILENO);
+
+    assert_eq!(exit.await, EXIT_ERROR);
+    let err = String::from_utf8(read_to_end.await.unwrap()).unwrap();
+    assert_eq!(err, "shift: shift count out of range\n");
+    assert_eq!(env.args_len(), 1);
+}
@@ -0,0 +1,71 @@
+#![deny(rust_2018_idioms)]
+
+use std::sync::Arc;
+
+mod support;
+pub use self::support::spawn::builtin::unset;
+pub use self::support::*;
+
+#[derive(Debug, Clone, Copy)]
+struct MockFn;
+
+#[async_trait::async_trait]
+impl<E: ?Sized + Send> Spawn<E> for MockFn {
+    type Error = RuntimeError;
+
+    async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        Ok(Box::pin(async { EXIT_SUCCESS }))
+    }
+}
+
+fn rc(s: &str) -> Arc<String> {
+    Arc::new(s.to_owned())
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&arg| arg.to_owned()).collect()
+}
+
+fn new_env_with_var_and_fn(name: &str) -> DefaultEnvArc {
+    let mut env = new_env_with_no_fds();
+    env.set_var(rc(name), rc("value"));
+    env.set_function(rc(name), Arc::new(MockFn));
+    env
+}
+
+#[tokio::test]
+async fn unsets_variables_by_default() {
+    for unset_args in &[
+        &["foo", "bar"][..],
+        &["-v", "foo", "bar"],
+        &["-f", "-v", "foo", "bar"],
+    ] {
+        let mut env = new_env_with_var_and_fn("foo");
+
+        let status = unset(args(unset_args), &mut env).await.await;
+        assert_eq!(status, EXIT_SUCCESS, "args: {:?}", unset_args);
+        assert_eq!(env.var(&rc("foo")), None);
+        assert!(env.has_function(&rc("foo")));
+    }
+}
+
+#[tokio::test]
+async fn unsets_functions_with_f_flag() {
+    for unset_args in &[&["-f", "foo", "bar"][..], &["-v", "-f", "foo", "bar"]] {
+        let mut env = new_env_with_var_and_fn("foo");
+
+        let status = unset(args(unset_args), &mut env).await.await;
+        assert_eq!(status, EXIT_SUCCESS, "args: {:?}", unset_args);
+        assert_eq!(env.var(&rc("foo")), Some(&rc("value")));
+        assert!(!env.has_function(&rc("foo")));
+    }
+}
+
+#[tokio::test]
+async fn invalid_flags_are_errors() {
+    let mut env = new_env_with_var_and_fn("foo");
+
+    let status = unset(args(&["-q", "foo"]), &mut env).await.await;
+    assert_eq!(status, EXIT_ERROR);
+    assert!(env.var(&rc("foo")).is_some());
+}
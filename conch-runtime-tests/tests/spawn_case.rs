@@ -20,6 +20,29 @@ async fn run(
     .await)
 }
 
+async fn run_with_terminators(
+    word: MockWord,
+    arms: Vec<(
+        PatternBodyPair<Vec<MockWord>, Vec<MockCmd>>,
+        CaseArmTerminator,
+    )>,
+) -> Result<ExitStatus, MockErr> {
+    let mut env = new_env();
+    Ok(case_with_terminators(
+        word,
+        arms.iter().map(|(pbp, terminator)| {
+            let pbp = PatternBodyPair {
+                patterns: &*pbp.patterns,
+                body: sequence_slice(&pbp.body),
+            };
+            (pbp, *terminator)
+        }),
+        &mut env,
+    )
+    .await?
+    .await)
+}
+
 #[tokio::test]
 async fn should_expand_only_first_word_tilde_without_further_field_splitting() {
     let word = mock_word_assert_cfg(WordEvalConfig {
@@ -191,3 +214,229 @@ async fn should_propagate_fatal_errors() {
         .await
     );
 }
+
+#[tokio::test]
+async fn should_run_next_body_unconditionally_on_fall_through() {
+    let word = mock_word_fields(Fields::Single("foo".to_owned()));
+    let no_match = mock_word_fields(Fields::Single("bar".to_owned()));
+    let exit = ExitStatus::Code(42);
+
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(ExitStatus::Code(5))],
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![mock_word_panic("word must not run")],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+            ],
+        )
+        .await
+    );
+
+    // Falling through the last arm is not an error
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![no_match],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+            ],
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn should_test_subsequent_patterns_on_continue() {
+    let word = mock_word_fields(Fields::Single("foo".to_owned()));
+    let no_match = mock_word_fields(Fields::Single("bar".to_owned()));
+    let exit = ExitStatus::Code(42);
+
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(ExitStatus::Code(5))],
+                    },
+                    CaseArmTerminator::Continue,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![no_match.clone()],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+            ],
+        )
+        .await
+    );
+
+    // The status of the last body run is kept if nothing else matches
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Continue,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![no_match],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+            ],
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn should_match_extended_patterns_if_configured() {
+    let exit = ExitStatus::Code(42);
+    let arms = vec![(
+        PatternBodyPair {
+            patterns: vec![mock_word_fields(Fields::Single(
+                "@(foo|bar).!(rs)".to_owned(),
+            ))],
+            body: vec![mock_status(exit)],
+        },
+        CaseArmTerminator::Break,
+    )];
+
+    for &(word, extglob, expected) in &[
+        ("bar.md", true, exit),
+        ("bar.rs", true, EXIT_SUCCESS),
+        ("baz.md", true, EXIT_SUCCESS),
+        ("bar.md", false, EXIT_SUCCESS),
+    ] {
+        let mut env = new_env();
+        let cfg = PatternMatchConfig {
+            extglob,
+            ..PatternMatchConfig::default()
+        };
+
+        let status = case_with_config(
+            mock_word_fields(Fields::Single(word.to_owned())),
+            arms.iter().map(|(pbp, terminator)| {
+                let pbp = PatternBodyPair {
+                    patterns: &*pbp.patterns,
+                    body: sequence_slice(&pbp.body),
+                };
+                (pbp, *terminator)
+            }),
+            cfg,
+            &mut env,
+        )
+        .await
+        .expect("case failed")
+        .await;
+
+        assert_eq!(status, expected, "word: {}, extglob: {}", word, extglob);
+    }
+}
+
+#[tokio::test]
+async fn quoted_patterns_should_only_match_literally_with_extglob() {
+    let exit = ExitStatus::Code(42);
+    let pattern = |word: &str| -> conch_parser::ast::Word<String, MockWord> {
+        conch_parser::ast::Word::SingleQuoted(word.to_owned())
+    };
+    let arms = vec![(
+        PatternBodyPair {
+            patterns: vec![pattern("@(a|b)"), pattern("!(x)"), pattern("+(c)")],
+            body: vec![mock_status(exit)],
+        },
+        CaseArmTerminator::Break,
+    )];
+
+    for &(word, expected) in &[
+        ("@(a|b)", exit),
+        ("!(x)", exit),
+        ("+(c)", exit),
+        ("a", EXIT_SUCCESS),
+        ("y", EXIT_SUCCESS),
+        ("cc", EXIT_SUCCESS),
+    ] {
+        let mut env = DefaultEnv::<String>::new().expect("failed to create env");
+        let cfg = PatternMatchConfig {
+            extglob: true,
+            ..PatternMatchConfig::default()
+        };
+
+        let status = case_with_config(
+            mock_word_fields(Fields::Single(word.to_owned())),
+            arms.iter().map(|(pbp, terminator)| {
+                let pbp = PatternBodyPair {
+                    patterns: &*pbp.patterns,
+                    body: sequence_slice(&pbp.body),
+                };
+                (pbp, *terminator)
+            }),
+            cfg,
+            &mut env,
+        )
+        .await
+        .expect("case failed")
+        .await;
+
+        assert_eq!(status, expected, "word: {}", word);
+    }
+}
@@ -0,0 +1,80 @@
+#![deny(rust_2018_idioms)]
+
+use std::path::Path;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::*;
+
+/// Records the environment's `$LINENO` when spawned, and fails if requested.
+struct LineSpy(Option<fn() -> RuntimeError>);
+
+#[async_trait::async_trait]
+impl Spawn<DefaultEnvArc> for LineSpy {
+    type Error = RuntimeError;
+
+    async fn spawn(
+        &self,
+        env: &mut DefaultEnvArc,
+    ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        assert_eq!(env.dynamic_var("LINENO"), Some("7".to_owned()));
+        match self.0 {
+            Some(err) => Err(err()),
+            None => Ok(Box::pin(async { EXIT_SUCCESS })),
+        }
+    }
+}
+
+fn location() -> SourceLocation {
+    SourceLocation {
+        file: Some(Arc::from(Path::new("script.sh"))),
+        line: 7,
+    }
+}
+
+#[tokio::test]
+async fn should_set_current_line_before_spawning() {
+    let mut env = new_env();
+    let future = located(location(), LineSpy(None))
+        .spawn(&mut env)
+        .await
+        .unwrap();
+
+    assert_eq!(future.await, EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn should_annotate_errors_with_location() {
+    let mut env = new_env();
+    let cmd = located(
+        location(),
+        LineSpy(Some(|| RuntimeError::Unimplemented("oops"))),
+    );
+
+    match cmd.spawn(&mut env).await {
+        Err(err @ RuntimeError::Located(..)) => {
+            assert_eq!(err.to_string(), "script.sh: line 7: oops");
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("unexpected success"),
+    }
+}
+
+#[tokio::test]
+async fn should_not_annotate_control_flow() {
+    let mut env = new_env();
+    let cmd = located(
+        location(),
+        LineSpy(Some(|| {
+            RuntimeError::ControlFlow(ControlFlow::Exit(EXIT_ERROR))
+        })),
+    );
+
+    match cmd.spawn(&mut env).await {
+        Err(RuntimeError::ControlFlow(ControlFlow::Exit(status))) => {
+            assert_eq!(status, EXIT_ERROR)
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("unexpected success"),
+    }
+}
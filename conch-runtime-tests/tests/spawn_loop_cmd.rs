@@ -168,3 +168,67 @@ async fn should_propagate_fatal_errors() {
         .await
     );
 }
+
+#[tokio::test]
+async fn should_handle_break_requests() {
+    let should_not_run = mock_panic("must not run");
+    let break_cmd = |n| MockCmd::Error(MockErr::ControlFlow(ControlFlow::Break(n)));
+    let mut env = new_env();
+
+    assert_eq!(
+        Ok(EXIT_SUCCESS),
+        loop_cmd(
+            false,
+            sequence_slice(&[&mock_status(EXIT_SUCCESS)]),
+            sequence_slice(&[&break_cmd(1), &should_not_run]),
+            &mut env,
+        )
+        .await
+    );
+    assert_eq!(EXIT_SUCCESS, env.last_status());
+
+    // Breaking from the guard should also work
+    assert_eq!(
+        Ok(EXIT_SUCCESS),
+        loop_cmd(
+            false,
+            sequence_slice(&[&break_cmd(1), &should_not_run]),
+            sequence_slice(&[&should_not_run]),
+            &mut env,
+        )
+        .await
+    );
+
+    assert_eq!(
+        Err(MockErr::ControlFlow(ControlFlow::Break(1))),
+        loop_cmd(
+            false,
+            sequence_slice(&[&mock_status(EXIT_SUCCESS)]),
+            sequence_slice(&[&break_cmd(2), &should_not_run]),
+            &mut env,
+        )
+        .await
+    );
+
+    assert_eq!(0, env.loop_depth());
+}
+
+#[tokio::test]
+async fn should_propagate_continue_requests_for_outer_loops() {
+    let should_not_run = mock_panic("must not run");
+    let continue_cmd = MockCmd::Error(MockErr::ControlFlow(ControlFlow::Continue(3)));
+    let mut env = new_env();
+
+    assert_eq!(
+        Err(MockErr::ControlFlow(ControlFlow::Continue(2))),
+        loop_cmd(
+            false,
+            sequence_slice(&[&mock_status(EXIT_SUCCESS)]),
+            sequence_slice(&[&continue_cmd, &should_not_run]),
+            &mut env,
+        )
+        .await
+    );
+
+    assert_eq!(0, env.loop_depth());
+}
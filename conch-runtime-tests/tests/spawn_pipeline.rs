@@ -47,6 +47,195 @@ async fn swallows_inner_errors() {
     assert_eq!(Ok(EXIT_SUCCESS), future.await);
 }
 
+#[tokio::test]
+async fn stages_absorb_exit_requests() {
+    let exit = ExitStatus::Code(42);
+    let exit_cmd = || MockCmd::Error(MockErr::ControlFlow(ControlFlow::Exit(exit)));
+
+    let future = run(false, exit_cmd(), vec![mock_status(EXIT_SUCCESS)]);
+    assert_eq!(Ok(EXIT_SUCCESS), future.await);
+
+    let future = run(false, mock_status(EXIT_SUCCESS), vec![exit_cmd()]);
+    assert_eq!(Ok(exit), future.await);
+}
+
+#[tokio::test]
+async fn records_status_of_each_command() {
+    let exit = ExitStatus::Code(42);
+    let mut env = new_env_with_no_fds();
+
+    let future = pipeline(
+        true,
+        mock_status(exit),
+        vec![mock_error(false), mock_status(EXIT_SUCCESS)],
+        &mut env,
+    )
+    .await
+    .unwrap();
+
+    let status = future.await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(env.last_pipeline_status(), [EXIT_SUCCESS]);
+
+    env.set_last_status(status);
+    assert_eq!(env.last_pipeline_status(), [exit, EXIT_ERROR, EXIT_SUCCESS]);
+
+    env.set_last_status(exit);
+    assert_eq!(env.last_pipeline_status(), [exit]);
+}
+
+#[tokio::test]
+async fn pipefail_resolves_to_last_failing_status() {
+    async fn run_pipefail(invert: bool, first: MockCmd, rest: Vec<MockCmd>) -> ExitStatus {
+        let mut env = new_env_with_no_fds();
+        let cfg = PipelineConfig {
+            pipefail: true,
+            lastpipe: false,
+        };
+
+        let future = pipeline_with_config(invert, cfg, first, rest, &mut env).await;
+        drop(env);
+        future.unwrap().await
+    }
+
+    let exit = ExitStatus::Code(42);
+
+    let status = run_pipefail(
+        false,
+        mock_status(EXIT_ERROR),
+        vec![mock_status(exit), mock_status(EXIT_SUCCESS)],
+    );
+    assert_eq!(status.await, exit);
+
+    let status = run_pipefail(
+        false,
+        mock_status(EXIT_SUCCESS),
+        vec![mock_status(EXIT_SUCCESS), mock_status(exit)],
+    );
+    assert_eq!(status.await, exit);
+
+    let status = run_pipefail(
+        false,
+        mock_status(EXIT_SUCCESS),
+        vec![mock_status(EXIT_SUCCESS)],
+    );
+    assert_eq!(status.await, EXIT_SUCCESS);
+
+    let status = run_pipefail(true, mock_status(exit), vec![mock_status(EXIT_SUCCESS)]);
+    assert_eq!(status.await, EXIT_SUCCESS);
+}
+
+#[tokio::test]
+async fn lastpipe_runs_final_command_in_current_env() {
+    const VAR: &str = "var";
+    const VALUE: &str = "value";
+
+    struct EnvSpy(fn(&mut DefaultEnvArc));
+
+    #[async_trait::async_trait]
+    impl Spawn<DefaultEnvArc> for EnvSpy {
+        type Error = RuntimeError;
+
+        async fn spawn(
+            &self,
+            env: &mut DefaultEnvArc,
+        ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            (self.0)(env);
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+    }
+
+    let cfg = PipelineConfig {
+        pipefail: false,
+        lastpipe: true,
+    };
+
+    let mut env = new_env();
+    let default_stdin = env.file_desc(STDIN_FILENO).unwrap().0.clone();
+
+    let future = pipeline_with_config(
+        false,
+        cfg,
+        EnvSpy(|_| {}),
+        vec![EnvSpy(|env| {
+            let (_, perms) = env.file_desc(STDIN_FILENO).unwrap();
+            assert_eq!(perms, Permissions::Read);
+            env.set_var(Arc::new(VAR.to_owned()), Arc::new(VALUE.to_owned()));
+        })],
+        &mut env,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(future.await, EXIT_SUCCESS);
+    assert_eq!(
+        env.var(&Arc::new(VAR.to_owned())),
+        Some(&Arc::new(VALUE.to_owned()))
+    );
+    assert_eq!(env.file_desc(STDIN_FILENO).unwrap().0, &default_stdin);
+}
+
+#[tokio::test]
+async fn lastpipe_records_status_of_each_command() {
+    struct SetsLastStatus;
+
+    #[async_trait::async_trait]
+    impl Spawn<DefaultEnvArc> for SetsLastStatus {
+        type Error = RuntimeError;
+
+        async fn spawn(
+            &self,
+            env: &mut DefaultEnvArc,
+        ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            // Like a compound command which runs several commands in turn
+            env.set_last_status(EXIT_ERROR);
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+    }
+
+    let cfg = PipelineConfig {
+        pipefail: false,
+        lastpipe: true,
+    };
+
+    let mut env = new_env_with_no_fds();
+    let future = pipeline_with_config(false, cfg, SetsLastStatus, vec![SetsLastStatus], &mut env)
+        .await
+        .unwrap();
+
+    let status = future.await;
+    assert_eq!(status, EXIT_SUCCESS);
+
+    env.set_last_status(status);
+    assert_eq!(env.last_pipeline_status(), [EXIT_SUCCESS, EXIT_SUCCESS]);
+}
+
+#[tokio::test]
+async fn lastpipe_propagates_exit_requests() {
+    let exit = ExitStatus::Code(42);
+    let cfg = PipelineConfig {
+        pipefail: false,
+        lastpipe: true,
+    };
+
+    let mut env = new_env_with_no_fds();
+    let result = pipeline_with_config(
+        false,
+        cfg,
+        mock_status(EXIT_SUCCESS),
+        vec![MockCmd::Error(MockErr::ControlFlow(ControlFlow::Exit(
+            exit,
+        )))],
+        &mut env,
+    )
+    .await;
+
+    assert_eq!(
+        result.err(),
+        Some(MockErr::ControlFlow(ControlFlow::Exit(exit)))
+    );
+}
+
 #[tokio::test]
 async fn status_inversion() {
     let future = run(
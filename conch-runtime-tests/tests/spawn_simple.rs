@@ -1,10 +1,14 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::builtin::{Builtin as RealBuiltin, BuiltinEnvironment, BuiltinUtility};
+use conch_runtime::env::builtin::{
+    Builtin as RealBuiltin, BuiltinEnvironment, BuiltinPrecedence, BuiltinUtility, CustomBuiltinEnv,
+};
 use conch_runtime::env::FileDescEnvironment;
 use conch_runtime::eval::RedirectAction;
 use conch_runtime::io::Permissions;
-use conch_runtime::spawn::simple_command;
+use conch_runtime::spawn::{simple_command, simple_command_with_resolution, CommandResolution};
+use conch_runtime::{EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND};
+use std::borrow::Cow;
 use std::sync::Arc;
 
 mod support;
@@ -183,6 +187,69 @@ async fn should_set_executable_cwd_same_as_env() {
     stdout.await.unwrap();
 }
 
+#[tokio::test]
+async fn paths_should_distinguish_missing_from_non_executable_commands() {
+    let tempdir = mktmp!();
+    std::fs::create_dir(tempdir.path().join("dir")).expect("failed to create dir");
+    std::fs::write(tempdir.path().join("file"), "").expect("failed to create file");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = tempdir.path().join("script");
+        std::fs::write(&script, "#!/conch-runtime/missing/interpreter\n")
+            .expect("failed to create script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("failed to set permissions");
+    }
+
+    let mut env = new_test_env();
+    env.change_working_dir(Cow::Borrowed(tempdir.path()))
+        .expect("failed to change working dir");
+
+    let cases = vec![
+        ("./missing", EXIT_CMD_NOT_FOUND, "command not found"),
+        ("./dir", EXIT_CMD_NOT_EXECUTABLE, "command not executable"),
+        #[cfg(unix)]
+        ("./file", EXIT_CMD_NOT_EXECUTABLE, "command not executable"),
+        #[cfg(unix)]
+        (
+            "./script",
+            EXIT_CMD_NOT_EXECUTABLE,
+            "command not executable",
+        ),
+    ];
+
+    for (name, expected, msg) in cases {
+        let pipe = env.open_pipe().expect("failed to open pipe");
+        let stderr = env.read_all(pipe.reader);
+
+        let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+            vec![].into_iter(),
+            vec![
+                RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(name.to_owned()))),
+                RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Open(
+                    2,
+                    pipe.writer,
+                    Permissions::Write,
+                ))),
+            ]
+            .into_iter(),
+            &mut env,
+        );
+
+        assert_eq!(future.await.unwrap().await, expected, "{}", name);
+
+        // The error should have been reported to the command's stderr
+        let stderr = stderr.await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&stderr),
+            format!("{}: {}\n", name, msg)
+        );
+    }
+}
+
 #[tokio::test]
 async fn command_redirect_and_env_var_overrides() {
     let mut env = new_test_env();
@@ -452,6 +519,74 @@ async fn builtins_should_have_lower_precedence_than_functions() {
     assert_eq!(FN_EXIT, future.await.unwrap().await);
 }
 
+#[tokio::test]
+async fn aliases_should_be_expanded_only_if_enabled() {
+    const FN_EXIT: ExitStatus = ExitStatus::Code(42);
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockFn;
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for MockFn
+    where
+        E: ?Sized + Send + Sync + ArgumentsEnvironment<Arg = Arc<String>>,
+    {
+        type Error = MockErr;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let args = env.args();
+            let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+            assert_eq!(args, vec!["-l", "-a", "foo"]);
+            Ok(Box::pin(async { FN_EXIT }))
+        }
+    }
+
+    let mut env = new_test_env();
+
+    let fn_name = "fn_name".to_owned();
+    env.set_function(Arc::new(fn_name.clone()), Arc::new(MockFn));
+
+    // A trailing blank means the following word should also be checked
+    env.set_alias("ll".to_owned(), format!("{} ", fn_name));
+    // A recursive definition should only be expanded once
+    env.set_alias(fn_name.clone(), format!("{} -l ", fn_name));
+    env.set_alias("aa".to_owned(), "-a".to_owned());
+    env.set_alias("foo".to_owned(), "must not expand".to_owned());
+
+    let words = || {
+        vec!["ll", "aa", "foo"]
+            .into_iter()
+            .map(|w| RedirectOrCmdWord::CmdWord(mock_word_literal(w)))
+    };
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        words(),
+        &mut env,
+    );
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+
+    env.set_expand_aliases(true);
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        words(),
+        &mut env,
+    );
+    assert_eq!(FN_EXIT, future.await.unwrap().await);
+
+    // Words which are not literal (e.g. quoted or expanded) are never aliases,
+    // even if they evaluate to the name of one
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![RedirectOrCmdWord::CmdWord(mock_word_fields(
+            Fields::Single("ll".to_owned()),
+        ))]
+        .into_iter(),
+        &mut env,
+    );
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+}
+
 #[tokio::test]
 async fn should_pass_restorers_to_builtin_utility_without_restore() {
     #[derive(Debug, Clone)]
@@ -542,3 +677,513 @@ async fn should_pass_restorers_to_builtin_utility_without_restore() {
     assert_ne!(None, env.file_desc(42));
     assert_ne!(None, env.var(&key));
 }
+
+#[tokio::test]
+async fn trivial_builtins_should_not_be_spawned() {
+    #[derive(Debug, Clone)]
+    struct MockBuiltinEnv;
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockBuiltin;
+
+    impl BuiltinEnvironment for MockBuiltinEnv {
+        type BuiltinName = Arc<String>;
+        type Builtin = MockBuiltin;
+
+        fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
+            if **name == BUILTIN_CMD {
+                Some(MockBuiltin)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl<'a>
+        BuiltinUtility<
+            'a,
+            Vec<String>,
+            EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+            TestEnvWithBuiltin<MockBuiltinEnv>,
+        > for MockBuiltin
+    {
+        fn spawn_builtin<'life0, 'life1, 'async_trait>(
+            &'life0 self,
+            _args: Vec<String>,
+            _restorer: &'life1 mut EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+        ) -> BoxFuture<'async_trait, BoxFuture<'static, ExitStatus>>
+        where
+            'life0: 'async_trait,
+            'life1: 'async_trait,
+            Self: 'async_trait,
+            Vec<String>: 'async_trait,
+        {
+            panic!("trivial builtins should not be spawned")
+        }
+
+        fn trivial_status(&self) -> Option<ExitStatus> {
+            Some(BUILTIN_EXIT_STATUS)
+        }
+    }
+
+    // A literal word which must never be evaluated
+    #[derive(Debug)]
+    struct UnevaluatedLiteral(&'static str);
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> WordEval<E> for UnevaluatedLiteral {
+        type EvalResult = String;
+        type Error = MockErr;
+
+        async fn eval_with_config(
+            &self,
+            _: &mut E,
+            _: WordEvalConfig,
+        ) -> Result<BoxFuture<'static, Fields<String>>, MockErr> {
+            panic!("literal commands of trivial builtins should not be evaluated")
+        }
+
+        fn as_literal(&self) -> Option<&str> {
+            Some(self.0)
+        }
+    }
+
+    let cfg = new_test_env_config!();
+    let mut env: TestEnvWithBuiltin<MockBuiltinEnv> =
+        Env::with_config(cfg.change_builtin_env(MockBuiltinEnv));
+
+    let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(String::from(BUILTIN_CMD)))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(String::from("arg")))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(BUILTIN_EXIT_STATUS, future.await.unwrap().await);
+
+    let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(UnevaluatedLiteral(BUILTIN_CMD)),
+            RedirectOrCmdWord::CmdWord(UnevaluatedLiteral("arg")),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(BUILTIN_EXIT_STATUS, future.await.unwrap().await);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn custom_builtins_should_respect_configured_precedence() {
+    use std::os::unix::fs::PermissionsExt;
+
+    const CUSTOM_EXIT_STATUS: ExitStatus = ExitStatus::Code(42);
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockBuiltin;
+
+    type CustomEnv = TestEnvWithBuiltin<CustomBuiltinEnv<Arc<String>, MockBuiltin>>;
+
+    impl<'a> BuiltinUtility<'a, Vec<String>, EnvRestorer<'a, CustomEnv>, CustomEnv> for MockBuiltin {
+        fn spawn_builtin<'life0, 'life1, 'async_trait>(
+            &'life0 self,
+            _args: Vec<String>,
+            _restorer: &'life1 mut EnvRestorer<'a, CustomEnv>,
+        ) -> BoxFuture<'async_trait, BoxFuture<'static, ExitStatus>>
+        where
+            'life0: 'async_trait,
+            'life1: 'async_trait,
+            Self: 'async_trait,
+            Vec<String>: 'async_trait,
+        {
+            Box::pin(async { Box::pin(async { CUSTOM_EXIT_STATUS }) as BoxFuture<'static, _> })
+        }
+    }
+
+    let tempdir = mktmp!();
+    let script = tempdir.path().join("custom");
+    std::fs::write(&script, "#!/bin/sh\nexit 7\n").expect("failed to write script");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("failed to set permissions");
+    let path = tempdir.path().to_str().unwrap().to_owned();
+
+    let cases = vec![
+        ("custom", BuiltinPrecedence::BeforePath, CUSTOM_EXIT_STATUS),
+        ("custom", BuiltinPrecedence::AfterPath, ExitStatus::Code(7)),
+        // Custom builtins shadow default ones
+        ("true", BuiltinPrecedence::AfterPath, CUSTOM_EXIT_STATUS),
+    ];
+
+    for (name, precedence, expected) in cases {
+        let mut builtins = CustomBuiltinEnv::new();
+        builtins.register("custom", MockBuiltin);
+        builtins.register("true", MockBuiltin);
+        builtins.set_precedence(name, precedence);
+
+        let mut env: CustomEnv =
+            Env::with_config(new_test_env_config!().change_builtin_env(builtins));
+        env.set_exported_var(Arc::new("PATH".to_owned()), Arc::new(path.clone()), true);
+
+        let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+            vec![].into_iter(),
+            vec![RedirectOrCmdWord::CmdWord(mock_word_fields(
+                Fields::Single(name.to_owned()),
+            ))]
+            .into_iter(),
+            &mut env,
+        );
+
+        assert_eq!(
+            future.await.unwrap().await,
+            expected,
+            "{} {:?}",
+            name,
+            precedence
+        );
+    }
+}
+
+#[tokio::test]
+async fn resolution_override_can_prefer_builtins_over_functions() {
+    const FN_EXIT: ExitStatus = ExitStatus::Code(42);
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockFn;
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send + Sync> Spawn<E> for MockFn {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            Ok(Box::pin(async { FN_EXIT }))
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockBuiltinEnv;
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockBuiltin;
+
+    impl BuiltinEnvironment for MockBuiltinEnv {
+        type BuiltinName = Arc<String>;
+        type Builtin = MockBuiltin;
+
+        fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
+            if **name == BUILTIN_CMD {
+                Some(MockBuiltin)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl<'a>
+        BuiltinUtility<
+            'a,
+            Vec<String>,
+            EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+            TestEnvWithBuiltin<MockBuiltinEnv>,
+        > for MockBuiltin
+    {
+        fn spawn_builtin<'life0, 'life1, 'async_trait>(
+            &'life0 self,
+            _args: Vec<String>,
+            _restorer: &'life1 mut EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+        ) -> BoxFuture<'async_trait, BoxFuture<'static, ExitStatus>>
+        where
+            'life0: 'async_trait,
+            'life1: 'async_trait,
+            Self: 'async_trait,
+            Vec<String>: 'async_trait,
+        {
+            let ret: BoxFuture<'_, _> = Box::pin(async { BUILTIN_EXIT_STATUS });
+            Box::pin(async move { ret })
+        }
+    }
+
+    let cfg = new_test_env_config!();
+    let mut env: TestEnvWithBuiltin<MockBuiltinEnv> =
+        Env::with_config(cfg.change_builtin_env(MockBuiltinEnv));
+
+    let fn_name = BUILTIN_CMD.to_owned();
+    env.set_function(Arc::new(fn_name.clone()), Arc::new(MockFn));
+
+    let cases = vec![
+        (CommandResolution::Default, BUILTIN_CMD, FN_EXIT),
+        (
+            CommandResolution::PreferBuiltin,
+            BUILTIN_CMD,
+            BUILTIN_EXIT_STATUS,
+        ),
+        (
+            CommandResolution::BuiltinOnly,
+            BUILTIN_CMD,
+            BUILTIN_EXIT_STATUS,
+        ),
+        (
+            CommandResolution::BuiltinOnly,
+            "missing",
+            EXIT_CMD_NOT_FOUND,
+        ),
+    ];
+
+    for (resolution, name, expected) in cases {
+        let future = simple_command_with_resolution::<MockRedirect<_>, String, _, _, _, _, _>(
+            vec![].into_iter(),
+            vec![RedirectOrCmdWord::CmdWord(mock_word_fields(
+                Fields::Single(name.to_owned()),
+            ))]
+            .into_iter(),
+            resolution,
+            &mut env,
+        );
+
+        assert_eq!(expected, future.await.unwrap().await, "{:?}", resolution);
+    }
+}
+
+#[tokio::test]
+async fn builtin_only_resolution_should_report_missing_builtins() {
+    let mut env = new_test_env();
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    let stderr = env.read_all(pipe.reader);
+
+    let future = simple_command_with_resolution::<MockRedirect<_>, String, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("missing".to_owned()))),
+            RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Open(
+                2,
+                pipe.writer,
+                Permissions::Write,
+            ))),
+        ]
+        .into_iter(),
+        CommandResolution::BuiltinOnly,
+        &mut env,
+    );
+
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+    drop(env);
+
+    let msg = stderr.await.unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&msg),
+        "missing: command not found\n"
+    );
+}
+
+#[tokio::test]
+async fn command_and_builtin_prefixes_should_override_resolution() {
+    const FN_EXIT: ExitStatus = ExitStatus::Code(42);
+    const MISSING_CMD: &str = "missing-cmd-which-should-not-exist";
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockFn;
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send + Sync> Spawn<E> for MockFn {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            Ok(Box::pin(async { FN_EXIT }))
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockBuiltinEnv;
+
+    #[derive(Debug, Clone, Copy)]
+    struct MockBuiltin;
+
+    impl BuiltinEnvironment for MockBuiltinEnv {
+        type BuiltinName = Arc<String>;
+        type Builtin = MockBuiltin;
+
+        fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
+            if **name == BUILTIN_CMD {
+                Some(MockBuiltin)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl<'a>
+        BuiltinUtility<
+            'a,
+            Vec<String>,
+            EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+            TestEnvWithBuiltin<MockBuiltinEnv>,
+        > for MockBuiltin
+    {
+        fn spawn_builtin<'life0, 'life1, 'async_trait>(
+            &'life0 self,
+            _args: Vec<String>,
+            _restorer: &'life1 mut EnvRestorer<'a, TestEnvWithBuiltin<MockBuiltinEnv>>,
+        ) -> BoxFuture<'async_trait, BoxFuture<'static, ExitStatus>>
+        where
+            'life0: 'async_trait,
+            'life1: 'async_trait,
+            Self: 'async_trait,
+            Vec<String>: 'async_trait,
+        {
+            let ret: BoxFuture<'_, _> = Box::pin(async { BUILTIN_EXIT_STATUS });
+            Box::pin(async move { ret })
+        }
+    }
+
+    let cfg = new_test_env_config!();
+    let mut env: TestEnvWithBuiltin<MockBuiltinEnv> =
+        Env::with_config(cfg.change_builtin_env(MockBuiltinEnv));
+
+    env.set_function(Arc::new(BUILTIN_CMD.to_owned()), Arc::new(MockFn));
+    env.set_function(Arc::new(MISSING_CMD.to_owned()), Arc::new(MockFn));
+
+    let cases: Vec<(&[&str], ExitStatus)> = vec![
+        (&[BUILTIN_CMD], FN_EXIT),
+        (&[MISSING_CMD], FN_EXIT),
+        (&["command", BUILTIN_CMD], BUILTIN_EXIT_STATUS),
+        (&["command", "--", BUILTIN_CMD], BUILTIN_EXIT_STATUS),
+        (&["command", MISSING_CMD], EXIT_CMD_NOT_FOUND),
+        (&["builtin", BUILTIN_CMD], BUILTIN_EXIT_STATUS),
+        (&["builtin", MISSING_CMD], EXIT_CMD_NOT_FOUND),
+        (&["command", "builtin", MISSING_CMD], EXIT_CMD_NOT_FOUND),
+        (&["command"], EXIT_SUCCESS),
+        (&["builtin"], EXIT_SUCCESS),
+    ];
+
+    for &(words, expected) in &cases {
+        let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+            vec![].into_iter(),
+            words
+                .iter()
+                .map(|&word| {
+                    RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(word.to_owned())))
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+            &mut env,
+        );
+
+        assert_eq!(expected, future.await.unwrap().await, "{:?}", words);
+    }
+
+    // Functions can override the prefixes themselves
+    env.set_function(Arc::new("command".to_owned()), Arc::new(MockFn));
+    let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("command".to_owned()))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(BUILTIN_CMD.to_owned()))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(FN_EXIT, future.await.unwrap().await);
+}
+
+#[tokio::test]
+async fn command_prefix_options_should_be_rejected() {
+    for &option in &["-v", "-V", "-p"] {
+        let mut env = new_test_env();
+
+        let pipe = env.open_pipe().expect("failed to open pipe");
+        let stderr = env.read_all(pipe.reader);
+
+        let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+            vec![].into_iter(),
+            vec![
+                RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("command".to_owned()))),
+                RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(option.to_owned()))),
+                RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(
+                    BUILTIN_CMD.to_owned(),
+                ))),
+                RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Open(
+                    2,
+                    pipe.writer,
+                    Permissions::Write,
+                ))),
+            ]
+            .into_iter(),
+            &mut env,
+        );
+
+        assert_eq!(EXIT_ERROR, future.await.unwrap().await);
+        drop(env);
+
+        let msg = stderr.await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&msg),
+            format!("command: {}: option not supported\n", option)
+        );
+    }
+}
+
+#[tokio::test]
+async fn allexport_should_export_assignments_without_a_command() {
+    let mut env = new_test_env();
+    env.set_option(ShellOption::AllExport, true);
+
+    let key = Arc::new("key".to_owned());
+    let val = "val".to_owned();
+
+    let future = simple_command::<MockRedirect<_>, _, _, _, _, _, _>(
+        vec![RedirectOrVarAssig::VarAssig(
+            key.clone(),
+            Some(mock_word_fields(Fields::Single(val.clone()))),
+        )]
+        .into_iter(),
+        vec![].into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    assert_eq!(env.exported_var(&key), Some((&Arc::new(val), true)));
+}
+
+#[tokio::test]
+async fn xtrace_should_write_expanded_commands_to_stderr() {
+    let mut env = new_test_env();
+    env.set_option(ShellOption::XTrace, true);
+    env.set_var(Arc::new("PS4".to_owned()), Arc::new("++ ".to_owned()));
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    let stderr = env.read_all(pipe.reader);
+
+    let future = simple_command_with_resolution::<MockRedirect<_>, String, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("missing".to_owned()))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Split(vec![
+                "foo".to_owned(),
+                "bar".to_owned(),
+            ]))),
+            RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Open(
+                2,
+                pipe.writer,
+                Permissions::Write,
+            ))),
+        ]
+        .into_iter(),
+        CommandResolution::BuiltinOnly,
+        &mut env,
+    );
+
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+    drop(env);
+
+    let msg = stderr.await.unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&msg),
+        "++ missing foo bar\nmissing: command not found\n"
+    );
+}
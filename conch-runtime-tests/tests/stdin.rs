@@ -0,0 +1,56 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::spawn::with_stdin_bytes;
+use std::sync::{Arc, Mutex};
+
+mod support;
+pub use self::support::*;
+
+/// Reads all of its standard input into a shared buffer.
+#[derive(Debug, Clone, Default)]
+struct ReadStdin(Arc<Mutex<Vec<u8>>>);
+
+#[async_trait::async_trait]
+impl<E: ?Sized + Send> Spawn<E> for ReadStdin
+where
+    E: AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle> + Send,
+{
+    type Error = MockErr;
+
+    async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        let fd = env
+            .file_desc(conch_runtime::STDIN_FILENO)
+            .expect("failed to get stdin")
+            .0
+            .clone();
+
+        let data = env.read_all(fd.into()).await.expect("failed to read stdin");
+        *self.0.lock().unwrap() = data;
+        Ok(Box::pin(async { ExitStatus::Code(42) }))
+    }
+}
+
+#[tokio::test]
+async fn should_feed_data_to_stdin() {
+    let cmd = ReadStdin::default();
+
+    let env = new_env();
+    let future = with_stdin_bytes(b"hello\0world\n".to_vec(), cmd.clone(), &env);
+    drop(env);
+
+    assert_eq!(ExitStatus::Code(42), future.await.expect("future failed"));
+    assert_eq!(*cmd.0.lock().unwrap(), b"hello\0world\n");
+}
+
+#[tokio::test]
+async fn should_not_wait_for_unread_data() {
+    let data = vec![b'x'; 1024 * 1024];
+
+    let env = new_env();
+    let future = with_stdin_bytes(data, mock_status(ExitStatus::Code(5)), &env);
+    drop(env);
+
+    assert_eq!(ExitStatus::Code(5), future.await.expect("future failed"));
+}
@@ -37,3 +37,13 @@ async fn should_terminate_on_fatal_errors_but_swallow_them() {
     let cmds = &[mock_error(true), mock_panic("should not run")];
     assert_eq!(EXIT_ERROR, subshell(sequence_slice(cmds), &new_env()).await);
 }
+
+#[tokio::test]
+async fn should_resolve_exit_requests_to_their_status() {
+    let exit = ExitStatus::Code(42);
+    let cmds = &[
+        MockCmd::Error(MockErr::ControlFlow(ControlFlow::Exit(exit))),
+        mock_panic("should not run"),
+    ];
+    assert_eq!(exit, subshell(sequence_slice(cmds), &new_env()).await);
+}
@@ -1,7 +1,11 @@
 #![deny(rust_2018_idioms)]
 use conch_runtime;
 
-use conch_runtime::spawn::substitution;
+use conch_runtime::spawn::{
+    capture_output, capture_output_chunks, substitution, Output, OutputChunk, OutputStream,
+};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::mpsc;
 
 mod support;
 pub use self::support::*;
@@ -75,3 +79,130 @@ async fn should_trim_trailing_newlines() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn bytes_should_preserve_output_exactly() {
+    let cmds = vec![
+        MockOutCmd::OutBytes(b"hello\0\xff"),
+        MockOutCmd::Cmd(mock_error(false)),
+        MockOutCmd::Out("world\r\n\n"),
+    ];
+
+    let env = new_env();
+    let future = substitution_bytes(sequence_slice(&cmds), &env);
+    drop(env);
+
+    assert_eq!(
+        &b"hello\0\xffworld\r\n\n"[..],
+        &*future.await.expect("future failed")
+    );
+}
+
+#[tokio::test]
+async fn should_replace_invalid_utf8() {
+    let cmds = vec![MockOutCmd::OutBytes(b"hello\xff\n")];
+
+    let env = new_env();
+    let future = substitution(sequence_slice(&cmds), &env);
+    drop(env);
+
+    assert_eq!("hello\u{FFFD}", future.await.expect("future failed"));
+}
+
+#[tokio::test]
+async fn capture_output_should_capture_stdout_and_status() {
+    let cmds = vec![
+        MockOutCmd::Out("hello\n"),
+        MockOutCmd::Cmd(mock_status(ExitStatus::Code(5))),
+    ];
+
+    let env = new_env();
+    let future = capture_output(sequence_slice(&cmds), &env);
+    drop(env);
+
+    let expected = Output {
+        stdout: b"hello\n".to_vec(),
+        stderr: Vec::new(),
+        status: ExitStatus::Code(5),
+    };
+    assert_eq!(expected, future.await.expect("future failed"));
+}
+
+#[tokio::test]
+async fn capture_output_should_capture_reported_errors() {
+    let cmds = vec![
+        MockOutCmd::Out("hello"),
+        MockOutCmd::Cmd(mock_error(true)),
+        MockOutCmd::Out("world"),
+    ];
+
+    let env = new_env();
+    let future = capture_output(sequence_slice(&cmds), &env);
+    drop(env);
+
+    let output = future.await.expect("future failed");
+    assert_eq!(output.stdout, b"hello");
+    assert!(!output.stderr.is_empty());
+    assert_eq!(output.status, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn capture_output_chunks_should_tag_streams() {
+    let cmds = vec![
+        MockOutCmd::Out("hello"),
+        MockOutCmd::Out("world"),
+        MockOutCmd::Cmd(mock_error(true)),
+    ];
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let env = new_env();
+    let future = capture_output_chunks(sequence_slice(&cmds), &env, tx);
+    drop(env);
+
+    assert_eq!(EXIT_ERROR, future.await.expect("future failed"));
+
+    let mut chunks = Vec::<OutputChunk>::new();
+    while let Some(chunk) = rx.recv().await {
+        chunks.push(chunk);
+    }
+
+    let stdout = chunks
+        .iter()
+        .filter(|chunk| chunk.stream == OutputStream::Stdout)
+        .flat_map(|chunk| chunk.data.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(stdout, b"helloworld");
+
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.stream == OutputStream::Stderr && !chunk.data.is_empty()));
+    assert!(chunks
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+}
+
+#[tokio::test]
+async fn capture_output_chunks_should_timestamp_with_env_clock() {
+    let cmds = vec![MockOutCmd::Out("hello"), MockOutCmd::Out("world")];
+    let time = UNIX_EPOCH + Duration::from_secs(1234);
+
+    let clock = MockClockEnv::new();
+    clock.set_system_time(time);
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.dynamic_var_env = DynamicVarEnv::with_sources(clock, RandomEnv::new());
+    let env = DefaultEnvArc::with_config(cfg);
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let future = capture_output_chunks(sequence_slice(&cmds), &env, tx);
+    drop(env);
+
+    assert_eq!(EXIT_SUCCESS, future.await.expect("future failed"));
+
+    let mut chunks = Vec::<OutputChunk>::new();
+    while let Some(chunk) = rx.recv().await {
+        chunks.push(chunk);
+    }
+
+    assert!(!chunks.is_empty());
+    assert!(chunks.iter().all(|chunk| chunk.timestamp == time));
+}
@@ -69,6 +69,9 @@ pub enum MockErr {
     ExpansionError(#[from] ExpansionError),
     RedirectionError(#[source] Arc<RedirectionError>),
     CommandError(#[source] Arc<CommandError>),
+    ControlFlow(#[from] ControlFlow),
+    StackOverflow(#[from] StackOverflowError),
+    NestingLimit(#[from] NestingLimitError),
 }
 
 impl conch_runtime::error::IsFatalError for MockErr {
@@ -78,13 +81,24 @@ impl conch_runtime::error::IsFatalError for MockErr {
             MockErr::ExpansionError(ref e) => e.is_fatal(),
             MockErr::RedirectionError(ref e) => e.is_fatal(),
             MockErr::CommandError(ref e) => e.is_fatal(),
+            MockErr::ControlFlow(_) | MockErr::StackOverflow(_) | MockErr::NestingLimit(_) => true,
+        }
+    }
+
+    fn control_flow(&self) -> Option<ControlFlow> {
+        match *self {
+            MockErr::ControlFlow(flow) => Some(flow),
+            _ => None,
         }
     }
 }
 
 impl From<RuntimeError> for MockErr {
     fn from(err: RuntimeError) -> Self {
-        MockErr::Fatal(err.is_fatal())
+        match err.control_flow() {
+            Some(flow) => MockErr::ControlFlow(flow),
+            None => MockErr::Fatal(err.is_fatal()),
+        }
     }
 }
 
@@ -158,6 +172,10 @@ pub fn mock_word_fields(fields: Fields<String>) -> MockWord {
     MockWord::Fields(fields)
 }
 
+pub fn mock_word_literal(literal: &str) -> MockWord {
+    MockWord::Literal(literal.to_owned())
+}
+
 pub fn mock_word_error(fatal: bool) -> MockWord {
     MockWord::Error(MockErr::Fatal(fatal))
 }
@@ -178,6 +196,7 @@ pub fn mock_word_panic(msg: &'static str) -> MockWord {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MockWord {
     Fields(Fields<String>),
+    Literal(String),
     Error(MockErr),
     AssertCfg(WordEvalConfig, Option<Fields<String>>),
     Panic(&'static str),
@@ -202,6 +221,7 @@ where
 
         let fields = match self {
             MockWord::Fields(f) => f.clone(),
+            MockWord::Literal(s) => Fields::Single(s.clone()),
             MockWord::AssertCfg(_, f) => f.clone().unwrap_or(Fields::Zero),
             MockWord::Error(e) => return Err(e.clone()),
             MockWord::Panic(msg) => panic!("{}", msg),
@@ -209,6 +229,13 @@ where
 
         Ok(Box::pin(async move { fields }))
     }
+
+    fn as_literal(&self) -> Option<&str> {
+        match self {
+            MockWord::Literal(s) => Some(s),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -248,6 +275,7 @@ impl<E: ?Sized> ParamEval<E> for MockParam {
 #[derive(Debug, Clone)]
 pub enum MockOutCmd {
     Out(&'static str),
+    OutBytes(&'static [u8]),
     Cmd(MockCmd),
 }
 
@@ -263,7 +291,8 @@ where
     async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
         match *self {
             MockOutCmd::Cmd(ref cmd) => cmd.spawn(env).await,
-            MockOutCmd::Out(ref msg) => {
+            MockOutCmd::Out(msg) => MockOutCmd::OutBytes(msg.as_bytes()).spawn(env).await,
+            MockOutCmd::OutBytes(ref msg) => {
                 let fd = env
                     .file_desc(STDOUT_FILENO)
                     .expect("failed to get stdout")
@@ -271,7 +300,7 @@ where
                     .clone()
                     .into();
 
-                env.write_all(fd, msg.as_bytes().into())
+                env.write_all(fd, msg[..].into())
                     .await
                     .expect("failed to write all");
 
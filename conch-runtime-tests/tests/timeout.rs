@@ -0,0 +1,46 @@
+#![deny(rust_2018_idioms)]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn should_propagate_result_if_completed_in_time() {
+    let mut env = ();
+    let duration = Duration::from_secs(10);
+
+    let exit = ExitStatus::Code(42);
+    assert_eq!(
+        with_timeout(duration, mock_status(exit), &mut env).await,
+        Ok(exit)
+    );
+
+    assert_eq!(
+        with_timeout(duration, mock_error(true), &mut env).await,
+        Err(MockErr::Fatal(true))
+    );
+}
+
+#[tokio::test]
+async fn should_cancel_command_and_return_timeout_error() {
+    struct MockNeverFinishes;
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> Spawn<E> for MockNeverFinishes {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            Ok(Box::pin(pending()))
+        }
+    }
+
+    let duration = Duration::from_millis(10);
+    assert_eq!(
+        with_timeout(duration, MockNeverFinishes, &mut ()).await,
+        Err(MockErr::CommandError(Arc::new(CommandError::Timeout(
+            duration
+        ))))
+    );
+}
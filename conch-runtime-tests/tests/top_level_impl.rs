@@ -33,3 +33,108 @@ async fn smoke() {
 
     assert_eq!(EXIT_SUCCESS, future.await);
 }
+
+type Cmd = ast::AtomicTopLevelCommand<Arc<String>>;
+type Word = ast::AtomicTopLevelWord<Arc<String>>;
+
+fn nested_braces(depth: usize) -> Cmd {
+    let mut cmds = vec![];
+    for _ in 0..depth {
+        let cmd = ast::AtomicTopLevelCommand(ast::Command::List(ast::AtomicCommandList {
+            first: ast::ListableCommand::Single(ast::PipeableCommand::Compound(Box::new(
+                ast::CompoundCommand {
+                    kind: ast::CompoundCommandKind::Brace(cmds),
+                    io: vec![],
+                },
+            ))),
+            rest: vec![],
+        }));
+
+        cmds = vec![cmd];
+    }
+
+    cmds.pop().expect("depth must be positive")
+}
+
+/// Generates `${UNSET:-${UNSET:-...x}}` nested `depth` levels deep.
+fn nested_defaults(depth: usize) -> Word {
+    let mut word = ast::AtomicTopLevelWord(ast::ComplexWord::Single(ast::Word::Simple(
+        ast::SimpleWord::Literal(Arc::new("x".to_owned())),
+    )));
+
+    for _ in 0..depth {
+        let subst = ast::ParameterSubstitution::Default(
+            true,
+            ast::Parameter::Var(Arc::new("UNSET".to_owned())),
+            Some(word),
+        );
+
+        word = ast::AtomicTopLevelWord(ast::ComplexWord::Single(ast::Word::Simple(
+            ast::SimpleWord::Subst(Box::new(subst)),
+        )));
+    }
+
+    word
+}
+
+#[test]
+fn deeply_nested_compound_commands_should_not_overflow_the_stack() {
+    let cmd = nested_braces(200);
+
+    std::thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(move || {
+            let mut rt = tokio::runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()
+                .expect("failed to create runtime");
+
+            rt.block_on(async move {
+                let mut env = new_env_with_no_fds();
+                let future = cmd.spawn(&mut env).await.unwrap();
+                assert_eq!(EXIT_SUCCESS, future.await);
+            });
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn compound_commands_nested_beyond_the_limit_should_error() {
+    let mut env = new_env_with_no_fds();
+    env.set_max_nesting_depth(Some(10));
+
+    let future = nested_braces(10).spawn(&mut env).await.unwrap();
+    assert_eq!(EXIT_SUCCESS, future.await);
+    assert_eq!(env.nesting_depth(), 0);
+
+    match nested_braces(11).spawn(&mut env).await {
+        Err(e) => assert_eq!(
+            e,
+            RuntimeError::NestingLimit(NestingLimitError { max_depth: 10 })
+        ),
+        Ok(_) => panic!("unexpected success"),
+    }
+    assert_eq!(env.nesting_depth(), 0);
+}
+
+#[tokio::test]
+async fn substitutions_nested_beyond_the_limit_should_error() {
+    let mut env = new_env_with_no_fds();
+    env.set_max_nesting_depth(Some(10));
+
+    let fields = nested_defaults(10).eval(&mut env).await.unwrap().await;
+    assert_eq!(fields, Fields::Single(Arc::new("x".to_owned())));
+    assert_eq!(env.nesting_depth(), 0);
+
+    match nested_defaults(11).eval(&mut env).await {
+        Err(e) => assert_eq!(
+            e,
+            RuntimeError::NestingLimit(NestingLimitError { max_depth: 10 })
+        ),
+        Ok(_) => panic!("unexpected success"),
+    }
+    assert_eq!(env.nesting_depth(), 0);
+}
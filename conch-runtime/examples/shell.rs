@@ -71,7 +71,7 @@ async fn main() {
 fn exit_with_status(status: ExitStatus) -> ! {
     let status = match status {
         ExitStatus::Code(n) => n,
-        ExitStatus::Signal(n) => n + 128,
+        ExitStatus::Signal(n) | ExitStatus::CoreDumped(n) => n + 128,
     };
 
     // Have our shell exit with the result of the last command
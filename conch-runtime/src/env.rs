@@ -4,47 +4,102 @@
 use futures_core::future::BoxFuture;
 use std::error::Error;
 
+mod alias;
 mod args;
 mod async_io;
 pub mod builtin;
+mod clock;
+mod control_flow;
 mod cur_dir;
+mod dir_stack;
+mod dynamic_var;
 mod env_impl;
 mod executable;
 mod fd;
 mod fd_manager;
 mod fd_opener;
 mod func;
+mod history;
+pub mod introspect;
+mod job;
 mod last_status;
+mod nesting;
+mod options;
+mod persistent_var;
+mod pipe_status;
+mod plan;
+mod policy;
+mod process_id;
+pub mod prompt;
+mod random;
 mod restorer;
+mod snapshot;
 mod string_wrapper;
+mod temp_file;
 mod var;
+mod var_watch;
 
+pub use self::alias::{AliasEnv, AliasEnvironment};
 pub use self::args::{
-    ArgsEnv, ArgumentsEnvironment, SetArgumentsEnvironment, ShiftArgumentsEnvironment,
+    ArgsEnv, ArgsGuard, ArgumentsEnvironment, SetArgumentsEnvironment, ShiftArgumentsEnvironment,
 };
 pub use self::async_io::{ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, TokioAsyncIoEnv};
 pub use self::builtin::{Builtin, BuiltinEnvironment};
+#[cfg(feature = "testing")]
+pub use self::clock::MockClockEnv;
+pub use self::clock::{ClockEnvironment, SystemClockEnv};
+pub use self::control_flow::{ControlFlowEnv, ControlFlowEnvironment};
 pub use self::cur_dir::{
     ChangeWorkingDirectoryEnvironment, VirtualWorkingDirEnv, WorkingDirectoryEnvironment,
 };
+pub use self::dir_stack::{DirStackEnv, DirStackEnvironment};
+pub use self::dynamic_var::{DynamicVarEnv, DynamicVariableEnvironment};
 pub use self::env_impl::{
     DefaultEnv, DefaultEnvArc, DefaultEnvConfig, DefaultEnvConfigArc, Env, EnvConfig,
 };
-pub use self::executable::{ExecutableData, ExecutableEnvironment, TokioExecEnv};
+pub use self::executable::{
+    CommandNotFoundExecEnv, EnvVarFilterExecEnv, ExecutableData, ExecutableEnvironment,
+    ProcessGroup, Resource, ResourceLimit, TokioExecEnv,
+};
+#[cfg(feature = "testing")]
+pub use self::executable::{MockExecEnv, MockExecInvocation, MockExecOutput};
 pub use self::fd::{FileDescEnv, FileDescEnvironment};
 pub use self::fd_manager::{
     FileDescManagerEnv, FileDescManagerEnvironment, TokioFileDescManagerEnv,
 };
 pub use self::fd_opener::{ArcFileDescOpenerEnv, FileDescOpener, FileDescOpenerEnv, Pipe};
 pub use self::func::{
-    FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment, UnsetFunctionEnvironment,
+    FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment, FunctionSource,
+    UnsetFunctionEnvironment,
 };
+pub use self::history::{HistoryEnv, HistoryEnvironment};
+pub use self::job::{Job, JobEnv, JobEnvironment, JobState};
 pub use self::last_status::{LastStatusEnv, LastStatusEnvironment};
-pub use self::restorer::{EnvRestorer, RedirectEnvRestorer, Restorer, VarEnvRestorer};
+#[cfg(feature = "conch-parser")]
+pub(crate) use self::nesting::NestingGuard;
+pub use self::nesting::{NestingEnv, NestingEnvironment};
+pub use self::options::{ShellOption, ShellOptionsEnv, ShellOptionsEnvironment};
+pub use self::persistent_var::{FileVarStore, PersistentVarEnv, VarStore};
+pub use self::pipe_status::{
+    LastPipelineStatusEnv, LastPipelineStatusEnvironment, PipelineStatusRecorder,
+};
+pub use self::plan::{ExecutionPlan, ExecutionPlanEnv, PlannedCommand, PlannedOpen, PlannedStep};
+pub use self::policy::{PolicyEnv, SecurityPolicy};
+pub use self::process_id::{ProcessIdEnv, ProcessIdEnvironment};
+#[cfg(feature = "testing")]
+pub use self::random::MockRandomEnv;
+pub use self::random::{RandomEnv, RandomEnvironment, RANDOM_MAX};
+pub use self::restorer::{
+    EnvRestorer, RedirectEnvRestorer, RedirectRestorer, Restorer, VarEnvRestorer, VarRestorer,
+};
+pub use self::snapshot::{EnvSnapshot, SnapshotVar};
 pub use self::string_wrapper::StringWrapper;
+pub use self::temp_file::{TempFile, TempFileEnvironment, TempPath};
 pub use self::var::{
-    ExportedVariableEnvironment, UnsetVariableEnvironment, VarEnv, VariableEnvironment,
+    ExportedVariableEnvironment, StrKey, UnsetVariableEnvironment, VarAttributes, VarEnv,
+    VariableAttributesEnvironment, VariableEnvironment,
 };
+pub use self::var_watch::{VarWatcher, WatchedVarEnv};
 
 /// An interface for checking if the current environment is an interactive one.
 pub trait IsInteractiveEnvironment {
@@ -0,0 +1,123 @@
+use crate::env::SubEnvironment;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// An interface for defining, removing, and looking up command aliases.
+pub trait AliasEnvironment {
+    /// Get the value of a particularly named alias if it was defined.
+    fn alias(&self, name: &str) -> Option<&str>;
+
+    /// Define an alias with a given name, replacing any previous definition.
+    fn set_alias(&mut self, name: String, value: String);
+
+    /// Removes the definition of an alias, returning `true` if it was defined.
+    fn unalias(&mut self, name: &str) -> bool;
+
+    /// Removes the definitions of all aliases.
+    fn unalias_all(&mut self);
+
+    /// Get all currently defined aliases, sorted by name.
+    fn aliases(&self) -> Vec<(&str, &str)>;
+
+    /// Indicates if aliases should be expanded before executing simple commands.
+    fn expand_aliases(&self) -> bool;
+
+    /// Enables or disables expanding aliases before executing simple commands.
+    fn set_expand_aliases(&mut self, enabled: bool);
+}
+
+impl<'a, T: ?Sized + AliasEnvironment> AliasEnvironment for &'a mut T {
+    fn alias(&self, name: &str) -> Option<&str> {
+        (**self).alias(name)
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        (**self).set_alias(name, value);
+    }
+
+    fn unalias(&mut self, name: &str) -> bool {
+        (**self).unalias(name)
+    }
+
+    fn unalias_all(&mut self) {
+        (**self).unalias_all();
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        (**self).aliases()
+    }
+
+    fn expand_aliases(&self) -> bool {
+        (**self).expand_aliases()
+    }
+
+    fn set_expand_aliases(&mut self, enabled: bool) {
+        (**self).set_expand_aliases(enabled);
+    }
+}
+
+/// An environment module for defining and looking up command aliases.
+///
+/// Alias expansion is disabled by default, as is the case for
+/// non-interactive shells.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AliasEnv {
+    aliases: Arc<BTreeMap<String, String>>,
+    expand_aliases: bool,
+}
+
+impl AliasEnv {
+    /// Constructs a new `AliasEnv` with no defined aliases.
+    pub fn new() -> Self {
+        Self {
+            aliases: Arc::new(BTreeMap::new()),
+            expand_aliases: false,
+        }
+    }
+}
+
+impl AliasEnvironment for AliasEnv {
+    fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        Arc::make_mut(&mut self.aliases).insert(name, value);
+    }
+
+    fn unalias(&mut self, name: &str) -> bool {
+        if self.aliases.contains_key(name) {
+            Arc::make_mut(&mut self.aliases).remove(name);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unalias_all(&mut self) {
+        if !self.aliases.is_empty() {
+            self.aliases = Arc::new(BTreeMap::new());
+        }
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    fn expand_aliases(&self) -> bool {
+        self.expand_aliases
+    }
+
+    fn set_expand_aliases(&mut self, enabled: bool) {
+        self.expand_aliases = enabled;
+    }
+}
+
+impl SubEnvironment for AliasEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
@@ -1,6 +1,7 @@
 use crate::env::SubEnvironment;
 use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::fmt;
 use std::sync::Arc;
 
 /// An interface for getting shell and function arguments.
@@ -16,6 +17,10 @@ pub trait ArgumentsEnvironment {
     /// Get the number of current arguments, NOT including the shell name.
     fn args_len(&self) -> usize;
     /// Get all current arguments as a possibly owned slice.
+    ///
+    /// Anything expanded from the result (e.g. the fields of `"$@"`) is a
+    /// snapshot of the arguments at the time of expansion, and is unaffected
+    /// by any later changes to them (e.g. via `shift` or `set --`).
     fn args(&self) -> Cow<'_, [Self::Arg]>;
 }
 
@@ -65,6 +70,8 @@ pub trait SetArgumentsEnvironment: ArgumentsEnvironment {
     type Args;
     /// Changes the environment's arguments to `new_args` and returns the old arguments.
     fn set_args(&mut self, new_args: Self::Args) -> Self::Args;
+    /// Changes the environment's name (i.e. `$0`) to `new_name` and returns the old name.
+    fn set_name(&mut self, new_name: Self::Arg) -> Self::Arg;
 }
 
 impl<'a, T: ?Sized + SetArgumentsEnvironment> SetArgumentsEnvironment for &'a mut T {
@@ -73,6 +80,84 @@ impl<'a, T: ?Sized + SetArgumentsEnvironment> SetArgumentsEnvironment for &'a mu
     fn set_args(&mut self, new_args: Self::Args) -> Self::Args {
         (**self).set_args(new_args)
     }
+
+    fn set_name(&mut self, new_name: Self::Arg) -> Self::Arg {
+        (**self).set_name(new_name)
+    }
+}
+
+/// A guard which temporarily overrides the name (i.e. `$0`) and/or positional
+/// arguments of an environment, such as while running a function or a sourced
+/// script, and restores the original values once dropped.
+///
+/// Since the guard restores the environment even if the work done with it is
+/// cancelled midway, the outer arguments are never left clobbered.
+pub struct ArgsGuard<'a, E: ?Sized + SetArgumentsEnvironment> {
+    env: &'a mut E,
+    name: Option<E::Arg>,
+    args: Option<E::Args>,
+}
+
+impl<'a, E: ?Sized + SetArgumentsEnvironment> ArgsGuard<'a, E> {
+    /// Wraps the environment without overriding anything yet.
+    pub fn new(env: &'a mut E) -> Self {
+        Self {
+            env,
+            name: None,
+            args: None,
+        }
+    }
+
+    /// Overrides the environment's name, which will be restored to its
+    /// original value when the guard is dropped.
+    pub fn set_name(&mut self, name: E::Arg) {
+        let old = self.env.set_name(name);
+        self.name.get_or_insert(old);
+    }
+
+    /// Overrides the environment's positional arguments, which will be
+    /// restored to their original values when the guard is dropped.
+    pub fn set_args(&mut self, args: E::Args) {
+        let old = self.env.set_args(args);
+        self.args.get_or_insert(old);
+    }
+
+    /// Gets a reference to the wrapped environment.
+    pub fn get(&self) -> &E {
+        self.env
+    }
+
+    /// Gets a mutable reference to the wrapped environment.
+    pub fn get_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+impl<'a, E> fmt::Debug for ArgsGuard<'a, E>
+where
+    E: ?Sized + fmt::Debug + SetArgumentsEnvironment,
+    E::Arg: fmt::Debug,
+    E::Args: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ArgsGuard")
+            .field("env", &self.env)
+            .field("name", &self.name)
+            .field("args", &self.args)
+            .finish()
+    }
+}
+
+impl<'a, E: ?Sized + SetArgumentsEnvironment> Drop for ArgsGuard<'a, E> {
+    fn drop(&mut self) {
+        if let Some(args) = self.args.take() {
+            self.env.set_args(args);
+        }
+
+        if let Some(name) = self.name.take() {
+            self.env.set_name(name);
+        }
+    }
 }
 
 /// An interface for shifting positional shell and function arguments.
@@ -190,6 +275,11 @@ impl<T: Clone> SetArgumentsEnvironment for ArgsEnv<T> {
     fn set_args(&mut self, new_args: Self::Args) -> Self::Args {
         ::std::mem::replace(&mut self.args, new_args)
     }
+
+    fn set_name(&mut self, new_name: Self::Arg) -> Self::Arg {
+        let old = ::std::mem::replace(&mut self.name, Arc::new(new_name));
+        Arc::try_unwrap(old).unwrap_or_else(|old| (*old).clone())
+    }
 }
 
 impl<T: Clone> ShiftArgumentsEnvironment for ArgsEnv<T> {
@@ -284,6 +374,45 @@ mod tests {
         assert_eq!(env.args(), args_old);
     }
 
+    #[test]
+    fn test_set_name() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1"]);
+        let copy = env.sub_env();
+
+        assert_eq!(env.set_name("script"), "shell");
+        assert_eq!(env.name(), &"script");
+        assert_eq!(env.arg(0), Some(&"script"));
+        assert_eq!(env.args(), vec!("1"));
+        assert_eq!(copy.name(), &"shell");
+    }
+
+    #[test]
+    fn test_args_guard_restores_name_and_args() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2"]);
+
+        {
+            let mut guard = ArgsGuard::new(&mut env);
+            guard.set_name("script");
+            guard.set_args(Arc::new(VecDeque::from(vec!["3"])));
+            guard.set_args(Arc::new(VecDeque::from(vec!["4", "5"])));
+            guard.get_mut().shift_args(1);
+
+            assert_eq!(guard.get().name(), &"script");
+            assert_eq!(guard.get().args(), vec!("5"));
+        }
+
+        assert_eq!(env.name(), &"shell");
+        assert_eq!(env.args(), vec!("1", "2"));
+
+        {
+            let mut guard = ArgsGuard::new(&mut env);
+            guard.set_args(Arc::new(VecDeque::new()));
+        }
+
+        assert_eq!(env.name(), &"shell");
+        assert_eq!(env.args(), vec!("1", "2"));
+    }
+
     #[test]
     fn test_shift_args() {
         let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2", "3", "4", "5", "6"]);
@@ -1,4 +1,5 @@
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::Cow;
 use std::io;
 
@@ -16,6 +17,17 @@ pub trait AsyncIoEnvironment {
     /// Asynchronously read *all* data from the specified handle.
     fn read_all(&mut self, fd: Self::IoHandle) -> BoxFuture<'static, io::Result<Vec<u8>>>;
 
+    /// Asynchronously read all data from the specified handle, yielding it
+    /// in chunks as soon as it becomes available.
+    ///
+    /// By default, all data is read (via `read_all`) and yielded as a single
+    /// chunk, thus implementations should override this if they are able to
+    /// read data incrementally.
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        let data = self.read_all(fd);
+        Box::pin(futures_util::stream::once(data))
+    }
+
     /// Asynchronously write `data` into the specified handle.
     fn write_all<'a>(
         &mut self,
@@ -40,6 +52,10 @@ where
         (**self).read_all(fd)
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        (**self).read_chunks(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -1,10 +1,14 @@
 use crate::env::{AsyncIoEnvironment, SubEnvironment};
 use crate::io::FileDesc;
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::Cow;
 use std::io;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// The maximum size of each chunk yielded by `read_chunks`.
+const CHUNK_SIZE: usize = 8 * 1024;
+
 /// An environment implementation which leverages Tokio formanages async
 /// operations on file descriptor handles.
 #[derive(Default, Debug, Clone)]
@@ -86,6 +90,28 @@ impl AsyncIo {
     }
 }
 
+/// Reads the next chunk of data, or `None` if the end of the data was reached
+/// (or an error was previously encountered).
+async fn read_chunk(io: Option<AsyncIo>) -> Option<(io::Result<Vec<u8>>, Option<AsyncIo>)> {
+    let mut io = io?;
+    let mut buf = vec![0; CHUNK_SIZE];
+
+    let read = match io {
+        #[cfg(unix)]
+        AsyncIo::PollEvented(ref mut fd) => fd.read(&mut buf).await,
+        AsyncIo::File(ref mut fd) => fd.read(&mut buf).await,
+    };
+
+    match read {
+        Ok(0) => None,
+        Ok(n) => {
+            buf.truncate(n);
+            Some((Ok(buf), Some(io)))
+        }
+        Err(e) => Some((Err(e), None)),
+    }
+}
+
 async fn do_write_all(fd: FileDesc, data: Cow<'_, [u8]>) -> io::Result<()> {
     match AsyncIo::new(fd) {
         #[cfg(unix)]
@@ -111,6 +137,13 @@ impl AsyncIoEnvironment for TokioAsyncIoEnv {
         })
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        Box::pin(futures_util::stream::unfold(
+            Some(AsyncIo::new(fd)),
+            read_chunk,
+        ))
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -1,6 +1,7 @@
 use crate::env::{AsyncIoEnvironment, SubEnvironment};
 use crate::io::{FileDesc, FileDescWrapper};
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::Cow;
 use std::io;
 use std::sync::Arc;
@@ -43,6 +44,13 @@ where
         }
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        match fd.try_unwrap() {
+            Ok(fd) => self.async_io.read_chunks(fd),
+            Err(e) => Box::pin(futures_util::stream::once(async { Err(e) })),
+        }
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
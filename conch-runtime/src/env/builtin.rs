@@ -2,16 +2,24 @@
 //! and provides a default implementations.
 
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
-    FileDescEnvironment, RedirectEnvRestorer, ShiftArgumentsEnvironment, StringWrapper,
-    SubEnvironment, VarEnvRestorer, VariableEnvironment,
+    AliasEnvironment, ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
+    ControlFlowEnvironment, DirStackEnvironment, DynamicVariableEnvironment, ExecutableEnvironment,
+    ExportedVariableEnvironment, FileDescEnvironment, FunctionFrameEnvironment, HistoryEnvironment,
+    JobEnvironment, LastStatusEnvironment, RedirectEnvRestorer, SetArgumentsEnvironment,
+    ShellOptionsEnvironment, ShiftArgumentsEnvironment, StrKey, StringWrapper, SubEnvironment,
+    UnsetFunctionEnvironment, UnsetVariableEnvironment, VarEnvRestorer,
+    VariableAttributesEnvironment,
 };
+use crate::io::FileDescWrapper;
 use crate::spawn::builtin;
 use crate::ExitStatus;
 use futures_core::future::BoxFuture;
 use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::marker::PhantomData;
+use std::sync::Arc;
+use void::Void;
 
 /// An interface for builtin utilities which can be spawned with some arguments.
 ///
@@ -46,6 +54,23 @@ where
         'life1: 'async_trait,
         Self: 'async_trait,
         A: 'async_trait;
+
+    /// Returns the exit status of the builtin utility if running it would
+    /// have no effect other than exiting with that status (e.g. `true`).
+    ///
+    /// Callers may use this to skip spawning the utility altogether, which
+    /// speeds up loop-heavy scripts. Commands made up solely of literal words
+    /// are not evaluated at all, otherwise any arguments or redirections of
+    /// the command are still evaluated as usual.
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        None
+    }
+
+    /// Returns whether the builtin utility should be used even if an
+    /// executable of the same name can be found via `$PATH`.
+    fn precedence(&self) -> BuiltinPrecedence {
+        BuiltinPrecedence::BeforePath
+    }
 }
 
 impl<'a, A, R, E, T> BuiltinUtility<'a, A, R, E> for &'_ T
@@ -67,6 +92,47 @@ where
     {
         (**self).spawn_builtin(args, restorer)
     }
+
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        (**self).trivial_status()
+    }
+
+    fn precedence(&self) -> BuiltinPrecedence {
+        (**self).precedence()
+    }
+}
+
+/// An uninhabited builtin utility, i.e. one which can never be registered.
+impl<'a, A, R, E> BuiltinUtility<'a, A, R, E> for Void
+where
+    R: ?Sized,
+    E: 'a + ?Sized,
+{
+    fn spawn_builtin<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        _args: A,
+        _restorer: &'life1 mut R,
+    ) -> BoxFuture<'async_trait, BoxFuture<'static, ExitStatus>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+        A: 'async_trait,
+    {
+        void::unreachable(*self)
+    }
+}
+
+/// Determines whether a builtin utility takes precedence over any executable
+/// of the same name which can be found via `$PATH`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinPrecedence {
+    /// The builtin utility is used even if an executable of the same name exists.
+    #[default]
+    BeforePath,
+    /// The builtin utility is only used if no executable of the same name
+    /// can be found via `$PATH`.
+    AfterPath,
 }
 
 /// An interface for getting shell builtin utilities.
@@ -78,6 +144,14 @@ pub trait BuiltinEnvironment {
 
     /// Lookup and get a particular builtin by its name.
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin>;
+
+    /// Lists the names of all available builtin utilities, sorted.
+    ///
+    /// Returns an empty list by default, for environments which cannot
+    /// enumerate their builtin utilities.
+    fn utility_names(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 impl<'a, T: ?Sized + BuiltinEnvironment> BuiltinEnvironment for &'a T {
@@ -87,26 +161,63 @@ impl<'a, T: ?Sized + BuiltinEnvironment> BuiltinEnvironment for &'a T {
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
         (**self).builtin(name)
     }
+
+    fn utility_names(&self) -> Vec<String> {
+        (**self).utility_names()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BuiltinKind {
+    Alias,
+    Bg,
+    Break,
     Cd,
     Colon,
+    Continue,
+    Declare,
+    Dirs,
     Echo,
+    Env,
+    Exec,
+    Exit,
     False,
+    Fc,
+    Fg,
+    History,
+    Jobs,
+    Kill,
+    #[cfg(feature = "conch-parser")]
+    Let,
+    Local,
+    Popd,
+    Pushd,
     Pwd,
+    Return,
+    Set,
     Shift,
     True,
+    Unalias,
+    Unset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Utility<B> {
+    Default(BuiltinKind),
+    Custom(B),
 }
 
 /// Represents a shell builtin utility managed by a `BuiltinEnv` instance.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Builtin {
-    kind: BuiltinKind,
+pub struct Builtin<B = Void> {
+    utility: Utility<B>,
+    precedence: BuiltinPrecedence,
 }
 
 /// An environment module for getting shell builtin utilities.
+///
+/// See `CustomBuiltinEnv` for registering custom utilities, or configuring the
+/// precedence of any utility over executables found via `$PATH`.
 pub struct BuiltinEnv<T> {
     phantom: PhantomData<fn(T)>,
 }
@@ -152,33 +263,223 @@ impl<T> SubEnvironment for BuiltinEnv<T> {
     }
 }
 
+/// An environment module for getting shell builtin utilities, which can be
+/// extended with custom utilities.
+///
+/// Besides the default builtin utilities, custom utilities of type `B` may be
+/// registered by name, which take precedence over any default utility of the
+/// same name. The precedence of any utility over executables found via `$PATH`
+/// may also be configured by name.
+pub struct CustomBuiltinEnv<T, B = Void> {
+    custom: Arc<HashMap<String, B>>,
+    precedence: Arc<HashMap<String, BuiltinPrecedence>>,
+    phantom: PhantomData<fn(T)>,
+}
+
+impl<T, B: Eq> Eq for CustomBuiltinEnv<T, B> {}
+impl<T, B: PartialEq> PartialEq<CustomBuiltinEnv<T, B>> for CustomBuiltinEnv<T, B> {
+    fn eq(&self, other: &CustomBuiltinEnv<T, B>) -> bool {
+        self.custom == other.custom && self.precedence == other.precedence
+    }
+}
+
+impl<T, B> fmt::Debug for CustomBuiltinEnv<T, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut custom = self.custom.keys().collect::<Vec<_>>();
+        custom.sort();
+
+        fmt.debug_struct("CustomBuiltinEnv")
+            .field("custom", &custom)
+            .field("precedence", &self.precedence)
+            .finish()
+    }
+}
+
+impl<T, B> Clone for CustomBuiltinEnv<T, B> {
+    fn clone(&self) -> Self {
+        Self {
+            custom: self.custom.clone(),
+            precedence: self.precedence.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, B> Default for CustomBuiltinEnv<T, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, B> CustomBuiltinEnv<T, B> {
+    /// Construct a new environment.
+    pub fn new() -> Self {
+        Self {
+            custom: Arc::new(HashMap::new()),
+            precedence: Arc::new(HashMap::new()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Register a custom builtin utility, shadowing any previously
+    /// registered or default utility of the same name.
+    pub fn register<N: Into<String>>(&mut self, name: N, builtin: B)
+    where
+        B: Clone,
+    {
+        Arc::make_mut(&mut self.custom).insert(name.into(), builtin);
+    }
+
+    /// Unregister a previously registered custom builtin utility,
+    /// restoring any default utility of the same name.
+    pub fn unregister(&mut self, name: &str)
+    where
+        B: Clone,
+    {
+        if self.custom.contains_key(name) {
+            Arc::make_mut(&mut self.custom).remove(name);
+        }
+    }
+
+    /// Set the precedence of the builtin utility (if any) with the specified
+    /// name over executables of the same name found via `$PATH`.
+    pub fn set_precedence<N: Into<String>>(&mut self, name: N, precedence: BuiltinPrecedence) {
+        Arc::make_mut(&mut self.precedence).insert(name.into(), precedence);
+    }
+}
+
+impl<T, B> SubEnvironment for CustomBuiltinEnv<T, B> {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// The names of all default builtin utilities, sorted.
+const DEFAULT_BUILTIN_NAMES: &[&str] = &[
+    ":",
+    "alias",
+    "bg",
+    "break",
+    "cd",
+    "continue",
+    "declare",
+    "dirs",
+    "echo",
+    "env",
+    "exec",
+    "exit",
+    "false",
+    "fc",
+    "fg",
+    "history",
+    "jobs",
+    "kill",
+    #[cfg(feature = "conch-parser")]
+    "let",
+    "local",
+    "popd",
+    "pushd",
+    "pwd",
+    "return",
+    "set",
+    "shift",
+    "true",
+    "typeset",
+    "unalias",
+    "unset",
+];
+
 fn lookup_builtin(name: &str) -> Option<BuiltinKind> {
     match name {
+        "alias" => Some(BuiltinKind::Alias),
+        "bg" => Some(BuiltinKind::Bg),
+        "break" => Some(BuiltinKind::Break),
         "cd" => Some(BuiltinKind::Cd),
         ":" => Some(BuiltinKind::Colon),
+        "continue" => Some(BuiltinKind::Continue),
+        "declare" | "typeset" => Some(BuiltinKind::Declare),
+        "dirs" => Some(BuiltinKind::Dirs),
         "echo" => Some(BuiltinKind::Echo),
+        "env" => Some(BuiltinKind::Env),
+        "exec" => Some(BuiltinKind::Exec),
+        "exit" => Some(BuiltinKind::Exit),
         "false" => Some(BuiltinKind::False),
+        "fc" => Some(BuiltinKind::Fc),
+        "fg" => Some(BuiltinKind::Fg),
+        "history" => Some(BuiltinKind::History),
+        "jobs" => Some(BuiltinKind::Jobs),
+        "kill" => Some(BuiltinKind::Kill),
+        #[cfg(feature = "conch-parser")]
+        "let" => Some(BuiltinKind::Let),
+        "local" => Some(BuiltinKind::Local),
+        "popd" => Some(BuiltinKind::Popd),
+        "pushd" => Some(BuiltinKind::Pushd),
         "pwd" => Some(BuiltinKind::Pwd),
+        "return" => Some(BuiltinKind::Return),
+        "set" => Some(BuiltinKind::Set),
         "shift" => Some(BuiltinKind::Shift),
         "true" => Some(BuiltinKind::True),
+        "unalias" => Some(BuiltinKind::Unalias),
+        "unset" => Some(BuiltinKind::Unset),
 
         _ => None,
     }
 }
 
-impl<T> BuiltinEnvironment for BuiltinEnv<T>
+impl<T: StringWrapper> BuiltinEnvironment for BuiltinEnv<T> {
+    type BuiltinName = T;
+    type Builtin = Builtin;
+
+    fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
+        Some(Builtin {
+            utility: Utility::Default(lookup_builtin(name.as_str())?),
+            precedence: BuiltinPrecedence::default(),
+        })
+    }
+
+    fn utility_names(&self) -> Vec<String> {
+        DEFAULT_BUILTIN_NAMES
+            .iter()
+            .map(|&name| name.to_owned())
+            .collect()
+    }
+}
+
+impl<T, B> BuiltinEnvironment for CustomBuiltinEnv<T, B>
 where
     T: StringWrapper,
+    B: Clone,
 {
     type BuiltinName = T;
-    type Builtin = Builtin;
+    type Builtin = Builtin<B>;
 
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
-        lookup_builtin(name.as_str()).map(|kind| Builtin { kind })
+        let name = name.as_str();
+        let utility = match self.custom.get(name) {
+            Some(builtin) => Utility::Custom(builtin.clone()),
+            None => Utility::Default(lookup_builtin(name)?),
+        };
+
+        Some(Builtin {
+            utility,
+            precedence: self.precedence.get(name).copied().unwrap_or_default(),
+        })
+    }
+
+    fn utility_names(&self) -> Vec<String> {
+        let mut names = DEFAULT_BUILTIN_NAMES
+            .iter()
+            .map(|&name| name.to_owned())
+            .chain(self.custom.keys().cloned())
+            .collect::<Vec<_>>();
+
+        names.sort();
+        names.dedup();
+        names
     }
 }
 
-impl<'a, A, R, E> BuiltinUtility<'a, A, R, E> for Builtin
+impl<'a, A, R, E, B> BuiltinUtility<'a, A, R, E> for Builtin<B>
 where
     A: Send + IntoIterator,
     A::Item: Send + StringWrapper,
@@ -188,16 +489,34 @@ where
         + ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + ArgumentsEnvironment
         + ChangeWorkingDirectoryEnvironment
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + DirStackEnvironment
+        + ExecutableEnvironment
+        + ExportedVariableEnvironment
         + FileDescEnvironment
-        + VariableEnvironment
-        + ShiftArgumentsEnvironment,
-    E::FileHandle: Clone,
+        + FunctionFrameEnvironment
+        + HistoryEnvironment
+        + JobEnvironment
+        + LastStatusEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + ShiftArgumentsEnvironment
+        + UnsetFunctionEnvironment
+        + UnsetVariableEnvironment
+        + VariableAttributesEnvironment,
+    E::Arg: From<String>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::FnName: From<String>,
     E::IoHandle: Send + From<E::FileHandle>,
-    E::Var: Borrow<String> + From<String>,
-    E::VarName: Borrow<String> + From<String>,
+    E::Var: Clone + Borrow<String> + From<String>,
+    E::VarName: Clone + StrKey + From<String>,
+    B: BuiltinUtility<'a, A, R, E>,
 {
     fn spawn_builtin<'life0, 'life1, 'async_trait>(
         &'life0 self,
@@ -210,16 +529,50 @@ where
         Self: 'async_trait,
         A: 'async_trait,
     {
-        let kind = self.kind;
+        let kind = match self.utility {
+            Utility::Default(kind) => kind,
+            Utility::Custom(ref builtin) => return builtin.spawn_builtin(args, restorer),
+        };
 
         Box::pin(async move {
             let env = restorer.get_mut();
 
             let ret = match kind {
+                BuiltinKind::Alias => builtin::alias(args, env).await,
+                BuiltinKind::Bg => builtin::bg(args, env).await,
+                BuiltinKind::Break => builtin::break_cmd(args, env).await,
                 BuiltinKind::Cd => builtin::cd(args, env).await,
+                BuiltinKind::Continue => builtin::continue_cmd(args, env).await,
+                BuiltinKind::Declare => builtin::declare(args, env).await,
+                BuiltinKind::Dirs => builtin::dirs(args, env).await,
                 BuiltinKind::Echo => builtin::echo(args, env).await,
+                BuiltinKind::Env => builtin::env_cmd(args, env).await,
+                BuiltinKind::Exec => {
+                    let mut args = args.into_iter().peekable();
+                    if args.peek().is_none() {
+                        // Any redirects applied to `exec` should persist
+                        restorer.clear_redirects();
+                    }
+
+                    builtin::exec(args, restorer.get_mut()).await
+                }
+                BuiltinKind::Exit => builtin::exit(args, env).await,
+                BuiltinKind::Fc => builtin::fc(args, env).await,
+                BuiltinKind::Fg => builtin::fg(args, env).await,
+                BuiltinKind::History => builtin::history(args, env).await,
+                BuiltinKind::Jobs => builtin::jobs(args, env).await,
+                BuiltinKind::Kill => builtin::kill(args, env).await,
+                #[cfg(feature = "conch-parser")]
+                BuiltinKind::Let => builtin::let_cmd(args, env).await,
+                BuiltinKind::Local => builtin::local(args, env).await,
+                BuiltinKind::Popd => builtin::popd(args, env).await,
+                BuiltinKind::Pushd => builtin::pushd(args, env).await,
                 BuiltinKind::Pwd => builtin::pwd(args, env).await,
+                BuiltinKind::Return => builtin::return_cmd(args, env).await,
+                BuiltinKind::Set => builtin::set(args, env).await,
                 BuiltinKind::Shift => builtin::shift(args, env).await,
+                BuiltinKind::Unalias => builtin::unalias(args, env).await,
+                BuiltinKind::Unset => builtin::unset(args, env).await,
 
                 BuiltinKind::Colon => Box::pin(async { builtin::colon() }),
                 BuiltinKind::False => Box::pin(async { builtin::false_cmd() }),
@@ -232,4 +585,18 @@ where
             ret
         })
     }
+
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        match self.utility {
+            Utility::Default(BuiltinKind::Colon) => Some(builtin::colon()),
+            Utility::Default(BuiltinKind::False) => Some(builtin::false_cmd()),
+            Utility::Default(BuiltinKind::True) => Some(builtin::true_cmd()),
+            Utility::Default(_) => None,
+            Utility::Custom(ref builtin) => builtin.trivial_status(),
+        }
+    }
+
+    fn precedence(&self) -> BuiltinPrecedence {
+        self.precedence
+    }
 }
@@ -0,0 +1,65 @@
+use crate::env::SubEnvironment;
+use std::time::{Instant, SystemTime};
+
+#[cfg(feature = "testing")]
+mod mock;
+
+#[cfg(feature = "testing")]
+pub use self::mock::MockClockEnv;
+
+/// An interface for querying the current time, e.g. for computing how
+/// long the shell has been running, or for timestamping history entries.
+pub trait ClockEnvironment {
+    /// Get the current point in time.
+    fn now(&self) -> Instant;
+    /// Get the current wall-clock time.
+    fn system_time(&self) -> SystemTime;
+}
+
+impl<'a, T: ?Sized + ClockEnvironment> ClockEnvironment for &'a T {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        (**self).system_time()
+    }
+}
+
+impl<'a, T: ?Sized + ClockEnvironment> ClockEnvironment for &'a mut T {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        (**self).system_time()
+    }
+}
+
+/// A `ClockEnvironment` implementation backed by the system's monotonic and
+/// wall clocks.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SystemClockEnv;
+
+impl SystemClockEnv {
+    /// Construct a new environment.
+    pub fn new() -> Self {
+        SystemClockEnv
+    }
+}
+
+impl ClockEnvironment for SystemClockEnv {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl SubEnvironment for SystemClockEnv {
+    fn sub_env(&self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,67 @@
+use crate::env::{ClockEnvironment, SubEnvironment};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A `ClockEnvironment` implementation whose time only moves forward when
+/// explicitly advanced, allowing for deterministic tests of time-based values.
+///
+/// Both the monotonic and wall-clock times are frozen, and advance together.
+/// All copies of the environment (including sub-environments) share the same
+/// clock, thus advancing any one of them advances all of them.
+#[derive(Debug, Clone)]
+pub struct MockClockEnv {
+    now: Arc<Mutex<(Instant, SystemTime)>>,
+}
+
+impl MockClockEnv {
+    /// Construct a new clock frozen at the current point in time.
+    pub fn new() -> Self {
+        Self::with_start(Instant::now())
+    }
+
+    /// Construct a new clock frozen at the specified point in time.
+    ///
+    /// The wall-clock time is frozen at the current time, unless it is
+    /// changed via `set_system_time`.
+    pub fn with_start(start: Instant) -> Self {
+        Self {
+            now: Arc::new(Mutex::new((start, SystemTime::now()))),
+        }
+    }
+
+    /// Move the clock forward by the specified duration.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        now.0 += duration;
+        now.1 += duration;
+    }
+
+    /// Change the wall-clock time (without affecting the monotonic time),
+    /// e.g. to a fixed date for deterministic prompts or history timestamps.
+    pub fn set_system_time(&self, time: SystemTime) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        now.1 = time;
+    }
+}
+
+impl Default for MockClockEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockEnvironment for MockClockEnv {
+    fn now(&self) -> Instant {
+        self.now.lock().unwrap_or_else(|e| e.into_inner()).0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.now.lock().unwrap_or_else(|e| e.into_inner()).1
+    }
+}
+
+impl SubEnvironment for MockClockEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
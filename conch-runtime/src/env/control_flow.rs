@@ -0,0 +1,164 @@
+use crate::env::SubEnvironment;
+use crate::error::ControlFlow;
+
+/// An interface for builtin utilities to request that the current flow
+/// of execution be altered (e.g. returning from a function).
+///
+/// Builtins only produce an exit status, so any request is recorded here
+/// and picked up by whichever spawner invoked the builtin, which will then
+/// propagate it as an error to the construct responsible for handling it.
+pub trait ControlFlowEnvironment {
+    /// Records a control flow request, replacing any previous pending request.
+    fn request_control_flow(&mut self, flow: ControlFlow);
+
+    /// Takes any pending control flow request out of the environment.
+    fn take_control_flow(&mut self) -> Option<ControlFlow>;
+
+    /// Denote that a new loop has been entered and is currently executing.
+    fn push_loop_frame(&mut self);
+
+    /// Denote that a loop has completed and is no longer executing.
+    fn pop_loop_frame(&mut self);
+
+    /// The number of loops which are currently being executed.
+    fn loop_depth(&self) -> usize;
+
+    /// Denote that the commands about to run are exempt from the `errexit`
+    /// option, e.g. because they form the condition of an `if` command.
+    fn push_errexit_exemption(&mut self);
+
+    /// Denote that the commands exempt from the `errexit` option have completed.
+    fn pop_errexit_exemption(&mut self);
+
+    /// Indicates if the commands currently running are exempt from the
+    /// `errexit` option.
+    fn is_errexit_exempt(&self) -> bool;
+
+    /// Records whether the exit status of the command which completed last
+    /// has already been tested (e.g. by a `!` or a following `&&`), in which
+    /// case its failure should not cause the shell to exit under `errexit`.
+    fn set_status_tested(&mut self, tested: bool);
+
+    /// Indicates if the exit status of the command which completed last has
+    /// already been tested.
+    fn is_status_tested(&self) -> bool;
+}
+
+impl<'a, T: ?Sized + ControlFlowEnvironment> ControlFlowEnvironment for &'a mut T {
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        (**self).request_control_flow(flow);
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        (**self).take_control_flow()
+    }
+
+    fn push_loop_frame(&mut self) {
+        (**self).push_loop_frame();
+    }
+
+    fn pop_loop_frame(&mut self) {
+        (**self).pop_loop_frame();
+    }
+
+    fn loop_depth(&self) -> usize {
+        (**self).loop_depth()
+    }
+
+    fn push_errexit_exemption(&mut self) {
+        (**self).push_errexit_exemption();
+    }
+
+    fn pop_errexit_exemption(&mut self) {
+        (**self).pop_errexit_exemption();
+    }
+
+    fn is_errexit_exempt(&self) -> bool {
+        (**self).is_errexit_exempt()
+    }
+
+    fn set_status_tested(&mut self, tested: bool) {
+        (**self).set_status_tested(tested);
+    }
+
+    fn is_status_tested(&self) -> bool {
+        (**self).is_status_tested()
+    }
+}
+
+/// An implementation of `ControlFlowEnvironment`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ControlFlowEnv {
+    pending: Option<ControlFlow>,
+    loop_depth: usize,
+    errexit_exemptions: usize,
+    status_tested: bool,
+}
+
+impl ControlFlowEnv {
+    /// Create a new environment instance without any pending requests.
+    pub fn new() -> Self {
+        Self {
+            pending: None,
+            loop_depth: 0,
+            errexit_exemptions: 0,
+            status_tested: false,
+        }
+    }
+}
+
+impl ControlFlowEnvironment for ControlFlowEnv {
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        self.pending = Some(flow);
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        self.pending.take()
+    }
+
+    fn push_loop_frame(&mut self) {
+        self.loop_depth += 1;
+    }
+
+    fn pop_loop_frame(&mut self) {
+        self.loop_depth = self.loop_depth.saturating_sub(1);
+    }
+
+    fn loop_depth(&self) -> usize {
+        self.loop_depth
+    }
+
+    fn push_errexit_exemption(&mut self) {
+        self.errexit_exemptions += 1;
+    }
+
+    fn pop_errexit_exemption(&mut self) {
+        self.errexit_exemptions = self.errexit_exemptions.saturating_sub(1);
+    }
+
+    fn is_errexit_exempt(&self) -> bool {
+        self.errexit_exemptions > 0
+    }
+
+    fn set_status_tested(&mut self, tested: bool) {
+        self.status_tested = tested;
+    }
+
+    fn is_status_tested(&self) -> bool {
+        self.status_tested
+    }
+}
+
+impl SubEnvironment for ControlFlowEnv {
+    fn sub_env(&self) -> Self {
+        // Pending requests belong to the parent's invocation only, but a
+        // subshell may still `break` out of (its copy of) an enclosing loop,
+        // and remains exempt from `errexit` if its parent was
+        Self {
+            pending: None,
+            loop_depth: self.loop_depth,
+            errexit_exemptions: self.errexit_exemptions,
+            status_tested: false,
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::env::SubEnvironment;
-use crate::path::NormalizedPath;
+use crate::path::{NormalizationError, NormalizedPath};
 use std::borrow::Cow;
 use std::env;
+use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -13,6 +14,45 @@ pub trait WorkingDirectoryEnvironment {
 
     /// Retrieves the current working directory of this environment.
     fn current_working_dir(&self) -> &Path;
+
+    /// Retrieves the current working directory of this environment with all
+    /// symbolic links resolved (i.e. the "physical" working directory).
+    fn canonical_working_dir(&self) -> Result<PathBuf, NormalizationError> {
+        let mut normalized_path = NormalizedPath::new();
+        normalized_path.join_normalized_physical(self.current_working_dir())?;
+        Ok(normalized_path.into_inner())
+    }
+
+    /// Resolves the specified path against the environment's working
+    /// directory (leaving absolute paths as is).
+    ///
+    /// All paths provided by the user (e.g. of redirects or scripts) should
+    /// be resolved this way, since the environment's working directory may
+    /// differ from that of the current process.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.path_relative_to_working_dir(Cow::Borrowed(path))
+            .into_owned()
+    }
+
+    /// Resolves the specified path against the environment's working
+    /// directory, with all symbolic links resolved.
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, NormalizationError> {
+        let mut normalized_path = NormalizedPath::new();
+        normalized_path.join_normalized_physical(self.resolve(path))?;
+        Ok(normalized_path.into_inner())
+    }
+
+    /// Queries the metadata of the file at the specified path (relative to
+    /// the environment's working directory), following any symbolic links.
+    fn path_metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        fs::metadata(self.resolve(path))
+    }
+
+    /// Indicates if anything exists at the specified path (relative to the
+    /// environment's working directory).
+    fn path_exists(&self, path: &Path) -> bool {
+        self.path_metadata(path).is_ok()
+    }
 }
 
 impl<'b, T: ?Sized + WorkingDirectoryEnvironment> WorkingDirectoryEnvironment for &'b T {
@@ -23,6 +63,26 @@ impl<'b, T: ?Sized + WorkingDirectoryEnvironment> WorkingDirectoryEnvironment fo
     fn current_working_dir(&self) -> &Path {
         (**self).current_working_dir()
     }
+
+    fn canonical_working_dir(&self) -> Result<PathBuf, NormalizationError> {
+        (**self).canonical_working_dir()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        (**self).resolve(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, NormalizationError> {
+        (**self).canonicalize(path)
+    }
+
+    fn path_metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        (**self).path_metadata(path)
+    }
+
+    fn path_exists(&self, path: &Path) -> bool {
+        (**self).path_exists(path)
+    }
 }
 
 impl<'b, T: ?Sized + WorkingDirectoryEnvironment> WorkingDirectoryEnvironment for &'b mut T {
@@ -33,6 +93,26 @@ impl<'b, T: ?Sized + WorkingDirectoryEnvironment> WorkingDirectoryEnvironment fo
     fn current_working_dir(&self) -> &Path {
         (**self).current_working_dir()
     }
+
+    fn canonical_working_dir(&self) -> Result<PathBuf, NormalizationError> {
+        (**self).canonical_working_dir()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        (**self).resolve(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, NormalizationError> {
+        (**self).canonicalize(path)
+    }
+
+    fn path_metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        (**self).path_metadata(path)
+    }
+
+    fn path_exists(&self, path: &Path) -> bool {
+        (**self).path_exists(path)
+    }
 }
 
 /// An interface for changing the shell's current working directory.
@@ -0,0 +1,122 @@
+use crate::env::SubEnvironment;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// An interface for maintaining a stack of saved directories, such as the one
+/// manipulated by the `pushd` and `popd` builtins.
+///
+/// The stack does not include the current working directory, and entries are
+/// ordered from most to least recently saved.
+pub trait DirStackEnvironment {
+    /// Get all saved directories, most recently saved first.
+    fn dir_stack(&self) -> &[PathBuf];
+
+    /// Saves a directory at the top of the stack.
+    fn push_dir(&mut self, dir: PathBuf);
+
+    /// Removes the saved directory at some index (where 0 is the top of the
+    /// stack), returning it if it existed.
+    fn remove_dir(&mut self, index: usize) -> Option<PathBuf>;
+
+    /// Replaces all saved directories, most recently saved first.
+    fn set_dir_stack(&mut self, dirs: Vec<PathBuf>);
+}
+
+impl<'a, T: ?Sized + DirStackEnvironment> DirStackEnvironment for &'a mut T {
+    fn dir_stack(&self) -> &[PathBuf] {
+        (**self).dir_stack()
+    }
+
+    fn push_dir(&mut self, dir: PathBuf) {
+        (**self).push_dir(dir);
+    }
+
+    fn remove_dir(&mut self, index: usize) -> Option<PathBuf> {
+        (**self).remove_dir(index)
+    }
+
+    fn set_dir_stack(&mut self, dirs: Vec<PathBuf>) {
+        (**self).set_dir_stack(dirs);
+    }
+}
+
+/// An environment module for maintaining a stack of saved directories.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirStackEnv {
+    dirs: Arc<Vec<PathBuf>>,
+}
+
+impl DirStackEnv {
+    /// Constructs a new environment with no saved directories.
+    pub fn new() -> Self {
+        Self {
+            dirs: Arc::new(Vec::new()),
+        }
+    }
+}
+
+impl DirStackEnvironment for DirStackEnv {
+    fn dir_stack(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    fn push_dir(&mut self, dir: PathBuf) {
+        Arc::make_mut(&mut self.dirs).insert(0, dir);
+    }
+
+    fn remove_dir(&mut self, index: usize) -> Option<PathBuf> {
+        if index < self.dirs.len() {
+            Some(Arc::make_mut(&mut self.dirs).remove(index))
+        } else {
+            None
+        }
+    }
+
+    fn set_dir_stack(&mut self, dirs: Vec<PathBuf>) {
+        self.dirs = Arc::new(dirs);
+    }
+}
+
+impl SubEnvironment for DirStackEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_remove_dirs() {
+        let mut env = DirStackEnv::new();
+        assert!(env.dir_stack().is_empty());
+
+        env.push_dir(PathBuf::from("/foo"));
+        env.push_dir(PathBuf::from("/bar"));
+        assert_eq!(
+            env.dir_stack(),
+            [PathBuf::from("/bar"), PathBuf::from("/foo")]
+        );
+
+        assert_eq!(env.remove_dir(2), None);
+        assert_eq!(env.remove_dir(1), Some(PathBuf::from("/foo")));
+        assert_eq!(env.dir_stack(), [PathBuf::from("/bar")]);
+    }
+
+    #[test]
+    fn test_changes_in_sub_env_should_not_affect_parent() {
+        let mut parent = DirStackEnv::new();
+        parent.push_dir(PathBuf::from("/foo"));
+
+        {
+            let mut child = parent.sub_env();
+            assert_eq!(child.dir_stack(), parent.dir_stack());
+
+            child.push_dir(PathBuf::from("/bar"));
+            child.set_dir_stack(Vec::new());
+        }
+
+        assert_eq!(parent.dir_stack(), [PathBuf::from("/foo")]);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::env::{ClockEnvironment, RandomEnv, RandomEnvironment, SubEnvironment, SystemClockEnv};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+/// The name of the variable holding the number of seconds since the shell started.
+const SECONDS: &str = "SECONDS";
+/// The name of the variable holding a new pseudo-random number every time it is read.
+const RANDOM: &str = "RANDOM";
+/// The name of the variable holding the line number of the current command.
+const LINENO: &str = "LINENO";
+
+/// An interface for variables whose values are computed whenever they are
+/// read (like `$SECONDS` or `$RANDOM`), rather than stored.
+pub trait DynamicVariableEnvironment {
+    /// Computes the current value of the variable `name`, or `None` if it is
+    /// not a dynamic variable.
+    fn dynamic_var(&self, name: &str) -> Option<String>;
+
+    /// Notifies the environment that `value` was assigned to the variable
+    /// `name`, e.g. assigning to `$SECONDS` restarts its count from `value`,
+    /// and assigning to `$RANDOM` reseeds the random number generator.
+    ///
+    /// Returns `true` if `name` is a dynamic variable, or `false` (without
+    /// any other effect) if it is not.
+    fn set_dynamic_var(&mut self, name: &str, value: &str) -> bool;
+
+    /// Records the line number (within its source) of the command which is
+    /// about to be executed, i.e. the value of `$LINENO`.
+    fn set_current_line(&mut self, line: usize);
+}
+
+impl<'a, T: ?Sized + DynamicVariableEnvironment> DynamicVariableEnvironment for &'a mut T {
+    fn dynamic_var(&self, name: &str) -> Option<String> {
+        (**self).dynamic_var(name)
+    }
+
+    fn set_dynamic_var(&mut self, name: &str, value: &str) -> bool {
+        (**self).set_dynamic_var(name, value)
+    }
+
+    fn set_current_line(&mut self, line: usize) {
+        (**self).set_current_line(line);
+    }
+}
+
+/// An environment module implementing the `$SECONDS`, `$RANDOM`, and
+/// `$LINENO` dynamic variables.
+///
+/// The clock and random number generator can be swapped out (e.g. with a
+/// `MockClockEnv` or `RandomEnv::with_seed`) to make their values
+/// deterministic. The clock is also exposed as the `ClockEnvironment` of the
+/// environment, e.g. for timestamping history entries. All sub-environments share the same clock and random
+/// number generator, and start off with the count of `$SECONDS` of their
+/// parent.
+#[derive(Clone)]
+pub struct DynamicVarEnv {
+    clock: Arc<dyn ClockEnvironment + Send + Sync>,
+    random: Arc<Mutex<dyn RandomEnvironment + Send>>,
+    start: Instant,
+    /// The value `$SECONDS` held at `start`.
+    seconds: i64,
+    line: usize,
+}
+
+impl DynamicVarEnv {
+    /// Creates a new environment using the system clock and a randomly
+    /// seeded generator.
+    pub fn new() -> Self {
+        Self::with_sources(SystemClockEnv::new(), RandomEnv::new())
+    }
+
+    /// Creates a new environment using the provided clock and random number
+    /// generator. `$SECONDS` will count up from the clock's current time.
+    pub fn with_sources<C, R>(clock: C, random: R) -> Self
+    where
+        C: 'static + ClockEnvironment + Send + Sync,
+        R: 'static + RandomEnvironment + Send,
+    {
+        let start = clock.now();
+
+        Self {
+            clock: Arc::new(clock),
+            random: Arc::new(Mutex::new(random)),
+            start,
+            seconds: 0,
+            line: 1,
+        }
+    }
+}
+
+impl Default for DynamicVarEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DynamicVarEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(DynamicVarEnv))
+            .field("start", &self.start)
+            .field("seconds", &self.seconds)
+            .field("line", &self.line)
+            .finish()
+    }
+}
+
+impl PartialEq for DynamicVarEnv {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.random, &other.random)
+            && self.start == other.start
+            && self.seconds == other.seconds
+            && self.line == other.line
+    }
+}
+
+impl Eq for DynamicVarEnv {}
+
+impl DynamicVariableEnvironment for DynamicVarEnv {
+    fn dynamic_var(&self, name: &str) -> Option<String> {
+        let value = match name {
+            SECONDS => {
+                let elapsed = self.clock.now().saturating_duration_since(self.start);
+                (self.seconds + elapsed.as_secs() as i64).to_string()
+            }
+            RANDOM => self
+                .random
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .next_random()
+                .to_string(),
+            LINENO => self.line.to_string(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn set_dynamic_var(&mut self, name: &str, value: &str) -> bool {
+        // Like other shells, values which aren't numbers are treated as zero
+        match name {
+            SECONDS => {
+                self.start = self.clock.now();
+                self.seconds = value.trim().parse().unwrap_or(0);
+            }
+            RANDOM => self
+                .random
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .reseed(value.trim().parse::<i64>().unwrap_or(0) as u64),
+            LINENO => self.line = value.trim().parse().unwrap_or(0),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn set_current_line(&mut self, line: usize) {
+        self.line = line;
+    }
+}
+
+impl ClockEnvironment for DynamicVarEnv {
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.clock.system_time()
+    }
+}
+
+impl SubEnvironment for DynamicVarEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_is_seedable_and_changes_on_every_read() {
+        let env = DynamicVarEnv::with_sources(SystemClockEnv::new(), RandomEnv::with_seed(42));
+        let mut rng = RandomEnv::with_seed(42);
+
+        let first = env.dynamic_var(RANDOM).unwrap();
+        let second = env.dynamic_var(RANDOM).unwrap();
+        assert_eq!(first, rng.next_random().to_string());
+        assert_eq!(second, rng.next_random().to_string());
+    }
+
+    #[test]
+    fn test_assigning_seconds_and_random() {
+        let mut env = DynamicVarEnv::with_sources(SystemClockEnv::new(), RandomEnv::with_seed(1));
+        let mut rng = RandomEnv::with_seed(42);
+
+        assert!(env.set_dynamic_var(SECONDS, "100"));
+        assert_eq!(env.dynamic_var(SECONDS), Some("100".to_owned()));
+        assert!(env.set_dynamic_var(SECONDS, "-5"));
+        assert_eq!(env.dynamic_var(SECONDS), Some("-5".to_owned()));
+
+        assert!(env.set_dynamic_var(RANDOM, "42"));
+        assert_eq!(env.dynamic_var(RANDOM), Some(rng.next_random().to_string()));
+
+        assert!(env.set_dynamic_var(LINENO, "7"));
+        assert_eq!(env.dynamic_var(LINENO), Some("7".to_owned()));
+
+        assert!(!env.set_dynamic_var("HOME", "/"));
+        assert_eq!(env.dynamic_var("HOME"), None);
+    }
+
+    #[test]
+    fn test_lineno_and_unknown_vars() {
+        let mut env = DynamicVarEnv::new();
+        assert_eq!(env.dynamic_var(LINENO), Some("1".to_owned()));
+
+        env.set_current_line(42);
+        assert_eq!(env.dynamic_var(LINENO), Some("42".to_owned()));
+        assert_eq!(env.sub_env().dynamic_var(LINENO), Some("42".to_owned()));
+
+        assert_eq!(env.dynamic_var("HOME"), None);
+    }
+}
@@ -2,29 +2,42 @@
 // FIXME: consumers still have all the pieces so they can make their own environment and swap out pieces there
 // FIXME: downside is any unit tests which want a mock env, will need to basically do the same
 use crate::env::builtin::{BuiltinEnv, BuiltinEnvironment};
+use crate::env::introspect::IntrospectEnvironment;
 use crate::env::{
-    ArgsEnv, ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
+    AliasEnv, AliasEnvironment, ArgsEnv, ArgumentsEnvironment, AsyncIoEnvironment,
+    ChangeWorkingDirectoryEnvironment, ClockEnvironment, ControlFlowEnv, ControlFlowEnvironment,
+    DirStackEnv, DirStackEnvironment, DynamicVarEnv, DynamicVariableEnvironment, EnvSnapshot,
     ExecutableData, ExecutableEnvironment, ExportedVariableEnvironment, FileDescEnvironment,
-    FileDescOpener, FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment,
-    IsInteractiveEnvironment, LastStatusEnv, LastStatusEnvironment, Pipe, ReportErrorEnvironment,
-    SetArgumentsEnvironment, ShiftArgumentsEnvironment, StringWrapper, SubEnvironment,
-    TokioExecEnv, TokioFileDescManagerEnv, UnsetFunctionEnvironment, UnsetVariableEnvironment,
-    VarEnv, VariableEnvironment, VirtualWorkingDirEnv, WorkingDirectoryEnvironment,
+    FileDescOpener, FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment, HistoryEnv,
+    HistoryEnvironment, IsInteractiveEnvironment, Job, JobEnv, JobEnvironment,
+    LastPipelineStatusEnv, LastPipelineStatusEnvironment, LastStatusEnv, LastStatusEnvironment,
+    NestingEnv, NestingEnvironment, Pipe, PipelineStatusRecorder, ProcessIdEnv,
+    ProcessIdEnvironment, ReportErrorEnvironment, SetArgumentsEnvironment, ShellOption,
+    ShellOptionsEnv, ShellOptionsEnvironment, ShiftArgumentsEnvironment, SnapshotVar,
+    StringWrapper, SubEnvironment, TempFile, TempFileEnvironment, TokioExecEnv,
+    TokioFileDescManagerEnv, UnsetFunctionEnvironment, UnsetVariableEnvironment, VarAttributes,
+    VarEnv, VariableAttributesEnvironment, VariableEnvironment, VirtualWorkingDirEnv,
+    WorkingDirectoryEnvironment,
 };
-use crate::error::{CommandError, RuntimeError};
+use crate::error::{CommandError, ControlFlow, RuntimeError};
 use crate::io::Permissions;
+use crate::path::NormalizationError;
 use crate::{ExitStatus, Fd, Spawn, IFS_DEFAULT, STDERR_FILENO};
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use std::any::Any;
 use std::borrow::{Borrow, Cow};
 use std::convert::From;
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fmt;
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
 use std::hash::Hash;
 use std::io;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 /// A struct for configuring a new `Env` instance.
 ///
@@ -61,6 +74,10 @@ pub struct EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
     pub working_dir_env: WD,
     /// An implementation of `BuiltinEnvironment`.
     pub builtin_env: B,
+    /// The process ids reported by the environment, e.g. for `$$`.
+    pub pid_env: ProcessIdEnv,
+    /// The source of dynamic variables, e.g. `$SECONDS` and `$RANDOM`.
+    pub dynamic_var_env: DynamicVarEnv,
     /// A marker to indicate the type used for function names.
     pub fn_name: PhantomData<N>,
     /// A marker to indicate the type used for function errors.
@@ -79,6 +96,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -98,6 +117,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -117,6 +138,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -133,6 +156,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -149,6 +174,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -168,6 +195,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -187,6 +216,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -203,6 +234,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: PhantomData,
             fn_error: self.fn_error,
         }
@@ -219,6 +252,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            pid_env: self.pid_env,
+            dynamic_var_env: self.dynamic_var_env,
             fn_name: self.fn_name,
             fn_error: PhantomData,
         }
@@ -262,6 +297,8 @@ where
             exec_env: TokioExecEnv::new(),
             working_dir_env: VirtualWorkingDirEnv::with_process_working_dir()?,
             builtin_env: BuiltinEnv::new(),
+            pid_env: ProcessIdEnv::new(),
+            dynamic_var_env: DynamicVarEnv::new(),
             fn_name: PhantomData,
             fn_error: PhantomData,
         })
@@ -279,6 +316,18 @@ pub struct Env<A, FM, L, V, EX, WD, B, N: Eq + Hash, ERR> {
     fn_env:
         FnEnv<N, Arc<dyn Spawn<Env<A, FM, L, V, EX, WD, B, N, ERR>, Error = ERR> + Send + Sync>>,
     fn_frame_env: FnFrameEnv,
+    nesting_env: NestingEnv,
+    options_env: ShellOptionsEnv,
+    /// The options to restore when each function frame is popped, if any.
+    options_frames: Vec<Option<ShellOptionsEnv>>,
+    control_flow_env: ControlFlowEnv,
+    alias_env: AliasEnv,
+    job_env: JobEnv,
+    dir_stack_env: DirStackEnv,
+    history_env: HistoryEnv,
+    pipe_status_env: LastPipelineStatusEnv,
+    pid_env: ProcessIdEnv,
+    dynamic_var_env: DynamicVarEnv,
     last_status_env: L,
     var_env: V,
     exec_env: EX,
@@ -317,6 +366,17 @@ where
             args_env: cfg.args_env,
             fn_env: FnEnv::new(),
             fn_frame_env: FnFrameEnv::new(),
+            nesting_env: NestingEnv::new(),
+            options_env: ShellOptionsEnv::new(),
+            options_frames: Vec::new(),
+            control_flow_env: ControlFlowEnv::new(),
+            alias_env: AliasEnv::new(),
+            job_env: JobEnv::new(),
+            dir_stack_env: DirStackEnv::new(),
+            history_env: HistoryEnv::new(),
+            pipe_status_env: LastPipelineStatusEnv::new(),
+            pid_env: cfg.pid_env,
+            dynamic_var_env: cfg.dynamic_var_env,
             file_desc_manager_env: cfg.file_desc_manager_env,
             last_status_env: cfg.last_status_env,
             var_env: cfg.var_env,
@@ -347,6 +407,105 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq + Clone + Borrow<String>,
+    L: LastStatusEnvironment,
+    V: ExportedVariableEnvironment,
+    V::VarName: Borrow<String> + Clone,
+    V::Var: Borrow<String> + Clone,
+    WD: WorkingDirectoryEnvironment,
+{
+    /// Captures the variables, names of defined functions, working directory,
+    /// enabled options, and last status of the environment.
+    pub fn snapshot(&self) -> EnvSnapshot {
+        let mut vars = self
+            .all_vars()
+            .into_iter()
+            .map(|(name, val, exported)| SnapshotVar {
+                name: name.borrow().clone(),
+                value: val.borrow().clone(),
+                exported,
+            })
+            .collect::<Vec<_>>();
+        vars.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut functions = self
+            .fn_env
+            .fn_names()
+            .map(|name| name.borrow().clone())
+            .collect::<Vec<_>>();
+        functions.sort();
+
+        let options = ShellOption::ALL
+            .iter()
+            .copied()
+            .filter(|&opt| self.is_option_enabled(opt))
+            .collect();
+
+        EnvSnapshot {
+            vars,
+            functions,
+            working_dir: self.current_working_dir().to_owned(),
+            options,
+            last_status: self.last_status(),
+        }
+    }
+
+    /// Restores the state captured by a snapshot (see `Env::snapshot`).
+    ///
+    /// Any variables or functions which were not captured by the snapshot
+    /// are unset. Since functions are only captured by name, any captured
+    /// functions which are not currently defined must be redefined by the
+    /// caller (e.g. by re-running the script which originally defined them).
+    ///
+    /// If the working directory cannot be restored, an error is returned
+    /// and the environment is left unchanged.
+    pub fn restore_from(&mut self, snapshot: &EnvSnapshot) -> io::Result<()>
+    where
+        V: UnsetVariableEnvironment,
+        V::VarName: From<String>,
+        V::Var: From<String>,
+        WD: ChangeWorkingDirectoryEnvironment,
+    {
+        self.working_dir_env
+            .change_working_dir(Cow::Borrowed(&snapshot.working_dir))?;
+
+        let is_captured = |name: &String| snapshot.vars.iter().any(|var| var.name == *name);
+        let stale_vars = self
+            .all_vars()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| !is_captured(name.borrow()))
+            .collect::<Vec<_>>();
+        for name in stale_vars {
+            self.unset_var(&name);
+        }
+
+        for var in &snapshot.vars {
+            let name = var.name.clone().into();
+            self.set_exported_var(name, var.value.clone().into(), var.exported);
+        }
+
+        let stale_fns = self
+            .fn_env
+            .fn_names()
+            .filter(|&name| !snapshot.functions.contains(name.borrow()))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in stale_fns {
+            self.unset_function(&name);
+        }
+
+        for &opt in ShellOption::ALL {
+            self.set_option(opt, snapshot.options.contains(&opt));
+        }
+
+        self.set_last_status(snapshot.last_status);
+        Ok(())
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> Clone for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     A: Clone,
@@ -365,6 +524,17 @@ where
             file_desc_manager_env: self.file_desc_manager_env.clone(),
             fn_env: self.fn_env.clone(),
             fn_frame_env: self.fn_frame_env,
+            nesting_env: self.nesting_env,
+            options_env: self.options_env,
+            options_frames: self.options_frames.clone(),
+            control_flow_env: self.control_flow_env,
+            alias_env: self.alias_env.clone(),
+            job_env: self.job_env.clone(),
+            dir_stack_env: self.dir_stack_env.clone(),
+            history_env: self.history_env.clone(),
+            pipe_status_env: self.pipe_status_env.clone(),
+            pid_env: self.pid_env.clone(),
+            dynamic_var_env: self.dynamic_var_env.clone(),
             last_status_env: self.last_status_env.clone(),
             var_env: self.var_env.clone(),
             exec_env: self.exec_env.clone(),
@@ -395,6 +565,17 @@ where
             .field("file_desc_manager_env", &self.file_desc_manager_env)
             .field("functions", &fn_names)
             .field("fn_frame_env", &self.fn_frame_env)
+            .field("nesting_env", &self.nesting_env)
+            .field("options_env", &self.options_env)
+            .field("options_frames", &self.options_frames)
+            .field("control_flow_env", &self.control_flow_env)
+            .field("alias_env", &self.alias_env)
+            .field("job_env", &self.job_env)
+            .field("dir_stack_env", &self.dir_stack_env)
+            .field("history_env", &self.history_env)
+            .field("pipe_status_env", &self.pipe_status_env)
+            .field("pid_env", &self.pid_env)
+            .field("dynamic_var_env", &self.dynamic_var_env)
             .field("last_status_env", &self.last_status_env)
             .field("var_env", &self.var_env)
             .field("exec_env", &self.exec_env)
@@ -446,6 +627,17 @@ where
             file_desc_manager_env: self.file_desc_manager_env.sub_env(),
             fn_env: self.fn_env.sub_env(),
             fn_frame_env: self.fn_frame_env.sub_env(),
+            nesting_env: self.nesting_env.sub_env(),
+            options_env: self.options_env.sub_env(),
+            options_frames: self.options_frames.clone(),
+            control_flow_env: self.control_flow_env.sub_env(),
+            alias_env: self.alias_env.sub_env(),
+            job_env: self.job_env.sub_env(),
+            dir_stack_env: self.dir_stack_env.sub_env(),
+            history_env: self.history_env.sub_env(),
+            pipe_status_env: self.pipe_status_env.sub_env(),
+            pid_env: self.pid_env.sub_env(),
+            dynamic_var_env: self.dynamic_var_env.sub_env(),
             last_status_env: self.last_status_env.sub_env(),
             var_env: self.var_env.sub_env(),
             exec_env: self.exec_env.sub_env(),
@@ -490,6 +682,10 @@ where
     fn set_args(&mut self, new_args: Self::Args) -> Self::Args {
         self.args_env.set_args(new_args)
     }
+
+    fn set_name(&mut self, new_name: Self::Arg) -> Self::Arg {
+        self.args_env.set_name(new_name)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> ShiftArgumentsEnvironment
@@ -514,6 +710,10 @@ where
         self.file_desc_manager_env.read_all(fd)
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        self.file_desc_manager_env.read_chunks(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -538,6 +738,10 @@ where
         self.file_desc_manager_env.file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        self.file_desc_manager_env.file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, fdes: Self::FileHandle, perms: Permissions) {
         self.file_desc_manager_env.set_file_desc(fd, fdes, perms)
     }
@@ -558,11 +762,38 @@ where
         self.file_desc_manager_env.open_path(path, opts)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.file_desc_manager_env
+            .open_path_with_permissions(path, perms, opts)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         self.file_desc_manager_env.open_pipe()
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> TempFileEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    FM: TempFileEnvironment,
+    N: Hash + Eq,
+{
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.file_desc_manager_env.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.file_desc_manager_env.open_named_temp_file(contents)
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> ReportErrorEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     A: ArgumentsEnvironment,
@@ -608,6 +839,14 @@ where
     fn has_function(&self, name: &Self::FnName) -> bool {
         self.fn_env.has_function(name)
     }
+
+    fn set_function_body(&mut self, name: &Self::FnName, body: Arc<dyn Any + Send + Sync>) {
+        self.fn_env.set_function_body(name, body);
+    }
+
+    fn function_body(&self, name: &Self::FnName) -> Option<&(dyn Any + Send + Sync)> {
+        self.fn_env.function_body(name)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> UnsetFunctionEnvironment
@@ -626,16 +865,260 @@ where
     N: Hash + Eq + Clone,
 {
     fn push_fn_frame(&mut self) {
-        self.fn_frame_env.push_fn_frame()
+        self.fn_frame_env.push_fn_frame();
+        self.push_options_frame();
     }
 
     fn pop_fn_frame(&mut self) {
-        self.fn_frame_env.pop_fn_frame()
+        self.fn_frame_env.pop_fn_frame();
+        self.pop_options_frame();
     }
 
     fn is_fn_running(&self) -> bool {
         self.fn_frame_env.is_fn_running()
     }
+
+    fn fn_frame_depth(&self) -> usize {
+        self.fn_frame_env.fn_frame_depth()
+    }
+
+    fn max_fn_frame_depth(&self) -> Option<usize> {
+        self.fn_frame_env.max_fn_frame_depth()
+    }
+
+    fn set_max_fn_frame_depth(&mut self, max_depth: Option<usize>) {
+        self.fn_frame_env.set_max_fn_frame_depth(max_depth);
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> NestingEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn push_nesting_level(&mut self) {
+        self.nesting_env.push_nesting_level();
+    }
+
+    fn pop_nesting_level(&mut self) {
+        self.nesting_env.pop_nesting_level();
+    }
+
+    fn nesting_depth(&self) -> usize {
+        self.nesting_env.nesting_depth()
+    }
+
+    fn max_nesting_depth(&self) -> Option<usize> {
+        self.nesting_env.max_nesting_depth()
+    }
+
+    fn set_max_nesting_depth(&mut self, max_depth: Option<usize>) {
+        self.nesting_env.set_max_nesting_depth(max_depth);
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ControlFlowEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        self.control_flow_env.request_control_flow(flow)
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        self.control_flow_env.take_control_flow()
+    }
+
+    fn push_loop_frame(&mut self) {
+        self.control_flow_env.push_loop_frame()
+    }
+
+    fn pop_loop_frame(&mut self) {
+        self.control_flow_env.pop_loop_frame()
+    }
+
+    fn loop_depth(&self) -> usize {
+        self.control_flow_env.loop_depth()
+    }
+
+    fn push_errexit_exemption(&mut self) {
+        self.control_flow_env.push_errexit_exemption()
+    }
+
+    fn pop_errexit_exemption(&mut self) {
+        self.control_flow_env.pop_errexit_exemption()
+    }
+
+    fn is_errexit_exempt(&self) -> bool {
+        self.control_flow_env.is_errexit_exempt()
+    }
+
+    fn set_status_tested(&mut self, tested: bool) {
+        self.control_flow_env.set_status_tested(tested)
+    }
+
+    fn is_status_tested(&self) -> bool {
+        self.control_flow_env.is_status_tested()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ShellOptionsEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn is_option_enabled(&self, opt: ShellOption) -> bool {
+        self.options_env.is_option_enabled(opt)
+    }
+
+    fn set_option(&mut self, opt: ShellOption, enabled: bool) {
+        self.options_env.set_option(opt, enabled)
+    }
+
+    fn push_options_frame(&mut self) {
+        self.options_frames.push(None);
+    }
+
+    fn pop_options_frame(&mut self) {
+        if let Some(Some(saved)) = self.options_frames.pop() {
+            self.options_env = saved;
+        }
+    }
+
+    fn make_options_local(&mut self) -> bool {
+        match self.options_frames.last_mut() {
+            Some(frame) => {
+                frame.get_or_insert(self.options_env);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> AliasEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn alias(&self, name: &str) -> Option<&str> {
+        self.alias_env.alias(name)
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        self.alias_env.set_alias(name, value)
+    }
+
+    fn unalias(&mut self, name: &str) -> bool {
+        self.alias_env.unalias(name)
+    }
+
+    fn unalias_all(&mut self) {
+        self.alias_env.unalias_all()
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        self.alias_env.aliases()
+    }
+
+    /// Aliases are always expanded in interactive mode, otherwise only if
+    /// expansion has been explicitly enabled.
+    fn expand_aliases(&self) -> bool {
+        self.interactive || self.alias_env.expand_aliases()
+    }
+
+    fn set_expand_aliases(&mut self, enabled: bool) {
+        self.alias_env.set_expand_aliases(enabled)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> JobEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn add_job(
+        &mut self,
+        command: String,
+        pgid: Option<u32>,
+        status: BoxFuture<'static, ExitStatus>,
+    ) -> usize {
+        self.job_env.add_job(command, pgid, status)
+    }
+
+    fn job(&self, id: usize) -> Option<&Job> {
+        self.job_env.job(id)
+    }
+
+    fn jobs(&self) -> Vec<&Job> {
+        self.job_env.jobs()
+    }
+
+    fn remove_job(&mut self, id: usize) -> Option<Job> {
+        self.job_env.remove_job(id)
+    }
+
+    fn set_job_stopped(&mut self, id: usize, stopped: bool) {
+        self.job_env.set_job_stopped(id, stopped)
+    }
+
+    fn set_current_job(&mut self, id: usize) {
+        self.job_env.set_current_job(id)
+    }
+
+    fn current_job(&self) -> Option<usize> {
+        self.job_env.current_job()
+    }
+
+    fn previous_job(&self) -> Option<usize> {
+        self.job_env.previous_job()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> DirStackEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn dir_stack(&self) -> &[PathBuf] {
+        self.dir_stack_env.dir_stack()
+    }
+
+    fn push_dir(&mut self, dir: PathBuf) {
+        self.dir_stack_env.push_dir(dir)
+    }
+
+    fn remove_dir(&mut self, index: usize) -> Option<PathBuf> {
+        self.dir_stack_env.remove_dir(index)
+    }
+
+    fn set_dir_stack(&mut self, dirs: Vec<PathBuf>) {
+        self.dir_stack_env.set_dir_stack(dirs)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> HistoryEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn history(&self) -> &[String] {
+        self.history_env.history()
+    }
+
+    fn history_base(&self) -> usize {
+        self.history_env.history_base()
+    }
+
+    fn add_history(&mut self, entry: String) {
+        self.history_env.add_history(entry)
+    }
+
+    fn clear_history(&mut self) {
+        self.history_env.clear_history()
+    }
+
+    fn history_limit(&self) -> Option<usize> {
+        self.history_env.history_limit()
+    }
+
+    fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.history_env.set_history_limit(limit)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> LastStatusEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
@@ -649,6 +1132,73 @@ where
 
     fn set_last_status(&mut self, status: ExitStatus) {
         self.last_status_env.set_last_status(status);
+        self.pipe_status_env.update_last_pipeline_status(status);
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> DynamicVariableEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn dynamic_var(&self, name: &str) -> Option<String> {
+        self.dynamic_var_env.dynamic_var(name)
+    }
+
+    fn set_dynamic_var(&mut self, name: &str, value: &str) -> bool {
+        self.dynamic_var_env.set_dynamic_var(name, value)
+    }
+
+    fn set_current_line(&mut self, line: usize) {
+        self.dynamic_var_env.set_current_line(line);
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ClockEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn now(&self) -> Instant {
+        self.dynamic_var_env.now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.dynamic_var_env.system_time()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ProcessIdEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn shell_pid(&self) -> u32 {
+        self.pid_env.shell_pid()
+    }
+
+    fn parent_pid(&self) -> u32 {
+        self.pid_env.parent_pid()
+    }
+
+    fn subshell_pid(&self) -> u32 {
+        self.pid_env.subshell_pid()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> LastPipelineStatusEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn last_pipeline_status(&self) -> &[ExitStatus] {
+        self.pipe_status_env.last_pipeline_status()
+    }
+
+    fn pipeline_status_recorder(&self) -> PipelineStatusRecorder {
+        self.pipe_status_env.pipeline_status_recorder()
+    }
+
+    fn update_last_pipeline_status(&mut self, status: ExitStatus) {
+        self.pipe_status_env.update_last_pipeline_status(status);
     }
 }
 
@@ -660,10 +1210,10 @@ where
     type VarName = V::VarName;
     type Var = V::Var;
 
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.var_env.var(name)
     }
@@ -690,6 +1240,37 @@ where
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
         self.var_env.set_exported_var(name, val, exported)
     }
+
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.exported_vars()
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.all_vars()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> VariableAttributesEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: VariableAttributesEnvironment,
+    N: Hash + Eq,
+{
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes {
+        self.var_env.var_attributes(name)
+    }
+
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes) {
+        self.var_env.set_var_attributes(name, attributes)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> UnsetVariableEnvironment
@@ -714,6 +1295,23 @@ where
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
         self.exec_env.spawn_executable(data)
     }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        self.exec_env.executable_exists(name, env_vars, current_dir)
+    }
+
+    fn begin_job(&mut self) -> bool {
+        self.exec_env.begin_job()
+    }
+
+    fn end_job(&mut self) {
+        self.exec_env.end_job()
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> WorkingDirectoryEnvironment
@@ -729,6 +1327,26 @@ where
     fn current_working_dir(&self) -> &Path {
         self.working_dir_env.current_working_dir()
     }
+
+    fn canonical_working_dir(&self) -> Result<PathBuf, NormalizationError> {
+        self.working_dir_env.canonical_working_dir()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.working_dir_env.resolve(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, NormalizationError> {
+        self.working_dir_env.canonicalize(path)
+    }
+
+    fn path_metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        self.working_dir_env.path_metadata(path)
+    }
+
+    fn path_exists(&self, path: &Path) -> bool {
+        self.working_dir_env.path_exists(path)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> ChangeWorkingDirectoryEnvironment
@@ -774,6 +1392,51 @@ where
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
         self.builtin_env.builtin(name)
     }
+
+    fn utility_names(&self) -> Vec<String> {
+        self.builtin_env.utility_names()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> IntrospectEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq + Borrow<String>,
+    B: BuiltinEnvironment,
+    V: VariableEnvironment,
+    V::VarName: Borrow<String>,
+{
+    fn function_names(&self) -> Vec<String> {
+        let mut names = self
+            .fn_env
+            .fn_names()
+            .map(|name| name.borrow().clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn builtin_names(&self) -> Vec<String> {
+        self.builtin_env.utility_names()
+    }
+
+    fn alias_names(&self) -> Vec<String> {
+        self.alias_env
+            .aliases()
+            .into_iter()
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    fn exported_var_names(&self) -> Vec<String> {
+        let mut names = self
+            .var_env
+            .env_vars()
+            .iter()
+            .map(|&(name, _)| name.borrow().clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
 }
 
 /// A default environment configured with provided (non-atomic) implementations.
@@ -1,13 +1,30 @@
 use crate::env::SubEnvironment;
 use crate::error::CommandError;
 use crate::io::FileDesc;
-use crate::{ExitStatus, EXIT_ERROR};
+use crate::{ExitStatus, Fd, EXIT_ERROR};
 use futures_core::future::BoxFuture;
+use futures_util::future::{select, Either};
+use std::borrow::Cow;
 use std::ffi::OsStr;
+use std::fs;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::path::Path;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::process::{Child, Command};
+use tokio::time::delay_until;
+
+mod env_filter;
+#[cfg(feature = "testing")]
+mod mock;
+mod not_found;
+
+pub use self::env_filter::EnvVarFilterExecEnv;
+#[cfg(feature = "testing")]
+pub use self::mock::{MockExecEnv, MockExecInvocation, MockExecOutput};
+pub use self::not_found::CommandNotFoundExecEnv;
 
 /// Any data required to execute a child process.
 #[derive(Debug, PartialEq, Eq)]
@@ -21,6 +38,10 @@ pub struct ExecutableData<'a> {
     /// if they do not appear in this collection.
     pub env_vars: &'a [(&'a OsStr, &'a OsStr)],
     /// The current working directory the executable should start out with.
+    ///
+    /// This is typically the environment's (virtual) working directory, which
+    /// may differ from that of the current process. A relative `name` with
+    /// multiple components (e.g. `./script`) is resolved against it as well.
     pub current_dir: &'a Path,
     /// The executable's standard input will be redirected to this descriptor
     /// or the equivalent of `/dev/null` if not specified.
@@ -31,6 +52,88 @@ pub struct ExecutableData<'a> {
     /// The executable's standard error will be redirected to this descriptor
     /// or the equivalent of `/dev/null` if not specified.
     pub stderr: Option<FileDesc>,
+    /// Any additional descriptors the executable should inherit, each made
+    /// available as the specified descriptor number (e.g. `3`).
+    ///
+    /// No other descriptors are inherited: all descriptors created by the
+    /// runtime are marked CLOEXEC so they cannot leak into the executable.
+    /// Standard input, output, and error should be specified via the
+    /// fields above instead. Inheriting descriptors is only supported on
+    /// Unix platforms and ignored elsewhere, and requires the executable
+    /// to be spawned via fork/exec rather than `posix_spawn`.
+    pub inherited_fds: Vec<(Fd, FileDesc)>,
+    /// If specified, the executable will be forcibly killed if it is still
+    /// running once this point in time has been reached.
+    pub deadline: Option<Instant>,
+    /// Any resource limits which should be applied to the executable (in
+    /// addition to those configured on the environment itself).
+    ///
+    /// Limits are only supported on Unix platforms and ignored elsewhere.
+    pub resource_limits: &'a [ResourceLimit],
+    /// The process group the executable should be placed in, or the
+    /// environment's default behavior if not specified.
+    ///
+    /// Process groups are only supported on Unix platforms and ignored elsewhere.
+    pub process_group: Option<ProcessGroup>,
+}
+
+/// The process group an executable should be placed in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ProcessGroup {
+    /// Place the executable in a new process group, led by itself.
+    New,
+    /// Place the executable in an existing process group with the specified id,
+    /// e.g. to group all the commands of a pipeline together.
+    Join(u32),
+}
+
+/// A kind of resource whose consumption can be limited for a child process.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Resource {
+    /// The maximum amount of CPU time in seconds (`RLIMIT_CPU`).
+    CpuTime,
+    /// The maximum size of files (in bytes) the process may create (`RLIMIT_FSIZE`).
+    FileSize,
+    /// The maximum size (in bytes) of the process's data segment (`RLIMIT_DATA`).
+    DataSize,
+    /// The maximum size (in bytes) of the process's stack (`RLIMIT_STACK`).
+    StackSize,
+    /// The maximum size (in bytes) of any core dump (`RLIMIT_CORE`).
+    CoreFileSize,
+    /// The maximum number of file descriptors the process may open (`RLIMIT_NOFILE`).
+    OpenFiles,
+    /// The maximum size (in bytes) of the process's virtual memory (`RLIMIT_AS`).
+    AddressSpace,
+    /// The maximum number of processes the user may run (`RLIMIT_NPROC`).
+    Processes,
+}
+
+/// A limit on a resource which will be applied to a child process
+/// after it has been forked, but before the executable is run.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ResourceLimit {
+    /// The resource to limit.
+    pub resource: Resource,
+    /// The soft limit, which is the value actually enforced for the process.
+    /// The current value is left as is if not specified.
+    pub soft: Option<u64>,
+    /// The hard limit, which acts as a ceiling for the soft limit.
+    /// The current value is left as is if not specified.
+    pub hard: Option<u64>,
+}
+
+impl ResourceLimit {
+    /// A value which indicates no limit on the resource.
+    pub const UNLIMITED: u64 = u64::MAX;
+
+    /// Construct a limit which sets both the soft and hard limits to the same value.
+    pub fn new(resource: Resource, limit: u64) -> Self {
+        Self {
+            resource,
+            soft: Some(limit),
+            hard: Some(limit),
+        }
+    }
 }
 
 /// An interface for asynchronously spawning executables.
@@ -40,6 +143,39 @@ pub trait ExecutableEnvironment {
         &self,
         data: ExecutableData<'_>,
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError>;
+
+    /// Determines whether the executable `name` can be found (e.g. via the
+    /// `PATH` among `env_vars`, or relative to `current_dir`) without
+    /// attempting to spawn it, i.e. whether spawning it would fail with
+    /// `CommandError::NotFound`.
+    ///
+    /// Returns `None` if this cannot be known ahead of time, which is the
+    /// default.
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        let _ = (name, env_vars, current_dir);
+        None
+    }
+
+    /// Begins a new job (e.g. a pipeline), such that any executables spawned
+    /// through this environment, or any sub-environment created from it before
+    /// the job ends, are managed as a single unit (e.g. by placing them in the
+    /// same process group when job control is enabled).
+    ///
+    /// Returns `false` (leaving the environment as is) if the environment is
+    /// already part of a job, in which case the caller should not end it.
+    /// Does nothing by default.
+    fn begin_job(&mut self) -> bool {
+        false
+    }
+
+    /// Ends a job started by `begin_job`. Executables which are still running
+    /// remain part of the job until they exit. Does nothing by default.
+    fn end_job(&mut self) {}
 }
 
 impl<'a, T: ExecutableEnvironment> ExecutableEnvironment for &'a T {
@@ -49,13 +185,58 @@ impl<'a, T: ExecutableEnvironment> ExecutableEnvironment for &'a T {
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
         (**self).spawn_executable(data)
     }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        (**self).executable_exists(name, env_vars, current_dir)
+    }
 }
 
 /// An `ExecutableEnvironment` implementation that uses `tokio`
 /// to monitor when child processes have exited.
-#[derive(Clone, Debug, Default)]
-#[allow(missing_copy_implementations)]
-pub struct TokioExecEnv(());
+///
+/// On Unix platforms children are not waited on by blocking threads: a single,
+/// process-wide `SIGCHLD` listener (driven by the `tokio` reactor) wakes up any
+/// pending children which then reap themselves without blocking. Thus spawning
+/// many short-lived processes costs no additional threads or file descriptors.
+///
+/// Children are spawned via `posix_spawn` where the standard library supports
+/// it, except when any descriptors are inherited, resource limits are applied,
+/// or the child is placed in a process group: these must all be configured
+/// after forking, thus such children are spawned via fork/exec instead.
+#[derive(Clone, Debug)]
+pub struct TokioExecEnv {
+    enoexec_interpreter: Option<Arc<Path>>,
+    resource_limits: Vec<ResourceLimit>,
+    job_control: bool,
+    foreground_terminal: Option<Arc<FileDesc>>,
+    max_args_size: Option<usize>,
+    job: Option<Arc<JobGroup>>,
+}
+
+/// The process group shared by the executables of a job.
+#[derive(Debug, Default)]
+struct JobGroup {
+    state: Mutex<JobGroupState>,
+}
+
+#[derive(Debug, Default)]
+struct JobGroupState {
+    /// The id of the job's process group, if it has been created yet.
+    pgid: Option<u32>,
+    /// The number of the job's executables which are still running.
+    running: usize,
+}
+
+impl Default for TokioExecEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl SubEnvironment for TokioExecEnv {
     fn sub_env(&self) -> Self {
@@ -66,26 +247,118 @@ impl SubEnvironment for TokioExecEnv {
 impl TokioExecEnv {
     /// Construct a new environment.
     pub fn new() -> Self {
-        Self(())
+        Self {
+            enoexec_interpreter: None,
+            resource_limits: Vec::new(),
+            job_control: false,
+            foreground_terminal: None,
+            max_args_size: crate::sys::arg_max(),
+            job: None,
+        }
     }
-}
 
-impl ExecutableEnvironment for TokioExecEnv {
-    fn spawn_executable(
+    /// Construct a new environment which will fall back to running any
+    /// executable the OS refuses to execute (i.e. failing with `ENOEXEC`,
+    /// typically a script without a `#!` line) via the specified interpreter.
+    ///
+    /// Like POSIX shells do, the interpreter will be invoked with the path
+    /// to the executable followed by the original arguments.
+    pub fn with_enoexec_interpreter<P: Into<PathBuf>>(interpreter: P) -> Self {
+        Self {
+            enoexec_interpreter: Some(interpreter.into().into()),
+            ..Self::new()
+        }
+    }
+
+    /// Get the interpreter used for running executables which fail with `ENOEXEC`, if any.
+    pub fn enoexec_interpreter(&self) -> Option<&Path> {
+        self.enoexec_interpreter.as_deref()
+    }
+
+    /// Get the resource limits applied to every spawned executable.
+    pub fn resource_limits(&self) -> &[ResourceLimit] {
+        &self.resource_limits
+    }
+
+    /// Set the resource limits to apply to every spawned executable.
+    ///
+    /// Any limits specified by the `ExecutableData` of a particular executable
+    /// will be applied after these (i.e. they take precedence).
+    /// Limits are only supported on Unix platforms and ignored elsewhere.
+    pub fn set_resource_limits<I>(&mut self, limits: I)
+    where
+        I: IntoIterator<Item = ResourceLimit>,
+    {
+        self.resource_limits = limits.into_iter().collect();
+    }
+
+    /// Indicates if every spawned executable is placed in its own process group.
+    pub fn job_control(&self) -> bool {
+        self.job_control
+    }
+
+    /// Enable or disable job control.
+    ///
+    /// When enabled, any executable whose `ExecutableData` does not specify a
+    /// process group will be placed in a new process group of its own, as an
+    /// interactive shell would do. All executables of a job (e.g. a pipeline,
+    /// see `ExecutableEnvironment::begin_job`) share a single process group.
+    /// Process groups are only supported on Unix platforms and ignored elsewhere.
+    pub fn set_job_control(&mut self, enabled: bool) {
+        self.job_control = enabled;
+    }
+
+    /// Get the terminal whose foreground process group is managed, if any.
+    pub fn foreground_terminal(&self) -> Option<&Arc<FileDesc>> {
+        self.foreground_terminal.as_ref()
+    }
+
+    /// Specify the (controlling) terminal whose foreground process group
+    /// should be managed.
+    ///
+    /// When set, any executable placed in a process group will be given
+    /// control of the terminal (such that it will receive any signals like
+    /// `SIGINT` when the user hits Ctrl-C), and control will be returned to
+    /// the process group of the current process once the executable (or every
+    /// executable of its job) exits.
+    pub fn set_foreground_terminal(&mut self, tty: Option<Arc<FileDesc>>) {
+        self.foreground_terminal = tty;
+    }
+
+    /// Get the maximum combined size (in bytes) of arguments and environment
+    /// variables which may be passed to an executable, if any.
+    pub fn max_args_size(&self) -> Option<usize> {
+        self.max_args_size
+    }
+
+    /// Set the maximum combined size (in bytes) of arguments and environment
+    /// variables which may be passed to an executable.
+    ///
+    /// Executables whose arguments exceed this limit will fail to spawn with
+    /// a `CommandError::ArgListTooLong` error (rather than a less descriptive
+    /// error from the OS). Defaults to the limit reported by the OS, if any.
+    pub fn set_max_args_size(&mut self, limit: Option<usize>) {
+        self.max_args_size = limit;
+    }
+
+    fn spawn_child(
         &self,
-        data: ExecutableData<'_>,
-    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        mut cmd: Command,
+        data: &ExecutableData<'_>,
+        pgid: Option<u32>,
+        stdin: Option<FileDesc>,
+        stdout: Option<FileDesc>,
+        stderr: Option<FileDesc>,
+    ) -> Result<Child, IoError> {
         let stdio = |fdes: Option<FileDesc>| fdes.map(Into::into).unwrap_or_else(Stdio::null);
 
-        let name = data.name;
-        let mut cmd = Command::new(&name);
         cmd.args(data.args)
             .kill_on_drop(true) // Ensure we clean up any dropped handles
             .env_clear() // Ensure we don't inherit from the process
             .current_dir(&data.current_dir)
-            .stdin(stdio(data.stdin))
-            .stdout(stdio(data.stdout))
-            .stderr(stdio(data.stderr));
+            .stdin(stdio(stdin))
+            .stdout(stdio(stdout))
+            .stderr(stdio(stderr));
 
         // Ensure a PATH env var is defined, otherwise it appears that
         // things default to the PATH env var defined for the process
@@ -95,30 +368,348 @@ impl ExecutableEnvironment for TokioExecEnv {
             cmd.env(k, v);
         }
 
-        let child = cmd
-            .spawn()
-            .map_err(|err| map_io_err(err, name.to_string_lossy().into_owned()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            let limits = self
+                .resource_limits
+                .iter()
+                .chain(data.resource_limits)
+                .copied()
+                .collect::<Vec<_>>();
+
+            let tty = self.foreground_terminal.as_ref().map(|tty| tty.as_raw_fd());
+            let mut inherited_fds = data
+                .inherited_fds
+                .iter()
+                .map(|(fd, fdes)| (fdes.as_raw_fd(), libc::c_int::from(*fd)))
+                .collect::<Vec<_>>();
+
+            // The standard library will spawn children via `posix_spawn` (which
+            // avoids the cost of forking the entire address space) whenever it
+            // can, but any `pre_exec` closure forces a fallback to fork/exec.
+            // Thus we only register one if there is actually work for it to do:
+            // inheriting descriptors, applying limits, or joining a process group
+            // all require it, so only children which do none of these keep the
+            // `posix_spawn` fast path.
+            if !limits.is_empty() || pgid.is_some() || !inherited_fds.is_empty() {
+                // Safety: setting resource limits, process groups, and descriptors
+                // only involves invoking syscalls (no allocations or locks), which
+                // is safe to do after forking.
+                unsafe {
+                    cmd.pre_exec(move || {
+                        crate::sys::inherit_fds(&mut inherited_fds)?;
+
+                        if let Some(pgid) = pgid {
+                            crate::sys::set_process_group(0, pgid)?;
+
+                            if let Some(tty) = tty {
+                                crate::sys::set_foreground_process_group(tty, 0)?;
+                            }
+                        }
+
+                        limits.iter().try_for_each(crate::sys::set_resource_limit)
+                    });
+                }
+            }
+        }
+
+        let child = cmd.spawn()?;
+
+        // Like other shells we also set the process group from the parent to
+        // avoid racing with the child. It may have already exec'd, however,
+        // so any errors are ignored.
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            if let Some(pgid) = pgid {
+                let pid = child.id();
+                let pgid = if pgid == 0 { pid } else { pgid };
+                let _ = crate::sys::set_process_group(pid, pgid);
+
+                if let Some(ref tty) = self.foreground_terminal {
+                    let _ = crate::sys::set_foreground_process_group(tty.as_raw_fd(), pgid);
+                }
+            }
+        }
+
+        Ok(child)
+    }
+
+    /// Spawns the program, falling back to running it through the configured
+    /// interpreter (if any) should the OS fail to recognize its format.
+    fn spawn_program(
+        &self,
+        program: &OsStr,
+        data: &mut ExecutableData<'_>,
+        pgid: Option<u32>,
+    ) -> Result<Child, CommandError> {
+        let name = data.name;
+        let stdin = data.stdin.take();
+        let stdout = data.stdout.take();
+        let stderr = data.stderr.take();
+
+        match self.enoexec_interpreter {
+            None => self
+                .spawn_child(Command::new(program), data, pgid, stdin, stdout, stderr)
+                .map_err(|err| map_spawn_err(err, name, program)),
+
+            Some(ref interpreter) => {
+                // Hold on to the original descriptors in case we need to retry
+                let dup = |fdes: &Option<FileDesc>| {
+                    fdes.as_ref()
+                        .map(FileDesc::duplicate)
+                        .transpose()
+                        .map_err(|err| map_io_err(err, name))
+                };
+
+                let (dup_stdin, dup_stdout, dup_stderr) =
+                    (dup(&stdin)?, dup(&stdout)?, dup(&stderr)?);
+                match self.spawn_child(
+                    Command::new(program),
+                    data,
+                    pgid,
+                    dup_stdin,
+                    dup_stdout,
+                    dup_stderr,
+                ) {
+                    Ok(child) => Ok(child),
+                    Err(ref err) if is_enoexec(err) => {
+                        let interpreter = interpreter.as_os_str();
+                        let mut cmd = Command::new(interpreter);
+                        cmd.arg(program);
+
+                        self.spawn_child(cmd, data, pgid, stdin, stdout, stderr)
+                            .map_err(|err| map_io_err(err, interpreter))
+                    }
+                    Err(err) => Err(map_spawn_err(err, name, program)),
+                }
+            }
+        }
+    }
+
+    /// Determines the process group an executable should be placed in,
+    /// where a value of 0 indicates a new process group.
+    fn process_group_id(
+        &self,
+        data: &ExecutableData<'_>,
+        job: Option<&JobGroupState>,
+    ) -> Option<u32> {
+        match data.process_group {
+            Some(ProcessGroup::New) => Some(0),
+            Some(ProcessGroup::Join(pgid)) => Some(pgid),
+            None if self.job_control => Some(job.and_then(|job| job.pgid).unwrap_or(0)),
+            None => None,
+        }
+    }
+}
+
+impl ExecutableEnvironment for TokioExecEnv {
+    fn spawn_executable(
+        &self,
+        mut data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let name = data.name;
+
+        if let Some(limit) = self.max_args_size {
+            let size = args_size(&data);
+            if size > limit {
+                let name = name.to_string_lossy().into_owned();
+                return Err(CommandError::ArgListTooLong(name, size, limit));
+            }
+        }
+
+        let program = resolve_program(name, data.current_dir);
+
+        // Executables which don't ask for a specific process group will share
+        // one with the rest of their job, so we hold on to the job's state
+        // until the first of them has created it.
+        let job = self
+            .job
+            .as_ref()
+            .filter(|_| self.job_control && data.process_group.is_none())
+            .map(|job| (job, job.state.lock().unwrap_or_else(|e| e.into_inner())));
+
+        let pgid = self.process_group_id(&data, job.as_ref().map(|(_, state)| &**state));
+        let child = self.spawn_program(&program, &mut data, pgid)?;
+
+        let job = job.map(|(job, mut state)| {
+            state.pgid = state.pgid.or_else(|| Some(child.id()));
+            state.running += 1;
+            Arc::clone(job)
+        });
+
+        let deadline = data.deadline;
+        let reclaim_terminal = self.foreground_terminal.clone().filter(|_| pgid.is_some());
 
         Ok(Box::pin(async move {
-            child.await.map(ExitStatus::from).unwrap_or(EXIT_ERROR)
+            let status = match deadline {
+                None => child.await,
+                Some(deadline) => {
+                    let mut child = child;
+                    let delay = delay_until(deadline.into());
+                    match select(&mut child, delay).await {
+                        Either::Left((status, _)) => status,
+                        Either::Right(((), _)) => {
+                            // Deadline elapsed, kill the child but still reap it
+                            // so the caller observes the real (signal) status.
+                            let _ = child.kill();
+                            child.await
+                        }
+                    }
+                }
+            };
+
+            // The terminal is only returned to the shell once every
+            // executable of the job has exited, since they all share it.
+            let job_done = match job {
+                Some(job) => {
+                    let mut state = job.state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.running -= 1;
+                    if state.running == 0 {
+                        state.pgid = None;
+                    }
+                    state.running == 0
+                }
+                None => true,
+            };
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::io::AsRawFd;
+
+                if let Some(tty) = reclaim_terminal.filter(|_| job_done) {
+                    let _ = crate::sys::set_foreground_process_group(tty.as_raw_fd(), 0);
+                }
+            }
+
+            #[cfg(not(unix))]
+            let _ = reclaim_terminal;
+
+            status.map(ExitStatus::from).unwrap_or(EXIT_ERROR)
         }))
     }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        Some(find_executable(name, env_vars, current_dir).is_some())
+    }
+
+    fn begin_job(&mut self) -> bool {
+        if !self.job_control || self.job.is_some() {
+            return false;
+        }
+
+        self.job = Some(Arc::new(JobGroup::default()));
+        true
+    }
+
+    fn end_job(&mut self) {
+        self.job = None;
+    }
 }
 
-fn map_io_err(err: IoError, name: String) -> CommandError {
-    #[cfg(unix)]
-    fn is_enoexec(err: &IoError) -> bool {
-        Some(::libc::ENOEXEC) == err.raw_os_error()
+/// Resolves a relative path to an executable (i.e. one with multiple
+/// components, like `./script`) against the directory it will be run in,
+/// since the OS would otherwise resolve it against the current process'
+/// working directory, which need not match the environment's.
+fn resolve_program<'a>(name: &'a OsStr, current_dir: &Path) -> Cow<'a, OsStr> {
+    let path = Path::new(name);
+    if path.is_relative() && path.components().nth(1).is_some() {
+        Cow::Owned(current_dir.join(path).into_os_string())
+    } else {
+        Cow::Borrowed(name)
     }
+}
 
-    #[cfg(windows)]
-    fn is_enoexec(_err: &IoError) -> bool {
-        false
+/// Finds the path of the executable the OS would spawn for `name`, either
+/// resolved against `current_dir` if it has multiple components, or by
+/// searching the `PATH` among `env_vars`, without checking its permissions.
+pub(crate) fn find_executable(
+    name: &OsStr,
+    env_vars: &[(&OsStr, &OsStr)],
+    current_dir: &Path,
+) -> Option<PathBuf> {
+    let program = resolve_program(name, current_dir);
+    let path = Path::new(&*program);
+    if path.is_absolute() || path.components().nth(1).is_some() {
+        return Some(path.to_path_buf()).filter(|path| path.exists());
     }
 
+    // Like the OS, search the PATH the executable would be spawned with
+    let search_path = env_vars
+        .iter()
+        .rev()
+        .find(|&&(key, _)| key == "PATH")
+        .map_or(OsStr::new(""), |&(_, val)| val);
+
+    std::env::split_paths(search_path)
+        .map(|dir| current_dir.join(dir).join(path))
+        .find(|path| path.exists())
+}
+
+/// Measures the combined size of an executable's arguments and environment
+/// the same way the OS does: each string is NUL terminated and referenced by
+/// a pointer in the argument/environment arrays.
+fn args_size(data: &ExecutableData<'_>) -> usize {
+    const PTR_SIZE: usize = size_of::<*const u8>();
+
+    let args = std::iter::once(data.name)
+        .chain(data.args.iter().cloned())
+        .map(|arg| arg.len() + 1 + PTR_SIZE)
+        .sum::<usize>();
+
+    let env_vars = data
+        .env_vars
+        .iter()
+        .map(|(k, v)| k.len() + v.len() + 2 + PTR_SIZE)
+        .sum::<usize>();
+
+    args + env_vars
+}
+
+#[cfg(unix)]
+fn is_enoexec(err: &IoError) -> bool {
+    Some(::libc::ENOEXEC) == err.raw_os_error()
+}
+
+#[cfg(windows)]
+fn is_enoexec(_err: &IoError) -> bool {
+    false
+}
+
+/// Maps a failure to spawn a program, which may have been named by a path
+/// (e.g. `./script`), distinguishing a missing program from one which exists
+/// but cannot be executed (e.g. a directory, a file which the current user
+/// lacks execute permissions for, or a script whose interpreter is missing).
+fn map_spawn_err(err: IoError, name: &OsStr, program: &OsStr) -> CommandError {
+    let path = Path::new(program);
+    if path.components().nth(1).is_some() {
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.is_dir()
+                || !crate::sys::is_executable(path)
+                || IoErrorKind::NotFound == err.kind()
+            {
+                return CommandError::NotExecutable(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    map_io_err(err, name)
+}
+
+fn map_io_err(err: IoError, name: &OsStr) -> CommandError {
+    let name = name.to_string_lossy().into_owned();
+
     if IoErrorKind::NotFound == err.kind() {
         CommandError::NotFound(name)
-    } else if is_enoexec(&err) {
+    } else if IoErrorKind::PermissionDenied == err.kind() || is_enoexec(&err) {
         CommandError::NotExecutable(name)
     } else {
         CommandError::Io(err, Some(name))
@@ -0,0 +1,124 @@
+use crate::env::{ExecutableData, ExecutableEnvironment, SubEnvironment};
+use crate::error::CommandError;
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+type EnvVarFilter = Arc<dyn Fn(&OsStr, &mut Vec<(OsString, OsString)>) + Send + Sync>;
+
+/// An `ExecutableEnvironment` implementation which allows filtering or
+/// transforming the environment variables of an executable before delegating
+/// to another `ExecutableEnvironment` implementation.
+///
+/// The filter is invoked with the name of the executable and the variables
+/// which were exported to it, which it may freely modify, e.g. to strip any
+/// secrets or to inject additional variables.
+#[derive(Clone)]
+pub struct EnvVarFilterExecEnv<T> {
+    exec_env: T,
+    filter: EnvVarFilter,
+}
+
+impl<T> EnvVarFilterExecEnv<T> {
+    /// Create a new environment with a provided filter and an implementation
+    /// for delegating operations.
+    pub fn new<F>(env: T, filter: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(&OsStr, &mut Vec<(OsString, OsString)>),
+    {
+        Self {
+            exec_env: env,
+            filter: Arc::new(filter),
+        }
+    }
+
+    /// Create a new environment which only passes along the variables whose
+    /// names appear in the specified allowlist.
+    pub fn with_allowlist<I, N>(env: T, allowlist: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<OsString>,
+    {
+        let allowlist = allowlist.into_iter().map(Into::into).collect::<Vec<_>>();
+        Self::new(env, move |_, vars| {
+            vars.retain(|(name, _)| allowlist.iter().any(|allowed| allowed == name))
+        })
+    }
+
+    fn filtered_env_vars(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+    ) -> Vec<(OsString, OsString)> {
+        let mut env_vars = env_vars
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+
+        (self.filter)(name, &mut env_vars);
+        env_vars
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EnvVarFilterExecEnv<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("EnvVarFilterExecEnv")
+            .field("exec_env", &self.exec_env)
+            .field("filter", &"..")
+            .finish()
+    }
+}
+
+impl<T: SubEnvironment> SubEnvironment for EnvVarFilterExecEnv<T> {
+    fn sub_env(&self) -> Self {
+        Self {
+            exec_env: self.exec_env.sub_env(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<T: ExecutableEnvironment> ExecutableEnvironment for EnvVarFilterExecEnv<T> {
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let env_vars = self.filtered_env_vars(data.name, data.env_vars);
+        let env_vars = env_vars
+            .iter()
+            .map(|(k, v)| (k.as_os_str(), v.as_os_str()))
+            .collect::<Vec<_>>();
+
+        self.exec_env.spawn_executable(ExecutableData {
+            env_vars: &env_vars,
+            ..data
+        })
+    }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        let env_vars = self.filtered_env_vars(name, env_vars);
+        let env_vars = env_vars
+            .iter()
+            .map(|(k, v)| (k.as_os_str(), v.as_os_str()))
+            .collect::<Vec<_>>();
+
+        self.exec_env
+            .executable_exists(name, &env_vars, current_dir)
+    }
+
+    fn begin_job(&mut self) -> bool {
+        self.exec_env.begin_job()
+    }
+
+    fn end_job(&mut self) {
+        self.exec_env.end_job()
+    }
+}
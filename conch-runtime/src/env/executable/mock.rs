@@ -0,0 +1,225 @@
+use crate::env::TokioAsyncIoEnv;
+use crate::env::{AsyncIoEnvironment, ExecutableData, ExecutableEnvironment, SubEnvironment};
+use crate::error::CommandError;
+use crate::io::FileDesc;
+use crate::{ExitStatus, Fd, EXIT_SUCCESS};
+use futures_core::future::BoxFuture;
+use futures_util::future::join;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The scripted result of running a mocked executable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockExecOutput {
+    /// Data which will be written to the command's standard output.
+    pub stdout: Vec<u8>,
+    /// Data which will be written to the command's standard error.
+    pub stderr: Vec<u8>,
+    /// The exit status the command will resolve with.
+    pub status: ExitStatus,
+}
+
+impl MockExecOutput {
+    /// Create an output which writes nothing and exits with the specified status.
+    pub fn status(status: ExitStatus) -> Self {
+        Self {
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            status,
+        }
+    }
+
+    /// Specify the data to be written to the command's standard output.
+    pub fn with_stdout<T: Into<Vec<u8>>>(mut self, stdout: T) -> Self {
+        self.stdout = stdout.into();
+        self
+    }
+
+    /// Specify the data to be written to the command's standard error.
+    pub fn with_stderr<T: Into<Vec<u8>>>(mut self, stderr: T) -> Self {
+        self.stderr = stderr.into();
+        self
+    }
+}
+
+impl Default for MockExecOutput {
+    fn default() -> Self {
+        Self::status(EXIT_SUCCESS)
+    }
+}
+
+/// An owned record of an attempt to spawn an executable through a `MockExecEnv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockExecInvocation {
+    /// The name/path to the executable.
+    pub name: OsString,
+    /// Arguments provided to the executable.
+    pub args: Vec<OsString>,
+    /// Environment variables provided to the executable.
+    pub env_vars: Vec<(OsString, OsString)>,
+    /// The working directory the executable was started with.
+    pub current_dir: PathBuf,
+    /// The (non-standard) descriptors the executable was to inherit.
+    pub inherited_fds: Vec<Fd>,
+}
+
+impl<'a> From<&'a ExecutableData<'a>> for MockExecInvocation {
+    fn from(data: &'a ExecutableData<'a>) -> Self {
+        Self {
+            name: data.name.to_owned(),
+            args: data.args.iter().map(|&a| a.to_owned()).collect(),
+            env_vars: data
+                .env_vars
+                .iter()
+                .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+            current_dir: data.current_dir.to_owned(),
+            inherited_fds: data.inherited_fds.iter().map(|&(fd, _)| fd).collect(),
+        }
+    }
+}
+
+type MockHandler =
+    Arc<dyn Fn(&MockExecInvocation) -> Result<MockExecOutput, CommandError> + Send + Sync>;
+
+/// An `ExecutableEnvironment` implementation which never spawns real processes.
+///
+/// Instead, command names are registered with canned outputs (or closures which
+/// produce them), and `spawn_executable` consults the registry to determine what
+/// to write to the command's stdout/stderr and what status to exit with. Any
+/// command which has not been registered will result in a `CommandError::NotFound`.
+///
+/// Commands are looked up by the exact name they are invoked with, and if that
+/// fails, by the file name of the path they are invoked with (e.g. registering
+/// `ls` will also match invocations of `/bin/ls`).
+///
+/// All invocations are recorded and can be inspected via `invocations`. Note that
+/// any sub-environments will share the same registry and invocation records
+/// as their parent.
+#[derive(Clone, Default)]
+pub struct MockExecEnv {
+    handlers: Arc<HashMap<OsString, MockHandler>>,
+    invocations: Arc<Mutex<Vec<MockExecInvocation>>>,
+}
+
+impl MockExecEnv {
+    /// Construct a new environment with no registered commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a command which will always produce the specified output.
+    pub fn register<N: Into<OsString>>(&mut self, name: N, output: MockExecOutput) {
+        self.register_fn(name, move |_| Ok(output.clone()));
+    }
+
+    /// Register a command whose outcome is determined by a closure at the time
+    /// the command is spawned.
+    ///
+    /// Returning an error from the closure will be treated as a failure to spawn
+    /// the command (e.g. returning `CommandError::NotExecutable` will behave as if
+    /// the executable lacked the appropriate permissions).
+    pub fn register_fn<N, F>(&mut self, name: N, handler: F)
+    where
+        N: Into<OsString>,
+        F: 'static + Send + Sync + Fn(&MockExecInvocation) -> Result<MockExecOutput, CommandError>,
+    {
+        Arc::make_mut(&mut self.handlers).insert(name.into(), Arc::new(handler));
+    }
+
+    /// Unregister a previously registered command.
+    pub fn unregister<N: AsRef<OsStr>>(&mut self, name: N) {
+        Arc::make_mut(&mut self.handlers).remove(name.as_ref());
+    }
+
+    /// Get a copy of all invocations recorded thus far, in the order they were made.
+    pub fn invocations(&self) -> Vec<MockExecInvocation> {
+        self.invocations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn handler(&self, name: &OsStr) -> Option<&MockHandler> {
+        self.handlers.get(name).or_else(|| {
+            Path::new(name)
+                .file_name()
+                .and_then(|file_name| self.handlers.get(file_name))
+        })
+    }
+}
+
+impl fmt::Debug for MockExecEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.handlers.keys().collect::<Vec<_>>();
+        names.sort();
+
+        fmt.debug_struct("MockExecEnv")
+            .field("registered", &names)
+            .field("invocations", &self.invocations)
+            .finish()
+    }
+}
+
+impl SubEnvironment for MockExecEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ExecutableEnvironment for MockExecEnv {
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let invocation = MockExecInvocation::from(&data);
+        self.invocations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(invocation.clone());
+
+        let output = match self.handler(data.name) {
+            Some(handler) => handler(&invocation)?,
+            None => {
+                let name = data.name.to_string_lossy().into_owned();
+                return Err(CommandError::NotFound(name));
+            }
+        };
+
+        let MockExecOutput {
+            stdout,
+            stderr,
+            status,
+        } = output;
+
+        let stdout = write_best_effort(data.stdout, stdout);
+        let stderr = write_best_effort(data.stderr, stderr);
+
+        Ok(Box::pin(async move {
+            let _ = join(stdout, stderr).await;
+            status
+        }))
+    }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        _env_vars: &[(&OsStr, &OsStr)],
+        _current_dir: &Path,
+    ) -> Option<bool> {
+        Some(self.handler(name).is_some())
+    }
+}
+
+async fn write_best_effort(fd: Option<FileDesc>, data: Vec<u8>) {
+    // Like a real process, we don't care if the reader went away
+    if let Some(fd) = fd {
+        if !data.is_empty() {
+            let _ = TokioAsyncIoEnv::new().write_all(fd, Cow::Owned(data)).await;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::env::{ExecutableData, ExecutableEnvironment, SubEnvironment};
+use crate::error::CommandError;
+use crate::io::FileDesc;
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+type CommandNotFoundHook = Arc<
+    dyn Fn(ExecutableData<'_>) -> Result<BoxFuture<'static, ExitStatus>, CommandError>
+        + Send
+        + Sync,
+>;
+
+/// An `ExecutableEnvironment` implementation which delegates to another
+/// `ExecutableEnvironment` implementation, but invokes a hook whenever an
+/// executable cannot be found, before the failure is reported.
+///
+/// The hook receives the original data of the executable (including its
+/// standard descriptors) and may, for example, write a suggestion of which
+/// package provides the command to its standard error, install the command
+/// and retry, or run some host function in its place.
+///
+/// Missing executables are detected ahead of time via
+/// `ExecutableEnvironment::executable_exists` where possible. Otherwise the
+/// descriptors are duplicated before attempting to spawn the executable, so
+/// they remain available to the hook. Should an executable which was found
+/// disappear before it is spawned, the hook is invoked without descriptors.
+///
+/// Whatever the hook returns becomes the outcome of spawning the executable,
+/// thus returning a `CommandError::NotFound` error preserves the usual
+/// "command not found" behavior (i.e. exiting with a status of 127).
+#[derive(Clone)]
+pub struct CommandNotFoundExecEnv<T> {
+    exec_env: T,
+    hook: CommandNotFoundHook,
+}
+
+impl<T> CommandNotFoundExecEnv<T> {
+    /// Create a new environment with a provided hook and an implementation
+    /// for delegating operations.
+    pub fn new<F>(env: T, hook: F) -> Self
+    where
+        F: 'static
+            + Send
+            + Sync
+            + Fn(ExecutableData<'_>) -> Result<BoxFuture<'static, ExitStatus>, CommandError>,
+    {
+        Self {
+            exec_env: env,
+            hook: Arc::new(hook),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CommandNotFoundExecEnv<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("CommandNotFoundExecEnv")
+            .field("exec_env", &self.exec_env)
+            .field("hook", &"..")
+            .finish()
+    }
+}
+
+impl<T: SubEnvironment> SubEnvironment for CommandNotFoundExecEnv<T> {
+    fn sub_env(&self) -> Self {
+        Self {
+            exec_env: self.exec_env.sub_env(),
+            hook: self.hook.clone(),
+        }
+    }
+}
+
+impl<T: ExecutableEnvironment> ExecutableEnvironment for CommandNotFoundExecEnv<T> {
+    fn spawn_executable(
+        &self,
+        mut data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        match self
+            .exec_env
+            .executable_exists(data.name, data.env_vars, data.current_dir)
+        {
+            Some(false) => return (self.hook)(data),
+            Some(true) => {
+                let ExecutableData {
+                    name,
+                    args,
+                    env_vars,
+                    current_dir,
+                    deadline,
+                    resource_limits,
+                    process_group,
+                    ..
+                } = data;
+
+                // The executable may still have disappeared since we checked,
+                // but the original descriptors have been consumed by then
+                return match self.exec_env.spawn_executable(data) {
+                    Err(CommandError::NotFound(_)) => (self.hook)(ExecutableData {
+                        name,
+                        args,
+                        env_vars,
+                        current_dir,
+                        stdin: None,
+                        stdout: None,
+                        stderr: None,
+                        inherited_fds: Vec::new(),
+                        deadline,
+                        resource_limits,
+                        process_group,
+                    }),
+                    result => result,
+                };
+            }
+            None => {}
+        }
+
+        // We can't tell ahead of time if the executable exists, so hold on
+        // to the original descriptors in case the hook needs them
+        let dup = |fdes: &FileDesc| {
+            fdes.duplicate().map_err(|err| {
+                let name = data.name.to_string_lossy().into_owned();
+                CommandError::Io(err, Some(name))
+            })
+        };
+
+        let stdin = data.stdin.as_ref().map(dup).transpose()?;
+        let stdout = data.stdout.as_ref().map(dup).transpose()?;
+        let stderr = data.stderr.as_ref().map(dup).transpose()?;
+        let inherited_fds = data
+            .inherited_fds
+            .iter()
+            .map(|(fd, fdes)| dup(fdes).map(|fdes| (*fd, fdes)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let attempt = ExecutableData {
+            stdin: data.stdin.take(),
+            stdout: data.stdout.take(),
+            stderr: data.stderr.take(),
+            inherited_fds: mem::replace(&mut data.inherited_fds, inherited_fds),
+            ..data
+        };
+
+        match self.exec_env.spawn_executable(attempt) {
+            Err(CommandError::NotFound(_)) => (self.hook)(ExecutableData {
+                stdin,
+                stdout,
+                stderr,
+                ..data
+            }),
+            result => result,
+        }
+    }
+
+    fn begin_job(&mut self) -> bool {
+        self.exec_env.begin_job()
+    }
+
+    fn end_job(&mut self) {
+        self.exec_env.end_job()
+    }
+}
@@ -12,6 +12,8 @@ pub trait FileDescEnvironment {
     type FileHandle;
     /// Get the permissions and a handle associated with an opened file descriptor.
     fn file_desc(&self, fd: Fd) -> Option<(&Self::FileHandle, Permissions)>;
+    /// Get all opened file descriptors, in ascending order.
+    fn file_descs(&self) -> Vec<Fd>;
     /// Associate a file descriptor with a given handle and permissions.
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions);
     /// Treat the specified file descriptor as closed for the current environment.
@@ -25,6 +27,10 @@ impl<'a, T: ?Sized + FileDescEnvironment> FileDescEnvironment for &'a mut T {
         (**self).file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        (**self).file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
         (**self).set_file_desc(fd, handle, perms)
     }
@@ -141,6 +147,12 @@ impl<T: Clone + Eq> FileDescEnvironment for FileDescEnv<T> {
             .map(|&(ref handle, perms)| (handle, perms))
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        let mut fds = self.fds.keys().copied().collect::<Vec<_>>();
+        fds.sort_unstable();
+        fds
+    }
+
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
         let needs_insert = {
             let existing = self
@@ -1,7 +1,11 @@
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, Pipe, SubEnvironment};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, Pipe, SubEnvironment, TempFile,
+    TempFileEnvironment,
+};
 use crate::io::Permissions;
 use crate::Fd;
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::Cow;
 use std::fs::OpenOptions;
 use std::io;
@@ -17,6 +21,30 @@ pub trait FileDescManagerEnvironment:
     + FileDescEnvironment<FileHandle = <Self as FileDescOpener>::OpenedFileHandle>
     + AsyncIoEnvironment<IoHandle = <Self as FileDescOpener>::OpenedFileHandle>
 {
+    /// Finds the lowest descriptor, greater than or equal to 10, which is not
+    /// currently in use.
+    ///
+    /// Descriptors below 10 are reserved for use by scripts, so automatically
+    /// allocated descriptors (e.g. for `{var}>file` redirects) should never
+    /// be lower.
+    ///
+    /// An error is returned if every such descriptor is already in use.
+    fn unused_fd(&self) -> io::Result<Fd> {
+        (10..=Fd::MAX)
+            .find(|&fd| self.file_desc(fd).is_none())
+            .ok_or_else(|| io::Error::other("too many open file descriptors"))
+    }
+
+    /// Stores a file handle at the lowest unused descriptor greater than or
+    /// equal to 10, returning the allocated descriptor.
+    ///
+    /// An error is returned (and the handle is dropped) if every such
+    /// descriptor is already in use.
+    fn alloc_fd(&mut self, handle: Self::FileHandle, perms: Permissions) -> io::Result<Fd> {
+        let fd = self.unused_fd()?;
+        self.set_file_desc(fd, handle, perms);
+        Ok(fd)
+    }
 }
 
 impl<T> FileDescManagerEnvironment for T
@@ -76,6 +104,17 @@ where
             .map(Self::OpenedFileHandle::from)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.opener
+            .open_path_with_permissions(path, perms, opts)
+            .map(Self::OpenedFileHandle::from)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         self.opener.open_pipe().map(|pipe| Pipe {
             reader: pipe.reader.into(),
@@ -84,6 +123,31 @@ where
     }
 }
 
+impl<O, S, A> TempFileEnvironment for FileDescManagerEnv<O, S, A>
+where
+    O: TempFileEnvironment,
+    A: AsyncIoEnvironment,
+    A::IoHandle: From<O::OpenedFileHandle>,
+{
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.opener
+            .open_temp_file(contents)
+            .map(Self::OpenedFileHandle::from)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.opener
+            .open_named_temp_file(contents)
+            .map(|file| TempFile {
+                handle: file.handle.into(),
+                path: file.path,
+            })
+    }
+}
+
 impl<O, S, A> FileDescEnvironment for FileDescManagerEnv<O, S, A>
 where
     S: FileDescEnvironment,
@@ -94,6 +158,10 @@ where
         self.storer.file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        self.storer.file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
         self.storer.set_file_desc(fd, handle, perms)
     }
@@ -113,6 +181,10 @@ where
         self.async_env.read_all(fd)
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        self.async_env.read_chunks(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -1,11 +1,12 @@
 use crate::env::{
     ArcFileDescOpenerEnv, ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, FileDescEnv,
     FileDescEnvironment, FileDescManagerEnv, FileDescOpener, FileDescOpenerEnv, Pipe,
-    SubEnvironment, TokioAsyncIoEnv,
+    SubEnvironment, TempFile, TempFileEnvironment, TokioAsyncIoEnv,
 };
 use crate::io::{FileDesc, Permissions};
 use crate::Fd;
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::Cow;
 use std::fs::OpenOptions;
 use std::io;
@@ -67,11 +68,33 @@ impl FileDescOpener for TokioFileDescManagerEnv {
         self.inner.open_path(path, opts)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.inner.open_path_with_permissions(path, perms, opts)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         self.inner.open_pipe()
     }
 }
 
+impl TempFileEnvironment for TokioFileDescManagerEnv {
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.inner.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.inner.open_named_temp_file(contents)
+    }
+}
+
 impl FileDescEnvironment for TokioFileDescManagerEnv {
     type FileHandle = Arc<FileDesc>;
 
@@ -79,6 +102,10 @@ impl FileDescEnvironment for TokioFileDescManagerEnv {
         self.inner.file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        self.inner.file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
         self.inner.set_file_desc(fd, handle, perms)
     }
@@ -95,6 +122,10 @@ impl AsyncIoEnvironment for TokioFileDescManagerEnv {
         self.inner.read_all(fd)
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        self.inner.read_chunks(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
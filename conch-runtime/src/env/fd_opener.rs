@@ -1,10 +1,19 @@
 use crate::env::SubEnvironment;
-use crate::io::{FileDesc, Pipe as OsPipe};
+use crate::io::{FileDesc, Permissions, Pipe as OsPipe};
 use std::fs::OpenOptions;
 use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
+/// The portable path of the null device.
+const DEV_NULL: &str = "/dev/null";
+
+/// The path of the platform's actual null device.
+#[cfg(unix)]
+const NULL_DEVICE: &str = "/dev/null";
+#[cfg(windows)]
+const NULL_DEVICE: &str = "NUL";
+
 /// A pipe reader/writer pair created by a `FileDescOpener`.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Pipe<T> {
@@ -21,6 +30,21 @@ pub trait FileDescOpener {
 
     /// Open a provided `path` with the specified `OpenOptions`.
     fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle>;
+
+    /// Open a provided `path` with the specified `OpenOptions`, which grant
+    /// the specified `Permissions` (e.g. when opening the file of a redirect).
+    ///
+    /// Since `OpenOptions` cannot be inspected, this allows implementations
+    /// to restrict how a path may be opened (e.g. only for reading).
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        _perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.open_path(path, opts)
+    }
+
     /// Create a new `Pipe` pair.
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>>;
 }
@@ -32,6 +56,15 @@ impl<'a, T: ?Sized + FileDescOpener> FileDescOpener for &'a mut T {
         (**self).open_path(path, opts)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        (**self).open_path_with_permissions(path, perms, opts)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         (**self).open_pipe()
     }
@@ -57,7 +90,15 @@ impl SubEnvironment for FileDescOpenerEnv {
 impl FileDescOpener for FileDescOpenerEnv {
     type OpenedFileHandle = FileDesc;
 
+    /// Opens the file at `path`, treating `/dev/null` as the platform's null
+    /// device (e.g. `NUL` on Windows) so that scripts can portably use it.
     fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+        let path = if path == Path::new(DEV_NULL) {
+            Path::new(NULL_DEVICE)
+        } else {
+            path
+        };
+
         opts.open(path).map(FileDesc::from)
     }
 
@@ -73,7 +114,7 @@ impl FileDescOpener for FileDescOpenerEnv {
 /// but wraps any returned handles with in an `Arc`.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct ArcFileDescOpenerEnv<O> {
-    opener: O,
+    pub(super) opener: O,
 }
 
 impl<O> ArcFileDescOpenerEnv<O> {
@@ -98,6 +139,17 @@ impl<O: FileDescOpener> FileDescOpener for ArcFileDescOpenerEnv<O> {
         self.opener.open_path(path, opts).map(Arc::new)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.opener
+            .open_path_with_permissions(path, perms, opts)
+            .map(Arc::new)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         self.opener.open_pipe().map(|pipe| Pipe {
             reader: Arc::new(pipe.reader),
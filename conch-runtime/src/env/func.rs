@@ -1,4 +1,5 @@
 use crate::env::SubEnvironment;
+use std::any::Any;
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::Hash;
@@ -20,6 +21,18 @@ pub trait FunctionEnvironment {
     fn has_function(&self, name: &Self::FnName) -> bool {
         self.function(name).is_some()
     }
+
+    /// Records the body of a function which was just registered (e.g. its
+    /// AST), so that its definition can later be exported to child shells.
+    ///
+    /// Environments which do not keep track of function bodies can ignore this.
+    fn set_function_body(&mut self, _name: &Self::FnName, _body: Arc<dyn Any + Send + Sync>) {}
+
+    /// Get the body of a particularly named function, if it was recorded
+    /// since the function was (re-)registered.
+    fn function_body(&self, _name: &Self::FnName) -> Option<&(dyn Any + Send + Sync)> {
+        None
+    }
 }
 
 impl<'a, T: ?Sized + FunctionEnvironment> FunctionEnvironment for &'a mut T {
@@ -37,6 +50,39 @@ impl<'a, T: ?Sized + FunctionEnvironment> FunctionEnvironment for &'a mut T {
     fn has_function(&self, name: &Self::FnName) -> bool {
         (**self).has_function(name)
     }
+
+    fn set_function_body(&mut self, name: &Self::FnName, body: Arc<dyn Any + Send + Sync>) {
+        (**self).set_function_body(name, body);
+    }
+
+    fn function_body(&self, name: &Self::FnName) -> Option<&(dyn Any + Send + Sync)> {
+        (**self).function_body(name)
+    }
+}
+
+/// An interface for recovering the source code of a function's body.
+pub trait FunctionSource {
+    /// Renders the body as shell source code, which yields an equivalent
+    /// body when parsed again.
+    fn function_source(&self) -> String;
+}
+
+impl<'a, T: ?Sized + FunctionSource> FunctionSource for &'a T {
+    fn function_source(&self) -> String {
+        (**self).function_source()
+    }
+}
+
+impl<T: ?Sized + FunctionSource> FunctionSource for Box<T> {
+    fn function_source(&self) -> String {
+        (**self).function_source()
+    }
+}
+
+impl<T: ?Sized + FunctionSource> FunctionSource for Arc<T> {
+    fn function_source(&self) -> String {
+        (**self).function_source()
+    }
 }
 
 /// An interface for unsetting shell functions.
@@ -59,6 +105,14 @@ pub trait FunctionFrameEnvironment {
     fn pop_fn_frame(&mut self);
     /// Determines if there is at least one function being currently executed.
     fn is_fn_running(&self) -> bool;
+    /// The number of functions currently being executed.
+    fn fn_frame_depth(&self) -> usize;
+    /// The maximum number of functions which may be executed at once (i.e.
+    /// the maximum nesting depth), if limited.
+    fn max_fn_frame_depth(&self) -> Option<usize>;
+    /// Changes (or removes) the maximum number of functions which may be
+    /// executed at once.
+    fn set_max_fn_frame_depth(&mut self, max_depth: Option<usize>);
 }
 
 impl<'a, T: ?Sized + FunctionFrameEnvironment> FunctionFrameEnvironment for &'a mut T {
@@ -73,18 +127,51 @@ impl<'a, T: ?Sized + FunctionFrameEnvironment> FunctionFrameEnvironment for &'a
     fn is_fn_running(&self) -> bool {
         (**self).is_fn_running()
     }
+
+    fn fn_frame_depth(&self) -> usize {
+        (**self).fn_frame_depth()
+    }
+
+    fn max_fn_frame_depth(&self) -> Option<usize> {
+        (**self).max_fn_frame_depth()
+    }
+
+    fn set_max_fn_frame_depth(&mut self, max_depth: Option<usize>) {
+        (**self).set_max_fn_frame_depth(max_depth);
+    }
 }
 
 /// An implementation of `FunctionFrameEnvironment`
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+///
+/// By default, functions may be nested up to `DEFAULT_MAX_DEPTH` levels deep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FnFrameEnv {
     num_frames: usize,
+    max_frames: Option<usize>,
 }
 
 impl FnFrameEnv {
+    /// The default maximum function nesting depth.
+    pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
     /// Create a new environment instance.
     pub fn new() -> Self {
-        Self { num_frames: 0 }
+        Self::with_max_depth(Some(Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Create a new environment instance which limits how deeply
+    /// functions may be nested (or not at all if `None`).
+    pub fn with_max_depth(max_depth: Option<usize>) -> Self {
+        Self {
+            num_frames: 0,
+            max_frames: max_depth,
+        }
+    }
+}
+
+impl Default for FnFrameEnv {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -108,6 +195,18 @@ impl FunctionFrameEnvironment for FnFrameEnv {
     fn is_fn_running(&self) -> bool {
         self.num_frames > 0
     }
+
+    fn fn_frame_depth(&self) -> usize {
+        self.num_frames
+    }
+
+    fn max_fn_frame_depth(&self) -> Option<usize> {
+        self.max_frames
+    }
+
+    fn set_max_fn_frame_depth(&mut self, max_depth: Option<usize>) {
+        self.max_frames = max_depth;
+    }
 }
 
 impl SubEnvironment for FnFrameEnv {
@@ -117,9 +216,12 @@ impl SubEnvironment for FnFrameEnv {
 }
 
 /// An environment module for setting and getting shell functions.
-#[derive(PartialEq, Eq)]
+///
+/// The body of each function is recorded alongside its definition (if
+/// provided), until the function is redefined or unset.
 pub struct FnEnv<N: Hash + Eq, F> {
     functions: Arc<HashMap<N, F>>,
+    bodies: Arc<HashMap<N, Arc<dyn Any + Send + Sync>>>,
 }
 
 impl<N: Hash + Eq, F> FnEnv<N, F> {
@@ -127,6 +229,7 @@ impl<N: Hash + Eq, F> FnEnv<N, F> {
     pub fn new() -> Self {
         Self {
             functions: HashMap::new().into(),
+            bodies: HashMap::new().into(),
         }
     }
 
@@ -150,6 +253,21 @@ where
     }
 }
 
+impl<N: Hash + Eq, F: PartialEq> PartialEq for FnEnv<N, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.functions == other.functions
+            && self.bodies.len() == other.bodies.len()
+            && self.bodies.iter().all(|(name, body)| {
+                other
+                    .bodies
+                    .get(name)
+                    .is_some_and(|other| Arc::ptr_eq(body, other))
+            })
+    }
+}
+
+impl<N: Hash + Eq, F: Eq> Eq for FnEnv<N, F> {}
+
 impl<N: Hash + Eq, F> Default for FnEnv<N, F> {
     fn default() -> Self {
         Self::new()
@@ -160,6 +278,7 @@ impl<N: Hash + Eq, F> Clone for FnEnv<N, F> {
     fn clone(&self) -> Self {
         Self {
             functions: self.functions.clone(),
+            bodies: self.bodies.clone(),
         }
     }
 }
@@ -183,8 +302,22 @@ where
     }
 
     fn set_function(&mut self, name: Self::FnName, func: Self::Fn) {
+        if self.bodies.contains_key(&name) {
+            Arc::make_mut(&mut self.bodies).remove(&name);
+        }
+
         Arc::make_mut(&mut self.functions).insert(name, func);
     }
+
+    fn set_function_body(&mut self, name: &Self::FnName, body: Arc<dyn Any + Send + Sync>) {
+        if self.has_function(name) {
+            Arc::make_mut(&mut self.bodies).insert(name.clone(), body);
+        }
+    }
+
+    fn function_body(&self, name: &Self::FnName) -> Option<&(dyn Any + Send + Sync)> {
+        self.bodies.get(name).map(|body| &**body)
+    }
 }
 
 impl<N, F> UnsetFunctionEnvironment for FnEnv<N, F>
@@ -196,6 +329,10 @@ where
         if self.has_function(name) {
             Arc::make_mut(&mut self.functions).remove(name);
         }
+
+        if self.bodies.contains_key(name) {
+            Arc::make_mut(&mut self.bodies).remove(name);
+        }
     }
 }
 
@@ -217,6 +354,32 @@ mod tests {
         assert_eq!(env.function(&name), None);
     }
 
+    #[test]
+    fn test_function_body_tracked_until_redefined_or_unset() {
+        let name = "var";
+        let body = Arc::new("{ echo body; }");
+        let get_body = |env: &FnEnv<_, _>| {
+            env.function_body(&name)
+                .and_then(|body| body.downcast_ref::<&str>())
+                .copied()
+        };
+
+        let mut env = FnEnv::new();
+        env.set_function_body(&name, body.clone());
+        assert_eq!(get_body(&env), None);
+
+        env.set_function(name, 1);
+        env.set_function_body(&name, body.clone());
+        assert_eq!(get_body(&env), Some("{ echo body; }"));
+
+        env.set_function(name, 2);
+        assert_eq!(get_body(&env), None);
+
+        env.set_function_body(&name, body);
+        env.unset_function(&name);
+        assert_eq!(get_body(&env), None);
+    }
+
     #[test]
     fn test_sub_env_no_needless_clone() {
         let not_set = "not set";
@@ -297,6 +460,25 @@ mod tests {
         assert_eq!(env.is_fn_running(), false);
     }
 
+    #[test]
+    fn test_fn_frame_depth() {
+        let mut env = FnFrameEnv::new();
+        assert_eq!(
+            env.max_fn_frame_depth(),
+            Some(FnFrameEnv::DEFAULT_MAX_DEPTH)
+        );
+        assert_eq!(env.fn_frame_depth(), 0);
+
+        env.push_fn_frame();
+        env.push_fn_frame();
+        assert_eq!(env.fn_frame_depth(), 2);
+
+        env.set_max_fn_frame_depth(None);
+        let sub_env = env.sub_env();
+        assert_eq!(sub_env.fn_frame_depth(), 2);
+        assert_eq!(sub_env.max_fn_frame_depth(), None);
+    }
+
     #[test]
     #[should_panic(expected = "function frame overflow")]
     fn test_fn_frame_overflow() {
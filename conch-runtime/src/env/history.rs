@@ -0,0 +1,158 @@
+use crate::env::SubEnvironment;
+use std::sync::Arc;
+
+/// An interface for maintaining the list of previously executed command
+/// lines, such as the one displayed by the `history` builtin.
+///
+/// Each entry is numbered, starting at 1 for the first ever entry; numbers
+/// of existing entries do not change if older entries are discarded.
+pub trait HistoryEnvironment {
+    /// Get all recorded entries, oldest first.
+    fn history(&self) -> &[String];
+
+    /// The number of the oldest recorded entry.
+    fn history_base(&self) -> usize;
+
+    /// Records a new entry, discarding the oldest entries if the limit would
+    /// be exceeded.
+    fn add_history(&mut self, entry: String);
+
+    /// Discards all recorded entries, numbering new entries from 1 again.
+    fn clear_history(&mut self);
+
+    /// The maximum number of entries which will be retained, if limited.
+    fn history_limit(&self) -> Option<usize>;
+
+    /// Changes (or removes) the maximum number of retained entries,
+    /// discarding the oldest entries if the new limit is exceeded.
+    fn set_history_limit(&mut self, limit: Option<usize>);
+}
+
+impl<'a, T: ?Sized + HistoryEnvironment> HistoryEnvironment for &'a mut T {
+    fn history(&self) -> &[String] {
+        (**self).history()
+    }
+
+    fn history_base(&self) -> usize {
+        (**self).history_base()
+    }
+
+    fn add_history(&mut self, entry: String) {
+        (**self).add_history(entry);
+    }
+
+    fn clear_history(&mut self) {
+        (**self).clear_history();
+    }
+
+    fn history_limit(&self) -> Option<usize> {
+        (**self).history_limit()
+    }
+
+    fn set_history_limit(&mut self, limit: Option<usize>) {
+        (**self).set_history_limit(limit);
+    }
+}
+
+/// An environment module for maintaining the command history.
+///
+/// By default, up to `DEFAULT_LIMIT` entries are retained.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEnv {
+    entries: Arc<Vec<String>>,
+    base: usize,
+    limit: Option<usize>,
+}
+
+impl HistoryEnv {
+    /// The default maximum number of retained entries.
+    pub const DEFAULT_LIMIT: usize = 500;
+
+    /// Constructs a new environment with no recorded entries.
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Vec::new()),
+            base: 1,
+            limit: Some(Self::DEFAULT_LIMIT),
+        }
+    }
+
+    fn truncate(&mut self) {
+        let excess = match self.limit {
+            Some(limit) if self.entries.len() > limit => self.entries.len() - limit,
+            _ => return,
+        };
+
+        Arc::make_mut(&mut self.entries).drain(..excess);
+        self.base += excess;
+    }
+}
+
+impl Default for HistoryEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistoryEnvironment for HistoryEnv {
+    fn history(&self) -> &[String] {
+        &self.entries
+    }
+
+    fn history_base(&self) -> usize {
+        self.base
+    }
+
+    fn add_history(&mut self, entry: String) {
+        Arc::make_mut(&mut self.entries).push(entry);
+        self.truncate();
+    }
+
+    fn clear_history(&mut self) {
+        self.entries = Arc::new(Vec::new());
+        self.base = 1;
+    }
+
+    fn history_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+        self.truncate();
+    }
+}
+
+impl SubEnvironment for HistoryEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_numbered_and_limited() {
+        let mut env = HistoryEnv::new();
+        env.set_history_limit(Some(2));
+
+        env.add_history("foo".to_owned());
+        env.add_history("bar".to_owned());
+        assert_eq!(env.history(), ["foo", "bar"]);
+        assert_eq!(env.history_base(), 1);
+
+        env.add_history("baz".to_owned());
+        assert_eq!(env.history(), ["bar", "baz"]);
+        assert_eq!(env.history_base(), 2);
+
+        env.set_history_limit(Some(1));
+        assert_eq!(env.history(), ["baz"]);
+        assert_eq!(env.history_base(), 3);
+
+        env.clear_history();
+        assert!(env.history().is_empty());
+        assert_eq!(env.history_base(), 1);
+    }
+}
@@ -0,0 +1,148 @@
+//! Read-only queries over the definitions of an environment, useful to tools
+//! embedding the runtime, such as completion engines.
+
+use crate::env::{StrKey, VariableEnvironment, WorkingDirectoryEnvironment};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const PATH: &str = "PATH";
+
+/// An interface for listing the names defined in an environment, e.g. to
+/// offer them as completions of a partially typed command or variable.
+pub trait IntrospectEnvironment {
+    /// Lists the names of all defined functions, sorted.
+    fn function_names(&self) -> Vec<String>;
+
+    /// Lists the names of all available builtin utilities, sorted.
+    fn builtin_names(&self) -> Vec<String>;
+
+    /// Lists the names of all defined aliases, sorted.
+    fn alias_names(&self) -> Vec<String>;
+
+    /// Lists the names of all exported variables, sorted.
+    fn exported_var_names(&self) -> Vec<String>;
+}
+
+impl<'a, T: ?Sized + IntrospectEnvironment> IntrospectEnvironment for &'a T {
+    fn function_names(&self) -> Vec<String> {
+        (**self).function_names()
+    }
+
+    fn builtin_names(&self) -> Vec<String> {
+        (**self).builtin_names()
+    }
+
+    fn alias_names(&self) -> Vec<String> {
+        (**self).alias_names()
+    }
+
+    fn exported_var_names(&self) -> Vec<String> {
+        (**self).exported_var_names()
+    }
+}
+
+/// Lists the executables which can be found via `$PATH`.
+///
+/// The contents of each scanned directory are cached until the directory
+/// itself is modified, making it cheap to repeatedly list executables (e.g.
+/// on every completion request). Note that changing the permissions of an
+/// existing file does not modify its directory, in which case `clear` can be
+/// used to force a rescan.
+#[derive(Debug, Default, Clone)]
+pub struct PathScanner {
+    dirs: HashMap<PathBuf, ScannedDir>,
+}
+
+#[derive(Debug, Clone)]
+struct ScannedDir {
+    modified: SystemTime,
+    executables: Vec<String>,
+}
+
+impl PathScanner {
+    /// Constructs a new scanner which has not yet scanned any directories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists the names of all executables found in the directories listed in
+    /// `$PATH` (relative to the environment's working directory), sorted and
+    /// without duplicates.
+    ///
+    /// Any directories which need to be (re)scanned are read synchronously.
+    pub fn executables<E>(&mut self, env: &E) -> Vec<String>
+    where
+        E: ?Sized + VariableEnvironment + WorkingDirectoryEnvironment,
+        E::VarName: StrKey,
+        E::Var: Borrow<String>,
+    {
+        let path = match E::VarName::lookup(env, PATH) {
+            Some(path) => path.borrow(),
+            None => return Vec::new(),
+        };
+
+        let mut names = Vec::new();
+        for dir in std::env::split_paths(path) {
+            let dir = env.path_relative_to_working_dir(Cow::Owned(dir));
+            names.extend_from_slice(self.scan(dir.into_owned()));
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Forgets the contents of all previously scanned directories.
+    pub fn clear(&mut self) {
+        self.dirs.clear();
+    }
+
+    fn scan(&mut self, dir: PathBuf) -> &[String] {
+        let modified = match fs::metadata(&dir).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(_) => {
+                self.dirs.remove(&dir);
+                return &[];
+            }
+        };
+
+        let stale = match self.dirs.get(&dir) {
+            Some(scanned) => scanned.modified != modified,
+            None => true,
+        };
+
+        if stale {
+            let executables = read_executables(&dir);
+            self.dirs.insert(
+                dir.clone(),
+                ScannedDir {
+                    modified,
+                    executables,
+                },
+            );
+        }
+
+        &self.dirs[&dir].executables
+    }
+}
+
+/// Reads the names of all executable files (following symbolic links) in a
+/// directory, skipping any which are not valid unicode.
+fn read_executables(dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let path = entry.path();
+            fs::metadata(&path).is_ok_and(|m| m.is_file()) && crate::sys::is_executable(&path)
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
@@ -0,0 +1,249 @@
+use crate::env::SubEnvironment;
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use futures_util::future::{FutureExt, Shared};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The state of a job tracked by a `JobEnvironment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// The job is still running.
+    Running,
+    /// The job has been stopped (e.g. via `SIGTSTP`).
+    Stopped,
+    /// The job has completed with the provided status.
+    Done(ExitStatus),
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            JobState::Running => fmt.write_str("Running"),
+            JobState::Stopped => fmt.write_str("Stopped"),
+            JobState::Done(ExitStatus::Code(0)) => fmt.write_str("Done"),
+            JobState::Done(ExitStatus::Code(code)) => write!(fmt, "Exit {}", code),
+            JobState::Done(ExitStatus::Signal(signal)) => write!(fmt, "Signal {}", signal),
+            JobState::Done(ExitStatus::CoreDumped(signal)) => {
+                write!(fmt, "Signal {} (core dumped)", signal)
+            }
+        }
+    }
+}
+
+/// A job (e.g. a command running in the background) tracked by a `JobEnvironment`.
+#[derive(Clone)]
+pub struct Job {
+    id: usize,
+    pgid: Option<u32>,
+    command: String,
+    stopped: bool,
+    status: Shared<BoxFuture<'static, ExitStatus>>,
+}
+
+impl Job {
+    /// The number which identifies the job, as used by job specs like `%1`.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The process group the job's processes were placed in, if any.
+    pub fn pgid(&self) -> Option<u32> {
+        self.pgid
+    }
+
+    /// A description of the command the job is running.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// The current state of the job.
+    pub fn state(&self) -> JobState {
+        match self.status.peek() {
+            Some(&status) => JobState::Done(status),
+            None if self.stopped => JobState::Stopped,
+            None => JobState::Running,
+        }
+    }
+
+    /// Returns a future which will resolve with the job's exit status.
+    pub fn wait(&self) -> BoxFuture<'static, ExitStatus> {
+        Box::pin(self.status.clone())
+    }
+}
+
+impl fmt::Debug for Job {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(Job))
+            .field("id", &self.id)
+            .field("pgid", &self.pgid)
+            .field("command", &self.command)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+/// An interface for tracking jobs, such as commands running in the background.
+pub trait JobEnvironment {
+    /// Registers a new job with the environment, returning its id.
+    ///
+    /// The job's `status` future will be driven to completion in the
+    /// background, regardless of whether anything is waiting on the job.
+    /// The new job becomes the current job.
+    fn add_job(
+        &mut self,
+        command: String,
+        pgid: Option<u32>,
+        status: BoxFuture<'static, ExitStatus>,
+    ) -> usize;
+
+    /// Get a job by its id, if it is still being tracked.
+    fn job(&self, id: usize) -> Option<&Job>;
+
+    /// Get all tracked jobs, ordered by their ids.
+    fn jobs(&self) -> Vec<&Job>;
+
+    /// Stops tracking a job, returning it if it existed.
+    fn remove_job(&mut self, id: usize) -> Option<Job>;
+
+    /// Marks a job as stopped or resumed.
+    fn set_job_stopped(&mut self, id: usize, stopped: bool);
+
+    /// Makes a job the current job (i.e. the job referred to by `%%` or `%+`).
+    fn set_current_job(&mut self, id: usize);
+
+    /// The id of the current job (i.e. `%%` or `%+`), if any.
+    fn current_job(&self) -> Option<usize>;
+
+    /// The id of the previous job (i.e. `%-`), if any.
+    fn previous_job(&self) -> Option<usize>;
+}
+
+impl<'a, T: ?Sized + JobEnvironment> JobEnvironment for &'a mut T {
+    fn add_job(
+        &mut self,
+        command: String,
+        pgid: Option<u32>,
+        status: BoxFuture<'static, ExitStatus>,
+    ) -> usize {
+        (**self).add_job(command, pgid, status)
+    }
+
+    fn job(&self, id: usize) -> Option<&Job> {
+        (**self).job(id)
+    }
+
+    fn jobs(&self) -> Vec<&Job> {
+        (**self).jobs()
+    }
+
+    fn remove_job(&mut self, id: usize) -> Option<Job> {
+        (**self).remove_job(id)
+    }
+
+    fn set_job_stopped(&mut self, id: usize, stopped: bool) {
+        (**self).set_job_stopped(id, stopped);
+    }
+
+    fn set_current_job(&mut self, id: usize) {
+        (**self).set_current_job(id);
+    }
+
+    fn current_job(&self) -> Option<usize> {
+        (**self).current_job()
+    }
+
+    fn previous_job(&self) -> Option<usize> {
+        (**self).previous_job()
+    }
+}
+
+/// An implementation of `JobEnvironment` which drives jobs via `tokio`.
+///
+/// Jobs are numbered starting at 1, and ids are reused once all jobs with
+/// higher ids have been removed, just like other shells do.
+#[derive(Debug, Default, Clone)]
+pub struct JobEnv {
+    jobs: BTreeMap<usize, Job>,
+    /// Job ids ordered from least to most recently used.
+    recent: Vec<usize>,
+}
+
+impl JobEnv {
+    /// Constructs a new environment which is not tracking any jobs.
+    pub fn new() -> Self {
+        Self {
+            jobs: BTreeMap::new(),
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl JobEnvironment for JobEnv {
+    fn add_job(
+        &mut self,
+        command: String,
+        pgid: Option<u32>,
+        status: BoxFuture<'static, ExitStatus>,
+    ) -> usize {
+        let id = self.jobs.keys().next_back().map_or(1, |id| id + 1);
+
+        // Drive the job in the background so that it can make progress
+        // (and record its status) even if nothing is waiting on it.
+        let status = status.shared();
+        let _ = tokio::spawn(status.clone());
+
+        let job = Job {
+            id,
+            pgid,
+            command,
+            stopped: false,
+            status,
+        };
+
+        self.jobs.insert(id, job);
+        self.recent.push(id);
+        id
+    }
+
+    fn job(&self, id: usize) -> Option<&Job> {
+        self.jobs.get(&id)
+    }
+
+    fn jobs(&self) -> Vec<&Job> {
+        self.jobs.values().collect()
+    }
+
+    fn remove_job(&mut self, id: usize) -> Option<Job> {
+        self.recent.retain(|&recent| recent != id);
+        self.jobs.remove(&id)
+    }
+
+    fn set_job_stopped(&mut self, id: usize, stopped: bool) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.stopped = stopped;
+        }
+    }
+
+    fn set_current_job(&mut self, id: usize) {
+        if self.jobs.contains_key(&id) {
+            self.recent.retain(|&recent| recent != id);
+            self.recent.push(id);
+        }
+    }
+
+    fn current_job(&self) -> Option<usize> {
+        self.recent.last().copied()
+    }
+
+    fn previous_job(&self) -> Option<usize> {
+        self.recent.iter().rev().nth(1).copied()
+    }
+}
+
+impl SubEnvironment for JobEnv {
+    fn sub_env(&self) -> Self {
+        // Like other shells, subshells do not have access to the parent's jobs
+        Self::new()
+    }
+}
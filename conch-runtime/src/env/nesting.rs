@@ -0,0 +1,183 @@
+use crate::env::SubEnvironment;
+#[cfg(feature = "conch-parser")]
+use crate::error::NestingLimitError;
+
+/// An interface for tracking how deeply compound commands and parameter
+/// substitutions are nested while they are being run.
+///
+/// Each nesting level is driven on the Rust stack, so pathological scripts
+/// (e.g. thousands of nested brace groups or substitutions) are rejected with
+/// an error once the maximum depth is reached, rather than overflowing it.
+pub trait NestingEnvironment {
+    /// Denote that a new nested command or substitution has started running.
+    fn push_nesting_level(&mut self);
+    /// Denote that a nested command or substitution is no longer running.
+    fn pop_nesting_level(&mut self);
+    /// The number of nested commands or substitutions currently running.
+    fn nesting_depth(&self) -> usize;
+    /// The maximum nesting depth, if limited.
+    fn max_nesting_depth(&self) -> Option<usize>;
+    /// Changes (or removes) the maximum nesting depth.
+    fn set_max_nesting_depth(&mut self, max_depth: Option<usize>);
+}
+
+impl<'a, T: ?Sized + NestingEnvironment> NestingEnvironment for &'a mut T {
+    fn push_nesting_level(&mut self) {
+        (**self).push_nesting_level();
+    }
+
+    fn pop_nesting_level(&mut self) {
+        (**self).pop_nesting_level();
+    }
+
+    fn nesting_depth(&self) -> usize {
+        (**self).nesting_depth()
+    }
+
+    fn max_nesting_depth(&self) -> Option<usize> {
+        (**self).max_nesting_depth()
+    }
+
+    fn set_max_nesting_depth(&mut self, max_depth: Option<usize>) {
+        (**self).set_max_nesting_depth(max_depth);
+    }
+}
+
+/// An implementation of `NestingEnvironment`.
+///
+/// By default, commands and substitutions may be nested up to
+/// `DEFAULT_MAX_DEPTH` levels deep. Note that the body of a function does not
+/// count as a nested command itself (see `function_body`), thus recursive
+/// functions are instead limited by the maximum function nesting depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NestingEnv {
+    depth: usize,
+    max_depth: Option<usize>,
+}
+
+impl NestingEnv {
+    /// The default maximum nesting depth, which matches the default maximum
+    /// function nesting depth (`FnFrameEnv::DEFAULT_MAX_DEPTH`).
+    ///
+    /// Note that this much nesting fits within the default (2MiB) stack of a
+    /// thread in optimized builds, but unoptimized builds may require a
+    /// larger stack (or a lower limit).
+    pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+    /// Create a new environment instance.
+    pub fn new() -> Self {
+        Self::with_max_depth(Some(Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Create a new environment instance which limits how deeply commands
+    /// and substitutions may be nested (or not at all if `None`).
+    pub fn with_max_depth(max_depth: Option<usize>) -> Self {
+        Self {
+            depth: 0,
+            max_depth,
+        }
+    }
+}
+
+impl Default for NestingEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NestingEnvironment for NestingEnv {
+    fn push_nesting_level(&mut self) {
+        self.depth = self.depth.saturating_add(1);
+    }
+
+    fn pop_nesting_level(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    fn nesting_depth(&self) -> usize {
+        self.depth
+    }
+
+    fn max_nesting_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    fn set_max_nesting_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+}
+
+impl SubEnvironment for NestingEnv {
+    fn sub_env(&self) -> Self {
+        // A subshell still runs on the same stack as its parent
+        *self
+    }
+}
+
+#[cfg(feature = "conch-parser")]
+/// Keeps a nesting level entered until dropped, even if the nested
+/// command or substitution is cancelled before it completes.
+pub(crate) struct NestingGuard<'a, E: ?Sized + NestingEnvironment> {
+    env: &'a mut E,
+}
+
+#[cfg(feature = "conch-parser")]
+impl<'a, E: ?Sized + NestingEnvironment> NestingGuard<'a, E> {
+    /// Enters a new nesting level, unless the environment's maximum nesting
+    /// depth has already been reached.
+    pub(crate) fn enter(env: &'a mut E) -> Result<Self, NestingLimitError> {
+        if let Some(max_depth) = env.max_nesting_depth() {
+            if env.nesting_depth() >= max_depth {
+                return Err(NestingLimitError { max_depth });
+            }
+        }
+
+        env.push_nesting_level();
+        Ok(Self { env })
+    }
+
+    /// Get a mutable reference to the wrapped environment.
+    pub(crate) fn get_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+#[cfg(feature = "conch-parser")]
+impl<'a, E: ?Sized + NestingEnvironment> Drop for NestingGuard<'a, E> {
+    fn drop(&mut self) {
+        self.env.pop_nesting_level();
+    }
+}
+
+#[cfg(all(test, feature = "conch-parser"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nesting_guard() {
+        let mut env = NestingEnv::with_max_depth(Some(2));
+
+        {
+            let mut outer = NestingGuard::enter(&mut env).unwrap();
+            assert_eq!(outer.get_mut().nesting_depth(), 1);
+
+            {
+                let mut inner = NestingGuard::enter(outer.get_mut()).unwrap();
+                assert_eq!(inner.get_mut().nesting_depth(), 2);
+
+                let err = NestingGuard::enter(inner.get_mut()).err();
+                assert_eq!(err, Some(NestingLimitError { max_depth: 2 }));
+                assert_eq!(inner.get_mut().nesting_depth(), 2);
+            }
+
+            assert_eq!(outer.get_mut().nesting_depth(), 1);
+        }
+
+        assert_eq!(env.nesting_depth(), 0);
+
+        env.set_max_nesting_depth(None);
+        env.push_nesting_level();
+        assert!(NestingGuard::enter(&mut env).is_ok());
+        assert_eq!(env.sub_env().nesting_depth(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::env::SubEnvironment;
+use std::fmt;
+
+/// A shell option which can be toggled via the `set` builtin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShellOption {
+    /// `-a`: mark all newly assigned variables for export.
+    AllExport,
+    /// `-e`: exit the shell if a command fails.
+    ErrExit,
+    /// `extglob`: recognize ksh-style extended patterns, e.g. `@(foo|bar)`.
+    ExtGlob,
+    /// `failglob`: treat a pathname pattern which matches no paths as an error.
+    FailGlob,
+    /// `globstar`: make a `**` pathname component match any number of
+    /// directories (and subdirectories).
+    GlobStar,
+    /// `lastpipe`: run the last command of a pipeline in the current shell
+    /// environment rather than a subshell.
+    LastPipe,
+    /// `-C`: prevent output redirection from overwriting existing files.
+    NoClobber,
+    /// `-f`: disable pathname expansion.
+    NoGlob,
+    /// `-u`: treat expanding an unset parameter as an error.
+    NoUnset,
+    /// `nullglob`: expand a pathname pattern which matches no paths to nothing,
+    /// rather than retaining the pattern itself.
+    NullGlob,
+    /// `pipefail`: make a pipeline's status that of the last (i.e. rightmost)
+    /// command to fail, or zero if all commands succeed.
+    PipeFail,
+    /// `-v`: write input to standard error as it is read (currently not supported).
+    Verbose,
+    /// `xpg_echo`: make the `echo` builtin follow XSI semantics, i.e. always
+    /// interpret escape sequences and treat all arguments as operands.
+    XpgEcho,
+    /// `-x`: write a trace of each command to standard error before executing it.
+    XTrace,
+}
+
+impl ShellOption {
+    /// All known shell options, in the order they are listed by `set -o`.
+    pub const ALL: &'static [ShellOption] = &[
+        ShellOption::AllExport,
+        ShellOption::ErrExit,
+        ShellOption::ExtGlob,
+        ShellOption::FailGlob,
+        ShellOption::GlobStar,
+        ShellOption::LastPipe,
+        ShellOption::NoClobber,
+        ShellOption::NoGlob,
+        ShellOption::NoUnset,
+        ShellOption::NullGlob,
+        ShellOption::PipeFail,
+        ShellOption::Verbose,
+        ShellOption::XpgEcho,
+        ShellOption::XTrace,
+    ];
+
+    /// Looks up an option by its long name, as used by `set -o name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|opt| opt.name() == name)
+    }
+
+    /// Looks up an option by its single character flag, as used by `set -e`.
+    pub fn from_flag(flag: char) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|opt| opt.flag() == Some(flag))
+    }
+
+    /// The long name of the option, as used by `set -o name`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ShellOption::AllExport => "allexport",
+            ShellOption::ErrExit => "errexit",
+            ShellOption::ExtGlob => "extglob",
+            ShellOption::FailGlob => "failglob",
+            ShellOption::GlobStar => "globstar",
+            ShellOption::LastPipe => "lastpipe",
+            ShellOption::NoClobber => "noclobber",
+            ShellOption::NoGlob => "noglob",
+            ShellOption::NoUnset => "nounset",
+            ShellOption::NullGlob => "nullglob",
+            ShellOption::PipeFail => "pipefail",
+            ShellOption::Verbose => "verbose",
+            ShellOption::XpgEcho => "xpg_echo",
+            ShellOption::XTrace => "xtrace",
+        }
+    }
+
+    /// The single character flag of the option, if it has one.
+    pub fn flag(&self) -> Option<char> {
+        match *self {
+            ShellOption::AllExport => Some('a'),
+            ShellOption::ErrExit => Some('e'),
+            ShellOption::ExtGlob => None,
+            ShellOption::FailGlob => None,
+            ShellOption::GlobStar => None,
+            ShellOption::LastPipe => None,
+            ShellOption::NoClobber => Some('C'),
+            ShellOption::NoGlob => Some('f'),
+            ShellOption::NoUnset => Some('u'),
+            ShellOption::NullGlob => None,
+            ShellOption::PipeFail => None,
+            ShellOption::Verbose => Some('v'),
+            ShellOption::XpgEcho => None,
+            ShellOption::XTrace => Some('x'),
+        }
+    }
+
+    fn mask(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+impl fmt::Display for ShellOption {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+/// An interface for querying and toggling shell options.
+pub trait ShellOptionsEnvironment {
+    /// Indicates if the specified option is currently enabled.
+    fn is_option_enabled(&self, opt: ShellOption) -> bool;
+
+    /// Enables or disables the specified option.
+    fn set_option(&mut self, opt: ShellOption, enabled: bool);
+
+    /// Begins a new scope for option changes, e.g. when a function is invoked.
+    ///
+    /// The default implementation does not track any scopes.
+    fn push_options_frame(&mut self) {}
+
+    /// Ends the current scope, restoring the options which were enabled when
+    /// `make_options_local` was first called within it (if at all).
+    ///
+    /// The default implementation does not track any scopes.
+    fn pop_options_frame(&mut self) {}
+
+    /// Arranges for the currently enabled options to be restored once the
+    /// current scope is popped, like `local -` does within a function.
+    ///
+    /// Returns `false` (and has no effect) if no scope has been pushed, which
+    /// is always the case for the default implementation.
+    fn make_options_local(&mut self) -> bool {
+        false
+    }
+}
+
+impl<'a, T: ?Sized + ShellOptionsEnvironment> ShellOptionsEnvironment for &'a mut T {
+    fn is_option_enabled(&self, opt: ShellOption) -> bool {
+        (**self).is_option_enabled(opt)
+    }
+
+    fn set_option(&mut self, opt: ShellOption, enabled: bool) {
+        (**self).set_option(opt, enabled);
+    }
+
+    fn push_options_frame(&mut self) {
+        (**self).push_options_frame();
+    }
+
+    fn pop_options_frame(&mut self) {
+        (**self).pop_options_frame();
+    }
+
+    fn make_options_local(&mut self) -> bool {
+        (**self).make_options_local()
+    }
+}
+
+/// An implementation of `ShellOptionsEnvironment`, with all options
+/// disabled by default.
+///
+/// Scoping option changes (e.g. via `make_options_local`) is not supported
+/// by this implementation on its own, but is by the `Env` which wraps it.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShellOptionsEnv {
+    enabled: u32,
+}
+
+impl ShellOptionsEnv {
+    /// Create a new environment instance with all options disabled.
+    pub fn new() -> Self {
+        Self { enabled: 0 }
+    }
+}
+
+impl fmt::Debug for ShellOptionsEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled = ShellOption::ALL
+            .iter()
+            .filter(|&&opt| self.is_option_enabled(opt));
+
+        fmt.debug_set().entries(enabled).finish()
+    }
+}
+
+impl ShellOptionsEnvironment for ShellOptionsEnv {
+    fn is_option_enabled(&self, opt: ShellOption) -> bool {
+        self.enabled & opt.mask() != 0
+    }
+
+    fn set_option(&mut self, opt: ShellOption, enabled: bool) {
+        if enabled {
+            self.enabled |= opt.mask();
+        } else {
+            self.enabled &= !opt.mask();
+        }
+    }
+}
+
+impl SubEnvironment for ShellOptionsEnv {
+    fn sub_env(&self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,330 @@
+use crate::env::{
+    ExportedVariableEnvironment, SubEnvironment, UnsetVariableEnvironment, VarAttributes,
+    VariableAttributesEnvironment, VariableEnvironment,
+};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// An interface for loading and storing the values of variables which
+/// should survive across different runtime instances.
+pub trait VarStore {
+    /// Load all previously stored variables and their values.
+    fn load(&self) -> io::Result<Vec<(String, String)>>;
+    /// Store the specified variables and their values, replacing any
+    /// previously stored variables.
+    fn store(&self, vars: &[(String, String)]) -> io::Result<()>;
+}
+
+impl<'a, T: ?Sized + VarStore> VarStore for &'a T {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        (**self).load()
+    }
+
+    fn store(&self, vars: &[(String, String)]) -> io::Result<()> {
+        (**self).store(vars)
+    }
+}
+
+/// A `VarStore` implementation backed by a simple key-value file.
+///
+/// Each variable is stored on its own line as `name=value`, where any
+/// backslashes, newlines, or carriage returns in the value are escaped.
+/// Names cannot be escaped, thus storing a variable whose name is empty or
+/// contains a `=`, newline, or carriage return fails with an `InvalidInput`
+/// error. A missing file is treated as if no variables were previously stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileVarStore {
+    path: PathBuf,
+}
+
+impl FileVarStore {
+    /// Create a new store backed by the file at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Get the path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn escape(val: &str) -> String {
+    val.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\n', '\r'])
+}
+
+fn unescape(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                ret.push('\n');
+            }
+            ('\\', Some('r')) => {
+                chars.next();
+                ret.push('\r');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                ret.push('\\');
+            }
+            (c, _) => ret.push(c),
+        }
+    }
+    ret
+}
+
+impl VarStore for FileVarStore {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.find('=') {
+                Some(idx) => Ok((line[..idx].to_owned(), unescape(&line[idx + 1..]))),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed variable entry: {}", line),
+                )),
+            })
+            .collect()
+    }
+
+    /// Writes all variables to a uniquely named temporary file before moving
+    /// it in place of the backing file, so that a partially written file is
+    /// never observed, even if several runtimes store variables at once.
+    fn store(&self, vars: &[(String, String)]) -> io::Result<()> {
+        let mut contents = String::new();
+        for (name, val) in vars {
+            if !is_valid_name(name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("cannot store variable with invalid name: {:?}", name),
+                ));
+            }
+
+            contents.push_str(name);
+            contents.push('=');
+            contents.push_str(&escape(val));
+            contents.push('\n');
+        }
+
+        static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = fs::write(&tmp, contents).and_then(|()| fs::rename(&tmp, &self.path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+/// A variable environment wrapper which allows designated variables to
+/// survive across runtime instances (e.g. for CLI tools which repeatedly
+/// embed the runtime), before delegating to another variable environment
+/// implementation.
+///
+/// Any designated variables are loaded from a `VarStore` when the environment
+/// is created, and their current values are stored whenever `save` is called.
+/// Designated variables which are unset at that time are removed from the store,
+/// while any other stored variables are preserved.
+/// Loaded variables are not exported.
+pub struct PersistentVarEnv<T, S> {
+    var_env: T,
+    store: Arc<S>,
+    persisted: Arc<HashSet<String>>,
+}
+
+impl<T, S> PersistentVarEnv<T, S>
+where
+    T: VariableEnvironment,
+    T::VarName: Borrow<String> + From<String>,
+    T::Var: Borrow<String> + From<String>,
+    S: VarStore,
+{
+    /// Create a new environment which persists the variables named by
+    /// `persisted` via the provided store, loading any of their previously
+    /// stored values into the provided variable environment.
+    ///
+    /// Any previously stored variables which are not designated are ignored.
+    pub fn new<I>(mut var_env: T, store: S, persisted: I) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let persisted = persisted
+            .into_iter()
+            .map(Into::into)
+            .collect::<HashSet<_>>();
+
+        for (name, val) in store.load()? {
+            if persisted.contains(&name) {
+                var_env.set_var(name.into(), val.into());
+            }
+        }
+
+        Ok(Self {
+            var_env,
+            store: Arc::new(store),
+            persisted: Arc::new(persisted),
+        })
+    }
+
+    /// Store the current values of all designated variables.
+    ///
+    /// Any stored variables which are not designated (e.g. those persisted
+    /// by another runtime sharing the same store) are left intact.
+    pub fn save(&self) -> io::Result<()> {
+        let mut vars = self.store.load()?;
+        vars.retain(|(name, _)| !self.persisted.contains(name));
+        vars.extend(self.persisted.iter().filter_map(|name| {
+            self.var_env
+                .var(name)
+                .map(|val| (name.clone(), val.borrow().clone()))
+        }));
+        vars.sort();
+
+        self.store.store(&vars)
+    }
+}
+
+impl<T, S> PersistentVarEnv<T, S> {
+    /// Indicates if the variable `name` is persisted.
+    pub fn is_persisted(&self, name: &str) -> bool {
+        self.persisted.contains(name)
+    }
+
+    /// Get a reference to the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Unwraps the underlying variable environment.
+    pub fn into_inner(self) -> T {
+        self.var_env
+    }
+}
+
+impl<T: Clone, S> Clone for PersistentVarEnv<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            var_env: self.var_env.clone(),
+            store: self.store.clone(),
+            persisted: self.persisted.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, S: fmt::Debug> fmt::Debug for PersistentVarEnv<T, S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut persisted = self.persisted.iter().collect::<Vec<_>>();
+        persisted.sort();
+
+        fmt.debug_struct(stringify!(PersistentVarEnv))
+            .field("var_env", &self.var_env)
+            .field("store", &self.store)
+            .field("persisted", &persisted)
+            .finish()
+    }
+}
+
+impl<T: SubEnvironment, S> SubEnvironment for PersistentVarEnv<T, S> {
+    fn sub_env(&self) -> Self {
+        Self {
+            var_env: self.var_env.sub_env(),
+            store: self.store.clone(),
+            persisted: self.persisted.clone(),
+        }
+    }
+}
+
+impl<T: VariableEnvironment, S> VariableEnvironment for PersistentVarEnv<T, S> {
+    type VarName = T::VarName;
+    type Var = T::Var;
+
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
+    where
+        Self::VarName: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.var_env.var(name)
+    }
+
+    fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
+        self.var_env.set_var(name, val);
+    }
+
+    fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
+        self.var_env.env_vars()
+    }
+}
+
+impl<T: ExportedVariableEnvironment, S> ExportedVariableEnvironment for PersistentVarEnv<T, S> {
+    fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)> {
+        self.var_env.exported_var(name)
+    }
+
+    fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
+        self.var_env.set_exported_var(name, val, exported)
+    }
+
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.exported_vars()
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.all_vars()
+    }
+}
+
+impl<T: VariableAttributesEnvironment, S> VariableAttributesEnvironment for PersistentVarEnv<T, S> {
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes {
+        self.var_env.var_attributes(name)
+    }
+
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes) {
+        self.var_env.set_var_attributes(name, attributes)
+    }
+}
+
+impl<T: UnsetVariableEnvironment, S> UnsetVariableEnvironment for PersistentVarEnv<T, S> {
+    fn unset_var(&mut self, name: &Self::VarName) {
+        self.var_env.unset_var(name)
+    }
+}
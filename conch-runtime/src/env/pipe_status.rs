@@ -0,0 +1,201 @@
+use crate::env::SubEnvironment;
+use crate::{ExitStatus, EXIT_SUCCESS};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An interface for getting the exit status of each command of the last
+/// pipeline to complete, much like bash's `$PIPESTATUS` array.
+///
+/// Pipelines resolve long after they have been spawned (at which point the
+/// environment is no longer borrowed), so their statuses are recorded via a
+/// `PipelineStatusRecorder` obtained when the pipeline is spawned. Whatever
+/// was recorded is then picked up the next time the last status is updated.
+pub trait LastPipelineStatusEnvironment {
+    /// Get the exit status of each command of the last pipeline to complete,
+    /// in the order the commands appear within the pipeline.
+    ///
+    /// Commands which did not run as part of a pipeline are treated as a
+    /// pipeline of a single command.
+    fn last_pipeline_status(&self) -> &[ExitStatus];
+
+    /// Get a recorder through which a pipeline being spawned can report the
+    /// statuses of its commands once they have completed.
+    fn pipeline_status_recorder(&self) -> PipelineStatusRecorder;
+
+    /// Updates the last pipeline statuses after a command which resolved to
+    /// `status` has completed.
+    ///
+    /// Any statuses recorded by a pipeline since the last update will be
+    /// used if present, otherwise `status` is assumed to be the sole status.
+    fn update_last_pipeline_status(&mut self, status: ExitStatus);
+}
+
+impl<'a, T: ?Sized + LastPipelineStatusEnvironment> LastPipelineStatusEnvironment for &'a mut T {
+    fn last_pipeline_status(&self) -> &[ExitStatus] {
+        (**self).last_pipeline_status()
+    }
+
+    fn pipeline_status_recorder(&self) -> PipelineStatusRecorder {
+        (**self).pipeline_status_recorder()
+    }
+
+    fn update_last_pipeline_status(&mut self, status: ExitStatus) {
+        (**self).update_last_pipeline_status(status);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    /// Incremented every time the last pipeline statuses are updated so that
+    /// recorders obtained before then (e.g. by background pipelines) cannot
+    /// clobber the statuses of more recent commands.
+    generation: usize,
+    statuses: Option<Vec<ExitStatus>>,
+}
+
+/// A handle for reporting the statuses of a pipeline's commands back to the
+/// environment which spawned it.
+#[derive(Clone)]
+pub struct PipelineStatusRecorder {
+    pending: Arc<Mutex<Pending>>,
+    generation: usize,
+}
+
+impl fmt::Debug for PipelineStatusRecorder {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(PipelineStatusRecorder))
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl PipelineStatusRecorder {
+    /// Records the statuses of all commands in a completed pipeline.
+    ///
+    /// Has no effect if the environment's statuses have since been updated.
+    pub fn record(&self, statuses: Vec<ExitStatus>) {
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if pending.generation == self.generation {
+            pending.statuses = Some(statuses);
+        }
+    }
+}
+
+/// An environment module for tracking the exit status of each command of
+/// the last pipeline to complete.
+#[derive(Debug)]
+pub struct LastPipelineStatusEnv {
+    statuses: Vec<ExitStatus>,
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl LastPipelineStatusEnv {
+    /// Creates a new environment as if a single successful command was run.
+    pub fn new() -> Self {
+        Self::with_statuses(vec![EXIT_SUCCESS])
+    }
+
+    /// Creates a new environment with the provided pipeline statuses.
+    pub fn with_statuses(statuses: Vec<ExitStatus>) -> Self {
+        Self {
+            statuses,
+            pending: Arc::new(Mutex::new(Pending::default())),
+        }
+    }
+}
+
+impl Default for LastPipelineStatusEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for LastPipelineStatusEnv {
+    /// Clones the recorded statuses, however, any pipelines spawned with
+    /// the original environment will only report back to it.
+    fn clone(&self) -> Self {
+        Self::with_statuses(self.statuses.clone())
+    }
+}
+
+impl LastPipelineStatusEnvironment for LastPipelineStatusEnv {
+    fn last_pipeline_status(&self) -> &[ExitStatus] {
+        &self.statuses
+    }
+
+    fn pipeline_status_recorder(&self) -> PipelineStatusRecorder {
+        let generation = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .generation;
+
+        PipelineStatusRecorder {
+            pending: self.pending.clone(),
+            generation,
+        }
+    }
+
+    fn update_last_pipeline_status(&mut self, status: ExitStatus) {
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        pending.generation = pending.generation.wrapping_add(1);
+        self.statuses = pending.statuses.take().unwrap_or_else(|| vec![status]);
+    }
+}
+
+impl SubEnvironment for LastPipelineStatusEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EXIT_ERROR;
+
+    #[test]
+    fn test_recorded_statuses_are_picked_up_on_update() {
+        let mut env = LastPipelineStatusEnv::new();
+        assert_eq!(env.last_pipeline_status(), [EXIT_SUCCESS]);
+
+        env.pipeline_status_recorder()
+            .record(vec![EXIT_ERROR, EXIT_SUCCESS]);
+        assert_eq!(env.last_pipeline_status(), [EXIT_SUCCESS]);
+
+        env.update_last_pipeline_status(EXIT_SUCCESS);
+        assert_eq!(env.last_pipeline_status(), [EXIT_ERROR, EXIT_SUCCESS]);
+
+        env.update_last_pipeline_status(EXIT_ERROR);
+        assert_eq!(env.last_pipeline_status(), [EXIT_ERROR]);
+    }
+
+    #[test]
+    fn test_stale_recorders_are_ignored() {
+        let mut env = LastPipelineStatusEnv::new();
+        let recorder = env.pipeline_status_recorder();
+
+        env.update_last_pipeline_status(EXIT_ERROR);
+        recorder.record(vec![EXIT_SUCCESS, EXIT_SUCCESS]);
+        env.update_last_pipeline_status(EXIT_ERROR);
+        assert_eq!(env.last_pipeline_status(), [EXIT_ERROR]);
+    }
+
+    #[test]
+    fn test_sub_env_does_not_receive_parent_recordings() {
+        let env = LastPipelineStatusEnv::new();
+        let mut child = env.sub_env();
+
+        env.pipeline_status_recorder().record(vec![EXIT_ERROR]);
+        child.update_last_pipeline_status(EXIT_SUCCESS);
+        assert_eq!(child.last_pipeline_status(), [EXIT_SUCCESS]);
+    }
+}
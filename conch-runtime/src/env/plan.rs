@@ -0,0 +1,276 @@
+use crate::env::{
+    ExecutableData, ExecutableEnvironment, FileDescOpener, Pipe, SubEnvironment, TempFile,
+    TempFileEnvironment,
+};
+use crate::error::CommandError;
+use crate::io::Permissions;
+use crate::{ExitStatus, Fd, EXIT_SUCCESS, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use futures_core::future::BoxFuture;
+use std::ffi::OsStr;
+use std::fs::OpenOptions;
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A path which was opened (e.g. for a redirect) while recording an `ExecutionPlan`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedOpen {
+    /// The (resolved) path which was opened.
+    pub path: String,
+    /// The permissions the path was opened with, if known.
+    pub permissions: Option<Permissions>,
+}
+
+/// An executable which was spawned while recording an `ExecutionPlan`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedCommand {
+    /// The name/path to the executable.
+    pub name: String,
+    /// Arguments provided to the executable.
+    pub args: Vec<String>,
+    /// Environment variables provided to the executable.
+    pub env_vars: Vec<(String, String)>,
+    /// The working directory the executable was started with.
+    pub current_dir: String,
+    /// The standard descriptors which were provided to the executable
+    /// (any others being the equivalent of `/dev/null`).
+    pub stdio: Vec<Fd>,
+    /// The (non-standard) descriptors the executable was to inherit.
+    pub inherited_fds: Vec<Fd>,
+}
+
+impl<'a> From<&'a ExecutableData<'a>> for PlannedCommand {
+    fn from(data: &'a ExecutableData<'a>) -> Self {
+        let lossy = |s: &OsStr| s.to_string_lossy().into_owned();
+        let stdio = [
+            (STDIN_FILENO, &data.stdin),
+            (STDOUT_FILENO, &data.stdout),
+            (STDERR_FILENO, &data.stderr),
+        ];
+
+        Self {
+            name: lossy(data.name),
+            args: data.args.iter().map(|&a| lossy(a)).collect(),
+            env_vars: data
+                .env_vars
+                .iter()
+                .map(|&(k, v)| (lossy(k), lossy(v)))
+                .collect(),
+            current_dir: data.current_dir.to_string_lossy().into_owned(),
+            stdio: stdio
+                .iter()
+                .filter(|(_, fdes)| fdes.is_some())
+                .map(|&(fd, _)| fd)
+                .collect(),
+            inherited_fds: data.inherited_fds.iter().map(|&(fd, _)| fd).collect(),
+        }
+    }
+}
+
+/// A single step of an `ExecutionPlan`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlannedStep {
+    /// A path was opened.
+    Open(PlannedOpen),
+    /// An executable was spawned.
+    Spawn(PlannedCommand),
+}
+
+/// A shared, structured trace of the side effects the runtime performed (or
+/// would have performed), in the order they were requested.
+///
+/// Steps are recorded by wrapping environment implementations with an
+/// `ExecutionPlanEnv`. All clones of a plan share the same steps, thus
+/// a plan can be inspected while (or after) a script is run. With the
+/// `serde` feature enabled, plans can be serialized (e.g. to JSON) so that
+/// planned executions can be compared across different versions of a script.
+///
+/// Note that names, arguments, and paths are recorded as (lossily converted)
+/// UTF-8 strings so they can be easily serialized and compared.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionPlan {
+    steps: Arc<Mutex<Vec<PlannedStep>>>,
+}
+
+impl ExecutionPlan {
+    /// Create a new, empty plan.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of all steps recorded thus far, in the order they were made.
+    pub fn steps(&self) -> Vec<PlannedStep> {
+        self.lock().clone()
+    }
+
+    /// Get a copy of all spawned executables recorded thus far, in the order they were made.
+    pub fn commands(&self) -> Vec<PlannedCommand> {
+        self.lock()
+            .iter()
+            .filter_map(|step| match step {
+                PlannedStep::Spawn(cmd) => Some(cmd.clone()),
+                PlannedStep::Open(_) => None,
+            })
+            .collect()
+    }
+
+    /// Take all steps recorded thus far, leaving the plan empty.
+    pub fn take_steps(&self) -> Vec<PlannedStep> {
+        mem::take(&mut *self.lock())
+    }
+
+    fn record(&self, step: PlannedStep) {
+        self.lock().push(step);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<PlannedStep>> {
+        self.steps.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An environment wrapper which records any paths opened or executables
+/// spawned through it into an `ExecutionPlan`, before delegating to another
+/// environment implementation.
+///
+/// When running in dry-run mode, executables are only recorded (but never
+/// spawned) and are treated as if they exited successfully. Paths are still
+/// opened, however, since redirects must produce real descriptors (wrapping
+/// an opener which does not touch the file system is recommended if that
+/// is undesirable). Temporary files (e.g. for heredocs) are not recorded.
+#[derive(Debug, Clone)]
+pub struct ExecutionPlanEnv<T> {
+    env: T,
+    plan: ExecutionPlan,
+    dry_run: bool,
+}
+
+impl<T> ExecutionPlanEnv<T> {
+    /// Create a new environment which records into the provided plan, and
+    /// delegates to another environment implementation.
+    pub fn new(env: T, plan: ExecutionPlan) -> Self {
+        Self {
+            env,
+            plan,
+            dry_run: false,
+        }
+    }
+
+    /// Create a new environment which records into the provided plan,
+    /// but never spawns any executables.
+    pub fn dry_run(env: T, plan: ExecutionPlan) -> Self {
+        Self {
+            env,
+            plan,
+            dry_run: true,
+        }
+    }
+
+    /// Get a reference to the plan being recorded.
+    pub fn plan(&self) -> &ExecutionPlan {
+        &self.plan
+    }
+
+    /// Indicates if executables are only recorded but never spawned.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Unwraps the underlying environment.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+
+    fn record_open(&self, path: &Path, permissions: Option<Permissions>) {
+        self.plan.record(PlannedStep::Open(PlannedOpen {
+            path: path.to_string_lossy().into_owned(),
+            permissions,
+        }));
+    }
+}
+
+impl<T: SubEnvironment> SubEnvironment for ExecutionPlanEnv<T> {
+    fn sub_env(&self) -> Self {
+        Self {
+            env: self.env.sub_env(),
+            plan: self.plan.clone(),
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+impl<T: FileDescOpener> FileDescOpener for ExecutionPlanEnv<T> {
+    type OpenedFileHandle = T::OpenedFileHandle;
+
+    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+        self.record_open(path, None);
+        self.env.open_path(path, opts)
+    }
+
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.record_open(path, Some(perms));
+        self.env.open_path_with_permissions(path, perms, opts)
+    }
+
+    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.env.open_pipe()
+    }
+}
+
+impl<T: TempFileEnvironment> TempFileEnvironment for ExecutionPlanEnv<T> {
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.env.open_named_temp_file(contents)
+    }
+}
+
+impl<T: ExecutableEnvironment> ExecutableEnvironment for ExecutionPlanEnv<T> {
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        self.plan
+            .record(PlannedStep::Spawn(PlannedCommand::from(&data)));
+
+        if self.dry_run {
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        } else {
+            self.env.spawn_executable(data)
+        }
+    }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        if self.dry_run {
+            Some(true)
+        } else {
+            self.env.executable_exists(name, env_vars, current_dir)
+        }
+    }
+
+    fn begin_job(&mut self) -> bool {
+        self.env.begin_job()
+    }
+
+    fn end_job(&mut self) {
+        self.env.end_job()
+    }
+}
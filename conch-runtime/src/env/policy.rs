@@ -0,0 +1,268 @@
+use crate::env::executable::find_executable;
+use crate::env::{
+    ChangeWorkingDirectoryEnvironment, ExecutableData, ExecutableEnvironment, FileDescOpener, Pipe,
+    SubEnvironment, TempFile, TempFileEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::CommandError;
+use crate::io::Permissions;
+use crate::path::{NormalizationError, NormalizedPath};
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// An interface for deciding which operations with side effects outside of
+/// the shell (e.g. opening files or spawning executables) are allowed.
+///
+/// Each check returns an error (typically of kind `PermissionDenied`) if the
+/// operation is not allowed, which is reported in place of the operation's
+/// own result. All operations are allowed by default.
+///
+/// Policies are enforced by wrapping environment implementations with a
+/// `PolicyEnv`, thus a single policy can be shared by all of them.
+pub trait SecurityPolicy {
+    /// Checks if the file at `path` may be opened with the specified permissions.
+    fn check_open_path(&self, _path: &Path, _perms: Permissions) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Checks if the executable described by `data` may be spawned.
+    ///
+    /// `program` is the canonical path of the executable which would be
+    /// spawned (i.e. as found via the `PATH` among `data.env_vars`), or
+    /// `data.name` as is if no such executable can be found.
+    fn check_spawn_executable(
+        &self,
+        _program: &Path,
+        _data: &ExecutableData<'_>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Checks if the working directory may be changed to `path`.
+    fn check_change_working_dir(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, T: ?Sized + SecurityPolicy> SecurityPolicy for &'a T {
+    fn check_open_path(&self, path: &Path, perms: Permissions) -> io::Result<()> {
+        (**self).check_open_path(path, perms)
+    }
+
+    fn check_spawn_executable(&self, program: &Path, data: &ExecutableData<'_>) -> io::Result<()> {
+        (**self).check_spawn_executable(program, data)
+    }
+
+    fn check_change_working_dir(&self, path: &Path) -> io::Result<()> {
+        (**self).check_change_working_dir(path)
+    }
+}
+
+/// An environment wrapper which consults a `SecurityPolicy` before opening
+/// paths, spawning executables, or changing the working directory, and only
+/// delegates to another environment implementation if the policy allows it.
+///
+/// The same policy can be shared by wrapping several environments (e.g. the
+/// file descriptor opener, executable, and working directory environments).
+///
+/// Paths are checked after they have been resolved against the working
+/// directory and canonicalized (or, for paths which do not exist yet, after
+/// their parent directory has been canonicalized). Paths opened without any known permissions (i.e. via
+/// `FileDescOpener::open_path`) are checked as if they were opened for both
+/// reading and writing. Temporary files (e.g. for heredocs) are not checked.
+pub struct PolicyEnv<T, P: ?Sized> {
+    env: T,
+    policy: Arc<P>,
+}
+
+impl<T, P: ?Sized> PolicyEnv<T, P> {
+    /// Create a new environment which enforces the provided policy on
+    /// operations of another environment implementation.
+    pub fn new(env: T, policy: Arc<P>) -> Self {
+        Self { env, policy }
+    }
+
+    /// Get a reference to the enforced policy.
+    pub fn policy(&self) -> &Arc<P> {
+        &self.policy
+    }
+
+    /// Unwraps the underlying environment.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: Clone, P: ?Sized> Clone for PolicyEnv<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            env: self.env.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, P: ?Sized> fmt::Debug for PolicyEnv<T, P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("PolicyEnv")
+            .field("env", &self.env)
+            .field("policy", &"..")
+            .finish()
+    }
+}
+
+impl<T: SubEnvironment, P: ?Sized> SubEnvironment for PolicyEnv<T, P> {
+    fn sub_env(&self) -> Self {
+        Self {
+            env: self.env.sub_env(),
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<T, P> FileDescOpener for PolicyEnv<T, P>
+where
+    T: FileDescOpener,
+    P: ?Sized + SecurityPolicy,
+{
+    type OpenedFileHandle = T::OpenedFileHandle;
+
+    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+        self.open_path_with_permissions(path, Permissions::ReadWrite, opts)
+    }
+
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.policy.check_open_path(&normalize_path(path), perms)?;
+        self.env.open_path_with_permissions(path, perms, opts)
+    }
+
+    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.env.open_pipe()
+    }
+}
+
+impl<T, P> TempFileEnvironment for PolicyEnv<T, P>
+where
+    T: TempFileEnvironment,
+    P: ?Sized + SecurityPolicy,
+{
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.env.open_named_temp_file(contents)
+    }
+}
+
+impl<T, P> ExecutableEnvironment for PolicyEnv<T, P>
+where
+    T: ExecutableEnvironment,
+    P: ?Sized + SecurityPolicy,
+{
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let program = find_executable(data.name, data.env_vars, data.current_dir)
+            .map_or_else(|| PathBuf::from(data.name), |path| normalize_path(&path));
+
+        if let Err(e) = self.policy.check_spawn_executable(&program, &data) {
+            let name = data.name.to_string_lossy().into_owned();
+            return Err(CommandError::Io(e, Some(name)));
+        }
+
+        self.env.spawn_executable(data)
+    }
+
+    fn executable_exists(
+        &self,
+        name: &OsStr,
+        env_vars: &[(&OsStr, &OsStr)],
+        current_dir: &Path,
+    ) -> Option<bool> {
+        self.env.executable_exists(name, env_vars, current_dir)
+    }
+
+    fn begin_job(&mut self) -> bool {
+        self.env.begin_job()
+    }
+
+    fn end_job(&mut self) {
+        self.env.end_job()
+    }
+}
+
+impl<T: WorkingDirectoryEnvironment, P: ?Sized> WorkingDirectoryEnvironment for PolicyEnv<T, P> {
+    fn path_relative_to_working_dir<'a>(&self, path: Cow<'a, Path>) -> Cow<'a, Path> {
+        self.env.path_relative_to_working_dir(path)
+    }
+
+    fn current_working_dir(&self) -> &Path {
+        self.env.current_working_dir()
+    }
+
+    fn canonical_working_dir(&self) -> Result<PathBuf, NormalizationError> {
+        self.env.canonical_working_dir()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.env.resolve(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, NormalizationError> {
+        self.env.canonicalize(path)
+    }
+
+    fn path_metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        self.env.path_metadata(path)
+    }
+
+    fn path_exists(&self, path: &Path) -> bool {
+        self.env.path_exists(path)
+    }
+}
+
+impl<T, P> ChangeWorkingDirectoryEnvironment for PolicyEnv<T, P>
+where
+    T: ChangeWorkingDirectoryEnvironment,
+    P: ?Sized + SecurityPolicy,
+{
+    fn change_working_dir<'a>(&mut self, path: Cow<'a, Path>) -> io::Result<()> {
+        self.policy
+            .check_change_working_dir(&normalize_path(&self.env.resolve(&path)))?;
+        self.env.change_working_dir(path)
+    }
+}
+
+/// Canonicalizes a path before it is checked by a policy, so that it cannot
+/// be bypassed via `..` components or symbolic links. Paths which do not
+/// exist yet (e.g. files about to be created) are resolved relative to their
+/// canonicalized parent, or only normalized logically as a last resort.
+fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+
+    let in_parent = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize().ok().map(|parent| parent.join(name)),
+        _ => None,
+    };
+
+    in_parent
+        .unwrap_or_else(|| NormalizedPath::new_normalized_logical(path.to_owned()).into_inner())
+}
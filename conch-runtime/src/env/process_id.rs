@@ -0,0 +1,147 @@
+use crate::env::SubEnvironment;
+use crate::io::getppid;
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// An interface for getting the process ids associated with the shell,
+/// such as the values of `$$`, `$PPID`, and `$BASHPID`.
+///
+/// Subshells are not necessarily run in forked processes, thus the ids
+/// reported here are logical ids which may not correspond to any actual
+/// OS processes (besides the shell's own).
+pub trait ProcessIdEnvironment {
+    /// The id of the shell process (i.e. `$$`), which remains the same
+    /// within any of its subshells.
+    fn shell_pid(&self) -> u32;
+
+    /// The id of the shell's parent process (i.e. `$PPID`).
+    fn parent_pid(&self) -> u32;
+
+    /// The id of the current (sub)shell (i.e. `$BASHPID`), which is distinct
+    /// for every subshell.
+    fn subshell_pid(&self) -> u32;
+}
+
+impl<'a, T: ?Sized + ProcessIdEnvironment> ProcessIdEnvironment for &'a mut T {
+    fn shell_pid(&self) -> u32 {
+        (**self).shell_pid()
+    }
+
+    fn parent_pid(&self) -> u32 {
+        (**self).parent_pid()
+    }
+
+    fn subshell_pid(&self) -> u32 {
+        (**self).subshell_pid()
+    }
+}
+
+/// An environment module for tracking the logical process ids of the shell
+/// and its subshells.
+///
+/// Each sub-environment is considered to be a subshell, and is assigned a
+/// new id by counting up from the shell's own id.
+///
+/// Fixed ids can be provided via `with_pids`, e.g. to make the results of
+/// expanding `$$` deterministic within tests.
+#[derive(Debug, Clone)]
+pub struct ProcessIdEnv {
+    shell_pid: u32,
+    parent_pid: u32,
+    subshell_pid: u32,
+    last_pid: Arc<AtomicU32>,
+}
+
+impl ProcessIdEnv {
+    /// Creates a new environment using the ids of the current process
+    /// and its parent.
+    pub fn new() -> Self {
+        Self::with_pids(process::id(), getppid() as u32)
+    }
+
+    /// Creates a new environment using the provided shell and parent
+    /// process ids.
+    pub fn with_pids(shell_pid: u32, parent_pid: u32) -> Self {
+        Self {
+            shell_pid,
+            parent_pid,
+            subshell_pid: shell_pid,
+            last_pid: Arc::new(AtomicU32::new(shell_pid)),
+        }
+    }
+}
+
+impl PartialEq for ProcessIdEnv {
+    fn eq(&self, other: &Self) -> bool {
+        self.shell_pid == other.shell_pid
+            && self.parent_pid == other.parent_pid
+            && self.subshell_pid == other.subshell_pid
+    }
+}
+
+impl Eq for ProcessIdEnv {}
+
+impl Default for ProcessIdEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessIdEnvironment for ProcessIdEnv {
+    fn shell_pid(&self) -> u32 {
+        self.shell_pid
+    }
+
+    fn parent_pid(&self) -> u32 {
+        self.parent_pid
+    }
+
+    fn subshell_pid(&self) -> u32 {
+        self.subshell_pid
+    }
+}
+
+impl SubEnvironment for ProcessIdEnv {
+    fn sub_env(&self) -> Self {
+        let subshell_pid = self
+            .last_pid
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
+
+        Self {
+            shell_pid: self.shell_pid,
+            parent_pid: self.parent_pid,
+            subshell_pid,
+            last_pid: self.last_pid.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subshells_share_shell_pid_but_not_subshell_pid() {
+        let env = ProcessIdEnv::with_pids(42, 1);
+        assert_eq!(env.shell_pid(), 42);
+        assert_eq!(env.parent_pid(), 1);
+        assert_eq!(env.subshell_pid(), 42);
+
+        let child = env.sub_env();
+        let sibling = env.sub_env();
+        let grandchild = child.sub_env();
+
+        for sub in &[&child, &sibling, &grandchild] {
+            assert_eq!(sub.shell_pid(), 42);
+            assert_eq!(sub.parent_pid(), 1);
+            assert_ne!(sub.subshell_pid(), 42);
+        }
+
+        assert_ne!(child.subshell_pid(), sibling.subshell_pid());
+        assert_ne!(child.subshell_pid(), grandchild.subshell_pid());
+        assert_ne!(sibling.subshell_pid(), grandchild.subshell_pid());
+        assert_eq!(env.clone().subshell_pid(), 42);
+    }
+}
@@ -0,0 +1,298 @@
+//! Expansion of the backslash-escaped special characters in prompt strings
+//! (i.e. `$PS1`, `$PS2`, and `$PS4`), for embedders which present their own
+//! interactive prompts.
+//!
+//! The following escapes are supported:
+//!
+//! * `\a`: a bell character
+//! * `\d`: the date in "Weekday Month Date" format (e.g. "Tue May 26")
+//! * `\e`: an escape character
+//! * `\h`: the host name up to the first `.`
+//! * `\H`: the host name
+//! * `\n`: a newline
+//! * `\r`: a carriage return
+//! * `\t`: the current time in 24-hour HH:MM:SS format
+//! * `\T`: the current time in 12-hour HH:MM:SS format
+//! * `\@`: the current time in 12-hour am/pm format
+//! * `\A`: the current time in 24-hour HH:MM format
+//! * `\u`: the user name
+//! * `\w`: the current working directory, with `$HOME` abbreviated with a tilde
+//! * `\W`: the basename of the current working directory, or a tilde if it is `$HOME`
+//! * `\$`: a `#` if the effective user is the superuser, otherwise a `$`
+//! * `\nnn`: the character whose code is the octal number `nnn`
+//! * `\\`: a backslash
+//! * `\[` and `\]`: removed, as they only delimit non-printing characters
+//!
+//! Any other escape sequences are left as is. The user and host names are
+//! taken from `$USER` and `$HOSTNAME` (if set), and from the system otherwise.
+//! The date and time are taken from the clock of the environment (see
+//! `ClockEnvironment`), and shown in the local time zone.
+//!
+//! Note that unlike some shells, parameter expansion, command substitution,
+//! etc. are *not* performed on the result.
+
+use crate::env::{ClockEnvironment, StrKey, VariableEnvironment, WorkingDirectoryEnvironment};
+use crate::{sys, HOME};
+use std::borrow::Borrow;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PS1: &str = "PS1";
+const PS2: &str = "PS2";
+const PS4: &str = "PS4";
+const USER: &str = "USER";
+const HOSTNAME: &str = "HOSTNAME";
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Expands the primary prompt, `$PS1`, which defaults to `"$ "` (or `"# "`
+/// for the superuser) if unset.
+pub fn expand_ps1<E>(env: &E) -> String
+where
+    E: ?Sized + ClockEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    expand_var_or(PS1, "\\$ ", env)
+}
+
+/// Expands the secondary (continuation) prompt, `$PS2`, which defaults
+/// to `"> "` if unset.
+pub fn expand_ps2<E>(env: &E) -> String
+where
+    E: ?Sized + ClockEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    expand_var_or(PS2, "> ", env)
+}
+
+/// Expands the execution trace prompt, `$PS4`, which defaults to `"+ "`
+/// if unset.
+pub fn expand_ps4<E>(env: &E) -> String
+where
+    E: ?Sized + ClockEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    expand_var_or(PS4, "+ ", env)
+}
+
+fn expand_var_or<E>(name: &str, default: &str, env: &E) -> String
+where
+    E: ?Sized + ClockEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    match E::VarName::lookup(env, name) {
+        Some(prompt) => expand_prompt(prompt.borrow(), env),
+        None => expand_prompt(default, env),
+    }
+}
+
+/// Expands all escape sequences in an arbitrary prompt string.
+pub fn expand_prompt<E>(prompt: &str, env: &E) -> String
+where
+    E: ?Sized + ClockEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let mut out = String::with_capacity(prompt.len());
+    let mut chars = prompt.chars().peekable();
+    let mut time = None;
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        let escape = match chars.next() {
+            Some(escape) => escape,
+            None => {
+                out.push('\\');
+                break;
+            }
+        };
+
+        match escape {
+            'a' => out.push('\x07'),
+            'e' => out.push('\x1b'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            '\\' => out.push('\\'),
+            '[' | ']' => {}
+            '$' => out.push(if sys::is_privileged_user() { '#' } else { '$' }),
+
+            'h' | 'H' => {
+                let host = var_or(HOSTNAME, env, sys::host_name);
+                let host = if escape == 'h' {
+                    host.split('.').next().unwrap_or_default()
+                } else {
+                    &host
+                };
+                out.push_str(host);
+            }
+
+            'u' => out.push_str(&var_or(USER, env, sys::user_name)),
+
+            'w' | 'W' => {
+                let home = E::VarName::lookup(env, HOME).map(|home| Path::new(home.borrow()));
+                let cwd = env.current_working_dir();
+
+                match home {
+                    Some(home) if home == cwd => out.push('~'),
+                    Some(home) if escape == 'w' && cwd.starts_with(home) => {
+                        let rest = cwd.strip_prefix(home).unwrap_or(cwd);
+                        out.push_str("~/");
+                        out.push_str(&rest.to_string_lossy());
+                    }
+                    _ if escape == 'w' => out.push_str(&cwd.to_string_lossy()),
+                    _ => match cwd.file_name() {
+                        Some(name) => out.push_str(&name.to_string_lossy()),
+                        None => out.push_str(&cwd.to_string_lossy()),
+                    },
+                }
+            }
+
+            'd' | 't' | 'T' | '@' | 'A' => {
+                let time = *time.get_or_insert_with(|| LocalTime::at(env.system_time()));
+                out.push_str(&time.format(escape));
+            }
+
+            '0'..='7' => {
+                let mut code = escape.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+
+                out.extend(std::char::from_u32(code));
+            }
+
+            other => {
+                out.push('\\');
+                out.push(other);
+            }
+        }
+    }
+
+    out
+}
+
+/// Looks up a variable's value, falling back to querying the system if unset.
+fn var_or<E>(name: &str, env: &E, query: fn() -> Option<String>) -> String
+where
+    E: ?Sized + VariableEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    match E::VarName::lookup(env, name) {
+        Some(val) => val.borrow().clone(),
+        None => query().unwrap_or_default(),
+    }
+}
+
+/// A point in time, broken down in the local time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LocalTime {
+    weekday: usize,
+    month: usize,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl LocalTime {
+    fn at(time: SystemTime) -> Self {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+
+        Self::from_secs(secs + sys::utc_offset(secs).unwrap_or(0))
+    }
+
+    /// Breaks down the (local) seconds since the Unix epoch.
+    fn from_secs(secs: i64) -> Self {
+        let days = secs.div_euclid(SECS_PER_DAY);
+        let secs_of_day = secs.rem_euclid(SECS_PER_DAY);
+
+        // Converts days to a civil date, see
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+        Self {
+            // The epoch fell on a Thursday
+            weekday: (days + 4).rem_euclid(7) as usize,
+            month: (month - 1) as usize,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+        }
+    }
+
+    fn format(&self, escape: char) -> String {
+        let hour12 = match self.hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+
+        match escape {
+            'd' => format!(
+                "{} {} {:02}",
+                WEEKDAYS[self.weekday], MONTHS[self.month], self.day
+            ),
+            't' => format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second),
+            'T' => format!("{:02}:{:02}:{:02}", hour12, self.minute, self.second),
+            '@' => {
+                let meridiem = if self.hour < 12 { "AM" } else { "PM" };
+                format!("{:02}:{:02} {}", hour12, self.minute, meridiem)
+            }
+            'A' => format!("{:02}:{:02}", self.hour, self.minute),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_time_from_secs() {
+        // 2020-02-29T13:04:05Z
+        let time = LocalTime::from_secs(1_582_981_445);
+        assert_eq!(time.format('d'), "Sat Feb 29");
+        assert_eq!(time.format('t'), "13:04:05");
+        assert_eq!(time.format('T'), "01:04:05");
+        assert_eq!(time.format('@'), "01:04 PM");
+        assert_eq!(time.format('A'), "13:04");
+
+        let epoch = LocalTime::from_secs(0);
+        assert_eq!(epoch.format('d'), "Thu Jan 01");
+        assert_eq!(epoch.format('@'), "12:00 AM");
+
+        let before_epoch = LocalTime::from_secs(-1);
+        assert_eq!(before_epoch.format('d'), "Wed Dec 31");
+        assert_eq!(before_epoch.format('t'), "23:59:59");
+    }
+}
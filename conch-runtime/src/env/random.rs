@@ -0,0 +1,98 @@
+use crate::env::SubEnvironment;
+use crate::io::getpid;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "testing")]
+mod mock;
+
+#[cfg(feature = "testing")]
+pub use self::mock::MockRandomEnv;
+
+/// The largest value a `RandomEnvironment` will produce, matching the
+/// range of `$RANDOM` in other shells.
+pub const RANDOM_MAX: u16 = 32767;
+
+/// A non-zero constant mixed into every seed.
+const SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// An interface for generating pseudo-random numbers, e.g. for `$RANDOM`.
+pub trait RandomEnvironment {
+    /// Generate the next pseudo-random number in the range `0..=RANDOM_MAX`.
+    fn next_random(&mut self) -> u16;
+
+    /// Restart the sequence of generated numbers from the specified seed.
+    fn reseed(&mut self, seed: u64);
+}
+
+impl<'a, T: ?Sized + RandomEnvironment> RandomEnvironment for &'a mut T {
+    fn next_random(&mut self) -> u16 {
+        (**self).next_random()
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        (**self).reseed(seed);
+    }
+}
+
+/// A `RandomEnvironment` implementation backed by a seedable (xorshift)
+/// pseudo-random number generator.
+///
+/// The generator is **not** cryptographically secure. Sub-environments
+/// start off with a copy of the parent's state, much like a forked shell.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RandomEnv {
+    state: u64,
+}
+
+impl RandomEnv {
+    /// Construct a new generator seeded from the current time and process id.
+    pub fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Self::with_seed(nanos ^ ((getpid() as u64) << 32))
+    }
+
+    /// Construct a new generator with the specified seed. Generators created
+    /// with the same seed will produce the same sequence of numbers.
+    pub fn with_seed(seed: u64) -> Self {
+        // Xorshift gets stuck on a zero state, so mix in a non-zero constant
+        // (and remap the one seed which would cancel it out).
+        let state = match seed ^ SEED_MIX {
+            0 => SEED_MIX,
+            state => state,
+        };
+
+        Self { state }
+    }
+}
+
+impl Default for RandomEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RandomEnvironment for RandomEnv {
+    fn next_random(&mut self) -> u16 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+
+        (x >> 48) as u16 & RANDOM_MAX
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        *self = Self::with_seed(seed);
+    }
+}
+
+impl SubEnvironment for RandomEnv {
+    fn sub_env(&self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,49 @@
+use crate::env::{RandomEnvironment, SubEnvironment, RANDOM_MAX};
+use std::sync::Arc;
+
+/// A `RandomEnvironment` implementation which replays a scripted sequence
+/// of values (wrapping around once exhausted), allowing for deterministic
+/// tests of values derived from random numbers.
+///
+/// Values larger than `RANDOM_MAX` are truncated to fit its range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRandomEnv {
+    values: Arc<[u16]>,
+    next: usize,
+}
+
+impl MockRandomEnv {
+    /// Construct a new environment which will replay the specified values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no values are specified.
+    pub fn new<I: IntoIterator<Item = u16>>(values: I) -> Self {
+        let values: Vec<u16> = values.into_iter().collect();
+        assert!(!values.is_empty(), "at least one value must be specified");
+
+        Self {
+            values: values.into(),
+            next: 0,
+        }
+    }
+}
+
+impl RandomEnvironment for MockRandomEnv {
+    fn next_random(&mut self) -> u16 {
+        let value = self.values[self.next];
+        self.next = (self.next + 1) % self.values.len();
+        value & RANDOM_MAX
+    }
+
+    /// Replays the scripted values from the start, regardless of the seed.
+    fn reseed(&mut self, _seed: u64) {
+        self.next = 0;
+    }
+}
+
+impl SubEnvironment for MockRandomEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
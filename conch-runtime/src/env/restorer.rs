@@ -1,10 +1,11 @@
 use crate::env::{
     AsyncIoEnvironment, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, Pipe,
-    UnsetVariableEnvironment, VariableEnvironment,
+    TempFile, TempFileEnvironment, UnsetVariableEnvironment, VariableEnvironment,
 };
 use crate::io::Permissions;
 use crate::Fd;
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
 use std::fs::OpenOptions;
@@ -105,6 +106,10 @@ pub trait RedirectEnvRestorer<'a, E: 'a + ?Sized + FileDescEnvironment>:
 
     /// Forget any redirects backed up to this point.
     fn clear_redirects(&mut self);
+
+    /// Forget the backup of a specific redirect, such that any changes to
+    /// it will persist even after other redirects are restored.
+    fn forget_redirect(&mut self, fd: Fd);
 }
 
 impl<'a, 'b, E, T> RedirectEnvRestorer<'a, E> for &'b mut T
@@ -127,6 +132,10 @@ where
     fn clear_redirects(&mut self) {
         (**self).clear_redirects();
     }
+
+    fn forget_redirect(&mut self, fd: Fd) {
+        (**self).forget_redirect(fd);
+    }
 }
 
 /// Maintains a state of environment modifications so that
@@ -140,8 +149,8 @@ where
     E::Var: Clone,
 {
     env: &'a mut E,
-    var_overrides: HashMap<E::VarName, Option<(E::Var, bool)>>,
-    redirect_overrides: HashMap<Fd, Option<(E::FileHandle, Permissions)>>,
+    var_overrides: VarBackups<E::VarName, E::Var>,
+    redirect_overrides: RedirectBackups<E::FileHandle>,
 }
 
 impl<'a, E> EnvRestorer<'a, E>
@@ -155,10 +164,17 @@ where
     pub fn new(env: &'a mut E) -> Self {
         Self {
             env,
-            var_overrides: HashMap::new(),
-            redirect_overrides: HashMap::new(),
+            var_overrides: VarBackups::new(),
+            redirect_overrides: RedirectBackups::new(),
         }
     }
+
+    /// Restore all variables and redirects to their original state.
+    ///
+    /// Equivalent to dropping the restorer, but makes the intent explicit.
+    pub fn restore(self) {
+        drop(self)
+    }
 }
 
 impl<'a, E> Restorer<'a, E> for EnvRestorer<'a, E>
@@ -173,7 +189,7 @@ where
     }
 
     fn get_mut(&mut self) -> &mut E {
-        &mut self.env
+        self.env
     }
 }
 
@@ -202,19 +218,11 @@ where
     }
 
     fn backup_var(&mut self, key: &E::VarName) {
-        let value = self.env.exported_var(key);
-        self.var_overrides
-            .entry(key.clone())
-            .or_insert_with(|| value.map(|(val, exported)| (val.clone(), exported)));
+        self.var_overrides.backup(&*self.env, key);
     }
 
     fn restore_vars(&mut self) {
-        for (key, val) in self.var_overrides.drain() {
-            match val {
-                Some((val, exported)) => self.env.set_exported_var(key, val, exported),
-                None => self.env.unset_var(&key),
-            }
-        }
+        self.var_overrides.restore(&mut *self.env);
     }
 
     fn clear_vars(&mut self) {
@@ -236,10 +244,10 @@ where
     type VarName = E::VarName;
     type Var = E::Var;
 
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.env.var(name)
     }
@@ -273,6 +281,10 @@ where
         self.backup_var(&name);
         self.env.set_exported_var(name, val, exported)
     }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)> {
+        self.env.all_vars()
+    }
 }
 
 impl<'a, E> UnsetVariableEnvironment for EnvRestorer<'a, E>
@@ -305,6 +317,10 @@ where
         self.env.file_desc(fd)
     }
 
+    fn file_descs(&self) -> Vec<Fd> {
+        self.env.file_descs()
+    }
+
     fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
         self.backup_redirect(fd);
         self.env.set_file_desc(fd, handle, perms)
@@ -333,11 +349,43 @@ where
         self.env.open_path(path, opts)
     }
 
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_path_with_permissions(path, perms, opts)
+    }
+
     fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         self.env.open_pipe()
     }
 }
 
+impl<'a, E> TempFileEnvironment for EnvRestorer<'a, E>
+where
+    E: ?Sized
+        + ExportedVariableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + UnsetVariableEnvironment,
+    E::FileHandle: Clone,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.env.open_named_temp_file(contents)
+    }
+}
+
 impl<'b, E> AsyncIoEnvironment for EnvRestorer<'b, E>
 where
     E: ?Sized
@@ -355,6 +403,10 @@ where
         self.env.read_all(fd)
     }
 
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        self.env.read_chunks(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -380,27 +432,443 @@ where
     }
 
     fn backup_redirect(&mut self, fd: Fd) {
-        let Self {
-            redirect_overrides,
+        self.redirect_overrides.backup(&*self.env, fd);
+    }
+
+    fn restore_redirects(&mut self) {
+        self.redirect_overrides.restore(&mut *self.env);
+    }
+
+    fn clear_redirects(&mut self) {
+        self.redirect_overrides.clear();
+    }
+
+    fn forget_redirect(&mut self, fd: Fd) {
+        self.redirect_overrides.remove(&fd);
+    }
+}
+
+/// Maintains a state of variable modifications so that they can be
+/// restored later, either on drop or on demand.
+///
+/// Unlike `EnvRestorer`, this restorer only requires that the wrapped
+/// environment manage variables, making it suitable for custom spawners
+/// which only need to apply temporary assignments.
+#[derive(Debug, PartialEq)]
+pub struct VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    env: &'a mut E,
+    overrides: VarBackups<E::VarName, E::Var>,
+}
+
+impl<'a, E> VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    /// Create a new restorer.
+    pub fn new(env: &'a mut E) -> Self {
+        Self {
             env,
-            ..
-        } = self;
-        redirect_overrides.entry(fd).or_insert_with(|| {
+            overrides: VarBackups::new(),
+        }
+    }
+
+    /// Restore all variable definitions to their original state.
+    ///
+    /// Equivalent to dropping the restorer, but makes the intent explicit.
+    pub fn restore(self) {
+        drop(self)
+    }
+}
+
+impl<'a, E> Restorer<'a, E> for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn get(&self) -> &E {
+        &*self.env
+    }
+
+    fn get_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+impl<'a, E> Drop for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn drop(&mut self) {
+        self.restore_vars();
+    }
+}
+
+impl<'a, E> VarEnvRestorer<'a, E> for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn reserve_vars(&mut self, additional: usize) {
+        self.overrides.reserve(additional);
+    }
+
+    fn backup_var(&mut self, key: &E::VarName) {
+        self.overrides.backup(&*self.env, key);
+    }
+
+    fn restore_vars(&mut self) {
+        self.overrides.restore(&mut *self.env);
+    }
+
+    fn clear_vars(&mut self) {
+        self.overrides.clear();
+    }
+}
+
+impl<'a, E> VariableEnvironment for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    type VarName = E::VarName;
+    type Var = E::Var;
+
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
+    where
+        Self::VarName: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.env.var(name)
+    }
+
+    fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
+        self.backup_var(&name);
+        self.env.set_var(name, val);
+    }
+
+    fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
+        self.env.env_vars()
+    }
+}
+
+impl<'a, E> ExportedVariableEnvironment for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)> {
+        self.env.exported_var(name)
+    }
+
+    fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
+        self.backup_var(&name);
+        self.env.set_exported_var(name, val, exported)
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)> {
+        self.env.all_vars()
+    }
+}
+
+impl<'a, E> UnsetVariableEnvironment for VarRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn unset_var(&mut self, name: &E::VarName) {
+        self.backup_var(name);
+        self.env.unset_var(name);
+    }
+}
+
+/// Maintains a state of file descriptor modifications so that they can be
+/// restored later, either on drop or on demand.
+///
+/// Unlike `EnvRestorer`, this restorer only requires that the wrapped
+/// environment manage file descriptors, making it suitable for custom
+/// spawners which only need to apply temporary redirects, e.g. via
+/// `RedirectAction::apply_with_restorer`.
+#[derive(Debug, PartialEq)]
+pub struct RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    env: &'a mut E,
+    overrides: RedirectBackups<E::FileHandle>,
+}
+
+impl<'a, E> RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    /// Create a new restorer.
+    pub fn new(env: &'a mut E) -> Self {
+        Self {
+            env,
+            overrides: RedirectBackups::new(),
+        }
+    }
+
+    /// Restore all redirects to their original state.
+    ///
+    /// Equivalent to dropping the restorer, but makes the intent explicit.
+    pub fn restore(self) {
+        drop(self)
+    }
+}
+
+impl<'a, E> Restorer<'a, E> for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    fn get(&self) -> &E {
+        &*self.env
+    }
+
+    fn get_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+impl<'a, E> Drop for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    fn drop(&mut self) {
+        self.restore_redirects();
+    }
+}
+
+impl<'a, E> FileDescEnvironment for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    type FileHandle = E::FileHandle;
+
+    fn file_desc(&self, fd: Fd) -> Option<(&Self::FileHandle, Permissions)> {
+        self.env.file_desc(fd)
+    }
+
+    fn file_descs(&self) -> Vec<Fd> {
+        self.env.file_descs()
+    }
+
+    fn set_file_desc(&mut self, fd: Fd, handle: Self::FileHandle, perms: Permissions) {
+        self.backup_redirect(fd);
+        self.env.set_file_desc(fd, handle, perms)
+    }
+
+    fn close_file_desc(&mut self, fd: Fd) {
+        self.backup_redirect(fd);
+        self.env.close_file_desc(fd)
+    }
+}
+
+impl<'a, E> FileDescOpener for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment + FileDescOpener,
+    E::FileHandle: Clone,
+{
+    type OpenedFileHandle = E::OpenedFileHandle;
+
+    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_path(path, opts)
+    }
+
+    fn open_path_with_permissions(
+        &mut self,
+        path: &Path,
+        perms: Permissions,
+        opts: &OpenOptions,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_path_with_permissions(path, perms, opts)
+    }
+
+    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.env.open_pipe()
+    }
+}
+
+impl<'a, E> TempFileEnvironment for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment + TempFileEnvironment,
+    E::FileHandle: Clone,
+{
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.env.open_named_temp_file(contents)
+    }
+}
+
+impl<'b, E> AsyncIoEnvironment for RedirectRestorer<'b, E>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    type IoHandle = E::IoHandle;
+
+    fn read_all(&mut self, fd: Self::IoHandle) -> BoxFuture<'static, io::Result<Vec<u8>>> {
+        self.env.read_all(fd)
+    }
+
+    fn read_chunks(&mut self, fd: Self::IoHandle) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        self.env.read_chunks(fd)
+    }
+
+    fn write_all<'a>(
+        &mut self,
+        fd: Self::IoHandle,
+        data: Cow<'a, [u8]>,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        self.env.write_all(fd, data)
+    }
+
+    fn write_all_best_effort(&mut self, fd: Self::IoHandle, data: Vec<u8>) {
+        self.env.write_all_best_effort(fd, data);
+    }
+}
+
+impl<'a, E> RedirectEnvRestorer<'a, E> for RedirectRestorer<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    fn reserve_redirects(&mut self, additional: usize) {
+        self.overrides.reserve(additional);
+    }
+
+    fn backup_redirect(&mut self, fd: Fd) {
+        self.overrides.backup(&*self.env, fd);
+    }
+
+    fn restore_redirects(&mut self) {
+        self.overrides.restore(&mut *self.env);
+    }
+
+    fn clear_redirects(&mut self) {
+        self.overrides.clear();
+    }
+
+    fn forget_redirect(&mut self, fd: Fd) {
+        self.overrides.remove(&fd);
+    }
+}
+
+/// The original values of any variables modified through a restorer,
+/// or `None` if they were originally unset.
+#[derive(Debug, PartialEq)]
+struct VarBackups<N: Hash + Eq, V> {
+    overrides: HashMap<N, Option<(V, bool)>>,
+}
+
+impl<N: Hash + Eq + Clone, V: Clone> VarBackups<N, V> {
+    fn new() -> Self {
+        Self {
+            overrides: HashMap::new(),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.overrides.reserve(additional);
+    }
+
+    fn backup<E>(&mut self, env: &E, key: &N)
+    where
+        E: ?Sized + ExportedVariableEnvironment<VarName = N, Var = V>,
+    {
+        let value = env.exported_var(key);
+        self.overrides
+            .entry(key.clone())
+            .or_insert_with(|| value.map(|(val, exported)| (val.clone(), exported)));
+    }
+
+    fn restore<E>(&mut self, env: &mut E)
+    where
+        E: ?Sized + ExportedVariableEnvironment<VarName = N, Var = V> + UnsetVariableEnvironment,
+    {
+        for (key, val) in self.overrides.drain() {
+            match val {
+                Some((val, exported)) => env.set_exported_var(key, val, exported),
+                None => env.unset_var(&key),
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.overrides.clear();
+    }
+}
+
+/// The original handles of any descriptors modified through a restorer,
+/// or `None` if they were originally closed.
+#[derive(Debug, PartialEq)]
+struct RedirectBackups<H> {
+    overrides: HashMap<Fd, Option<(H, Permissions)>>,
+}
+
+impl<H: Clone> RedirectBackups<H> {
+    fn new() -> Self {
+        Self {
+            overrides: HashMap::new(),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.overrides.reserve(additional);
+    }
+
+    fn backup<E>(&mut self, env: &E, fd: Fd)
+    where
+        E: ?Sized + FileDescEnvironment<FileHandle = H>,
+    {
+        self.overrides.entry(fd).or_insert_with(|| {
             env.file_desc(fd)
                 .map(|(handle, perms)| (handle.clone(), perms))
         });
     }
 
-    fn restore_redirects(&mut self) {
-        for (fd, backup) in self.redirect_overrides.drain() {
+    fn restore<E>(&mut self, env: &mut E)
+    where
+        E: ?Sized + FileDescEnvironment<FileHandle = H>,
+    {
+        for (fd, backup) in self.overrides.drain() {
             match backup {
-                Some((handle, perms)) => self.env.set_file_desc(fd, handle, perms),
-                None => self.env.close_file_desc(fd),
+                Some((handle, perms)) => env.set_file_desc(fd, handle, perms),
+                None => env.close_file_desc(fd),
             }
         }
     }
 
-    fn clear_redirects(&mut self) {
-        self.redirect_overrides.clear();
+    fn remove(&mut self, fd: &Fd) {
+        self.overrides.remove(fd);
+    }
+
+    fn clear(&mut self) {
+        self.overrides.clear();
     }
 }
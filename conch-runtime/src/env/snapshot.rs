@@ -0,0 +1,39 @@
+use crate::env::ShellOption;
+use crate::ExitStatus;
+use std::path::PathBuf;
+
+/// A variable captured by an `EnvSnapshot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotVar {
+    /// The name of the variable.
+    pub name: String,
+    /// The value of the variable.
+    pub value: String,
+    /// Whether the variable is exported to spawned utilities.
+    pub exported: bool,
+}
+
+/// A point-in-time capture of the state of an environment, which can later be
+/// restored (e.g. for checkpointing long-running scripted workflows).
+///
+/// Snapshots are created via `Env::snapshot` and restored via `Env::restore_from`.
+/// With the `serde` feature enabled, snapshots can be serialized so they can be
+/// persisted outside of the current process.
+///
+/// Since function bodies are arbitrary values which cannot be serialized,
+/// functions are only captured by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvSnapshot {
+    /// All variables (including shell-only variables), sorted by name.
+    pub vars: Vec<SnapshotVar>,
+    /// The names of all defined functions, sorted.
+    pub functions: Vec<String>,
+    /// The current working directory.
+    pub working_dir: PathBuf,
+    /// All enabled shell options.
+    pub options: Vec<ShellOption>,
+    /// The exit status of the last command.
+    pub last_status: ExitStatus,
+}
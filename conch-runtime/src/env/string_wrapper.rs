@@ -1,14 +1,43 @@
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::hash::Hash;
 use std::rc::Rc;
 use std::sync::Arc;
 
+/// The names of commonly used variables which are interned.
+const INTERNED_NAMES: &[&str] = &[
+    "CDPATH", "HOME", "IFS", "OLDPWD", "OPTARG", "OPTIND", "PATH", "PPID", "PS1", "PS2", "PS4",
+    "PWD", "SHLVL",
+];
+
+lazy_static::lazy_static! {
+    static ref INTERNED: HashMap<&'static str, Arc<String>> = INTERNED_NAMES
+        .iter()
+        .map(|&name| (name, Arc::new(name.to_owned())))
+        .collect();
+}
+
+/// Looks up the shared copy of a commonly used string (e.g. `IFS`), if one
+/// has been interned.
+pub(crate) fn interned(s: &str) -> Option<&'static Arc<String>> {
+    INTERNED.get(s)
+}
+
 /// An interface for any `Clone`able wrapper around a `String`.
 pub trait StringWrapper: Borrow<String> + Clone + Eq + From<String> + Hash {
     /// Unwrap to an owned `String`.
     fn into_owned(self) -> String;
     /// Borrow the contents as a slice.
     fn as_str(&self) -> &str;
+
+    /// Wrap a well known string, such as the name of a commonly used
+    /// variable (e.g. `IFS` or `PATH`).
+    ///
+    /// Wrappers which can be shared across threads will reuse a single
+    /// interned copy of such names rather than allocating a new one each time.
+    fn from_static(s: &'static str) -> Self {
+        s.to_owned().into()
+    }
 }
 
 impl StringWrapper for String {
@@ -56,4 +85,30 @@ impl StringWrapper for Arc<String> {
     fn as_str(&self) -> &str {
         self
     }
+
+    fn from_static(s: &'static str) -> Self {
+        interned(s)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_static_reuses_interned_names() {
+        let a = Arc::<String>::from_static("IFS");
+        let b = Arc::<String>::from_static("IFS");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(*a, "IFS");
+
+        let a = Arc::<String>::from_static("not interned");
+        let b = Arc::<String>::from_static("not interned");
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(a, b);
+
+        assert_eq!(Rc::<String>::from_static("IFS").as_str(), "IFS");
+    }
 }
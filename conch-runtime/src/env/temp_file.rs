@@ -0,0 +1,189 @@
+use crate::env::{ArcFileDescOpenerEnv, FileDescOpener, FileDescOpenerEnv};
+use crate::io::FileDesc;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of unique names to try before giving up on creating a temp file.
+const NUM_RETRIES: usize = 64;
+
+/// A temporary file created by a `TempFileEnvironment`, which remains on the
+/// file system until its path is dropped.
+#[derive(Debug)]
+pub struct TempFile<T> {
+    /// A handle to the file, positioned at the start of its contents.
+    pub handle: T,
+    /// The path to the file, which is removed once dropped.
+    pub path: TempPath,
+}
+
+/// The path to a temporary file which will be removed when dropped.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TempPath {
+    path: PathBuf,
+}
+
+impl TempPath {
+    /// Takes ownership of a path which should be removed once dropped.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Keeps the file around even after the path is dropped.
+    pub fn keep(mut self) -> PathBuf {
+        std::mem::replace(&mut self.path, PathBuf::new())
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// An interface for creating temporary files, e.g. for holding the body of
+/// a heredoc until a command reads it.
+///
+/// Implementations may be swapped out to keep temporary files within a
+/// sandboxed directory, or to avoid the file system altogether.
+pub trait TempFileEnvironment: FileDescOpener {
+    /// Creates an anonymous temporary file holding `contents`, positioned at
+    /// the start of its contents. The file is not reachable through any path,
+    /// and is removed once all of its handles are closed.
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle>;
+
+    /// Creates a temporary file holding `contents`, which can also be reached
+    /// through its path (e.g. by other processes) until the path is dropped.
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>>;
+}
+
+impl<'a, T: ?Sized + TempFileEnvironment> TempFileEnvironment for &'a mut T {
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        (**self).open_temp_file(contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        (**self).open_named_temp_file(contents)
+    }
+}
+
+/// Creates a new file with a unique name within the system's temp directory
+/// (e.g. as specified by `$TMPDIR`), which only the current user can access.
+fn create_unique_file(opts: &mut OpenOptions) -> io::Result<(File, PathBuf)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+
+    opts.read(true).write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+
+    for _ in 0..NUM_RETRIES {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("conch-{}-{}-{:x}", process::id(), count, nanos));
+
+        match opts.open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "failed to find a unique name for a temporary file",
+    ))
+}
+
+fn fill(mut file: File, contents: &[u8]) -> io::Result<FileDesc> {
+    file.write_all(contents)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(FileDesc::from(file))
+}
+
+impl TempFileEnvironment for FileDescOpenerEnv {
+    #[cfg(unix)]
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        let (file, path) = create_unique_file(&mut OpenOptions::new())?;
+        fs::remove_file(path)?;
+        fill(file, contents)
+    }
+
+    #[cfg(windows)]
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        use std::os::windows::fs::OpenOptionsExt;
+        use winapi::um::winbase::FILE_FLAG_DELETE_ON_CLOSE;
+
+        let mut opts = OpenOptions::new();
+        opts.custom_flags(FILE_FLAG_DELETE_ON_CLOSE);
+        let (file, _path) = create_unique_file(&mut opts)?;
+        fill(file, contents)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        let (file, path) = create_unique_file(&mut OpenOptions::new())?;
+        let path = TempPath::new(path);
+
+        Ok(TempFile {
+            handle: fill(file, contents)?,
+            path,
+        })
+    }
+}
+
+impl<O: TempFileEnvironment> TempFileEnvironment for ArcFileDescOpenerEnv<O> {
+    fn open_temp_file(&mut self, contents: &[u8]) -> io::Result<Self::OpenedFileHandle> {
+        self.opener.open_temp_file(contents).map(Arc::new)
+    }
+
+    fn open_named_temp_file(
+        &mut self,
+        contents: &[u8],
+    ) -> io::Result<TempFile<Self::OpenedFileHandle>> {
+        self.opener
+            .open_named_temp_file(contents)
+            .map(|file| TempFile {
+                handle: Arc::new(file.handle),
+                path: file.path,
+            })
+    }
+}
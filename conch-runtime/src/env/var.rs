@@ -1,8 +1,10 @@
+use crate::env::string_wrapper::interned;
 use crate::env::SubEnvironment;
 use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::Hash;
+use std::ops::Deref;
 use std::sync::Arc;
 
 /// An interface for setting and getting shell and environment variables.
@@ -13,10 +15,10 @@ pub trait VariableEnvironment {
     type Var;
     /// Get the value of some variable. The values of both shell-only
     /// and environment variables will be looked up and returned.
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq;
+        Q: ?Sized + Hash + Eq;
     /// Set the value of some variable, maintaining its status as an
     /// environment variable if previously set as such.
     fn set_var(&mut self, name: Self::VarName, val: Self::Var);
@@ -29,10 +31,10 @@ impl<'a, T: ?Sized + VariableEnvironment> VariableEnvironment for &'a mut T {
     type VarName = T::VarName;
     type Var = T::Var;
 
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         (**self).var(name)
     }
@@ -46,6 +48,92 @@ impl<'a, T: ?Sized + VariableEnvironment> VariableEnvironment for &'a mut T {
     }
 }
 
+/// An interface for variable names which can be looked up via string slices.
+///
+/// Evaluators frequently need to look up variables by a name which is known
+/// up front (e.g. `$IFS` or `$HOME`), or by a name which is not of the same
+/// type as the environment's `VarName`. This is implemented for any name which
+/// dereferences to (and can be borrowed as) either a `str` (e.g. `String`,
+/// `&str`, `Arc<str>`) or a `String` (e.g. `Arc<String>`). Names borrowed as a
+/// `str` are looked up without allocating, while names borrowed as a `String`
+/// only allocate a temporary key for names which are not commonly used.
+///
+/// Names which do neither (e.g. a newtype which only implements
+/// `Borrow<String>`) can implement this trait directly.
+pub trait StrKey: Sized {
+    /// Borrow the name as a string slice.
+    fn as_key_str(&self) -> &str;
+
+    /// Look up the value of a variable whose name matches `name` in `env`.
+    fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+    where
+        E: ?Sized + VariableEnvironment<VarName = Self>;
+}
+
+mod key {
+    use super::VariableEnvironment;
+    use std::borrow::Borrow;
+
+    /// The string types which a `StrKey` name can be borrowed as.
+    pub trait KeyStr {
+        fn as_str(&self) -> &str;
+
+        fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+        where
+            E: ?Sized + VariableEnvironment,
+            E::VarName: Borrow<Self>;
+    }
+}
+
+impl key::KeyStr for str {
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+    where
+        E: ?Sized + VariableEnvironment,
+        E::VarName: Borrow<Self>,
+    {
+        env.var(name)
+    }
+}
+
+impl key::KeyStr for String {
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+    where
+        E: ?Sized + VariableEnvironment,
+        E::VarName: Borrow<Self>,
+    {
+        // Commonly used names are already interned
+        match interned(name) {
+            Some(name) => env.var::<String>(name),
+            None => env.var(&name.to_owned()),
+        }
+    }
+}
+
+impl<T> StrKey for T
+where
+    T: Deref + Borrow<<T as Deref>::Target>,
+    T::Target: key::KeyStr,
+{
+    fn as_key_str(&self) -> &str {
+        key::KeyStr::as_str(&**self)
+    }
+
+    fn lookup<'a, E>(env: &'a E, name: &str) -> Option<&'a E::Var>
+    where
+        E: ?Sized + VariableEnvironment<VarName = Self>,
+    {
+        <T::Target as key::KeyStr>::lookup(env, name)
+    }
+}
+
 /// An interface for setting and getting shell and environment variables and
 /// controlling whether or not they can appear as environment variables to
 /// subprocesses.
@@ -54,6 +142,38 @@ pub trait ExportedVariableEnvironment: VariableEnvironment {
     fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)>;
     /// Set the value of some variable, and set it's exported status as specified.
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool);
+
+    /// Get a snapshot of all exported variables and their values, i.e. the
+    /// environment inherited by any utilities spawned from this environment.
+    ///
+    /// The snapshot does not borrow the environment, and is returned in no
+    /// particular order.
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.env_vars()
+            .iter()
+            .map(|&(name, val)| (name.clone(), val.clone()))
+            .collect()
+    }
+
+    /// Get a snapshot of all variables (including shell-only variables), their
+    /// values, and whether they are exported.
+    ///
+    /// The snapshot does not borrow the environment, and is returned in no
+    /// particular order. By default only exported variables are included.
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.exported_vars()
+            .into_iter()
+            .map(|(name, val)| (name, val, true))
+            .collect()
+    }
 }
 
 impl<'a, T: ?Sized + ExportedVariableEnvironment> ExportedVariableEnvironment for &'a mut T {
@@ -64,6 +184,22 @@ impl<'a, T: ?Sized + ExportedVariableEnvironment> ExportedVariableEnvironment fo
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
         (**self).set_exported_var(name, val, exported)
     }
+
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        (**self).exported_vars()
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        (**self).all_vars()
+    }
 }
 
 /// An interface for unsetting shell and envrironment variables.
@@ -78,13 +214,68 @@ impl<'a, T: ?Sized + UnsetVariableEnvironment> UnsetVariableEnvironment for &'a
     }
 }
 
+/// The attributes of a shell variable, e.g. as set by the `declare` builtin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VarAttributes {
+    /// Any values assigned to the variable are evaluated as arithmetic expressions.
+    pub integer: bool,
+    /// The variable can no longer be assigned or unset.
+    pub readonly: bool,
+    /// The variable was declared as an indexed array (e.g. via `declare -a`).
+    ///
+    /// Since array variables are not yet supported, such a variable holds a
+    /// single element (at index 0) which is accessed through its name.
+    pub array: bool,
+    /// The variable was declared as an associative array (e.g. via
+    /// `declare -A`), holding a single element (with the key `0`) just like
+    /// indexed arrays.
+    pub assoc: bool,
+}
+
+/// An interface for getting and setting the attributes of shell variables.
+///
+/// Implementations are expected to ignore any assignments to (and unsetting
+/// of) readonly variables. Assignments made by the shell itself go through
+/// `eval::assignable_value` first, which reports any such attempts, and
+/// evaluates the values assigned to integer variables.
+pub trait VariableAttributesEnvironment: VariableEnvironment {
+    /// Get the attributes of some variable, regardless of whether it is set.
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes;
+    /// Set the attributes of some variable, without changing its value.
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes);
+}
+
+impl<'a, T: ?Sized + VariableAttributesEnvironment> VariableAttributesEnvironment for &'a mut T {
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes {
+        (**self).var_attributes(name)
+    }
+
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes) {
+        (**self).set_var_attributes(name, attributes)
+    }
+}
+
+/// The maximum number of parent scopes a `VarEnv` will chain through before
+/// sub-environments flatten them into a single scope, keeping lookups fast.
+const MAX_SCOPE_DEPTH: usize = 8;
+
 /// An environment module for setting, getting, and exporting shell variables.
-#[derive(PartialEq, Eq)]
+///
+/// Sub-environments share (rather than copy) the variables of their parent,
+/// and only record their own modifications, making it cheap to spawn many
+/// subshells even if a large number of variables are defined.
 pub struct VarEnv<N: Eq + Hash, V> {
-    /// A mapping of variable names to their values.
+    /// A mapping of variable names to their values, shadowing any inherited
+    /// from the parent scope. A value of `None` indicates the variable was unset.
     ///
     /// The tupled boolean indicates if a variable should be exported to other commands.
-    vars: Arc<HashMap<N, (V, bool)>>,
+    vars: Arc<HashMap<N, Option<(V, bool)>>>,
+    /// The scope this environment was derived from, if any.
+    parent: Option<Arc<VarEnv<N, V>>>,
+    /// The number of scopes chained through `parent`.
+    depth: usize,
+    /// The attributes of any variables which have been given some.
+    attrs: Arc<HashMap<N, VarAttributes>>,
 }
 
 impl<N, V> VarEnv<N, V>
@@ -95,6 +286,9 @@ where
     pub fn new() -> Self {
         Self {
             vars: Arc::new(HashMap::new()),
+            parent: None,
+            depth: 0,
+            attrs: Arc::new(HashMap::new()),
         }
     }
 
@@ -115,11 +309,53 @@ where
         Self {
             vars: Arc::new(
                 iter.into_iter()
-                    .map(|(k, v)| (k, (v, true)))
+                    .map(|(k, v)| (k, Some((v, true))))
                     .collect::<HashMap<_, _>>(),
             ),
+            parent: None,
+            depth: 0,
+            attrs: Arc::new(HashMap::new()),
         }
     }
+
+    /// Looks up the value of a variable in this or any parent scope.
+    fn lookup<Q>(&self, name: &Q) -> Option<&(V, bool)>
+    where
+        N: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut env = self;
+        loop {
+            if let Some(entry) = env.vars.get(name) {
+                return entry.as_ref();
+            }
+
+            env = env.parent.as_deref()?;
+        }
+    }
+
+    /// Indicates if the variable was marked as readonly, and thus cannot be
+    /// assigned or unset.
+    fn is_readonly(&self, name: &N) -> bool {
+        matches!(self.attrs.get(name), Some(attrs) if attrs.readonly)
+    }
+
+    /// Collects all variables visible from this scope.
+    fn visible_vars(&self) -> HashMap<&N, &(V, bool)> {
+        let mut vars = HashMap::new();
+        let mut scope = Some(self);
+
+        while let Some(env) = scope {
+            for (name, entry) in &*env.vars {
+                vars.entry(name).or_insert_with(|| entry.as_ref());
+            }
+            scope = env.parent.as_deref();
+        }
+
+        vars.into_iter()
+            .filter_map(|(name, entry)| entry.map(|entry| (name, entry)))
+            .collect()
+    }
 }
 
 impl<N, V> VariableEnvironment for VarEnv<N, V>
@@ -130,29 +366,33 @@ where
     type VarName = N;
     type Var = V;
 
-    fn var<Q: ?Sized>(&self, name: &Q) -> Option<&Self::Var>
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
     where
         Self::VarName: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
-        self.vars.get(name).map(|&(ref val, _)| val)
+        self.lookup(name).map(|&(ref val, _)| val)
     }
 
     fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
-        let (needs_insert, exported) = match self.vars.get(&name) {
+        if self.is_readonly(&name) {
+            return;
+        }
+
+        let (needs_insert, exported) = match self.lookup(&name) {
             Some(&(ref existing_val, exported)) => (&val != existing_val, exported),
             None => (true, false),
         };
 
         if needs_insert {
-            Arc::make_mut(&mut self.vars).insert(name, (val, exported));
+            Arc::make_mut(&mut self.vars).insert(name, Some((val, exported)));
         }
     }
 
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
         let ret: Vec<_> = self
-            .vars
-            .iter()
+            .visible_vars()
+            .into_iter()
             .filter_map(|(k, &(ref v, exported))| if exported { Some((k, v)) } else { None })
             .collect();
 
@@ -166,21 +406,47 @@ where
     V: Eq + Clone,
 {
     fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)> {
-        self.vars
-            .get(name)
+        self.lookup(name)
             .map(|&(ref val, exported)| (val, exported))
     }
 
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
-        let needs_insert = match self.vars.get(&name) {
-            Some(&(ref existing_val, _)) => val != *existing_val,
+        // Readonly variables can still be exported, but keep their value
+        let val = if self.is_readonly(&name) {
+            match self.lookup(&name) {
+                Some(&(ref existing_val, _)) => existing_val.clone(),
+                None => return,
+            }
+        } else {
+            val
+        };
+
+        let needs_insert = match self.lookup(&name) {
+            Some(&(ref existing_val, was_exported)) => {
+                val != *existing_val || exported != was_exported
+            }
             None => true,
         };
 
         if needs_insert {
-            Arc::make_mut(&mut self.vars).insert(name, (val, exported));
+            Arc::make_mut(&mut self.vars).insert(name, Some((val, exported)));
         }
     }
+
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)> {
+        self.visible_vars()
+            .into_iter()
+            .filter(|&(_, &(_, exported))| exported)
+            .map(|(name, &(ref val, _))| (name.clone(), val.clone()))
+            .collect()
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)> {
+        self.visible_vars()
+            .into_iter()
+            .map(|(name, &(ref val, exported))| (name.clone(), val.clone(), exported))
+            .collect()
+    }
 }
 
 impl<N, V> UnsetVariableEnvironment for VarEnv<N, V>
@@ -189,12 +455,68 @@ where
     V: Eq + Clone,
 {
     fn unset_var(&mut self, name: &N) {
-        if self.vars.contains_key(name) {
-            Arc::make_mut(&mut self.vars).remove(name);
+        match self.attrs.get(name) {
+            Some(attrs) if attrs.readonly => return,
+            Some(_) => {
+                Arc::make_mut(&mut self.attrs).remove(name);
+            }
+            None => {}
+        }
+
+        if self.lookup(name).is_none() {
+            return;
+        }
+
+        let vars = Arc::make_mut(&mut self.vars);
+        if self.parent.is_some() {
+            // Shadow any definition inherited from the parent scope
+            vars.insert(name.clone(), None);
+        } else {
+            vars.remove(name);
         }
     }
 }
 
+impl<N, V> VariableAttributesEnvironment for VarEnv<N, V>
+where
+    N: Eq + Clone + Hash,
+    V: Eq + Clone,
+{
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes {
+        self.attrs.get(name).copied().unwrap_or_default()
+    }
+
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes) {
+        if self.var_attributes(&name) == attributes {
+            return;
+        }
+
+        let attrs = Arc::make_mut(&mut self.attrs);
+        if attributes == VarAttributes::default() {
+            attrs.remove(&name);
+        } else {
+            attrs.insert(name, attributes);
+        }
+    }
+}
+
+impl<N, V> PartialEq for VarEnv<N, V>
+where
+    N: Eq + Hash,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.visible_vars() == other.visible_vars()
+    }
+}
+
+impl<N, V> Eq for VarEnv<N, V>
+where
+    N: Eq + Hash,
+    V: Eq,
+{
+}
+
 impl<N, V> fmt::Debug for VarEnv<N, V>
 where
     N: Eq + Ord + Hash + fmt::Debug,
@@ -206,7 +528,7 @@ where
         let mut vars = BTreeMap::new();
         let mut env_vars = BTreeMap::new();
 
-        for (name, &(ref val, is_env)) in &*self.vars {
+        for (name, &(ref val, is_env)) in self.visible_vars() {
             if is_env {
                 env_vars.insert(name, val);
             } else {
@@ -237,16 +559,55 @@ where
     fn clone(&self) -> Self {
         Self {
             vars: self.vars.clone(),
+            parent: self.parent.clone(),
+            depth: self.depth,
+            attrs: self.attrs.clone(),
         }
     }
 }
 
 impl<N, V> SubEnvironment for VarEnv<N, V>
 where
-    N: Eq + Hash,
+    N: Eq + Hash + Clone,
+    V: Clone,
 {
     fn sub_env(&self) -> Self {
-        self.clone()
+        if self.vars.is_empty() {
+            // Nothing to shadow, the parent scope can be shared as is
+            return Self {
+                vars: Arc::new(HashMap::new()),
+                parent: self.parent.clone(),
+                depth: self.depth,
+                attrs: self.attrs.clone(),
+            };
+        }
+
+        if self.depth >= MAX_SCOPE_DEPTH {
+            let vars = self
+                .visible_vars()
+                .into_iter()
+                .map(|(name, entry)| (name.clone(), Some(entry.clone())))
+                .collect();
+
+            return Self {
+                vars: Arc::new(HashMap::new()),
+                parent: Some(Arc::new(Self {
+                    vars: Arc::new(vars),
+                    parent: None,
+                    depth: 0,
+                    attrs: Arc::new(HashMap::new()),
+                })),
+                depth: 1,
+                attrs: self.attrs.clone(),
+            };
+        }
+
+        Self {
+            vars: Arc::new(HashMap::new()),
+            parent: Some(Arc::new(self.clone())),
+            depth: self.depth + 1,
+            attrs: self.attrs.clone(),
+        }
     }
 }
 
@@ -289,6 +650,24 @@ mod tests {
         assert_eq!(env.exported_var(&name), Some((&new_value, false)));
     }
 
+    #[test]
+    fn test_exported_vars_snapshot() {
+        let mut env = VarEnv::with_env_vars(vec![("exported", "value"), ("unset", "value")]);
+        env.set_var("local", "value");
+
+        let mut child = env.sub_env();
+        child.set_exported_var("local", "new_value", true);
+        child.unset_var(&"unset");
+
+        let mut vars = child.exported_vars();
+        vars.sort();
+        assert_eq!(vars, vec![("exported", "value"), ("local", "new_value")]);
+
+        let mut vars = env.exported_vars();
+        vars.sort();
+        assert_eq!(vars, vec![("exported", "value"), ("unset", "value")]);
+    }
+
     #[test]
     fn test_sub_env_no_needless_clone() {
         let not_set = "not set";
@@ -299,16 +678,89 @@ mod tests {
 
         let mut env = env.sub_env();
         env.set_var(name, value);
-        if Arc::get_mut(&mut env.vars).is_some() {
+        if !env.vars.is_empty() {
             panic!("needles clone!");
         }
 
         env.unset_var(&not_set);
-        if Arc::get_mut(&mut env.vars).is_some() {
+        if !env.vars.is_empty() {
             panic!("needles clone!");
         }
     }
 
+    #[test]
+    fn test_str_key_lookup() {
+        let mut env = VarEnv::<Arc<String>, Arc<String>>::new();
+        env.set_var(Arc::new("IFS".to_owned()), Arc::new(" ".to_owned()));
+        env.set_var(Arc::new("foo".to_owned()), Arc::new("bar".to_owned()));
+
+        assert_eq!(
+            Arc::<String>::lookup(&env, "IFS"),
+            Some(&Arc::new(" ".to_owned()))
+        );
+        assert_eq!(
+            Arc::<String>::lookup(&env, "foo"),
+            Some(&Arc::new("bar".to_owned()))
+        );
+        assert_eq!(Arc::<String>::lookup(&env, "PATH"), None);
+    }
+
+    #[test]
+    fn test_sub_env_shares_parent_vars() {
+        let name = "var";
+        let value = "value";
+        let mut parent = VarEnv::new();
+        parent.set_var(name, value);
+
+        let mut child = parent.sub_env();
+        child.set_var("other", value);
+
+        let shared = child.parent.as_ref().expect("no parent scope");
+        assert!(Arc::ptr_eq(&shared.vars, &parent.vars));
+        assert_eq!(parent.var("other"), None);
+    }
+
+    #[test]
+    fn test_unset_var_in_child_env_should_not_affect_parent() {
+        let name = "var";
+        let value = "value";
+        let mut parent = VarEnv::with_env_vars(vec![(name, value)]);
+
+        {
+            let mut child = parent.sub_env();
+            child.unset_var(&name);
+            assert_eq!(child.var(name), None);
+            assert!(child.env_vars().is_empty());
+
+            child.set_var(name, "new value");
+            assert_eq!(child.exported_var(&name), Some((&"new value", false)));
+        }
+
+        assert_eq!(parent.exported_var(&name), Some((&value, true)));
+        parent.unset_var(&name);
+        assert_eq!(parent.var(name), None);
+    }
+
+    #[test]
+    fn test_deeply_nested_sub_envs() {
+        let mut env = VarEnv::new();
+        env.set_var("var0".to_owned(), 0);
+
+        for i in 1..(MAX_SCOPE_DEPTH * 3) {
+            let mut child = env.sub_env();
+            assert!(child.depth <= MAX_SCOPE_DEPTH);
+
+            child.set_var(format!("var{}", i), i);
+            env = child;
+        }
+
+        for i in 0..(MAX_SCOPE_DEPTH * 3) {
+            assert_eq!(env.var(&format!("var{}", i)), Some(&i));
+        }
+
+        assert_eq!(env, env.sub_env());
+    }
+
     #[test]
     fn test_env_vars() {
         use std::collections::HashSet;
@@ -378,4 +830,33 @@ mod tests {
         let vars: HashSet<(_, _)> = HashSet::from_iter(child.env_vars().into_owned());
         assert_eq!(vars, env_vars);
     }
+
+    #[test]
+    fn test_readonly_vars_are_not_changed_in_sub_envs() {
+        let name = || String::from("var");
+        let mut env = VarEnv::<String, String>::new();
+
+        env.set_var(name(), String::from("value"));
+        env.set_var_attributes(
+            name(),
+            VarAttributes {
+                readonly: true,
+                ..VarAttributes::default()
+            },
+        );
+
+        let mut child = env.sub_env();
+        child.set_var(name(), String::from("changed"));
+        child.set_exported_var(name(), String::from("changed"), true);
+        child.unset_var(&name());
+        assert_eq!(
+            child.exported_var(&name()),
+            Some((&String::from("value"), true))
+        );
+        assert!(child.var_attributes(&name()).readonly);
+        assert_eq!(
+            env.exported_var(&name()),
+            Some((&String::from("value"), false))
+        );
+    }
 }
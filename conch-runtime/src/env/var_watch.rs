@@ -0,0 +1,238 @@
+use crate::env::{
+    ExportedVariableEnvironment, SubEnvironment, UnsetVariableEnvironment, VarAttributes,
+    VariableAttributesEnvironment, VariableEnvironment,
+};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// A callback invoked with the name and new value (or `None` if it was unset)
+/// of a watched variable whenever it is assigned.
+pub type VarWatcher<N, V> = Arc<dyn Fn(&N, Option<&V>) + Send + Sync>;
+
+type Watchers<N, V> = HashMap<N, Vec<VarWatcher<N, V>>>;
+
+/// A variable environment wrapper which notifies watchers whenever specific
+/// variables are assigned or unset, before delegating to another variable
+/// environment implementation.
+///
+/// This allows embedders to react to changes of variables such as `PATH` or
+/// `PS1` immediately (e.g. to invalidate cached command locations or to
+/// re-render a prompt), rather than polling them after every command.
+///
+/// Watchers are notified on every assignment, even if the value remains the
+/// same. Sub-environments (i.e. subshells) do not inherit any watchers, since
+/// their changes are never visible to the environment being watched.
+pub struct WatchedVarEnv<T: VariableEnvironment> {
+    var_env: T,
+    watchers: Arc<Watchers<T::VarName, T::Var>>,
+}
+
+impl<T: VariableEnvironment> WatchedVarEnv<T> {
+    /// Create a new environment without any watchers which delegates to
+    /// the provided variable environment.
+    pub fn new(var_env: T) -> Self {
+        Self {
+            var_env,
+            watchers: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a watcher which will be invoked whenever the variable
+    /// `name` is assigned or unset.
+    pub fn watch<F>(&mut self, name: T::VarName, watcher: F)
+    where
+        T::VarName: Clone,
+        F: 'static + Send + Sync + Fn(&T::VarName, Option<&T::Var>),
+    {
+        Arc::make_mut(&mut self.watchers)
+            .entry(name)
+            .or_default()
+            .push(Arc::new(watcher));
+    }
+
+    /// Removes all watchers of the variable `name`.
+    pub fn unwatch(&mut self, name: &T::VarName)
+    where
+        T::VarName: Clone,
+    {
+        if self.watchers.contains_key(name) {
+            Arc::make_mut(&mut self.watchers).remove(name);
+        }
+    }
+
+    /// Unwraps the underlying variable environment, dropping any watchers.
+    pub fn into_inner(self) -> T {
+        self.var_env
+    }
+}
+
+/// Notifies all watchers of a variable of its current value.
+fn notify<T: VariableEnvironment>(
+    var_env: &T,
+    name: &T::VarName,
+    watchers: &[VarWatcher<T::VarName, T::Var>],
+) {
+    let val = var_env.var(name);
+    for watcher in watchers {
+        watcher(name, val);
+    }
+}
+
+impl<T> Clone for WatchedVarEnv<T>
+where
+    T: Clone + VariableEnvironment,
+{
+    fn clone(&self) -> Self {
+        Self {
+            var_env: self.var_env.clone(),
+            watchers: self.watchers.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for WatchedVarEnv<T>
+where
+    T: fmt::Debug + VariableEnvironment,
+    T::VarName: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(WatchedVarEnv))
+            .field("var_env", &self.var_env)
+            .field("watched", &self.watchers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<T> Default for WatchedVarEnv<T>
+where
+    T: Default + VariableEnvironment,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> SubEnvironment for WatchedVarEnv<T>
+where
+    T: SubEnvironment + VariableEnvironment,
+{
+    fn sub_env(&self) -> Self {
+        Self::new(self.var_env.sub_env())
+    }
+}
+
+impl<T: VariableEnvironment> VariableEnvironment for WatchedVarEnv<T> {
+    type VarName = T::VarName;
+    type Var = T::Var;
+
+    fn var<Q>(&self, name: &Q) -> Option<&Self::Var>
+    where
+        Self::VarName: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.var_env.var(name)
+    }
+
+    fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
+        let watched = self.watchers.get_key_value(&name);
+        self.var_env.set_var(name, val);
+        if let Some((name, watchers)) = watched {
+            notify(&self.var_env, name, watchers);
+        }
+    }
+
+    fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
+        self.var_env.env_vars()
+    }
+}
+
+impl<T: ExportedVariableEnvironment> ExportedVariableEnvironment for WatchedVarEnv<T> {
+    fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)> {
+        self.var_env.exported_var(name)
+    }
+
+    fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
+        let watched = self.watchers.get_key_value(&name);
+        self.var_env.set_exported_var(name, val, exported);
+        if let Some((name, watchers)) = watched {
+            notify(&self.var_env, name, watchers);
+        }
+    }
+
+    fn exported_vars(&self) -> Vec<(Self::VarName, Self::Var)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.exported_vars()
+    }
+
+    fn all_vars(&self) -> Vec<(Self::VarName, Self::Var, bool)>
+    where
+        Self::VarName: Clone,
+        Self::Var: Clone,
+    {
+        self.var_env.all_vars()
+    }
+}
+
+impl<T: VariableAttributesEnvironment> VariableAttributesEnvironment for WatchedVarEnv<T> {
+    fn var_attributes(&self, name: &Self::VarName) -> VarAttributes {
+        self.var_env.var_attributes(name)
+    }
+
+    fn set_var_attributes(&mut self, name: Self::VarName, attributes: VarAttributes) {
+        self.var_env.set_var_attributes(name, attributes)
+    }
+}
+
+impl<T: UnsetVariableEnvironment> UnsetVariableEnvironment for WatchedVarEnv<T> {
+    fn unset_var(&mut self, name: &Self::VarName) {
+        let watched = self.watchers.get_key_value(name);
+        self.var_env.unset_var(name);
+        if let Some((name, watchers)) = watched {
+            notify(&self.var_env, name, watchers);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::VarEnv;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_watchers_are_notified_of_assignments() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut env = WatchedVarEnv::new(VarEnv::<&str, &str>::new());
+
+        let seen_clone = seen.clone();
+        env.watch("PATH", move |name, val| {
+            seen_clone.lock().unwrap().push((*name, val.cloned()));
+        });
+
+        env.set_var("PATH", "/bin");
+        env.set_var("HOME", "/home");
+        env.set_exported_var("PATH", "/usr/bin", true);
+        env.unset_var(&"PATH");
+        env.sub_env().set_var("PATH", "/sbin");
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("PATH", Some("/bin")),
+                ("PATH", Some("/usr/bin")),
+                ("PATH", None),
+            ]
+        );
+
+        env.unwatch(&"PATH");
+        env.set_var("PATH", "/bin");
+        assert_eq!(seen.lock().unwrap().len(), 3);
+        assert_eq!(env.var("PATH"), Some(&"/bin"));
+    }
+}
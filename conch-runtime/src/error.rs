@@ -3,11 +3,14 @@
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
 use crate::io::Permissions;
-use crate::Fd;
+use crate::{ExitStatus, Fd, EXIT_SUCCESS};
 use std::convert::From;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 use std::io::Error as IoError;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Determines whether an error should be treated as "fatal".
 ///
@@ -22,6 +25,15 @@ use std::io::Error as IoError;
 pub trait IsFatalError: 'static + Send + Sync + Error {
     /// Checks whether the error should be considered a "fatal" error.
     fn is_fatal(&self) -> bool;
+
+    /// Checks whether the error is actually a `ControlFlow` request being
+    /// propagated through the spawners, rather than a real failure.
+    ///
+    /// Errors carrying such requests should also be considered fatal so
+    /// that intermediate commands pass them through untouched.
+    fn control_flow(&self) -> Option<ControlFlow> {
+        None
+    }
 }
 
 impl IsFatalError for void::Void {
@@ -30,6 +42,96 @@ impl IsFatalError for void::Void {
     }
 }
 
+/// The location of a command within the source it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// The file the command was read from, if any.
+    pub file: Option<Arc<Path>>,
+    /// The line (starting from 1) on which the command begins.
+    pub line: usize,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self.file {
+            Some(ref file) => write!(fmt, "{}: line {}", file.display(), self.line),
+            None => write!(fmt, "line {}", self.line),
+        }
+    }
+}
+
+/// An error which can be annotated with the location of the command which
+/// caused it.
+pub trait WithLocation {
+    /// Annotates the error with the provided location.
+    ///
+    /// Implementations may choose to leave errors which aren't real failures
+    /// (e.g. control flow requests) or which already carry a location as is.
+    fn with_location(self, location: SourceLocation) -> Self;
+}
+
+impl WithLocation for void::Void {
+    fn with_location(self, _: SourceLocation) -> Self {
+        void::unreachable(self)
+    }
+}
+
+/// A request, raised by builtins such as `return`, `exit`, or `break`, to
+/// unwind execution up to an enclosing construct which knows how to handle it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ControlFlow {
+    /// Return from the currently executing function with the provided status.
+    Return(ExitStatus),
+    /// Exit the current shell (or subshell) with the provided status.
+    Exit(ExitStatus),
+    /// Break out of the specified number of enclosing loops.
+    Break(usize),
+    /// Skip to the next iteration of the specified enclosing loop,
+    /// breaking out of any loops nested within it.
+    Continue(usize),
+}
+
+impl ControlFlow {
+    /// The exit status a subshell should resolve with if this request
+    /// reaches it without being handled by anything else.
+    pub fn exit_status(&self) -> ExitStatus {
+        match *self {
+            ControlFlow::Return(status) | ControlFlow::Exit(status) => status,
+            ControlFlow::Break(_) | ControlFlow::Continue(_) => EXIT_SUCCESS,
+        }
+    }
+}
+
+impl Display for ControlFlow {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            ControlFlow::Return(_) => write!(fmt, "return: can only `return` from a function"),
+            ControlFlow::Exit(status) => write!(fmt, "exit requested with {:?}", status),
+            ControlFlow::Break(_) | ControlFlow::Continue(_) => {
+                write!(fmt, "only meaningful in a `for`, `while`, or `until` loop")
+            }
+        }
+    }
+}
+
+/// An error raised when functions are nested more deeply than allowed,
+/// e.g. by a function which endlessly calls itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("maximum function nesting level exceeded ({max_depth})")]
+pub struct StackOverflowError {
+    /// The maximum nesting depth which was exceeded.
+    pub max_depth: usize,
+}
+
+/// An error raised when compound commands or parameter substitutions are
+/// nested more deeply than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("maximum command nesting level exceeded ({max_depth})")]
+pub struct NestingLimitError {
+    /// The maximum nesting depth which was exceeded.
+    pub max_depth: usize,
+}
+
 /// An error which may arise during parameter expansion.
 #[derive(PartialEq, Eq, Clone, Debug, thiserror::Error)]
 pub enum ExpansionError {
@@ -45,6 +147,21 @@ pub enum ExpansionError {
     /// Attempted to evaluate a null or unset parameter, i.e. `${var:?msg}`.
     #[error("{0}: {1}")]
     EmptyParameter(String /* var */, String /* msg */),
+    /// Attempted to expand an unset parameter while the `nounset` option
+    /// was enabled (e.g. via `set -u`).
+    #[error("{0}: unbound variable")]
+    UnsetParameter(String),
+    /// A pathname pattern did not match any paths while the `failglob`
+    /// option was enabled.
+    #[error("no match: {0}")]
+    NoGlobMatch(String),
+    /// Attempted to assign a variable which was marked as readonly.
+    #[error("{0}: readonly variable")]
+    ReadonlyVariable(String),
+    /// The value assigned to an integer variable was not a valid arithmetic
+    /// expression.
+    #[error("{0}: syntax error in expression")]
+    ArithSyntax(String),
 }
 
 impl IsFatalError for ExpansionError {
@@ -54,7 +171,11 @@ impl IsFatalError for ExpansionError {
             ExpansionError::DivideByZero
             | ExpansionError::NegativeExponent
             | ExpansionError::BadAssig(_)
-            | ExpansionError::EmptyParameter(_, _) => true,
+            | ExpansionError::EmptyParameter(_, _)
+            | ExpansionError::UnsetParameter(_)
+            | ExpansionError::NoGlobMatch(_)
+            | ExpansionError::ReadonlyVariable(_)
+            | ExpansionError::ArithSyntax(_) => true,
         }
     }
 }
@@ -69,8 +190,14 @@ pub enum RedirectionError {
     /// Attempted to duplicate a file descriptor with Read/Write
     /// access that differs from the original.
     BadFdPerms(Fd, Permissions /* new perms */),
+    /// A variable (e.g. of a `{var}>file` redirect) could not be used to
+    /// store or look up a file descriptor.
+    BadFdVar(String),
+    /// Attempted to overwrite an existing file while the `noclobber`
+    /// option was set.
+    NoClobber(String),
     /// Any I/O error returned by the OS during execution and the
-    /// file that caused the error if applicable.
+    /// file (or descriptor) that caused the error if applicable.
     Io(#[source] IoError, Option<String>),
 }
 
@@ -83,6 +210,8 @@ impl PartialEq for RedirectionError {
             (&Io(ref e1, ref a), &Io(ref e2, ref b)) => e1.kind() == e2.kind() && a == b,
             (&Ambiguous(ref a), &Ambiguous(ref b)) => a == b,
             (&BadFdSrc(ref a), &BadFdSrc(ref b)) => a == b,
+            (&BadFdVar(ref a), &BadFdVar(ref b)) => a == b,
+            (&NoClobber(ref a), &NoClobber(ref b)) => a == b,
             (&BadFdPerms(fd_a, perms_a), &BadFdPerms(fd_b, perms_b)) => {
                 fd_a == fd_b && perms_a == perms_b
             }
@@ -120,8 +249,17 @@ impl Display for RedirectionError {
                 )
             }
 
+            RedirectionError::BadFdVar(ref var) => {
+                let description = "unable to store or look up a file descriptor in a variable";
+                write!(fmt, "{}: {}", description, var)
+            }
+
+            RedirectionError::NoClobber(ref path) => {
+                write!(fmt, "cannot overwrite existing file: {}", path)
+            }
+
             RedirectionError::Io(ref e, None) => write!(fmt, "{}", e),
-            RedirectionError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            RedirectionError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", path, e),
         }
     }
 }
@@ -132,6 +270,8 @@ impl IsFatalError for RedirectionError {
             RedirectionError::Ambiguous(_)
             | RedirectionError::BadFdSrc(_)
             | RedirectionError::BadFdPerms(_, _)
+            | RedirectionError::BadFdVar(_)
+            | RedirectionError::NoClobber(_)
             | RedirectionError::Io(_, _) => false,
         }
     }
@@ -145,8 +285,13 @@ pub enum CommandError {
     /// Utility or script does not have executable permissions.
     NotExecutable(String),
     /// Any I/O error returned by the OS during execution and the
-    /// file that caused the error if applicable.
+    /// command (or file) that caused the error if applicable.
     Io(#[source] IoError, Option<String>),
+    /// The command did not finish within the allotted duration.
+    Timeout(Duration),
+    /// The combined size of the command's arguments and environment (in bytes)
+    /// exceeds the maximum size allowed by the OS or environment.
+    ArgListTooLong(String, usize /* size */, usize /* limit */),
 }
 
 impl Eq for CommandError {}
@@ -158,6 +303,10 @@ impl PartialEq for CommandError {
             (&NotFound(ref a), &NotFound(ref b))
             | (&NotExecutable(ref a), &NotExecutable(ref b)) => a == b,
             (&Io(ref e1, ref a), &Io(ref e2, ref b)) => e1.kind() == e2.kind() && a == b,
+            (&Timeout(a), &Timeout(b)) => a == b,
+            (&ArgListTooLong(ref a, size_a, limit_a), &ArgListTooLong(ref b, size_b, limit_b)) => {
+                a == b && size_a == size_b && limit_a == limit_b
+            }
             _ => false,
         }
     }
@@ -169,7 +318,13 @@ impl Display for CommandError {
             CommandError::NotFound(ref c) => write!(fmt, "{}: command not found", c),
             CommandError::NotExecutable(ref c) => write!(fmt, "{}: command not executable", c),
             CommandError::Io(ref e, None) => write!(fmt, "{}", e),
-            CommandError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            CommandError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", path, e),
+            CommandError::Timeout(d) => write!(fmt, "command timed out after {:?}", d),
+            CommandError::ArgListTooLong(ref c, size, limit) => write!(
+                fmt,
+                "{}: argument list too long ({} bytes, limit is {} bytes)",
+                c, size, limit
+            ),
         }
     }
 }
@@ -177,27 +332,46 @@ impl Display for CommandError {
 impl IsFatalError for CommandError {
     fn is_fatal(&self) -> bool {
         match *self {
-            CommandError::NotFound(_) | CommandError::NotExecutable(_) | CommandError::Io(_, _) => {
-                false
-            }
+            CommandError::NotFound(_)
+            | CommandError::NotExecutable(_)
+            | CommandError::Io(_, _)
+            | CommandError::Timeout(_)
+            | CommandError::ArgListTooLong(_, _, _) => false,
         }
     }
 }
 
 /// An error which may arise while executing commands.
-#[derive(Debug, thiserror::Error)]
+///
+/// Errors wrapping an `ExpansionError`, `RedirectionError`, or `CommandError`
+/// are transparent: they are displayed exactly like the wrapped error, and
+/// their `source` is the wrapped error's `source` (e.g. the underlying I/O
+/// error), so that walking the chain never repeats the same message.
+#[derive(Debug)]
 pub enum RuntimeError {
     /// Any I/O error returned by the OS during execution and the
     /// file that caused the error if applicable.
-    Io(#[source] IoError, Option<String>),
+    Io(IoError, Option<String>),
     /// Any error that occured during a parameter expansion.
-    Expansion(#[from] ExpansionError),
+    Expansion(ExpansionError),
     /// Any error that occured during a redirection.
-    Redirection(#[from] RedirectionError),
+    Redirection(RedirectionError),
     /// Any error that occured during a command spawning.
-    Command(#[from] CommandError),
+    Command(CommandError),
     /// Runtime feature not currently supported.
     Unimplemented(&'static str),
+    /// Execution was cancelled by the host before it could complete.
+    Cancelled,
+    /// A control flow request (e.g. `return` or `exit`) which is still
+    /// propagating to the construct which will handle it.
+    ControlFlow(ControlFlow),
+    /// Functions were nested more deeply than allowed.
+    StackOverflow(StackOverflowError),
+    /// Commands or substitutions were nested more deeply than allowed.
+    NestingLimit(NestingLimitError),
+    /// Any of the above errors, along with the location of the command which
+    /// caused it.
+    Located(SourceLocation, Box<RuntimeError>),
 }
 
 impl Eq for RuntimeError {}
@@ -211,6 +385,11 @@ impl PartialEq for RuntimeError {
             (&Redirection(ref a), &Redirection(ref b)) => a == b,
             (&Command(ref a), &Command(ref b)) => a == b,
             (&Unimplemented(a), &Unimplemented(b)) => a == b,
+            (&Cancelled, &Cancelled) => true,
+            (&ControlFlow(a), &ControlFlow(b)) => a == b,
+            (&StackOverflow(a), &StackOverflow(b)) => a == b,
+            (&NestingLimit(a), &NestingLimit(b)) => a == b,
+            (&Located(ref l1, ref e1), &Located(ref l2, ref e2)) => l1 == l2 && e1 == e2,
             _ => false,
         }
     }
@@ -223,8 +402,30 @@ impl Display for RuntimeError {
             RuntimeError::Redirection(ref e) => write!(fmt, "{}", e),
             RuntimeError::Command(ref e) => write!(fmt, "{}", e),
             RuntimeError::Unimplemented(e) => write!(fmt, "{}", e),
+            RuntimeError::Cancelled => write!(fmt, "execution cancelled"),
+            RuntimeError::ControlFlow(ref c) => write!(fmt, "{}", c),
+            RuntimeError::StackOverflow(ref e) => write!(fmt, "{}", e),
+            RuntimeError::NestingLimit(ref e) => write!(fmt, "{}", e),
             RuntimeError::Io(ref e, None) => write!(fmt, "{}", e),
-            RuntimeError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            RuntimeError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", path, e),
+            RuntimeError::Located(ref location, ref e) => write!(fmt, "{}: {}", location, e),
+        }
+    }
+}
+
+impl Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RuntimeError::Io(ref e, _) => Some(e),
+            RuntimeError::Expansion(ref e) => e.source(),
+            RuntimeError::Redirection(ref e) => e.source(),
+            RuntimeError::Command(ref e) => e.source(),
+            RuntimeError::Located(_, ref e) => Some(&**e),
+            RuntimeError::Unimplemented(_)
+            | RuntimeError::Cancelled
+            | RuntimeError::ControlFlow(_)
+            | RuntimeError::StackOverflow(_)
+            | RuntimeError::NestingLimit(_) => None,
         }
     }
 }
@@ -236,6 +437,28 @@ impl IsFatalError for RuntimeError {
             RuntimeError::Redirection(ref e) => e.is_fatal(),
             RuntimeError::Command(ref e) => e.is_fatal(),
             RuntimeError::Io(_, _) | RuntimeError::Unimplemented(_) => false,
+            RuntimeError::Cancelled
+            | RuntimeError::ControlFlow(_)
+            | RuntimeError::StackOverflow(_)
+            | RuntimeError::NestingLimit(_) => true,
+            RuntimeError::Located(_, ref e) => e.is_fatal(),
+        }
+    }
+
+    fn control_flow(&self) -> Option<ControlFlow> {
+        match *self {
+            RuntimeError::ControlFlow(c) => Some(c),
+            RuntimeError::Located(_, ref e) => e.control_flow(),
+            _ => None,
+        }
+    }
+}
+
+impl WithLocation for RuntimeError {
+    fn with_location(self, location: SourceLocation) -> Self {
+        match self {
+            RuntimeError::ControlFlow(_) | RuntimeError::Located(..) => self,
+            e => RuntimeError::Located(location, Box::new(e)),
         }
     }
 }
@@ -246,6 +469,42 @@ impl From<IoError> for RuntimeError {
     }
 }
 
+impl From<ExpansionError> for RuntimeError {
+    fn from(err: ExpansionError) -> Self {
+        RuntimeError::Expansion(err)
+    }
+}
+
+impl From<RedirectionError> for RuntimeError {
+    fn from(err: RedirectionError) -> Self {
+        RuntimeError::Redirection(err)
+    }
+}
+
+impl From<CommandError> for RuntimeError {
+    fn from(err: CommandError) -> Self {
+        RuntimeError::Command(err)
+    }
+}
+
+impl From<ControlFlow> for RuntimeError {
+    fn from(flow: ControlFlow) -> Self {
+        RuntimeError::ControlFlow(flow)
+    }
+}
+
+impl From<StackOverflowError> for RuntimeError {
+    fn from(err: StackOverflowError) -> Self {
+        RuntimeError::StackOverflow(err)
+    }
+}
+
+impl From<NestingLimitError> for RuntimeError {
+    fn from(err: NestingLimitError) -> Self {
+        RuntimeError::NestingLimit(err)
+    }
+}
+
 impl From<void::Void> for RuntimeError {
     fn from(err: void::Void) -> Self {
         void::unreachable(err)
@@ -265,4 +524,58 @@ mod tests {
         send_and_sync::<CommandError>();
         send_and_sync::<RuntimeError>();
     }
+
+    #[test]
+    fn located_errors_delegate_to_inner_error() {
+        let location = SourceLocation {
+            file: Some(Arc::from(Path::new("script.sh"))),
+            line: 3,
+        };
+
+        let err = RuntimeError::Command(CommandError::NotFound("foo".to_owned()))
+            .with_location(location.clone());
+        assert_eq!(err.to_string(), "script.sh: line 3: foo: command not found");
+        assert!(!err.is_fatal());
+
+        let err = RuntimeError::Cancelled.with_location(location.clone());
+        assert!(err.is_fatal());
+        match err {
+            RuntimeError::Located(ref l, ref e) => {
+                assert_eq!(*l, location);
+                assert_eq!(**e, RuntimeError::Cancelled);
+            }
+            ref e => panic!("unexpected error: {:?}", e),
+        }
+
+        let exit = ControlFlow::Exit(EXIT_SUCCESS);
+        let err = RuntimeError::ControlFlow(exit).with_location(location);
+        assert_eq!(err, RuntimeError::ControlFlow(exit));
+    }
+
+    #[test]
+    fn wrapped_errors_are_transparent_sources() {
+        use std::io::ErrorKind;
+
+        let io = || IoError::new(ErrorKind::NotFound, "no such file");
+
+        let err = RuntimeError::Redirection(RedirectionError::Io(io(), Some("foo".to_owned())));
+        assert_eq!(err.to_string(), "foo: no such file");
+        assert_eq!(err.source().unwrap().to_string(), "no such file");
+        assert!(err.source().unwrap().source().is_none());
+
+        let err = RuntimeError::Command(CommandError::NotFound("foo".to_owned()));
+        assert!(err.source().is_none());
+
+        let location = SourceLocation {
+            file: None,
+            line: 1,
+        };
+        let err = RuntimeError::Command(CommandError::Io(io(), Some("foo".to_owned())))
+            .with_location(location);
+        assert_eq!(err.to_string(), "line 1: foo: no such file");
+
+        let inner = err.source().unwrap();
+        assert_eq!(inner.to_string(), "foo: no such file");
+        assert_eq!(inner.source().unwrap().to_string(), "no such file");
+    }
 }
@@ -9,25 +9,43 @@ mod assignment;
 mod concat;
 mod double_quoted;
 mod fields;
+mod glob_walk;
 mod param_subst;
+mod pattern;
+mod pattern_cache;
 mod redirect;
 mod redirect_or_cmd_word;
 mod redirect_or_var_assig;
 
+#[cfg(feature = "conch-parser")]
+mod arith;
 #[cfg(feature = "conch-parser")]
 pub mod ast_impl;
 
-pub use self::assignment::eval_as_assignment;
+#[cfg(feature = "conch-parser")]
+pub use self::arith::{eval_arith_str, parse_arith_str, ArithStrError};
+
+pub use self::assignment::{assignable_value, eval_as_assignment};
 pub use self::concat::concat;
+#[cfg(feature = "conch-parser")]
+pub(crate) use self::concat::concat_pattern_fields;
 pub use self::double_quoted::double_quoted;
-pub use self::fields::Fields;
+pub use self::fields::{ifs_join_separator, Fields};
+pub use self::glob_walk::{
+    expand_glob, glob_field, glob_paths, glob_word, GlobConfig, NoMatchBehavior,
+};
 pub use self::param_subst::{alternative, assign, default, error, len};
 pub use self::param_subst::{
-    remove_largest_prefix, remove_largest_suffix, remove_smallest_prefix, remove_smallest_suffix,
+    remove_largest_prefix, remove_largest_prefix_with_config, remove_largest_suffix,
+    remove_largest_suffix_with_config, remove_smallest_prefix, remove_smallest_prefix_with_config,
+    remove_smallest_suffix, remove_smallest_suffix_with_config, PatternMatchConfig,
 };
+pub(crate) use self::pattern::escape_pattern;
 pub use self::redirect::{
-    redirect_append, redirect_clobber, redirect_dup_read, redirect_dup_write, redirect_heredoc,
-    redirect_read, redirect_readwrite, redirect_write, RedirectAction, RedirectEval,
+    redirect_append, redirect_append_all, redirect_clobber, redirect_dup_read, redirect_dup_write,
+    redirect_dup_write_or_all, redirect_fd_var, redirect_heredoc, redirect_herestring,
+    redirect_read, redirect_readwrite, redirect_write, redirect_write_all, RedirectAction,
+    RedirectEval,
 };
 pub use self::redirect_or_cmd_word::{
     eval_redirects_or_cmd_words_with_restorer, EvalRedirectOrCmdWordError, RedirectOrCmdWord,
@@ -102,6 +120,10 @@ pub struct WordEvalConfig {
 /// A convenience trait representing the result of a word evaluation.
 pub type WordEvalResult<T, E> = Result<BoxFuture<'static, Fields<T>>, E>;
 
+/// A convenience trait representing the result of evaluating a word's fields
+/// along with their sources as patterns (see `WordEval::eval_pattern_fields`).
+pub type PatternFieldsResult<T, E> = Result<Vec<(T, String)>, E>;
+
 /// A trait for evaluating shell words with various rules for expansion.
 pub trait WordEval<E: ?Sized> {
     /// The underlying representation of the evaulation type (e.g. `String`, `Arc<String>`).
@@ -152,6 +174,52 @@ pub trait WordEval<E: ?Sized> {
         'life0: 'async_trait,
         'life1: 'async_trait,
         Self: 'async_trait;
+
+    /// Evaluates a word for use as a pattern (e.g. of a `case` arm), and
+    /// returns the source of the pattern to be compiled.
+    ///
+    /// Any quoted portions of the word should be escaped such that they only
+    /// ever match literally. By default, no portion of the word is considered
+    /// quoted, and all evaluated fields are joined with a space.
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        join_pattern(self.eval_with_config(env, cfg), false)
+    }
+
+    /// Evaluates a word like `eval_with_config`, but pairs each resulting
+    /// field with its source as a pattern (e.g. for pathname expansion).
+    ///
+    /// Any quoted portions of a field should be escaped such that they only
+    /// ever match literally. By default, no portion of the word is considered
+    /// quoted.
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        pattern_fields(self.eval_with_config(env, cfg), false)
+    }
+
+    /// Returns the source of the word if it consists of a single unquoted
+    /// literal, which evaluates to itself without any expansions (e.g. `ls`,
+    /// but not `"ls"`, `\\ls`, or `$cmd`), such as is considered by alias
+    /// expansion. By default, no word is considered literal.
+    fn as_literal(&self) -> Option<&str> {
+        None
+    }
 }
 
 impl<'a, T, E> WordEval<E> for &'a T
@@ -186,6 +254,36 @@ where
     {
         (**self).eval_with_config(env, cfg)
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern(env, cfg)
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern_fields(env, cfg)
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        (**self).as_literal()
+    }
 }
 
 impl<T, E> WordEval<E> for Box<T>
@@ -220,6 +318,36 @@ where
     {
         (**self).eval_with_config(env, cfg)
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern(env, cfg)
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern_fields(env, cfg)
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        (**self).as_literal()
+    }
 }
 
 impl<T, E> WordEval<E> for std::sync::Arc<T>
@@ -254,37 +382,107 @@ where
     {
         (**self).eval_with_config(env, cfg)
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern(env, cfg)
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        (**self).eval_pattern_fields(env, cfg)
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        (**self).as_literal()
+    }
 }
 
 // Evaluate a word as a pattern. Note this is not a public API since there needs to be a
 // better abstraction for allowing consumers to override/define patterns (i.e. don't
 // tie ourselves to `glob`).
-pub(crate) async fn eval_as_pattern<W, E>(word: W, env: &mut E) -> Result<glob::Pattern, W::Error>
+pub(crate) async fn eval_as_pattern<W, E>(
+    word: W,
+    env: &mut E,
+    extglob: bool,
+) -> Result<std::sync::Arc<pattern::Pattern>, W::Error>
 where
     W: WordEval<E>,
     E: ?Sized,
 {
-    let future = word.eval_with_config(
-        env,
-        WordEvalConfig {
-            tilde_expansion: TildeExpansion::First,
-            split_fields_further: false,
-        },
-    );
-
-    // FIXME: "intelligently" compile the pattern here
-    // Other shells will treat certain glob "errors" (like unmatched char groups)
-    // as just literal values. Also it would be interesting to explore treating
-    // variables/interpolated values as literals unconditionally (i.e. glob
-    // special chars like *, !, ?, etc. would only have special meaning if they
-    // appear in the original source). Unfortunately, this future doesn't appear
-    // flexible enough to accomplish that (the actual word itself needs to
-    // determine what is special and what isn't at each step), so this may
-    // need to move into its own trait (right now WordEval *must* return a
-    // Pattern future).
-    let pat = future.await?.await.join();
-    let pat = glob::Pattern::new(pat.as_str())
-        .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(pat.as_str())))
-        .expect("pattern compilation unexpectedly failed");
-    Ok(pat)
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::First,
+        split_fields_further: false,
+    };
+
+    // NB: other shells will treat certain glob "errors" (like unmatched char groups)
+    // as just literal values, which is what the compiler falls back to as well.
+    let pat = word.eval_pattern(env, cfg).await?;
+    Ok(pattern_cache::compile_pattern(&pat, extglob))
+}
+
+/// Joins the fields of an evaluated word into the source of a pattern,
+/// optionally escaping them such that they only match literally.
+pub(crate) fn join_pattern<'a, T, E>(
+    future: BoxFuture<'a, WordEvalResult<T, E>>,
+    escape: bool,
+) -> BoxFuture<'a, Result<String, E>>
+where
+    T: 'a + StringWrapper,
+    E: 'a,
+{
+    Box::pin(async move {
+        let fields = future.await?;
+        let pat = fields.await.join();
+        if escape {
+            Ok(escape_pattern(pat.as_str()))
+        } else {
+            Ok(pat.into_owned())
+        }
+    })
+}
+
+/// Pairs each field of an evaluated word with its source as a pattern,
+/// optionally escaping them such that they only match literally.
+pub(crate) fn pattern_fields<'a, T, E>(
+    future: BoxFuture<'a, WordEvalResult<T, E>>,
+    escape: bool,
+) -> BoxFuture<'a, PatternFieldsResult<T, E>>
+where
+    T: 'a + StringWrapper,
+    E: 'a,
+{
+    Box::pin(async move {
+        let future = future.await?;
+        let fields = future
+            .await
+            .into_iter()
+            .map(|field| {
+                let pat = if escape {
+                    escape_pattern(field.as_str())
+                } else {
+                    field.as_str().to_owned()
+                };
+                (field, pat)
+            })
+            .collect();
+
+        Ok(fields)
+    })
 }
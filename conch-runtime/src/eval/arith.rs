@@ -0,0 +1,63 @@
+use crate::env::{DynamicVariableEnvironment, StrKey, VariableEnvironment};
+use crate::error::ExpansionError;
+use crate::eval::ArithEval;
+use conch_parser::ast::builder::DefaultBuilder;
+use conch_parser::ast::DefaultArithmetic;
+use conch_parser::lexer::Lexer;
+use conch_parser::parse::Parser;
+use std::borrow::Borrow;
+
+/// An error which may arise when evaluating an arithmetic expression given
+/// as a string.
+#[derive(PartialEq, Eq, Clone, Debug, thiserror::Error)]
+pub enum ArithStrError {
+    /// The source of the expression could not be parsed.
+    #[error("{0}: syntax error in expression")]
+    Syntax(String),
+    /// The expression could not be evaluated.
+    #[error(transparent)]
+    Expansion(#[from] ExpansionError),
+}
+
+impl From<ArithStrError> for ExpansionError {
+    fn from(err: ArithStrError) -> Self {
+        match err {
+            ArithStrError::Syntax(src) => ExpansionError::ArithSyntax(src),
+            ArithStrError::Expansion(err) => err,
+        }
+    }
+}
+
+/// Parses an arithmetic expression, e.g. the value assigned to an integer
+/// variable, using the same grammar as arithmetic substitutions (`$(( ))`).
+///
+/// An empty (or whitespace-only) source is treated as the literal `0`.
+pub fn parse_arith_str(src: &str) -> Result<DefaultArithmetic, ArithStrError> {
+    if src.trim().is_empty() {
+        return Ok(DefaultArithmetic::Literal(0));
+    }
+
+    let syntax_error = || ArithStrError::Syntax(src.to_owned());
+    let mut parser = Parser::<_, DefaultBuilder<String>>::new(Lexer::new(src.chars()));
+    let expr = parser
+        .arithmetic_substitution()
+        .map_err(|_| syntax_error())?;
+
+    // Make sure the entire source was consumed by the expression
+    match parser.complete_command() {
+        Ok(None) => Ok(expr),
+        Ok(Some(_)) | Err(_) => Err(syntax_error()),
+    }
+}
+
+/// Parses and evaluates an arithmetic expression in the context of an
+/// environment, as if it was the body of an arithmetic substitution.
+pub fn eval_arith_str<E>(src: &str, env: &mut E) -> Result<isize, ArithStrError>
+where
+    E: ?Sized + DynamicVariableEnvironment + VariableEnvironment,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let expr = parse_arith_str(src)?;
+    Ok(expr.eval(env)?)
+}
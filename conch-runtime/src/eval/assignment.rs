@@ -1,4 +1,7 @@
-use crate::env::VariableEnvironment;
+use crate::env::{
+    DynamicVariableEnvironment, StrKey, VariableAttributesEnvironment, VariableEnvironment,
+};
+use crate::error::ExpansionError;
 use crate::eval::{Fields, TildeExpansion, WordEval, WordEvalConfig};
 use std::borrow::Borrow;
 
@@ -6,12 +9,14 @@ use std::borrow::Borrow;
 ///
 /// Tilde, parameter, command substitution, arithmetic expansions, and quote removals
 /// will be performed, however. In addition, if multiple fields arise as a result
-/// of evaluating `$@` or `$*`, the fields will be joined with a single space.
+/// of evaluating `$*`, they will be joined in the same way as within double quotes
+/// (see `Fields::join_with_ifs`), while any other fields (e.g. those of `$@`) will
+/// be joined with a single space.
 pub async fn eval_as_assignment<W, E>(word: W, env: &mut E) -> Result<W::EvalResult, W::Error>
 where
     W: WordEval<E>,
     E: ?Sized + VariableEnvironment,
-    E::VarName: Borrow<String>,
+    E::VarName: StrKey,
     E::Var: Borrow<String>,
 {
     let future = word.eval_with_config(
@@ -31,3 +36,42 @@ where
 
     Ok(ret)
 }
+
+/// Prepares a value to be assigned to the variable `name` (e.g. via
+/// `name=value`) according to the variable's attributes.
+///
+/// Attempting to assign a readonly variable results in an error. The values
+/// of integer variables are evaluated as arithmetic expressions (see
+/// `eval_arith_str`), which may assign other variables as a side effect, and
+/// results in an error if the expression is invalid. Integer variables are
+/// only supported with the `conch-parser` feature enabled.
+///
+/// Assigning a dynamic variable (e.g. `$SECONDS` or `$RANDOM`) also notifies
+/// the environment of its new value (see `set_dynamic_var`).
+pub fn assignable_value<E>(
+    name: &E::VarName,
+    value: E::Var,
+    env: &mut E,
+) -> Result<E::Var, ExpansionError>
+where
+    E: ?Sized + DynamicVariableEnvironment + VariableAttributesEnvironment,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let attrs = env.var_attributes(name);
+    if attrs.readonly {
+        let name = name.as_key_str().to_owned();
+        return Err(ExpansionError::ReadonlyVariable(name));
+    }
+
+    #[cfg(feature = "conch-parser")]
+    let value = if attrs.integer {
+        let value = crate::eval::eval_arith_str(value.borrow(), env)?;
+        E::Var::from(value.to_string())
+    } else {
+        value
+    };
+
+    env.set_dynamic_var(name.as_key_str(), value.borrow());
+    Ok(value)
+}
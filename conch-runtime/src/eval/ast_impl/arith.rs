@@ -1,4 +1,4 @@
-use crate::env::VariableEnvironment;
+use crate::env::{DynamicVariableEnvironment, StrKey, VariableEnvironment};
 use crate::error::ExpansionError;
 use crate::eval::ArithEval;
 use conch_parser::ast::Arithmetic;
@@ -8,17 +8,26 @@ use std::borrow::Borrow;
 impl<T, E: ?Sized> ArithEval<E> for Arithmetic<T>
 where
     T: Borrow<String> + Clone,
-    E: VariableEnvironment,
-    E::VarName: Borrow<String> + From<T>,
+    E: DynamicVariableEnvironment + VariableEnvironment,
+    E::VarName: StrKey + From<T>,
     E::Var: Borrow<String> + From<String>,
 {
     fn eval(&self, env: &mut E) -> Result<isize, ExpansionError> {
         // FIXME: interesting observation: bash and zsh seem to recursively expand vars to other vars
         // FIXME: e.g. x=3, y=x, z=y, $(( $z *= 5 ))
         let get_var = |env: &E, var: &T| {
-            env.var(var.borrow())
-                .and_then(|s| s.borrow().as_str().parse().ok())
-                .unwrap_or(0)
+            let var: &String = var.borrow();
+            match env.dynamic_var(var) {
+                Some(value) => value.parse().ok(),
+                None => E::VarName::lookup(env, var).and_then(|s| s.borrow().as_str().parse().ok()),
+            }
+            .unwrap_or(0)
+        };
+
+        let set_var = |env: &mut E, var: &T, value: isize| {
+            let value = value.to_string();
+            env.set_dynamic_var(var.borrow(), &value);
+            env.set_var(var.clone().into(), value.into());
         };
 
         let ret = match *self {
@@ -27,25 +36,25 @@ where
 
             PostIncr(ref var) => {
                 let value = get_var(env, var);
-                env.set_var(var.clone().into(), (value + 1).to_string().into());
+                set_var(env, var, value + 1);
                 value
             }
 
             PostDecr(ref var) => {
                 let value = get_var(env, var);
-                env.set_var(var.clone().into(), (value - 1).to_string().into());
+                set_var(env, var, value - 1);
                 value
             }
 
             PreIncr(ref var) => {
                 let value = get_var(env, var) + 1;
-                env.set_var(var.clone().into(), value.to_string().into());
+                set_var(env, var, value);
                 value
             }
 
             PreDecr(ref var) => {
                 let value = get_var(env, var) - 1;
-                env.set_var(var.clone().into(), value.to_string().into());
+                set_var(env, var, value);
                 value
             }
 
@@ -173,7 +182,7 @@ where
 
             Assign(ref var, ref value) => {
                 let value = value.eval(env)?;
-                env.set_var(var.clone().into(), value.to_string().into());
+                set_var(env, var, value);
                 value
             }
 
@@ -1,4 +1,7 @@
-use crate::eval::{concat, WordEval, WordEvalConfig, WordEvalResult};
+use crate::eval::{
+    concat, concat_pattern_fields, PatternFieldsResult, TildeExpansion, WordEval, WordEvalConfig,
+    WordEvalResult,
+};
 use conch_parser::ast::ComplexWord;
 use futures_core::future::BoxFuture;
 
@@ -26,4 +29,54 @@ where
             ComplexWord::Concat(words) => Box::pin(concat(words, env, cfg)),
         }
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        match self {
+            ComplexWord::Single(w) => w.eval_pattern(env, cfg),
+            ComplexWord::Concat(words) => Box::pin(async move {
+                let mut pat = String::new();
+                let mut cfg = cfg;
+
+                for word in words {
+                    pat.push_str(&word.eval_pattern(env, cfg).await?);
+                    // Like with concat, only the first word is subject to tilde expansion
+                    cfg.tilde_expansion = TildeExpansion::None;
+                }
+
+                Ok(pat)
+            }),
+        }
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        match self {
+            ComplexWord::Single(w) => w.eval_pattern_fields(env, cfg),
+            ComplexWord::Concat(words) => Box::pin(concat_pattern_fields(words, env, cfg)),
+        }
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        match self {
+            ComplexWord::Single(w) => w.as_literal(),
+            ComplexWord::Concat(_) => None,
+        }
+    }
 }
@@ -1,16 +1,18 @@
 use crate::env::{
-    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment,
-    LastStatusEnvironment, ReportErrorEnvironment, SubEnvironment, VariableEnvironment,
+    AsyncIoEnvironment, ControlFlowEnvironment, FileDescEnvironment, IsInteractiveEnvironment,
+    LastStatusEnvironment, NestingEnvironment, NestingGuard, ReportErrorEnvironment,
+    ShellOptionsEnvironment, StrKey, SubEnvironment, TempFileEnvironment, VariableEnvironment,
 };
-use crate::error::{ExpansionError, IsFatalError};
+use crate::error::{ExpansionError, IsFatalError, NestingLimitError};
 use crate::eval::{
     alternative, assign, default, error, len, remove_largest_prefix, remove_largest_suffix,
-    remove_smallest_prefix, remove_smallest_suffix, ArithEval, Fields, ParamEval, WordEval,
-    WordEvalConfig, WordEvalResult,
+    remove_smallest_prefix, remove_smallest_suffix, ArithEval, Fields, ParamEval, TildeExpansion,
+    WordEval, WordEvalConfig, WordEvalResult,
 };
 use crate::spawn::{sequence_slice, substitution, Spawn};
 use conch_parser::ast;
 use conch_parser::ast::ParameterSubstitution::*;
+use futures_core::future::BoxFuture;
 use std::fmt;
 use std::io::Error as IoError;
 
@@ -19,19 +21,22 @@ impl<P, W, C, A, E> WordEval<E> for ast::ParameterSubstitution<P, W, C, A>
 where
     P: Send + Sync + ParamEval<E, EvalResult = W::EvalResult> + fmt::Display,
     W: Send + Sync + WordEval<E>,
-    W::EvalResult: 'static + Send,
-    W::Error: Send + From<ExpansionError> + From<C::Error>,
+    W::EvalResult: 'static + Send + StrKey,
+    W::Error: Send + From<ExpansionError> + From<NestingLimitError> + From<C::Error>,
     C: Send + Sync + Spawn<E>,
     C::Error: IsFatalError + From<IoError>,
     A: Send + Sync + ArithEval<E>,
     E: Send
         + Sync
         + AsyncIoEnvironment
+        + ControlFlowEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + IsInteractiveEnvironment
         + LastStatusEnvironment
+        + NestingEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment
         + VariableEnvironment<VarName = W::EvalResult, Var = W::EvalResult>,
     E::FileHandle: Send + From<E::OpenedFileHandle>,
@@ -46,38 +51,17 @@ where
     ///
     /// Note: even if the caller specifies no splitting should be done,
     /// multiple fields can occur if `$@` or `$*` is evaluated.
+    ///
+    /// An error is returned (without evaluating anything) if the environment's
+    /// maximum nesting depth would be exceeded.
     async fn eval_with_config(
         &self,
         env: &mut E,
         cfg: WordEvalConfig,
     ) -> WordEvalResult<Self::EvalResult, W::Error> {
-        let te = cfg.tilde_expansion;
-
-        let fields = match self {
-            Command(body) => {
-                let ret = substitution(sequence_slice(body), env).await?;
-                Fields::Single(W::EvalResult::from(ret))
-            }
-            Len(ref p) => Fields::Single(len(p, env)),
-
-            Arith(a) => {
-                let ret = match a.as_ref() {
-                    Some(a) => a.eval(env)?,
-                    None => 0,
-                };
-
-                Fields::Single(W::EvalResult::from(ret.to_string()))
-            }
-
-            Default(strict, p, def) => default(*strict, p, def.as_ref(), env, te).await?,
-            Assign(strict, p, assig) => assign(*strict, p, assig.as_ref(), env, te).await?,
-            Error(strict, p, msg) => error(*strict, p, msg.as_ref(), env, te).await?,
-            Alternative(strict, p, al) => alternative(*strict, p, al.as_ref(), env, te).await?,
-            RemoveSmallestSuffix(p, pat) => remove_smallest_suffix(p, pat.as_ref(), env).await?,
-            RemoveLargestSuffix(p, pat) => remove_largest_suffix(p, pat.as_ref(), env).await?,
-            RemoveSmallestPrefix(p, pat) => remove_smallest_prefix(p, pat.as_ref(), env).await?,
-            RemoveLargestPrefix(p, pat) => remove_largest_prefix(p, pat.as_ref(), env).await?,
-        };
+        let mut guard = NestingGuard::enter(env).map_err(W::Error::from)?;
+        let env = guard.get_mut();
+        let fields = eval_fields(self, env, cfg.tilde_expansion).await?;
 
         let ret = if cfg.split_fields_further {
             fields.split(env)
@@ -88,3 +72,62 @@ where
         Ok(Box::pin(async move { ret }))
     }
 }
+
+// NB: each kind of substitution is evaluated by its own boxed future (rather
+// than being part of a single `async` block) so that evaluating a deeply
+// nested word only reserves stack space for the substitutions actually used.
+fn eval_fields<'a, P, W, C, A, E>(
+    subst: &'a ast::ParameterSubstitution<P, W, C, A>,
+    env: &'a mut E,
+    te: TildeExpansion,
+) -> BoxFuture<'a, Result<Fields<W::EvalResult>, W::Error>>
+where
+    P: Send + Sync + ParamEval<E, EvalResult = W::EvalResult> + fmt::Display,
+    W: Send + Sync + WordEval<E>,
+    W::EvalResult: 'static + Send + StrKey,
+    W::Error: Send + From<ExpansionError> + From<C::Error>,
+    C: Send + Sync + Spawn<E>,
+    C::Error: IsFatalError + From<IoError>,
+    A: Send + Sync + ArithEval<E>,
+    E: Send
+        + Sync
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + IsInteractiveEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment
+        + VariableEnvironment<VarName = W::EvalResult, Var = W::EvalResult>,
+    E::FileHandle: Send + From<E::OpenedFileHandle>,
+    E::OpenedFileHandle: Send,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    match subst {
+        Command(body) => Box::pin(async move {
+            let ret = substitution(sequence_slice(body), env).await?;
+            Ok(Fields::Single(W::EvalResult::from(ret)))
+        }),
+        Len(ref p) => Box::pin(async move { Ok(Fields::Single(len(p, env))) }),
+
+        Arith(a) => Box::pin(async move {
+            let ret = match a.as_ref() {
+                Some(a) => a.eval(env)?,
+                None => 0,
+            };
+
+            Ok(Fields::Single(W::EvalResult::from(ret.to_string())))
+        }),
+
+        Default(strict, p, def) => Box::pin(default(*strict, p, def.as_ref(), env, te)),
+        Assign(strict, p, assig) => Box::pin(assign(*strict, p, assig.as_ref(), env, te)),
+        Error(strict, p, msg) => Box::pin(error(*strict, p, msg.as_ref(), env, te)),
+        Alternative(strict, p, al) => Box::pin(alternative(*strict, p, al.as_ref(), env, te)),
+        RemoveSmallestSuffix(p, pat) => Box::pin(remove_smallest_suffix(p, pat.as_ref(), env)),
+        RemoveLargestSuffix(p, pat) => Box::pin(remove_largest_suffix(p, pat.as_ref(), env)),
+        RemoveSmallestPrefix(p, pat) => Box::pin(remove_smallest_prefix(p, pat.as_ref(), env)),
+        RemoveLargestPrefix(p, pat) => Box::pin(remove_largest_prefix(p, pat.as_ref(), env)),
+    }
+}
@@ -1,17 +1,25 @@
-use crate::env::{ArgumentsEnvironment, LastStatusEnvironment, StringWrapper, VariableEnvironment};
+use crate::env::{
+    ArgumentsEnvironment, DynamicVariableEnvironment, LastStatusEnvironment, ProcessIdEnvironment,
+    ShellOption, ShellOptionsEnvironment, StrKey, StringWrapper, VariableEnvironment,
+};
 use crate::eval::{Fields, ParamEval};
-use crate::io::getpid;
 use crate::ExitStatus;
 use conch_parser::ast::Parameter;
-use std::borrow::Borrow;
 
 const EXIT_SIGNAL_OFFSET: u32 = 128;
+const BASHPID: &str = "BASHPID";
+const PPID: &str = "PPID";
 
 impl<T, E: ?Sized> ParamEval<E> for Parameter<T>
 where
     T: StringWrapper,
-    E: ArgumentsEnvironment<Arg = T> + LastStatusEnvironment + VariableEnvironment<Var = T>,
-    E::VarName: Borrow<String>,
+    E: ArgumentsEnvironment<Arg = T>
+        + DynamicVariableEnvironment
+        + LastStatusEnvironment
+        + ProcessIdEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment<Var = T>,
+    E::VarName: StrKey,
 {
     type EvalResult = T;
 
@@ -26,22 +34,39 @@ where
         };
 
         let ret = match *self {
-            Parameter::At   => Some(get_args().map_or(Fields::Zero, Fields::At)),
+            Parameter::At => Some(get_args().map_or(Fields::Zero, Fields::At)),
             Parameter::Star => Some(get_args().map_or(Fields::Zero, Fields::Star)),
 
-            Parameter::Pound  => Some(Fields::Single(env.args_len().to_string().into())),
-            Parameter::Dollar => Some(Fields::Single(getpid().to_string().into())),
-            Parameter::Dash   |        // FIXME: implement properly
-            Parameter::Bang   => None, // FIXME: eventual job control would be nice
+            Parameter::Pound => Some(Fields::Single(env.args_len().to_string().into())),
+            Parameter::Dollar => Some(Fields::Single(env.shell_pid().to_string().into())),
+            Parameter::Dash => Some(Fields::Single(option_flags(env).into())),
+            Parameter::Bang => None, // FIXME: eventual job control would be nice
 
-            Parameter::Question => Some(Fields::Single(match env.last_status() {
-                ExitStatus::Code(c)   => c as u32,
-                ExitStatus::Signal(c) => c as u32 + EXIT_SIGNAL_OFFSET,
-            }.to_string().into())),
+            Parameter::Question => Some(Fields::Single(
+                match env.last_status() {
+                    ExitStatus::Code(c) => c as u32,
+                    ExitStatus::Signal(c) | ExitStatus::CoreDumped(c) => {
+                        c as u32 + EXIT_SIGNAL_OFFSET
+                    }
+                }
+                .to_string()
+                .into(),
+            )),
 
             Parameter::Positional(0) => Some(Fields::Single(env.name().clone())),
             Parameter::Positional(p) => env.arg(p as usize).cloned().map(Fields::Single),
-            Parameter::Var(ref var)  => env.var(var.borrow()).cloned().map(Fields::Single),
+            Parameter::Var(ref var) if var.as_str() == BASHPID => {
+                Some(Fields::Single(env.subshell_pid().to_string().into()))
+            }
+            Parameter::Var(ref var) if var.as_str() == PPID => {
+                Some(Fields::Single(env.parent_pid().to_string().into()))
+            }
+            Parameter::Var(ref var) => match env.dynamic_var(var.as_str()) {
+                Some(value) => Some(Fields::Single(value.into())),
+                None => E::VarName::lookup(env, var.as_str())
+                    .cloned()
+                    .map(Fields::Single),
+            },
         };
 
         ret.map(|f| {
@@ -67,3 +92,12 @@ where
         }
     }
 }
+
+/// The flags of all currently enabled options (which have one), like `$-`.
+fn option_flags<E: ?Sized + ShellOptionsEnvironment>(env: &E) -> String {
+    ShellOption::ALL
+        .iter()
+        .filter(|&&opt| env.is_option_enabled(opt))
+        .filter_map(ShellOption::flag)
+        .collect()
+}
@@ -1,6 +1,6 @@
 use crate::env::{
     AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment,
-    WorkingDirectoryEnvironment,
+    ShellOptionsEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::error::RedirectionError;
 use crate::eval::{
@@ -20,6 +20,7 @@ where
         + FileDescEnvironment
         + FileDescOpener
         + IsInteractiveEnvironment
+        + ShellOptionsEnvironment
         + WorkingDirectoryEnvironment,
     E::FileHandle: Clone + From<E::OpenedFileHandle>,
     E::IoHandle: From<E::FileHandle>,
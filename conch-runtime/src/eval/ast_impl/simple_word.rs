@@ -1,27 +1,40 @@
-use crate::env::{StringWrapper, VariableEnvironment};
-use crate::eval::{Fields, ParamEval, TildeExpansion, WordEval, WordEvalConfig, WordEvalResult};
+use crate::env::{
+    ShellOption, ShellOptionsEnvironment, StrKey, StringWrapper, VariableEnvironment,
+};
+use crate::error::ExpansionError;
+use crate::eval::{
+    escape_pattern, join_pattern, pattern_fields, Fields, ParamEval, TildeExpansion, WordEval,
+    WordEvalConfig, WordEvalResult,
+};
 use crate::HOME;
 use conch_parser::ast::SimpleWord;
 use conch_parser::ast::SimpleWord::*;
-use std::borrow::Borrow;
+use futures_core::future::BoxFuture;
+use std::fmt;
 
 #[async_trait::async_trait]
 impl<T, P, S, E> WordEval<E> for SimpleWord<T, P, S>
 where
     T: 'static + Send + Sync + StringWrapper,
-    P: Send + Sync + ParamEval<E, EvalResult = T>,
+    P: Send + Sync + ParamEval<E, EvalResult = T> + fmt::Display,
     S: Send + Sync + WordEval<E, EvalResult = T>,
-    E: ?Sized + Send + VariableEnvironment<Var = T>,
-    E::VarName: Borrow<String>,
+    S::Error: From<ExpansionError>,
+    E: ?Sized + Send + ShellOptionsEnvironment + VariableEnvironment<Var = T>,
+    E::VarName: StrKey,
 {
     type EvalResult = T;
     type Error = S::Error;
 
-    async fn eval_with_config(
-        &self,
-        env: &mut E,
+    fn eval_with_config<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
         cfg: WordEvalConfig,
-    ) -> WordEvalResult<Self::EvalResult, Self::Error> {
+    ) -> BoxFuture<'async_trait, WordEvalResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
         let result = match self {
             Literal(s) | Escaped(s) => Fields::Single(s.clone()),
 
@@ -38,18 +51,59 @@ where
                     // Note: even though we are expanding the equivalent of `$HOME`, a tilde
                     // expansion is NOT considered a parameter expansion, and therefore
                     // should not be subjected to field splitting.
-                    env.var(&HOME)
+                    E::VarName::lookup(env, HOME)
                         .map_or(Fields::Zero, |f| Fields::Single(f.clone()))
                 }
             },
 
-            Param(p) => p
-                .eval(cfg.split_fields_further, env)
-                .unwrap_or(Fields::Zero),
+            // NB: `$@` and `$*` always evaluate to (possibly zero) fields,
+            // so they are never considered unset here, as POSIX requires.
+            Param(p) => match p.eval(cfg.split_fields_further, env) {
+                Some(fields) => fields,
+                None if env.is_option_enabled(ShellOption::NoUnset) => {
+                    let err = ExpansionError::UnsetParameter(p.to_string());
+                    return Box::pin(async move { Err(err.into()) });
+                }
+                None => Fields::Zero,
+            },
 
-            Subst(s) => return s.eval_with_config(env, cfg).await,
+            // Substitutions may be deeply nested, so hand out their future
+            // as is rather than awaiting it from yet another boxed future
+            Subst(s) => return s.eval_with_config(env, cfg),
         };
 
-        Ok(Box::pin(async move { result }))
+        Box::pin(async move { Ok(box_up(result)) })
+    }
+
+    async fn eval_pattern(&self, env: &mut E, cfg: WordEvalConfig) -> Result<String, Self::Error> {
+        match self {
+            Escaped(s) => Ok(escape_pattern(s.as_str())),
+            _ => join_pattern(self.eval_with_config(env, cfg), false).await,
+        }
+    }
+
+    async fn eval_pattern_fields(
+        &self,
+        env: &mut E,
+        cfg: WordEvalConfig,
+    ) -> Result<Vec<(T, String)>, Self::Error> {
+        // Tilde expansions are never subject to pathname expansion
+        let escape = matches!(self, Escaped(_) | Tilde);
+
+        pattern_fields(self.eval_with_config(env, cfg), escape).await
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        match self {
+            Literal(s) => Some(s.as_str()),
+            _ => None,
+        }
     }
 }
+
+fn box_up<T>(t: T) -> BoxFuture<'static, T>
+where
+    T: 'static + Send,
+{
+    Box::pin(async move { t })
+}
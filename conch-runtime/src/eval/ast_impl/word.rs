@@ -1,8 +1,10 @@
-use crate::env::VariableEnvironment;
-use crate::eval::{double_quoted, Fields, WordEval, WordEvalConfig, WordEvalResult};
+use crate::env::{StrKey, StringWrapper, VariableEnvironment};
+use crate::eval::{
+    double_quoted, escape_pattern, join_pattern, pattern_fields, Fields, PatternFieldsResult,
+    WordEval, WordEvalConfig, WordEvalResult,
+};
 use conch_parser::ast::Word;
 use futures_core::future::BoxFuture;
-use std::borrow::Borrow;
 
 impl<T, W, E> WordEval<E> for Word<T, W>
 where
@@ -11,7 +13,7 @@ where
     W::EvalResult: 'static + Send + Sync + From<T>,
     W::Error: Send,
     E: ?Sized + Send + VariableEnvironment<Var = W::EvalResult>,
-    E::VarName: Borrow<String>,
+    E::VarName: StrKey,
 {
     type EvalResult = W::EvalResult;
     type Error = W::Error;
@@ -35,6 +37,51 @@ where
             Word::DoubleQuoted(d) => Box::pin(double_quoted(d, env)),
         }
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        match self {
+            Word::Simple(w) => w.eval_pattern(env, cfg),
+            Word::SingleQuoted(s) => {
+                let pat = escape_pattern(W::EvalResult::from(s.clone()).as_str());
+                Box::pin(async move { Ok(pat) })
+            }
+            Word::DoubleQuoted(d) => join_pattern(Box::pin(double_quoted(d, env)), true),
+        }
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        match self {
+            Word::Simple(w) => w.eval_pattern_fields(env, cfg),
+            Word::SingleQuoted(_) | Word::DoubleQuoted(_) => {
+                pattern_fields(self.eval_with_config(env, cfg), true)
+            }
+        }
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        match self {
+            Word::Simple(w) => w.as_literal(),
+            Word::SingleQuoted(_) | Word::DoubleQuoted(_) => None,
+        }
+    }
 }
 
 // Not sure why we need this as a stand alone function, but it seems like the
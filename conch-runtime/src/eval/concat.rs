@@ -83,6 +83,46 @@ where
     }))
 }
 
+/// Concatenates multiple words like `concat`, but pairs each resulting field
+/// with its source as a pattern (see `WordEval::eval_pattern_fields`).
+#[cfg(feature = "conch-parser")]
+pub(crate) async fn concat_pattern_fields<I, E>(
+    words: I,
+    env: &mut E,
+    cfg: WordEvalConfig,
+) -> Result<Vec<(<I::Item as WordEval<E>>::EvalResult, String)>, <I::Item as WordEval<E>>::Error>
+where
+    I: IntoIterator,
+    I::Item: WordEval<E>,
+    E: ?Sized,
+{
+    let mut fields = vec![];
+    let mut cfg = cfg;
+
+    for word in words {
+        let next = word.eval_pattern_fields(env, cfg).await?;
+        // Like with concat, only the first word is subject to tilde expansion
+        cfg.tilde_expansion = TildeExpansion::None;
+
+        let mut iter = next.into_iter();
+        if let Some((next, next_pat)) = iter.next() {
+            match fields.pop() {
+                None => fields.push((next, next_pat)),
+                Some((last, mut pat)) => {
+                    let mut new = last.into_owned();
+                    new.push_str(next.as_str());
+                    pat.push_str(&next_pat);
+                    fields.push((new.into(), pat));
+                }
+            }
+        }
+
+        fields.extend(iter);
+    }
+
+    Ok(fields)
+}
+
 fn append<T: StringWrapper>(previous: &mut Vec<T>, next: Fields<T>) {
     let mut iter = next.into_iter().fuse();
 
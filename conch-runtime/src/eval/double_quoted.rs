@@ -1,4 +1,4 @@
-use crate::env::{StringWrapper, VariableEnvironment};
+use crate::env::{StrKey, StringWrapper, VariableEnvironment};
 use crate::eval::{Fields, TildeExpansion, WordEval, WordEvalConfig};
 use futures_core::future::BoxFuture;
 
@@ -15,7 +15,7 @@ where
     W: WordEval<E>,
     W::EvalResult: 'static + Send,
     E: ?Sized + VariableEnvironment<Var = W::EvalResult>,
-    E::VarName: std::borrow::Borrow<String>,
+    E::VarName: StrKey,
 {
     do_double_quoted(words.into_iter(), env).await
 }
@@ -29,7 +29,7 @@ where
     W: WordEval<E>,
     W::EvalResult: 'static + Send,
     E: ?Sized + VariableEnvironment<Var = W::EvalResult>,
-    E::VarName: std::borrow::Borrow<String>,
+    E::VarName: StrKey,
 {
     // Make sure we are NOT doing any tilde expanions for further field splitting
     let cfg = WordEvalConfig {
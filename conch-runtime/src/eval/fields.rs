@@ -1,10 +1,22 @@
-use crate::env::{StringWrapper, VariableEnvironment};
+use crate::env::{StrKey, StringWrapper, VariableEnvironment};
 use crate::IFS_DEFAULT;
 use std::borrow::Borrow;
+use std::ops::Range;
 use std::vec;
 
-lazy_static::lazy_static! {
-    static ref IFS: String = String::from("IFS");
+const IFS: &str = "IFS";
+
+/// Determines the separator used for joining fields given the value of `$IFS`
+/// (e.g. when evaluating `$*` within double quotes):
+///
+/// * if `$IFS` is unset, fields are joined with a space,
+/// * if `$IFS` is empty, fields are concatenated without any separator,
+/// * otherwise fields are joined with the first character of `$IFS`.
+pub fn ifs_join_separator(ifs: Option<&str>) -> &str {
+    match ifs {
+        None => " ",
+        Some(ifs) => ifs.chars().next().map_or("", |c| &ifs[..c.len_utf8()]),
+    }
 }
 
 /// Represents the types of fields that may result from evaluating a word.
@@ -59,36 +71,35 @@ impl<T: StringWrapper> Fields<T> {
         }
     }
 
-    /// Joins any field unconditionally with the first character of `$IFS`.
-    /// If `$IFS` is unset, fields are joined with a space, or concatenated
-    /// if `$IFS` is empty.
+    /// Joins all fields (including any empty ones) using the specified separator.
+    ///
+    /// Note: `Zero` is treated as a empty-but-present field for simplicity.
+    pub fn join_with(self, sep: &str) -> T {
+        match self {
+            Fields::Zero => String::new().into(),
+            Fields::Single(s) => s,
+            Fields::At(v) | Fields::Star(v) | Fields::Split(v) => v
+                .iter()
+                .map(StringWrapper::as_str)
+                .collect::<Vec<_>>()
+                .join(sep)
+                .into(),
+        }
+    }
+
+    /// Joins all fields (including any empty ones) in the same way as `$*`
+    /// is joined within double quotes, i.e. using the separator described
+    /// by `ifs_join_separator` based on the current value of `$IFS`.
     ///
     /// Note: `Zero` is treated as a empty-but-present field for simplicity.
     pub fn join_with_ifs<E: ?Sized>(self, env: &E) -> T
     where
         E: VariableEnvironment,
-        E::VarName: Borrow<String>,
+        E::VarName: StrKey,
         E::Var: Borrow<String>,
     {
-        match self {
-            Fields::Zero => String::new().into(),
-            Fields::Single(s) => s,
-            Fields::At(v) | Fields::Star(v) | Fields::Split(v) => {
-                let sep = env.var(&IFS).map(|s| s.borrow().as_str()).map_or(" ", |s| {
-                    if s.is_empty() {
-                        ""
-                    } else {
-                        &s[0..1]
-                    }
-                });
-
-                v.iter()
-                    .map(StringWrapper::as_str)
-                    .collect::<Vec<_>>()
-                    .join(sep)
-                    .into()
-            }
-        }
+        let ifs = E::VarName::lookup(env, IFS).map(|s| s.borrow().as_str());
+        self.join_with(ifs_join_separator(ifs))
     }
 
     /// Splits a vector of fields further based on the contents of the `IFS`
@@ -97,7 +108,7 @@ impl<T: StringWrapper> Fields<T> {
     pub fn split<E: ?Sized>(self, env: &E) -> Fields<T>
     where
         E: VariableEnvironment,
-        E::VarName: Borrow<String>,
+        E::VarName: StrKey,
         E::Var: Borrow<String>,
     {
         match self {
@@ -145,15 +156,19 @@ impl<T> IntoIterator for Fields<T> {
 }
 
 /// Actual implementation of `split_fields`.
+///
+/// Words which do not need to be split (i.e. which end up as a single field
+/// spanning the entire word) are reused as is, and new strings are only
+/// allocated for fields which are a portion of a word.
 fn split_fields_internal<T, E: ?Sized>(words: Vec<T>, env: &E) -> Vec<T>
 where
     T: StringWrapper,
     E: VariableEnvironment,
-    E::VarName: Borrow<String>,
+    E::VarName: StrKey,
     E::Var: Borrow<String>,
 {
     // If IFS is set but null, there is nothing left to split
-    let ifs = env.var(&IFS).map_or(IFS_DEFAULT, |s| s.borrow().as_str());
+    let ifs = E::VarName::lookup(env, IFS).map_or(IFS_DEFAULT, |s| s.borrow().as_str());
     if ifs.is_empty() {
         return words;
     }
@@ -161,74 +176,71 @@ where
     let whitespace: Vec<char> = ifs.chars().filter(|c| c.is_whitespace()).collect();
 
     let mut fields = Vec::with_capacity(words.len());
-    'word: for word in words.iter().map(StringWrapper::as_str) {
-        if word.is_empty() {
-            continue;
+    let mut ranges = Vec::new();
+    for word in words {
+        ranges.clear();
+        split_word(word.as_str(), ifs, &whitespace, &mut ranges);
+
+        match ranges.as_slice() {
+            [range] if range.start == 0 && range.end == word.as_str().len() => fields.push(word),
+            ranges => fields.extend(
+                ranges
+                    .iter()
+                    .map(|range| String::from(&word.as_str()[range.clone()]).into()),
+            ),
         }
+    }
 
-        let mut iter = word.chars().enumerate().peekable();
-        loop {
-            let start;
-            loop {
-                match iter.next() {
-                    // If we are still skipping leading whitespace, and we hit the
-                    // end of the word there are no fields to create, even empty ones.
-                    None => continue 'word,
-                    Some((idx, c)) => {
-                        if whitespace.contains(&c) {
-                            continue;
-                        } else if ifs.contains(c) {
-                            // If we hit an IFS char here then we have encountered an
-                            // empty field, since the last iteration of this loop either
-                            // had just consumed an IFS char, or its the start of the word.
-                            // In either case the result should be the same.
-                            fields.push(String::new().into());
-                        } else {
-                            // Must have found a regular field character
-                            start = idx;
-                            break;
-                        }
-                    }
-                }
-            }
+    fields
+}
 
-            let end;
-            loop {
-                match iter.next() {
-                    None => {
-                        end = None;
+/// Splits a single word based on the characters of `ifs`, recording the
+/// byte range of each resulting field. Empty fields have an empty range.
+fn split_word(word: &str, ifs: &str, whitespace: &[char], ranges: &mut Vec<Range<usize>>) {
+    let mut iter = word.char_indices().peekable();
+    loop {
+        let start;
+        loop {
+            match iter.next() {
+                // If we are still skipping leading whitespace, and we hit the
+                // end of the word there are no fields to create, even empty ones.
+                None => return,
+                Some((idx, c)) => {
+                    if whitespace.contains(&c) {
+                        continue;
+                    } else if ifs.contains(c) {
+                        // If we hit an IFS char here then we have encountered an
+                        // empty field, since the last iteration of this loop either
+                        // had just consumed an IFS char, or its the start of the word.
+                        // In either case the result should be the same.
+                        ranges.push(idx..idx);
+                    } else {
+                        // Must have found a regular field character
+                        start = idx;
                         break;
                     }
-                    Some((idx, c)) => {
-                        if ifs.contains(c) {
-                            end = Some(idx);
-                            break;
-                        }
-                    }
                 }
             }
+        }
 
-            let field = match end {
-                Some(end) => &word[start..end],
-                None => &word[start..],
-            };
+        let mut end = word.len();
+        for (idx, c) in iter.by_ref() {
+            if ifs.contains(c) {
+                end = idx;
+                break;
+            }
+        }
 
-            fields.push(String::from(field).into());
+        ranges.push(start..end);
 
-            // Since now we've hit an IFS character, we need to also skip past
-            // any adjacent IFS whitespace as well. This also conveniently
-            // ignores any trailing IFS whitespace in the input as well.
-            loop {
-                match iter.peek() {
-                    Some(&(_, c)) if whitespace.contains(&c) => {
-                        iter.next();
-                    }
-                    Some(_) | None => break,
-                }
+        // Since now we've hit an IFS character, we need to also skip past
+        // any adjacent IFS whitespace as well. This also conveniently
+        // ignores any trailing IFS whitespace in the input as well.
+        while let Some(&(_, c)) = iter.peek() {
+            if !whitespace.contains(&c) {
+                break;
             }
+            iter.next();
         }
     }
-
-    fields.shrink_to_fit();
-    fields
 }
@@ -0,0 +1,477 @@
+use crate::env::{
+    ShellOption, ShellOptionsEnvironment, StringWrapper, WorkingDirectoryEnvironment,
+};
+use crate::error::ExpansionError;
+use crate::eval::pattern::{has_extended_group, Pattern};
+use crate::eval::pattern_cache::compile_pattern;
+use crate::eval::{TildeExpansion, WordEval, WordEvalConfig};
+use crate::io::blocking_io;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_util::future::FutureExt;
+use futures_util::stream::{self, StreamExt};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Options for walking the file system when expanding pathname patterns
+/// (e.g. `src/*.rs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlobConfig {
+    /// The maximum number of directories which may be read concurrently.
+    /// A limit of zero is treated as a limit of one.
+    pub max_concurrent_reads: usize,
+    /// What should happen if a pattern does not match any paths.
+    pub no_match: NoMatchBehavior,
+    /// Whether ksh-style extended patterns (e.g. `@(foo|bar)`, `!(*.rs)`)
+    /// should be recognized, like the `extglob` option.
+    pub extglob: bool,
+    /// Whether a `**` component should match any number of directories
+    /// (and subdirectories), like the `globstar` option.
+    pub globstar: bool,
+    /// Whether pathname expansion is disabled altogether, like the `noglob`
+    /// option, such that fields are never expanded by `glob_field`.
+    pub noglob: bool,
+}
+
+impl Default for GlobConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_reads: 8,
+            no_match: NoMatchBehavior::default(),
+            extglob: false,
+            globstar: false,
+            noglob: false,
+        }
+    }
+}
+
+impl GlobConfig {
+    /// Creates a default configuration, except with any behavior controlled
+    /// by the `extglob`, `globstar`, `noglob`, `nullglob`, and `failglob`
+    /// options determined by the options of an environment.
+    pub fn from_options<E: ?Sized + ShellOptionsEnvironment>(env: &E) -> Self {
+        Self {
+            no_match: NoMatchBehavior::from_options(env),
+            extglob: env.is_option_enabled(ShellOption::ExtGlob),
+            globstar: env.is_option_enabled(ShellOption::GlobStar),
+            noglob: env.is_option_enabled(ShellOption::NoGlob),
+            ..Self::default()
+        }
+    }
+}
+
+/// Describes what should happen when a pathname pattern does not match any paths.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoMatchBehavior {
+    /// Retain the (unexpanded) field itself, as POSIX specifies.
+    #[default]
+    Literal,
+    /// Expand to no fields at all, like the `nullglob` option.
+    Null,
+    /// Treat the pattern as an error, like the `failglob` option.
+    Fail,
+}
+
+impl NoMatchBehavior {
+    /// Determines the behavior based on the `nullglob` and `failglob` options
+    /// enabled in an environment (the latter taking precedence if both are).
+    pub fn from_options<E: ?Sized + ShellOptionsEnvironment>(env: &E) -> Self {
+        if env.is_option_enabled(ShellOption::FailGlob) {
+            NoMatchBehavior::Fail
+        } else if env.is_option_enabled(ShellOption::NullGlob) {
+            NoMatchBehavior::Null
+        } else {
+            NoMatchBehavior::Literal
+        }
+    }
+}
+
+/// A path matched thus far, along with how it should be displayed (i.e. as
+/// written in the pattern, rather than resolved against the working directory).
+#[derive(Debug, Clone)]
+struct Candidate {
+    display: String,
+    path: PathBuf,
+}
+
+impl Candidate {
+    fn join(&self, name: &str) -> Self {
+        let mut display = self.display.clone();
+        if !display.is_empty() && !display.ends_with('/') {
+            display.push('/');
+        }
+        display.push_str(name);
+
+        Self {
+            display,
+            path: self.path.join(name),
+        }
+    }
+}
+
+/// A single (slash separated) component of a pathname pattern.
+#[derive(Debug)]
+enum Component {
+    Literal(String),
+    Pattern(Arc<Pattern>),
+    /// A `**` component while `globstar` is enabled.
+    Recursive,
+}
+
+impl Component {
+    fn new(src: &str, cfg: GlobConfig) -> Self {
+        if cfg.globstar && src == "**" {
+            return Component::Recursive;
+        }
+
+        match unescape_literal(src, cfg.extglob) {
+            Some(name) => Component::Literal(name),
+            None => Component::Pattern(compile_pattern(src, cfg.extglob)),
+        }
+    }
+}
+
+/// Returns the literal value of a pattern, unless it contains any special
+/// characters which were not escaped (e.g. via `escape_pattern`),
+/// or any extended groups if `extglob` is set.
+fn unescape_literal(pattern: &str, extglob: bool) -> Option<String> {
+    if extglob && has_extended_group(pattern) {
+        return None;
+    }
+
+    let mut ret = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' | ']' => return None,
+            '[' => {
+                let mut escaped = chars.clone();
+                match (escaped.next(), escaped.next()) {
+                    (Some(c @ '*'), Some(']'))
+                    | (Some(c @ '?'), Some(']'))
+                    | (Some(c @ '['), Some(']'))
+                    | (Some(c @ ']'), Some(']'))
+                    | (Some(c @ '('), Some(']'))
+                    | (Some(c @ '|'), Some(']'))
+                    | (Some(c @ ')'), Some(']')) => {
+                        ret.push(c);
+                        chars = escaped;
+                    }
+                    _ => return None,
+                }
+            }
+            c => ret.push(c),
+        }
+    }
+
+    Some(ret)
+}
+
+async fn exists(path: &Path, dirs_only: bool) -> bool {
+    let path = path.to_owned();
+    blocking_io(move || {
+        if dirs_only {
+            fs::metadata(path).map(|meta| meta.is_dir())
+        } else {
+            fs::symlink_metadata(path).map(|_| true)
+        }
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Reads the names of all entries of a directory (along with whether each is
+/// a directory, without following symlinks) which satisfy `filter`.
+///
+/// Names which aren't valid UTF-8 are skipped since they cannot be
+/// represented as fields.
+async fn read_dir<F>(path: &Path, filter: F) -> io::Result<Vec<(String, bool)>>
+where
+    F: Fn(&str) -> bool + Send + 'static,
+{
+    let path = path.to_owned();
+    blocking_io(move || {
+        let mut ret = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => break,
+            };
+
+            let name = match entry.file_name().to_str() {
+                Some(name) if filter(name) => name.to_owned(),
+                _ => continue,
+            };
+
+            let is_dir = entry.file_type().map(|ty| ty.is_dir()).unwrap_or(false);
+            ret.push((name, is_dir));
+        }
+        Ok(ret)
+    })
+    .await
+}
+
+/// Reads all (non-hidden) entries of a directory sorted by name, along with
+/// whether each is a directory (without following symlinks).
+async fn read_children(parent: &Candidate) -> Vec<(Candidate, bool)> {
+    let mut children = read_dir(&parent.path, |name| !name.starts_with('.'))
+        .await
+        .unwrap_or_default();
+    children.sort();
+
+    children
+        .into_iter()
+        .map(|(name, is_dir)| (parent.join(&name), is_dir))
+        .collect()
+}
+
+/// Finds all descendants of `parent` for a `**` component, in depth-first
+/// order with the entries of each directory sorted by name. Unless the
+/// component is the last of the pattern, `parent` itself is included as well
+/// (i.e. `**` can match zero directories).
+///
+/// Like other shells, hidden entries are skipped and symlinks to directories
+/// are never followed, which avoids walking any cycles.
+async fn expand_recursive(parent: Candidate, is_last: bool, dirs_only: bool) -> Vec<Candidate> {
+    let mut ret = Vec::new();
+    let mut stack = read_children(&parent).await;
+    stack.reverse();
+
+    if !is_last {
+        ret.push(parent);
+    }
+
+    while let Some((candidate, is_dir)) = stack.pop() {
+        if is_dir {
+            let mut children = read_children(&candidate).await;
+            children.reverse();
+            stack.extend(children);
+        }
+
+        if is_dir || !dirs_only {
+            ret.push(candidate);
+        }
+    }
+
+    ret
+}
+
+/// Finds all entries of `parent` which are matched by `component`, sorted by name.
+///
+/// Any directories which cannot be read (e.g. due to insufficient permissions)
+/// are treated as if they were empty, as other shells do.
+async fn expand_component(
+    parent: Candidate,
+    component: &Component,
+    is_last: bool,
+    dirs_only: bool,
+) -> Vec<Candidate> {
+    let pat = match component {
+        Component::Literal(name) => {
+            let candidate = parent.join(name);
+            return if exists(&candidate.path, dirs_only).await {
+                vec![candidate]
+            } else {
+                Vec::new()
+            };
+        }
+        Component::Recursive => return expand_recursive(parent, is_last, dirs_only).await,
+        Component::Pattern(pat) => pat,
+    };
+
+    let opts = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+
+    let pat = pat.clone();
+    let mut names = read_dir(&parent.path, move |name| pat.matches_with(name, opts))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    names.sort();
+
+    let mut ret = Vec::with_capacity(names.len());
+    for name in names {
+        let candidate = parent.join(&name);
+        if !dirs_only || exists(&candidate.path, true).await {
+            ret.push(candidate);
+        }
+    }
+
+    ret
+}
+
+/// Expands a pathname pattern (e.g. `src/*.rs`) into a stream of all existing
+/// paths it matches.
+///
+/// Relative patterns are resolved against the working directory of `env`, although all matches are
+/// yielded as they were written in the pattern (i.e. relative paths remain
+/// relative). Directories are read asynchronously (up to the configured
+/// number at a time) so that scanning large directories never blocks the
+/// current thread. Matches are yielded in a deterministic order: sorted
+/// component by component, regardless of how many directories are read
+/// concurrently.
+///
+/// Entries starting with a `.` are only matched if the pattern component
+/// explicitly starts with a `.` as well, and a pattern ending with a `/` only
+/// matches directories. Names which are not valid UTF-8 are never matched.
+/// If `cfg.globstar` is set, a `**` component matches any number of nested
+/// (non-hidden) directories, including none at all.
+///
+/// Note: this stream must be polled within the context of a `tokio` runtime.
+pub fn glob_paths<E>(pattern: &str, env: &E, cfg: GlobConfig) -> BoxStream<'static, String>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    let (start, rest) = if let Some(rest) = pattern.strip_prefix('/') {
+        let root = Candidate {
+            display: "/".to_owned(),
+            path: PathBuf::from("/"),
+        };
+        (root, rest)
+    } else {
+        let cwd = Candidate {
+            display: String::new(),
+            path: env.current_working_dir().to_owned(),
+        };
+        (cwd, pattern)
+    };
+
+    let trailing_slash = rest.ends_with('/');
+    let mut components = rest
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .map(|c| Component::new(c, cfg))
+        .collect::<Vec<_>>();
+
+    let last = match components.pop() {
+        Some(last) => Arc::new(last),
+        None => return stream::empty().boxed(),
+    };
+
+    let limit = cfg.max_concurrent_reads.max(1);
+
+    // NB: `buffered` yields results in the order the directories were
+    // provided, thus the output remains sorted no matter the limit.
+    let parents = async move {
+        let mut parents = vec![start];
+        for component in &components {
+            parents = stream::iter(parents)
+                .map(|parent| expand_component(parent, component, false, true))
+                .buffered(limit)
+                .concat()
+                .await;
+        }
+        parents
+    };
+
+    stream::once(parents)
+        .flat_map(move |parents| {
+            let last = last.clone();
+            stream::iter(parents)
+                .map(move |parent| {
+                    let last = last.clone();
+                    async move { expand_component(parent, &last, true, trailing_slash).await }
+                })
+                .buffered(limit)
+                .flat_map(stream::iter)
+        })
+        .map(move |candidate| {
+            let mut display = candidate.display;
+            if trailing_slash {
+                display.push('/');
+            }
+            display
+        })
+        .boxed()
+}
+
+/// Expands a pathname pattern into all existing paths it matches.
+///
+/// See `glob_paths` for more details.
+pub fn expand_glob<E>(pattern: &str, env: &E, cfg: GlobConfig) -> BoxFuture<'static, Vec<String>>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    glob_paths(pattern, env, cfg).collect().boxed()
+}
+
+/// Performs pathname expansion on a single field of an evaluated word.
+///
+/// `field` is the value of the field itself, while `pattern` is its source as
+/// a pattern (e.g. as returned by `WordEval::eval_pattern_fields`), with any quoted
+/// portions escaped. Fields whose pattern has no (unescaped) special characters
+/// are never expanded (nor is any field if `cfg.noglob` is set), otherwise all
+/// matched paths are returned (see `glob_paths` for more details). If nothing
+/// is matched the field is retained, dropped, or treated as an error, depending
+/// on `cfg.no_match`.
+pub fn glob_field<E>(
+    field: &str,
+    pattern: &str,
+    env: &E,
+    cfg: GlobConfig,
+) -> BoxFuture<'static, Result<Vec<String>, ExpansionError>>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    let field = field.to_owned();
+    if cfg.noglob || unescape_literal(pattern, cfg.extglob).is_some() {
+        return Box::pin(async move { Ok(vec![field]) });
+    }
+
+    let matches = expand_glob(pattern, env, cfg);
+    Box::pin(async move {
+        let matches = matches.await;
+        if !matches.is_empty() {
+            return Ok(matches);
+        }
+
+        match cfg.no_match {
+            NoMatchBehavior::Literal => Ok(vec![field]),
+            NoMatchBehavior::Null => Ok(Vec::new()),
+            NoMatchBehavior::Fail => Err(ExpansionError::NoGlobMatch(field)),
+        }
+    })
+}
+
+/// Evaluates a word (e.g. a command argument) and performs pathname expansion
+/// on each of its fields, as configured by the options of the environment
+/// (see `GlobConfig::from_options`).
+///
+/// Any quoted portions of the word only ever match literally (see
+/// `WordEval::eval_pattern_fields`), and fields which would not be expanded
+/// are retained as they were evaluated.
+pub async fn glob_word<W, E>(word: &W, env: &mut E) -> Result<Vec<W::EvalResult>, W::Error>
+where
+    W: ?Sized + WordEval<E>,
+    W::Error: From<ExpansionError>,
+    E: ?Sized + ShellOptionsEnvironment + WorkingDirectoryEnvironment,
+{
+    let eval_cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::First,
+        split_fields_further: true,
+    };
+
+    let fields = word.eval_pattern_fields(env, eval_cfg).await?;
+    let cfg = GlobConfig::from_options(env);
+
+    let mut ret = Vec::with_capacity(fields.len());
+    for (field, pattern) in fields {
+        if cfg.noglob || unescape_literal(&pattern, cfg.extglob).is_some() {
+            ret.push(field);
+            continue;
+        }
+
+        let matches = glob_field(field.as_str(), &pattern, env, cfg).await?;
+        ret.extend(matches.into_iter().map(W::EvalResult::from));
+    }
+
+    Ok(ret)
+}
@@ -14,7 +14,9 @@ pub use self::default::default;
 pub use self::error::error;
 pub use self::len::len;
 pub use self::remove::{
-    remove_largest_prefix, remove_largest_suffix, remove_smallest_prefix, remove_smallest_suffix,
+    remove_largest_prefix, remove_largest_prefix_with_config, remove_largest_suffix,
+    remove_largest_suffix_with_config, remove_smallest_prefix, remove_smallest_prefix_with_config,
+    remove_smallest_suffix, remove_smallest_suffix_with_config, PatternMatchConfig,
 };
 
 /// Determines if a `Fields` variant can be considered non-empty/non-null.
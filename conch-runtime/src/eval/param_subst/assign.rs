@@ -1,5 +1,5 @@
 use super::is_present;
-use crate::env::VariableEnvironment;
+use crate::env::{StrKey, VariableEnvironment};
 use crate::error::ExpansionError;
 use crate::eval::{Fields, ParamEval, TildeExpansion, WordEval, WordEvalConfig};
 use std::fmt::Display;
@@ -12,6 +12,10 @@ use std::fmt::Display;
 /// Otherwise, `assign` will be evaluated using `cfg`, that value assigned to
 /// the variable in the current environment, and the value yielded.
 ///
+/// If the value to assign evaluates to the fields of `$*`, they are joined in
+/// the same way as within double quotes (see `Fields::join_with_ifs`), while
+/// any other fields (e.g. those of `$@`) are joined with a space.
+///
 /// Note: field splitting will neither be done on the parameter, nor the value to assign.
 pub async fn assign<P, W, E>(
     strict: bool,
@@ -23,7 +27,7 @@ pub async fn assign<P, W, E>(
 where
     P: ?Sized + ParamEval<E, EvalResult = W::EvalResult> + Display,
     W: WordEval<E>,
-    W::EvalResult: From<String>,
+    W::EvalResult: From<String> + StrKey,
     W::Error: From<ExpansionError>,
     E: ?Sized + VariableEnvironment<VarName = W::EvalResult, Var = W::EvalResult>,
 {
@@ -47,7 +51,11 @@ where
             );
 
             let fields = future.await?.await;
-            env.set_var(assig_name, fields.clone().join());
+            let val = match fields.clone() {
+                f @ Fields::Star(_) => f.join_with_ifs(env),
+                f => f.join(),
+            };
+            env.set_var(assig_name, val);
             fields
         }
         None => {
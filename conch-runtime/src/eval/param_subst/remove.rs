@@ -1,11 +1,113 @@
-use crate::env::StringWrapper;
+use crate::env::{ShellOption, ShellOptionsEnvironment, StringWrapper};
+use crate::eval::pattern::Pattern;
 use crate::eval::{eval_as_pattern, Fields, ParamEval, WordEval};
+use std::iter;
 
-const PAT_REMOVE_MATCH_OPTS: glob::MatchOptions = glob::MatchOptions {
-    case_sensitive: true,
-    require_literal_separator: false,
-    require_literal_leading_dot: false,
-};
+/// Options for matching patterns, e.g. against the value of a parameter when
+/// removing its prefixes or suffixes, or against the word of a `case` command.
+///
+/// Regardless of the options, prefixes and suffixes are only ever removed
+/// at character boundaries, thus multi-byte UTF-8 values are never split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PatternMatchConfig {
+    /// Whether patterns should match case sensitively (e.g. unless a shell
+    /// option such as `nocasematch` is enabled). Case insensitive matching
+    /// compares the (Unicode) lowercase forms of each character.
+    pub case_sensitive: bool,
+    /// Whether ksh-style extended patterns (e.g. `@(foo|bar)`, `!(*.rs)`)
+    /// should be recognized, like the `extglob` option.
+    pub extglob: bool,
+}
+
+impl Default for PatternMatchConfig {
+    fn default() -> Self {
+        Self {
+            case_sensitive: true,
+            extglob: false,
+        }
+    }
+}
+
+impl PatternMatchConfig {
+    /// Creates a default configuration, except with any extended syntax
+    /// enabled based on the options of an environment.
+    pub fn from_options<E: ?Sized + ShellOptionsEnvironment>(env: &E) -> Self {
+        Self {
+            extglob: env.is_option_enabled(ShellOption::ExtGlob),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<PatternMatchConfig> for glob::MatchOptions {
+    fn from(cfg: PatternMatchConfig) -> Self {
+        Self {
+            case_sensitive: cfg.case_sensitive,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        }
+    }
+}
+
+/// A pattern compiled for a single evaluation, along with any literal text
+/// which all of its matches must start and end with.
+///
+/// Checking for the literal text is far cheaper than running the full pattern,
+/// which allows skipping most candidates which cannot possibly match (e.g. for
+/// `${var%.*}` only suffixes which start with a `.` need to be considered).
+struct Matcher<'a> {
+    pat: &'a Pattern,
+    opts: glob::MatchOptions,
+    literal_prefix: &'a str,
+    literal_suffix: &'a str,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(pat: &'a Pattern, cfg: PatternMatchConfig) -> Self {
+        let is_special = |c| matches!(c, '*' | '?' | '[' | ']');
+
+        let src = pat.as_str();
+        let (literal_prefix, literal_suffix) = if cfg.case_sensitive && !pat.is_extended() {
+            let prefix_end = src.find(is_special).unwrap_or(src.len());
+            // NB: all special characters are ASCII and thus a single byte long
+            let suffix_start = src.rfind(is_special).map_or(0, |i| i + 1);
+            (&src[..prefix_end], &src[suffix_start..])
+        } else {
+            // Literal text can only be compared as is when matching case sensitively,
+            // and extended groups can start (or end) with any character
+            ("", "")
+        };
+
+        Self {
+            pat,
+            opts: cfg.into(),
+            literal_prefix,
+            literal_suffix,
+        }
+    }
+
+    /// Indicates if any prefix of `src` could possibly be matched.
+    fn may_match_prefix_of(&self, src: &str) -> bool {
+        src.starts_with(self.literal_prefix)
+    }
+
+    /// Indicates if any suffix of `src` could possibly be matched.
+    fn may_match_suffix_of(&self, src: &str) -> bool {
+        src.ends_with(self.literal_suffix)
+    }
+
+    fn matches(&self, candidate: &str) -> bool {
+        candidate.starts_with(self.literal_prefix)
+            && candidate.ends_with(self.literal_suffix)
+            && self.pat.matches_with(candidate, self.opts)
+    }
+}
+
+/// Yields the byte offset of every character boundary of `s` (including its end),
+/// which are the only offsets `s` can be safely split at.
+fn char_boundaries(s: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+    s.char_indices().map(|(i, _)| i).chain(iter::once(s.len()))
+}
 
 /// Evaluates a parameter and remove a pattern from it.
 ///
@@ -14,13 +116,14 @@ async fn remove_pattern<P, W, E, R>(
     param: &P,
     pat: Option<W>,
     env: &mut E,
+    cfg: PatternMatchConfig,
     remove: R,
 ) -> Result<Fields<W::EvalResult>, W::Error>
 where
     P: ?Sized + ParamEval<E, EvalResult = W::EvalResult>,
     W: WordEval<E>,
     E: ?Sized,
-    R: for<'a> Fn(&'a str, &'_ glob::Pattern) -> &'a str,
+    R: for<'a> Fn(&'a str, &Matcher<'_>) -> &'a str,
 {
     let val = match param.eval(false, env) {
         Some(val) => val,
@@ -28,12 +131,15 @@ where
     };
 
     let pat = match pat {
-        Some(p) => eval_as_pattern(p, env).await?,
+        Some(p) => eval_as_pattern(p, env, cfg.extglob).await?,
         None => return Ok(val),
     };
 
+    // NB: the pattern is compiled (and its literal text found) only once,
+    // and reused for every field of the parameter.
+    let matcher = Matcher::new(&pat, cfg);
     let remove = |s: W::EvalResult| {
-        let trimmed = remove(s.as_str(), &pat);
+        let trimmed = remove(s.as_str(), &matcher);
         W::EvalResult::from(trimmed.to_owned())
     };
 
@@ -68,18 +174,33 @@ where
     W: WordEval<E>,
     E: ?Sized,
 {
-    remove_pattern(param, pat, env, |src, pat| {
-        if !pat.matches_with("", PAT_REMOVE_MATCH_OPTS) {
-            for idx in src.char_indices().rev().map(|(i, _)| i) {
-                let candidate = &src[idx..];
-                if pat.matches_with(candidate, PAT_REMOVE_MATCH_OPTS) {
-                    let end = src.len() - candidate.len();
-                    return &src[0..end];
-                }
-            }
+    remove_smallest_suffix_with_config(param, pat, env, PatternMatchConfig::default()).await
+}
+
+/// Evaluate a parameter and remove the shortest matching suffix, matching the pattern
+/// according to the provided configuration.
+///
+/// See `remove_smallest_suffix` for more details.
+pub async fn remove_smallest_suffix_with_config<P, W, E>(
+    param: &P,
+    pat: Option<W>,
+    env: &mut E,
+    cfg: PatternMatchConfig,
+) -> Result<Fields<W::EvalResult>, W::Error>
+where
+    P: ?Sized + ParamEval<E, EvalResult = W::EvalResult>,
+    W: WordEval<E>,
+    E: ?Sized,
+{
+    remove_pattern(param, pat, env, cfg, |src, matcher| {
+        if !matcher.may_match_suffix_of(src) {
+            return src;
         }
 
-        src
+        char_boundaries(src)
+            .rev()
+            .find(|&idx| matcher.matches(&src[idx..]))
+            .map_or(src, |idx| &src[..idx])
     })
     .await
 }
@@ -102,21 +223,32 @@ where
     W: WordEval<E>,
     E: ?Sized,
 {
-    remove_pattern(param, pat, env, |src, pat| {
-        let mut iter = src.char_indices();
-
-        loop {
-            let candidate = iter.as_str();
-            let candidate_start = match iter.next() {
-                Some((i, _)) => i,
-                // candidate == "", nothing to trim
-                None => return src,
-            };
-
-            if pat.matches_with(candidate, PAT_REMOVE_MATCH_OPTS) {
-                return &src[0..candidate_start];
-            }
+    remove_largest_suffix_with_config(param, pat, env, PatternMatchConfig::default()).await
+}
+
+/// Evaluate a parameter and remove the largest matching suffix, matching the pattern
+/// according to the provided configuration.
+///
+/// See `remove_largest_suffix` for more details.
+pub async fn remove_largest_suffix_with_config<P, W, E>(
+    param: &P,
+    pat: Option<W>,
+    env: &mut E,
+    cfg: PatternMatchConfig,
+) -> Result<Fields<W::EvalResult>, W::Error>
+where
+    P: ?Sized + ParamEval<E, EvalResult = W::EvalResult>,
+    W: WordEval<E>,
+    E: ?Sized,
+{
+    remove_pattern(param, pat, env, cfg, |src, matcher| {
+        if !matcher.may_match_suffix_of(src) {
+            return src;
         }
+
+        char_boundaries(src)
+            .find(|&idx| matcher.matches(&src[idx..]))
+            .map_or(src, |idx| &src[..idx])
     })
     .await
 }
@@ -139,20 +271,32 @@ where
     W: WordEval<E>,
     E: ?Sized,
 {
-    remove_pattern(param, pat, env, |src, pat| {
-        for idx in src.char_indices().map(|(i, _)| i) {
-            let candidate = &src[0..idx];
-            if pat.matches_with(candidate, PAT_REMOVE_MATCH_OPTS) {
-                return &src[idx..];
-            }
-        }
+    remove_smallest_prefix_with_config(param, pat, env, PatternMatchConfig::default()).await
+}
 
-        // Don't forget to check the entire string for a match
-        if pat.matches_with(src, PAT_REMOVE_MATCH_OPTS) {
-            ""
-        } else {
-            src
+/// Evaluate a parameter and remove the shortest matching prefix, matching the pattern
+/// according to the provided configuration.
+///
+/// See `remove_smallest_prefix` for more details.
+pub async fn remove_smallest_prefix_with_config<P, W, E>(
+    param: &P,
+    pat: Option<W>,
+    env: &mut E,
+    cfg: PatternMatchConfig,
+) -> Result<Fields<W::EvalResult>, W::Error>
+where
+    P: ?Sized + ParamEval<E, EvalResult = W::EvalResult>,
+    W: WordEval<E>,
+    E: ?Sized,
+{
+    remove_pattern(param, pat, env, cfg, |src, matcher| {
+        if !matcher.may_match_prefix_of(src) {
+            return src;
         }
+
+        char_boundaries(src)
+            .find(|&idx| matcher.matches(&src[..idx]))
+            .map_or(src, |idx| &src[idx..])
     })
     .await
 }
@@ -175,22 +319,33 @@ where
     W: WordEval<E>,
     E: ?Sized,
 {
-    remove_pattern(param, pat, env, |src, pat| {
-        let mut prefix_start = src.len();
-        let mut iter = src.char_indices();
-
-        loop {
-            let candidate = iter.as_str();
-            if pat.matches_with(candidate, PAT_REMOVE_MATCH_OPTS) {
-                return &src[prefix_start..];
-            }
-
-            prefix_start = match iter.next_back() {
-                Some((i, _)) => i,
-                // candidate == "", nothing to trim
-                None => return src,
-            };
+    remove_largest_prefix_with_config(param, pat, env, PatternMatchConfig::default()).await
+}
+
+/// Evaluate a parameter and remove the largest matching prefix, matching the pattern
+/// according to the provided configuration.
+///
+/// See `remove_largest_prefix` for more details.
+pub async fn remove_largest_prefix_with_config<P, W, E>(
+    param: &P,
+    pat: Option<W>,
+    env: &mut E,
+    cfg: PatternMatchConfig,
+) -> Result<Fields<W::EvalResult>, W::Error>
+where
+    P: ?Sized + ParamEval<E, EvalResult = W::EvalResult>,
+    W: WordEval<E>,
+    E: ?Sized,
+{
+    remove_pattern(param, pat, env, cfg, |src, matcher| {
+        if !matcher.may_match_prefix_of(src) {
+            return src;
         }
+
+        char_boundaries(src)
+            .rev()
+            .find(|&idx| matcher.matches(&src[..idx]))
+            .map_or(src, |idx| &src[idx..])
     })
     .await
 }
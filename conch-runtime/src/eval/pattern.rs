@@ -0,0 +1,513 @@
+//! A compiler for patterns which may use ksh-style extended syntax.
+//!
+//! The `glob` crate is used for all ordinary patterns, but it cannot express
+//! extended patterns such as `@(foo|bar)`, thus they are compiled and matched
+//! here instead. The `glob` crate also only folds the case of ASCII characters,
+//! thus ordinary patterns are matched here as well when case insensitively
+//! matching any other characters.
+
+use glob::MatchOptions;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// A compiled pattern, which may use extended syntax if it was enabled
+/// when the pattern was compiled.
+#[derive(Debug, Clone)]
+pub(crate) enum Pattern {
+    /// An ordinary pattern, along with its tokens for when it must be matched
+    /// case insensitively against non-ASCII characters.
+    Glob(glob::Pattern, Vec<Token>),
+    /// A pattern which uses extended syntax, along with its source.
+    Extended(String, Vec<Token>),
+}
+
+impl Pattern {
+    /// Compiles a pattern, treating it as a literal if it is not a valid pattern.
+    ///
+    /// Extended syntax is only recognized if `extglob` is set.
+    pub(crate) fn new(pat: &str, extglob: bool) -> Self {
+        if extglob && has_extended_group(pat) {
+            if let Some(tokens) = Parser::new(pat, true).parse() {
+                return Pattern::Extended(pat.to_owned(), tokens);
+            }
+        }
+
+        let pattern = glob::Pattern::new(pat)
+            .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(pat)))
+            .expect("pattern compilation unexpectedly failed");
+
+        let tokens = Parser::new(pattern.as_str(), false)
+            .parse()
+            .expect("ordinary patterns have no groups to terminate");
+
+        Pattern::Glob(pattern, tokens)
+    }
+
+    /// Returns the source of the pattern.
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            Pattern::Glob(pat, _) => pat.as_str(),
+            Pattern::Extended(src, _) => src,
+        }
+    }
+
+    /// Indicates if the pattern uses any extended syntax.
+    pub(crate) fn is_extended(&self) -> bool {
+        match self {
+            Pattern::Glob(..) => false,
+            Pattern::Extended(..) => true,
+        }
+    }
+
+    /// Indicates if the pattern matches the entirety of `s`.
+    pub(crate) fn matches_with(&self, s: &str, opts: MatchOptions) -> bool {
+        match self {
+            Pattern::Glob(pat, _)
+                if opts.case_sensitive || (s.is_ascii() && pat.as_str().is_ascii()) =>
+            {
+                pat.matches_with(s, opts)
+            }
+            Pattern::Glob(_, tokens) | Pattern::Extended(_, tokens) => {
+                Self::matches_tokens(tokens, s, opts)
+            }
+        }
+    }
+
+    fn matches_tokens(tokens: &[Token], s: &str, opts: MatchOptions) -> bool {
+        let text = s.chars().collect::<Vec<_>>();
+
+        let leading_dot = text.first() == Some(&'.');
+        if opts.require_literal_leading_dot
+            && leading_dot
+            && tokens.first() != Some(&Token::Char('.'))
+        {
+            return false;
+        }
+
+        Matcher::new(opts).tokens(tokens, &text)
+    }
+}
+
+/// Escapes any special characters of `s` such that it only ever matches
+/// literally, even as part of a pattern using extended syntax.
+///
+/// Unlike `glob::Pattern::escape`, the parentheses and `|` of extended groups
+/// are escaped as well, so that neither `@(a|b)` nor an unquoted `@` followed
+/// by a quoted `(a|b)` can open a group.
+pub(crate) fn escape_pattern(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '?' | '*' | '[' | ']' | '(' | '|' | ')' => {
+                ret.push('[');
+                ret.push(c);
+                ret.push(']');
+            }
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Indicates if a pattern contains any extended groups, e.g. `@(`.
+pub(crate) fn has_extended_group(pat: &str) -> bool {
+    let mut chars = pat.chars().peekable();
+    while let Some(c) = chars.next() {
+        if GroupKind::from_char(c).is_some() && chars.peek() == Some(&'(') {
+            return true;
+        }
+    }
+    false
+}
+
+/// The kind of an extended group, determined by the character preceding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GroupKind {
+    /// `?(list)`: matches zero or one occurrence of the alternatives.
+    ZeroOrOne,
+    /// `*(list)`: matches zero or more occurrences of the alternatives.
+    ZeroOrMore,
+    /// `+(list)`: matches one or more occurrences of the alternatives.
+    OneOrMore,
+    /// `@(list)`: matches exactly one of the alternatives.
+    ExactlyOne,
+    /// `!(list)`: matches anything except one of the alternatives.
+    Not,
+}
+
+impl GroupKind {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '?' => Some(GroupKind::ZeroOrOne),
+            '*' => Some(GroupKind::ZeroOrMore),
+            '+' => Some(GroupKind::OneOrMore),
+            '@' => Some(GroupKind::ExactlyOne),
+            '!' => Some(GroupKind::Not),
+            _ => None,
+        }
+    }
+}
+
+/// A single element of an extended pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// A literal character.
+    Char(char),
+    /// `?`: any single character.
+    AnyChar,
+    /// `*`: any sequence of characters.
+    AnySequence,
+    /// `[...]`: any (or with `!`, none) of the specified character ranges.
+    Class(bool /* negated */, Vec<(char, char)>),
+    /// An extended group of alternatives.
+    Group(GroupKind, Vec<Vec<Token>>),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    extglob: bool,
+}
+
+impl Parser {
+    fn new(src: &str, extglob: bool) -> Self {
+        Self {
+            chars: src.chars().collect(),
+            pos: 0,
+            extglob,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Parses an entire pattern, or returns `None` if any group is unterminated.
+    fn parse(mut self) -> Option<Vec<Token>> {
+        let tokens = self.sequence(false)?;
+        debug_assert_eq!(self.pos, self.chars.len());
+        Some(tokens)
+    }
+
+    /// Parses a sequence of tokens up to the end of the pattern or, if `in_group`
+    /// is set, up to the (unconsumed) `|` or `)` which ends the current alternative.
+    fn sequence(&mut self, in_group: bool) -> Option<Vec<Token>> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if in_group && (c == '|' || c == ')') {
+                return Some(tokens);
+            }
+
+            self.pos += 1;
+            let kind = GroupKind::from_char(c).filter(|_| self.extglob && self.peek() == Some('('));
+            let token = match (kind, c) {
+                (Some(kind), _) => {
+                    self.pos += 1;
+                    Token::Group(kind, self.alternatives()?)
+                }
+                (None, '?') => Token::AnyChar,
+                (None, '*') => Token::AnySequence,
+                (None, '[') => self.class().unwrap_or(Token::Char('[')),
+                (None, c) => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+
+        if in_group {
+            None
+        } else {
+            Some(tokens)
+        }
+    }
+
+    /// Parses the `|` separated alternatives of a group, consuming its closing `)`.
+    fn alternatives(&mut self) -> Option<Vec<Vec<Token>>> {
+        let mut alternatives = Vec::new();
+
+        loop {
+            alternatives.push(self.sequence(true)?);
+            match self.peek() {
+                Some('|') => self.pos += 1,
+                Some(')') => {
+                    self.pos += 1;
+                    return Some(alternatives);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Parses a character class following a `[`, leaving the position
+    /// unchanged if the class is unterminated.
+    fn class(&mut self) -> Option<Token> {
+        let start = self.pos;
+        let mut pos = start;
+
+        let negated = self.chars.get(pos) == Some(&'!');
+        if negated {
+            pos += 1;
+        }
+
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let lo = *self.chars.get(pos)?;
+            if lo == ']' && !first {
+                break;
+            }
+            first = false;
+
+            match (self.chars.get(pos + 1), self.chars.get(pos + 2)) {
+                (Some('-'), Some(&hi)) if hi != ']' => {
+                    ranges.push((lo, hi));
+                    pos += 3;
+                }
+                _ => {
+                    ranges.push((lo, lo));
+                    pos += 1;
+                }
+            }
+        }
+
+        self.pos = pos + 1;
+        Some(Token::Class(negated, ranges))
+    }
+}
+
+/// The key of a memoized match: the address and length of the tokens (or
+/// alternatives) being matched, and those of the text they are matched against.
+type MemoKey = (usize, usize, usize, usize);
+
+fn memo_key<T>(tokens: &[T], text: &[char]) -> MemoKey {
+    (
+        tokens.as_ptr() as usize,
+        tokens.len(),
+        text.as_ptr() as usize,
+        text.len(),
+    )
+}
+
+/// A backtracking matcher for extended patterns.
+///
+/// Since all tokens and text being matched are slices of the same pattern and
+/// text, the result of matching any of them is memoized, thus repeated groups
+/// (e.g. `+(a|aa)`) cannot cause an exponential amount of backtracking.
+struct Matcher {
+    opts: MatchOptions,
+    tokens: RefCell<HashMap<MemoKey, bool>>,
+    repeated: RefCell<HashMap<MemoKey, bool>>,
+}
+
+impl Matcher {
+    fn new(opts: MatchOptions) -> Self {
+        Self {
+            opts,
+            tokens: RefCell::default(),
+            repeated: RefCell::default(),
+        }
+    }
+
+    fn chars_eq(&self, a: char, b: char) -> bool {
+        a == b || (!self.opts.case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn is_wildcard_match(&self, c: char) -> bool {
+        !(self.opts.require_literal_separator && c == '/')
+    }
+
+    fn in_class(&self, c: char, ranges: &[(char, char)]) -> bool {
+        let in_range = |c: char| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+
+        in_range(c)
+            || (!self.opts.case_sensitive
+                && (c.to_lowercase().any(in_range) || c.to_uppercase().any(in_range)))
+    }
+
+    /// Indicates if `tokens` match the entirety of `text`.
+    fn tokens(&self, tokens: &[Token], text: &[char]) -> bool {
+        let (first, rest) = match tokens.split_first() {
+            Some(split) => split,
+            None => return text.is_empty(),
+        };
+
+        let key = memo_key(tokens, text);
+        if let Some(&ret) = self.tokens.borrow().get(&key) {
+            return ret;
+        }
+
+        let ret = self.tokens_uncached(first, rest, text);
+        self.tokens.borrow_mut().insert(key, ret);
+        ret
+    }
+
+    fn tokens_uncached(&self, first: &Token, rest: &[Token], text: &[char]) -> bool {
+        match first {
+            Token::Char(c) => match text.split_first() {
+                Some((&t, text)) => self.chars_eq(*c, t) && self.tokens(rest, text),
+                None => false,
+            },
+            Token::AnyChar => match text.split_first() {
+                Some((&t, text)) => self.is_wildcard_match(t) && self.tokens(rest, text),
+                None => false,
+            },
+            Token::Class(negated, ranges) => match text.split_first() {
+                Some((&t, text)) => {
+                    self.is_wildcard_match(t)
+                        && self.in_class(t, ranges) != *negated
+                        && self.tokens(rest, text)
+                }
+                None => false,
+            },
+            Token::AnySequence => {
+                for i in 0..=text.len() {
+                    if self.tokens(rest, &text[i..]) {
+                        return true;
+                    }
+                    if i < text.len() && !self.is_wildcard_match(text[i]) {
+                        return false;
+                    }
+                }
+                false
+            }
+            Token::Group(kind, alternatives) => (0..=text.len()).any(|i| {
+                self.group(*kind, alternatives, &text[..i]) && self.tokens(rest, &text[i..])
+            }),
+        }
+    }
+
+    /// Indicates if a group matches the entirety of `text`.
+    fn group(&self, kind: GroupKind, alternatives: &[Vec<Token>], text: &[char]) -> bool {
+        let any = |text: &[char]| alternatives.iter().any(|alt| self.tokens(alt, text));
+
+        match kind {
+            GroupKind::ExactlyOne => any(text),
+            GroupKind::ZeroOrOne => text.is_empty() || any(text),
+            GroupKind::ZeroOrMore => text.is_empty() || self.repeated(alternatives, text),
+            GroupKind::OneOrMore => {
+                self.repeated(alternatives, text) || (text.is_empty() && any(text))
+            }
+            GroupKind::Not => !any(text),
+        }
+    }
+
+    /// Indicates if `text` is made up of one or more non-empty occurrences of
+    /// any of the alternatives.
+    fn repeated(&self, alternatives: &[Vec<Token>], text: &[char]) -> bool {
+        let key = memo_key(alternatives, text);
+        if let Some(&ret) = self.repeated.borrow().get(&key) {
+            return ret;
+        }
+
+        let ret = (1..=text.len()).any(|i| {
+            alternatives.iter().any(|alt| self.tokens(alt, &text[..i]))
+                && (i == text.len() || self.repeated(alternatives, &text[i..]))
+        });
+
+        self.repeated.borrow_mut().insert(key, ret);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pat: &str, s: &str) -> bool {
+        Pattern::new(pat, true).matches_with(s, MatchOptions::new())
+    }
+
+    #[test]
+    fn test_extended_groups() {
+        assert!(matches("@(foo|bar).rs", "foo.rs"));
+        assert!(matches("@(foo|bar).rs", "bar.rs"));
+        assert!(!matches("@(foo|bar).rs", "foobar.rs"));
+
+        assert!(matches("a?(b|c)d", "ad"));
+        assert!(matches("a?(b|c)d", "acd"));
+        assert!(!matches("a?(b|c)d", "abcd"));
+
+        assert!(matches("a*(b|c)d", "ad"));
+        assert!(matches("a*(b|c)d", "abcbd"));
+        assert!(!matches("a*(b|c)d", "abxd"));
+
+        assert!(!matches("a+(b|c)d", "ad"));
+        assert!(matches("a+(b|c)d", "acbd"));
+
+        assert!(matches("!(*.rs)", "foo.txt"));
+        assert!(!matches("!(*.rs)", "foo.rs"));
+        assert!(matches("foo.!(rs|md)", "foo.txt"));
+        assert!(!matches("foo.!(rs|md)", "foo.md"));
+
+        // Nested groups and ordinary syntax
+        assert!(matches("@(x+([0-9])|y)*", "x123z"));
+        assert!(matches("@(x+([0-9])|y)*", "y"));
+        assert!(!matches("@(x+([0-9])|y)*", "xz"));
+        assert!(matches("[!a]?([*])", "b"));
+        assert!(matches("[!a]?([*])", "b*"));
+        assert!(!matches("[!a]?([*])", "a*"));
+    }
+
+    #[test]
+    fn test_repeated_groups_do_not_backtrack_exponentially() {
+        let text = format!("{}b", "a".repeat(64));
+        assert!(!matches("+(a|aa)", &text));
+        assert!(!matches("*(*(a|aa))", &text));
+        assert!(matches("+(a|aa)b", &text));
+        assert!(!matches("!(+(a|aa)b)", &text));
+    }
+
+    #[test]
+    fn test_extended_syntax_requires_extglob() {
+        assert!(!Pattern::new("@(a|b)", false).is_extended());
+        assert!(Pattern::new("@(a|b)", false).matches_with("@(a|b)", MatchOptions::new()));
+
+        assert!(Pattern::new("@(a|b)", true).is_extended());
+        assert!(!Pattern::new("plain*", true).is_extended());
+
+        // Unterminated groups fall back to ordinary patterns
+        assert!(!Pattern::new("@(a|b", true).is_extended());
+        assert!(matches("@(a|b", "@(a|b"));
+    }
+
+    #[test]
+    fn test_extended_match_options() {
+        let opts = MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: true,
+            require_literal_leading_dot: true,
+        };
+        let pat = Pattern::new("@(FOO|[A-C]x)*", true);
+
+        assert!(pat.matches_with("foo", opts));
+        assert!(pat.matches_with("bX.rs", opts));
+        assert!(!pat.matches_with("foo/bar", opts));
+        assert!(!Pattern::new("!(a)", true).matches_with(".hidden", opts));
+        assert!(Pattern::new(".!(a)", true).matches_with(".hidden", opts));
+    }
+
+    #[test]
+    fn test_case_insensitive_matching_folds_unicode() {
+        let opts = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+
+        for &extglob in &[false, true] {
+            let pat = Pattern::new("\u{c9}t\u{c9}*[\u{c0}-\u{c5}]", extglob);
+            assert!(!pat.is_extended());
+            assert!(pat.matches_with("\u{e9}t\u{e9} \u{e0}", opts));
+            assert!(pat.matches_with("\u{c9}T\u{c9}\u{c2}", opts));
+            assert!(!pat.matches_with("\u{e9}t\u{e9} \u{e0}", MatchOptions::new()));
+
+            // Groups are only recognized with `extglob`
+            let pat = Pattern::new("@(\u{e9})", extglob);
+            assert_eq!(pat.matches_with("@(\u{c9})", opts), !extglob);
+            assert_eq!(pat.matches_with("\u{c9}", opts), extglob);
+        }
+
+        let pat = Pattern::new("@(\u{c9}T\u{c9}|hiver)", true);
+        assert!(pat.matches_with("\u{e9}t\u{e9}", opts));
+        assert!(pat.matches_with("HIVER", opts));
+    }
+}
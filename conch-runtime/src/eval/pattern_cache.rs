@@ -0,0 +1,111 @@
+//! A small cache of compiled glob patterns.
+
+use crate::eval::pattern::Pattern;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The maximum number of compiled patterns retained by each thread.
+const CAPACITY: usize = 64;
+
+/// Recently compiled patterns keyed by their source text and whether extended
+/// syntax was enabled.
+#[derive(Default)]
+struct Cache {
+    /// Each pattern along with the tick at which it was last used, split by
+    /// whether extended syntax was enabled so that lookups can be made with a
+    /// borrowed pattern without allocating a key.
+    patterns: [HashMap<String, (Arc<Pattern>, u64)>; 2],
+    /// Incremented on every lookup to order the entries by recency.
+    tick: u64,
+}
+
+impl Cache {
+    fn len(&self) -> usize {
+        self.patterns.iter().map(HashMap::len).sum()
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+/// Compiles a pattern, treating it as a literal if it is not a valid pattern.
+/// Extended syntax (e.g. `@(a|b)`) is only recognized if `extglob` is set.
+///
+/// Recently compiled patterns are cached, so evaluating the same pattern
+/// repeatedly (e.g. a `case` arm or `${var%pat}` within a loop) does not
+/// require recompiling it each time.
+pub(crate) fn compile_pattern(pat: &str, extglob: bool) -> Arc<Pattern> {
+    CACHE.with(|cache| {
+        let cache = &mut *cache.borrow_mut();
+        cache.tick += 1;
+        let tick = cache.tick;
+
+        if let Some((pattern, last_used)) = cache.patterns[extglob as usize].get_mut(pat) {
+            *last_used = tick;
+            return pattern.clone();
+        }
+
+        if cache.len() >= CAPACITY {
+            let lru = cache
+                .patterns
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, patterns)| patterns.iter().map(move |entry| (idx, entry)))
+                .min_by_key(|(_, (_, (_, last_used)))| *last_used)
+                .map(|(idx, (key, _))| (idx, key.clone()));
+
+            if let Some((idx, lru)) = lru {
+                cache.patterns[idx].remove(&lru);
+            }
+        }
+
+        let pattern = Arc::new(Pattern::new(pat, extglob));
+        cache.patterns[extglob as usize].insert(pat.to_owned(), (pattern.clone(), tick));
+        pattern
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_cached(pat: &str) -> bool {
+        CACHE.with(|cache| cache.borrow().patterns[false as usize].contains_key(pat))
+    }
+
+    fn matches(pat: &str, s: &str) -> bool {
+        compile_pattern(pat, false).matches_with(s, glob::MatchOptions::new())
+    }
+
+    #[test]
+    fn test_compile_pattern() {
+        assert!(matches("foo*", "foobar"));
+        assert!(matches("foo*", "foobaz"));
+        assert!(is_cached("foo*"));
+
+        // Invalid patterns are treated as literals
+        assert!(matches("[foo", "[foo"));
+        assert!(matches("[foo", "[foo"));
+    }
+
+    #[test]
+    fn test_least_recently_used_patterns_are_evicted() {
+        compile_pattern("first", false);
+        compile_pattern("second", false);
+
+        for i in 0..(CAPACITY - 2) {
+            compile_pattern(&format!("pat{}", i), false);
+        }
+
+        // Touch the first pattern so the second is evicted instead
+        compile_pattern("first", false);
+        compile_pattern("overflow", false);
+
+        assert!(is_cached("first"));
+        assert!(!is_cached("second"));
+        assert!(is_cached("overflow"));
+        CACHE.with(|cache| assert_eq!(cache.borrow().len(), CAPACITY));
+    }
+}
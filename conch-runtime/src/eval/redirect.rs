@@ -1,15 +1,16 @@
 //! A module which defines evaluating any kind of redirection.
 
 use crate::env::{
-    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment,
-    StringWrapper, WorkingDirectoryEnvironment,
+    FileDescEnvironment, FileDescManagerEnvironment, FileDescOpener, IsInteractiveEnvironment,
+    RedirectEnvRestorer, ShellOption, ShellOptionsEnvironment, StringWrapper, TempFileEnvironment,
+    VariableEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::error::RedirectionError;
 use crate::eval::{Fields, TildeExpansion, WordEval, WordEvalConfig};
 use crate::io::Permissions;
-use crate::{Fd, STDIN_FILENO, STDOUT_FILENO};
+use crate::{Fd, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use futures_core::future::BoxFuture;
-use std::borrow::Cow;
+use std::borrow::Borrow;
 use std::fs::OpenOptions;
 use std::io;
 use std::path::Path;
@@ -23,9 +24,15 @@ pub enum RedirectAction<T> {
     /// Indicates that a descriptor should be opened with
     /// a given file handle and permissions.
     Open(Fd, T, Permissions),
-    /// Indicates that the body of a heredoc should be asynchronously written
-    /// to a file handle on a best effor basis (i.e. write as much of the body
-    /// as possible but give up on appropriate errors such as broken pipes).
+    /// Indicates that several actions should be applied in order, e.g. `&>file`
+    /// will open the same file handle as both standard output and error.
+    Multiple(Vec<RedirectAction<T>>),
+    /// Indicates that the wrapped action should persist beyond the command
+    /// it was evaluated for (e.g. `{var}>file`), instead of being restored
+    /// along with any other redirects.
+    Persistent(Box<RedirectAction<T>>),
+    /// Indicates that the body of a heredoc should be made readable through
+    /// a descriptor, by placing it into an anonymous temporary file.
     HereDoc(Fd, Vec<u8>),
 }
 
@@ -33,26 +40,75 @@ impl<T> RedirectAction<T> {
     /// Applies changes to a given environment as appropriate.
     pub fn apply<E>(self, env: &mut E) -> io::Result<()>
     where
-        E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + FileDescOpener,
+        E: ?Sized + FileDescEnvironment + TempFileEnvironment,
         E::FileHandle: From<T> + From<E::OpenedFileHandle>,
-        E::IoHandle: From<E::FileHandle>,
     {
         match self {
             RedirectAction::Close(fd) => env.close_file_desc(fd),
             RedirectAction::Open(fd, file_desc, perms) => {
                 env.set_file_desc(fd, file_desc.into(), perms)
             }
+            RedirectAction::Multiple(actions) => {
+                for action in actions {
+                    action.apply(env)?;
+                }
+            }
+            RedirectAction::Persistent(action) => action.apply(env)?,
             RedirectAction::HereDoc(fd, body) => {
-                let pipe = env.open_pipe()?;
-                env.set_file_desc(fd, pipe.reader.into(), Permissions::Read);
-
-                let writer = E::FileHandle::from(pipe.writer);
-                env.write_all_best_effort(E::IoHandle::from(writer), body);
+                let file = env.open_temp_file(&body)?;
+                env.set_file_desc(fd, file.into(), Permissions::Read);
             }
         }
 
         Ok(())
     }
+
+    /// Applies changes to the environment managed by a restorer, making sure
+    /// that any persistent changes will not be restored later.
+    pub fn apply_with_restorer<'a, R, E>(self, restorer: &mut R) -> io::Result<()>
+    where
+        R: ?Sized + TempFileEnvironment + RedirectEnvRestorer<'a, E>,
+        R::FileHandle: From<T> + From<R::OpenedFileHandle>,
+        E: 'a + ?Sized + FileDescEnvironment,
+    {
+        let mut persistent_fds = Vec::new();
+        self.collect_fds(false, &mut persistent_fds);
+
+        self.apply(restorer)?;
+        for fd in persistent_fds {
+            restorer.forget_redirect(fd);
+        }
+
+        Ok(())
+    }
+
+    /// Describes the (first) descriptor affected by this action, for use as
+    /// the context of any errors raised while applying it.
+    pub(crate) fn describe(&self) -> Option<String> {
+        let mut fds = Vec::new();
+        self.collect_fds(true, &mut fds);
+        fds.first().map(|fd| format!("file descriptor {}", fd))
+    }
+
+    /// Collects the descriptors affected by this action, or only those
+    /// affected by persistent actions if `all` is `false`.
+    fn collect_fds(&self, all: bool, fds: &mut Vec<Fd>) {
+        match self {
+            RedirectAction::Close(fd)
+            | RedirectAction::Open(fd, _, _)
+            | RedirectAction::HereDoc(fd, _) => {
+                if all {
+                    fds.push(*fd);
+                }
+            }
+            RedirectAction::Multiple(actions) => {
+                for action in actions {
+                    action.collect_fds(all, fds);
+                }
+            }
+            RedirectAction::Persistent(action) => action.collect_fds(true, fds),
+        }
+    }
 }
 
 /// A trait for evaluating file descriptor redirections.
@@ -137,19 +193,85 @@ where
         + FileDescOpener
         + IsInteractiveEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
     let requested_path = join_path!(eval_path(path, env).await?);
-    let actual_path =
-        env.path_relative_to_working_dir(Cow::Borrowed(Path::new(requested_path.as_str())));
+    let fdesc = open_redirect_path(requested_path.as_str(), perms, opts, env)?;
+    Ok(RedirectAction::Open(fd, fdesc, perms))
+}
+
+/// Determines which shell descriptor a special path such as `/dev/stdout`
+/// or `/dev/fd/3` refers to, if any.
+fn special_path_fd(path: &str) -> Option<Fd> {
+    match path {
+        "/dev/stdin" => Some(STDIN_FILENO),
+        "/dev/stdout" => Some(STDOUT_FILENO),
+        "/dev/stderr" => Some(STDERR_FILENO),
+        _ => path
+            .strip_prefix("/dev/fd/")
+            .and_then(|fd| Fd::from_str_radix(fd, 10).ok()),
+    }
+}
 
-    let ret = env
+/// Opens the file at `requested_path` (relative to the current working
+/// directory) for use as a redirect.
+///
+/// Special paths which refer to descriptors (i.e. `/dev/stdin`, `/dev/stdout`,
+/// `/dev/stderr`, and `/dev/fd/N`) resolve to the shell's own descriptors,
+/// regardless of whether the OS supports them or not.
+fn open_redirect_path<E>(
+    requested_path: &str,
+    perms: Permissions,
+    opts: &OpenOptions,
+    env: &mut E,
+) -> Result<E::FileHandle, RedirectionError>
+where
+    E: ?Sized + FileDescEnvironment + FileDescOpener + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
+{
+    if let Some(fd) = special_path_fd(requested_path) {
+        return env
+            .file_desc(fd)
+            .map(|(fdes, _)| fdes.clone())
+            .ok_or_else(|| RedirectionError::BadFdSrc(requested_path.to_owned()));
+    }
+
+    let actual_path = env.resolve(Path::new(requested_path));
+
+    env
         // FIXME: on unix set file permission bits based on umask
-        .open_path(&*actual_path, &opts)
-        .map(|fdesc| RedirectAction::Open(fd, E::FileHandle::from(fdesc), perms))
-        .map_err(|err| RedirectionError::Io(err, Some(requested_path.into_owned())));
+        .open_path_with_permissions(&actual_path, perms, opts)
+        .map(E::FileHandle::from)
+        .map_err(|err| RedirectionError::Io(err, Some(requested_path.to_owned())))
+}
+
+/// Opens a file which both standard output and standard error will be
+/// redirected to.
+async fn redirect_all<W, E>(
+    path: W,
+    opts: &OpenOptions,
+    env: &mut E,
+) -> Result<RedirectAction<E::FileHandle>, W::Error>
+where
+    W: WordEval<E>,
+    W::Error: From<RedirectionError>,
+    E: ?Sized
+        + FileDescEnvironment
+        + FileDescOpener
+        + IsInteractiveEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
+{
+    let requested_path = join_path!(eval_path(path, env).await?);
+    let fdesc = open_redirect_path(requested_path.as_str(), Permissions::Write, opts, env)?;
+    Ok(open_stdout_and_stderr(fdesc))
+}
 
-    Ok(ret?)
+fn open_stdout_and_stderr<T: Clone>(fdesc: T) -> RedirectAction<T> {
+    RedirectAction::Multiple(vec![
+        RedirectAction::Open(STDOUT_FILENO, fdesc.clone(), Permissions::Write),
+        RedirectAction::Open(STDERR_FILENO, fdesc, Permissions::Write),
+    ])
 }
 
 /// Evaluate a redirect which will open a file for reading.
@@ -168,7 +290,7 @@ where
         + FileDescOpener
         + IsInteractiveEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
     let fd = fd.unwrap_or(STDIN_FILENO);
     let perms = Permissions::Read;
@@ -177,11 +299,12 @@ where
 }
 
 /// Evaluate a redirect which will open a file for writing, failing if the
-/// `noclobber` option is set.
+/// `noclobber` option is set and the file already exists.
 ///
 /// If `fd` is not specified, then `STDOUT_FILENO` will be used.
 ///
-/// > *Note*: checks for `noclobber` are not yet implemented.
+/// Note that `noclobber` only protects regular files: other existing files
+/// (e.g. `/dev/null`) can still be opened for writing.
 pub async fn redirect_write<W, E>(
     fd: Option<Fd>,
     path: W,
@@ -194,11 +317,33 @@ where
         + FileDescEnvironment
         + FileDescOpener
         + IsInteractiveEnvironment
+        + ShellOptionsEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
-    // FIXME: check for and fail if noclobber option is set
-    redirect_clobber(fd, path, env).await
+    if !env.is_option_enabled(ShellOption::NoClobber) {
+        return redirect_clobber(fd, path, env).await;
+    }
+
+    let fd = fd.unwrap_or(STDOUT_FILENO);
+    let perms = Permissions::Write;
+
+    let requested_path = join_path!(eval_path(path, env).await?);
+    let mut opts: OpenOptions = perms.into();
+    match env.path_metadata(Path::new(requested_path.as_str())) {
+        _ if special_path_fd(requested_path.as_str()).is_some() => {}
+        Ok(ref metadata) if metadata.is_file() => {
+            return Err(RedirectionError::NoClobber(requested_path.into_owned()).into());
+        }
+        // Avoid racing with anyone else creating the file in the mean time
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            opts.create_new(true);
+        }
+        _ => {}
+    }
+
+    let fdesc = open_redirect_path(requested_path.as_str(), perms, &opts, env)?;
+    Ok(RedirectAction::Open(fd, fdesc, perms))
 }
 
 /// Evaluate a redirect which will open a file for reading and writing.
@@ -217,7 +362,7 @@ where
         + FileDescOpener
         + IsInteractiveEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
     let fd = fd.unwrap_or(STDIN_FILENO);
     let perms = Permissions::ReadWrite;
@@ -242,7 +387,7 @@ where
         + FileDescOpener
         + IsInteractiveEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
     let fd = fd.unwrap_or(STDOUT_FILENO);
     let perms = Permissions::Write;
@@ -266,7 +411,7 @@ where
         + FileDescOpener
         + IsInteractiveEnvironment
         + WorkingDirectoryEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
 {
     let fd = fd.unwrap_or(STDOUT_FILENO);
     let mut opts = OpenOptions::new();
@@ -288,8 +433,19 @@ where
     E::FileHandle: Clone,
 {
     let src_fd = join_path!(eval_path(src_fd, env).await?);
-    let src_fd = src_fd.as_str();
+    Ok(dup_fd(dst_fd, src_fd.as_str(), readable, env)?)
+}
 
+fn dup_fd<E>(
+    dst_fd: Fd,
+    src_fd: &str,
+    readable: bool,
+    env: &E,
+) -> Result<RedirectAction<E::FileHandle>, RedirectionError>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
     if src_fd == "-" {
         return Ok(RedirectAction::Close(dst_fd));
     }
@@ -303,11 +459,11 @@ where
             if (readable && perms.readable()) || (!readable && perms.writable()) {
                 fdes.clone()
             } else {
-                return Err(RedirectionError::BadFdPerms(fd, perms).into());
+                return Err(RedirectionError::BadFdPerms(fd, perms));
             }
         }
 
-        None => return Err(RedirectionError::BadFdSrc(src_fd.to_owned()).into()),
+        None => return Err(RedirectionError::BadFdSrc(src_fd.to_owned())),
     };
 
     let perms = if readable {
@@ -357,6 +513,82 @@ where
     redirect_dup(dst_fd.unwrap_or(STDOUT_FILENO), src_fd, false, env).await
 }
 
+/// Evaluate a redirect which will open a file for writing, and redirect both
+/// standard output and standard error to it (i.e. `&>word`).
+pub async fn redirect_write_all<W, E>(
+    path: W,
+    env: &mut E,
+) -> Result<RedirectAction<E::FileHandle>, W::Error>
+where
+    W: WordEval<E>,
+    W::Error: From<RedirectionError>,
+    E: ?Sized
+        + FileDescEnvironment
+        + FileDescOpener
+        + IsInteractiveEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
+{
+    redirect_all(path, &Permissions::Write.into(), env).await
+}
+
+/// Evaluate a redirect which will open a file in append mode, and redirect
+/// both standard output and standard error to it (i.e. `&>>word`).
+pub async fn redirect_append_all<W, E>(
+    path: W,
+    env: &mut E,
+) -> Result<RedirectAction<E::FileHandle>, W::Error>
+where
+    W: WordEval<E>,
+    W::Error: From<RedirectionError>,
+    E: ?Sized
+        + FileDescEnvironment
+        + FileDescOpener
+        + IsInteractiveEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
+{
+    let mut opts = OpenOptions::new();
+    opts.append(true);
+
+    redirect_all(path, &opts, env).await
+}
+
+/// Evaluate a `[n]>&word` redirect, which behaves like `redirect_dup_write`
+/// if `word` evaluates to a file descriptor or `-`.
+///
+/// Otherwise, if `fd` is not specified, both standard output and standard
+/// error are redirected to the file `word` (just like `&>word`). Specifying
+/// `fd` in this case results in a `RedirectionError::BadFdSrc` error.
+pub async fn redirect_dup_write_or_all<W, E>(
+    fd: Option<Fd>,
+    word: W,
+    env: &mut E,
+) -> Result<RedirectAction<E::FileHandle>, W::Error>
+where
+    W: WordEval<E>,
+    W::Error: From<RedirectionError>,
+    E: ?Sized
+        + FileDescEnvironment
+        + FileDescOpener
+        + IsInteractiveEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + From<E::OpenedFileHandle>,
+{
+    let word = join_path!(eval_path(word, env).await?);
+    let word = word.as_str();
+
+    if word == "-" || Fd::from_str_radix(word, 10).is_ok() {
+        return Ok(dup_fd(fd.unwrap_or(STDOUT_FILENO), word, false, env)?);
+    } else if fd.is_some() {
+        return Err(RedirectionError::BadFdSrc(word.to_owned()).into());
+    }
+
+    let perms = Permissions::Write;
+    let fdesc = open_redirect_path(word, perms, &perms.into(), env)?;
+    Ok(open_stdout_and_stderr(fdesc))
+}
+
 /// Evaluate a redirect which write the body of a *here-document* into `fd`.
 ///
 /// If `fd` is not specified, then `STDIN_FILENO` will be used.
@@ -393,3 +625,91 @@ where
 
     Ok(RedirectAction::HereDoc(fd.unwrap_or(STDIN_FILENO), body))
 }
+
+/// Evaluate a redirect which will write a *here-string* into `fd`.
+///
+/// The word is expanded without any further field splitting (multiple fields
+/// are joined with spaces), and a trailing newline is appended, before being
+/// written to `fd` just like the body of a *here-document*.
+///
+/// If `fd` is not specified, then `STDIN_FILENO` will be used.
+pub async fn redirect_herestring<W, E>(
+    fd: Option<Fd>,
+    word: W,
+    env: &mut E,
+) -> Result<RedirectAction<E::FileHandle>, W::Error>
+where
+    W: WordEval<E>,
+    E: ?Sized + FileDescEnvironment + IsInteractiveEnvironment,
+{
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::First,
+        split_fields_further: false,
+    };
+
+    let mut body = match word.eval_with_config(env, cfg).await?.await {
+        Fields::Zero => Vec::new(),
+        Fields::Single(field) => field.into_owned().into_bytes(),
+        Fields::At(v) | Fields::Star(v) | Fields::Split(v) => {
+            let fields = v.iter().map(StringWrapper::as_str).collect::<Vec<_>>();
+            fields.join(" ").into_bytes()
+        }
+    };
+
+    body.push(b'\n');
+    Ok(RedirectAction::HereDoc(fd.unwrap_or(STDIN_FILENO), body))
+}
+
+/// Evaluate a `{var}>word`-style redirect, where `redirect` is the redirect
+/// which would be applied to an arbitrary descriptor (e.g. `>word`).
+///
+/// Rather than using the descriptor `redirect` would normally use, the lowest
+/// unused descriptor greater than or equal to 10 is used instead, and its
+/// number is stored in the variable `var`. If `redirect` would close its
+/// descriptor (e.g. `{var}>&-`), the descriptor stored in `var` is closed.
+/// An I/O error is returned if every descriptor greater than or equal to 10
+/// is already in use.
+///
+/// The resulting action is `Persistent`, meaning the descriptor will remain
+/// open (or closed) even after the command it was evaluated for completes.
+pub async fn redirect_fd_var<R, E>(
+    var: &E::VarName,
+    redirect: R,
+    env: &mut E,
+) -> Result<RedirectAction<R::Handle>, R::Error>
+where
+    R: RedirectEval<E>,
+    R::Error: From<RedirectionError>,
+    E: ?Sized + FileDescManagerEnvironment + VariableEnvironment,
+    E::VarName: StringWrapper,
+    E::Var: Borrow<String> + From<String>,
+{
+    let bad_var = || RedirectionError::BadFdVar(var.as_str().to_owned());
+    let no_fd = |e| RedirectionError::Io(e, Some(var.as_str().to_owned()));
+
+    let action = match redirect.eval(env).await? {
+        RedirectAction::Close(_) => {
+            let fd = env
+                .var(var.borrow())
+                .and_then(|fd| Fd::from_str_radix(fd.borrow(), 10).ok())
+                .ok_or_else(bad_var)?;
+
+            RedirectAction::Close(fd)
+        }
+        RedirectAction::Open(_, handle, perms) => {
+            let fd = env.unused_fd().map_err(no_fd)?;
+            RedirectAction::Open(fd, handle, perms)
+        }
+        RedirectAction::HereDoc(_, body) => {
+            let fd = env.unused_fd().map_err(no_fd)?;
+            RedirectAction::HereDoc(fd, body)
+        }
+        RedirectAction::Multiple(_) | RedirectAction::Persistent(_) => return Err(bad_var().into()),
+    };
+
+    if let RedirectAction::Open(fd, _, _) | RedirectAction::HereDoc(fd, _) = action {
+        env.set_var(var.clone(), fd.to_string().into());
+    }
+
+    Ok(RedirectAction::Persistent(Box::new(action)))
+}
@@ -1,8 +1,11 @@
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, RedirectEnvRestorer};
-use crate::error::{IsFatalError, RedirectionError};
-use crate::eval::{RedirectEval, WordEval};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, RedirectEnvRestorer, ShellOptionsEnvironment,
+    TempFileEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::{ExpansionError, IsFatalError, RedirectionError};
+use crate::eval::{glob_word, RedirectEval, WordEval};
 use std::error::Error;
 
 /// Represents a redirect or a command word.
@@ -49,6 +52,8 @@ where
 /// a `RedirectEnvRestorer` will be returned which allows the caller to reverse the
 /// changes from applying these redirections. On error, the redirections will
 /// be automatically restored.
+///
+/// Shell words are subject to pathname expansion (see `glob_word`).
 pub async fn eval_redirects_or_cmd_words_with_restorer<'a, R, W, I, E, RR>(
     restorer: &mut RR,
     words: I,
@@ -58,9 +63,20 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
-    E: 'a + ?Sized + Send + Sync + FileDescEnvironment,
-    RR: ?Sized + Send + Sync + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    W::Error: 'static + Error + From<ExpansionError>,
+    E: 'a
+        + ?Sized
+        + Send
+        + Sync
+        + FileDescEnvironment
+        + ShellOptionsEnvironment
+        + WorkingDirectoryEnvironment,
+    RR: ?Sized
+        + Send
+        + Sync
+        + AsyncIoEnvironment
+        + TempFileEnvironment
+        + RedirectEnvRestorer<'a, E>,
     RR::FileHandle: From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
 {
@@ -88,19 +104,24 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
-    E: 'a + ?Sized + Send + Sync + FileDescEnvironment,
-    RR: ?Sized + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    W::Error: 'static + Error + From<ExpansionError>,
+    E: 'a
+        + ?Sized
+        + Send
+        + Sync
+        + FileDescEnvironment
+        + ShellOptionsEnvironment
+        + WorkingDirectoryEnvironment,
+    RR: ?Sized + AsyncIoEnvironment + TempFileEnvironment + RedirectEnvRestorer<'a, E>,
     RR::FileHandle: From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
 {
     match candidate {
         RedirectOrCmdWord::CmdWord(w) => {
-            let fields = w
-                .eval(restorer.get_mut())
+            let fields = glob_word(&w, restorer.get_mut())
                 .await
                 .map_err(EvalRedirectOrCmdWordError::CmdWord)?;
-            results.extend(fields.await);
+            results.extend(fields);
         }
         RedirectOrCmdWord::Redirect(r) => {
             let action = r
@@ -108,8 +129,9 @@ where
                 .await
                 .map_err(EvalRedirectOrCmdWordError::Redirect)?;
 
-            if let Err(e) = action.apply(restorer) {
-                let err = R::Error::from(RedirectionError::Io(e, None));
+            let context = action.describe();
+            if let Err(e) = action.apply_with_restorer(restorer) {
+                let err = R::Error::from(RedirectionError::Io(e, context));
                 return Err(EvalRedirectOrCmdWordError::Redirect(err));
             }
         }
@@ -1,11 +1,12 @@
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
 use crate::env::{
-    AsyncIoEnvironment, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener,
-    RedirectEnvRestorer, VarEnvRestorer, VariableEnvironment,
+    AsyncIoEnvironment, DynamicVariableEnvironment, ExportedVariableEnvironment,
+    FileDescEnvironment, RedirectEnvRestorer, StrKey, TempFileEnvironment, VarEnvRestorer,
+    VariableAttributesEnvironment,
 };
-use crate::error::{IsFatalError, RedirectionError};
-use crate::eval::{eval_as_assignment, RedirectEval, WordEval};
+use crate::error::{ExpansionError, IsFatalError, RedirectionError};
+use crate::eval::{assignable_value, eval_as_assignment, RedirectEval, WordEval};
 use std::borrow::Borrow;
 use std::error::Error;
 
@@ -67,14 +68,20 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
-    E: 'a + ?Sized + Send + Sync + FileDescEnvironment + VariableEnvironment,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
+    W::Error: 'static + Error + From<ExpansionError>,
+    E: 'a
+        + ?Sized
+        + Send
+        + Sync
+        + DynamicVariableEnvironment
+        + FileDescEnvironment
+        + VariableAttributesEnvironment,
+    E::VarName: StrKey + From<V> + From<String>,
+    E::Var: Borrow<String> + From<W::EvalResult> + From<String>,
     RR: ?Sized
         + Send
         + AsyncIoEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
@@ -107,13 +114,19 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
-    E: 'a + ?Sized + Send + Sync + FileDescEnvironment + VariableEnvironment,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
+    W::Error: 'static + Error + From<ExpansionError>,
+    E: 'a
+        + ?Sized
+        + Send
+        + Sync
+        + DynamicVariableEnvironment
+        + FileDescEnvironment
+        + VariableAttributesEnvironment,
+    E::VarName: StrKey + From<V> + From<String>,
+    E::Var: Borrow<String> + From<W::EvalResult> + From<String>,
     RR: ?Sized
         + AsyncIoEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
@@ -130,7 +143,9 @@ where
             };
 
             let key = E::VarName::from(key);
-            let val = E::Var::from(val);
+            let val = assignable_value(&key, E::Var::from(val), restorer.get_mut())
+                .map_err(|e| EvalRedirectOrVarAssigError::VarAssig(e.into()))?;
+
             match export_vars {
                 Some(export) => restorer.set_exported_var(key, val, export),
                 None => restorer.set_var(key, val),
@@ -142,8 +157,9 @@ where
                 .await
                 .map_err(EvalRedirectOrVarAssigError::Redirect)?;
 
-            if let Err(e) = action.apply(restorer) {
-                let err = R::Error::from(RedirectionError::Io(e, None));
+            let context = action.describe();
+            if let Err(e) = action.apply_with_restorer(restorer) {
+                let err = R::Error::from(RedirectionError::Io(e, context));
                 return Err(EvalRedirectOrVarAssigError::Redirect(err));
             }
         }
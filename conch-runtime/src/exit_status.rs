@@ -1,3 +1,4 @@
+use crate::sys;
 use std::fmt;
 use std::process;
 
@@ -12,6 +13,7 @@ pub const EXIT_CMD_NOT_FOUND: ExitStatus = ExitStatus::Code(127);
 
 /// Describes the result of a process after it has terminated.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExitStatus {
     /// Normal termination with an exit code.
     Code(i32),
@@ -20,6 +22,12 @@ pub enum ExitStatus {
     ///
     /// Never generated on Windows.
     Signal(i32),
+
+    /// Termination by signal which also produced a core dump, with the
+    /// signal number.
+    ///
+    /// Never generated on Windows.
+    CoreDumped(i32),
 }
 
 impl ExitStatus {
@@ -28,13 +36,58 @@ impl ExitStatus {
     pub fn success(self) -> bool {
         self == EXIT_SUCCESS
     }
+
+    /// The exit code, if terminated normally.
+    pub fn code(self) -> Option<i32> {
+        match self {
+            ExitStatus::Code(code) => Some(code),
+            ExitStatus::Signal(_) | ExitStatus::CoreDumped(_) => None,
+        }
+    }
+
+    /// The number of the signal which caused termination, if any.
+    pub fn signal(self) -> Option<i32> {
+        match self {
+            ExitStatus::Code(_) => None,
+            ExitStatus::Signal(signal) | ExitStatus::CoreDumped(signal) => Some(signal),
+        }
+    }
+
+    /// Indicates if termination by a signal also produced a core dump.
+    pub fn core_dumped(self) -> bool {
+        match self {
+            ExitStatus::CoreDumped(_) => true,
+            ExitStatus::Code(_) | ExitStatus::Signal(_) => false,
+        }
+    }
+
+    /// The name of the signal which caused termination (e.g. `"SIGINT"`),
+    /// if any, and if it is known on the current platform.
+    pub fn signal_name(self) -> Option<String> {
+        let signal = self.signal()?;
+        sys::SIGNALS
+            .iter()
+            .find(|&&(_, num)| num == signal)
+            .map(|&(name, _)| format!("SIG{}", name))
+    }
 }
 
 impl fmt::Display for ExitStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             ExitStatus::Code(code) => write!(f, "exit code: {}", code),
-            ExitStatus::Signal(code) => write!(f, "signal: {}", code),
+            ExitStatus::Signal(signal) | ExitStatus::CoreDumped(signal) => {
+                match self.signal_name() {
+                    Some(name) => write!(f, "signal: {}", name)?,
+                    None => write!(f, "signal: {}", signal)?,
+                }
+
+                if self.core_dumped() {
+                    f.write_str(" (core dumped)")?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
@@ -42,18 +95,58 @@ impl fmt::Display for ExitStatus {
 impl From<process::ExitStatus> for ExitStatus {
     fn from(exit: process::ExitStatus) -> ExitStatus {
         #[cfg(unix)]
-        fn get_signal(exit: process::ExitStatus) -> Option<i32> {
-            ::std::os::unix::process::ExitStatusExt::signal(&exit)
+        fn get_signal(exit: process::ExitStatus) -> Option<ExitStatus> {
+            use std::os::unix::process::ExitStatusExt;
+
+            let signal = exit.signal()?;
+            if libc::WCOREDUMP(exit.into_raw()) {
+                Some(ExitStatus::CoreDumped(signal))
+            } else {
+                Some(ExitStatus::Signal(signal))
+            }
         }
 
         #[cfg(windows)]
-        fn get_signal(_exit: process::ExitStatus) -> Option<i32> {
+        fn get_signal(_exit: process::ExitStatus) -> Option<ExitStatus> {
             None
         }
 
         match exit.code() {
             Some(code) => ExitStatus::Code(code),
-            None => get_signal(exit).map_or(EXIT_ERROR, ExitStatus::Signal),
+            None => get_signal(exit).unwrap_or(EXIT_ERROR),
         }
     }
 }
+
+impl From<ExitStatus> for process::ExitStatus {
+    fn from(exit: ExitStatus) -> process::ExitStatus {
+        #[cfg(unix)]
+        fn from_status(exit: ExitStatus) -> process::ExitStatus {
+            use std::os::unix::process::ExitStatusExt;
+
+            // Encoded in the same way as the raw status reported by `waitpid`
+            let raw = match exit {
+                ExitStatus::Code(code) => (code & 0xff) << 8,
+                ExitStatus::Signal(signal) => signal & 0x7f,
+                ExitStatus::CoreDumped(signal) => (signal & 0x7f) | 0x80,
+            };
+
+            process::ExitStatus::from_raw(raw)
+        }
+
+        #[cfg(windows)]
+        fn from_status(exit: ExitStatus) -> process::ExitStatus {
+            use std::os::windows::process::ExitStatusExt;
+
+            // Signals are reported like `$?` would report them
+            let code = match exit {
+                ExitStatus::Code(code) => code,
+                ExitStatus::Signal(signal) | ExitStatus::CoreDumped(signal) => signal + 128,
+            };
+
+            process::ExitStatus::from_raw(code as u32)
+        }
+
+        from_status(exit)
+    }
+}
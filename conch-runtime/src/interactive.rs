@@ -0,0 +1,487 @@
+//! A driver for interactive shells, tying together reading input, parsing,
+//! prompting, and executing commands.
+//!
+//! Building a shell binary only requires providing a `LineSource` (e.g. a
+//! line editor) and an environment to a `Repl`. Embedders which need more
+//! control over any of these steps can still wire the individual pieces
+//! together themselves.
+//!
+//! With the `rustyline` feature enabled, `RustylineSource` offers a line
+//! editor ready to be used by a `Repl`.
+
+use crate::env::prompt::{expand_ps1, expand_ps2};
+use crate::env::{
+    ClockEnvironment, ControlFlowEnvironment, DynamicVariableEnvironment, HistoryEnvironment,
+    LastStatusEnvironment, ReportErrorEnvironment, ShellOptionsEnvironment, StrKey,
+    VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::{ControlFlow, IsFatalError, WithLocation};
+use crate::io::blocking_io;
+use crate::script::{parse, run_rc_file, RcFile};
+use crate::spawn::swallow_non_fatal_errors;
+use crate::{ExitStatus, Spawn, EXIT_ERROR};
+use conch_parser::ast::AtomicTopLevelCommand;
+use conch_parser::parse::ParseError;
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use void::Void;
+
+#[cfg(feature = "rustyline")]
+mod line_editor;
+
+#[cfg(feature = "rustyline")]
+pub use self::line_editor::RustylineSource;
+
+const HISTFILE: &str = "HISTFILE";
+const HISTFILESIZE: &str = "HISTFILESIZE";
+const HISTSIZE: &str = "HISTSIZE";
+
+/// A source of input lines for an interactive shell, such as a terminal
+/// line editor.
+#[async_trait::async_trait]
+pub trait LineSource {
+    /// Displays the `prompt` (if appropriate) and reads the next line of
+    /// input (without its trailing newline), or `None` if there is no more
+    /// input available.
+    async fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>>;
+
+    /// Records an entry of the shell's history, e.g. so that a line editor
+    /// can offer navigating to it. Multi-line commands are recorded as a
+    /// single entry.
+    ///
+    /// Sources which do not maintain their own history can ignore this.
+    fn add_history(&mut self, _entry: &str) {}
+}
+
+#[async_trait::async_trait]
+impl<'a, T: ?Sized + LineSource + Send> LineSource for &'a mut T {
+    async fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        (**self).read_line(prompt).await
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        (**self).add_history(entry)
+    }
+}
+
+/// A `LineSource` which yields lines from an iterator, ignoring any prompts.
+///
+/// Useful for feeding scripted input to a `Repl`.
+#[derive(Debug, Clone)]
+pub struct LineIter<I> {
+    iter: I,
+}
+
+impl<I> LineIter<I> {
+    /// Creates a new source which yields the lines of `iter`.
+    pub fn new(iter: I) -> Self {
+        Self { iter }
+    }
+}
+
+#[async_trait::async_trait]
+impl<I> LineSource for LineIter<I>
+where
+    I: Send + Iterator,
+    I::Item: Into<String>,
+{
+    async fn read_line(&mut self, _prompt: &str) -> io::Result<Option<String>> {
+        Ok(self.iter.next().map(Into::into))
+    }
+}
+
+/// The outcome of a single iteration of a `Repl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplStep {
+    /// A complete command was read and executed (or could not be parsed),
+    /// resulting in the provided status.
+    Ran(ExitStatus),
+    /// The shell was requested to exit (e.g. via the `exit` builtin) with
+    /// the provided status.
+    Exit(ExitStatus),
+    /// There is no more input to execute.
+    Eof,
+}
+
+/// An interactive read-eval-print loop.
+///
+/// Each iteration prompts for a line of input using `$PS1`, and keeps reading
+/// lines (prompting with `$PS2`) until a complete command has been entered.
+/// The command is then parsed and executed, recording its status as the last
+/// status of the environment. Any errors are reported to the environment and
+/// do not terminate the loop, unless the shell is requested to exit.
+///
+/// Each command entered is also recorded in the environment's history (as
+/// well as the history of the `LineSource`), which retains up to `$HISTSIZE`
+/// entries (if set). The history can be persisted to (and restored from) the
+/// file named by `$HISTFILE` via `save_history` and `load_history`.
+///
+/// Like bash, each saved entry is preceded by a `#` comment line holding a
+/// timestamp, which allows entries spanning multiple lines to be restored.
+/// Files without such lines are read as one entry per line. Whenever the
+/// history is saved, the file is truncated to the last `$HISTFILESIZE` entries
+/// (or `$HISTSIZE` if unset), where negative values indicate there is no limit.
+///
+/// Before reading any input, `run` executes the startup (or rc) script of the
+/// shell (by default `$CONCHRC` or `$HOME/.conchrc`, see `set_rc_file`).
+///
+/// Note that the environment is otherwise used as is, so embedders will likely
+/// want to configure it as an interactive environment beforehand.
+#[derive(Debug)]
+pub struct Repl<L, E> {
+    lines: L,
+    env: E,
+    /// The number of the oldest history entry not yet in the history file.
+    unsaved: usize,
+    /// The startup script to run, unless it has already been run.
+    rc_file: Option<RcFile>,
+}
+
+impl<L, E> Repl<L, E> {
+    /// Creates a new driver which reads input from `lines`, and executes
+    /// commands within `env`.
+    pub fn new(lines: L, env: E) -> Self {
+        Self {
+            lines,
+            env,
+            unsaved: 1,
+            rc_file: Some(RcFile::default()),
+        }
+    }
+
+    /// Sets the startup (or rc) script to run before reading any input, e.g.
+    /// `RcFile::Disabled` to skip it altogether.
+    ///
+    /// Has no effect if the loop has already been run.
+    pub fn set_rc_file(&mut self, rc: RcFile) {
+        if self.rc_file.is_some() {
+            self.rc_file = Some(rc);
+        }
+    }
+
+    /// Gets a reference to the environment in which commands are executed.
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    /// Gets a mutable reference to the environment in which commands are executed.
+    pub fn env_mut(&mut self) -> &mut E {
+        &mut self.env
+    }
+
+    /// Unwraps the input source and environment.
+    pub fn into_inner(self) -> (L, E) {
+        (self.lines, self.env)
+    }
+}
+
+impl<L, E> Repl<L, E>
+where
+    L: LineSource,
+    E: Send
+        + ClockEnvironment
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + HistoryEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String>,
+    AtomicTopLevelCommand<E::VarName>: Sync + Spawn<E>,
+    <AtomicTopLevelCommand<E::VarName> as Spawn<E>>::Error:
+        IsFatalError + WithLocation + Error + Send + Sync + 'static,
+{
+    /// Runs the loop until the end of input is reached or the shell is
+    /// requested to exit, returning the exit status of the shell.
+    ///
+    /// The startup script (see `set_rc_file`) is run the first time the loop
+    /// is run. Any errors encountered while running it are reported to the
+    /// environment, and do not prevent the loop from running.
+    pub async fn run(&mut self) -> io::Result<ExitStatus> {
+        if let Some(rc) = self.rc_file.take() {
+            if let Err(err) = run_rc_file(&rc, &mut self.env).await {
+                self.env.report_error(&err).await;
+                self.env.set_last_status(EXIT_ERROR);
+            }
+        }
+
+        loop {
+            match self.step().await? {
+                ReplStep::Ran(_) => {}
+                ReplStep::Exit(status) => return Ok(status),
+                ReplStep::Eof => return Ok(self.env.last_status()),
+            }
+        }
+    }
+
+    /// Reads, parses, and executes the next complete command.
+    ///
+    /// If the input ends before the command is complete, the resulting parse
+    /// error is reported and `ReplStep::Eof` is returned.
+    pub async fn step(&mut self) -> io::Result<ReplStep> {
+        let mut buf = String::new();
+        let mut incomplete = None;
+
+        loop {
+            let prompt = if buf.is_empty() {
+                expand_ps1(&self.env)
+            } else {
+                expand_ps2(&self.env)
+            };
+
+            let line = match self.lines.read_line(&prompt).await? {
+                Some(line) => line,
+                None => {
+                    if let Some(err) = incomplete {
+                        self.fail(&err).await;
+                    }
+
+                    return Ok(ReplStep::Eof);
+                }
+            };
+
+            buf.push_str(&line);
+            buf.push('\n');
+
+            let parsed = parse(&buf);
+            if !matches!(parsed, Err(ref err) if is_incomplete(err)) {
+                self.record_history(&buf);
+            }
+
+            match parsed {
+                Ok(cmds) => return Ok(self.execute(cmds).await),
+                Err(err) => {
+                    if is_incomplete(&err) {
+                        incomplete = Some(err);
+                    } else {
+                        self.fail(&err).await;
+                        return Ok(ReplStep::Ran(EXIT_ERROR));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends the entries in the file named by `$HISTFILE` (if set) to the
+    /// environment's history (and the history of the `LineSource`).
+    ///
+    /// A missing history file is not considered an error.
+    pub async fn load_history(&mut self) -> io::Result<()> {
+        let path = match self.history_file() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let contents = match blocking_io(move || fs::read_to_string(path)).await {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        self.apply_history_size();
+        for entry in parse_history_file(&contents) {
+            self.lines.add_history(&entry);
+            self.env.add_history(entry);
+        }
+
+        self.unsaved = self.env.history_base() + self.env.history().len();
+        Ok(())
+    }
+
+    /// Appends any history entries which were recorded since the history was
+    /// last loaded or saved to the file named by `$HISTFILE` (if set), after
+    /// which the file is truncated to the last `$HISTFILESIZE` entries.
+    pub async fn save_history(&mut self) -> io::Result<()> {
+        let path = match self.history_file() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let base = self.env.history_base();
+        let end = base + self.env.history().len();
+        if self.unsaved > end {
+            // The history has since been cleared
+            self.unsaved = base;
+        }
+
+        let timestamp = self
+            .env
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        let mut contents = String::new();
+        for entry in &self.env.history()[self.unsaved.saturating_sub(base)..] {
+            contents.push_str(&format!("#{}\n{}\n", timestamp, entry));
+        }
+
+        let limit = self.history_file_size();
+        if !contents.is_empty() {
+            blocking_io(move || {
+                let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+                file.write_all(contents.as_bytes())?;
+                drop(file);
+
+                match limit {
+                    Some(limit) => truncate_history_file(&path, limit),
+                    None => Ok(()),
+                }
+            })
+            .await?;
+        }
+
+        self.unsaved = end;
+        Ok(())
+    }
+
+    fn history_file(&self) -> Option<PathBuf> {
+        let path = E::VarName::lookup(&self.env, HISTFILE)
+            .map(|path| path.borrow().as_str())
+            .filter(|path| !path.is_empty())?;
+
+        Some(self.env.resolve(Path::new(path)))
+    }
+
+    /// The number of entries the history file should be truncated to, taken
+    /// from `$HISTFILESIZE`, or `$HISTSIZE` if the former is unset.
+    fn history_file_size(&self) -> Option<usize> {
+        let size = E::VarName::lookup(&self.env, HISTFILESIZE)
+            .or_else(|| E::VarName::lookup(&self.env, HISTSIZE))
+            .and_then(|size| size.borrow().trim().parse::<isize>().ok())?;
+
+        if size < 0 {
+            None
+        } else {
+            Some(size as usize)
+        }
+    }
+
+    /// Applies any limit imposed by `$HISTSIZE`, where negative values
+    /// indicate there is no limit.
+    fn apply_history_size(&mut self) {
+        let size = E::VarName::lookup(&self.env, HISTSIZE)
+            .and_then(|size| size.borrow().trim().parse::<isize>().ok());
+
+        match size {
+            Some(size) if size < 0 => self.env.set_history_limit(None),
+            Some(size) => self.env.set_history_limit(Some(size as usize)),
+            None => {}
+        }
+    }
+
+    fn record_history(&mut self, buf: &str) {
+        let entry = buf.trim_end_matches('\n');
+        if !entry.trim().is_empty() {
+            self.apply_history_size();
+            self.lines.add_history(entry);
+            self.env.add_history(entry.to_owned());
+        }
+    }
+
+    async fn execute(&mut self, cmds: Vec<AtomicTopLevelCommand<E::VarName>>) -> ReplStep {
+        for cmd in &cmds {
+            let status = match swallow_non_fatal_errors(cmd, &mut self.env).await {
+                Ok(future) => future.await,
+                Err(err) => {
+                    if let Some(ControlFlow::Exit(status)) = err.control_flow() {
+                        self.env.set_last_status(status);
+                        return ReplStep::Exit(status);
+                    }
+
+                    self.env.report_error(&err).await;
+                    EXIT_ERROR
+                }
+            };
+
+            self.env.set_last_status(status);
+        }
+
+        ReplStep::Ran(self.env.last_status())
+    }
+
+    async fn fail(&mut self, err: &ParseError<Void>) {
+        self.env.report_error(err).await;
+        self.env.set_last_status(EXIT_ERROR);
+    }
+}
+
+/// Indicates if a line of a history file holds the timestamp of an entry.
+fn is_timestamp(line: &str) -> bool {
+    match line.strip_prefix('#') {
+        Some(time) => !time.is_empty() && time.bytes().all(|b| b.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/// Splits the contents of a history file into its entries.
+///
+/// Lines consisting of a `#` followed by a timestamp begin a new entry, which
+/// spans all lines up to the next timestamp. Any lines which are not preceded
+/// by a timestamp are considered entries of their own.
+fn parse_history_file(contents: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        if is_timestamp(line) {
+            entries.extend(current.take().filter(|entry| !entry.is_empty()));
+            current = Some(String::new());
+        } else if let Some(entry) = current.as_mut() {
+            if !entry.is_empty() {
+                entry.push('\n');
+            }
+            entry.push_str(line);
+        } else if !line.is_empty() {
+            entries.push(line.to_owned());
+        }
+    }
+
+    entries.extend(current.filter(|entry| !entry.is_empty()));
+    entries
+}
+
+/// Rewrites the history file at `path` to only hold its last `limit` entries,
+/// keeping their timestamps intact. Files within the limit are left untouched.
+fn truncate_history_file(path: &Path, limit: usize) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+
+    let mut starts = Vec::new();
+    let mut timestamped = false;
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches('\n');
+        if is_timestamp(trimmed) {
+            timestamped = true;
+            starts.push(offset);
+        } else if !timestamped && !trimmed.is_empty() {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+
+    if starts.len() <= limit {
+        return Ok(());
+    }
+
+    let start = starts
+        .get(starts.len() - limit)
+        .copied()
+        .unwrap_or(contents.len());
+    fs::write(path, &contents[start..])
+}
+
+/// Indicates if a parse error was caused by the input ending before a
+/// command was complete, i.e. more input may complete it.
+fn is_incomplete(err: &ParseError<Void>) -> bool {
+    matches!(
+        err,
+        ParseError::UnexpectedEOF | ParseError::IncompleteCmd(..) | ParseError::Unmatched(..)
+    )
+}
@@ -0,0 +1,92 @@
+use super::LineSource;
+use crate::io::blocking_io;
+use rustyline::error::ReadlineError;
+use rustyline::history::{DefaultHistory, History};
+use rustyline::{Editor, Helper};
+use std::fmt;
+use std::io;
+
+/// A `LineSource` backed by a `rustyline` line editor, providing line editing
+/// and history navigation (of all commands recorded by the `Repl`) when
+/// reading from a terminal.
+///
+/// Reading a line blocks until it has been entered, thus each line is read on
+/// the `tokio` blocking pool.
+///
+/// Interrupting the editor (e.g. via `Ctrl-C`) yields an empty line, leaving
+/// the `Repl` free to continue prompting for input.
+pub struct RustylineSource<H: Helper = (), I: History = DefaultHistory> {
+    // NB: only `None` if a read was cancelled while the editor was in use
+    editor: Option<Editor<H, I>>,
+}
+
+impl RustylineSource {
+    /// Creates a new source backed by a default configured editor.
+    pub fn new() -> io::Result<Self> {
+        Editor::new().map(Self::with_editor).map_err(into_io_error)
+    }
+}
+
+impl<H: Helper, I: History> RustylineSource<H, I> {
+    /// Creates a new source backed by the provided editor, e.g. one which was
+    /// configured with a custom `Helper` offering completions.
+    pub fn with_editor(editor: Editor<H, I>) -> Self {
+        Self {
+            editor: Some(editor),
+        }
+    }
+
+    /// Gets a mutable reference to the underlying editor, unless a previous
+    /// read was cancelled while the editor was in use.
+    pub fn editor_mut(&mut self) -> Option<&mut Editor<H, I>> {
+        self.editor.as_mut()
+    }
+}
+
+impl<H: Helper, I: History> fmt::Debug for RustylineSource<H, I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RustylineSource").finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<H, I> LineSource for RustylineSource<H, I>
+where
+    H: 'static + Send + Helper,
+    I: 'static + Send + History,
+{
+    async fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let mut editor = self.editor.take().ok_or_else(|| {
+            io::Error::other("line editor was lost by a previously cancelled read")
+        })?;
+
+        let prompt = prompt.to_owned();
+        let (editor, result) = blocking_io(move || {
+            let result = editor.readline(&prompt);
+            Ok((editor, result))
+        })
+        .await?;
+
+        self.editor = Some(editor);
+        match result {
+            Ok(line) => Ok(Some(line)),
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+            Err(e) => Err(into_io_error(e)),
+        }
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        if let Some(editor) = self.editor.as_mut() {
+            // NB: failing to navigate to an entry later is not worth failing over
+            let _ = editor.add_history_entry(entry);
+        }
+    }
+}
+
+fn into_io_error(err: ReadlineError) -> io::Error {
+    match err {
+        ReadlineError::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
@@ -12,7 +12,9 @@ use std::process::Stdio;
 pub use self::file_desc_wrapper::FileDescWrapper;
 pub use self::permissions::Permissions;
 pub use self::pipe::Pipe;
-pub use crate::sys::io::getpid;
+pub use crate::sys::io::{getpid, getppid};
+#[cfg(unix)]
+pub use crate::sys::io::{mkfifo, recv_fd, send_fd};
 
 /// A wrapper around an owned OS file primitive. The wrapper
 /// allows reading from or writing to the OS file primitive, and
@@ -168,6 +170,18 @@ pub(crate) fn dup_stdio() -> Result<(FileDesc, FileDesc, FileDesc)> {
     ))
 }
 
+/// Runs a blocking I/O operation (e.g. reading a file or directory) on the
+/// `tokio` blocking pool so that it does not stall any threads driving futures.
+pub(crate) async fn blocking_io<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,6 +5,7 @@ use std::path::Path;
 
 /// An indicator of the read/write permissions of an OS file primitive.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Permissions {
     /// A file was opened for reading only.
     Read,
@@ -2,6 +2,8 @@ use crate::io::FileDesc;
 use crate::sys;
 use crate::IntoInner;
 use std::io::Result as IoResult;
+#[cfg(unix)]
+use std::path::Path;
 
 /// A wrapper for a reader and writer OS pipe pair.
 #[derive(Debug)]
@@ -23,6 +25,31 @@ impl Pipe {
             writer: FileDesc::from_inner(writer),
         })
     }
+
+    /// Creates a new named pipe (FIFO) at `path`, accessible only by the
+    /// current user, and opens both of its ends.
+    ///
+    /// Both file descriptors will be in non-blocking mode (see
+    /// `FileDesc::set_nonblock`) and have their CLOEXEC flags set. The FIFO
+    /// itself remains on the file system until it is removed by the caller.
+    #[cfg(unix)]
+    pub fn new_named(path: &Path) -> IoResult<Pipe> {
+        sys::io::mkfifo(path, 0o600)?;
+        Self::open_named(path)
+    }
+
+    /// Opens both ends of an existing named pipe (FIFO) at `path`.
+    ///
+    /// Both file descriptors will be in non-blocking mode (see
+    /// `FileDesc::set_nonblock`) and have their CLOEXEC flags set.
+    #[cfg(unix)]
+    pub fn open_named(path: &Path) -> IoResult<Pipe> {
+        let (reader, writer) = sys::io::open_fifo(path)?;
+        Ok(Pipe {
+            reader: FileDesc::from_inner(reader),
+            writer: FileDesc::from_inner(writer),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -50,4 +77,29 @@ mod tests {
         guard.join().unwrap();
         assert_eq!(msg, read);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn smoke_named() {
+        use std::env;
+        use std::fs;
+        use std::process;
+
+        let path = env::temp_dir().join(format!("conch-fifo-test-{}", process::id()));
+        let Pipe {
+            mut reader,
+            mut writer,
+        } = Pipe::new_named(&path).unwrap();
+
+        let msg = "fifo message";
+        writer.write_all(msg.as_bytes()).unwrap();
+        drop(writer);
+
+        let mut read = String::new();
+        reader.read_to_string(&mut read).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(msg, read);
+
+        assert!(Pipe::open_named(&path).is_err());
+    }
 }
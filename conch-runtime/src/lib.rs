@@ -17,13 +17,29 @@
 //! numbered file descriptors [other than stdio] is difficult/impossible due to the
 //! way Windows addresses file handles).
 //!
+//! # Getting started
+//!
+//! Embedders which simply want to run some shell code can use the
+//! `shell::Shell` type, which bundles a default environment with the runtime
+//! needed to drive it. Composing custom environments and spawning individual
+//! commands directly is considered advanced use, as described in the
+//! `shell` module documentation.
+//!
 //! [POSIX]: http://pubs.opengroup.org/onlinepubs/9699919799/
 //! [`conch-parser`]: https://docs.rs/conch-parser
 //!
 //! # Supported Cargo Features
 //!
 //! * `conch-parser`: enable implementations on the default AST types provided
-//! by the `conch-parser` crate
+//!   by the `conch-parser` crate, as well as the `interactive`, `script`, and `shell`
+//!   modules
+//! * `rustyline`: enable `interactive::RustylineSource`, a `LineSource` backed by
+//!   the `rustyline` line editor (requires the `conch-parser` feature as well)
+//! * `serde`: enable serializing and deserializing runtime data, such as the
+//!   execution plans recorded by `env::ExecutionPlanEnv`, environment snapshots,
+//!   exit statuses, and shell options
+//! * `testing`: enable deterministic test doubles for embedders, namely
+//!   `env::MockExecEnv`, `env::MockClockEnv`, and `env::MockRandomEnv`
 
 #![doc(html_root_url = "https://docs.rs/conch-runtime/0.1")]
 #![cfg_attr(not(test), deny(clippy::print_stdout))]
@@ -39,8 +55,14 @@
 pub mod env;
 pub mod error;
 pub mod eval;
+#[cfg(feature = "conch-parser")]
+pub mod interactive;
 pub mod io;
 pub mod path;
+#[cfg(feature = "conch-parser")]
+pub mod script;
+#[cfg(feature = "conch-parser")]
+pub mod shell;
 pub mod spawn;
 
 mod exit_status;
@@ -74,9 +96,8 @@ pub const STDOUT_FILENO: Fd = 1;
 /// File descriptor for standard error.
 pub const STDERR_FILENO: Fd = 2;
 
-lazy_static::lazy_static! {
-    static ref HOME: String = String::from("HOME");
-}
+/// The name of the variable which holds the user's home directory.
+const HOME: &str = "HOME";
 
 /// The type that represents a file descriptor within shell scripts.
 pub type Fd = u16;
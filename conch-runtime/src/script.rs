@@ -0,0 +1,423 @@
+//! Running entire shell scripts, from parsing their source to executing
+//! each of their commands.
+
+use crate::env::{
+    ArgsGuard, ControlFlowEnvironment, DynamicVariableEnvironment, ExportedVariableEnvironment,
+    FunctionEnvironment, FunctionSource, LastStatusEnvironment, ReportErrorEnvironment,
+    SetArgumentsEnvironment, ShellOptionsEnvironment, StrKey, VariableEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use crate::error::{ControlFlow, IsFatalError, SourceLocation, WithLocation};
+use crate::io::blocking_io;
+use crate::spawn::{located, should_exit_on_error, swallow_non_fatal_errors};
+use crate::{ExitStatus, Spawn, EXIT_ERROR, HOME};
+use conch_parser::ast::builder::AtomicDefaultBuilder;
+use conch_parser::ast::{self, AtomicTopLevelCommand};
+use conch_parser::lexer::Lexer;
+use conch_parser::parse::{ParseError, Parser};
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use void::Void;
+
+/// The variable which may name a custom rc script.
+const CONCHRC: &str = "CONCHRC";
+/// The name of the default rc script, relative to `$HOME`.
+const DEFAULT_RC_FILE: &str = ".conchrc";
+/// The prefix of the variables which hold the definitions of exported functions.
+const EXPORTED_FN_PREFIX: &str = "CONCH_FUNC_";
+
+/// The body of any functions defined by the scripts this module parses.
+pub(crate) type FnBody<T> =
+    ast::ShellCompoundCommand<T, ast::AtomicTopLevelWord<T>, AtomicTopLevelCommand<T>>;
+
+/// The source code of a script to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptSource<'a> {
+    /// The script's source code itself.
+    Str(&'a str),
+    /// The path to a file containing the script's source code, relative to
+    /// the environment's current working directory.
+    Path(&'a Path),
+}
+
+impl<'a> From<&'a str> for ScriptSource<'a> {
+    fn from(src: &'a str) -> Self {
+        ScriptSource::Str(src)
+    }
+}
+
+impl<'a> From<&'a Path> for ScriptSource<'a> {
+    fn from(path: &'a Path) -> Self {
+        ScriptSource::Path(path)
+    }
+}
+
+/// An error which prevents a script from running at all.
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    /// The script's file could not be read.
+    #[error("{}: {}", .1.display(), .0)]
+    Io(#[source] IoError, PathBuf),
+    /// The script's source code could not be parsed.
+    #[error(transparent)]
+    Parse(#[from] ParseError<Void>),
+}
+
+/// Parses and runs all commands of a script, one after the other, returning
+/// the exit status of the last command run.
+///
+/// Each command's status is recorded as the last status of the environment.
+/// If the `errexit` option is enabled (e.g. via `set -e`), the script is
+/// stopped as soon as a command fails. Errors encountered while running any
+/// command are reported to the environment, and running the script stops if
+/// the shell is requested to exit (e.g. via the `exit` builtin).
+///
+/// Any errors are annotated with the location (the script's path, if any, and
+/// line number) of the command which failed, and `$LINENO` is updated before
+/// each command is run.
+///
+/// An error is returned only if the script could not be read or parsed, in
+/// which case none of its commands are run.
+pub async fn run_script<'a, S, E>(src: S, env: &mut E) -> Result<ExitStatus, ScriptError>
+where
+    S: Into<ScriptSource<'a>>,
+    E: ?Sized
+        + Send
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: From<String>,
+    AtomicTopLevelCommand<E::VarName>: Sync + Spawn<E>,
+    <AtomicTopLevelCommand<E::VarName> as Spawn<E>>::Error: IsFatalError + WithLocation + Error,
+{
+    let (file, cmds) = match src.into() {
+        ScriptSource::Str(src) => (None, parse_located(src)?),
+        ScriptSource::Path(path) => {
+            let path = env.resolve(path);
+            let src = blocking_io({
+                let path = path.clone();
+                move || fs::read_to_string(path)
+            })
+            .await
+            .map_err(|e| ScriptError::Io(e, path.clone()))?;
+
+            let cmds = parse_located(&src)?;
+            (Some(Arc::from(path)), cmds)
+        }
+    };
+
+    for (line, cmd) in &cmds {
+        let location = SourceLocation {
+            file: file.clone(),
+            line: *line,
+        };
+
+        env.set_status_tested(false);
+        let status = match swallow_non_fatal_errors(located(location, cmd), env).await {
+            Ok(future) => future.await,
+            Err(err) => {
+                if let Some(ControlFlow::Exit(status)) = err.control_flow() {
+                    env.set_last_status(status);
+                    return Ok(status);
+                }
+
+                env.report_error(&err).await;
+                EXIT_ERROR
+            }
+        };
+
+        env.set_last_status(status);
+        if should_exit_on_error(status, env) {
+            break;
+        }
+    }
+
+    Ok(env.last_status())
+}
+
+/// Runs a script (see `run_script`) within the current environment, as if it
+/// were sourced (e.g. via `. file args...`).
+///
+/// While the script runs, `$0` names the script's path (if it is read from a
+/// file), and the positional parameters are replaced with `args` (if
+/// provided). Both are restored once the script completes (or is cancelled),
+/// while any other changes it makes (e.g. to variables or functions) remain
+/// in effect afterwards. Note that if no `args` are provided, the script
+/// shares (and may modify) the positional parameters of its caller.
+pub async fn source_script<'a, S, A, E>(
+    src: S,
+    args: Option<A>,
+    env: &mut E,
+) -> Result<ExitStatus, ScriptError>
+where
+    S: Into<ScriptSource<'a>>,
+    E: ?Sized
+        + Send
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Arg: From<String>,
+    E::Args: From<A>,
+    E::VarName: From<String>,
+    AtomicTopLevelCommand<E::VarName>: Sync + Spawn<E>,
+    <AtomicTopLevelCommand<E::VarName> as Spawn<E>>::Error: IsFatalError + WithLocation + Error,
+{
+    let src = src.into();
+    let mut guard = ArgsGuard::new(env);
+
+    if let ScriptSource::Path(path) = src {
+        guard.set_name(path.display().to_string().into());
+    }
+
+    if let Some(args) = args {
+        guard.set_args(args.into());
+    }
+
+    run_script(src, guard.get_mut()).await
+}
+
+/// Where to find the startup (or rc) script, which configures the defaults of
+/// a shell (e.g. its aliases, functions, and options) before it runs any
+/// other commands.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub enum RcFile {
+    /// Run the script named by `$CONCHRC` (or `$HOME/.conchrc` if it is not
+    /// set), but only if it exists.
+    #[default]
+    Default,
+    /// Run the script at the provided path (relative to the environment's
+    /// working directory), which must exist.
+    Path(PathBuf),
+    /// Do not run any rc script.
+    Disabled,
+}
+
+/// Runs the rc script described by `rc` (see `RcFile`) within the environment,
+/// returning its exit status, or `None` if no script was run.
+///
+/// The script is run like any other script (see `run_script`), thus any
+/// aliases, functions, or options it defines remain in effect afterwards.
+pub async fn run_rc_file<E>(rc: &RcFile, env: &mut E) -> Result<Option<ExitStatus>, ScriptError>
+where
+    E: ?Sized
+        + Send
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String>,
+    AtomicTopLevelCommand<E::VarName>: Sync + Spawn<E>,
+    <AtomicTopLevelCommand<E::VarName> as Spawn<E>>::Error: IsFatalError + WithLocation + Error,
+{
+    let path = match rc {
+        RcFile::Disabled => return Ok(None),
+        RcFile::Path(path) => path.clone(),
+        RcFile::Default => match default_rc_path(env) {
+            Some(path) if env.path_exists(&path) => path,
+            _ => return Ok(None),
+        },
+    };
+
+    run_script(ScriptSource::Path(&path), env).await.map(Some)
+}
+
+/// Determines the path of the default rc script, if any.
+fn default_rc_path<E>(env: &E) -> Option<PathBuf>
+where
+    E: ?Sized + VariableEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let lookup = |name| {
+        E::VarName::lookup(env, name)
+            .map(|val| val.borrow().as_str())
+            .filter(|val| !val.is_empty())
+    };
+
+    match lookup(CONCHRC) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => lookup(HOME).map(|home| Path::new(home).join(DEFAULT_RC_FILE)),
+    }
+}
+
+/// Exports the current definition of a function to child shells, by storing
+/// its source in the exported `$CONCH_FUNC_<name>` variable (which can be
+/// loaded by the child via `import_functions`), returning whether the function
+/// was exported.
+///
+/// Only functions whose body was recorded by the environment, and which were
+/// defined by shell code parsed by this crate (unlike host functions), can be
+/// exported. Since the definition is exported as is, a function which is later
+/// redefined must be exported again to update its definition.
+pub fn export_function<E>(name: &E::FnName, env: &mut E) -> bool
+where
+    E: ?Sized + ExportedVariableEnvironment + FunctionEnvironment,
+    E::FnName: Borrow<String>,
+    E::VarName: 'static + Borrow<String> + From<String>,
+    E::Var: From<String>,
+{
+    let body = env
+        .function_body(name)
+        .and_then(|body| body.downcast_ref::<FnBody<E::VarName>>());
+
+    let source = match body {
+        Some(body) => body.function_source(),
+        None => return false,
+    };
+
+    let name: &String = name.borrow();
+    if name.contains('=') {
+        return false;
+    }
+
+    let var = format!("{}{}", EXPORTED_FN_PREFIX, name);
+    env.set_exported_var(var.into(), source.into(), true);
+    true
+}
+
+/// An exported function definition which could not be imported.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: error importing function definition")]
+struct ImportFunctionError(String);
+
+/// Defines all functions exported to the environment (see `export_function`),
+/// e.g. by a parent shell, returning the names of the imported functions.
+///
+/// Each definition must consist of a single compound command, thus a crafted
+/// variable cannot be used to run any other commands. Invalid definitions are
+/// reported to the environment and skipped.
+///
+/// Since the variables holding the definitions remain exported, the imported
+/// functions are also exported to any children of the environment.
+pub async fn import_functions<E>(env: &mut E) -> Vec<String>
+where
+    E: ?Sized + Send + ReportErrorEnvironment + VariableEnvironment,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String>,
+    AtomicTopLevelCommand<E::VarName>: Spawn<E>,
+{
+    let mut exported = env
+        .env_vars()
+        .iter()
+        .filter_map(|(name, val)| {
+            let fn_name = name.as_key_str().strip_prefix(EXPORTED_FN_PREFIX)?;
+            let source: &String = (*val).borrow();
+            Some((fn_name.to_owned(), source.clone()))
+        })
+        .collect::<Vec<_>>();
+    exported.sort();
+
+    let mut imported = Vec::new();
+    for (name, source) in exported {
+        let cmd = match parse_function_def(name.clone(), &source) {
+            Some(cmd) => cmd,
+            None => {
+                env.report_error(&ImportFunctionError(name)).await;
+                continue;
+            }
+        };
+
+        // NB: defining a function cannot fail
+        if let Ok(future) = cmd.spawn(env).await {
+            future.await;
+            imported.push(name);
+        }
+    }
+
+    imported
+}
+
+/// Parses the definition of a function named `name`, whose body must be
+/// a single compound command.
+fn parse_function_def<T: From<String>>(
+    name: String,
+    body: &str,
+) -> Option<AtomicTopLevelCommand<T>> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let lexer = Lexer::new(body.chars());
+    let mut parser = Parser::with_builder(lexer, AtomicDefaultBuilder::new());
+    let body = parser.compound_command().ok()?;
+    if !matches!(parser.complete_command(), Ok(None)) {
+        return None;
+    }
+
+    let def = ast::PipeableCommand::FunctionDef(name.into(), Arc::new(body));
+    Some(AtomicTopLevelCommand(ast::Command::List(ast::AndOrList {
+        first: ast::ListableCommand::Single(def),
+        rest: Vec::new(),
+    })))
+}
+
+/// Parses all commands in `src`.
+pub(crate) fn parse<T: From<String>>(
+    src: &str,
+) -> Result<Vec<AtomicTopLevelCommand<T>>, ParseError<Void>> {
+    let lexer = Lexer::new(src.chars());
+    Parser::with_builder(lexer, AtomicDefaultBuilder::new())
+        .into_iter()
+        .collect()
+}
+
+/// Parses all commands in `src`, along with the (1-based) line each of
+/// them starts on.
+pub(crate) fn parse_located<T: From<String>>(
+    src: &str,
+) -> Result<Vec<(usize, AtomicTopLevelCommand<T>)>, ParseError<Void>> {
+    let lexer = Lexer::new(src.chars());
+    let mut parser = Parser::with_builder(lexer, AtomicDefaultBuilder::new());
+    let mut cmds = Vec::new();
+
+    loop {
+        // The parser's position is wherever the previous command ended, so
+        // skip over any blank lines or comments preceding the next command.
+        let pos = parser.pos();
+        match parser.complete_command()? {
+            Some(cmd) => {
+                let rest = src.get(pos.byte..).unwrap_or("");
+                cmds.push((pos.line + leading_line_count(rest), cmd));
+            }
+            None => return Ok(cmds),
+        }
+    }
+}
+
+/// Counts the number of lines consisting only of whitespace or comments at
+/// the start of `src`.
+fn leading_line_count(src: &str) -> usize {
+    let mut count = 0;
+    let mut in_comment = false;
+
+    for c in src.chars() {
+        match c {
+            '\n' => {
+                count += 1;
+                in_comment = false;
+            }
+            '#' => in_comment = true,
+            _ if in_comment || c.is_whitespace() => {}
+            _ => break,
+        }
+    }
+
+    count
+}
@@ -0,0 +1,198 @@
+//! A ready to use shell, for embedders which simply want to run some shell
+//! code without dealing with environments or async runtimes.
+//!
+//! The `Shell` type pairs a `DefaultEnvArc` with its own (single threaded)
+//! tokio runtime, and exposes blocking methods for the most common tasks.
+//!
+//! # Advanced use
+//!
+//! Embedders which need more control (e.g. a custom environment composed of
+//! their own `*Environment` implementations, driving commands on an existing
+//! runtime, or spawning individual commands concurrently) should instead
+//! assemble an `Env` themselves and `spawn` parsed commands within it (see
+//! the `env` and `spawn` modules, as well as `script::run_script`).
+
+use crate::env::{
+    DefaultEnvArc, FunctionEnvironment, LastStatusEnvironment, ReportErrorEnvironment, StrKey,
+    VariableEnvironment,
+};
+use crate::script::{
+    export_function, import_functions, parse, run_rc_file, run_script, FnBody, RcFile, ScriptError,
+    ScriptSource,
+};
+use crate::spawn::host_fn;
+use crate::{ExitStatus, EXIT_ERROR};
+use conch_parser::ast;
+use futures_core::future::BoxFuture;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+/// A shell environment bundled with the runtime needed to execute commands
+/// within it.
+///
+/// All state (variables, functions, the working directory, etc.) persists
+/// across invocations, much like an interactive shell session.
+#[derive(Debug)]
+pub struct Shell {
+    env: DefaultEnvArc,
+    runtime: Runtime,
+}
+
+impl Shell {
+    /// Creates a new shell with a default environment, after running the
+    /// default startup (or rc) script, if any (see `RcFile::Default`).
+    pub fn new() -> io::Result<Self> {
+        Self::with_rc_file(&RcFile::Default)
+    }
+
+    /// Creates a new shell with a default environment, after running the
+    /// specified startup (or rc) script, if any.
+    ///
+    /// Any errors encountered while running the script (e.g. if it could not
+    /// be read or parsed) are reported to the environment, and do not prevent
+    /// the shell from being created. Use `RcFile::Disabled` to skip the script.
+    pub fn with_rc_file(rc: &RcFile) -> io::Result<Self> {
+        let runtime = new_runtime()?;
+        let env = runtime.enter(DefaultEnvArc::new)?;
+        let mut shell = Self { env, runtime };
+
+        let Self { env, runtime } = &mut shell;
+        runtime.block_on(async {
+            if let Err(err) = run_rc_file(rc, env).await {
+                env.report_error(&err).await;
+                env.set_last_status(EXIT_ERROR);
+            }
+        });
+
+        Ok(shell)
+    }
+
+    /// Creates a new shell which runs commands within the provided environment.
+    ///
+    /// No startup script is run, since the environment is used as is.
+    pub fn with_env(env: DefaultEnvArc) -> io::Result<Self> {
+        Ok(Self {
+            env,
+            runtime: new_runtime()?,
+        })
+    }
+
+    /// Parses and runs the provided source code, returning the exit status
+    /// of the last command run.
+    ///
+    /// See `script::run_script` for more details.
+    pub fn run_str(&mut self, src: &str) -> Result<ExitStatus, ScriptError> {
+        self.run(ScriptSource::Str(src))
+    }
+
+    /// Parses and runs the script at the provided path (relative to the
+    /// shell's current working directory), returning the exit status of
+    /// the last command run.
+    ///
+    /// See `script::run_script` for more details.
+    pub fn run_file<P: AsRef<Path>>(&mut self, path: P) -> Result<ExitStatus, ScriptError> {
+        self.run(ScriptSource::Path(path.as_ref()))
+    }
+
+    /// Runs a startup (or rc) script, if any, returning its exit status, or
+    /// `None` if no script was run.
+    ///
+    /// Useful for (re-)running a script within an existing shell, e.g. one
+    /// created via `Shell::with_env`, since `Shell::new` already runs the
+    /// default script (i.e. `$CONCHRC` or `$HOME/.conchrc`).
+    ///
+    /// See `script::run_rc_file` for more details.
+    pub fn run_rc_file(&mut self, rc: &RcFile) -> Result<Option<ExitStatus>, ScriptError> {
+        let Self { env, runtime } = self;
+        runtime.block_on(run_rc_file(rc, env))
+    }
+
+    fn run(&mut self, src: ScriptSource<'_>) -> Result<ExitStatus, ScriptError> {
+        let Self { env, runtime } = self;
+        runtime.block_on(run_script(src, env))
+    }
+
+    /// Sets the value of a shell variable.
+    pub fn set_var(&mut self, name: &str, value: &str) {
+        self.env
+            .set_var(Arc::new(name.to_owned()), Arc::new(value.to_owned()));
+    }
+
+    /// Gets the value of a shell variable, if it is set.
+    pub fn get_var(&self, name: &str) -> Option<&str> {
+        Arc::<String>::lookup(&self.env, name).map(|value| value.as_str())
+    }
+
+    /// Defines (or redefines) a shell function whose body is the provided
+    /// source code.
+    ///
+    /// The body is parsed immediately, but only run whenever the function
+    /// is invoked.
+    pub fn define_fn(&mut self, name: &str, body: &str) -> Result<(), ScriptError> {
+        let name = Arc::new(name.to_owned());
+        let body: Arc<FnBody<_>> = Arc::new(ast::CompoundCommand {
+            kind: ast::CompoundCommandKind::Brace(parse(body)?),
+            io: Vec::new(),
+        });
+
+        self.env.set_function(name.clone(), body.clone());
+        self.env.set_function_body(&name, body);
+        Ok(())
+    }
+
+    /// Exports the current definition of a shell function to child shells,
+    /// returning whether the function was exported.
+    ///
+    /// See `script::export_function` for more details.
+    pub fn export_fn(&mut self, name: &str) -> bool {
+        export_function(&Arc::new(name.to_owned()), &mut self.env)
+    }
+
+    /// Defines all functions exported by a parent shell, returning their names.
+    ///
+    /// See `script::import_functions` for more details.
+    pub fn import_fns(&mut self) -> Vec<String> {
+        let Self { env, runtime } = self;
+        runtime.block_on(import_functions(env))
+    }
+
+    /// Defines (or redefines) a shell function which invokes a native function.
+    ///
+    /// See `spawn::host_fn` for more details.
+    pub fn define_host_fn<F>(&mut self, name: &str, f: F)
+    where
+        F: 'static
+            + Send
+            + Sync
+            + Fn(&mut DefaultEnvArc, Vec<String>) -> BoxFuture<'static, ExitStatus>,
+    {
+        self.env
+            .set_function(Arc::new(name.to_owned()), Arc::new(host_fn(f)));
+    }
+
+    /// Gets the exit status of the last command run.
+    pub fn last_status(&self) -> ExitStatus {
+        self.env.last_status()
+    }
+
+    /// Gets a reference to the shell's environment.
+    pub fn env(&self) -> &DefaultEnvArc {
+        &self.env
+    }
+
+    /// Gets a mutable reference to the shell's environment.
+    pub fn env_mut(&mut self) -> &mut DefaultEnvArc {
+        &mut self.env
+    }
+
+    /// Unwraps the shell's environment.
+    pub fn into_env(self) -> DefaultEnvArc {
+        self.env
+    }
+}
+
+fn new_runtime() -> io::Result<Runtime> {
+    Builder::new().basic_scheduler().enable_all().build()
+}
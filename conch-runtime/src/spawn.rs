@@ -5,37 +5,65 @@ use async_trait::async_trait;
 use futures_core::future::BoxFuture;
 
 mod and_or;
+mod arith_cmd;
+mod cancel;
 mod case;
+mod errexit;
 mod for_cmd;
 mod func_exec;
+mod host_fn;
 mod if_cmd;
 mod local_redirections;
+mod located;
 mod loop_cmd;
 mod pipeline;
 mod sequence;
 mod simple;
+mod stdin;
 mod subshell;
 mod substitution;
 mod swallow_non_fatal;
+mod timeout;
 
 #[cfg(feature = "conch-parser")]
 pub mod ast_impl;
 pub mod builtin;
 
+#[cfg(feature = "conch-parser")]
+pub(crate) use self::arith_cmd::arith_status;
+pub(crate) use self::cancel::YIELD_INTERVAL;
+pub(crate) use self::errexit::should_exit_on_error;
+
 // Pub reexports
 pub use self::and_or::{and_or_list, AndOr};
-pub use self::case::{case, PatternBodyPair};
-pub use self::for_cmd::{for_args, for_loop, for_with_args};
+pub use self::arith_cmd::arith_cmd;
+pub use self::cancel::{with_cancellation, yield_now, CancellationToken};
+#[cfg(feature = "conch-parser")]
+pub(crate) use self::case::BreakAfterEach;
+pub use self::case::{
+    case, case_with_config, case_with_terminators, CaseArmTerminator, PatternBodyPair,
+};
+pub use self::for_cmd::{for_args, for_each_concurrent, for_loop, for_with_args};
 pub use self::func_exec::{function, function_body};
+pub use self::host_fn::{host_fn, HostFn};
 pub use self::if_cmd::if_cmd;
 pub use self::local_redirections::spawn_with_local_redirections_and_restorer;
+pub use self::located::{located, Located};
 pub use self::loop_cmd::loop_cmd;
-pub use self::pipeline::pipeline;
+pub use self::pipeline::{pipeline, pipeline_with_config, PipelineConfig};
 pub use self::sequence::{sequence, sequence_exact, sequence_slice, SequenceSlice};
-pub use self::simple::{simple_command, simple_command_with_restorer};
+pub use self::simple::{
+    simple_command, simple_command_with_resolution, simple_command_with_restorer,
+    simple_command_with_restorer_and_resolution, CommandResolution,
+};
+pub use self::stdin::with_stdin_bytes;
 pub use self::subshell::subshell;
-pub use self::substitution::substitution;
+pub use self::substitution::{
+    capture_output, capture_output_chunks, substitution, substitution_bytes, Output, OutputChunk,
+    OutputStream,
+};
 pub use self::swallow_non_fatal::swallow_non_fatal_errors;
+pub use self::timeout::with_timeout;
 
 /// A trait for spawning commands.
 ///
@@ -134,6 +162,15 @@ where
     }
 }
 
+/// The boxed future returned by `Spawn::spawn`.
+///
+/// Implementations which merely delegate to another command can return its
+/// future as is, rather than wrapping it in yet another box. Note that since
+/// `Spawn::spawn` always returns a boxed future, every nesting level still
+/// requires (at least) one allocation, regardless of how deeply it is nested.
+#[cfg(feature = "conch-parser")]
+pub(crate) type BoxSpawn<'a, ERR> = BoxFuture<'a, Result<BoxFuture<'static, ExitStatus>, ERR>>;
+
 /// A grouping of guard and body commands.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct GuardBodyPair<T> {
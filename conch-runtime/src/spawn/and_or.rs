@@ -1,4 +1,4 @@
-use crate::env::{LastStatusEnvironment, ReportErrorEnvironment};
+use crate::env::{ControlFlowEnvironment, LastStatusEnvironment, ReportErrorEnvironment};
 use crate::error::IsFatalError;
 use crate::spawn::swallow_non_fatal_errors;
 use crate::{ExitStatus, Spawn};
@@ -15,6 +15,9 @@ pub enum AndOr<T> {
 }
 
 /// Spawns an `And`/`Or` list of commands from an initial command and an iterator.
+///
+/// All but the last command of the list are exempt from the `errexit` option,
+/// as their exit status is tested by the list itself.
 pub async fn and_or_list<T, I, E>(
     first: T,
     rest: I,
@@ -24,7 +27,7 @@ where
     T: Spawn<E>,
     T::Error: IsFatalError,
     I: IntoIterator<Item = AndOr<T>>,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment + ReportErrorEnvironment,
 {
     do_and_or_list(first, rest.into_iter().peekable(), env).await
 }
@@ -38,23 +41,34 @@ where
     T: Spawn<E>,
     T::Error: IsFatalError,
     I: Iterator<Item = AndOr<T>>,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment + ReportErrorEnvironment,
 {
     loop {
-        let future = swallow_non_fatal_errors(&next, env).await?;
+        env.set_status_tested(false);
 
         // If we have no further commands to process, we can return the
         // current command's future (so the caller may drop the environment)
         if rest.peek().is_none() {
-            return Ok(future);
+            return swallow_non_fatal_errors(&next, env).await;
         }
 
-        let status = future.await;
+        env.push_errexit_exemption();
+        let status = match swallow_non_fatal_errors(&next, env).await {
+            Ok(future) => future.await,
+            Err(e) => {
+                env.pop_errexit_exemption();
+                return Err(e);
+            }
+        };
+        env.pop_errexit_exemption();
         env.set_last_status(status);
 
         'find_next: loop {
             match (rest.next(), status.success()) {
-                (None, _) => return Ok(Box::pin(async move { status })),
+                (None, _) => {
+                    env.set_status_tested(true);
+                    return Ok(Box::pin(async move { status }));
+                }
 
                 (Some(AndOr::And(cmd)), true) | (Some(AndOr::Or(cmd)), false) => {
                     next = cmd;
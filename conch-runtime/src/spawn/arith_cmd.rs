@@ -0,0 +1,34 @@
+use crate::error::ExpansionError;
+use crate::eval::ArithEval;
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_core::future::BoxFuture;
+
+/// Spawns a standalone arithmetic command, e.g. `(( x += 1 ))`.
+///
+/// The expression is evaluated (applying any side effects to the environment)
+/// and the command will exit successfully only if its value is non-zero.
+///
+/// Note that the `conch-parser` AST has no representation for standalone
+/// arithmetic commands (only for arithmetic substitutions), so this is never
+/// reached by running a script; it is provided for embedders whose own AST
+/// supports such commands.
+pub async fn arith_cmd<A, E>(
+    expr: &A,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, ExpansionError>
+where
+    A: ?Sized + ArithEval<E>,
+    E: ?Sized,
+{
+    let status = arith_status(expr.eval(env)?);
+    Ok(Box::pin(async move { status }))
+}
+
+/// Converts the value of an arithmetic command into its exit status.
+pub(crate) fn arith_status(value: isize) -> ExitStatus {
+    if value == 0 {
+        EXIT_ERROR
+    } else {
+        EXIT_SUCCESS
+    }
+}
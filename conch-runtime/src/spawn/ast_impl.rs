@@ -10,6 +10,7 @@ mod compound;
 mod listable;
 mod pipeable;
 mod simple;
+mod source;
 mod top_level_impl;
 
 impl<T> From<ast::GuardBodyPair<T>> for GuardBodyPair<Vec<T>> {
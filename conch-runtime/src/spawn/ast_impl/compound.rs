@@ -1,19 +1,23 @@
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, EnvRestorer, ExportedVariableEnvironment,
-    FileDescEnvironment, FileDescOpener, LastStatusEnvironment, ReportErrorEnvironment,
-    SubEnvironment, UnsetVariableEnvironment, VariableEnvironment,
+    ArgumentsEnvironment, AsyncIoEnvironment, ControlFlowEnvironment, EnvRestorer,
+    ExportedVariableEnvironment, FileDescEnvironment, LastStatusEnvironment, NestingEnvironment,
+    NestingGuard, ReportErrorEnvironment, ShellOptionsEnvironment, SubEnvironment,
+    TempFileEnvironment, UnsetVariableEnvironment, VariableEnvironment,
+    WorkingDirectoryEnvironment,
 };
-use crate::error::{IsFatalError, RedirectionError};
-use crate::eval::{RedirectEval, WordEval};
+use crate::error::{
+    ControlFlow, ExpansionError, IsFatalError, NestingLimitError, RedirectionError,
+};
+use crate::eval::{PatternMatchConfig, RedirectEval, WordEval};
 use crate::spawn::{
-    case, for_args, for_loop, if_cmd, loop_cmd, sequence_exact, sequence_slice,
-    spawn_with_local_redirections_and_restorer, subshell, GuardBodyPair, PatternBodyPair, Spawn,
+    case_with_config, for_args, for_loop, if_cmd, loop_cmd, sequence_exact, sequence_slice,
+    spawn_with_local_redirections_and_restorer, subshell, BoxSpawn, BreakAfterEach, GuardBodyPair,
+    PatternBodyPair, SequenceSlice, Spawn,
 };
 use crate::{ExitStatus, EXIT_SUCCESS};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
 
-#[async_trait::async_trait]
 impl<S, R, E> Spawn<E> for ast::CompoundCommand<S, R>
 where
     S: Send + Sync + Spawn<E>,
@@ -25,7 +29,7 @@ where
         + AsyncIoEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + UnsetVariableEnvironment,
     E::FileHandle: Clone + Send + From<E::OpenedFileHandle>,
     E::IoHandle: Send + From<E::FileHandle>,
@@ -34,78 +38,147 @@ where
 {
     type Error = S::Error;
 
-    async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
-        spawn_with_local_redirections_and_restorer(&self.io, &self.kind, &mut EnvRestorer::new(env))
-            .await
+    fn spawn<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+    ) -> BoxSpawn<'async_trait, Self::Error>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        // Without any redirects there is nothing to restore, so we can hand
+        // out the inner command's future as is rather than boxing it again
+        if self.io.is_empty() {
+            return self.kind.spawn(env);
+        }
+
+        Box::pin(async move {
+            let mut restorer = EnvRestorer::new(env);
+            spawn_with_local_redirections_and_restorer(&self.io, &self.kind, &mut restorer).await
+        })
     }
 }
 
-#[async_trait::async_trait]
 impl<V, W, S, E> Spawn<E> for ast::CompoundCommandKind<V, W, S>
 where
     V: Send + Sync + Clone,
     W: Sync + WordEval<E>,
-    W::Error: Send + IsFatalError,
+    W::EvalResult: Send,
+    W::Error: Send + IsFatalError + From<ExpansionError>,
     S: Send + Sync + Spawn<E>,
-    S::Error: From<W::Error> + IsFatalError,
-    E: ?Sized
-        + Send
+    S::Error: From<ControlFlow> + From<NestingLimitError> + From<W::Error> + IsFatalError,
+    E: Send
         + Sync
         + ArgumentsEnvironment
+        + ControlFlowEnvironment
         + LastStatusEnvironment
+        + NestingEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment
-        + VariableEnvironment,
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
     E::Var: Send + From<E::Arg> + From<W::EvalResult>,
     E::VarName: Send + Clone + From<V>,
 {
     type Error = S::Error;
 
-    async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
-        use ast::CompoundCommandKind::*;
-        match self {
-            Brace(cmds) => sequence_exact(cmds, env).await,
+    /// Spawns the compound command.
+    ///
+    /// An error is returned (without spawning anything) if the environment's
+    /// maximum nesting depth would be exceeded.
+    fn spawn<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+    ) -> BoxSpawn<'async_trait, Self::Error>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            let mut guard = NestingGuard::enter(env).map_err(S::Error::from)?;
+            spawn_kind(self, guard.get_mut()).await
+        })
+    }
+}
+
+fn spawn_kind<'a, V, W, S, E>(
+    kind: &'a ast::CompoundCommandKind<V, W, S>,
+    env: &'a mut E,
+) -> BoxSpawn<'a, S::Error>
+where
+    V: Send + Sync + Clone,
+    W: Sync + WordEval<E>,
+    W::EvalResult: Send,
+    W::Error: Send + IsFatalError + From<ExpansionError>,
+    S: Send + Sync + Spawn<E>,
+    S::Error: From<ControlFlow> + From<W::Error> + IsFatalError,
+    E: Send
+        + Sync
+        + ArgumentsEnvironment
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Var: Send + From<E::Arg> + From<W::EvalResult>,
+    E::VarName: Send + Clone + From<V>,
+{
+    // NB: each arm is boxed on its own (rather than being part of a single
+    // `async` block) so that polling a deeply nested command only reserves
+    // stack space for the kind of command actually being run.
+    use ast::CompoundCommandKind::*;
+    match kind {
+        Brace(cmds) => Box::pin(sequence_exact(cmds, env)),
+
+        If {
+            conditionals,
+            else_branch,
+        } => Box::pin(if_cmd(
+            conditionals.iter().map(|gbp| GuardBodyPair {
+                guard: sequence_slice(&gbp.guard),
+                body: sequence_slice(&gbp.body),
+            }),
+            else_branch.as_ref().map(|e| sequence_slice(e)),
+            env,
+        )),
 
-            If {
-                conditionals,
-                else_branch,
-            } => {
-                if_cmd(
-                    conditionals.iter().map(|gbp| GuardBodyPair {
-                        guard: sequence_slice(&gbp.guard),
-                        body: sequence_slice(&gbp.body),
-                    }),
-                    else_branch.as_ref().map(|e| sequence_slice(e)),
-                    env,
-                )
-                .await
-            }
+        For { var, words, body } => match words {
+            Some(words) => Box::pin(for_loop(
+                var.clone().into(),
+                words,
+                sequence_slice(body),
+                env,
+            )),
+            None => Box::pin(for_args(var.clone().into(), sequence_slice(body), env)),
+        },
 
-            For { var, words, body } => match words {
-                Some(words) => for_loop(var.clone().into(), words, sequence_slice(body), env).await,
-                None => for_args(var.clone().into(), sequence_slice(body), env).await,
-            },
+        Case { word, arms } => Box::pin(async move {
+            let arms = BreakAfterEach(arms.iter().map(case_arm));
+            case_with_config(word, arms, PatternMatchConfig::from_options(env), env).await
+        }),
 
-            Case { word, arms } => {
-                case(
-                    word,
-                    arms.iter().map(|pbp| PatternBodyPair {
-                        patterns: pbp.patterns.as_slice(),
-                        body: sequence_slice(&pbp.body),
-                    }),
-                    env,
-                )
-                .await
-            }
+        While(ast::GuardBodyPair { guard, body }) => Box::pin(spawn_loop(false, guard, body, env)),
+        Until(ast::GuardBodyPair { guard, body }) => Box::pin(spawn_loop(true, guard, body, env)),
 
-            While(ast::GuardBodyPair { guard, body }) => spawn_loop(false, guard, body, env).await,
-            Until(ast::GuardBodyPair { guard, body }) => spawn_loop(true, guard, body, env).await,
+        Subshell(cmds) => Box::pin(async move {
+            let ret = subshell(sequence_slice(cmds), env).await;
+            let ret: BoxFuture<'static, ExitStatus> = Box::pin(async move { ret });
+            Ok(ret)
+        }),
+    }
+}
 
-            Subshell(cmds) => {
-                let ret = subshell(sequence_slice(cmds), env).await;
-                Ok(Box::pin(async move { ret }))
-            }
-        }
+// NB: a function (rather than a closure) is general over the lifetime of
+// the arm, which keeps the `case` future `Send`.
+fn case_arm<W, S>(pbp: &ast::PatternBodyPair<W, S>) -> PatternBodyPair<&[W], SequenceSlice<'_, S>> {
+    PatternBodyPair {
+        patterns: pbp.patterns.as_slice(),
+        body: sequence_slice(&pbp.body),
     }
 }
 
@@ -117,8 +190,14 @@ async fn spawn_loop<S, E>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Send + Sync + Spawn<E>,
-    S::Error: IsFatalError,
-    E: ?Sized + Send + Sync + LastStatusEnvironment + ReportErrorEnvironment,
+    S::Error: IsFatalError + From<ControlFlow>,
+    E: ?Sized
+        + Send
+        + Sync
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
 {
     let ret = if guard.is_empty() && body.is_empty() {
         // Not a well formed command, rather than burning CPU and spinning
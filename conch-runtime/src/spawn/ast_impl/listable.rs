@@ -1,6 +1,10 @@
-use crate::env::{FileDescEnvironment, FileDescOpener, ReportErrorEnvironment, SubEnvironment};
+use crate::env::{
+    ControlFlowEnvironment, ExecutableEnvironment, FileDescEnvironment,
+    LastPipelineStatusEnvironment, ReportErrorEnvironment, ShellOption, ShellOptionsEnvironment,
+    SubEnvironment, TempFileEnvironment,
+};
 use crate::error::IsFatalError;
-use crate::spawn::{pipeline, ExitStatus, Spawn};
+use crate::spawn::{pipeline_with_config, ExitStatus, PipelineConfig, Spawn};
 use crate::{EXIT_ERROR, EXIT_SUCCESS};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
@@ -13,11 +17,15 @@ where
     E: ?Sized
         + Send
         + Sync
+        + ControlFlowEnvironment
+        + ExecutableEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
+        + LastPipelineStatusEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E::FileHandle: Send + Clone + From<E::OpenedFileHandle>,
     E::OpenedFileHandle: Send,
 {
     type Error = S::Error;
@@ -37,9 +45,14 @@ where
                 match cmds.as_slice() {
                     // Malformed command, just treat it as a successfull command
                     [] => Box::pin(async move { Ok(dummy(*invert)) }),
-                    [first, rest @ ..] => {
-                        Box::pin(async move { Ok(pipeline(*invert, first, rest, env).await?) })
-                    }
+                    [first, rest @ ..] => Box::pin(async move {
+                        let cfg = PipelineConfig {
+                            pipefail: env.is_option_enabled(ShellOption::PipeFail),
+                            lastpipe: env.is_option_enabled(ShellOption::LastPipe),
+                        };
+
+                        Ok(pipeline_with_config(*invert, cfg, first, rest, env).await?)
+                    }),
                 }
             }
         }
@@ -31,6 +31,7 @@ where
             ast::PipeableCommand::Compound(c) => c.spawn(env),
             ast::PipeableCommand::FunctionDef(name, func) => Box::pin(async move {
                 env.set_function(name.clone().into(), E::Fn::from(func.clone()));
+                env.set_function_body(&name.clone().into(), func.clone());
                 let ret: BoxFuture<'static, ExitStatus> = Box::pin(async { EXIT_SUCCESS });
                 Ok(ret)
             }),
@@ -1,10 +1,14 @@
 use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
 use crate::env::{
-    AsyncIoEnvironment, EnvRestorer, ExecutableEnvironment, ExportedVariableEnvironment,
-    FileDescEnvironment, FileDescOpener, FunctionEnvironment, FunctionFrameEnvironment,
-    SetArgumentsEnvironment, UnsetVariableEnvironment, WorkingDirectoryEnvironment,
+    AliasEnvironment, AsyncIoEnvironment, ControlFlowEnvironment, DynamicVariableEnvironment,
+    EnvRestorer, ExecutableEnvironment, ExportedVariableEnvironment, FileDescEnvironment,
+    FunctionEnvironment, FunctionFrameEnvironment, NestingEnvironment, SetArgumentsEnvironment,
+    ShellOptionsEnvironment, StrKey, TempFileEnvironment, UnsetVariableEnvironment,
+    VariableAttributesEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::{
+    CommandError, ControlFlow, ExpansionError, IsFatalError, RedirectionError, StackOverflowError,
 };
-use crate::error::{CommandError, RedirectionError};
 use crate::eval::{RedirectEval, RedirectOrCmdWord, RedirectOrVarAssig, WordEval};
 use crate::io::FileDescWrapper;
 use crate::spawn::{simple_command, Spawn};
@@ -23,20 +27,26 @@ where
     V: Send + Sync + Clone,
     W: Send + Sync + WordEval<E>,
     W::EvalResult: Send,
-    W::Error: 'static + Send + Sync + Error,
+    W::Error: 'static + Send + Sync + Error + From<ExpansionError>,
     E: ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + FunctionEnvironment
         + FunctionFrameEnvironment
+        + NestingEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
         + UnsetVariableEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Arg: Send + From<W::EvalResult>,
     E::Args: Send + From<VecDeque<E::Arg>>,
@@ -45,11 +55,16 @@ where
     E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
     E::FnName: Send + Sync + From<W::EvalResult>,
     E::Fn: Send + Sync + Clone + Spawn<E>,
-    <E::Fn as Spawn<E>>::Error:
-        From<CommandError> + From<RedirectionError> + From<R::Error> + From<W::Error>,
+    <E::Fn as Spawn<E>>::Error: IsFatalError
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>
+        + From<R::Error>
+        + From<W::Error>,
     E::IoHandle: Send + Sync + From<E::FileHandle>,
-    E::VarName: Send + Sync + Clone + Borrow<String> + From<V>,
-    E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult>,
+    E::VarName: Send + Sync + Clone + StrKey + From<V> + From<String>,
+    E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult> + From<String>,
 {
     type Error = <E::Fn as Spawn<E>>::Error;
 
@@ -0,0 +1,725 @@
+//! Renders AST nodes back into shell source code, so that function bodies
+//! can be recovered from their definitions (see `FunctionSource`).
+//!
+//! The rendered source is not meant to match the original formatting (e.g.
+//! comments are dropped, and some words may be quoted differently), only to
+//! parse into an equivalent command.
+
+use crate::env::FunctionSource;
+use conch_parser::ast;
+use std::borrow::Borrow;
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const INDENT: &str = "    ";
+
+impl<T, W, C> FunctionSource for ast::ShellCompoundCommand<T, W, C>
+where
+    T: Borrow<String>,
+    W: Unparse,
+    C: Unparse,
+{
+    fn function_source(&self) -> String {
+        let mut out = SourceWriter::new();
+        self.unparse(&mut out);
+        out.finish()
+    }
+}
+
+/// How the current word is quoted, i.e. which characters need escaping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    None,
+    Double,
+    Heredoc,
+    /// Within a heredoc whose delimiter is quoted, i.e. nothing is expanded.
+    RawHeredoc,
+}
+
+#[derive(Debug)]
+pub struct SourceWriter {
+    buf: String,
+    indent: usize,
+    quote: Quote,
+    /// Whether the current word is within a `${...}` parameter substitution.
+    in_param: bool,
+    /// Whether anything which is expanded (e.g. a parameter) has been written
+    /// within a `RawHeredoc`.
+    expanded: bool,
+    /// The bodies (and delimiters) of any heredocs to write after the current line.
+    heredocs: Vec<String>,
+}
+
+impl SourceWriter {
+    fn new() -> Self {
+        Self {
+            buf: String::new(),
+            indent: 0,
+            quote: Quote::None,
+            in_param: false,
+            expanded: false,
+            heredocs: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.buf.push(c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.buf.push_str(s);
+    }
+
+    /// Ends the current line (followed by the bodies of any heredocs
+    /// started on it), and starts a new line at the current indentation.
+    fn newline(&mut self) {
+        self.buf.push('\n');
+        for heredoc in self.heredocs.drain(..) {
+            self.buf.push_str(&heredoc);
+        }
+
+        for _ in 0..self.indent {
+            self.buf.push_str(INDENT);
+        }
+    }
+
+    /// Writes each command on its own line, indented one level deeper.
+    fn block<C: Unparse>(&mut self, cmds: &[C]) {
+        self.indent += 1;
+        for cmd in cmds {
+            self.newline();
+            cmd.unparse(self);
+        }
+        self.indent -= 1;
+        self.newline();
+    }
+
+    fn literal(&mut self, s: &str) {
+        for c in s.chars() {
+            let escape = match self.quote {
+                Quote::None => !is_unquoted_safe(c),
+                Quote::Double => matches!(c, '$' | '`' | '"' | '\\'),
+                Quote::Heredoc => matches!(c, '$' | '`' | '\\'),
+                Quote::RawHeredoc => false,
+            } || (self.in_param && c == '}');
+
+            if c == '\n' && self.quote == Quote::None {
+                self.push_str("'\n'");
+            } else {
+                if escape {
+                    self.push('\\');
+                }
+                self.push(c);
+            }
+        }
+    }
+
+    fn escaped(&mut self, s: &str) {
+        self.expanded = true;
+        for c in s.chars() {
+            if c == '\n' && self.quote == Quote::None {
+                self.push_str("'\n'");
+            } else {
+                self.push('\\');
+                self.push(c);
+            }
+        }
+    }
+
+    /// Writes a word using the provided quoting, restoring the current
+    /// quoting afterwards.
+    fn quoted<W: ?Sized + Unparse>(&mut self, quote: Quote, in_param: bool, word: &W) {
+        let outer = mem::replace(&mut self.quote, quote);
+        let outer_in_param = mem::replace(&mut self.in_param, in_param);
+        word.unparse(self);
+        self.quote = outer;
+        self.in_param = outer_in_param;
+    }
+
+    /// Writes the start of a heredoc, and queues its body to be written
+    /// after the current line.
+    ///
+    /// Bodies without any expansions are written verbatim (using a quoted
+    /// delimiter), otherwise any special characters are escaped.
+    fn heredoc<W: Unparse>(&mut self, body: &W) {
+        let start = self.buf.len();
+        let outer_expanded = mem::replace(&mut self.expanded, false);
+        self.quoted(Quote::RawHeredoc, false, body);
+        let raw = !mem::replace(&mut self.expanded, outer_expanded);
+        if !raw {
+            self.buf.truncate(start);
+            self.quoted(Quote::Heredoc, false, body);
+        }
+
+        let mut body = self.buf.split_off(start);
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+
+        let mut delim = String::from("EOF");
+        let mut suffix = 0;
+        while body.lines().any(|line| line == delim) {
+            suffix += 1;
+            delim = format!("EOF{}", suffix);
+        }
+
+        self.push_str("<<");
+        if raw {
+            self.push('\'');
+            self.push_str(&delim);
+            self.push('\'');
+        } else {
+            self.push_str(&delim);
+        }
+        body.push_str(&delim);
+        body.push('\n');
+        self.heredocs.push(body);
+    }
+
+    fn finish(mut self) -> String {
+        if !self.heredocs.is_empty() {
+            self.newline();
+        }
+
+        self.buf
+    }
+}
+
+/// Characters which have no special meaning in unquoted words.
+fn is_unquoted_safe(c: char) -> bool {
+    c.is_alphanumeric() || "_-./=:,+%@^".contains(c)
+}
+
+/// An AST node which can be written as shell source code.
+pub trait Unparse {
+    /// Writes the node's source code to `out`.
+    fn unparse(&self, out: &mut SourceWriter);
+}
+
+impl<T: ?Sized + Unparse> Unparse for Box<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        (**self).unparse(out)
+    }
+}
+
+impl<T: ?Sized + Unparse> Unparse for Rc<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        (**self).unparse(out)
+    }
+}
+
+impl<T: ?Sized + Unparse> Unparse for Arc<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        (**self).unparse(out)
+    }
+}
+
+macro_rules! impl_top_level {
+    ($($Node:ident),*) => {
+        $(
+            impl<T: Borrow<String>> Unparse for ast::$Node<T> {
+                fn unparse(&self, out: &mut SourceWriter) {
+                    self.0.unparse(out)
+                }
+            }
+        )*
+    };
+}
+
+impl_top_level!(
+    TopLevelCommand,
+    AtomicTopLevelCommand,
+    TopLevelWord,
+    AtomicTopLevelWord
+);
+
+impl<T: Unparse> Unparse for ast::Command<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::Command::Job(cmd) => {
+                cmd.unparse(out);
+                out.push_str(" &");
+            }
+            ast::Command::List(cmd) => cmd.unparse(out),
+        }
+    }
+}
+
+impl<T: Unparse> Unparse for ast::AndOrList<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        self.first.unparse(out);
+        for cmd in &self.rest {
+            match cmd {
+                ast::AndOr::And(cmd) => {
+                    out.push_str(" && ");
+                    cmd.unparse(out);
+                }
+                ast::AndOr::Or(cmd) => {
+                    out.push_str(" || ");
+                    cmd.unparse(out);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Unparse> Unparse for ast::ListableCommand<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::ListableCommand::Single(cmd) => cmd.unparse(out),
+            ast::ListableCommand::Pipe(invert, cmds) => {
+                if *invert {
+                    out.push_str("! ");
+                }
+
+                for (i, cmd) in cmds.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(" | ");
+                    }
+                    cmd.unparse(out);
+                }
+            }
+        }
+    }
+}
+
+impl<N, S, C, F> Unparse for ast::PipeableCommand<N, S, C, F>
+where
+    N: Borrow<String>,
+    S: Unparse,
+    C: Unparse,
+    F: Unparse,
+{
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::PipeableCommand::Simple(cmd) => cmd.unparse(out),
+            ast::PipeableCommand::Compound(cmd) => cmd.unparse(out),
+            ast::PipeableCommand::FunctionDef(name, body) => {
+                out.push_str(name.borrow());
+                out.push_str("() ");
+                body.unparse(out);
+            }
+        }
+    }
+}
+
+impl<V, W, R> Unparse for ast::SimpleCommand<V, W, R>
+where
+    V: Borrow<String>,
+    W: Unparse,
+    R: Unparse,
+{
+    fn unparse(&self, out: &mut SourceWriter) {
+        let mut first = true;
+        let mut space = |out: &mut SourceWriter| {
+            if !mem::replace(&mut first, false) {
+                out.push(' ');
+            }
+        };
+
+        for item in &self.redirects_or_env_vars {
+            space(out);
+            match item {
+                ast::RedirectOrEnvVar::Redirect(redirect) => redirect.unparse(out),
+                ast::RedirectOrEnvVar::EnvVar(name, val) => {
+                    out.push_str(name.borrow());
+                    out.push('=');
+                    if let Some(val) = val {
+                        val.unparse(out);
+                    }
+                }
+            }
+        }
+
+        for item in &self.redirects_or_cmd_words {
+            space(out);
+            match item {
+                ast::RedirectOrCmdWord::Redirect(redirect) => redirect.unparse(out),
+                ast::RedirectOrCmdWord::CmdWord(word) => word.unparse(out),
+            }
+        }
+    }
+}
+
+impl<K: Unparse, R: Unparse> Unparse for ast::CompoundCommand<K, R> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        self.kind.unparse(out);
+        for redirect in &self.io {
+            out.push(' ');
+            redirect.unparse(out);
+        }
+    }
+}
+
+impl<V, W, C> Unparse for ast::CompoundCommandKind<V, W, C>
+where
+    V: Borrow<String>,
+    W: Unparse,
+    C: Unparse,
+{
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::CompoundCommandKind::Brace(cmds) => {
+                out.push('{');
+                out.block(cmds);
+                out.push('}');
+            }
+            ast::CompoundCommandKind::Subshell(cmds) => {
+                out.push('(');
+                out.block(cmds);
+                out.push(')');
+            }
+            ast::CompoundCommandKind::While(gbp) | ast::CompoundCommandKind::Until(gbp) => {
+                out.push_str(match self {
+                    ast::CompoundCommandKind::While(_) => "while",
+                    _ => "until",
+                });
+                out.block(&gbp.guard);
+                out.push_str("do");
+                out.block(&gbp.body);
+                out.push_str("done");
+            }
+            ast::CompoundCommandKind::If {
+                conditionals,
+                else_branch,
+            } => {
+                for (i, gbp) in conditionals.iter().enumerate() {
+                    out.push_str(if i == 0 { "if" } else { "elif" });
+                    out.block(&gbp.guard);
+                    out.push_str("then");
+                    out.block(&gbp.body);
+                }
+
+                if let Some(body) = else_branch {
+                    out.push_str("else");
+                    out.block(body);
+                }
+
+                out.push_str("fi");
+            }
+            ast::CompoundCommandKind::For { var, words, body } => {
+                out.push_str("for ");
+                out.push_str(var.borrow());
+                if let Some(words) = words {
+                    out.push_str(" in");
+                    for word in words {
+                        out.push(' ');
+                        word.unparse(out);
+                    }
+                }
+
+                out.newline();
+                out.push_str("do");
+                out.block(body);
+                out.push_str("done");
+            }
+            ast::CompoundCommandKind::Case { word, arms } => {
+                out.push_str("case ");
+                word.unparse(out);
+                out.push_str(" in");
+
+                out.indent += 1;
+                for arm in arms {
+                    out.newline();
+                    for (i, pat) in arm.patterns.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(" | ");
+                        }
+                        pat.unparse(out);
+                    }
+                    out.push(')');
+
+                    out.block(&arm.body);
+                    out.push_str(INDENT);
+                    out.push_str(";;");
+                }
+                out.indent -= 1;
+
+                out.newline();
+                out.push_str("esac");
+            }
+        }
+    }
+}
+
+impl<W: Unparse> Unparse for ast::Redirect<W> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        let (fd, op, word) = match self {
+            ast::Redirect::Read(fd, word) => (fd, "<", word),
+            ast::Redirect::Write(fd, word) => (fd, ">", word),
+            ast::Redirect::ReadWrite(fd, word) => (fd, "<>", word),
+            ast::Redirect::Append(fd, word) => (fd, ">>", word),
+            ast::Redirect::Clobber(fd, word) => (fd, ">|", word),
+            ast::Redirect::DupRead(fd, word) => (fd, "<&", word),
+            ast::Redirect::DupWrite(fd, word) => (fd, ">&", word),
+            ast::Redirect::Heredoc(fd, body) => {
+                if let Some(fd) = fd {
+                    out.push_str(&fd.to_string());
+                }
+                out.heredoc(body);
+                return;
+            }
+        };
+
+        if let Some(fd) = fd {
+            out.push_str(&fd.to_string());
+        }
+        out.push_str(op);
+        word.unparse(out);
+    }
+}
+
+impl<W: Unparse> Unparse for ast::ComplexWord<W> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::ComplexWord::Single(word) => word.unparse(out),
+            ast::ComplexWord::Concat(words) => {
+                for word in words {
+                    word.unparse(out);
+                }
+            }
+        }
+    }
+}
+
+impl<L: Borrow<String>, W: Unparse> Unparse for ast::Word<L, W> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::Word::Simple(word) => word.unparse(out),
+            ast::Word::DoubleQuoted(words) => {
+                out.push('"');
+                let in_param = out.in_param;
+                out.quoted(Quote::Double, in_param, &words[..]);
+                out.push('"');
+            }
+            ast::Word::SingleQuoted(s) => {
+                if out.quote == Quote::None {
+                    out.push('\'');
+                    out.push_str(s.borrow());
+                    out.push('\'');
+                } else {
+                    out.literal(s.borrow());
+                }
+            }
+        }
+    }
+}
+
+impl<W: Unparse> Unparse for [W] {
+    fn unparse(&self, out: &mut SourceWriter) {
+        for word in self {
+            word.unparse(out);
+        }
+    }
+}
+
+impl<L, T, S> Unparse for ast::SimpleWord<L, ast::Parameter<T>, S>
+where
+    L: Borrow<String>,
+    T: Borrow<String>,
+    S: Unparse,
+{
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            ast::SimpleWord::Literal(s) => out.literal(s.borrow()),
+            ast::SimpleWord::Escaped(s) => out.escaped(s.borrow()),
+            ast::SimpleWord::Param(param) => {
+                out.expanded = true;
+                param.unparse(out);
+            }
+            ast::SimpleWord::Subst(subst) => {
+                out.expanded = true;
+                subst.unparse(out);
+            }
+            ast::SimpleWord::Star => out.push('*'),
+            ast::SimpleWord::Question => out.push('?'),
+            ast::SimpleWord::SquareOpen => out.push('['),
+            ast::SimpleWord::SquareClose => out.push(']'),
+            ast::SimpleWord::Tilde => out.push('~'),
+            ast::SimpleWord::Colon => out.push(':'),
+        }
+    }
+}
+
+/// Writes the name of a parameter, as it appears within `${...}`.
+fn param_name<T: Borrow<String>>(param: &ast::Parameter<T>, out: &mut SourceWriter) {
+    match param {
+        ast::Parameter::At => out.push('@'),
+        ast::Parameter::Star => out.push('*'),
+        ast::Parameter::Pound => out.push('#'),
+        ast::Parameter::Question => out.push('?'),
+        ast::Parameter::Dash => out.push('-'),
+        ast::Parameter::Dollar => out.push('$'),
+        ast::Parameter::Bang => out.push('!'),
+        ast::Parameter::Positional(n) => out.push_str(&n.to_string()),
+        ast::Parameter::Var(name) => out.push_str(name.borrow()),
+    }
+}
+
+impl<T: Borrow<String>> Unparse for ast::Parameter<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        match self {
+            // NB: always brace named and positional parameters so they
+            // cannot run into any literals following them
+            ast::Parameter::Positional(_) | ast::Parameter::Var(_) => {
+                out.push_str("${");
+                param_name(self, out);
+                out.push('}');
+            }
+            _ => {
+                out.push('$');
+                param_name(self, out);
+            }
+        }
+    }
+}
+
+impl<T, W, C> Unparse for ast::ParameterSubstitution<ast::Parameter<T>, W, C, ast::Arithmetic<T>>
+where
+    T: Borrow<String>,
+    W: Unparse,
+    C: Unparse,
+{
+    fn unparse(&self, out: &mut SourceWriter) {
+        use conch_parser::ast::ParameterSubstitution::*;
+
+        let (param, op, word) = match self {
+            Command(cmds) => {
+                let outer_heredocs = mem::take(&mut out.heredocs);
+                let outer_quote = mem::replace(&mut out.quote, Quote::None);
+                let outer_in_param = mem::replace(&mut out.in_param, false);
+
+                out.push_str("$(");
+                if !cmds.is_empty() {
+                    out.block(cmds);
+                }
+                out.push(')');
+
+                out.heredocs = outer_heredocs;
+                out.quote = outer_quote;
+                out.in_param = outer_in_param;
+                return;
+            }
+            Arith(expr) => {
+                out.push_str("$((");
+                if let Some(expr) = expr {
+                    out.push(' ');
+                    expr.unparse(out);
+                    out.push(' ');
+                }
+                out.push_str("))");
+                return;
+            }
+            Len(param) => {
+                out.push_str("${#");
+                param_name(param, out);
+                out.push('}');
+                return;
+            }
+            Default(colon, param, word) => (param, if *colon { ":-" } else { "-" }, word),
+            Assign(colon, param, word) => (param, if *colon { ":=" } else { "=" }, word),
+            Error(colon, param, word) => (param, if *colon { ":?" } else { "?" }, word),
+            Alternative(colon, param, word) => (param, if *colon { ":+" } else { "+" }, word),
+            RemoveSmallestSuffix(param, word) => (param, "%", word),
+            RemoveLargestSuffix(param, word) => (param, "%%", word),
+            RemoveSmallestPrefix(param, word) => (param, "#", word),
+            RemoveLargestPrefix(param, word) => (param, "##", word),
+        };
+
+        out.push_str("${");
+        param_name(param, out);
+        out.push_str(op);
+        if let Some(word) = word {
+            let quote = out.quote;
+            out.quoted(quote, true, word);
+        }
+        out.push('}');
+    }
+}
+
+impl<T: Borrow<String>> Unparse for ast::Arithmetic<T> {
+    fn unparse(&self, out: &mut SourceWriter) {
+        use conch_parser::ast::Arithmetic::*;
+
+        let (lhs, op, rhs) = match self {
+            Var(name) => return out.push_str(name.borrow()),
+            Literal(n) => return out.push_str(&n.to_string()),
+            PostIncr(name) => return out.push_str(&format!("{}++", name.borrow())),
+            PostDecr(name) => return out.push_str(&format!("{}--", name.borrow())),
+            PreIncr(name) => return out.push_str(&format!("++{}", name.borrow())),
+            PreDecr(name) => return out.push_str(&format!("--{}", name.borrow())),
+            UnaryPlus(expr) => return unary("+", expr, out),
+            UnaryMinus(expr) => return unary("-", expr, out),
+            LogicalNot(expr) => return unary("!", expr, out),
+            BitwiseNot(expr) => return unary("~", expr, out),
+            Ternary(cond, then, els) => {
+                operand(cond, out);
+                out.push_str(" ? ");
+                operand(then, out);
+                out.push_str(" : ");
+                operand(els, out);
+                return;
+            }
+            Assign(name, expr) => {
+                out.push_str(name.borrow());
+                out.push_str(" = ");
+                operand(expr, out);
+                return;
+            }
+            Sequence(exprs) => {
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    operand(expr, out);
+                }
+                return;
+            }
+            Pow(lhs, rhs) => (lhs, "**", rhs),
+            Mult(lhs, rhs) => (lhs, "*", rhs),
+            Div(lhs, rhs) => (lhs, "/", rhs),
+            Modulo(lhs, rhs) => (lhs, "%", rhs),
+            Add(lhs, rhs) => (lhs, "+", rhs),
+            Sub(lhs, rhs) => (lhs, "-", rhs),
+            ShiftLeft(lhs, rhs) => (lhs, "<<", rhs),
+            ShiftRight(lhs, rhs) => (lhs, ">>", rhs),
+            Less(lhs, rhs) => (lhs, "<", rhs),
+            LessEq(lhs, rhs) => (lhs, "<=", rhs),
+            Great(lhs, rhs) => (lhs, ">", rhs),
+            GreatEq(lhs, rhs) => (lhs, ">=", rhs),
+            Eq(lhs, rhs) => (lhs, "==", rhs),
+            NotEq(lhs, rhs) => (lhs, "!=", rhs),
+            BitwiseAnd(lhs, rhs) => (lhs, "&", rhs),
+            BitwiseXor(lhs, rhs) => (lhs, "^", rhs),
+            BitwiseOr(lhs, rhs) => (lhs, "|", rhs),
+            LogicalAnd(lhs, rhs) => (lhs, "&&", rhs),
+            LogicalOr(lhs, rhs) => (lhs, "||", rhs),
+        };
+
+        operand(lhs, out);
+        out.push(' ');
+        out.push_str(op);
+        out.push(' ');
+        operand(rhs, out);
+    }
+}
+
+fn unary<T: Borrow<String>>(op: &str, expr: &ast::Arithmetic<T>, out: &mut SourceWriter) {
+    out.push_str(op);
+    operand(expr, out);
+}
+
+/// Writes an operand of an arithmetic operator, parenthesizing it (unless
+/// it is a plain variable or literal) so the original precedence is kept.
+fn operand<T: Borrow<String>>(expr: &ast::Arithmetic<T>, out: &mut SourceWriter) {
+    match expr {
+        ast::Arithmetic::Var(_) => expr.unparse(out),
+        ast::Arithmetic::Literal(n) if *n >= 0 => expr.unparse(out),
+        _ => {
+            out.push('(');
+            expr.unparse(out);
+            out.push(')');
+        }
+    }
+}
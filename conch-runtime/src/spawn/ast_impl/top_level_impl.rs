@@ -1,13 +1,15 @@
 use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, EnvRestorer, ExecutableEnvironment,
-    ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, FunctionEnvironment,
-    FunctionFrameEnvironment, IsInteractiveEnvironment, LastStatusEnvironment,
-    ReportErrorEnvironment, SetArgumentsEnvironment, StringWrapper, SubEnvironment,
-    UnsetVariableEnvironment, WorkingDirectoryEnvironment,
+    AliasEnvironment, ArgumentsEnvironment, AsyncIoEnvironment, ControlFlowEnvironment,
+    DynamicVariableEnvironment, EnvRestorer, ExecutableEnvironment, ExportedVariableEnvironment,
+    FileDescEnvironment, FunctionEnvironment, FunctionFrameEnvironment, IsInteractiveEnvironment,
+    LastPipelineStatusEnvironment, LastStatusEnvironment, NestingEnvironment, ProcessIdEnvironment,
+    ReportErrorEnvironment, SetArgumentsEnvironment, ShellOptionsEnvironment, StrKey,
+    StringWrapper, SubEnvironment, TempFileEnvironment, UnsetVariableEnvironment,
+    VariableAttributesEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::error::RuntimeError;
-use crate::eval::{WordEval, WordEvalConfig, WordEvalResult};
+use crate::eval::{PatternFieldsResult, WordEval, WordEvalConfig, WordEvalResult};
 use crate::io::FileDescWrapper;
 use crate::spawn::Spawn;
 use crate::ExitStatus;
@@ -19,25 +21,32 @@ use std::sync::Arc;
 
 impl<T, E> Spawn<E> for AtomicTopLevelCommand<T>
 where
-    T: 'static + StringWrapper + Display + Send + Sync,
-    E: ?Sized
-        + Send
+    T: 'static + StrKey + StringWrapper + Display + Send + Sync,
+    E: Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + ArgumentsEnvironment<Arg = T>
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment<VarName = T, Var = T>
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + FunctionEnvironment
         + FunctionFrameEnvironment
         + IsInteractiveEnvironment
+        + LastPipelineStatusEnvironment
         + LastStatusEnvironment
+        + NestingEnvironment
+        + ProcessIdEnvironment
         + ReportErrorEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment
         + UnsetVariableEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Args: Send + From<VecDeque<E::Arg>>,
     E::Builtin: Send + Sync,
@@ -69,25 +78,32 @@ where
 
 impl<T, E> WordEval<E> for AtomicTopLevelWord<T>
 where
-    T: 'static + StringWrapper + Display + Send + Sync,
-    E: ?Sized
-        + Send
+    T: 'static + StrKey + StringWrapper + Display + Send + Sync,
+    E: Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + ArgumentsEnvironment<Arg = T>
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment<VarName = T, Var = T>
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + FunctionEnvironment
         + FunctionFrameEnvironment
         + IsInteractiveEnvironment
+        + LastPipelineStatusEnvironment
         + LastStatusEnvironment
+        + NestingEnvironment
+        + ProcessIdEnvironment
         + ReportErrorEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment
         + UnsetVariableEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Args: Send + From<VecDeque<E::Arg>>,
     E::Builtin: Send + Sync,
@@ -117,4 +133,34 @@ where
     {
         self.0.eval_with_config(env, cfg)
     }
+
+    fn eval_pattern<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, Result<String, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        self.0.eval_pattern(env, cfg)
+    }
+
+    fn eval_pattern_fields<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+        cfg: WordEvalConfig,
+    ) -> BoxFuture<'async_trait, PatternFieldsResult<Self::EvalResult, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        self.0.eval_pattern_fields(env, cfg)
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        WordEval::<E>::as_literal(&self.0)
+    }
 }
@@ -44,17 +44,77 @@ where
     .await
 }
 
+/// Reports each error (if any), resolving to `EXIT_ERROR` if there were any
+/// errors, or `EXIT_SUCCESS` otherwise.
+pub(crate) async fn report_errs<E, ERR>(
+    builtin_name: &str,
+    env: &mut E,
+    errs: Vec<ERR>,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    ERR: fmt::Display,
+{
+    if errs.is_empty() {
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let mut futures = Vec::with_capacity(errs.len());
+    for err in errs {
+        futures.push(report_err(builtin_name, env, err).await);
+    }
+
+    Box::pin(async move {
+        for future in futures {
+            future.await;
+        }
+
+        EXIT_ERROR
+    })
+}
+
+mod alias;
 mod cd;
+mod control_flow;
+mod declare;
+mod dir_stack;
 mod echo;
+mod env;
+mod exec;
+mod history;
+mod job_spec;
+mod jobs;
+mod kill;
+#[cfg(feature = "conch-parser")]
+mod let_cmd;
+mod local;
 mod pwd;
+mod set;
 mod shift;
 mod trivial;
+mod unset;
 
+pub use self::alias::{alias, unalias};
 pub use self::cd::cd;
+pub use self::control_flow::{break_cmd, continue_cmd, exit, return_cmd};
+pub use self::declare::declare;
+pub use self::dir_stack::{dirs, popd, pushd};
 pub use self::echo::echo;
+pub use self::env::env_cmd;
+pub use self::exec::exec;
+pub use self::history::{fc, history};
+pub use self::jobs::{bg, fg, jobs};
+pub use self::kill::kill;
+#[cfg(feature = "conch-parser")]
+pub use self::let_cmd::let_cmd;
+pub use self::local::local;
 pub use self::pwd::pwd;
+pub use self::set::set;
 pub use self::shift::shift;
 pub use self::trivial::{colon, false_cmd, true_cmd};
+pub use self::unset::unset;
 
 pub(crate) async fn generate_and_print_output<E, F, ERR>(
     builtin_name: &str,
@@ -0,0 +1,150 @@
+use super::{generate_and_print_output, report_errs};
+use crate::env::{AliasEnvironment, AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use clap::{App, AppSettings, Arg};
+use futures_util::future::BoxFuture;
+use void::Void;
+
+const ALIAS: &str = "alias";
+const UNALIAS: &str = "unalias";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: not found")]
+struct NotFoundError(String);
+
+/// The `alias` builtin command will define an alias for each `name=value`
+/// argument, and print the definition of each `name` argument.
+///
+/// Invoking `alias` without arguments (or with `-p`) will print the
+/// definitions of all aliases in a form which can be reused as input.
+pub async fn alias<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AliasEnvironment + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    const ARG_PRINT: &str = "p";
+    const ARG_DEFINITIONS: &str = "definitions";
+
+    let app = App::new(ALIAS)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Defines or displays aliases")
+        .arg(
+            Arg::with_name(ARG_PRINT)
+                .short(ARG_PRINT)
+                .help("Print all defined aliases in a reusable format."),
+        )
+        .arg(
+            Arg::with_name(ARG_DEFINITIONS)
+                .multiple(true)
+                .help("Aliases to define (`name=value`) or print (`name`)."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(ALIAS, app.get_matches_from_safe(args), env);
+
+    let definitions = matches
+        .values_of(ARG_DEFINITIONS)
+        .map(|values| values.map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut out = String::new();
+    if definitions.is_empty() || matches.is_present(ARG_PRINT) {
+        for (name, value) in env.aliases() {
+            out.push_str(&format_alias(name, value));
+        }
+    }
+
+    let mut not_found = Vec::new();
+    for definition in definitions {
+        let mut split = definition.splitn(2, '=');
+        let name = split.next().unwrap_or_default();
+
+        match split.next() {
+            Some(value) => env.set_alias(name.to_owned(), value.to_owned()),
+            None => match env.alias(name) {
+                Some(value) => out.push_str(&format_alias(name, value)),
+                None => not_found.push(NotFoundError(definition.clone())),
+            },
+        }
+    }
+
+    let print = if out.is_empty() {
+        None
+    } else {
+        let bytes = out.into_bytes();
+        Some(generate_and_print_output(ALIAS, env, |_| -> Result<_, Void> { Ok(bytes) }).await)
+    };
+
+    let errors = report_errs(ALIAS, env, not_found).await;
+
+    Box::pin(async move {
+        let print_status = match print {
+            Some(future) => future.await,
+            None => EXIT_SUCCESS,
+        };
+
+        let error_status = errors.await;
+        if print_status.success() {
+            error_status
+        } else {
+            print_status
+        }
+    })
+}
+
+/// The `unalias` builtin command will remove the definition of each alias
+/// specified, or all aliases if invoked with `-a`.
+pub async fn unalias<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AliasEnvironment + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    const ARG_ALL: &str = "a";
+    const ARG_NAMES: &str = "names";
+
+    let app = App::new(UNALIAS)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Removes alias definitions")
+        .arg(
+            Arg::with_name(ARG_ALL)
+                .short(ARG_ALL)
+                .help("Remove all alias definitions."),
+        )
+        .arg(
+            Arg::with_name(ARG_NAMES)
+                .multiple(true)
+                .required_unless(ARG_ALL)
+                .help("The names of the aliases to remove."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(UNALIAS, app.get_matches_from_safe(args), env);
+
+    if matches.is_present(ARG_ALL) {
+        env.unalias_all();
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let not_found = matches
+        .values_of(ARG_NAMES)
+        .into_iter()
+        .flatten()
+        .filter(|name| !env.unalias(name))
+        .map(|name| NotFoundError(name.to_owned()))
+        .collect();
+
+    report_errs(UNALIAS, env, not_found).await
+}
+
+/// Formats an alias definition such that it can be reused as shell input.
+fn format_alias(name: &str, value: &str) -> String {
+    format!("alias {}='{}'\n", name, value.replace('\'', "'\\''"))
+}
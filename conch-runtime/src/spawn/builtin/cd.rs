@@ -1,7 +1,7 @@
 use super::{generate_and_print_output, report_err};
 use crate::env::{
-    AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment, FileDescEnvironment, StringWrapper,
-    VariableEnvironment, WorkingDirectoryEnvironment,
+    AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment, FileDescEnvironment, StrKey,
+    StringWrapper, VariableEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::path::{NormalizationError, NormalizedPath};
 use crate::{ExitStatus, EXIT_SUCCESS, HOME};
@@ -32,10 +32,8 @@ If the specified argument is neither an absolute path, nor begins with ./ or
 discovered using an alternative directory name from $CDPATH, the new working
 directory will be printed to standard output.";
 
-lazy_static::lazy_static! {
-    static ref CDPATH: String = String::from("CDPATH");
-    static ref OLDPWD: String = String::from("OLDPWD");
-}
+const CDPATH: &str = "CDPATH";
+const OLDPWD: &str = "OLDPWD";
 
 #[derive(Debug, thiserror::Error)]
 enum VarNotDefinedError {
@@ -78,7 +76,7 @@ where
         + WorkingDirectoryEnvironment,
     E::FileHandle: Clone,
     E::IoHandle: From<E::FileHandle>,
-    E::VarName: Borrow<String> + From<String>,
+    E::VarName: StrKey + From<String>,
     E::Var: Borrow<String> + From<String>,
 {
     let matches = try_and_report!(CD, get_matches(args.into_iter()), env);
@@ -156,7 +154,7 @@ fn get_new_working_dir<E: ?Sized>(
 ) -> Result<(NormalizedPath, bool), CdError>
 where
     E: VariableEnvironment + WorkingDirectoryEnvironment,
-    E::VarName: Borrow<String>,
+    E::VarName: StrKey,
     E::Var: Borrow<String>,
 {
     let (new_working_dir, should_print_pwd) = get_dir_arg(flags.dir, env)?;
@@ -189,16 +187,16 @@ fn get_dir_arg<'a, E: ?Sized>(
 ) -> Result<(Cow<'a, Path>, bool), VarNotDefinedError>
 where
     E: VariableEnvironment + WorkingDirectoryEnvironment,
-    E::VarName: Borrow<String>,
+    E::VarName: StrKey,
     E::Var: Borrow<String>,
 {
     let mut should_print_pwd = false;
     let dir = match dir {
-        None => match env.var(&HOME) {
+        None => match E::VarName::lookup(env, HOME) {
             Some(home) => Path::new((*home).borrow()),
             None => return Err(VarNotDefinedError::Home),
         },
-        Some("-") => match env.var(&OLDPWD) {
+        Some("-") => match E::VarName::lookup(env, OLDPWD) {
             Some(oldpwd) => {
                 should_print_pwd = true;
                 Path::new((*oldpwd).borrow())
@@ -209,7 +207,7 @@ where
     };
 
     let candidate = if is_cdpath_candidate(dir) {
-        env.var(&CDPATH)
+        E::VarName::lookup(env, CDPATH)
             .and_then(|cdpath| cdpath_candidate(dir, cdpath.borrow().as_str(), env))
     } else {
         None
@@ -248,12 +246,14 @@ where
     cdpaths
         .split(':')
         .map(PathBuf::from)
-        .map(|buf| buf.join(dir))
-        .map(|buf| env.path_relative_to_working_dir(Cow::Owned(buf)))
-        .find(|path| path.is_dir())
+        .map(|buf| env.resolve(&buf.join(dir)))
+        .find(|path| env.path_metadata(path).is_ok_and(|m| m.is_dir()))
+        .map(Cow::Owned)
 }
 
-fn perform_cd_change<E: ?Sized>(
+/// Changes the working directory, updating `$PWD` and `$OLDPWD` accordingly,
+/// and returns the new working directory if it should be printed.
+pub(super) fn perform_cd_change<E: ?Sized>(
     should_print_pwd: bool,
     new_working_dir: PathBuf,
     env: &mut E,
@@ -275,7 +275,7 @@ where
         None
     };
 
-    env.set_var(OLDPWD.clone().into(), old_pwd.into());
+    env.set_var(OLDPWD.to_owned().into(), old_pwd.into());
     env.set_var("PWD".to_owned().into(), pwd.into());
 
     Ok(ret)
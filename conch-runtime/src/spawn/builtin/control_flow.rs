@@ -0,0 +1,199 @@
+use super::report_err;
+use crate::env::{
+    AsyncIoEnvironment, ControlFlowEnvironment, FileDescEnvironment, FunctionFrameEnvironment,
+    LastStatusEnvironment, StringWrapper,
+};
+use crate::error::ControlFlow;
+use crate::{ExitStatus, EXIT_SUCCESS};
+use clap::{App, AppSettings, Arg};
+use futures_util::future::BoxFuture;
+use std::convert::TryFrom;
+
+const BREAK: &str = "break";
+const CONTINUE: &str = "continue";
+const EXIT: &str = "exit";
+const RETURN: &str = "return";
+
+#[derive(Debug, thiserror::Error)]
+#[error("numeric argument required")]
+struct NumericArgumentRequiredError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("can only `return` from a function")]
+struct NotInFunctionError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("loop count out of range")]
+struct LoopCountOutOfRangeError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("only meaningful in a `for`, `while`, or `until` loop")]
+struct NotInLoopError;
+
+/// The `break` builtin command will stop executing the `n`th enclosing
+/// `for`, `while`, or `until` loop (or the innermost loop, if not specified).
+///
+/// If `n` is greater than the number of enclosing loops, the outermost
+/// loop will be exited.
+pub async fn break_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    request_loop_control(BREAK, args, env, ControlFlow::Break).await
+}
+
+/// The `continue` builtin command will skip to the next iteration of the
+/// `n`th enclosing `for`, `while`, or `until` loop (or the innermost loop,
+/// if not specified).
+///
+/// If `n` is greater than the number of enclosing loops, the outermost
+/// loop will be resumed.
+pub async fn continue_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    request_loop_control(CONTINUE, args, env, ControlFlow::Continue).await
+}
+
+async fn request_loop_control<I, E, F>(
+    name: &'static str,
+    args: I,
+    env: &mut E,
+    make_request: F,
+) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    F: FnOnce(usize) -> ControlFlow,
+{
+    const LOOP_COUNT_ARG_NAME: &str = "n";
+
+    let app = App::new(name)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .setting(AppSettings::AllowNegativeNumbers)
+        .arg(
+            Arg::with_name(LOOP_COUNT_ARG_NAME)
+                .help("the number of enclosing loops to affect, defaults to 1")
+                .validator(|n| {
+                    n.parse::<i64>()
+                        .map(|_| ())
+                        .map_err(|_| NumericArgumentRequiredError.to_string())
+                }),
+        );
+
+    let app_args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(name, app.get_matches_from_safe(app_args), env);
+
+    let n = matches
+        .value_of(LOOP_COUNT_ARG_NAME)
+        .and_then(|n| n.parse::<i64>().ok())
+        .unwrap_or(1);
+
+    if n < 1 {
+        return report_err(name, env, LoopCountOutOfRangeError).await;
+    }
+
+    let depth = env.loop_depth();
+    if depth == 0 {
+        return report_err(name, env, NotInLoopError).await;
+    }
+
+    let n = usize::try_from(n).map_or(depth, |n| n.min(depth));
+    env.request_control_flow(make_request(n));
+    Box::pin(async { EXIT_SUCCESS })
+}
+
+/// The `return` builtin command will stop executing the current function
+/// and cause it to exit with the specified status (or the status of the
+/// last command which was run, if not specified).
+///
+/// It is an error to invoke `return` outside of a function.
+pub async fn return_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + FunctionFrameEnvironment
+        + LastStatusEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let status = try_and_report!(RETURN, parse_status(RETURN, args, env), env);
+
+    if !env.is_fn_running() {
+        return report_err(RETURN, env, NotInFunctionError).await;
+    }
+
+    env.request_control_flow(ControlFlow::Return(status));
+    Box::pin(async move { status })
+}
+
+/// The `exit` builtin command will stop executing the current shell (or
+/// subshell) and cause it to exit with the specified status (or the status
+/// of the last command which was run, if not specified).
+///
+/// Note that no `EXIT` traps are run at this time.
+pub async fn exit<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let status = try_and_report!(EXIT, parse_status(EXIT, args, env), env);
+
+    env.request_control_flow(ControlFlow::Exit(status));
+    Box::pin(async move { status })
+}
+
+fn parse_status<I, E>(name: &'static str, args: I, env: &E) -> Result<ExitStatus, clap::Error>
+where
+    I: Iterator<Item = String>,
+    E: ?Sized + LastStatusEnvironment,
+{
+    const STATUS_ARG_NAME: &str = "n";
+
+    let app = App::new(name)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .setting(AppSettings::AllowNegativeNumbers)
+        .arg(
+            Arg::with_name(STATUS_ARG_NAME)
+                .help("the status to exit with, defaults to the status of the last command")
+                .validator(|n| {
+                    n.parse::<i32>()
+                        .map(|_| ())
+                        .map_err(|_| NumericArgumentRequiredError.to_string())
+                }),
+        );
+
+    app.get_matches_from_safe(args).map(|matches| {
+        matches
+            .value_of(STATUS_ARG_NAME)
+            .and_then(|n| n.parse::<i32>().ok())
+            // Only the least significant 8 bits are visible to a parent process
+            .map_or_else(|| env.last_status(), |n| ExitStatus::Code(n & 0xff))
+    })
+}
@@ -0,0 +1,288 @@
+use super::{generate_and_print_output, report_errs};
+use crate::env::{
+    AsyncIoEnvironment, DynamicVariableEnvironment, ExportedVariableEnvironment,
+    FileDescEnvironment, StrKey, StringWrapper, VarAttributes, VariableAttributesEnvironment,
+};
+use crate::error::ExpansionError;
+use crate::eval::assignable_value;
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+use void::Void;
+
+const DECLARE: &str = "declare";
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+enum DeclareError {
+    #[error("{0}{1}: invalid option")]
+    InvalidOption(char /* sign */, char),
+    #[error("`{0}': not a valid identifier")]
+    InvalidIdentifier(String),
+    #[error("{0}: cannot convert between indexed and associative arrays")]
+    ArrayConversion(String),
+    #[error("{0}: cannot destroy array variables in this way")]
+    ArrayDestruction(String),
+    #[error("{0}: not found")]
+    NotFound(String),
+    #[error(transparent)]
+    Assignment(#[from] ExpansionError),
+}
+
+/// The changes to apply to the attributes of each declared variable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Flags {
+    integer: Option<bool>,
+    readonly: Option<bool>,
+    export: Option<bool>,
+    array: Option<bool>,
+    assoc: Option<bool>,
+    print: bool,
+}
+
+/// The `declare` (or `typeset`) builtin command will declare each `name` or
+/// `name=value` argument as a variable, setting any attributes specified
+/// via `-i` (integer), `-r` (readonly), `-x` (exported), `-a` (indexed
+/// array), and `-A` (associative array), or removing them if specified via
+/// `+i` and `+x` instead.
+///
+/// Invoking `declare` without any names (or with `-p`) will print the
+/// definitions of the specified variables (or all variables which have all
+/// of the specified attributes) in a form which can be reused as input.
+///
+/// Variables declared without a value which are not already set remain
+/// unset. Since array variables are not yet supported, those declared via
+/// `-a` or `-A` hold a single element which is accessed through their name.
+/// Integer variables (i.e. `-i`) require the `conch-parser` feature.
+pub async fn declare<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + DynamicVariableEnvironment
+        + ExportedVariableEnvironment
+        + FileDescEnvironment
+        + VariableAttributesEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::Var: Clone + Borrow<String> + From<String>,
+    E::VarName: Clone + StrKey + From<String>,
+{
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let (flags, declarations) = try_and_report!(DECLARE, parse_args(args), env);
+
+    let mut out = String::new();
+    if declarations.is_empty() {
+        let mut vars = env.all_vars();
+        vars.sort_by(|(a, _, _), (b, _, _)| a.as_key_str().cmp(b.as_key_str()));
+
+        for (name, val, exported) in vars {
+            let attrs = env.var_attributes(&name);
+            let matches = (flags.integer != Some(true) || attrs.integer)
+                && (flags.readonly != Some(true) || attrs.readonly)
+                && (flags.export != Some(true) || exported)
+                && (flags.array != Some(true) || attrs.array)
+                && (flags.assoc != Some(true) || attrs.assoc);
+
+            if matches {
+                out.push_str(&format_declaration(
+                    name.as_key_str(),
+                    val.borrow(),
+                    attrs,
+                    exported,
+                ));
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    for declaration in declarations {
+        let mut split = declaration.splitn(2, '=');
+        let name = split.next().unwrap_or_default().to_owned();
+        let value = split.next().map(str::to_owned);
+
+        if flags.print && value.is_none() {
+            let key = E::VarName::from(name.clone());
+            match env.exported_var(&key) {
+                Some((val, exported)) => out.push_str(&format_declaration(
+                    &name,
+                    val.borrow(),
+                    env.var_attributes(&key),
+                    exported,
+                )),
+                None => errors.push(DeclareError::NotFound(name)),
+            }
+            continue;
+        }
+
+        if let Err(e) = declare_var(flags, name, value, env) {
+            errors.push(e);
+        }
+    }
+
+    let print = if out.is_empty() {
+        None
+    } else {
+        let bytes = out.into_bytes();
+        Some(generate_and_print_output(DECLARE, env, |_| -> Result<_, Void> { Ok(bytes) }).await)
+    };
+
+    let errors = report_errs(DECLARE, env, errors).await;
+
+    Box::pin(async move {
+        let print_status = match print {
+            Some(future) => future.await,
+            None => EXIT_SUCCESS,
+        };
+
+        let error_status = errors.await;
+        if print_status.success() {
+            error_status
+        } else {
+            print_status
+        }
+    })
+}
+
+fn parse_args<I: Iterator<Item = String>>(
+    mut args: I,
+) -> Result<(Flags, Vec<String>), DeclareError> {
+    let mut flags = Flags::default();
+
+    while let Some(arg) = args.next() {
+        let mut chars = arg.chars();
+        let enable = match chars.next() {
+            Some('-') if arg == "--" => break,
+            Some('-') if arg != "-" => true,
+            Some('+') if arg != "+" => false,
+            _ => return Ok((flags, Some(arg).into_iter().chain(args).collect())),
+        };
+
+        let sign = if enable { '-' } else { '+' };
+        for flag in chars {
+            match flag {
+                #[cfg(feature = "conch-parser")]
+                'i' => flags.integer = Some(enable),
+                'r' => flags.readonly = Some(enable),
+                'x' => flags.export = Some(enable),
+                'a' => flags.array = Some(enable),
+                'A' => flags.assoc = Some(enable),
+                'p' => flags.print = true,
+                _ => return Err(DeclareError::InvalidOption(sign, flag)),
+            }
+        }
+    }
+
+    Ok((flags, args.collect()))
+}
+
+fn declare_var<E>(
+    flags: Flags,
+    name: String,
+    value: Option<String>,
+    env: &mut E,
+) -> Result<(), DeclareError>
+where
+    E: ?Sized
+        + DynamicVariableEnvironment
+        + ExportedVariableEnvironment
+        + VariableAttributesEnvironment,
+    E::Var: Clone + Borrow<String> + From<String>,
+    E::VarName: Clone + StrKey + From<String>,
+{
+    if !is_valid_name(&name) {
+        return Err(DeclareError::InvalidIdentifier(name));
+    }
+
+    let key = E::VarName::from(name.clone());
+    let attrs = env.var_attributes(&key);
+    let new_attrs = VarAttributes {
+        integer: flags.integer.unwrap_or(attrs.integer),
+        readonly: flags.readonly.unwrap_or(attrs.readonly) || attrs.readonly,
+        array: flags.array.unwrap_or(attrs.array) || attrs.array,
+        assoc: flags.assoc.unwrap_or(attrs.assoc) || attrs.assoc,
+    };
+
+    if (flags.array == Some(false) && attrs.array) || (flags.assoc == Some(false) && attrs.assoc) {
+        return Err(DeclareError::ArrayDestruction(name));
+    }
+
+    if new_attrs.array && new_attrs.assoc {
+        return Err(DeclareError::ArrayConversion(name));
+    }
+
+    if attrs.readonly && (value.is_some() || new_attrs != attrs) {
+        return Err(ExpansionError::ReadonlyVariable(name).into());
+    }
+
+    // Make the variable readonly only after assigning its new value
+    env.set_var_attributes(
+        key.clone(),
+        VarAttributes {
+            readonly: attrs.readonly,
+            ..new_attrs
+        },
+    );
+
+    let value = match value {
+        Some(value) => Some(assignable_value(&key, E::Var::from(value), env)?),
+        None => env.var(&key).cloned(),
+    };
+
+    if let Some(value) = value {
+        match flags.export {
+            Some(export) => env.set_exported_var(key.clone(), value, export),
+            None => env.set_var(key.clone(), value),
+        }
+    }
+
+    env.set_var_attributes(key, new_attrs);
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Formats a variable declaration such that it can be reused as shell input.
+fn format_declaration(name: &str, value: &str, attrs: VarAttributes, exported: bool) -> String {
+    let mut flags = String::new();
+    if attrs.array {
+        flags.push('a');
+    }
+    if attrs.assoc {
+        flags.push('A');
+    }
+    if attrs.integer {
+        flags.push('i');
+    }
+    if attrs.readonly {
+        flags.push('r');
+    }
+    if exported {
+        flags.push('x');
+    }
+    if flags.is_empty() {
+        flags.push('-');
+    }
+
+    let mut quoted = String::with_capacity(value.len());
+    for c in value.chars() {
+        if let '"' | '\\' | '$' | '`' = c {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    if attrs.array || attrs.assoc {
+        format!("{} -{} {}=([0]=\"{}\")\n", DECLARE, flags, name, quoted)
+    } else {
+        format!("{} -{} {}=\"{}\"\n", DECLARE, flags, name, quoted)
+    }
+}
@@ -0,0 +1,407 @@
+use super::cd::perform_cd_change;
+use super::{generate_and_print_output, report_err};
+use crate::env::{
+    AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment, DirStackEnvironment,
+    FileDescEnvironment, StrKey, StringWrapper, VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::path::NormalizedPath;
+use crate::{ExitStatus, EXIT_SUCCESS, HOME};
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+use std::io;
+use std::path::{Path, PathBuf};
+use void::Void;
+
+const DIRS: &str = "dirs";
+const POPD: &str = "popd";
+const PUSHD: &str = "pushd";
+
+#[derive(Debug, thiserror::Error)]
+enum DirStackError {
+    #[error("directory stack empty")]
+    Empty,
+    #[error("no other directory")]
+    NoOtherDirectory,
+    #[error("{0}: directory stack index out of range")]
+    OutOfRange(String),
+    #[error("{0}: invalid option")]
+    InvalidOption(String),
+    #[error("{0}: invalid argument")]
+    InvalidArgument(String),
+    #[error("too many arguments")]
+    TooManyArgs,
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// How the directory stack should be printed.
+#[derive(Debug, Default, Clone, Copy)]
+struct Style {
+    /// Print full paths, without abbreviating `$HOME` to `~`.
+    long: bool,
+    /// Print one directory per line.
+    per_line: bool,
+    /// Print one directory per line, prefixed with its position in the stack.
+    verbose: bool,
+}
+
+/// The parsed arguments of a directory stack builtin.
+#[derive(Debug, Default)]
+struct Args {
+    /// Options (without the leading `-`) which were specified.
+    options: Vec<char>,
+    /// Any non-option arguments, including stack indices like `+N` or `-N`.
+    operands: Vec<String>,
+}
+
+/// The `dirs` builtin command will print the directory stack, starting
+/// with the current working directory.
+///
+/// Invoking `dirs -c` will clear the stack, `-l` will not abbreviate `$HOME`
+/// as `~`, `-p` will print one directory per line, and `-v` will also print
+/// the position of each directory. Specifying `+N` (or `-N`) will only print
+/// the Nth directory counting from the left (or right) of the stack.
+pub async fn dirs<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + DirStackEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let args = try_and_report!(DIRS, parse_args(args, "clpv"), env);
+
+    if args.options.contains(&'c') {
+        env.set_dir_stack(Vec::new());
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let mut style = Style::default();
+    for option in args.options {
+        match option {
+            'l' => style.long = true,
+            'p' => style.per_line = true,
+            _ => style.verbose = true,
+        }
+    }
+
+    let mut stack = full_stack(env);
+    let mut offset = 0;
+    match args.operands.as_slice() {
+        [] => {}
+        [operand] => match stack_index(operand, stack.len()) {
+            Some(Ok(index)) => {
+                stack = vec![stack.swap_remove(index)];
+                offset = index;
+            }
+            Some(Err(e)) => return report_err(DIRS, env, e).await,
+            None => {
+                return report_err(DIRS, env, DirStackError::InvalidArgument(operand.clone())).await
+            }
+        },
+        _ => return report_err(DIRS, env, DirStackError::TooManyArgs).await,
+    }
+
+    let out = format_stack(&stack, offset, style, env);
+    generate_and_print_output(DIRS, env, |_| -> Result<_, Void> { Ok(out.into_bytes()) }).await
+}
+
+/// The `pushd` builtin command will save the current working directory on
+/// the directory stack and change to the specified directory.
+///
+/// Without arguments, the current working directory is swapped with the top
+/// of the stack. Specifying `+N` (or `-N`) rotates the stack such that the
+/// Nth directory counting from the left (or right) becomes the new working
+/// directory. Invoking `pushd -n` will only manipulate the stack, keeping the
+/// current working directory unchanged. The resulting stack is printed
+/// like `dirs` would.
+pub async fn pushd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ChangeWorkingDirectoryEnvironment
+        + DirStackEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let args = try_and_report!(PUSHD, parse_args(args, "n"), env);
+    let no_cd = !args.options.is_empty();
+
+    let result = match args.operands.as_slice() {
+        [] => rotate(1, no_cd, env),
+        [operand] => match stack_index(operand, env.dir_stack().len() + 1) {
+            Some(Ok(index)) => rotate(index, no_cd, env),
+            Some(Err(e)) => Err(e),
+            None => push(Path::new(operand), no_cd, env),
+        },
+        _ => Err(DirStackError::TooManyArgs),
+    };
+
+    try_and_report!(PUSHD, result, env);
+    print_stack(PUSHD, env).await
+}
+
+/// The `popd` builtin command will remove the top of the directory stack
+/// and change to that directory.
+///
+/// Specifying `+N` (or `-N`) removes the Nth directory counting from the left
+/// (or right) of the stack instead, changing directories only if the current
+/// working directory is removed. Invoking `popd -n` will only manipulate the
+/// stack, keeping the current working directory unchanged. The resulting
+/// stack is printed like `dirs` would.
+pub async fn popd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ChangeWorkingDirectoryEnvironment
+        + DirStackEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: StrKey + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let args = try_and_report!(POPD, parse_args(args, "n"), env);
+    let no_cd = !args.options.is_empty();
+
+    let index = match args.operands.as_slice() {
+        [] => Ok(0),
+        [operand] => stack_index(operand, env.dir_stack().len() + 1)
+            .unwrap_or_else(|| Err(DirStackError::InvalidArgument(operand.clone()))),
+        _ => Err(DirStackError::TooManyArgs),
+    };
+
+    let index = try_and_report!(POPD, index, env);
+    try_and_report!(POPD, pop(index, no_cd, env), env);
+    print_stack(POPD, env).await
+}
+
+fn parse_args<I>(args: I, valid_options: &str) -> Result<Args, DirStackError>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+{
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().map(StringWrapper::into_owned);
+
+    for arg in &mut args {
+        if arg == "--" {
+            break;
+        }
+
+        let is_option =
+            arg.len() > 1 && arg.starts_with('-') && !arg[1..].chars().all(|c| c.is_ascii_digit());
+
+        if !is_option {
+            parsed.operands.push(arg);
+            continue;
+        }
+
+        for option in arg[1..].chars() {
+            if !valid_options.contains(option) {
+                return Err(DirStackError::InvalidOption(format!("-{}", option)));
+            }
+
+            parsed.options.push(option);
+        }
+    }
+
+    parsed.operands.extend(args);
+    Ok(parsed)
+}
+
+/// Parses an index (`+N` or `-N`) into a stack of the specified length, or
+/// `None` if `arg` is not an index at all.
+fn stack_index(arg: &str, len: usize) -> Option<Result<usize, DirStackError>> {
+    let (from_left, n) = match (arg.strip_prefix('+'), arg.strip_prefix('-')) {
+        (Some(n), _) => (true, n),
+        (_, Some(n)) => (false, n),
+        (None, None) => return None,
+    };
+
+    let n = n.parse::<usize>().ok()?;
+    let out_of_range = || DirStackError::OutOfRange(arg.to_owned());
+
+    let index = if n >= len {
+        Err(out_of_range())
+    } else if from_left {
+        Ok(n)
+    } else {
+        Ok(len - 1 - n)
+    };
+
+    Some(index)
+}
+
+/// Returns the whole directory stack, starting with the current working directory.
+fn full_stack<E>(env: &E) -> Vec<PathBuf>
+where
+    E: ?Sized + DirStackEnvironment + WorkingDirectoryEnvironment,
+{
+    let mut stack = Vec::with_capacity(env.dir_stack().len() + 1);
+    stack.push(env.current_working_dir().to_path_buf());
+    stack.extend_from_slice(env.dir_stack());
+    stack
+}
+
+fn push<E>(dir: &Path, no_cd: bool, env: &mut E) -> Result<(), DirStackError>
+where
+    E: ?Sized
+        + ChangeWorkingDirectoryEnvironment
+        + DirStackEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    let dir = NormalizedPath::new_normalized_logical(env.resolve(dir)).into_inner();
+
+    if no_cd {
+        env.push_dir(dir);
+    } else {
+        let old_dir = env.current_working_dir().to_path_buf();
+        perform_cd_change(false, dir, env)?;
+        env.push_dir(old_dir);
+    }
+
+    Ok(())
+}
+
+/// Rotates the stack such that the directory at `index` is at the top.
+fn rotate<E>(index: usize, no_cd: bool, env: &mut E) -> Result<(), DirStackError>
+where
+    E: ?Sized
+        + ChangeWorkingDirectoryEnvironment
+        + DirStackEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    if env.dir_stack().is_empty() {
+        return Err(DirStackError::NoOtherDirectory);
+    }
+
+    if no_cd {
+        // The working directory stays put, so only rotate the saved directories
+        let mut saved = env.dir_stack().to_vec();
+        saved.rotate_left(index.saturating_sub(1));
+        env.set_dir_stack(saved);
+        return Ok(());
+    }
+
+    let mut stack = full_stack(env);
+    stack.rotate_left(index);
+
+    let mut stack = stack.into_iter();
+    if let Some(dir) = stack.next() {
+        perform_cd_change(false, dir, env)?;
+    }
+
+    env.set_dir_stack(stack.collect());
+    Ok(())
+}
+
+/// Removes the directory at `index` of the whole stack.
+fn pop<E>(index: usize, no_cd: bool, env: &mut E) -> Result<(), DirStackError>
+where
+    E: ?Sized
+        + ChangeWorkingDirectoryEnvironment
+        + DirStackEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    if env.dir_stack().is_empty() {
+        return Err(DirStackError::Empty);
+    }
+
+    if index > 0 || no_cd {
+        env.remove_dir(index.saturating_sub(1));
+        return Ok(());
+    }
+
+    let dir = env.dir_stack()[0].clone();
+    perform_cd_change(false, dir, env)?;
+    env.remove_dir(0);
+    Ok(())
+}
+
+async fn print_stack<E>(builtin_name: &str, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized
+        + AsyncIoEnvironment
+        + DirStackEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let out = format_stack(&full_stack(env), 0, Style::default(), env);
+    generate_and_print_output(builtin_name, env, |_| -> Result<_, Void> {
+        Ok(out.into_bytes())
+    })
+    .await
+}
+
+/// Formats the directories of a stack, the first of which is at `offset`
+/// within the whole stack.
+fn format_stack<E>(stack: &[PathBuf], offset: usize, style: Style, env: &E) -> String
+where
+    E: ?Sized + VariableEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let home = E::VarName::lookup(env, HOME)
+        .map(|home| home.borrow().as_str())
+        .filter(|home| !home.is_empty() && !style.long);
+
+    let display = |dir: &Path| -> String {
+        let abbreviated = home.and_then(|home| dir.strip_prefix(home).ok());
+        match abbreviated {
+            Some(rest) if rest.as_os_str().is_empty() => String::from("~"),
+            Some(rest) => format!("~/{}", rest.display()),
+            None => dir.display().to_string(),
+        }
+    };
+
+    let mut out = String::new();
+    if style.verbose {
+        for (index, dir) in stack.iter().enumerate() {
+            out.push_str(&format!("{:2}  {}\n", offset + index, display(dir)));
+        }
+    } else if style.per_line {
+        for dir in stack {
+            out.push_str(&display(dir));
+            out.push('\n');
+        }
+    } else {
+        let dirs = stack.iter().map(|dir| display(dir)).collect::<Vec<_>>();
+        out.push_str(&dirs.join(" "));
+        out.push('\n');
+    }
+
+    out
+}
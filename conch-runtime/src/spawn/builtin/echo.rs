@@ -1,5 +1,7 @@
 use super::generate_and_print_output;
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, ShellOption, ShellOptionsEnvironment, StringWrapper,
+};
 use crate::ExitStatus;
 use futures_util::future::BoxFuture;
 use std::cmp;
@@ -7,16 +9,31 @@ use std::iter::Peekable;
 use void::Void;
 
 /// The `echo` builtin command will print out its arguments joined by a space.
+///
+/// By default, `echo` follows bash's semantics: leading `-n`, `-e`, and `-E`
+/// flags are recognized, and escape sequences are only interpreted if
+/// requested. If the `xpg_echo` shell option is enabled, `echo` follows XSI
+/// semantics instead: no flags are recognized, and escape sequences are
+/// always interpreted.
 pub async fn echo<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
 where
     I: IntoIterator,
     I::Item: StringWrapper,
-    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + ShellOptionsEnvironment,
     E::FileHandle: Clone,
     E::IoHandle: From<E::FileHandle>,
 {
     let args = args.into_iter().fuse().peekable();
-    let (flags, args) = parse_args(args);
+    let (flags, args) = if env.is_option_enabled(ShellOption::XpgEcho) {
+        let flags = Flags {
+            interpret_escapes: true,
+            suppress_newline: false,
+        };
+
+        (flags, Some(args))
+    } else {
+        parse_args(args)
+    };
 
     generate_and_print_output("echo", env, |_| -> Result<_, Void> {
         Ok(generate_output(flags, args.into_iter().flatten()))
@@ -0,0 +1,229 @@
+use super::{generate_and_print_output, generate_and_write_bytes_to_fd_if_present, report_err};
+use crate::env::{
+    AsyncIoEnvironment, ExecutableData, ExecutableEnvironment, ExportedVariableEnvironment,
+    FileDescEnvironment, StrKey, StringWrapper, WorkingDirectoryEnvironment,
+};
+use crate::error::CommandError;
+use crate::io::{FileDesc, FileDescWrapper};
+use crate::{
+    ExitStatus, Fd, EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND, EXIT_ERROR, STDERR_FILENO,
+    STDIN_FILENO, STDOUT_FILENO,
+};
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+use std::ffi::OsStr;
+use void::Void;
+
+const ENV: &str = "env";
+
+/// The `env` builtin command prints each exported variable (sorted by name)
+/// as a `name=value` pair, one per line.
+///
+/// Like the `env` utility, any leading `name=value` operands are added to
+/// the printed environment (replacing any previous values), `-i` starts out
+/// with an empty environment instead, and `-u name` removes `name` from it.
+/// If a utility name follows, it is run with the resulting environment
+/// instead of printing it.
+///
+/// Any other options are passed along to the external `env` utility (found
+/// via `$PATH`) instead. Utilities are spawned through the environment's
+/// `ExecutableEnvironment` like any other command, so any resource limits
+/// configured on it apply to them as well.
+pub async fn env_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ExecutableEnvironment
+        + ExportedVariableEnvironment
+        + FileDescEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Clone + StrKey,
+    E::Var: Clone + Borrow<String>,
+{
+    let args = args
+        .into_iter()
+        .map(StringWrapper::into_owned)
+        .collect::<Vec<_>>();
+
+    let mut ignore_env = false;
+    let mut unset = Vec::new();
+    let mut operands = &args[..];
+    while let Some((arg, rest)) = operands.split_first() {
+        match arg.as_str() {
+            "-i" | "-" | "--ignore-environment" => ignore_env = true,
+            "--" => {
+                operands = rest;
+                break;
+            }
+            "-u" | "--unset" => match rest.split_first() {
+                Some((name, rest)) => {
+                    unset.push(name.as_str());
+                    operands = rest;
+                    continue;
+                }
+                None => {
+                    let msg = format!("{}: option requires an argument", arg);
+                    return report_err(ENV, env, msg).await;
+                }
+            },
+            opt if opt.starts_with("--unset=") => unset.push(&opt["--unset=".len()..]),
+            opt if opt.starts_with("-u") => unset.push(&opt["-u".len()..]),
+            opt if opt.starts_with('-') => {
+                let vars = exported_vars(env);
+                return run_utility(ENV, &args, &vars, env).await;
+            }
+            _ => break,
+        }
+
+        operands = rest;
+    }
+
+    let mut vars = if ignore_env {
+        Vec::new()
+    } else {
+        exported_vars(env)
+    };
+
+    vars.retain(|(name, _)| !unset.contains(&name.as_str()));
+
+    while let Some((arg, rest)) = operands.split_first() {
+        let (name, val) = match arg.find('=') {
+            Some(idx) if idx > 0 => (&arg[..idx], &arg[idx + 1..]),
+            _ => break,
+        };
+
+        match vars.iter_mut().find(|(existing, _)| existing == name) {
+            Some(var) => var.1 = val.to_owned(),
+            None => vars.push((name.to_owned(), val.to_owned())),
+        }
+
+        operands = rest;
+    }
+
+    vars.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (utility, utility_args) = match operands.split_first() {
+        Some(split) => split,
+        None => {
+            return generate_and_print_output(ENV, env, |_| -> Result<_, Void> {
+                let mut bytes = Vec::new();
+                for (name, val) in &vars {
+                    bytes.extend_from_slice(name.as_bytes());
+                    bytes.push(b'=');
+                    bytes.extend_from_slice(val.as_bytes());
+                    bytes.push(b'\n');
+                }
+
+                Ok(bytes)
+            })
+            .await;
+        }
+    };
+
+    run_utility(utility, utility_args, &vars, env).await
+}
+
+fn exported_vars<E>(env: &E) -> Vec<(String, String)>
+where
+    E: ?Sized + ExportedVariableEnvironment,
+    E::VarName: Clone + StrKey,
+    E::Var: Clone + Borrow<String>,
+{
+    env.exported_vars()
+        .into_iter()
+        .map(|(name, val)| (name.as_key_str().to_owned(), val.borrow().clone()))
+        .collect()
+}
+
+/// Runs `utility` with only the provided environment variables, reporting
+/// any errors which prevent it from being spawned.
+async fn run_utility<E>(
+    utility: &str,
+    args: &[String],
+    vars: &[(String, String)],
+    env: &mut E,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ExecutableEnvironment
+        + FileDescEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let err = match spawn_utility(utility, args, vars, env) {
+        Ok(future) => return future,
+        Err(e) => e,
+    };
+
+    let status = match err {
+        CommandError::NotFound(_) => EXIT_CMD_NOT_FOUND,
+        CommandError::NotExecutable(_) | CommandError::ArgListTooLong(_, _, _) => {
+            EXIT_CMD_NOT_EXECUTABLE
+        }
+        CommandError::Io(_, _) | CommandError::Timeout(_) => EXIT_ERROR,
+    };
+
+    generate_and_write_bytes_to_fd_if_present(ENV, env, STDERR_FILENO, status, |_| {
+        Ok::<_, Void>(format_err!(ENV, err))
+    })
+    .await
+}
+
+/// Spawns `utility` with only the provided environment variables, and all of
+/// the environment's open file descriptors.
+fn spawn_utility<E>(
+    utility: &str,
+    args: &[String],
+    vars: &[(String, String)],
+    env: &E,
+) -> Result<BoxFuture<'static, ExitStatus>, CommandError>
+where
+    E: ?Sized + ExecutableEnvironment + FileDescEnvironment + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + FileDescWrapper,
+{
+    let get_io = |fd: Fd| -> Result<Option<FileDesc>, CommandError> {
+        match env.file_desc(fd) {
+            None => Ok(None),
+            Some((fdes, _)) => fdes
+                .clone()
+                .try_unwrap()
+                .map(Some)
+                .map_err(|e| CommandError::Io(e, Some(format!("file descriptor {}", fd)))),
+        }
+    };
+
+    let mut inherited_fds = Vec::new();
+    for fd in env.file_descs() {
+        if fd != STDIN_FILENO && fd != STDOUT_FILENO && fd != STDERR_FILENO {
+            if let Some(fdes) = get_io(fd)? {
+                inherited_fds.push((fd, fdes));
+            }
+        }
+    }
+
+    let args = args.iter().map(OsStr::new).collect::<Vec<_>>();
+    let env_vars = vars
+        .iter()
+        .map(|(name, val)| (OsStr::new(name), OsStr::new(val)))
+        .collect::<Vec<_>>();
+
+    env.spawn_executable(ExecutableData {
+        name: OsStr::new(utility),
+        args: &args,
+        env_vars: &env_vars,
+        current_dir: env.current_working_dir(),
+        stdin: get_io(STDIN_FILENO)?,
+        stdout: get_io(STDOUT_FILENO)?,
+        stderr: get_io(STDERR_FILENO)?,
+        inherited_fds,
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
+    })
+}
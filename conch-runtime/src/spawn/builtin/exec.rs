@@ -0,0 +1,31 @@
+use super::report_err;
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+
+const EXEC: &str = "exec";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: replacing the shell is not supported")]
+struct UnsupportedError(String);
+
+/// The `exec` builtin command, which currently only supports being invoked
+/// without a utility to run, e.g. `exec 3>file`.
+///
+/// Any redirections applied to such an invocation should persist in the
+/// current environment, which is up to the caller (e.g. by clearing any
+/// redirect backups via `RedirectEnvRestorer::clear_redirects`). Replacing
+/// the shell with another utility is not supported.
+pub async fn exec<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    match args.into_iter().next() {
+        Some(utility) => report_err(EXEC, env, UnsupportedError(utility.into_owned())).await,
+        None => Box::pin(async { EXIT_SUCCESS }),
+    }
+}
@@ -0,0 +1,211 @@
+use super::{generate_and_print_output, report_err};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, HistoryEnvironment, StringWrapper};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use void::Void;
+
+const FC: &str = "fc";
+const HISTORY: &str = "history";
+
+/// The number of entries `fc -l` lists by default.
+const FC_DEFAULT_COUNT: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+enum HistoryError {
+    #[error("{0}: invalid option")]
+    InvalidOption(String),
+    #[error("{0}: numeric argument required")]
+    NotNumeric(String),
+    #[error("too many arguments")]
+    TooManyArgs,
+    #[error("history specification out of range")]
+    OutOfRange,
+    #[error("only listing commands (-l) is supported")]
+    Unsupported,
+}
+
+/// The `history` builtin command will print the recorded command history,
+/// or only the last `n` entries if specified.
+///
+/// Invoking `history -c` will clear the history instead.
+pub async fn history<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + HistoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut args = args.into_iter().map(StringWrapper::into_owned).peekable();
+
+    let mut clear = false;
+    while let Some(arg) = args.peek() {
+        match arg.as_str() {
+            "--" => {
+                args.next();
+                break;
+            }
+            "-c" => clear = true,
+            arg if arg.len() > 1 && arg.starts_with('-') && arg.parse::<isize>().is_err() => {
+                let err = HistoryError::InvalidOption(arg.to_owned());
+                return report_err(HISTORY, env, err).await;
+            }
+            _ => break,
+        }
+
+        args.next();
+    }
+
+    if clear {
+        env.clear_history();
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let operands = args.collect::<Vec<_>>();
+    let count = match operands.as_slice() {
+        [] => env.history().len(),
+        [count] => match count.parse::<usize>() {
+            Ok(count) => count,
+            Err(_) => {
+                return report_err(HISTORY, env, HistoryError::NotNumeric(count.clone())).await
+            }
+        },
+        _ => return report_err(HISTORY, env, HistoryError::TooManyArgs).await,
+    };
+
+    let base = env.history_base();
+    let entries = env.history();
+    let skip = entries.len().saturating_sub(count);
+
+    let mut out = String::new();
+    for (i, entry) in entries.iter().enumerate().skip(skip) {
+        out.push_str(&format!("{:5}  {}\n", base + i, entry));
+    }
+
+    generate_and_print_output(HISTORY, env, |_| -> Result<_, Void> {
+        Ok(out.into_bytes())
+    })
+    .await
+}
+
+/// The `fc` builtin command will list a range of entries from the command
+/// history when invoked with `-l`.
+///
+/// The range is specified by `first` and `last` operands, each of which can
+/// be an entry number, a negative offset from the most recent entry, or a
+/// string matching the most recent entry which starts with it. By default, the
+/// last 16 entries are listed. Specifying `-n` omits the entry numbers, and
+/// `-r` reverses the order of the listing.
+///
+/// Editing or re-executing entries is not supported.
+pub async fn fc<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + HistoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut args = args.into_iter().map(StringWrapper::into_owned).peekable();
+
+    let mut list = false;
+    let mut numbered = true;
+    let mut reverse = false;
+    while let Some(arg) = args.peek() {
+        if arg == "--" {
+            args.next();
+            break;
+        } else if arg.len() < 2 || !arg.starts_with('-') || arg.parse::<isize>().is_ok() {
+            break;
+        }
+
+        for option in arg[1..].chars() {
+            match option {
+                'l' => list = true,
+                'n' => numbered = false,
+                'r' => reverse = true,
+                'e' | 's' => return report_err(FC, env, HistoryError::Unsupported).await,
+                _ => {
+                    let err = HistoryError::InvalidOption(arg.clone());
+                    return report_err(FC, env, err).await;
+                }
+            }
+        }
+
+        args.next();
+    }
+
+    if !list {
+        return report_err(FC, env, HistoryError::Unsupported).await;
+    }
+
+    let operands = args.collect::<Vec<_>>();
+    if operands.len() > 2 {
+        return report_err(FC, env, HistoryError::TooManyArgs).await;
+    }
+
+    let entries = env.history();
+    if entries.is_empty() {
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let default_first = entries.len().saturating_sub(FC_DEFAULT_COUNT);
+    let first = match operands.first() {
+        Some(spec) => resolve_spec(spec, env),
+        None => Some(default_first),
+    };
+    let last = match operands.get(1) {
+        Some(spec) => resolve_spec(spec, env),
+        None => Some(entries.len() - 1),
+    };
+
+    let (first, last) = match (first, last) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return report_err(FC, env, HistoryError::OutOfRange).await,
+    };
+
+    let mut indices = if first <= last {
+        (first..=last).collect::<Vec<_>>()
+    } else {
+        (last..=first).rev().collect()
+    };
+
+    if reverse {
+        indices.reverse();
+    }
+
+    let base = env.history_base();
+    let mut out = String::new();
+    for i in indices {
+        if numbered {
+            out.push_str(&(base + i).to_string());
+        }
+
+        out.push('\t');
+        out.push_str(&entries[i]);
+        out.push('\n');
+    }
+
+    generate_and_print_output(FC, env, |_| -> Result<_, Void> { Ok(out.into_bytes()) }).await
+}
+
+/// Resolves an `fc` operand to the index of an existing history entry.
+///
+/// Numbers which fall outside of the recorded history are clamped to the
+/// oldest or most recent entry.
+fn resolve_spec<E>(spec: &str, env: &E) -> Option<usize>
+where
+    E: ?Sized + HistoryEnvironment,
+{
+    let entries = env.history();
+    let last = entries.len().checked_sub(1)?;
+
+    match spec.parse::<isize>() {
+        Ok(offset) if offset < 0 => Some(last.saturating_sub(offset.unsigned_abs() - 1)),
+        Ok(number) => {
+            let index = (number as usize).saturating_sub(env.history_base());
+            Some(index.min(last))
+        }
+        Err(_) => entries.iter().rposition(|entry| entry.starts_with(spec)),
+    }
+}
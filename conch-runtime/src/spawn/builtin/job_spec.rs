@@ -0,0 +1,99 @@
+//! Parsing and resolution of job specs (e.g. `%1`, `%%`, `%-`) shared by
+//! the builtins which operate on jobs.
+
+use crate::env::JobEnvironment;
+
+/// A reference to a job, as specified by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JobSpec<'a> {
+    /// The current job: `%%`, `%+`, or `%`.
+    Current,
+    /// The previous job: `%-`.
+    Previous,
+    /// The job with a specific id: `%n`.
+    Id(usize),
+    /// The job whose command begins with some prefix: `%string`.
+    Prefix(&'a str),
+    /// The job whose command contains some string: `%?string`.
+    Contains(&'a str),
+}
+
+/// Errors which can occur when resolving a job spec to a specific job.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum JobSpecError {
+    /// The job spec did not begin with a `%`.
+    #[error("{0}: not a valid job spec")]
+    Invalid(String),
+    /// No jobs matched the job spec.
+    #[error("{0}: no such job")]
+    NoSuchJob(String),
+    /// More than one job matched the job spec.
+    #[error("{0}: ambiguous job spec")]
+    Ambiguous(String),
+}
+
+impl<'a> JobSpec<'a> {
+    /// Parses a job spec, or `None` if `spec` does not start with a `%`.
+    pub(crate) fn parse(spec: &'a str) -> Option<Self> {
+        let spec = spec.strip_prefix('%')?;
+        let spec = match spec {
+            "" | "%" | "+" => JobSpec::Current,
+            "-" => JobSpec::Previous,
+            _ => match spec.strip_prefix('?') {
+                Some(needle) => JobSpec::Contains(needle),
+                None => spec
+                    .parse()
+                    .map(JobSpec::Id)
+                    .unwrap_or(JobSpec::Prefix(spec)),
+            },
+        };
+
+        Some(spec)
+    }
+}
+
+/// Parses and resolves a job spec to the id of a job tracked by the environment.
+pub(crate) fn resolve_job_spec<E>(spec: &str, env: &E) -> Result<usize, JobSpecError>
+where
+    E: ?Sized + JobEnvironment,
+{
+    let parsed = JobSpec::parse(spec).ok_or_else(|| JobSpecError::Invalid(spec.to_owned()))?;
+    let no_such_job = || JobSpecError::NoSuchJob(spec.to_owned());
+
+    let matches: Box<dyn Fn(&str) -> bool + '_> = match parsed {
+        JobSpec::Current => return env.current_job().ok_or_else(no_such_job),
+        JobSpec::Previous => return env.previous_job().ok_or_else(no_such_job),
+        JobSpec::Id(id) => return env.job(id).map(|job| job.id()).ok_or_else(no_such_job),
+        JobSpec::Prefix(prefix) => Box::new(move |command| command.starts_with(prefix)),
+        JobSpec::Contains(needle) => Box::new(move |command| command.contains(needle)),
+    };
+
+    let mut ids = env
+        .jobs()
+        .into_iter()
+        .filter(|job| matches(job.command()))
+        .map(|job| job.id());
+
+    match (ids.next(), ids.next()) {
+        (Some(id), None) => Ok(id),
+        (Some(_), Some(_)) => Err(JobSpecError::Ambiguous(spec.to_owned())),
+        (None, _) => Err(no_such_job()),
+    }
+}
+
+/// Resolves the job referred to by a builtin's optional job spec argument,
+/// defaulting to the current job if no spec was given.
+pub(crate) fn resolve_job_spec_or_current<E>(
+    spec: Option<&str>,
+    env: &E,
+) -> Result<usize, JobSpecError>
+where
+    E: ?Sized + JobEnvironment,
+{
+    match spec {
+        Some(spec) => resolve_job_spec(spec, env),
+        None => env
+            .current_job()
+            .ok_or_else(|| JobSpecError::NoSuchJob("current".to_owned())),
+    }
+}
@@ -0,0 +1,243 @@
+use super::job_spec::{resolve_job_spec, resolve_job_spec_or_current};
+use super::{generate_and_print_output, report_err};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, Job, JobEnvironment, JobState, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use clap::{App, AppSettings, Arg};
+use futures_util::future::BoxFuture;
+use std::io;
+use void::Void;
+
+const BG: &str = "bg";
+const FG: &str = "fg";
+const JOBS: &str = "jobs";
+
+const ARG_JOB_SPEC: &str = "job_spec";
+
+#[derive(Debug, thiserror::Error)]
+#[error("job has terminated")]
+struct TerminatedError;
+
+/// The `jobs` builtin command will print the status of each specified job
+/// (or all jobs if none are specified).
+///
+/// Invoking `jobs -l` will additionally print the process group of each job,
+/// while `jobs -p` will print only the process group of each job. Any jobs
+/// reported as done are no longer tracked afterwards.
+pub async fn jobs<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + JobEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    const ARG_LONG: &str = "l";
+    const ARG_PGID: &str = "p";
+
+    let app = App::new(JOBS)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Displays the status of jobs")
+        .arg(
+            Arg::with_name(ARG_LONG)
+                .short(ARG_LONG)
+                .overrides_with(ARG_PGID)
+                .help("Also print the process group of each job."),
+        )
+        .arg(
+            Arg::with_name(ARG_PGID)
+                .short(ARG_PGID)
+                .overrides_with(ARG_LONG)
+                .help("Only print the process group of each job."),
+        )
+        .arg(
+            Arg::with_name(ARG_JOB_SPEC)
+                .multiple(true)
+                .help("The jobs to print, defaults to all jobs."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(JOBS, app.get_matches_from_safe(args), env);
+
+    let ids = match matches.values_of(ARG_JOB_SPEC) {
+        Some(specs) => {
+            let ids = specs
+                .map(|spec| resolve_job_spec(spec, env))
+                .collect::<Result<Vec<_>, _>>();
+            try_and_report!(JOBS, ids, env)
+        }
+        None => env.jobs().into_iter().map(|job| job.id()).collect(),
+    };
+
+    let current = env.current_job();
+    let previous = env.previous_job();
+
+    let mut out = String::new();
+    let mut done = Vec::new();
+    for id in ids {
+        let job = match env.job(id) {
+            Some(job) => job,
+            None => continue,
+        };
+
+        let state = job.state();
+        if let JobState::Done(_) = state {
+            done.push(id);
+        }
+
+        if matches.is_present(ARG_PGID) {
+            if let Some(pgid) = job.pgid() {
+                out.push_str(&format!("{}\n", pgid));
+            }
+            continue;
+        }
+
+        let marker = if Some(id) == current {
+            '+'
+        } else if Some(id) == previous {
+            '-'
+        } else {
+            ' '
+        };
+
+        out.push_str(&format!("[{}]{} ", id, marker));
+        if matches.is_present(ARG_LONG) {
+            if let Some(pgid) = job.pgid() {
+                out.push_str(&format!("{} ", pgid));
+            }
+        }
+        out.push_str(&format!(" {:<24}{}\n", state.to_string(), job.command()));
+    }
+
+    for id in done {
+        env.remove_job(id);
+    }
+
+    if out.is_empty() {
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let bytes = out.into_bytes();
+    generate_and_print_output(JOBS, env, |_| -> Result<_, Void> { Ok(bytes) }).await
+}
+
+/// The `fg` builtin command will move the specified job (or the current job
+/// if none is specified) into the foreground, resuming it if it was stopped,
+/// and wait for it to complete (or report its status if it has already
+/// completed).
+///
+/// The job is no longer tracked afterwards, and its exit status becomes
+/// the exit status of the builtin.
+pub async fn fg<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + JobEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let app = App::new(FG)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Moves a job into the foreground")
+        .arg(
+            Arg::with_name(ARG_JOB_SPEC)
+                .help("The job to move into the foreground, defaults to the current job."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(FG, app.get_matches_from_safe(args), env);
+
+    let spec = matches.value_of(ARG_JOB_SPEC);
+    let id = try_and_report!(FG, resolve_job_spec_or_current(spec, env), env);
+    try_and_report!(FG, resume(id, env), env);
+
+    let job = match env.remove_job(id) {
+        Some(job) => job,
+        None => return Box::pin(async { EXIT_ERROR }),
+    };
+
+    let status = job.wait();
+    let bytes = format!("{}\n", job.command()).into_bytes();
+    let print = generate_and_print_output(FG, env, |_| -> Result<_, Void> { Ok(bytes) }).await;
+
+    Box::pin(async move {
+        print.await;
+        status.await
+    })
+}
+
+/// The `bg` builtin command will resume each specified job (or the current
+/// job if none are specified) in the background if it was stopped.
+pub async fn bg<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + JobEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let app = App::new(BG)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Resumes jobs in the background")
+        .arg(
+            Arg::with_name(ARG_JOB_SPEC)
+                .multiple(true)
+                .help("The jobs to resume, defaults to the current job."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(BG, app.get_matches_from_safe(args), env);
+
+    let specs = matches
+        .values_of(ARG_JOB_SPEC)
+        .map(|specs| specs.map(Some).collect::<Vec<_>>())
+        .unwrap_or_else(|| vec![None]);
+
+    let mut out = String::new();
+    for spec in specs {
+        let id = try_and_report!(BG, resolve_job_spec_or_current(spec, env), env);
+        if let Some(JobState::Done(_)) = env.job(id).map(Job::state) {
+            return report_err(BG, env, TerminatedError).await;
+        }
+
+        try_and_report!(BG, resume(id, env), env);
+
+        if let Some(job) = env.job(id) {
+            let marker = if env.current_job() == Some(id) {
+                '+'
+            } else {
+                ' '
+            };
+            out.push_str(&format!("[{}]{} {} &\n", id, marker, job.command()));
+        }
+    }
+
+    let bytes = out.into_bytes();
+    generate_and_print_output(BG, env, |_| -> Result<_, Void> { Ok(bytes) }).await
+}
+
+/// Resumes a stopped job (if necessary) and makes it the current job.
+fn resume<E>(id: usize, env: &mut E) -> io::Result<()>
+where
+    E: ?Sized + JobEnvironment,
+{
+    let (state, pgid) = match env.job(id) {
+        Some(job) => (job.state(), job.pgid()),
+        None => return Ok(()),
+    };
+
+    if let JobState::Stopped = state {
+        if let Some(pgid) = pgid {
+            crate::sys::continue_process_group(pgid)?;
+        }
+
+        env.set_job_stopped(id, false);
+    }
+
+    env.set_current_job(id);
+    Ok(())
+}
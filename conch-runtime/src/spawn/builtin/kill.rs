@@ -0,0 +1,217 @@
+use super::job_spec::{resolve_job_spec, JobSpecError};
+use super::{generate_and_print_output, report_errs};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, JobEnvironment, StringWrapper};
+use crate::{sys, ExitStatus};
+use futures_util::future::BoxFuture;
+use std::io;
+use void::Void;
+
+const KILL: &str = "kill";
+
+/// Statuses of commands terminated by a signal are offset by this amount.
+const SIGNAL_STATUS_OFFSET: i32 = 128;
+
+#[derive(Debug, thiserror::Error)]
+enum KillError {
+    #[error("{0}: invalid signal specification")]
+    InvalidSignal(String),
+    #[error("{0}: arguments must be process or job IDs")]
+    InvalidTarget(String),
+    #[error("{0}: job has no process group")]
+    NoProcessGroup(String),
+    #[error("option requires an argument -- {0}")]
+    MissingArgument(char),
+    #[error("usage: kill [-s sigspec | -sigspec] pid | jobspec ... or kill -l [sigspec]")]
+    Usage,
+    #[error("({0}) - {1}")]
+    SendFailed(i32, #[source] io::Error),
+    #[error(transparent)]
+    JobSpec(#[from] JobSpecError),
+}
+
+/// What the `kill` builtin was asked to do.
+#[derive(Debug)]
+enum Mode {
+    /// `kill -l [sigspec...]`: list signals or convert between names and numbers.
+    List(Vec<String>),
+    /// Send a signal to some processes or jobs.
+    Send(i32, Vec<String>),
+}
+
+/// The `kill` builtin command will send a signal (`SIGTERM` by default) to
+/// each specified process or job (e.g. `%1`).
+///
+/// The signal can be specified by name (with or without a `SIG` prefix) or
+/// by number, via `-s sigspec`, `-n signum`, or `-sigspec`. Invoking `kill -l`
+/// will list the names of all supported signals, or convert each argument
+/// between signal names and numbers.
+pub async fn kill<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + JobEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let mode = try_and_report!(KILL, parse_args(args), env);
+
+    let (out, errors) = match mode {
+        Mode::List(specs) => list_signals(specs),
+        Mode::Send(signal, targets) => {
+            let errors = targets
+                .into_iter()
+                .filter_map(|target| send_signal(&target, signal, env).err())
+                .collect();
+
+            (String::new(), errors)
+        }
+    };
+
+    if out.is_empty() {
+        return report_errs(KILL, env, errors).await;
+    }
+
+    let bytes = out.into_bytes();
+    let print = generate_and_print_output(KILL, env, |_| -> Result<_, Void> { Ok(bytes) }).await;
+    let errors = report_errs(KILL, env, errors).await;
+
+    Box::pin(async move {
+        let print_status = print.await;
+        let error_status = errors.await;
+        if print_status.success() {
+            error_status
+        } else {
+            print_status
+        }
+    })
+}
+
+fn parse_args<I>(args: I) -> Result<Mode, KillError>
+where
+    I: Iterator<Item = String>,
+{
+    let mut args = args.peekable();
+    let mut signal = None;
+
+    match args.peek().map(String::as_str) {
+        Some("-l") | Some("-L") => {
+            args.next();
+            return Ok(Mode::List(args.collect()));
+        }
+
+        Some("-s") | Some("-n") => {
+            let flag = args.next().unwrap_or_default();
+            let spec = args
+                .next()
+                .ok_or_else(|| KillError::MissingArgument(flag.chars().nth(1).unwrap_or('s')))?;
+            signal = Some(parse_signal(&spec).ok_or(KillError::InvalidSignal(spec))?);
+        }
+
+        Some("--") => {}
+
+        Some(arg) if arg.len() > 1 && arg.starts_with('-') => {
+            let spec = &arg[1..];
+            let parsed = parse_signal(spec).ok_or_else(|| KillError::InvalidSignal(spec.into()))?;
+            signal = Some(parsed);
+            args.next();
+        }
+
+        _ => {}
+    }
+
+    if args.peek().map(String::as_str) == Some("--") {
+        args.next();
+    }
+
+    let targets = args.collect::<Vec<_>>();
+    if targets.is_empty() {
+        return Err(KillError::Usage);
+    }
+
+    Ok(Mode::Send(signal.unwrap_or(sys::SIGTERM), targets))
+}
+
+/// Parses a signal number or (case insensitive) name, with or without a `SIG` prefix.
+fn parse_signal(spec: &str) -> Option<i32> {
+    if let Ok(signal) = spec.parse::<i32>() {
+        return if signal >= 0 { Some(signal) } else { None };
+    }
+
+    let spec = spec.to_ascii_uppercase();
+    let name = spec.strip_prefix("SIG").unwrap_or(&spec);
+
+    sys::SIGNALS
+        .iter()
+        .find(|&&(signal_name, _)| signal_name == name)
+        .map(|&(_, signal)| signal)
+}
+
+/// Lists the names of all known signals if no specs are given, otherwise
+/// converts each signal number (or status of a signalled command) to its
+/// name, and each signal name to its number.
+fn list_signals(specs: Vec<String>) -> (String, Vec<KillError>) {
+    let mut out = String::new();
+    let mut errors = Vec::new();
+
+    if specs.is_empty() {
+        let mut signals = sys::SIGNALS.to_vec();
+        signals.sort_by_key(|&(_, signal)| signal);
+
+        for (name, _) in signals {
+            out.push_str(name);
+            out.push('\n');
+        }
+    }
+
+    for spec in specs {
+        let converted = match spec.parse::<i32>() {
+            Ok(status) => {
+                let signal = if status > SIGNAL_STATUS_OFFSET {
+                    status - SIGNAL_STATUS_OFFSET
+                } else {
+                    status
+                };
+
+                sys::SIGNALS
+                    .iter()
+                    .find(|&&(_, known)| known == signal)
+                    .map(|&(name, _)| name.to_owned())
+            }
+            Err(_) => parse_signal(&spec).map(|signal| signal.to_string()),
+        };
+
+        match converted {
+            Some(converted) => {
+                out.push_str(&converted);
+                out.push('\n');
+            }
+            None => errors.push(KillError::InvalidSignal(spec)),
+        }
+    }
+
+    (out, errors)
+}
+
+/// Sends a signal to the process (or process group, if negative) or job
+/// specified by `target`.
+fn send_signal<E>(target: &str, signal: i32, env: &E) -> Result<(), KillError>
+where
+    E: ?Sized + JobEnvironment,
+{
+    let pid = if target.starts_with('%') {
+        let id = resolve_job_spec(target, env)?;
+        let pgid = env
+            .job(id)
+            .and_then(|job| job.pgid())
+            .ok_or_else(|| KillError::NoProcessGroup(target.to_owned()))?;
+
+        -(pgid as i32)
+    } else {
+        target
+            .parse::<i32>()
+            .map_err(|_| KillError::InvalidTarget(target.to_owned()))?
+    };
+
+    sys::send_signal(pid, signal).map_err(|e| KillError::SendFailed(pid, e))
+}
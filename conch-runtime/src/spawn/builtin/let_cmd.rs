@@ -0,0 +1,51 @@
+use super::report_err;
+use crate::env::{
+    AsyncIoEnvironment, DynamicVariableEnvironment, FileDescEnvironment, StrKey, StringWrapper,
+    VariableEnvironment,
+};
+use crate::eval::eval_arith_str;
+use crate::spawn::arith_status;
+use crate::ExitStatus;
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+
+const LET: &str = "let";
+
+#[derive(Debug, thiserror::Error)]
+#[error("expression expected")]
+struct ExpressionExpectedError;
+
+/// The `let` builtin command evaluates each argument as an arithmetic
+/// expression (as if it was the body of an arithmetic substitution).
+///
+/// The command exits successfully only if the last expression evaluated to
+/// a non-zero value. Evaluation stops at the first expression which cannot be
+/// parsed or evaluated, after reporting the error.
+pub async fn let_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + DynamicVariableEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::Var: Borrow<String> + From<String>,
+    E::VarName: StrKey + From<String>,
+{
+    let mut last = None;
+    for arg in args {
+        let value = try_and_report!(LET, eval_arith_str(arg.as_str(), env), env);
+        last = Some(value);
+    }
+
+    match last {
+        Some(value) => {
+            let status = arith_status(value);
+            Box::pin(async move { status })
+        }
+        None => report_err(LET, env, ExpressionExpectedError).await,
+    }
+}
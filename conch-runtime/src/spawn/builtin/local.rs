@@ -0,0 +1,54 @@
+use super::report_err;
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, FunctionFrameEnvironment, ShellOptionsEnvironment,
+    StringWrapper,
+};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+
+const LOCAL: &str = "local";
+
+#[derive(Debug, thiserror::Error)]
+#[error("can only be used in a function")]
+struct NotInFunctionError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: local variables are not supported")]
+struct UnsupportedError(String);
+
+/// The `local` builtin command, which currently only supports `local -`.
+///
+/// Invoking `local -` within a function makes any changes to the shell
+/// options (e.g. via `set`) local to that function, such that the options
+/// are restored once it returns. Local variables are not yet supported.
+pub async fn local<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + FunctionFrameEnvironment
+        + ShellOptionsEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    if !env.is_fn_running() {
+        return report_err(LOCAL, env, NotInFunctionError).await;
+    }
+
+    let mut options = false;
+    for arg in args {
+        if arg.as_str() == "-" {
+            options = true;
+        } else {
+            return report_err(LOCAL, env, UnsupportedError(arg.into_owned())).await;
+        }
+    }
+
+    if options {
+        env.make_options_local();
+    }
+
+    Box::pin(async { EXIT_SUCCESS })
+}
@@ -2,11 +2,10 @@ use super::generate_and_print_output;
 use crate::env::{
     AsyncIoEnvironment, FileDescEnvironment, StringWrapper, WorkingDirectoryEnvironment,
 };
-use crate::path::{has_dot_components, NormalizationError, NormalizedPath};
+use crate::path::{has_dot_components, NormalizationError};
 use crate::spawn::ExitStatus;
 use clap::{App, AppSettings, Arg};
 use futures_util::future::BoxFuture;
-use std::path::Path;
 
 const PWD: &str = "pwd";
 
@@ -24,9 +23,9 @@ where
 
     generate_and_print_output(PWD, env, |env| {
         let mut cwd_bytes = if is_physical {
-            physical(env.current_working_dir())
+            physical(env)
         } else {
-            logical(env.current_working_dir())
+            logical(env)
         };
 
         if let Ok(ref mut bytes) = cwd_bytes {
@@ -67,18 +66,23 @@ fn parse_args_is_physical<I: Iterator<Item = String>>(args: I) -> Result<bool, c
         .map(|matches| matches.is_present(ARG_PHYSICAL))
 }
 
-fn logical(path: &Path) -> Result<Vec<u8>, NormalizationError> {
+fn logical<E>(env: &E) -> Result<Vec<u8>, NormalizationError>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    let path = env.current_working_dir();
     if has_dot_components(path) {
-        physical(path)
+        physical(env)
     } else {
         let bytes = path.to_string_lossy().into_owned().into_bytes();
         Ok(bytes)
     }
 }
 
-fn physical(path: &Path) -> Result<Vec<u8>, NormalizationError> {
-    let mut normalized_path = NormalizedPath::new();
-    normalized_path
-        .join_normalized_physical(path)
-        .map(|()| normalized_path.to_string_lossy().into_owned().into_bytes())
+fn physical<E>(env: &E) -> Result<Vec<u8>, NormalizationError>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    env.canonical_working_dir()
+        .map(|path| path.to_string_lossy().into_owned().into_bytes())
 }
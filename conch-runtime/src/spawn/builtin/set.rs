@@ -0,0 +1,177 @@
+use super::generate_and_print_output;
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, SetArgumentsEnvironment, ShellOption,
+    ShellOptionsEnvironment, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::collections::VecDeque;
+use void::Void;
+
+const SET: &str = "set";
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+enum SetError {
+    #[error("{0}{1}: invalid option")]
+    InvalidOption(char /* sign */, char),
+    #[error("{0}: invalid option name")]
+    InvalidOptionName(String),
+    #[error("{0}: option not supported")]
+    Unsupported(ShellOption),
+}
+
+/// How the current option settings should be printed, if at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrintOptions {
+    /// `set -o`: a human readable listing.
+    Listing,
+    /// `set +o`: commands which can be used to restore the settings.
+    Commands,
+}
+
+#[derive(Debug, Default)]
+struct Flags {
+    changes: Vec<(ShellOption, bool)>,
+    print: Option<PrintOptions>,
+    new_args: Option<Vec<String>>,
+}
+
+/// The `set` builtin command can toggle shell options (e.g. `set -e` or
+/// `set +o xtrace`) and replace the current positional parameters (e.g.
+/// `set -- a b c`).
+///
+/// Invoking `set -o` (or `set +o`) without an option name will print the
+/// current option settings. Listing all variables when invoked without
+/// arguments is not supported, nor is enabling the `verbose` option, since
+/// scripts are parsed in their entirety before any of their commands run.
+pub async fn set<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment,
+    E::Arg: From<String>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let flags = try_and_report!(SET, parse_args(args), env);
+
+    for (opt, enabled) in flags.changes {
+        env.set_option(opt, enabled);
+    }
+
+    if let Some(new_args) = flags.new_args {
+        let new_args = new_args
+            .into_iter()
+            .map(E::Arg::from)
+            .collect::<VecDeque<_>>();
+        env.set_args(new_args.into());
+    }
+
+    match flags.print {
+        Some(print) => {
+            generate_and_print_output(SET, env, |env| -> Result<_, Void> {
+                Ok(print_options(print, env).into_bytes())
+            })
+            .await
+        }
+        None => Box::pin(async { EXIT_SUCCESS }),
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Flags, SetError> {
+    let mut flags = Flags::default();
+
+    while let Some(arg) = args.next() {
+        let mut chars = arg.chars();
+        let sign = chars.next();
+        let enable = match sign {
+            Some('-') if arg == "--" => {
+                flags.new_args = Some(args.collect());
+                break;
+            }
+            Some('-') if arg == "-" => {
+                // POSIX leaves this unspecified, but historically it has
+                // turned off tracing and marks the end of the options.
+                flags.changes.push((ShellOption::XTrace, false));
+                flags.changes.push((ShellOption::Verbose, false));
+
+                let rest = args.collect::<Vec<_>>();
+                if !rest.is_empty() {
+                    flags.new_args = Some(rest);
+                }
+                break;
+            }
+            Some('-') => true,
+            Some('+') if arg != "+" => false,
+            _ => {
+                flags.new_args = Some(Some(arg).into_iter().chain(args).collect());
+                break;
+            }
+        };
+
+        let sign = if enable { '-' } else { '+' };
+        for flag in chars {
+            if flag != 'o' {
+                let opt =
+                    ShellOption::from_flag(flag).ok_or(SetError::InvalidOption(sign, flag))?;
+                flags.changes.push((opt, enable));
+                continue;
+            }
+
+            match args.next() {
+                Some(name) => {
+                    let opt =
+                        ShellOption::from_name(&name).ok_or(SetError::InvalidOptionName(name))?;
+                    flags.changes.push((opt, enable));
+                }
+                None => {
+                    flags.print = Some(if enable {
+                        PrintOptions::Listing
+                    } else {
+                        PrintOptions::Commands
+                    });
+                }
+            }
+        }
+    }
+
+    match flags
+        .changes
+        .iter()
+        .find(|&&change| change == (ShellOption::Verbose, true))
+    {
+        Some(&(opt, _)) => Err(SetError::Unsupported(opt)),
+        None => Ok(flags),
+    }
+}
+
+fn print_options<E>(print: PrintOptions, env: &E) -> String
+where
+    E: ?Sized + ShellOptionsEnvironment,
+{
+    let mut out = String::new();
+
+    for &opt in ShellOption::ALL {
+        let enabled = env.is_option_enabled(opt);
+        let line = match print {
+            PrintOptions::Listing => {
+                let state = if enabled { "on" } else { "off" };
+                format!("{:<15}\t{}\n", opt.name(), state)
+            }
+            PrintOptions::Commands => {
+                let sign = if enabled { '-' } else { '+' };
+                format!("set {}o {}\n", sign, opt.name())
+            }
+        };
+
+        out.push_str(&line);
+    }
+
+    out
+}
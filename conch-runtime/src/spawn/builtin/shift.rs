@@ -1,8 +1,9 @@
+use super::report_err;
 use crate::env::{
     ArgumentsEnvironment, AsyncIoEnvironment, FileDescEnvironment, ShiftArgumentsEnvironment,
     StringWrapper,
 };
-use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use crate::{ExitStatus, EXIT_SUCCESS};
 use clap::{App, AppSettings, Arg};
 use futures_util::future::BoxFuture;
 use std::borrow::Cow;
@@ -14,10 +15,17 @@ const SHIFT: &str = "shift";
 #[error("numeric argument required")]
 struct NumericArgumentRequiredError;
 
+#[derive(Debug, thiserror::Error)]
+#[error("shift count out of range")]
+struct ShiftCountOutOfRangeError;
+
 /// The `shift` builtin command will shift all shell or function positional
 /// arguments up by the specified amount. For example, shifting by 2 will
 /// result in `$1` holding the previous value of `$3`, `$2` holding the
 /// previous value of `$4`, and so on.
+///
+/// Shifting by more than the number of available arguments is an error,
+/// in which case the arguments are left untouched.
 pub async fn shift<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
 where
     I: IntoIterator,
@@ -38,14 +46,12 @@ where
         env
     );
 
-    let ret = if amt > env.args_len() {
-        EXIT_ERROR
-    } else {
-        env.shift_args(amt);
-        EXIT_SUCCESS
-    };
+    if amt > env.args_len() {
+        return report_err(SHIFT, env, ShiftCountOutOfRangeError).await;
+    }
 
-    Box::pin(async move { ret })
+    env.shift_args(amt);
+    Box::pin(async { EXIT_SUCCESS })
 }
 
 fn parse_args_amount<I: Iterator<Item = String>>(
@@ -0,0 +1,71 @@
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, StringWrapper, UnsetFunctionEnvironment,
+    UnsetVariableEnvironment,
+};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use clap::{App, AppSettings, Arg};
+use futures_util::future::BoxFuture;
+
+const UNSET: &str = "unset";
+
+/// The `unset` builtin command will remove each specified variable, or each
+/// specified function if invoked with `-f`.
+///
+/// Attempting to unset a variable or function which is not defined is not
+/// considered an error.
+pub async fn unset<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + UnsetFunctionEnvironment
+        + UnsetVariableEnvironment,
+    E::FileHandle: Clone,
+    E::FnName: From<String>,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: From<String>,
+{
+    const ARG_FUNCTIONS: &str = "f";
+    const ARG_VARIABLES: &str = "v";
+    const ARG_NAMES: &str = "names";
+
+    let app = App::new(UNSET)
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::DisableVersion)
+        .about("Removes variables or functions")
+        .arg(
+            Arg::with_name(ARG_FUNCTIONS)
+                .short(ARG_FUNCTIONS)
+                .overrides_with(ARG_VARIABLES)
+                .help("Treat each name as a function."),
+        )
+        .arg(
+            Arg::with_name(ARG_VARIABLES)
+                .short(ARG_VARIABLES)
+                .overrides_with(ARG_FUNCTIONS)
+                .help("Treat each name as a variable (the default)."),
+        )
+        .arg(
+            Arg::with_name(ARG_NAMES)
+                .multiple(true)
+                .help("The names of the variables or functions to remove."),
+        );
+
+    let args = args.into_iter().map(StringWrapper::into_owned);
+    let matches = try_and_report!(UNSET, app.get_matches_from_safe(args), env);
+
+    let names = matches.values_of(ARG_NAMES).into_iter().flatten();
+    if matches.is_present(ARG_FUNCTIONS) {
+        for name in names {
+            env.unset_function(&name.to_owned().into());
+        }
+    } else {
+        for name in names {
+            env.unset_var(&name.to_owned().into());
+        }
+    }
+
+    Box::pin(async { EXIT_SUCCESS })
+}
@@ -0,0 +1,187 @@
+use crate::error::RuntimeError;
+use crate::{ExitStatus, Spawn};
+use futures_util::future::{select, Either};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// The number of iterations loop-like commands will run before
+/// preemptively yielding via `yield_now`.
+pub(crate) const YIELD_INTERVAL: usize = 20;
+
+/// A handle which allows a host to cooperatively cancel the execution of
+/// commands which have been spawned via `with_cancellation`.
+///
+/// Cloning a token yields a handle to the same underlying state, so that
+/// cancelling through any clone will affect all of them.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    next_waiter_id: AtomicUsize,
+    wakers: Mutex<Vec<(usize, Waker)>>,
+}
+
+impl CancellationToken {
+    /// Construct a new token which has not yet been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of any commands observing this token.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+
+        let wakers = {
+            let mut wakers = self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner());
+            std::mem::take(&mut *wakers)
+        };
+
+        for (_, waker) in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Checks whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once cancellation has been requested.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + '_ {
+        Cancelled {
+            token: self,
+            id: self.inner.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// A future which resolves once its token is cancelled.
+///
+/// Any waker it has registered is removed once it is dropped, so tokens
+/// which are repeatedly awaited (but never cancelled) do not accumulate them.
+#[must_use = "futures do nothing unless polled"]
+struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    id: usize,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self
+            .token
+            .inner
+            .wakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Check again while holding the lock so we can't miss a wakeup
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        match wakers.iter_mut().find(|(id, _)| *id == self.id) {
+            Some((_, waker)) if waker.will_wake(cx.waker()) => {}
+            Some((_, waker)) => *waker = cx.waker().clone(),
+            None => wakers.push((self.id, cx.waker().clone())),
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        let mut wakers = self
+            .token
+            .inner
+            .wakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        wakers.retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Spawns a command and waits for its completion, unless it is cancelled
+/// via the provided `token` first.
+///
+/// Cancellation applies to both spawning the command and awaiting its result.
+/// Once cancellation is requested, all futures of the command are dropped,
+/// which unwinds any nested pipelines, loops, or substitutions still in flight
+/// and kills any child processes they have spawned. The command will then
+/// resolve with a `RuntimeError::Cancelled` error.
+///
+/// Note that cancellation is cooperative: it is observed whenever the command
+/// yields, which commands such as loops will periodically do even if they are
+/// always ready to make progress.
+pub async fn with_cancellation<S, E>(
+    token: &CancellationToken,
+    cmd: S,
+    env: &mut E,
+) -> Result<ExitStatus, S::Error>
+where
+    S: Spawn<E>,
+    S::Error: From<RuntimeError>,
+    E: ?Sized,
+{
+    if token.is_cancelled() {
+        return Err(RuntimeError::Cancelled.into());
+    }
+
+    let run = async {
+        let future = cmd.spawn(env).await?;
+        Ok(future.await)
+    };
+
+    futures_util::pin_mut!(run);
+    let cancelled = token.cancelled();
+    futures_util::pin_mut!(cancelled);
+
+    match select(run, cancelled).await {
+        Either::Left((ret, _)) => ret,
+        Either::Right(((), _)) => Err(RuntimeError::Cancelled.into()),
+    }
+}
+
+/// A cancellation point which yields control back to the executor once
+/// (while signalling immediate readiness) before resolving.
+///
+/// Long running commands which may always be ready to make progress (e.g.
+/// loops or builtins) should periodically await this so that other futures
+/// on the same thread are not starved, and so that cancellation requested
+/// via `with_cancellation` can be observed.
+pub fn yield_now() -> impl Future<Output = ()> {
+    YieldOnce { yielded: false }
+}
+
+/// A future which yields once and resolves.
+#[must_use = "futures do nothing unless polled"]
+struct YieldOnce {
+    yielded: bool,
+}
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
@@ -1,10 +1,9 @@
 use crate::env::{LastStatusEnvironment, ReportErrorEnvironment, StringWrapper};
 use crate::error::IsFatalError;
-use crate::eval::{eval_as_pattern, TildeExpansion, WordEval, WordEvalConfig};
+use crate::eval::{eval_as_pattern, PatternMatchConfig, TildeExpansion, WordEval, WordEvalConfig};
 use crate::spawn::ExitStatus;
 use crate::{Spawn, EXIT_ERROR, EXIT_SUCCESS};
 use futures_core::future::BoxFuture;
-use glob::MatchOptions;
 
 /// A grouping of patterns and body commands.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -15,6 +14,21 @@ pub struct PatternBodyPair<W, C> {
     pub body: C,
 }
 
+/// Determines how a `case` command proceeds after running the body of a
+/// matched arm.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum CaseArmTerminator {
+    /// `;;`: the `case` command is finished.
+    #[default]
+    Break,
+    /// `;&`: the body of the next arm is run as well, regardless of whether
+    /// any of its patterns match.
+    FallThrough,
+    /// `;;&`: the patterns of any subsequent arms are tested as well, and the
+    /// body of the next matching arm is run.
+    Continue,
+}
+
 /// Spawns a `case` commands from a word to match number of case arms.
 ///
 /// First the provided `word` will be evaluated and compared to each
@@ -35,18 +49,77 @@ where
     S: Spawn<E>,
     S::Error: From<W::Error> + From<P::Error>,
     E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+{
+    case_with_terminators(word, BreakAfterEach(arms), env).await
+}
+
+/// Terminates each arm with `;;`.
+///
+/// NB: a named adapter (rather than a closure passed to `Iterator::map`)
+/// keeps the resulting future `Send` for any lifetimes of the arms.
+pub(crate) struct BreakAfterEach<I>(pub(crate) I);
+
+impl<I: Iterator> Iterator for BreakAfterEach<I> {
+    type Item = (I::Item, CaseArmTerminator);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|arm| (arm, CaseArmTerminator::Break))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Spawns a `case` command whose arms may fall through to subsequent arms
+/// (i.e. via `;&` or `;;&`).
+///
+/// Behaves just like `case`, except that after the body of a matched arm has
+/// run, the `case` command proceeds as specified by the arm's terminator.
+/// The status of the `case` command is that of the last body which was run,
+/// or success if no arms matched.
+pub async fn case_with_terminators<'a, I, W, P, S, E>(
+    word: W,
+    arms: I,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = (PatternBodyPair<&'a [P], S>, CaseArmTerminator)>,
+    W: WordEval<E>,
+    P: 'a + WordEval<E>,
+    P::Error: IsFatalError,
+    S: Spawn<E>,
+    S::Error: From<W::Error> + From<P::Error>,
+    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+{
+    case_with_config(word, arms, PatternMatchConfig::default(), env).await
+}
+
+/// Spawns a `case` command whose arms may fall through to subsequent arms,
+/// matching patterns according to the provided configuration (e.g. to
+/// recognize extended patterns).
+///
+/// See `case_with_terminators` for more details.
+pub async fn case_with_config<'a, I, W, P, S, E>(
+    word: W,
+    arms: I,
+    match_cfg: PatternMatchConfig,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = (PatternBodyPair<&'a [P], S>, CaseArmTerminator)>,
+    W: WordEval<E>,
+    P: 'a + WordEval<E>,
+    P::Error: IsFatalError,
+    S: Spawn<E>,
+    S::Error: From<W::Error> + From<P::Error>,
+    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
 {
     let cfg = WordEvalConfig {
         tilde_expansion: TildeExpansion::First,
         split_fields_further: false,
     };
 
-    let match_opts = MatchOptions {
-        case_sensitive: true,
-        require_literal_separator: false,
-        require_literal_leading_dot: false,
-    };
-
     let word = match word.eval_with_config(env, cfg).await {
         Ok(w) => w.await.join().into_owned(),
         Err(e) => {
@@ -55,25 +128,66 @@ where
         }
     };
 
-    for arm in arms {
-        for pat in arm.patterns {
-            let pat = match eval_as_pattern(pat, env).await {
-                Ok(pat) => pat,
-                Err(e) => {
-                    if e.is_fatal() {
-                        return Err(S::Error::from(e));
-                    } else {
-                        env.report_error(&e).await;
-                        continue;
-                    }
-                }
-            };
+    let mut arms = arms.peekable();
+    let mut fall_through = false;
+    let mut last_status = None;
 
-            if pat.matches_with(&word, match_opts) {
-                return arm.body.spawn(env).await;
+    while let Some((arm, terminator)) = arms.next() {
+        if !fall_through && !arm_matches::<_, S, _>(&word, arm.patterns, match_cfg, env).await? {
+            continue;
+        }
+
+        let future = arm.body.spawn(env).await?;
+
+        let is_last_arm = arms.peek().is_none();
+        fall_through = match terminator {
+            CaseArmTerminator::Break => return Ok(future),
+            _ if is_last_arm => return Ok(future),
+            CaseArmTerminator::FallThrough => true,
+            CaseArmTerminator::Continue => false,
+        };
+
+        let status = future.await;
+        env.set_last_status(status);
+        last_status = Some(status);
+    }
+
+    let status = last_status.unwrap_or(EXIT_SUCCESS);
+    Ok(Box::pin(async move { status }))
+}
+
+/// Checks if any of the patterns of an arm match a word, reporting any
+/// non-fatal errors which arise while evaluating the patterns.
+async fn arm_matches<P, S, E>(
+    word: &str,
+    patterns: &[P],
+    match_cfg: PatternMatchConfig,
+    env: &mut E,
+) -> Result<bool, S::Error>
+where
+    P: WordEval<E>,
+    P::Error: IsFatalError,
+    S: Spawn<E>,
+    S::Error: From<P::Error>,
+    E: ?Sized + ReportErrorEnvironment,
+{
+    for pat in patterns {
+        let pat = match eval_as_pattern(pat, env, match_cfg.extglob).await {
+            Ok(pat) => pat,
+            Err(e) => {
+                if e.is_fatal() {
+                    return Err(S::Error::from(e));
+                } else {
+                    env.report_error(&e).await;
+                    continue;
+                }
             }
+        };
+
+        if pat.matches_with(word, match_cfg.into()) {
+            return Ok(true);
         }
     }
 
-    Ok(Box::pin(async { EXIT_SUCCESS }))
+    Ok(false)
 }
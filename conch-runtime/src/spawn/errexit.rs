@@ -0,0 +1,19 @@
+use crate::env::{ControlFlowEnvironment, ShellOption, ShellOptionsEnvironment};
+use crate::ExitStatus;
+
+/// Indicates if a command which completed with the provided status should
+/// cause the shell to exit, i.e. it failed while the `errexit` option was
+/// enabled, without being exempt from it or having its status tested.
+///
+/// Like other shells, commands are exempt from `errexit` while they form the
+/// condition of an `if`, `while`, or `until` command, or precede a `&&` or
+/// `||` operator, as is any pipeline preceded by `!`.
+pub(crate) fn should_exit_on_error<E>(status: ExitStatus, env: &E) -> bool
+where
+    E: ?Sized + ControlFlowEnvironment + ShellOptionsEnvironment,
+{
+    !status.success()
+        && env.is_option_enabled(ShellOption::ErrExit)
+        && !env.is_errexit_exempt()
+        && !env.is_status_tested()
+}
@@ -1,13 +1,25 @@
-use crate::env::{ArgumentsEnvironment, LastStatusEnvironment, VariableEnvironment};
-use crate::eval::WordEval;
-use crate::spawn::{ExitStatus, Spawn};
+use super::loop_cmd::{break_loop, loop_control, LoopControl};
+use super::subshell::subshell_with_env;
+use crate::env::{
+    ArgumentsEnvironment, ControlFlowEnvironment, LastStatusEnvironment, ReportErrorEnvironment,
+    ShellOptionsEnvironment, SubEnvironment, VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::{ControlFlow, ExpansionError, IsFatalError};
+use crate::eval::{glob_word, WordEval};
+use crate::spawn::{should_exit_on_error, yield_now, ExitStatus, Spawn, YIELD_INTERVAL};
 use crate::EXIT_SUCCESS;
 use futures_core::future::BoxFuture;
+use futures_util::stream::{self, StreamExt};
 
 /// Spawns a `for` loop with all the fields when `words` are evaluated.
 ///
 /// For each element in the environment's arguments, `name` will be assigned
 /// with its value and `body` will be executed.
+///
+/// All `words` are evaluated before the body is first run, thus the loop
+/// iterates over a snapshot of their fields: any changes the body makes
+/// (e.g. running `shift` or `set --` while iterating over `"$@"`) only affect
+/// expansions made afterwards, not the remaining iterations.
 pub async fn for_loop<W, I, S, E>(
     name: E::VarName,
     words: I,
@@ -17,9 +29,15 @@ pub async fn for_loop<W, I, S, E>(
 where
     I: IntoIterator<Item = W>,
     W: WordEval<E>,
+    W::Error: From<ExpansionError>,
     S: Spawn<E>,
-    S::Error: From<W::Error>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
+    S::Error: IsFatalError + From<ControlFlow> + From<W::Error>,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
     E::VarName: Clone,
     E::Var: From<W::EvalResult>,
 {
@@ -35,34 +53,105 @@ async fn do_for_loop<W, I, S, E>(
 where
     I: Iterator<Item = W>,
     W: WordEval<E>,
+    W::Error: From<ExpansionError>,
     S: Spawn<E>,
-    S::Error: From<W::Error>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
+    S::Error: IsFatalError + From<ControlFlow> + From<W::Error>,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
     E::VarName: Clone,
     E::Var: From<W::EvalResult>,
+{
+    let values = eval_words(words, env).await?;
+    do_for_with_args(name, values.into_iter(), body, env).await
+}
+
+/// Eagerly evaluates (and globs) all `words` into the fields the loop will iterate over.
+async fn eval_words<W, I, E>(words: I, env: &mut E) -> Result<Vec<E::Var>, W::Error>
+where
+    I: Iterator<Item = W>,
+    W: WordEval<E>,
+    W::Error: From<ExpansionError>,
+    E: ?Sized + ShellOptionsEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::Var: From<W::EvalResult>,
 {
     let (lo, hi) = words.size_hint();
     let mut values = Vec::with_capacity(hi.unwrap_or(lo));
 
     for word in words {
-        let fields = word
-            .eval(env)
-            .await
-            .map_err(S::Error::from)?
-            .await
-            .into_iter()
-            .map(E::Var::from);
-
-        values.extend(fields);
+        let fields = glob_word(&word, env).await?;
+        values.extend(fields.into_iter().map(E::Var::from));
     }
 
-    do_for_with_args(name, values.into_iter(), body, env).await
+    Ok(values)
+}
+
+/// Spawns a `for` loop whose iterations run concurrently.
+///
+/// All `words` are evaluated up front, after which each of their fields is
+/// assigned to `name` in a separate sub environment in which `body` will be
+/// executed, as if it were run in a subshell. At most `limit` iterations
+/// (but always at least one) will be running at any given time.
+///
+/// Since each iteration runs in its own sub environment, any changes it makes
+/// (including any `break`, `continue`, or `exit` requests) do not affect the
+/// other iterations or the loop's environment. The loop's exit status is that
+/// of the first iteration (in the order of the fields) which did not succeed,
+/// or success if all iterations succeeded.
+pub async fn for_each_concurrent<W, I, S, E>(
+    name: E::VarName,
+    words: I,
+    body: S,
+    limit: usize,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: IntoIterator<Item = W>,
+    W: WordEval<E>,
+    W::Error: From<ExpansionError>,
+    S: Spawn<E>,
+    S::Error: IsFatalError + From<W::Error>,
+    E: ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::VarName: Clone,
+    E::Var: From<W::EvalResult>,
+{
+    let values = eval_words(words.into_iter(), env).await?;
+
+    let env = &*env;
+    let body = &body;
+    let statuses = stream::iter(values)
+        .map(|value| {
+            let mut env = env.sub_env();
+            env.set_var(name.clone(), value);
+            subshell_with_env(body, env)
+        })
+        .buffered(limit.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    let status = statuses
+        .into_iter()
+        .find(|status| !status.success())
+        .unwrap_or(EXIT_SUCCESS);
+
+    Ok(Box::pin(async move { status }))
 }
 
 /// Spawns a `for` loop with the environment's currently set arguments.
 ///
 /// For each element in the environment's arguments, `name` will be assigned
 /// with its value and `body` will be executed.
+///
+/// Like `for_loop`, the arguments are captured before the body is first run,
+/// thus changing them within the body (e.g. via `shift`) does not affect the
+/// remaining iterations.
 pub async fn for_args<S, E>(
     name: E::VarName,
     body: S,
@@ -70,10 +159,17 @@ pub async fn for_args<S, E>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
-    E: ?Sized + ArgumentsEnvironment + LastStatusEnvironment + VariableEnvironment,
+    S::Error: IsFatalError + From<ControlFlow>,
+    E: ?Sized
+        + ArgumentsEnvironment
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
     E::VarName: Clone,
     E::Var: From<E::Arg>,
 {
+    // NB: collect the arguments up front since the body may change them
     let args = env
         .args()
         .iter()
@@ -97,13 +193,41 @@ pub async fn for_with_args<I, S, E>(
 where
     I: IntoIterator<Item = E::Var>,
     S: Spawn<E>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
+    S::Error: IsFatalError + From<ControlFlow>,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
     E::VarName: Clone,
 {
     do_for_with_args(name, args.into_iter(), body, env).await
 }
 
 async fn do_for_with_args<I, S, E>(
+    name: E::VarName,
+    args: I,
+    body: S,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = E::Var>,
+    S: Spawn<E>,
+    S::Error: IsFatalError + From<ControlFlow>,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::VarName: Clone,
+{
+    env.push_loop_frame();
+    let ret = run_iterations(name, args, body, env).await;
+    env.pop_loop_frame();
+    ret
+}
+
+async fn run_iterations<I, S, E>(
     name: E::VarName,
     mut args: I,
     body: S,
@@ -112,7 +236,12 @@ async fn do_for_with_args<I, S, E>(
 where
     I: Iterator<Item = E::Var>,
     S: Spawn<E>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
+    S::Error: IsFatalError + From<ControlFlow>,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
     E::VarName: Clone,
 {
     let mut cur_arg = match args.next() {
@@ -120,13 +249,42 @@ where
         None => return Ok(Box::pin(async { EXIT_SUCCESS })),
     };
 
-    for next in args {
+    for (i, next) in args.enumerate() {
+        // Periodically yield in case the body is always ready to make progress
+        // (see `loop_cmd` for more details).
+        if i > 0 && i % YIELD_INTERVAL == 0 {
+            yield_now().await;
+        }
+
         env.set_var(name.clone(), cur_arg);
-        let status = body.spawn(env).await?.await;
+        env.set_status_tested(false);
+        let status = match body.spawn(env).await {
+            Ok(future) => future.await,
+            Err(e) => match loop_control(e)? {
+                LoopControl::Break => {
+                    let status = break_loop(env);
+                    return Ok(Box::pin(async move { status }));
+                }
+                LoopControl::Continue => EXIT_SUCCESS,
+            },
+        };
         env.set_last_status(status);
+
+        if should_exit_on_error(status, env) {
+            return Ok(Box::pin(async move { status }));
+        }
+
         cur_arg = next;
     }
 
     env.set_var(name, cur_arg);
-    body.spawn(env).await
+    env.set_status_tested(false);
+    match body.spawn(env).await {
+        Err(e) => {
+            // Whether breaking or continuing, there are no more iterations to run
+            loop_control(e)?;
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+        ret => ret,
+    }
 }
@@ -1,4 +1,8 @@
-use crate::env::{FunctionEnvironment, FunctionFrameEnvironment, SetArgumentsEnvironment};
+use crate::env::{
+    ArgsGuard, FunctionEnvironment, FunctionFrameEnvironment, NestingEnvironment,
+    SetArgumentsEnvironment,
+};
+use crate::error::{ControlFlow, IsFatalError, StackOverflowError};
 use crate::{ExitStatus, Spawn};
 use futures_core::future::BoxFuture;
 
@@ -10,9 +14,13 @@ pub async fn function<S, A, E: ?Sized>(
     env: &mut E,
 ) -> Option<Result<BoxFuture<'static, ExitStatus>, S::Error>>
 where
-    E: FunctionEnvironment<Fn = S> + FunctionFrameEnvironment + SetArgumentsEnvironment,
+    E: FunctionEnvironment<Fn = S>
+        + FunctionFrameEnvironment
+        + NestingEnvironment
+        + SetArgumentsEnvironment,
     E::Args: From<A>,
     S: Clone + Spawn<E>,
+    S::Error: IsFatalError + From<StackOverflowError>,
 {
     match env.function(name).cloned() {
         Some(func) => Some(function_body(func, args, env).await),
@@ -21,6 +29,15 @@ where
 }
 
 /// Creates a future adapter that will execute a function body with the given set of arguments.
+///
+/// Any `return` request raised while spawning the body will be handled here
+/// and resolve to the requested status. An error is returned (instead of
+/// spawning the body) if the environment's maximum function nesting depth
+/// would be exceeded.
+///
+/// The body itself does not count towards the environment's maximum command
+/// nesting depth (since function frames are limited on their own), although
+/// any commands nested within it still do.
 pub async fn function_body<S, A, E: ?Sized>(
     body: S,
     args: A,
@@ -28,7 +45,8 @@ pub async fn function_body<S, A, E: ?Sized>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
-    E: FunctionFrameEnvironment + SetArgumentsEnvironment,
+    S::Error: IsFatalError + From<StackOverflowError>,
+    E: FunctionFrameEnvironment + NestingEnvironment + SetArgumentsEnvironment,
     E::Args: From<A>,
 {
     do_function_body(body, args.into(), env).await
@@ -41,14 +59,32 @@ async fn do_function_body<S, E: ?Sized>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
-    E: FunctionFrameEnvironment + SetArgumentsEnvironment,
+    S::Error: IsFatalError + From<StackOverflowError>,
+    E: FunctionFrameEnvironment + NestingEnvironment + SetArgumentsEnvironment,
 {
-    env.push_fn_frame();
-    let old_args = env.set_args(args);
-
-    let ret = body.spawn(env).await;
+    if let Some(max_depth) = env.max_fn_frame_depth() {
+        if env.fn_frame_depth() >= max_depth {
+            return Err(S::Error::from(StackOverflowError { max_depth }));
+        }
+    }
 
-    env.set_args(old_args);
+    let max_nesting_depth = env.max_nesting_depth();
+    env.set_max_nesting_depth(max_nesting_depth.map(|max| max.saturating_add(1)));
+    env.push_fn_frame();
+    let ret = {
+        // NB: the name of the shell (`$0`) is left intact while running functions
+        let mut guard = ArgsGuard::new(env);
+        guard.set_args(args);
+        body.spawn(guard.get_mut()).await
+    };
     env.pop_fn_frame();
-    ret
+    env.set_max_nesting_depth(max_nesting_depth);
+
+    match ret {
+        Err(e) => match e.control_flow() {
+            Some(ControlFlow::Return(status)) => Ok(Box::pin(async move { status })),
+            _ => Err(e),
+        },
+        ret => ret,
+    }
 }
@@ -0,0 +1,70 @@
+use crate::env::{ArgumentsEnvironment, StringWrapper};
+use crate::{ExitStatus, Spawn};
+use futures_core::future::BoxFuture;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Creates a `Spawn` adapter around a native (host) function, so that it can
+/// be registered as a shell function via `FunctionEnvironment::set_function`.
+///
+/// Whenever the function is invoked, `f` is called with the environment and
+/// the arguments the function was invoked with (not including its name), and
+/// the future it returns resolves to the function's exit status.
+///
+/// Host functions never fail to spawn, thus the adapter can be used with any
+/// `Spawn::Error` type the environment expects.
+pub fn host_fn<F, ERR>(f: F) -> HostFn<F, ERR> {
+    HostFn {
+        f,
+        phantom: PhantomData,
+    }
+}
+
+/// A `Spawn` adapter around a native (host) function.
+///
+/// Created by the `host_fn` function.
+pub struct HostFn<F, ERR> {
+    f: F,
+    phantom: PhantomData<fn() -> ERR>,
+}
+
+impl<F, ERR> fmt::Debug for HostFn<F, ERR> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("HostFn").finish()
+    }
+}
+
+impl<F: Clone, ERR> Clone for HostFn<F, ERR> {
+    fn clone(&self) -> Self {
+        host_fn(self.f.clone())
+    }
+}
+
+impl<F, ERR, E> Spawn<E> for HostFn<F, ERR>
+where
+    F: Sync + Fn(&mut E, Vec<String>) -> BoxFuture<'static, ExitStatus>,
+    E: ?Sized + Send + ArgumentsEnvironment,
+    E::Arg: StringWrapper,
+{
+    type Error = ERR;
+
+    fn spawn<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+    ) -> BoxFuture<'async_trait, Result<BoxFuture<'static, ExitStatus>, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            let args = env
+                .args()
+                .iter()
+                .map(|arg| arg.as_str().to_owned())
+                .collect();
+
+            Ok((self.f)(env, args))
+        })
+    }
+}
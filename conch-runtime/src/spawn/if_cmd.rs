@@ -1,4 +1,4 @@
-use crate::env::LastStatusEnvironment;
+use crate::env::{ControlFlowEnvironment, LastStatusEnvironment};
 use crate::error::IsFatalError;
 use crate::spawn::GuardBodyPair;
 use crate::{ExitStatus, Spawn, EXIT_SUCCESS};
@@ -10,6 +10,9 @@ use futures_core::future::BoxFuture;
 /// corresponding body will be evaluated. If no guard exits successfully,
 /// the `else` branch will be run, if present. Otherwise, the `If` command
 /// will exit successfully.
+///
+/// The guards are exempt from the `errexit` option, as their exit status is
+/// tested by the command itself.
 pub async fn if_cmd<S, ELS, I, E>(
     conditionals: I,
     else_branch: Option<ELS>,
@@ -20,17 +23,27 @@ where
     S::Error: IsFatalError,
     ELS: Spawn<E, Error = S::Error>,
     I: Iterator<Item = GuardBodyPair<S>>,
-    E: ?Sized + LastStatusEnvironment,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment,
 {
     for gbp in conditionals {
-        let status = gbp.guard.spawn(env).await?.await;
+        env.push_errexit_exemption();
+        let status = match gbp.guard.spawn(env).await {
+            Ok(future) => future.await,
+            Err(e) => {
+                env.pop_errexit_exemption();
+                return Err(e);
+            }
+        };
+        env.pop_errexit_exemption();
         env.set_last_status(status);
 
         if status.success() {
+            env.set_status_tested(false);
             return gbp.body.spawn(env).await;
         }
     }
 
+    env.set_status_tested(false);
     let ret = match else_branch {
         Some(els) => els.spawn(env).await?,
         None => Box::pin(async { EXIT_SUCCESS }),
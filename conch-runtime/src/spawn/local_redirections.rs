@@ -1,4 +1,6 @@
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, RedirectEnvRestorer};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, RedirectEnvRestorer, TempFileEnvironment,
+};
 use crate::error::RedirectionError;
 use crate::eval::RedirectEval;
 use crate::spawn::{ExitStatus, Spawn};
@@ -25,7 +27,7 @@ where
     S: Spawn<E>,
     S::Error: From<RedirectionError> + From<R::Error>,
     E: 'a + ?Sized + FileDescEnvironment,
-    RR: ?Sized + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    RR: ?Sized + AsyncIoEnvironment + TempFileEnvironment + RedirectEnvRestorer<'a, E>,
     RR::FileHandle: Send + From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
 {
@@ -45,7 +47,7 @@ where
     S: Spawn<E>,
     S::Error: From<RedirectionError> + From<R::Error>,
     E: 'a + ?Sized + FileDescEnvironment,
-    RR: ?Sized + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    RR: ?Sized + AsyncIoEnvironment + TempFileEnvironment + RedirectEnvRestorer<'a, E>,
     RR::FileHandle: Send + From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
 {
@@ -56,9 +58,10 @@ where
 
     for redirect in redirects {
         let action = redirect.eval(restorer.get_mut()).await?;
+        let context = action.describe();
         action
-            .apply(restorer)
-            .map_err(|e| RedirectionError::Io(e, None))?;
+            .apply_with_restorer(restorer)
+            .map_err(|e| RedirectionError::Io(e, context))?;
     }
 
     cmd.spawn(restorer.get_mut()).await
@@ -0,0 +1,52 @@
+use crate::env::DynamicVariableEnvironment;
+use crate::error::{SourceLocation, WithLocation};
+use crate::{ExitStatus, Spawn};
+use futures_core::future::BoxFuture;
+
+/// Creates a `Spawn` adapter which records where in its source a command
+/// was defined.
+///
+/// Whenever the command is spawned, the line it starts on becomes the
+/// environment's `$LINENO`, and any error it fails with is annotated with
+/// its location.
+pub fn located<S>(location: SourceLocation, cmd: S) -> Located<S> {
+    Located { location, cmd }
+}
+
+/// A `Spawn` adapter which tracks the source location of a command.
+///
+/// Created by the `located` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Located<S> {
+    /// Where the command appears in its source.
+    pub location: SourceLocation,
+    /// The command itself.
+    pub cmd: S,
+}
+
+impl<S, E> Spawn<E> for Located<S>
+where
+    S: Send + Sync + Spawn<E>,
+    S::Error: WithLocation,
+    E: ?Sized + Send + DynamicVariableEnvironment,
+{
+    type Error = S::Error;
+
+    fn spawn<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        env: &'life1 mut E,
+    ) -> BoxFuture<'async_trait, Result<BoxFuture<'static, ExitStatus>, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            env.set_current_line(self.location.line);
+            self.cmd
+                .spawn(env)
+                .await
+                .map_err(|e| e.with_location(self.location.clone()))
+        })
+    }
+}
@@ -1,9 +1,31 @@
-use crate::env::LastStatusEnvironment;
-use crate::spawn::Spawn;
+use crate::env::{ControlFlowEnvironment, LastStatusEnvironment, ShellOptionsEnvironment};
+use crate::error::{ControlFlow, IsFatalError};
+use crate::spawn::{should_exit_on_error, yield_now, Spawn, YIELD_INTERVAL};
 use crate::{ExitStatus, EXIT_SUCCESS};
-use std::future::Future;
-use std::pin::Pin;
-use std::task::{Context, Poll};
+
+/// A `break` or `continue` request which targets the innermost loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoopControl {
+    Break,
+    Continue,
+}
+
+/// Determines if an error raised by a loop's guard or body is a `break` or
+/// `continue` request directed at this loop. Requests which target an outer
+/// loop are propagated with one less level to unwind, and any other errors
+/// are propagated as is.
+pub(crate) fn loop_control<ERR>(err: ERR) -> Result<LoopControl, ERR>
+where
+    ERR: IsFatalError + From<ControlFlow>,
+{
+    match err.control_flow() {
+        Some(ControlFlow::Break(n)) if n > 1 => Err(ControlFlow::Break(n - 1).into()),
+        Some(ControlFlow::Continue(n)) if n > 1 => Err(ControlFlow::Continue(n - 1).into()),
+        Some(ControlFlow::Break(_)) => Ok(LoopControl::Break),
+        Some(ControlFlow::Continue(_)) => Ok(LoopControl::Continue),
+        _ => Err(err),
+    }
+}
 
 /// Spawns a loop command such as `while` or `until` using a guard and a body.
 ///
@@ -12,7 +34,11 @@ use std::task::{Context, Poll};
 /// `invert_guard_status == false`, the loop will continue as long as the guard
 /// exits successfully. If `invert_guard_status == true`, the loop will continue
 /// **until** the guard exits successfully.
-// FIXME: implement a `break` built in command to break loops
+///
+/// Any `break` or `continue` requests raised by the guard or body will be
+/// handled here (or propagated if they target an outer loop). The guard is
+/// exempt from the `errexit` option, but the loop stops as soon as its body
+/// fails while the option is enabled.
 pub async fn loop_cmd<G, B, E>(
     invert_guard_status: bool,
     guard: G,
@@ -21,8 +47,27 @@ pub async fn loop_cmd<G, B, E>(
 ) -> Result<ExitStatus, G::Error>
 where
     G: Spawn<E>,
+    G::Error: IsFatalError + From<ControlFlow>,
     B: Spawn<E, Error = G::Error>,
-    E: ?Sized + LastStatusEnvironment,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment + ShellOptionsEnvironment,
+{
+    env.push_loop_frame();
+    let ret = do_loop_cmd(invert_guard_status, guard, body, env).await;
+    env.pop_loop_frame();
+    ret
+}
+
+async fn do_loop_cmd<G, B, E>(
+    invert_guard_status: bool,
+    guard: G,
+    body: B,
+    env: &mut E,
+) -> Result<ExitStatus, G::Error>
+where
+    G: Spawn<E>,
+    G::Error: IsFatalError + From<ControlFlow>,
+    B: Spawn<E, Error = G::Error>,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment + ShellOptionsEnvironment,
 {
     // bash/zsh will exit loops with a successful status if
     // loop breaks out of the first round without running the body,
@@ -33,9 +78,23 @@ where
         // In case we end up running in a hot loop which is always ready to
         // do more work, we'll preemptively yield (but signal immediate
         // readiness) every once in a while so that other futures running on
-        // the same thread get a chance to make some progress too.
-        for _ in 0..20usize {
-            let guard_status = guard.spawn(env).await?.await;
+        // the same thread get a chance to make some progress too (and so
+        // that any cancellation requests can be observed).
+        for _ in 0..YIELD_INTERVAL {
+            env.push_errexit_exemption();
+            let guard_status = match guard.spawn(env).await {
+                Ok(future) => Ok(future.await),
+                Err(e) => Err(e),
+            };
+            env.pop_errexit_exemption();
+
+            let guard_status = match guard_status {
+                Ok(status) => status,
+                Err(e) => match loop_control(e)? {
+                    LoopControl::Break => return Ok(break_loop(env)),
+                    LoopControl::Continue => continue,
+                },
+            };
             let should_continue = guard_status.success() ^ invert_guard_status;
 
             if !should_continue {
@@ -47,37 +106,32 @@ where
 
             // Set the guard status so that the body can access it if needed
             env.set_last_status(guard_status);
+            env.set_status_tested(false);
 
-            last_body_status = body.spawn(env).await?.await;
+            last_body_status = match body.spawn(env).await {
+                Ok(future) => future.await,
+                Err(e) => match loop_control(e)? {
+                    LoopControl::Break => return Ok(break_loop(env)),
+                    LoopControl::Continue => EXIT_SUCCESS,
+                },
+            };
             env.set_last_status(last_body_status);
-        }
 
-        YieldOnce::new().await
-    }
-}
-
-/// A future which yields once and resolves.
-#[must_use = "futures do nothing unless polled"]
-struct YieldOnce {
-    yielded: bool,
-}
+            if should_exit_on_error(last_body_status, env) {
+                return Ok(last_body_status);
+            }
+        }
 
-impl YieldOnce {
-    fn new() -> Self {
-        Self { yielded: false }
+        yield_now().await
     }
 }
 
-impl Future for YieldOnce {
-    type Output = ();
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if self.yielded {
-            Poll::Ready(())
-        } else {
-            self.yielded = true;
-            cx.waker().wake_by_ref();
-            Poll::Pending
-        }
-    }
+/// Breaking out of a loop is considered a success, regardless of what
+/// may have been run before.
+pub(crate) fn break_loop<E>(env: &mut E) -> ExitStatus
+where
+    E: ?Sized + LastStatusEnvironment,
+{
+    env.set_last_status(EXIT_SUCCESS);
+    EXIT_SUCCESS
 }
@@ -1,4 +1,7 @@
-use crate::env::{FileDescEnvironment, FileDescOpener, ReportErrorEnvironment, SubEnvironment};
+use crate::env::{
+    ControlFlowEnvironment, ExecutableEnvironment, FileDescEnvironment,
+    LastPipelineStatusEnvironment, ReportErrorEnvironment, SubEnvironment, TempFileEnvironment,
+};
 use crate::error::IsFatalError;
 use crate::io::Permissions;
 use crate::spawn::swallow_non_fatal_errors;
@@ -7,12 +10,23 @@ use futures_core::future::BoxFuture;
 use futures_core::stream::Stream;
 use futures_util::future::poll_fn;
 use futures_util::stream::{FuturesUnordered, StreamExt};
-use std::error::Error;
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+/// A config object for customizing how a pipeline is run.
+#[derive(Default, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PipelineConfig {
+    /// Resolve to the status of the last (i.e. rightmost) command to fail,
+    /// or `EXIT_SUCCESS` if all commands succeed, like `set -o pipefail`.
+    pub pipefail: bool,
+    /// Run the last command within the environment the pipeline was spawned
+    /// in (rather than a sub-environment) like bash's `lastpipe`, such that
+    /// its side effects (e.g. `... | read var`) persist after the pipeline.
+    pub lastpipe: bool,
+}
+
 /// Spawns a pipeline of commands.
 ///
 /// The standard output of the previous command will be piped as standard input
@@ -21,7 +35,12 @@ use std::task::{Context, Poll};
 ///
 /// If `invert_last_status` is set to `false`, the pipeline will fully resolve
 /// to the last command's exit status. Otherwise, `EXIT_ERROR` will be returned
-/// if the last command succeeds, and `EXIT_SUCCESS` will be returned otherwise.
+/// if the last command succeeds, and `EXIT_SUCCESS` will be returned otherwise,
+/// in which case the pipeline is also exempt from the `errexit` option.
+///
+/// Once every command has completed, their (non-inverted) exit statuses are
+/// recorded in the environment, and are available via
+/// `LastPipelineStatusEnvironment` after the last status is next updated.
 pub async fn pipeline<S, I, E>(
     invert_last_status: bool,
     first: S,
@@ -32,14 +51,101 @@ where
     I: IntoIterator<Item = S>,
     S: Send + Sync + Spawn<E>,
     S::Error: From<io::Error> + IsFatalError,
-    E: Send + FileDescEnvironment + FileDescOpener + ReportErrorEnvironment + SubEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E: Send
+        + ControlFlowEnvironment
+        + ExecutableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + LastPipelineStatusEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: Send + Clone + From<E::OpenedFileHandle>,
 {
-    do_pipeline(invert_last_status, first, rest.into_iter(), env).await
+    let cfg = PipelineConfig::default();
+    do_pipeline(invert_last_status, cfg, first, rest.into_iter(), env).await
+}
+
+/// Spawns a pipeline of commands, customized by the provided config.
+///
+/// Identical to `pipeline` when using the default config. With `pipefail`
+/// enabled, the status the pipeline resolves to (before any inversion) is
+/// that of the last command to fail. With `lastpipe` enabled, the last
+/// command is spawned directly in `env` with its standard input temporarily
+/// redirected, and any unhandled control flow (e.g. `exit`) is propagated
+/// to the caller rather than being absorbed by the pipeline.
+pub async fn pipeline_with_config<S, I, E>(
+    invert_last_status: bool,
+    cfg: PipelineConfig,
+    first: S,
+    rest: I,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: IntoIterator<Item = S>,
+    S: Send + Sync + Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: Send
+        + ControlFlowEnvironment
+        + ExecutableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + LastPipelineStatusEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: Send + Clone + From<E::OpenedFileHandle>,
+{
+    do_pipeline(invert_last_status, cfg, first, rest.into_iter(), env).await
 }
 
 async fn do_pipeline<S, I, E>(
     invert_last_status: bool,
+    cfg: PipelineConfig,
+    first: S,
+    rest: I,
+    orig_env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = S>,
+    S: Send + Sync + Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: Send
+        + ControlFlowEnvironment
+        + ExecutableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + LastPipelineStatusEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: Send + Clone + From<E::OpenedFileHandle>,
+{
+    // All commands of a pipeline are managed as a single job (e.g. they will
+    // share a process group), which must begin before any of their
+    // environments are created so that they all become a part of it.
+    let mut rest = rest.peekable();
+    let job = rest.peek().is_some() && orig_env.begin_job();
+
+    // A pipeline whose status is inverted is exempt from `errexit`
+    if invert_last_status {
+        orig_env.push_errexit_exemption();
+    }
+
+    let ret = spawn_pipeline(invert_last_status, cfg, first, rest, orig_env).await;
+
+    if invert_last_status {
+        orig_env.pop_errexit_exemption();
+        orig_env.set_status_tested(true);
+    }
+
+    if job {
+        orig_env.end_job();
+    }
+
+    ret
+}
+
+async fn spawn_pipeline<S, I, E>(
+    invert_last_status: bool,
+    cfg: PipelineConfig,
     first: S,
     mut rest: I,
     orig_env: &mut E,
@@ -48,8 +154,14 @@ where
     I: Iterator<Item = S>,
     S: Send + Sync + Spawn<E>,
     S::Error: From<io::Error> + IsFatalError,
-    E: Send + FileDescEnvironment + FileDescOpener + ReportErrorEnvironment + SubEnvironment,
-    E::FileHandle: From<E::OpenedFileHandle>,
+    E: Send
+        + ExecutableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + LastPipelineStatusEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: Send + Clone + From<E::OpenedFileHandle>,
 {
     // When we spawn each command in the pipeline, we'll pins them to their own
     // (sub) environments.
@@ -85,7 +197,7 @@ where
             let pipe = env.open_pipe()?;
 
             env.set_file_desc(STDOUT_FILENO, pipe.writer.into(), Permissions::Write);
-            env_futures.push(spawn_and_swallow_errors(first, env));
+            env_futures.push(spawn_and_swallow_errors(env_futures.len(), first, env));
 
             pipe.reader
         };
@@ -99,18 +211,35 @@ where
             env.set_file_desc(STDOUT_FILENO, pipe.writer.into(), Permissions::Write);
             next_in = pipe.reader;
 
-            env_futures.push(spawn_and_swallow_errors(last, env));
+            env_futures.push(spawn_and_swallow_errors(env_futures.len(), last, env));
             last = next;
         }
 
-        let mut env = orig_env.sub_env();
-        env.set_file_desc(STDIN_FILENO, next_in.into(), Permissions::Read);
+        if cfg.lastpipe {
+            Box::pin(spawn_with_stdin(last, next_in.into(), orig_env))
+        } else {
+            let mut env = orig_env.sub_env();
+            env.set_file_desc(STDIN_FILENO, next_in.into(), Permissions::Read);
 
-        Box::pin(async move {
-            let ret = swallow_non_fatal_errors(last, &mut env).await;
-            drop(env);
-            ret
-        })
+            Box::pin(async move {
+                let ret = swallow_non_fatal_errors(last, &mut env).await;
+                drop(env);
+
+                // Each command runs in its own subshell, so any unhandled
+                // control flow (e.g. `exit`) only affects that command.
+                match ret {
+                    Err(e) => match e.control_flow() {
+                        Some(flow) => {
+                            let status = flow.exit_status();
+                            let future: BoxFuture<'static, _> = Box::pin(async move { status });
+                            Ok(future)
+                        }
+                        None => Err(e),
+                    },
+                    ret => ret,
+                }
+            })
+        }
     } else {
         Box::pin(swallow_non_fatal_errors(first, orig_env))
     };
@@ -132,98 +261,168 @@ where
     // at which point we can move into the second "static future" phase. But this requires
     // doing some extra book keeping which happens below.
 
-    let mut env_futures = Box::pin(env_futures);
-    let mut static_futures = Box::pin(FuturesUnordered::new());
-    let mut final_cmd_state = FinalCmdState::EnvFuture(final_cmd_env_future);
+    let (final_cmd, mut statuses, mut static_futures) = {
+        // The statuses of all but the final command, in pipeline order.
+        let mut statuses = vec![EXIT_SUCCESS; env_futures.len()];
+        let mut env_futures = Box::pin(env_futures);
+        let mut static_futures = Box::pin(FuturesUnordered::new());
+        let mut final_cmd_state = FinalCmdState::EnvFuture(final_cmd_env_future);
 
-    poll_fn(|cx| {
-        let env_futures_done = loop {
-            match env_futures.as_mut().poll_next(cx) {
-                Poll::Ready(Some(sf)) => sf.map(|sf| static_futures.push(sf)),
-                Poll::Ready(None) => break true,
-                Poll::Pending => break false,
+        poll_fn(|cx| {
+            let env_futures_done = loop {
+                match env_futures.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(sf)) => static_futures.push(sf),
+                    Poll::Ready(None) => break true,
+                    Poll::Pending => break false,
+                };
             };
-        };
 
-        loop {
-            match &mut final_cmd_state {
-                FinalCmdState::EnvFuture(ef) => {
-                    final_cmd_state = match ef.as_mut().poll(cx) {
-                        Poll::Pending => break,
-                        Poll::Ready(Ok(f)) => FinalCmdState::Maybe(MaybeDone::Future(f)),
-                        Poll::Ready(Err(e)) => FinalCmdState::Error(e),
-                    };
-                }
+            loop {
+                match &mut final_cmd_state {
+                    FinalCmdState::EnvFuture(ef) => {
+                        final_cmd_state = match ef.as_mut().poll(cx) {
+                            Poll::Pending => break,
+                            Poll::Ready(Ok(f)) => FinalCmdState::Maybe(MaybeDone::Future(f)),
+                            Poll::Ready(Err(e)) => FinalCmdState::Error(e),
+                        };
+                    }
+
+                    FinalCmdState::Error(_) => {}
 
-                FinalCmdState::Error(_) => {}
+                    FinalCmdState::Maybe(f) => {
+                        let _ = Pin::new(f).poll(cx);
+                    }
+                }
 
-                FinalCmdState::Maybe(f) => {
-                    let _ = Pin::new(f).poll(cx);
+                // Don't need references to any environments
+                // or commands any more, so bail!
+                if env_futures_done {
+                    return Poll::Ready(());
                 }
             }
 
-            // Don't need references to any environments
-            // or commands any more, so bail!
-            if env_futures_done {
-                return Poll::Ready(());
+            // Still have pending futures, keep polling any static_futures so they
+            // can make progress.
+            while let Poll::Ready(Some((i, status))) = static_futures.as_mut().poll_next(cx) {
+                statuses[i] = status;
             }
-        }
 
-        // Still have pending futures, keep polling any static_futures so they
-        // can make progress.
-        while let Poll::Ready(Some(_exit)) = static_futures.as_mut().poll_next(cx) {}
-
-        Poll::Pending
-    })
-    .await;
-
-    let final_cmd = match final_cmd_state {
-        FinalCmdState::EnvFuture(_) => unreachable!(),
-        FinalCmdState::Maybe(m) => m,
-        FinalCmdState::Error(e) => {
-            // We need to return an error back to the caller, but before we
-            // do so, we should continue to poll any pending futures and give
-            // those commands a chance to complete (or die due to pipe errors)
-            // rather than abruptly dropping/killing them.
-            while let Some(_status) = static_futures.next().await {}
-            return Err(e);
-        }
+            Poll::Pending
+        })
+        .await;
+
+        let final_cmd = match final_cmd_state {
+            FinalCmdState::EnvFuture(_) => unreachable!(),
+            FinalCmdState::Maybe(m) => m,
+            FinalCmdState::Error(e) => {
+                // We need to return an error back to the caller, but before we
+                // do so, we should continue to poll any pending futures and give
+                // those commands a chance to complete (or die due to pipe errors)
+                // rather than abruptly dropping/killing them.
+                while let Some(_status) = static_futures.next().await {}
+                return Err(e);
+            }
+        };
+
+        (final_cmd, statuses, static_futures)
     };
 
+    // With `lastpipe` the final command may have updated the last status of
+    // the original environment while it was being spawned (e.g. if it is a
+    // compound command), which would invalidate any recorder obtained before
+    // then, so we only get one once every command has been spawned.
+    let recorder = orig_env.pipeline_status_recorder();
+
     Ok(Box::pin(async move {
-        let (_, final_status) = futures_util::join!(
-            async move { while let Some(_status) = static_futures.next().await {} },
+        let (mut statuses, final_status) = futures_util::join!(
+            async move {
+                while let Some((i, status)) = static_futures.next().await {
+                    statuses[i] = status;
+                }
+
+                statuses
+            },
             final_cmd,
         );
 
+        statuses.push(final_status);
+        let status = if cfg.pipefail {
+            statuses
+                .iter()
+                .rev()
+                .copied()
+                .find(|status| !status.success())
+                .unwrap_or(final_status)
+        } else {
+            final_status
+        };
+
+        recorder.record(statuses);
+
         if invert_last_status {
-            if final_status.success() {
+            if status.success() {
                 EXIT_ERROR
             } else {
                 EXIT_SUCCESS
             }
         } else {
-            final_status
+            status
         }
     }))
 }
 
+/// Spawns the final command of a pipeline directly within `env`, with its
+/// standard input set to `stdin` until it has been spawned.
+async fn spawn_with_stdin<S, E>(
+    cmd: S,
+    stdin: E::FileHandle,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    S: Spawn<E>,
+    S::Error: IsFatalError,
+    E: FileDescEnvironment + ReportErrorEnvironment,
+    E::FileHandle: Clone,
+{
+    let orig_stdin = env
+        .file_desc(STDIN_FILENO)
+        .map(|(handle, perms)| (handle.clone(), perms));
+
+    env.set_file_desc(STDIN_FILENO, stdin, Permissions::Read);
+    let ret = swallow_non_fatal_errors(cmd, env).await;
+
+    match orig_stdin {
+        Some((handle, perms)) => env.set_file_desc(STDIN_FILENO, handle, perms),
+        None => env.close_file_desc(STDIN_FILENO),
+    }
+
+    ret
+}
+
+/// Spawns a non-final command of a pipeline, resolving to a future which
+/// yields the command's position in the pipeline along with its status.
 async fn spawn_and_swallow_errors<S, E>(
+    index: usize,
     cmd: S,
     mut env: E,
-) -> Option<BoxFuture<'static, ExitStatus>>
+) -> BoxFuture<'static, (usize, ExitStatus)>
 where
     S: Spawn<E>,
-    S::Error: 'static + Send + Sync + Error,
+    S::Error: IsFatalError,
     E: ReportErrorEnvironment,
 {
-    match cmd.spawn(&mut env).await {
-        Ok(f) => Some(f),
-        Err(e) => {
-            env.report_error(&e).await;
-            None
-        }
-    }
+    let status = match cmd.spawn(&mut env).await {
+        Ok(f) => return Box::pin(async move { (index, f.await) }),
+        Err(e) => match e.control_flow() {
+            Some(flow) => flow.exit_status(),
+            None => {
+                env.report_error(&e).await;
+                EXIT_ERROR
+            }
+        },
+    };
+
+    Box::pin(async move { (index, status) })
 }
 
 enum FinalCmdState<EF, ERR> {
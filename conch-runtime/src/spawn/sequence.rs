@@ -1,20 +1,28 @@
-use crate::env::{IsInteractiveEnvironment, LastStatusEnvironment, ReportErrorEnvironment};
+use crate::env::{
+    ControlFlowEnvironment, IsInteractiveEnvironment, LastStatusEnvironment,
+    ReportErrorEnvironment, ShellOptionsEnvironment,
+};
 use crate::error::IsFatalError;
-use crate::spawn::swallow_non_fatal_errors;
+use crate::spawn::{should_exit_on_error, swallow_non_fatal_errors};
 use crate::{ExitStatus, Spawn, EXIT_SUCCESS};
 use futures_core::future::BoxFuture;
 
 /// Spawns any iterable collection of sequential items.
 ///
 /// Commands are sequentially executed regardless of the exit status of
-/// previous commands. All non-fatal errors are reported and swallowed,
-/// however, "fatal" errors are bubbled up and the sequence terminated.
+/// previous commands (unless one fails while the `errexit` option is enabled).
+/// All non-fatal errors are reported and swallowed, however, "fatal" errors
+/// are bubbled up and the sequence terminated.
 pub async fn sequence<I, E: ?Sized>(
     iter: I,
     env: &mut E,
 ) -> Result<BoxFuture<'static, ExitStatus>, <I::Item as Spawn<E>>::Error>
 where
-    E: IsInteractiveEnvironment + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ControlFlowEnvironment
+        + IsInteractiveEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
     I: IntoIterator,
     I::Item: Spawn<E>,
     <I::Item as Spawn<E>>::Error: IsFatalError,
@@ -31,8 +39,9 @@ where
 /// Spawns an exact-size iterator of sequential items.
 ///
 /// Commands are sequentially executed regardless of the exit status of
-/// previous commands. All non-fatal errors are reported and swallowed,
-/// however, "fatal" errors are bubbled up and the sequence terminated.
+/// previous commands (unless one fails while the `errexit` option is enabled).
+/// All non-fatal errors are reported and swallowed, however, "fatal" errors
+/// are bubbled up and the sequence terminated.
 pub async fn sequence_exact<I, E>(
     cmds: I,
     env: &mut E,
@@ -42,7 +51,11 @@ where
     I::IntoIter: ExactSizeIterator,
     I::Item: Spawn<E>,
     <I::Item as Spawn<E>>::Error: IsFatalError,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
 {
     do_sequence(cmds.into_iter(), env, |_, iter| iter.len() != 0).await
 }
@@ -66,7 +79,12 @@ impl<'a, S, E> Spawn<E> for SequenceSlice<'a, S>
 where
     S: Send + Sync + Spawn<E>,
     S::Error: IsFatalError,
-    E: ?Sized + Send + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized
+        + Send
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
 {
     type Error = S::Error;
 
@@ -89,13 +107,18 @@ async fn do_sequence<I, E>(
     has_more: impl Fn(&E, &mut I) -> bool,
 ) -> Result<BoxFuture<'static, ExitStatus>, <I::Item as Spawn<E>>::Error>
 where
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
     I: Iterator,
     I::Item: Spawn<E>,
     <I::Item as Spawn<E>>::Error: IsFatalError,
 {
     let mut last_status = EXIT_SUCCESS; // Init in case we don't run at all
     while let Some(cmd) = iter.next() {
+        env.set_status_tested(false);
         let cmd = swallow_non_fatal_errors(&cmd, env).await?;
 
         if has_more(env, &mut iter) {
@@ -103,6 +126,12 @@ where
             // we should keep polling and hold on to the environment here
             last_status = cmd.await;
             env.set_last_status(last_status);
+
+            if should_exit_on_error(last_status, env) {
+                // Any enclosing commands will similarly stop upon seeing
+                // this status, until the shell itself exits.
+                break;
+            }
         } else {
             // The last command of our sequence which no longer needs
             // an environment context, so we can yield it back to the caller.
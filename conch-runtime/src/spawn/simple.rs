@@ -1,28 +1,56 @@
-use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
+use crate::env::builtin::{BuiltinEnvironment, BuiltinPrecedence, BuiltinUtility};
 use crate::env::{
-    AsyncIoEnvironment, EnvRestorer, ExecutableData, ExecutableEnvironment,
-    ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, FunctionEnvironment,
-    FunctionFrameEnvironment, RedirectEnvRestorer, SetArgumentsEnvironment,
-    UnsetVariableEnvironment, VarEnvRestorer, WorkingDirectoryEnvironment,
+    AliasEnvironment, AsyncIoEnvironment, ControlFlowEnvironment, DynamicVariableEnvironment,
+    EnvRestorer, ExecutableData, ExecutableEnvironment, ExportedVariableEnvironment,
+    FileDescEnvironment, FunctionEnvironment, FunctionFrameEnvironment, NestingEnvironment,
+    RedirectEnvRestorer, SetArgumentsEnvironment, ShellOption, ShellOptionsEnvironment, StrKey,
+    StringWrapper, TempFileEnvironment, UnsetVariableEnvironment, VarEnvRestorer,
+    VariableAttributesEnvironment, VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::error::{
+    CommandError, ControlFlow, ExpansionError, IsFatalError, RedirectionError, StackOverflowError,
 };
-use crate::error::{CommandError, RedirectionError};
 use crate::eval::{
     eval_redirects_or_cmd_words_with_restorer, eval_redirects_or_var_assignments_with_restorer,
     EvalRedirectOrCmdWordError, EvalRedirectOrVarAssigError, RedirectEval, RedirectOrCmdWord,
     RedirectOrVarAssig, WordEval,
 };
 use crate::io::FileDescWrapper;
+use crate::spawn::builtin::report_err;
 use crate::spawn::{function_body, Spawn};
 use crate::{
     ExitStatus, EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND, EXIT_ERROR, EXIT_SUCCESS,
     STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO,
 };
 use futures_core::future::BoxFuture;
-use std::borrow::Borrow;
-use std::collections::VecDeque;
+use std::borrow::{Borrow, Cow};
+use std::collections::{HashSet, VecDeque};
 use std::error::Error;
 use std::ffi::OsStr;
 
+const COMMAND: &str = "command";
+const PATH: &str = "PATH";
+const PS4: &str = "PS4";
+const DEFAULT_PS4: &str = "+ ";
+
+/// Rules for resolving the name of a simple command to something which can be spawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommandResolution {
+    /// Look for a function first, then a builtin utility, and finally
+    /// fall back to spawning an executable.
+    #[default]
+    Default,
+    /// Look for a builtin utility first, then a function, and finally
+    /// fall back to spawning an executable.
+    PreferBuiltin,
+    /// Only consider builtin utilities. If no such builtin exists, the command
+    /// will exit as if it were not found.
+    BuiltinOnly,
+    /// Ignore any functions, looking for a builtin utility first and then
+    /// falling back to spawning an executable.
+    SkipFunctions,
+}
+
 /// Spawns a shell command (or function) after applying any redirects and
 /// environment variable assignments.
 pub async fn simple_command<'a, R, V, W, IV, IW, S, E>(
@@ -36,20 +64,86 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
+    W::Error: 'static + Error + From<ExpansionError>,
+    E: ?Sized
+        + Send
+        + Sync
+        + AliasEnvironment
+        + AsyncIoEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + ExecutableEnvironment
+        + ExportedVariableEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + FunctionEnvironment<Fn = S>
+        + FunctionFrameEnvironment
+        + NestingEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + UnsetVariableEnvironment
+        + VariableAttributesEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, EnvRestorer<'a, E>, E>,
+    E::Arg: From<W::EvalResult>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
+    E::FnName: From<W::EvalResult>,
+    E::IoHandle: Send + Sync + From<E::FileHandle>,
+    E::VarName: Send + Sync + Clone + StrKey + From<V> + From<String>,
+    E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult> + From<String>,
+    S: Spawn<E> + Clone,
+    S::Error: IsFatalError
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>,
+{
+    simple_command_with_resolution(vars, words, CommandResolution::Default, env).await
+}
+
+/// Spawns a shell command (or function) after applying any redirects and
+/// environment variable assignments, resolving the command name according
+/// to the specified `CommandResolution` rules.
+///
+/// This allows a caller to control how a specific invocation is resolved
+/// (e.g. ensuring that a command is always handled by a builtin utility)
+/// without having to reconfigure the environment itself.
+pub async fn simple_command_with_resolution<'a, R, V, W, IV, IW, S, E>(
+    vars: IV,
+    words: IW,
+    resolution: CommandResolution,
+    env: &'a mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    IV: Iterator<Item = RedirectOrVarAssig<R, V, W>>,
+    IW: Iterator<Item = RedirectOrCmdWord<R, W>>,
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: WordEval<E>,
+    W::Error: 'static + Error + From<ExpansionError>,
     E: ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + NestingEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
         + UnsetVariableEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, EnvRestorer<'a, E>, E>,
     E::Arg: From<W::EvalResult>,
@@ -57,12 +151,19 @@ where
     E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
     E::FnName: From<W::EvalResult>,
     E::IoHandle: Send + Sync + From<E::FileHandle>,
-    E::VarName: Send + Sync + Clone + Borrow<String> + From<V>,
-    E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult>,
+    E::VarName: Send + Sync + Clone + StrKey + From<V> + From<String>,
+    E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult> + From<String>,
     S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    S::Error: IsFatalError
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>,
 {
-    simple_command_with_restorer(vars, words, &mut EnvRestorer::new(env)).await
+    simple_command_with_restorer_and_resolution(vars, words, resolution, &mut EnvRestorer::new(env))
+        .await
 }
 
 /// Spawns a shell command (or function) after applying any redirects and
@@ -78,12 +179,76 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
+    W::Error: 'static + Error + From<ExpansionError>,
+    RR: ?Sized
+        + Send
+        + Sync
+        + AsyncIoEnvironment
+        + TempFileEnvironment
+        + ExportedVariableEnvironment
+        + RedirectEnvRestorer<'a, E>
+        + VarEnvRestorer<'a, E>,
+    RR::FileHandle: From<RR::OpenedFileHandle>,
+    RR::IoHandle: Send + From<RR::FileHandle>,
+    E: 'a
+        + ?Sized
+        + Send
+        + Sync
+        + AliasEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
+        + ExecutableEnvironment
+        + ExportedVariableEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment<Fn = S>
+        + FunctionFrameEnvironment
+        + NestingEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + VariableAttributesEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
+    E::Arg: From<W::EvalResult>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::FnName: From<W::EvalResult>,
+    E::VarName: Clone + StrKey + From<V> + From<String>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult> + From<String>,
+    S: Spawn<E> + Clone,
+    S::Error: IsFatalError
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>,
+{
+    simple_command_with_restorer_and_resolution(vars, words, CommandResolution::Default, restorer)
+        .await
+}
+
+/// Spawns a shell command (or function) after applying any redirects and
+/// environment variable assignments, resolving the command name according
+/// to the specified `CommandResolution` rules.
+pub async fn simple_command_with_restorer_and_resolution<'a, R, V, W, IV, IW, RR, S, E>(
+    vars: IV,
+    words: IW,
+    resolution: CommandResolution,
+    restorer: &mut RR,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    IV: Iterator<Item = RedirectOrVarAssig<R, V, W>>,
+    IW: Iterator<Item = RedirectOrCmdWord<R, W>>,
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: WordEval<E>,
+    W::Error: 'static + Error + From<ExpansionError>,
     RR: ?Sized
         + Send
         + Sync
         + AsyncIoEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
@@ -93,25 +258,37 @@ where
         + ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + NestingEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
     E::Arg: From<W::EvalResult>,
     E::Args: From<VecDeque<E::Arg>>,
     E::FileHandle: Clone + FileDescWrapper,
     E::FnName: From<W::EvalResult>,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
+    E::VarName: Clone + StrKey + From<V> + From<String>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult> + From<String>,
     S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    S::Error: IsFatalError
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>,
 {
-    let ret = do_simple_command_with_restorer(vars, words, restorer).await;
+    let ret = do_simple_command_with_restorer(vars, words, resolution, restorer).await;
     restorer.restore_vars();
     restorer.restore_redirects();
     ret
@@ -120,6 +297,7 @@ where
 async fn do_simple_command_with_restorer<'a, R, V, W, IV, IW, RR, S, E>(
     vars: IV,
     mut words: IW,
+    resolution: CommandResolution,
     restorer: &mut RR,
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
@@ -128,12 +306,12 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
-    W::Error: 'static + Error,
+    W::Error: 'static + Error + From<ExpansionError>,
     RR: ?Sized
         + Send
         + Sync
         + AsyncIoEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
@@ -143,23 +321,35 @@ where
         + ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + ControlFlowEnvironment
+        + DynamicVariableEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + NestingEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + VariableAttributesEnvironment
         + WorkingDirectoryEnvironment,
     E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
     E::Arg: From<W::EvalResult>,
     E::Args: From<VecDeque<E::Arg>>,
     E::FileHandle: Clone + FileDescWrapper,
     E::FnName: From<W::EvalResult>,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
+    E::VarName: Clone + StrKey + From<V> + From<String>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult> + From<String>,
     S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    S::Error: IsFatalError
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<ControlFlow>
+        + From<StackOverflowError>
+        + From<RedirectionError>,
 {
     // Any other redirects encountered before we found a command word
     let mut other_redirects = Vec::new();
@@ -181,54 +371,179 @@ where
     // behave as if the variables were exported. Otherwise
     // variables should maintain an exported status if they had one
     // or default to non-exported!
-    let export_vars = first_word.as_ref().map(|_| true);
+    let export_vars = first_word.as_ref().map(|_| true).or_else(|| {
+        // Unless the `allexport` option says all assignments should be exported
+        Some(true).filter(|_| restorer.get().is_option_enabled(ShellOption::AllExport))
+    });
 
-    let vars = vars.chain(other_redirects.into_iter());
-    let words = first_word.into_iter().chain(words);
+    let vars = vars.chain(other_redirects).collect::<Vec<_>>();
+    let words = first_word.into_iter().chain(words).collect::<Vec<_>>();
+
+    // Aliases are only expanded in words which were written out literally (e.g.
+    // not quoted or the result of an expansion), as determined before they are
+    // evaluated. Each such word will then evaluate to exactly one field.
+    let literal_words = words
+        .iter()
+        .filter_map(|w| match w {
+            RedirectOrCmdWord::CmdWord(w) => Some(w),
+            RedirectOrCmdWord::Redirect(_) => None,
+        })
+        .take_while(|w| w.as_literal().is_some())
+        .count();
+
+    // A command consisting solely of literal words which name a trivial builtin
+    // (e.g. the `true` in `while true; do ...`) has nothing to evaluate, so we
+    // can skip straight to the builtin's exit status.
+    if vars.is_empty() && literal_words == words.len() {
+        let env = restorer.get();
+        let name = match words.first() {
+            Some(RedirectOrCmdWord::CmdWord(w)) => w.as_literal(),
+            _ => None,
+        };
+
+        let name = name
+            .filter(|_| !env.is_option_enabled(ShellOption::XTrace))
+            .filter(|name| !env.expand_aliases() || env.alias(name).is_none())
+            .map(|name| E::FnName::from(W::EvalResult::from(name.to_owned())));
+
+        if let Some(name) = name {
+            let shadowed =
+                resolution == CommandResolution::Default && env.function(&name).is_some();
+            let status = env
+                .builtin(&name)
+                .filter(|_| !shadowed)
+                .filter(|b| {
+                    b.precedence() != BuiltinPrecedence::AfterPath
+                        || resolution == CommandResolution::BuiltinOnly
+                })
+                .and_then(|b| b.trivial_status());
+
+            if let Some(status) = status {
+                return Ok(Box::pin(async move { status }));
+            }
+        }
+    }
 
-    eval_redirects_or_var_assignments_with_restorer(export_vars, vars, restorer)
+    eval_redirects_or_var_assignments_with_restorer(export_vars, vars.into_iter(), restorer)
         .await
         .map_err(|e| match e {
             EvalRedirectOrVarAssigError::Redirect(e) => S::Error::from(e),
             EvalRedirectOrVarAssigError::VarAssig(e) => S::Error::from(e),
         })?;
 
-    let mut words = eval_redirects_or_cmd_words_with_restorer(restorer, words)
+    let mut words = eval_redirects_or_cmd_words_with_restorer(restorer, words.into_iter())
         .await
         .map_err(|e| match e {
             EvalRedirectOrCmdWordError::Redirect(e) => S::Error::from(e),
             EvalRedirectOrCmdWordError::CmdWord(e) => S::Error::from(e),
         })?;
 
-    let cmd_name = if words.is_empty() {
+    expand_aliases(&mut words, literal_words, restorer.get());
+
+    if !words.is_empty() && restorer.get().is_option_enabled(ShellOption::XTrace) {
+        let mut trace = E::VarName::lookup(restorer.get(), PS4)
+            .map_or(DEFAULT_PS4, |ps4| ps4.borrow())
+            .to_owned();
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                trace.push(' ');
+            }
+            trace.push_str(word.borrow());
+        }
+        trace.push('\n');
+
+        trace_command(trace, restorer).await;
+    }
+
+    if words.is_empty() {
         // "Empty" command which is probably just assigning variables.
         // Any redirect side effects have already been applied, but ensure
         // we keep the actual variable values.
         restorer.clear_vars();
         return Ok(Box::pin(async { EXIT_SUCCESS }));
+    }
+
+    let resolution = match resolve_command_prefixes(&mut words, resolution, restorer.get()) {
+        Ok(resolution) => resolution,
+        Err(option) => {
+            let err = format!("{}: option not supported", option.as_str());
+            return Ok(report_err(COMMAND, restorer, err).await);
+        }
+    };
+    let cmd_name = if words.is_empty() {
+        // `command` or `builtin` without a command to run
+        return Ok(Box::pin(async { EXIT_SUCCESS }));
     } else {
         words.remove(0)
     };
 
     {
+        // Builtins may defer to an executable of the same name found via `$PATH`
+        let name: &str = cmd_name.borrow();
+        let defers_to_path = |builtin: &E::Builtin, env: &E| {
+            builtin.precedence() == BuiltinPrecedence::AfterPath
+                && resolution != CommandResolution::BuiltinOnly
+                && is_on_path(name, env)
+        };
+
         let cmd_name = cmd_name.clone().into();
         let env = restorer.get_mut();
 
-        if let Some(func) = env.function(&cmd_name).cloned() {
+        let builtin_first = resolution != CommandResolution::Default;
+        if builtin_first {
+            let builtin = env.builtin(&cmd_name).filter(|b| !defers_to_path(b, env));
+            if let Some(builtin) = builtin {
+                if let Some(status) = builtin.trivial_status() {
+                    return Ok(Box::pin(async move { status }));
+                }
+
+                let ret = builtin.spawn_builtin(words, restorer).await;
+                return check_control_flow(ret, restorer.get_mut());
+            } else if resolution == CommandResolution::BuiltinOnly {
+                let err = CommandError::NotFound(name.to_owned());
+                return Ok(report_command_error(&err, restorer).await);
+            }
+        }
+
+        let skip_functions = resolution == CommandResolution::SkipFunctions;
+        if let Some(func) = env.function(&cmd_name).filter(|_| !skip_functions).cloned() {
             let args = words.into_iter().map(Into::into).collect();
             return Ok(function_body(func, args, env).await?);
-        } else if let Some(builtin) = env.builtin(&cmd_name) {
-            return Ok(builtin.spawn_builtin(words, restorer).await);
+        } else if !builtin_first {
+            let builtin = env.builtin(&cmd_name).filter(|b| !defers_to_path(b, env));
+            if let Some(builtin) = builtin {
+                if let Some(status) = builtin.trivial_status() {
+                    return Ok(Box::pin(async move { status }));
+                }
+
+                let ret = builtin.spawn_builtin(words, restorer).await;
+                return check_control_flow(ret, restorer.get_mut());
+            }
         }
     }
 
-    // FIXME: inherit all open file descriptors on UNIX systems
-    let (stdin, stdout, stderr) = {
+    let (stdin, stdout, stderr, inherited, err_fdes) = {
         let env = restorer.get();
+        let inherited = env
+            .file_descs()
+            .into_iter()
+            .filter(|&fd| fd != STDIN_FILENO && fd != STDOUT_FILENO && fd != STDERR_FILENO)
+            .filter_map(|fd| env.file_desc(fd).map(|(fdes, _)| (fd, fdes.clone())))
+            .collect::<Vec<_>>();
+
+        // Hold on to the command's (possibly redirected) standard error so
+        // we can report if it fails to spawn, just like the command would
+        let err_fdes = env
+            .file_desc(STDERR_FILENO)
+            .filter(|(_, perms)| perms.writable())
+            .map(|(fdes, _)| fdes.clone());
+
         (
             env.file_desc(STDIN_FILENO).map(|(fdes, _)| fdes).cloned(),
             env.file_desc(STDOUT_FILENO).map(|(fdes, _)| fdes).cloned(),
             env.file_desc(STDERR_FILENO).map(|(fdes, _)| fdes).cloned(),
+            inherited,
+            err_fdes,
         )
     };
 
@@ -240,28 +555,25 @@ where
     // the Rc/Arc counts should be just one here and we can cheaply unwrap
     // the handles. Otherwise, we're forced to duplicate the actual handle
     // (which is a pretty unfortunate "limitation" of std::process::Command)
-    let get_io = move |fd, fdes: Option<E::FileHandle>| match fdes {
-        None => Ok(None),
-        Some(fdes_wrapper) => match fdes_wrapper.try_unwrap() {
-            Ok(fdes) => Ok(Some(fdes)),
-            Err(err) => {
-                let msg = format!("file descriptor {}", fd);
-                Err(RedirectionError::Io(err, Some(msg)))
-            }
-        },
+    let unwrap_io = |fd, fdes_wrapper: E::FileHandle| {
+        fdes_wrapper.try_unwrap().map_err(|err| {
+            let msg = format!("file descriptor {}", fd);
+            RedirectionError::Io(err, Some(msg))
+        })
     };
+    let get_io = |fd, fdes: Option<E::FileHandle>| fdes.map(|fdes| unwrap_io(fd, fdes)).transpose();
 
     let env = restorer.get();
     let args = words
         .iter()
         .map(|a| OsStr::new(a.borrow()))
         .collect::<Vec<_>>();
-    let env_vars = env
-        .env_vars()
+    let exported_vars = env.exported_vars();
+    let env_vars = exported_vars
         .iter()
-        .map(|&(ref key, ref val)| {
-            let key = OsStr::new((*key).borrow());
-            let val = OsStr::new((*val).borrow());
+        .map(|(key, val)| {
+            let key = OsStr::new(key.as_key_str());
+            let val = OsStr::new(val.borrow());
             (key, val)
         })
         .collect::<Vec<_>>();
@@ -276,6 +588,13 @@ where
         stdin: get_io(STDIN_FILENO, stdin)?,
         stdout: get_io(STDOUT_FILENO, stdout)?,
         stderr: get_io(STDERR_FILENO, stderr)?,
+        inherited_fds: inherited
+            .into_iter()
+            .map(|(fd, fdes)| unwrap_io(fd, fdes).map(|fdes| (fd, fdes)))
+            .collect::<Result<_, _>>()?,
+        deadline: None,
+        resource_limits: &[],
+        process_group: None,
     };
 
     let child = env.spawn_executable(data);
@@ -288,13 +607,7 @@ where
         Ok(ret) => Ok(ret),
         Err(e) => {
             if let Some(e) = find_root_cause(&e).downcast_ref::<CommandError>() {
-                let status = match e {
-                    CommandError::NotExecutable(_) => EXIT_CMD_NOT_EXECUTABLE,
-                    CommandError::NotFound(_) => EXIT_CMD_NOT_FOUND,
-                    CommandError::Io(_, _) => EXIT_ERROR,
-                };
-
-                Ok(Box::pin(async move { status }))
+                Ok(write_command_error(e, err_fdes, restorer))
             } else {
                 Err(S::Error::from(e))
             }
@@ -302,6 +615,94 @@ where
     }
 }
 
+/// Indicates if an executable with the specified name can be found in any of
+/// the directories listed in `$PATH`.
+fn is_on_path<E>(name: &str, env: &E) -> bool
+where
+    E: ?Sized + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: StrKey,
+    E::Var: Borrow<String>,
+{
+    let path = match E::VarName::lookup(env, PATH) {
+        Some(path) => path.borrow(),
+        None => return false,
+    };
+
+    std::env::split_paths(path).any(|dir| {
+        let path = env.path_relative_to_working_dir(Cow::Owned(dir.join(name)));
+        env.path_metadata(&path).is_ok_and(|m| m.is_file()) && crate::sys::is_executable(&path)
+    })
+}
+
+/// Determines the exit status of a command which failed to spawn.
+fn command_error_status(err: &CommandError) -> ExitStatus {
+    match err {
+        CommandError::NotExecutable(_) | CommandError::ArgListTooLong(_, _, _) => {
+            EXIT_CMD_NOT_EXECUTABLE
+        }
+        CommandError::NotFound(_) => EXIT_CMD_NOT_FOUND,
+        CommandError::Io(_, _) | CommandError::Timeout(_) => EXIT_ERROR,
+    }
+}
+
+/// Reports a command which could not be spawned to the (possibly redirected)
+/// standard error of the environment, resolving to the appropriate exit status.
+async fn report_command_error<E>(err: &CommandError, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let fdes = env
+        .file_desc(STDERR_FILENO)
+        .filter(|(_, perms)| perms.writable())
+        .map(|(fdes, _)| fdes.clone());
+
+    write_command_error(err, fdes, env)
+}
+
+/// Writes a command which could not be spawned to the specified descriptor
+/// (if any), resolving to the appropriate exit status.
+fn write_command_error<E>(
+    err: &CommandError,
+    fdes: Option<E::FileHandle>,
+    env: &mut E,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let status = command_error_status(err);
+    let fdes = match fdes {
+        Some(fdes) => fdes,
+        None => return Box::pin(async move { status }),
+    };
+
+    let future = env.write_all(fdes.into(), Cow::Owned(format!("{}\n", err).into_bytes()));
+    Box::pin(async move {
+        let _ = future.await;
+        status
+    })
+}
+
+/// Writes a trace of a command, like `set -x` does, to the (possibly
+/// redirected) standard error of the environment.
+async fn trace_command<E>(trace: String, env: &mut E)
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let fdes = match env.file_desc(STDERR_FILENO) {
+        Some((fdes, perms)) if perms.writable() => fdes.clone(),
+        _ => return,
+    };
+
+    let _ = env
+        .write_all(fdes.into(), Cow::Owned(trace.into_bytes()))
+        .await;
+}
+
 fn find_root_cause<'a>(mut err: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
     while let Some(e) = err.source() {
         err = e;
@@ -309,3 +710,132 @@ fn find_root_cause<'a>(mut err: &'a (dyn Error + 'static)) -> &'a (dyn Error + '
 
     err
 }
+
+/// Builtins can only resolve to an exit status, so any control flow they
+/// requested (e.g. `return`) is turned into an error here so that it can
+/// propagate to whichever spawner is responsible for handling it.
+fn check_control_flow<E, ERR>(
+    ret: BoxFuture<'static, ExitStatus>,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, ERR>
+where
+    E: ?Sized + ControlFlowEnvironment,
+    ERR: From<ControlFlow>,
+{
+    match env.take_control_flow() {
+        Some(flow) => Err(ERR::from(flow)),
+        None => Ok(ret),
+    }
+}
+
+/// Strips any leading `command` (skip functions) or `builtin` (only consider
+/// builtin utilities) words, returning the resolution rules they imply.
+///
+/// Like other builtin utilities, `command` and `builtin` can be overridden by
+/// functions of the same name unless functions are already being skipped.
+///
+/// The options of `command` (e.g. `command -v`) are not currently supported,
+/// and the first one found is returned as an error instead.
+fn resolve_command_prefixes<T, E>(
+    words: &mut Vec<T>,
+    mut resolution: CommandResolution,
+    env: &E,
+) -> Result<CommandResolution, T>
+where
+    T: StringWrapper,
+    E: ?Sized + FunctionEnvironment,
+    E::FnName: From<T>,
+{
+    while let Some(word) = words.first() {
+        let prefix_resolution = match word.as_str() {
+            COMMAND => CommandResolution::SkipFunctions,
+            "builtin" => CommandResolution::BuiltinOnly,
+            _ => break,
+        };
+
+        if resolution == CommandResolution::Default && env.has_function(&word.clone().into()) {
+            break;
+        }
+
+        words.remove(0);
+        match words.first().map(StringWrapper::as_str) {
+            Some("--") => {
+                words.remove(0);
+            }
+            Some(option)
+                if prefix_resolution == CommandResolution::SkipFunctions
+                    && option.len() > 1
+                    && option.starts_with('-') =>
+            {
+                return Err(words.remove(0));
+            }
+            _ => {}
+        }
+
+        if resolution != CommandResolution::BuiltinOnly {
+            resolution = prefix_resolution;
+        }
+    }
+
+    Ok(resolution)
+}
+
+/// Expands any aliases found at the start of a command (if enabled), where
+/// only the first `literal_words` words were written out literally.
+///
+/// Aliases are expanded by splitting their values into fields at whitespace,
+/// and the first resulting field will be checked for an alias as well. If an
+/// alias' value ends with a blank, the word following it will also be checked.
+fn expand_aliases<T, E>(words: &mut Vec<T>, literal_words: usize, env: &E)
+where
+    T: StringWrapper,
+    E: ?Sized + AliasEnvironment,
+{
+    if !env.expand_aliases() {
+        return;
+    }
+
+    // Avoid infinitely expanding recursive definitions such as `alias ls='ls -F'`
+    let mut expanded = HashSet::new();
+    let mut idx = 0;
+
+    for _ in 0..literal_words {
+        if idx >= words.len() {
+            break;
+        }
+
+        let mut following = idx + 1;
+        let mut check_following = false;
+
+        while let Some(word) = words.get(idx) {
+            let name = word.as_str();
+            let value = match env.alias(name) {
+                Some(value) if !expanded.contains(name) => value,
+                _ => break,
+            };
+
+            expanded.insert(name.to_owned());
+            check_following = value.ends_with(char::is_whitespace);
+
+            let fields = value
+                .split_whitespace()
+                .map(|field| T::from(field.to_owned()))
+                .collect::<Vec<_>>();
+
+            following = following - 1 + fields.len();
+            let is_empty = fields.is_empty();
+            words.splice(idx..=idx, fields);
+
+            // The following word was not a part of the alias' value
+            if is_empty {
+                break;
+            }
+        }
+
+        if !check_following {
+            break;
+        }
+
+        idx = following;
+    }
+}
@@ -0,0 +1,42 @@
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, Pipe, ReportErrorEnvironment, SubEnvironment,
+    TempFileEnvironment,
+};
+use crate::error::IsFatalError;
+use crate::io::Permissions;
+use crate::spawn::subshell::subshell_with_env;
+use crate::{ExitStatus, Spawn, STDIN_FILENO};
+use std::future::Future;
+use std::io;
+
+/// Spawns something in a subshell whose standard input is a pipe which will
+/// be fed the provided `data`, resolving with its exit status.
+///
+/// The data is written in the background on a best effort basis (see
+/// `AsyncIoEnvironment::write_all_best_effort`), so it is not an error if
+/// the command exits without reading all of it.
+pub fn with_stdin_bytes<S, E>(
+    data: Vec<u8>,
+    spawn: S,
+    env: &E,
+) -> impl Future<Output = Result<ExitStatus, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let mut env = env.sub_env();
+    async move {
+        let Pipe { reader, writer } = env.open_pipe()?;
+        env.set_file_desc(STDIN_FILENO, reader.into(), Permissions::Read);
+        env.write_all_best_effort(writer.into(), data);
+
+        Ok(subshell_with_env(spawn, env).await)
+    }
+}
@@ -1,16 +1,17 @@
 use crate::env::{ReportErrorEnvironment, SubEnvironment};
+use crate::error::IsFatalError;
 use crate::{ExitStatus, Spawn, EXIT_ERROR};
-use std::error::Error;
 use std::future::Future;
 
 /// Spawns anything as if running in a subshell environment.
 ///
 /// The `env` parameter will be copied as a `SubEnvironment`, in whose context
-/// the commands will be executed.
+/// the commands will be executed. Any control flow requests (e.g. `exit`)
+/// which escape the commands will terminate the subshell only.
 pub fn subshell<S, E>(spawn: S, env: &E) -> impl Future<Output = ExitStatus>
 where
     S: Spawn<E>,
-    S::Error: 'static + Send + Sync + Error,
+    S::Error: IsFatalError,
     E: ReportErrorEnvironment + SubEnvironment,
 {
     subshell_with_env(spawn, env.sub_env())
@@ -19,14 +20,18 @@ where
 pub(crate) async fn subshell_with_env<S, E>(spawn: S, mut env: E) -> ExitStatus
 where
     S: Spawn<E>,
-    S::Error: 'static + Send + Sync + Error,
+    S::Error: IsFatalError,
     E: ReportErrorEnvironment,
 {
     match spawn.spawn(&mut env).await {
         Ok(future) => future.await,
-        Err(e) => {
-            env.report_error(&e).await;
-            EXIT_ERROR
-        }
+        Err(e) => match e.control_flow() {
+            // Any unhandled `exit` (or `return`, etc.) only applies to the subshell
+            Some(flow) => flow.exit_status(),
+            None => {
+                env.report_error(&e).await;
+                EXIT_ERROR
+            }
+        },
     }
 }
@@ -1,43 +1,38 @@
 use crate::env::{
-    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, Pipe, ReportErrorEnvironment,
-    SubEnvironment,
+    AsyncIoEnvironment, ClockEnvironment, FileDescEnvironment, Pipe, ReportErrorEnvironment,
+    SubEnvironment, TempFileEnvironment,
 };
+use crate::error::IsFatalError;
 use crate::io::Permissions;
 use crate::spawn::subshell::subshell_with_env;
-use crate::{Spawn, STDOUT_FILENO};
+use crate::{ExitStatus, Fd, Spawn, STDERR_FILENO, STDOUT_FILENO};
+use futures_core::stream::BoxStream;
+use futures_util::stream::StreamExt;
 use std::borrow::Cow;
-use std::error::Error;
 use std::future::Future;
 use std::io;
+use std::time::SystemTime;
+use tokio::sync::mpsc::UnboundedSender;
 
 /// Spawns something whose standard output will be captured (and trailing newlines trimmed).
+///
+/// Any invalid UTF-8 sequences in the output will be replaced with
+/// `U+FFFD REPLACEMENT CHARACTER`; use `substitution_bytes` to avoid this.
 pub fn substitution<S, E>(spawn: S, env: &E) -> impl Future<Output = Result<String, S::Error>>
 where
     S: Spawn<E>,
-    S::Error: 'static + Send + Sync + From<io::Error> + Error,
+    S::Error: From<io::Error> + IsFatalError,
     E: AsyncIoEnvironment
         + FileDescEnvironment
-        + FileDescOpener
+        + TempFileEnvironment
         + ReportErrorEnvironment
         + SubEnvironment,
     E::FileHandle: From<E::OpenedFileHandle>,
     E::IoHandle: From<E::OpenedFileHandle>,
 {
-    let mut env = env.sub_env();
+    let output = substitution_bytes(spawn, env);
     async move {
-        let Pipe {
-            reader: cmd_output,
-            writer: cmd_stdout_fd,
-        } = env.open_pipe()?;
-
-        let cmd_stdout_fd: E::FileHandle = cmd_stdout_fd.into();
-        env.set_file_desc(STDOUT_FILENO, cmd_stdout_fd, Permissions::Write);
-
-        let output = env.read_all(cmd_output.into());
-        let cmd = subshell_with_env(spawn, env);
-
-        let (buf, _) = futures_util::join!(output, cmd);
-        let mut buf = buf?;
+        let mut buf = output.await?;
 
         while Some(&b'\n') == buf.last() {
             buf.pop();
@@ -54,3 +49,202 @@ where
         Ok(ret)
     }
 }
+
+/// Spawns something whose standard output will be captured byte-for-byte.
+///
+/// Unlike `substitution`, the output is returned exactly as it was written:
+/// trailing newlines are not trimmed, and invalid UTF-8 or embedded NUL
+/// bytes are preserved as is.
+pub fn substitution_bytes<S, E>(
+    spawn: S,
+    env: &E,
+) -> impl Future<Output = Result<Vec<u8>, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let output = capture(spawn, env, false);
+    async move { Ok(output.await?.stdout) }
+}
+
+/// The output captured from a command spawned via `capture_output`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    /// Everything the command wrote to its standard output.
+    pub stdout: Vec<u8>,
+    /// Everything the command wrote to its standard error (including any
+    /// errors reported while running it).
+    pub stderr: Vec<u8>,
+    /// The exit status of the command.
+    pub status: ExitStatus,
+}
+
+/// Spawns something (e.g. a `sequence_slice` of commands) whose standard
+/// output and standard error will both be captured byte-for-byte, along with
+/// its exit status.
+///
+/// Like with command substitutions, the command is run in a subshell of the
+/// provided environment, so any changes it makes to its environment are not
+/// visible to the caller.
+pub fn capture_output<S, E>(spawn: S, env: &E) -> impl Future<Output = Result<Output, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    capture(spawn, env, true)
+}
+
+/// Spawns something in a subshell whose standard output (and optionally
+/// standard error) will be captured.
+fn capture<S, E>(
+    spawn: S,
+    env: &E,
+    capture_stderr: bool,
+) -> impl Future<Output = Result<Output, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let mut env = env.sub_env();
+    async move {
+        let stdout = redirect_to_pipe(STDOUT_FILENO, &mut env)?;
+        let stdout = env.read_all(stdout);
+        let stderr = if capture_stderr {
+            let stderr = redirect_to_pipe(STDERR_FILENO, &mut env)?;
+            env.read_all(stderr)
+        } else {
+            Box::pin(async { Ok(Vec::new()) })
+        };
+
+        let cmd = subshell_with_env(spawn, env);
+
+        let (stdout, stderr, status) = futures_util::join!(stdout, stderr, cmd);
+        Ok(Output {
+            stdout: stdout?,
+            stderr: stderr?,
+            status,
+        })
+    }
+}
+
+/// The stream to which a captured chunk of output was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputStream {
+    /// The standard output stream.
+    Stdout,
+    /// The standard error stream.
+    Stderr,
+}
+
+/// A chunk of output captured via `capture_output_chunks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputChunk {
+    /// The stream to which the chunk was written.
+    pub stream: OutputStream,
+    /// The time at which the chunk was read.
+    pub timestamp: SystemTime,
+    /// The data which was written.
+    pub data: Vec<u8>,
+}
+
+/// Spawns something whose standard output and standard error will be
+/// captured, sending each chunk of output to `chunks` as soon as it is
+/// written, tagged with the stream it was written to and the time it was
+/// read (according to the clock of the environment), and resolving with the
+/// command's exit status.
+///
+/// This allows the caller to consume the (interleaved) output live, e.g. to
+/// display it with timestamps. Like with `capture_output`, the command is
+/// run in a subshell of the provided environment. Note that output is still
+/// read (and discarded) if the receiving half of the channel is closed, so
+/// that the command is not interrupted.
+pub fn capture_output_chunks<S, E>(
+    spawn: S,
+    env: &E,
+    chunks: UnboundedSender<OutputChunk>,
+) -> impl Future<Output = Result<ExitStatus, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: From<io::Error> + IsFatalError,
+    E: AsyncIoEnvironment
+        + ClockEnvironment
+        + FileDescEnvironment
+        + TempFileEnvironment
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    // NB: the command's environment is moved into the subshell, so timestamps
+    // are read from a separate copy which does not hold the pipes open.
+    let clock = env.sub_env();
+    let mut env = env.sub_env();
+    async move {
+        let stdout = redirect_to_pipe(STDOUT_FILENO, &mut env)?;
+        let stdout = env.read_chunks(stdout);
+        let stdout = forward_chunks(OutputStream::Stdout, stdout, &clock, &chunks);
+        let stderr = redirect_to_pipe(STDERR_FILENO, &mut env)?;
+        let stderr = env.read_chunks(stderr);
+        let stderr = forward_chunks(OutputStream::Stderr, stderr, &clock, &chunks);
+
+        let cmd = subshell_with_env(spawn, env);
+
+        let (stdout, stderr, status) = futures_util::join!(stdout, stderr, cmd);
+        stdout?;
+        stderr?;
+        Ok(status)
+    }
+}
+
+async fn forward_chunks<C: ClockEnvironment>(
+    stream: OutputStream,
+    mut data: BoxStream<'static, io::Result<Vec<u8>>>,
+    clock: &C,
+    chunks: &UnboundedSender<OutputChunk>,
+) -> io::Result<()> {
+    while let Some(data) = data.next().await {
+        let chunk = OutputChunk {
+            stream,
+            timestamp: clock.system_time(),
+            data: data?,
+        };
+
+        // NB: keep reading even if the receiver is gone
+        let _ = chunks.send(chunk);
+    }
+
+    Ok(())
+}
+
+/// Replaces `fd` with the write end of a new pipe, returning its read end.
+fn redirect_to_pipe<E>(fd: Fd, env: &mut E) -> io::Result<E::IoHandle>
+where
+    E: AsyncIoEnvironment + FileDescEnvironment + TempFileEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let Pipe { reader, writer } = env.open_pipe()?;
+    env.set_file_desc(fd, writer.into(), Permissions::Write);
+    Ok(reader.into())
+}
@@ -0,0 +1,32 @@
+use crate::error::CommandError;
+use crate::{ExitStatus, Spawn};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Spawns a command and waits for its completion, cancelling it if it does
+/// not finish within the specified `duration`.
+///
+/// The timeout applies to both spawning the command and awaiting its result.
+/// Should the timeout elapse, any futures of the command are dropped (which
+/// will kill any child processes it has spawned) and the command will resolve
+/// with a `CommandError::Timeout` error.
+pub async fn with_timeout<S, E>(
+    duration: Duration,
+    cmd: S,
+    env: &mut E,
+) -> Result<ExitStatus, S::Error>
+where
+    S: Spawn<E>,
+    S::Error: From<CommandError>,
+    E: ?Sized,
+{
+    let run = async {
+        let future = cmd.spawn(env).await?;
+        Ok(future.await)
+    };
+
+    match timeout(duration, run).await {
+        Ok(ret) => ret,
+        Err(_) => Err(CommandError::Timeout(duration).into()),
+    }
+}
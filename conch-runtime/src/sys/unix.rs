@@ -1,6 +1,12 @@
 //! Extensions and implementations specific to Unix platforms.
 
+use crate::env::{Resource, ResourceLimit};
+use std::ffi::{CStr, CString};
 use std::io::{Error, ErrorKind, Result};
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+use std::ptr;
 
 pub mod io;
 
@@ -35,3 +41,263 @@ pub(crate) fn cvt_r<T: IsMinusOne, F: FnMut() -> T>(mut f: F) -> Result<T> {
         }
     }
 }
+
+/// Applies a resource limit to the current process.
+///
+/// Only performs syscalls, thus it is safe to invoke between fork and exec.
+pub(crate) fn set_resource_limit(limit: &ResourceLimit) -> Result<()> {
+    let resource = match limit.resource {
+        Resource::CpuTime => libc::RLIMIT_CPU,
+        Resource::FileSize => libc::RLIMIT_FSIZE,
+        Resource::DataSize => libc::RLIMIT_DATA,
+        Resource::StackSize => libc::RLIMIT_STACK,
+        Resource::CoreFileSize => libc::RLIMIT_CORE,
+        Resource::OpenFiles => libc::RLIMIT_NOFILE,
+        Resource::AddressSpace => libc::RLIMIT_AS,
+        Resource::Processes => libc::RLIMIT_NPROC,
+    };
+
+    let to_rlim = |limit: u64| {
+        if limit == ResourceLimit::UNLIMITED {
+            libc::RLIM_INFINITY
+        } else {
+            limit as libc::rlim_t
+        }
+    };
+
+    let mut rlim = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+
+    unsafe {
+        if libc::getrlimit(resource, &mut rlim) == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        if let Some(soft) = limit.soft {
+            rlim.rlim_cur = to_rlim(soft);
+        }
+        if let Some(hard) = limit.hard {
+            rlim.rlim_max = to_rlim(hard);
+        }
+
+        if libc::setrlimit(resource, &rlim) == -1 {
+            return Err(Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Places the process `pid` (or the current process if 0) in the process
+/// group `pgid` (or a new group led by `pid` if 0).
+///
+/// Only performs syscalls, thus it is safe to invoke between fork and exec.
+pub(crate) fn set_process_group(pid: u32, pgid: u32) -> Result<()> {
+    if unsafe { libc::setpgid(pid as libc::pid_t, pgid as libc::pid_t) } == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Makes each `(source, target)` descriptor pair available to the current
+/// process as `target`, with its CLOEXEC flag cleared so that it will be
+/// inherited by the executable.
+///
+/// Only performs syscalls, thus it is safe to invoke between fork and exec.
+pub(crate) fn inherit_fds(fds: &mut [(RawFd, RawFd)]) -> Result<()> {
+    let min_fd = fds.iter().map(|&(_, target)| target + 1).max().unwrap_or(0);
+
+    // Any source which is also the target of another pair would be
+    // clobbered before it gets used, so move it out of the way first.
+    for i in 0..fds.len() {
+        let src = fds[i].0;
+        let clobbered = fds
+            .iter()
+            .enumerate()
+            .any(|(j, &(_, target))| i != j && target == src);
+
+        if clobbered {
+            fds[i].0 = cvt_r(|| unsafe { libc::fcntl(src, libc::F_DUPFD_CLOEXEC, min_fd) })?;
+        }
+    }
+
+    for &(src, target) in fds.iter() {
+        if src == target {
+            // dup2 would be a no-op which leaves the CLOEXEC flag as is
+            unsafe {
+                let flags = cvt_r(|| libc::fcntl(src, libc::F_GETFD))?;
+                cvt_r(|| libc::fcntl(src, libc::F_SETFD, flags & !libc::FD_CLOEXEC))?;
+            }
+        } else {
+            cvt_r(|| unsafe { libc::dup2(src, target) })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The signals which can be sent via `send_signal`, named without their
+/// `SIG` prefix.
+pub(crate) const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("SYS", libc::SIGSYS),
+];
+
+/// The signal sent by `kill` if none is specified.
+pub(crate) const SIGTERM: i32 = libc::SIGTERM;
+
+/// Sends `signal` to the process `pid`, or to all processes in the process
+/// group `-pid` if `pid` is negative (see `kill(2)`).
+pub(crate) fn send_signal(pid: i32, signal: i32) -> Result<()> {
+    if unsafe { libc::kill(pid, signal) } == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Resumes all (stopped) processes in the process group `pgid` by sending
+/// them `SIGCONT`.
+pub(crate) fn continue_process_group(pgid: u32) -> Result<()> {
+    send_signal(-(pgid as libc::pid_t), libc::SIGCONT)
+}
+
+/// Makes the process group `pgid` (or the current process's group if 0) the
+/// foreground process group of the terminal `tty`.
+///
+/// `SIGTTOU` is temporarily blocked so that the calling process will not get
+/// stopped if it is not in the foreground itself. Only performs syscalls,
+/// thus it is safe to invoke between fork and exec.
+pub(crate) fn set_foreground_process_group(tty: RawFd, pgid: u32) -> Result<()> {
+    unsafe {
+        let pgid = if pgid == 0 {
+            libc::getpgrp()
+        } else {
+            pgid as libc::pid_t
+        };
+
+        let mut block = mem::zeroed::<libc::sigset_t>();
+        let mut old = mem::zeroed::<libc::sigset_t>();
+        libc::sigemptyset(&mut block);
+        libc::sigaddset(&mut block, libc::SIGTTOU);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &block, &mut old);
+
+        let ret = if libc::tcsetpgrp(tty, pgid) == -1 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        };
+
+        libc::pthread_sigmask(libc::SIG_SETMASK, &old, ptr::null_mut());
+        ret
+    }
+}
+
+/// Queries the maximum combined size of arguments and environment variables
+/// which may be passed to a new process.
+pub(crate) fn arg_max() -> Option<usize> {
+    let ret = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    if ret > 0 {
+        Some(ret as usize)
+    } else {
+        None
+    }
+}
+
+/// Indicates if the effective user of the current process is the superuser.
+pub(crate) fn is_privileged_user() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Indicates if the file at the specified path may be executed by the
+/// current process, as determined by `access(2)`.
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), libc::X_OK) == 0 },
+        Err(_) => false,
+    }
+}
+
+/// Looks up the login name of the effective user of the current process.
+pub(crate) fn user_name() -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result = ptr::null_mut();
+
+    unsafe {
+        let mut pwd = mem::zeroed::<libc::passwd>();
+        let ret = libc::getpwuid_r(
+            libc::geteuid(),
+            &mut pwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        );
+
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+
+        CStr::from_ptr(pwd.pw_name).to_str().ok().map(String::from)
+    }
+}
+
+/// Queries the host name of the machine.
+pub(crate) fn host_name() -> Option<String> {
+    let mut buf = [0 as libc::c_char; 256];
+
+    unsafe {
+        if libc::gethostname(buf.as_mut_ptr(), buf.len()) == -1 {
+            return None;
+        }
+
+        // NB: the name may be truncated without a trailing nul
+        buf[buf.len() - 1] = 0;
+        CStr::from_ptr(buf.as_ptr()).to_str().ok().map(String::from)
+    }
+}
+
+/// Queries the offset (in seconds) of the local time zone from UTC at the
+/// specified number of seconds since the Unix epoch.
+pub(crate) fn utc_offset(secs: i64) -> Option<i64> {
+    let time = secs as libc::time_t;
+
+    unsafe {
+        let mut tm = mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            None
+        } else {
+            Some(tm.tm_gmtoff as i64)
+        }
+    }
+}
@@ -4,10 +4,14 @@ use crate::io::FileDesc;
 use crate::sys::cvt_r;
 use crate::IntoInner;
 use libc::{self, c_void, size_t};
+use std::ffi::CString;
 use std::fs::File;
-use std::io::{Result, SeekFrom};
-use std::mem;
+use std::io::{Error, ErrorKind, Result, SeekFrom};
+use std::mem::{self, size_of};
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
 use std::process::Stdio;
 
 /// A wrapper around an owned UNIX file descriptor. The wrapper
@@ -95,9 +99,14 @@ impl RawIo {
         self.fd
     }
 
-    /// Duplicates the underlying file descriptor via `libc::dup`.
+    /// Duplicates the underlying file descriptor, setting the CLOEXEC flag
+    /// on the duplicate so that it does not leak into any spawned processes.
     pub fn duplicate(&self) -> Result<Self> {
-        unsafe { Ok(RawIo::new(cvt_r(|| libc::dup(self.fd))?)) }
+        unsafe {
+            Ok(RawIo::new(cvt_r(|| {
+                libc::fcntl(self.fd, libc::F_DUPFD_CLOEXEC, 0)
+            })?))
+        }
     }
 
     /// Reads from the underlying file descriptor.
@@ -229,6 +238,122 @@ pub fn pipe() -> Result<(RawIo, RawIo)> {
     }
 }
 
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "path contained a null byte"))
+}
+
+/// Creates a named pipe (FIFO) at `path` with the specified permission bits
+/// (which are further restricted by the process' umask).
+pub fn mkfifo(path: &Path, mode: libc::mode_t) -> Result<()> {
+    let path = path_to_cstring(path)?;
+    cvt_r(|| unsafe { libc::mkfifo(path.as_ptr(), mode) }).map(|_| ())
+}
+
+/// Opens both ends of the named pipe (FIFO) at `path` and returns them as a
+/// `(reader, writer)` pair.
+///
+/// Opening either end of a FIFO normally blocks until the other end is also
+/// opened, so both descriptors are opened (and left) in non-blocking mode.
+/// The CLOEXEC flag will be set on both file descriptors on creation.
+pub fn open_fifo(path: &Path) -> Result<(RawIo, RawIo)> {
+    let path = path_to_cstring(path)?;
+    let open = |access| unsafe {
+        cvt_r(|| libc::open(path.as_ptr(), access | libc::O_NONBLOCK | libc::O_CLOEXEC))
+            .map(|fd| RawIo::new(fd))
+    };
+
+    // NB: the reader must be opened first, as opening the writer end of a
+    // FIFO without any readers fails with ENXIO in non-blocking mode.
+    let reader = open(libc::O_RDONLY)?;
+    let writer = open(libc::O_WRONLY)?;
+    Ok((reader, writer))
+}
+
+/// Sends a duplicate of `fd` to the process at the other end of `stream`
+/// (via `SCM_RIGHTS`), which can receive it with `recv_fd`.
+///
+/// A single byte of regular data is sent along with the descriptor.
+pub fn send_fd(stream: &UnixStream, fd: &FileDesc) -> Result<()> {
+    unsafe {
+        let mut data = [0u8; 1];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr() as *mut c_void,
+            iov_len: data.len(),
+        };
+
+        let space = libc::CMSG_SPACE(size_of::<RawFd>() as u32) as usize;
+        let mut control = vec![0u8; space];
+
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = space as _;
+
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        (libc::CMSG_DATA(cmsg) as *mut RawFd).write_unaligned(fd.as_raw_fd());
+
+        cvt_r(|| libc::sendmsg(stream.as_raw_fd(), &msg, 0)).map(|_| ())
+    }
+}
+
+/// Receives a descriptor sent by `send_fd` from the process at the other end
+/// of `stream`.
+///
+/// The CLOEXEC flag will be set on the received descriptor, though this is
+/// nonatomic on some UNIX systems (like BSD).
+pub fn recv_fd(stream: &UnixStream) -> Result<FileDesc> {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "emscripten"))]
+    const FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "emscripten")))]
+    const FLAGS: libc::c_int = 0;
+
+    unsafe {
+        let mut data = [0u8; 1];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr() as *mut c_void,
+            iov_len: data.len(),
+        };
+
+        let space = libc::CMSG_SPACE(size_of::<RawFd>() as u32) as usize;
+        let mut control = vec![0u8; space];
+
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = space as _;
+
+        let read = cvt_r(|| libc::recvmsg(stream.as_raw_fd(), &mut msg, FLAGS))?;
+
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        let valid = !cmsg.is_null()
+            && (*cmsg).cmsg_level == libc::SOL_SOCKET
+            && (*cmsg).cmsg_type == libc::SCM_RIGHTS;
+
+        if !valid {
+            let msg = if read == 0 {
+                "socket was closed before a file descriptor was received"
+            } else {
+                "message did not contain a file descriptor"
+            };
+
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+
+        let raw = RawIo::new((libc::CMSG_DATA(cmsg) as *const RawFd).read_unaligned());
+        if FLAGS == 0 {
+            raw.set_cloexec(true)?;
+        }
+
+        Ok(FileDesc::from_inner(raw))
+    }
+}
+
 /// Duplicates file descriptors for (stdin, stdout, stderr) and returns them in that order.
 pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
     unsafe {
@@ -244,3 +369,8 @@ pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
 pub fn getpid() -> libc::pid_t {
     unsafe { libc::getpid() }
 }
+
+/// Returns the process ID of the parent of the calling process
+pub fn getppid() -> libc::pid_t {
+    unsafe { libc::getppid() }
+}
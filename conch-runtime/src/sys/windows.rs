@@ -1,6 +1,7 @@
 //! Extensions and implementations specific to Windows platforms.
 
-use std::io::{Error, Result};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
 
 pub mod io;
 
@@ -31,3 +32,60 @@ pub(crate) fn cvt<I: IsZero>(i: I) -> Result<I> {
         Ok(i)
     }
 }
+
+/// Queries the maximum combined size of arguments and environment variables
+/// which may be passed to a new process.
+///
+/// Windows limits the length of the command line in characters instead,
+/// which we don't attempt to model.
+pub(crate) fn arg_max() -> Option<usize> {
+    None
+}
+
+/// Windows has no equivalent of sending arbitrary signals to processes.
+pub(crate) const SIGNALS: &[(&str, i32)] = &[];
+
+/// The signal sent by `kill` if none is specified.
+pub(crate) const SIGTERM: i32 = 15;
+
+/// Sending signals to processes is not supported on Windows.
+pub(crate) fn send_signal(_pid: i32, _signal: i32) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Other,
+        "sending signals is not supported on Windows",
+    ))
+}
+
+/// Process groups (and thus resuming them) are not supported on Windows.
+pub(crate) fn continue_process_group(_pgid: u32) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Other,
+        "process groups are not supported on Windows",
+    ))
+}
+
+/// Privileged users are not detected on Windows.
+pub(crate) fn is_privileged_user() -> bool {
+    false
+}
+
+/// Files lack execute permissions on Windows, so the OS is left to decide
+/// whether it can run them.
+pub(crate) fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+/// Looking up the current user is not supported on Windows.
+pub(crate) fn user_name() -> Option<String> {
+    None
+}
+
+/// Looking up the host name is not supported on Windows.
+pub(crate) fn host_name() -> Option<String> {
+    None
+}
+
+/// Local time zones are not supported on Windows, times are in UTC.
+pub(crate) fn utc_offset(_secs: i64) -> Option<i64> {
+    None
+}
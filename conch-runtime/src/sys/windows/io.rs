@@ -15,6 +15,9 @@ use winapi::um::handleapi::{CloseHandle, DuplicateHandle, INVALID_HANDLE_VALUE};
 use winapi::um::namedpipeapi::CreatePipe;
 use winapi::um::processenv::GetStdHandle;
 use winapi::um::processthreadsapi::{GetCurrentProcess, GetCurrentProcessId};
+use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
 use winapi::um::winbase::{
     FILE_BEGIN, FILE_CURRENT, FILE_END, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE,
 };
@@ -227,3 +230,32 @@ pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
 pub fn getpid() -> DWORD {
     unsafe { GetCurrentProcessId() }
 }
+
+/// Retrieves the process identifier of the parent of the calling process,
+/// or zero if it cannot be determined.
+pub fn getppid() -> DWORD {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return 0;
+        }
+
+        let pid = GetCurrentProcessId();
+        let mut ppid = 0;
+        let mut entry: PROCESSENTRY32W = mem::zeroed();
+        entry.dwSize = mem::size_of::<PROCESSENTRY32W>() as DWORD;
+
+        let mut found = Process32FirstW(snapshot, &mut entry);
+        while found != FALSE {
+            if entry.th32ProcessID == pid {
+                ppid = entry.th32ParentProcessID;
+                break;
+            }
+
+            found = Process32NextW(snapshot, &mut entry);
+        }
+
+        CloseHandle(snapshot);
+        ppid
+    }
+}